@@ -0,0 +1,1506 @@
+use anyhow::{Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use reqwest::Client;
+use std::io::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use find_common::api::{
+    Annotation, AppSettingsResponse, BulkRequest, BulkSessionInitRequest, BulkSessionResponse, ClientMetrics, CompactResponse, ContextBatchItem,
+    ContextBatchRequest, ContextBatchResponse, ContextBatchResult, ContextResponse, CreateAnnotationRequest, FileRecord, FileResponse,
+    InboxDeleteResponse, InboxPauseResponse, InboxResumeResponse, InboxRetryResponse,
+    InboxShowResponse, InboxStatusResponse, KnownHashesRequest, KnownHashesResponse, MaintainRequest,
+    MaintainResponse, MaintenanceRequest, MaintenanceStatus, PreviewResponse, RecentFile, RecentResponse, ReextractRequest, ReextractResponse, SearchResponse,
+    SearchSummaryResponse,
+    SourceDeleteResponse, SourceInfo, SourceRenameRequest, SourceRenameResponse, StatsResponse, StatsStreamEvent,
+    TrashResponse, TrashRestoreRequest, TrashRestoreResponse, UploadInitRequest,
+    UploadInitResponse, UploadPatchResponse, UploadScanHints, UploadStatusResponse,
+    parse_stale_sources, STALE_SOURCES_HEADER,
+};
+use find_common::config::RemoteClientConfig;
+
+use crate::pause::PauseControl;
+use crate::spool::Spool;
+use crate::throttle::UploadThrottle;
+
+/// Compressed bulk payloads above this size are sent through a resumable
+/// session (`/api/v1/bulk/sessions`) instead of a single POST.
+const RESUMABLE_THRESHOLD: usize = 8 * 1024 * 1024;
+/// Size of each `PATCH` in a resumable bulk upload.
+const RESUMABLE_CHUNK: usize = 2 * 1024 * 1024;
+/// Consecutive failed chunks tolerated before a resumable upload gives up.
+const RESUMABLE_MAX_RETRIES: u32 = 5;
+
+/// Server-side state of a file's original bytes (`/api/v1/originals`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginalUpload {
+    /// The server holds a copy.
+    Stored,
+    /// The server has no copy yet.
+    Missing,
+    /// The file exceeds the server's `[originals] max_file_size_mb`.
+    TooLarge,
+    /// The server's originals store is disabled.
+    Disabled,
+}
+
+pub struct ApiClient {
+    client: Client,
+    base_url: String,
+    token: String,
+    /// Upload bandwidth limits; `None` = unrestricted.
+    throttle: Option<Arc<UploadThrottle>>,
+    /// Quiet hours and on-demand pauses; `None` = never paused.
+    pause: Option<PauseControl>,
+    /// Where bulk requests go while the server is unreachable; `None` =
+    /// fail instead.
+    spool: Option<Spool>,
+    /// Compressed size of the last bulk request sent to the server; 0 once
+    /// read (see `take_last_bulk_size`).
+    last_bulk_size: AtomicUsize,
+    /// When the server last accepted a bulk request (Unix seconds, 0 = never).
+    last_bulk_ok: AtomicI64,
+    /// The last bulk request that failed: when, and why.
+    last_bulk_error: Mutex<Option<(i64, String)>>,
+    /// `Retry-After` in seconds of the last bulk request the server turned
+    /// away with 503 (maintenance mode); 0 = none.
+    retry_after: AtomicU64,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            throttle: None,
+            pause: None,
+            spool: None,
+            last_bulk_size: AtomicUsize::new(0),
+            last_bulk_ok: AtomicI64::new(0),
+            last_bulk_error: Mutex::new(None),
+            retry_after: AtomicU64::new(0),
+        }
+    }
+
+    /// Spool bulk requests to `spool` while the server is unreachable.
+    pub fn with_spool(mut self, spool: Option<Spool>) -> Self {
+        self.spool = spool;
+        self
+    }
+
+    /// Pace every upload from this client through `throttle`.
+    pub fn with_throttle(mut self, throttle: Option<UploadThrottle>) -> Self {
+        self.throttle = throttle.map(Arc::new);
+        self
+    }
+
+    /// Hold bulk uploads while `pause` says indexing is paused.
+    pub fn with_pause(mut self, pause: PauseControl) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Whether indexing is paused (quiet hours or `find-admin pause`).
+    pub fn is_paused(&self) -> bool {
+        self.pause.as_ref().is_some_and(PauseControl::is_paused)
+    }
+
+    /// Why indexing is paused, for status reports.
+    pub fn pause_reason(&self) -> Option<String> {
+        self.pause.as_ref()?.reason().map(|r| r.to_string())
+    }
+
+    /// Wait until indexing is no longer paused.
+    pub async fn wait_while_paused(&self) {
+        if let Some(p) = &self.pause {
+            p.wait().await;
+        }
+    }
+
+    /// Largest body to upload in one request: `max`, or less when a rate
+    /// limit is configured.
+    pub fn upload_chunk_size(&self, max: usize) -> usize {
+        self.throttle.as_ref().map_or(max, |t| t.chunk_size(max))
+    }
+
+    /// Compressed size of the bulk request sent since the last call, or
+    /// `None` if none was (e.g. it was spooled).  Feeds adaptive batch sizing.
+    pub fn take_last_bulk_size(&self) -> Option<usize> {
+        Some(self.last_bulk_size.swap(0, Ordering::Relaxed)).filter(|&n| n > 0)
+    }
+
+    /// When the server last accepted a bulk request (Unix seconds).
+    pub fn last_bulk_ok_at(&self) -> Option<i64> {
+        Some(self.last_bulk_ok.load(Ordering::Relaxed)).filter(|&t| t > 0)
+    }
+
+    /// The last bulk request that failed, as `(unix seconds, error)`.
+    pub fn last_bulk_error(&self) -> Option<(i64, String)> {
+        self.last_bulk_error.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// How long the server asked us to wait after turning away the last
+    /// bulk request (maintenance mode), if it did.
+    pub fn server_retry_after(&self) -> Option<Duration> {
+        Some(self.retry_after.load(Ordering::Relaxed)).filter(|&s| s > 0).map(Duration::from_secs)
+    }
+
+    /// Remember the `Retry-After` of a 503 response, so spooled batches wait
+    /// as long as the server asked.
+    fn note_retry_after(&self, resp: &reqwest::Response) {
+        let secs = if resp.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            resp.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+        } else {
+            None
+        };
+        self.retry_after.store(secs.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Number of bulk requests waiting in the spool.
+    pub fn spooled_batches(&self) -> usize {
+        self.spool.as_ref().map_or(0, |s| s.entries().len())
+    }
+
+    /// Wait until `bytes` more may be uploaded under the configured limits.
+    async fn throttle(&self, bytes: usize) {
+        if let Some(t) = &self.throttle {
+            t.acquire(bytes).await;
+        }
+    }
+
+    /// Whether a compressed bulk payload of `len` bytes should go through a
+    /// resumable session rather than a single POST.
+    fn use_resumable(&self, len: usize) -> bool {
+        len > RESUMABLE_THRESHOLD || len > self.upload_chunk_size(RESUMABLE_THRESHOLD)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// GET /api/v1/files?source=<name>  — returns existing (path, mtime) list.
+    pub async fn list_files(&self, source: &str) -> Result<Vec<FileRecord>> {
+        let resp = self
+            .client
+            .get(self.url("/api/v1/files"))
+            .query(&[("source", source)])
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/files")?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        resp.error_for_status()
+            .context("GET /api/v1/files status")?
+            .json::<Vec<FileRecord>>()
+            .await
+            .context("parsing file list")
+    }
+
+    /// GET /api/v1/files?source=<name>&archive=<path>  — the indexed members
+    /// of one archive.
+    pub async fn list_archive_members(&self, source: &str, archive: &str) -> Result<Vec<FileRecord>> {
+        let resp = self
+            .client
+            .get(self.url("/api/v1/files"))
+            .query(&[("source", source), ("archive", archive)])
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/files")?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        let files = resp.error_for_status()
+            .context("GET /api/v1/files status")?
+            .json::<Vec<FileRecord>>()
+            .await
+            .context("parsing file list")?;
+        // Servers that predate `archive` ignore it and list the whole source.
+        let prefix = format!("{archive}::");
+        Ok(files.into_iter().filter(|f| f.path.starts_with(&prefix)).collect())
+    }
+
+    /// Submit a bulk request.  With a spool configured, a request the server
+    /// cannot be reached for is spooled rather than failing, and requests
+    /// queue behind anything already spooled so they are applied in order.
+    pub async fn bulk(&self, req: &BulkRequest) -> Result<()> {
+        let Some(spool) = &self.spool else {
+            return self.send_bulk(req).await;
+        };
+        if spool.is_empty() || (spool.ready() && self.flush_spool().await) {
+            match self.send_bulk(req).await {
+                Err(e) if is_unreachable(&e) => {
+                    let retry_after = self.server_retry_after();
+                    match retry_after {
+                        Some(wait) => tracing::warn!(
+                            "server is not accepting batches ({e:#}); spooling batch to {} and retrying in {}s",
+                            spool.dir().display(),
+                            wait.as_secs(),
+                        ),
+                        None => tracing::warn!("server unreachable ({e:#}); spooling batch to {}", spool.dir().display()),
+                    }
+                    spool.record_failure(retry_after);
+                }
+                other => return other,
+            }
+        }
+        spool.push(req)
+    }
+
+    /// Resend spooled bulk requests, oldest first.  Returns true once the
+    /// spool is empty; stops at the first one the server cannot be reached
+    /// for.  Requests the server refuses are set aside and logged.
+    pub async fn flush_spool(&self) -> bool {
+        let Some(spool) = &self.spool else { return true };
+        let entries = spool.entries();
+        if entries.is_empty() {
+            return true;
+        }
+        tracing::info!("sending {} spooled batch(es)", entries.len());
+        for path in entries {
+            let req = match spool.load(&path) {
+                Ok(req) => req,
+                Err(e) => {
+                    tracing::warn!("setting aside unreadable spooled batch: {e:#}");
+                    spool.reject(&path);
+                    continue;
+                }
+            };
+            match self.send_bulk(&req).await {
+                Ok(()) => spool.remove(&path),
+                Err(e) if is_unreachable(&e) => {
+                    spool.record_failure(self.server_retry_after());
+                    return false;
+                }
+                Err(e) => {
+                    tracing::warn!("server refused spooled batch {}; setting it aside: {e:#}", path.display());
+                    spool.reject(&path);
+                }
+            }
+        }
+        spool.record_success();
+        true
+    }
+
+    /// Flush the spool, retrying with exponential backoff for up to
+    /// `max_wait`.  Fails if batches are still spooled after that, unless
+    /// the server is in maintenance mode: then they are left spooled
+    /// without waiting, and later batches queue behind them.
+    pub async fn drain_spool(&self, max_wait: Duration) -> Result<()> {
+        let Some(spool) = &self.spool else { return Ok(()) };
+        let deadline = tokio::time::Instant::now() + max_wait;
+        while !self.flush_spool().await {
+            if self.server_retry_after().is_some() {
+                tracing::warn!(
+                    "server is in maintenance mode; {} batch(es) stay spooled in {} until it accepts them again",
+                    spool.entries().len(),
+                    spool.dir().display(),
+                );
+                return Ok(());
+            }
+            let wait = spool.retry_in().unwrap_or_default();
+            if tokio::time::Instant::now() + wait > deadline {
+                anyhow::bail!(
+                    "server still unreachable; {} batch(es) remain spooled in {} and will be sent by the next run",
+                    spool.entries().len(),
+                    spool.dir().display(),
+                );
+            }
+            tracing::info!("server unreachable; retrying spooled batches in {}s", wait.as_secs());
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Time until spooled batches are next due to be retried; `None` when
+    /// nothing is spooled.
+    pub fn spool_retry_in(&self) -> Option<Duration> {
+        self.spool.as_ref().and_then(Spool::retry_in)
+    }
+
+    /// Send one bulk request once any pause has lifted, recording the
+    /// outcome for `find-watch`'s status heartbeat.
+    async fn send_bulk(&self, req: &BulkRequest) -> Result<()> {
+        self.wait_while_paused().await;
+        self.retry_after.store(0, Ordering::Relaxed);
+        let result = self.post_bulk(req).await;
+        let now = chrono::Utc::now().timestamp();
+        match &result {
+            Ok(()) => self.last_bulk_ok.store(now, Ordering::Relaxed),
+            Err(e) => *self.last_bulk_error.lock().unwrap_or_else(|e| e.into_inner()) = Some((now, format!("{e:#}"))),
+        }
+        result
+    }
+
+    /// POST /api/v1/bulk  — upserts, deletions, and scan-complete in one request (gzip-compressed).
+    async fn post_bulk(&self, req: &BulkRequest) -> Result<()> {
+        let json = serde_json::to_vec(req).context("serialising bulk request")?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json).context("compressing bulk request")?;
+        let compressed = encoder.finish().context("finishing gzip stream")?;
+        self.last_bulk_size.store(compressed.len(), Ordering::Relaxed);
+        if self.use_resumable(compressed.len()) {
+            return self.bulk_resumable(compressed, false).await;
+        }
+        self.throttle(compressed.len()).await;
+
+        let resp = self.client
+            .post(self.url("/api/v1/bulk"))
+            .bearer_auth(&self.token)
+            .header("Content-Encoding", "gzip")
+            .header("Content-Type", "application/json")
+            .body(compressed)
+            .send()
+            .await
+            .context("POST /api/v1/bulk")?;
+        self.note_retry_after(&resp);
+
+        let status = resp.status();
+        if status == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+            // Over the v1 body limit (e.g. one pathologically large file):
+            // resend through the unbounded streaming endpoint.
+            tracing::debug!("bulk request too large for /api/v1/bulk; retrying via /api/v2/bulk");
+            return self.bulk_stream(req).await;
+        }
+        if status == reqwest::StatusCode::ACCEPTED || status.is_success() {
+            return Ok(());
+        }
+        resp.error_for_status().context("POST /api/v1/bulk status")?;
+        Err(anyhow::anyhow!("POST /api/v1/bulk: unexpected status {status}"))
+    }
+
+    /// POST /api/v2/bulk  — the same request as gzip-compressed NDJSON: a
+    /// header line (everything except `files`) followed by one file per line.
+    /// Has no size limit; the server splits it into batches itself.
+    pub async fn bulk_stream(&self, req: &BulkRequest) -> Result<()> {
+        let header = BulkRequest {
+            source: req.source.clone(),
+            files: Vec::new(),
+            delete_paths: req.delete_paths.clone(),
+            scan_timestamp: req.scan_timestamp,
+            indexing_failures: req.indexing_failures.clone(),
+            rename_paths: req.rename_paths.clone(),
+        };
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, &header).context("serialising bulk header")?;
+        for file in &req.files {
+            encoder.write_all(b"\n").context("compressing bulk stream")?;
+            serde_json::to_writer(&mut encoder, file).context("serialising bulk file")?;
+        }
+        encoder.write_all(b"\n").context("compressing bulk stream")?;
+        let compressed = encoder.finish().context("finishing gzip stream")?;
+        if self.use_resumable(compressed.len()) {
+            return self.bulk_resumable(compressed, true).await;
+        }
+        self.throttle(compressed.len()).await;
+
+        let resp = self.client
+            .post(self.url("/api/v2/bulk"))
+            .bearer_auth(&self.token)
+            .header("Content-Encoding", "gzip")
+            .header("Content-Type", "application/x-ndjson")
+            .body(compressed)
+            .send()
+            .await
+            .context("POST /api/v2/bulk")?;
+        self.note_retry_after(&resp);
+
+        let status = resp.status();
+        if status == reqwest::StatusCode::ACCEPTED || status.is_success() {
+            return Ok(());
+        }
+        resp.error_for_status().context("POST /api/v2/bulk status")?;
+        Err(anyhow::anyhow!("POST /api/v2/bulk: unexpected status {status}"))
+    }
+
+    /// Send an already-compressed bulk payload through a resumable session:
+    /// chunks that fail (e.g. a dropped VPN link) are retried from the offset
+    /// the server reports, so only the missing bytes are resent.  `stream`
+    /// selects the v2 NDJSON format.
+    pub async fn bulk_resumable(&self, payload: Vec<u8>, stream: bool) -> Result<()> {
+        let total = payload.len() as u64;
+        let session = self.bulk_session_init(stream, total).await?;
+        let id = session.session_id;
+
+        let chunk_size = self.upload_chunk_size(RESUMABLE_CHUNK) as u64;
+        let mut offset = session.received;
+        let mut failures = 0u32;
+        while offset < total {
+            let end = (offset + chunk_size).min(total);
+            let chunk = payload[offset as usize..end as usize].to_vec();
+            match self.bulk_session_patch(&id, offset, total, chunk).await {
+                Ok(received) => {
+                    offset = received;
+                    failures = 0;
+                }
+                Err(e) => {
+                    failures += 1;
+                    if failures > RESUMABLE_MAX_RETRIES {
+                        return Err(e.context(format!("bulk session {id}: giving up at byte {offset} of {total}")));
+                    }
+                    tracing::warn!("bulk session {id}: chunk at byte {offset} failed ({e:#}); resuming");
+                    tokio::time::sleep(std::time::Duration::from_secs(1 << failures.min(5))).await;
+                    if let Ok(status) = self.bulk_session_status(&id).await {
+                        offset = status.received;
+                    }
+                }
+            }
+        }
+
+        self.client
+            .post(self.url(&format!("/api/v1/bulk/sessions/{id}/commit")))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("POST /api/v1/bulk/sessions/commit")?
+            .error_for_status()
+            .context("bulk session commit status")?;
+        Ok(())
+    }
+
+    /// POST /api/v1/bulk/sessions — open a resumable bulk upload.
+    pub async fn bulk_session_init(&self, stream: bool, size: u64) -> Result<BulkSessionResponse> {
+        let resp = self.client
+            .post(self.url("/api/v1/bulk/sessions"))
+            .bearer_auth(&self.token)
+            .json(&BulkSessionInitRequest { stream, size: Some(size) })
+            .send()
+            .await
+            .context("POST /api/v1/bulk/sessions")?;
+        self.note_retry_after(&resp);
+        resp.error_for_status()
+            .context("bulk session init status")?
+            .json::<BulkSessionResponse>()
+            .await
+            .context("parsing bulk session response")
+    }
+
+    /// PATCH /api/v1/bulk/sessions/{id} — append the bytes starting at
+    /// `offset`.  Returns the server's new offset; on an offset mismatch
+    /// (409) that is the offset to resume from.
+    pub async fn bulk_session_patch(&self, id: &str, offset: u64, total: u64, data: Vec<u8>) -> Result<u64> {
+        let end = offset + data.len() as u64;
+        self.throttle(data.len()).await;
+        let resp = self.client
+            .patch(self.url(&format!("/api/v1/bulk/sessions/{id}")))
+            .bearer_auth(&self.token)
+            .header("Content-Range", format!("bytes {offset}-{}/{total}", end.saturating_sub(1)))
+            .header("Content-Type", "application/octet-stream")
+            .body(data)
+            .send()
+            .await
+            .context("PATCH /api/v1/bulk/sessions")?;
+        if resp.status() != reqwest::StatusCode::CONFLICT {
+            resp.error_for_status_ref().context("bulk session patch status")?;
+        }
+        resp.json::<UploadPatchResponse>()
+            .await
+            .map(|r| r.received)
+            .context("parsing bulk session patch response")
+    }
+
+    /// GET /api/v1/bulk/sessions/{id} — bytes received so far.
+    pub async fn bulk_session_status(&self, id: &str) -> Result<BulkSessionResponse> {
+        self.client
+            .get(self.url(&format!("/api/v1/bulk/sessions/{id}")))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/bulk/sessions")?
+            .error_for_status()
+            .context("bulk session status")?
+            .json::<BulkSessionResponse>()
+            .await
+            .context("parsing bulk session response")
+    }
+
+    /// POST /api/v1/hashes/known — which of `hashes` the server already
+    /// stores extracted content for.  Empty for servers without the endpoint.
+    pub async fn known_hashes(&self, hashes: Vec<String>) -> Result<std::collections::HashSet<String>> {
+        let resp = self.client
+            .post(self.url("/api/v1/hashes/known"))
+            .bearer_auth(&self.token)
+            .json(&KnownHashesRequest { hashes })
+            .send()
+            .await
+            .context("POST /api/v1/hashes/known")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Default::default());
+        }
+        resp.error_for_status()
+            .context("known hashes status")?
+            .json::<KnownHashesResponse>()
+            .await
+            .map(|r| r.known.into_iter().collect())
+            .context("parsing known hashes response")
+    }
+
+    /// GET /api/v1/context
+    pub async fn context(
+        &self,
+        source: &str,
+        path: &str,
+        archive_path: Option<&str>,
+        line: usize,
+        window: usize,
+    ) -> Result<ContextResponse> {
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/context"))
+            .bearer_auth(&self.token)
+            .query(&[
+                ("source", source),
+                ("path", path),
+                ("line", &line.to_string()),
+                ("window", &window.to_string()),
+            ]);
+        if let Some(ap) = archive_path {
+            req = req.query(&[("archive_path", ap)]);
+        }
+        req.send()
+            .await
+            .context("GET /api/v1/context")?
+            .error_for_status()
+            .context("context status")?
+            .json::<ContextResponse>()
+            .await
+            .context("parsing context response")
+    }
+
+    /// GET /api/v1/file — up to `limit` content lines of a file, starting at
+    /// the 0-based line index `offset`, with its metadata.
+    pub async fn get_file(
+        &self,
+        source: &str,
+        path: &str,
+        archive_path: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<FileResponse> {
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/file"))
+            .bearer_auth(&self.token)
+            .query(&[
+                ("source", source),
+                ("path", path),
+                ("offset", &offset.to_string()),
+                ("limit", &limit.to_string()),
+            ]);
+        if let Some(ap) = archive_path {
+            req = req.query(&[("archive_path", ap)]);
+        }
+        req.send()
+            .await
+            .context("GET /api/v1/file")?
+            .error_for_status()
+            .context("file status")?
+            .json::<FileResponse>()
+            .await
+            .context("parsing file response")
+    }
+
+    /// GET /api/v1/file/preview — a window of up to `lines` content lines
+    /// centred on `line` (from the top when `None`), with the detected
+    /// highlighting language.
+    pub async fn file_preview(
+        &self,
+        source: &str,
+        path: &str,
+        line: Option<usize>,
+        lines: usize,
+    ) -> Result<PreviewResponse> {
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/file/preview"))
+            .bearer_auth(&self.token)
+            .query(&[("source", source), ("path", path), ("lines", &lines.to_string())]);
+        if let Some(line) = line {
+            req = req.query(&[("line", line.to_string())]);
+        }
+        req.send()
+            .await
+            .context("GET /api/v1/file/preview")?
+            .error_for_status()
+            .context("file preview status")?
+            .json::<PreviewResponse>()
+            .await
+            .context("parsing file preview response")
+    }
+
+    /// POST /api/v1/context-batch — several context windows in one request.
+    /// Results are not necessarily in request order.
+    pub async fn context_batch(&self, requests: Vec<ContextBatchItem>) -> Result<Vec<ContextBatchResult>> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/context-batch"))
+            .bearer_auth(&self.token)
+            .json(&ContextBatchRequest { requests })
+            .send()
+            .await
+            .context("POST /api/v1/context-batch")?
+            .error_for_status()
+            .context("context-batch status")?
+            .json::<ContextBatchResponse>()
+            .await
+            .context("parsing context-batch response")?;
+        Ok(resp.results)
+    }
+
+    /// GET /api/v1/stats
+    pub async fn get_stats(&self, refresh: bool) -> Result<StatsResponse> {
+        let url = if refresh {
+            self.url("/api/v1/stats?refresh=true")
+        } else {
+            self.url("/api/v1/stats")
+        };
+        self.client
+            .get(url)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/stats")?
+            .error_for_status()
+            .context("stats status")?
+            .json::<StatsResponse>()
+            .await
+            .context("parsing stats response")
+    }
+
+    /// GET /api/v1/sources
+    pub async fn get_sources(&self) -> Result<Vec<SourceInfo>> {
+        self.client
+            .get(self.url("/api/v1/sources"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/sources")?
+            .error_for_status()
+            .context("sources status")?
+            .json::<Vec<SourceInfo>>()
+            .await
+            .context("parsing sources response")
+    }
+
+    /// POST /api/v1/annotations — attach a note to a file, or to one line of it.
+    pub async fn create_annotation(&self, req: &CreateAnnotationRequest) -> Result<Annotation> {
+        self.client
+            .post(self.url("/api/v1/annotations"))
+            .bearer_auth(&self.token)
+            .json(req)
+            .send()
+            .await
+            .context("POST /api/v1/annotations")?
+            .error_for_status()
+            .context("annotations status")?
+            .json::<Annotation>()
+            .await
+            .context("parsing annotation response")
+    }
+
+    /// GET /api/v1/settings
+    pub async fn get_settings(&self) -> Result<AppSettingsResponse> {
+        self.client
+            .get(self.url("/api/v1/settings"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/settings")?
+            .error_for_status()
+            .context("settings status")?
+            .json::<AppSettingsResponse>()
+            .await
+            .context("parsing settings response")
+    }
+
+    /// GET /api/v1/client-config — the scan settings the server hands out.
+    pub async fn client_config(&self) -> Result<RemoteClientConfig> {
+        self.client
+            .get(self.url("/api/v1/client-config"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/client-config")?
+            .error_for_status()
+            .context("client config status")?
+            .json::<RemoteClientConfig>()
+            .await
+            .context("parsing client config response")
+    }
+
+    /// GET /api/v1/recent
+    ///
+    /// `since` is a unix timestamp; `sources` and `kinds` are allowlists
+    /// (empty = all).
+    pub async fn get_recent(
+        &self,
+        limit: usize,
+        sort_by_mtime: bool,
+        sources: &[String],
+        since: Option<i64>,
+        kinds: &[String],
+    ) -> Result<Vec<RecentFile>> {
+        let sort = if sort_by_mtime { "mtime" } else { "indexed" };
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/recent"))
+            .bearer_auth(&self.token)
+            .query(&[("limit", limit.to_string().as_str()), ("sort", sort)]);
+        for s in sources {
+            req = req.query(&[("source", s.as_str())]);
+        }
+        for k in kinds {
+            req = req.query(&[("kind", k.as_str())]);
+        }
+        if let Some(since) = since {
+            req = req.query(&[("since", since)]);
+        }
+        req.send()
+            .await
+            .context("GET /api/v1/recent")?
+            .error_for_status()
+            .context("recent status")?
+            .json::<RecentResponse>()
+            .await
+            .context("parsing recent response")
+            .map(|r| r.files)
+    }
+
+    /// GET /api/v1/recent/stream — SSE stream of live activity events.
+    ///
+    /// Connects to the server-sent-events endpoint and calls `on_event` for
+    /// each event received.  Runs until the connection drops or the returned
+    /// future is cancelled (e.g. via `tokio::select!` with a ctrl-c branch).
+    pub async fn stream_recent<F>(&self, limit: usize, sort_by_mtime: bool, mut on_event: F) -> Result<()>
+    where
+        F: FnMut(RecentFile),
+    {
+        let sort = if sort_by_mtime { "mtime" } else { "indexed" };
+        let mut resp = self.client
+            .get(self.url(&format!("/api/v1/recent/stream?limit={limit}&sort={sort}")))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/recent/stream")?
+            .error_for_status()
+            .context("recent/stream status")?;
+
+        // Parse SSE frames: lines starting with "data:" separated by blank lines.
+        let mut buf = Vec::<u8>::new();
+        while let Some(chunk) = resp.chunk().await.context("reading SSE stream")? {
+            buf.extend_from_slice(&chunk);
+            // Process all complete events (terminated by \n\n).
+            while let Some(pos) = find_double_newline(&buf) {
+                if let Ok(event_str) = std::str::from_utf8(&buf[..pos]) {
+                    for line in event_str.lines() {
+                        if let Some(data) = line.strip_prefix("data:") {
+                            if let Ok(file) = serde_json::from_str::<RecentFile>(data.trim()) {
+                                on_event(file);
+                            }
+                        }
+                    }
+                }
+                buf.drain(..pos + 2);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stream `GET /api/v1/stats/stream` (SSE).  Calls `on_event` for each snapshot.
+    pub async fn stream_stats<F>(&self, mut on_event: F) -> Result<()>
+    where
+        F: FnMut(StatsStreamEvent),
+    {
+        let mut resp = self.client
+            .get(self.url("/api/v1/stats/stream"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/stats/stream")?
+            .error_for_status()
+            .context("stats/stream status")?;
+
+        let mut buf = Vec::<u8>::new();
+        while let Some(chunk) = resp.chunk().await.context("reading stats SSE stream")? {
+            buf.extend_from_slice(&chunk);
+            while let Some(pos) = find_double_newline(&buf) {
+                if let Ok(event_str) = std::str::from_utf8(&buf[..pos]) {
+                    for line in event_str.lines() {
+                        if let Some(data) = line.strip_prefix("data:") {
+                            match serde_json::from_str::<StatsStreamEvent>(data.trim()) {
+                                Ok(event) => on_event(event),
+                                Err(e) => eprintln!("[stats stream] deserialize error: {e}"),
+                            }
+                        }
+                    }
+                }
+                buf.drain(..pos + 2);
+            }
+        }
+        Ok(())
+    }
+
+    /// GET /api/v1/admin/inbox
+    pub async fn inbox_status(&self) -> Result<InboxStatusResponse> {
+        self.client
+            .get(self.url("/api/v1/admin/inbox"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/admin/inbox")?
+            .error_for_status()
+            .context("inbox status")?
+            .json::<InboxStatusResponse>()
+            .await
+            .context("parsing inbox status response")
+    }
+
+    /// DELETE /api/v1/admin/inbox?target=<target>
+    pub async fn inbox_clear(&self, target: &str) -> Result<InboxDeleteResponse> {
+        self.client
+            .delete(self.url("/api/v1/admin/inbox"))
+            .bearer_auth(&self.token)
+            .query(&[("target", target)])
+            .send()
+            .await
+            .context("DELETE /api/v1/admin/inbox")?
+            .error_for_status()
+            .context("inbox clear status")?
+            .json::<InboxDeleteResponse>()
+            .await
+            .context("parsing inbox delete response")
+    }
+
+    /// GET /api/v1/admin/inbox/show?name=<name>
+    pub async fn inbox_show(&self, name: &str) -> Result<Option<InboxShowResponse>> {
+        let resp = self.client
+            .get(self.url("/api/v1/admin/inbox/show"))
+            .bearer_auth(&self.token)
+            .query(&[("name", name)])
+            .send()
+            .await
+            .context("GET /api/v1/admin/inbox/show")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(
+            resp.error_for_status()
+                .context("inbox show")?
+                .json::<InboxShowResponse>()
+                .await
+                .context("parsing inbox show response")?,
+        ))
+    }
+
+    /// DELETE /api/v1/admin/source?source=<name>
+    pub async fn delete_source(&self, source: &str) -> Result<SourceDeleteResponse> {
+        let resp = self
+            .client
+            .delete(self.url("/api/v1/admin/source"))
+            .bearer_auth(&self.token)
+            .query(&[("source", source)])
+            .send()
+            .await
+            .context("DELETE /api/v1/admin/source")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("source '{}' not found", source);
+        }
+        resp.error_for_status()
+            .context("delete source status")?
+            .json::<SourceDeleteResponse>()
+            .await
+            .context("parsing delete source response")
+    }
+
+    /// POST /api/v1/admin/source/rename
+    pub async fn rename_source(&self, from: &str, to: &str) -> Result<SourceRenameResponse> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/admin/source/rename"))
+            .bearer_auth(&self.token)
+            .json(&SourceRenameRequest { from: from.to_string(), to: to.to_string() })
+            .send()
+            .await
+            .context("POST /api/v1/admin/source/rename")?;
+        match resp.status() {
+            reqwest::StatusCode::NOT_FOUND => anyhow::bail!("source '{}' not found", from),
+            reqwest::StatusCode::CONFLICT => anyhow::bail!("source '{}' already exists", to),
+            reqwest::StatusCode::BAD_REQUEST => anyhow::bail!("invalid source name"),
+            _ => {}
+        }
+        resp.error_for_status()
+            .context("rename source status")?
+            .json::<SourceRenameResponse>()
+            .await
+            .context("parsing rename source response")
+    }
+
+    /// GET /api/v1/admin/trash
+    pub async fn trash(&self, source: &str, prefix: Option<&str>, limit: usize) -> Result<TrashResponse> {
+        let mut query = vec![("source", source.to_string()), ("limit", limit.to_string())];
+        if let Some(prefix) = prefix {
+            query.push(("prefix", prefix.to_string()));
+        }
+        let resp = self
+            .client
+            .get(self.url("/api/v1/admin/trash"))
+            .bearer_auth(&self.token)
+            .query(&query)
+            .send()
+            .await
+            .context("GET /api/v1/admin/trash")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("source '{}' not found", source);
+        }
+        resp.error_for_status()
+            .context("trash status")?
+            .json::<TrashResponse>()
+            .await
+            .context("parsing trash response")
+    }
+
+    /// POST /api/v1/admin/trash/restore
+    pub async fn restore_trash(&self, req: &TrashRestoreRequest) -> Result<TrashRestoreResponse> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/admin/trash/restore"))
+            .bearer_auth(&self.token)
+            .json(req)
+            .send()
+            .await
+            .context("POST /api/v1/admin/trash/restore")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("source '{}' not found", req.source);
+        }
+        resp.error_for_status()
+            .context("restore trash status")?
+            .json::<TrashRestoreResponse>()
+            .await
+            .context("parsing restore trash response")
+    }
+
+    /// POST /api/v1/admin/inbox/retry
+    pub async fn inbox_retry(&self) -> Result<InboxRetryResponse> {
+        self.client
+            .post(self.url("/api/v1/admin/inbox/retry"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("POST /api/v1/admin/inbox/retry")?
+            .error_for_status()
+            .context("inbox retry status")?
+            .json::<InboxRetryResponse>()
+            .await
+            .context("parsing inbox retry response")
+    }
+
+    /// POST /api/v1/admin/compact
+    pub async fn compact(&self, dry_run: bool) -> Result<CompactResponse> {
+        let url = if dry_run {
+            self.url("/api/v1/admin/compact?dry_run=true")
+        } else {
+            self.url("/api/v1/admin/compact")
+        };
+        self.client
+            .post(url)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("POST /api/v1/admin/compact")?
+            .error_for_status()
+            .context("compact status")?
+            .json::<CompactResponse>()
+            .await
+            .context("parsing compact response")
+    }
+
+    /// POST /api/v1/admin/maintain
+    pub async fn maintain(&self, req: &MaintainRequest) -> Result<MaintainResponse> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/admin/maintain"))
+            .bearer_auth(&self.token)
+            .json(req)
+            .send()
+            .await
+            .context("POST /api/v1/admin/maintain")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("source '{}' not found", req.source.as_deref().unwrap_or_default());
+        }
+        resp.error_for_status()
+            .context("maintain status")?
+            .json::<MaintainResponse>()
+            .await
+            .context("parsing maintain response")
+    }
+
+    /// POST /api/v1/admin/reextract
+    pub async fn reextract(&self, req: &ReextractRequest) -> Result<ReextractResponse> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/admin/reextract"))
+            .bearer_auth(&self.token)
+            .json(req)
+            .send()
+            .await
+            .context("POST /api/v1/admin/reextract")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("source '{}' not found", req.source.as_deref().unwrap_or_default());
+        }
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST {
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            anyhow::bail!("{}", body["error"].as_str().unwrap_or("invalid request"));
+        }
+        resp.error_for_status()
+            .context("reextract status")?
+            .json::<ReextractResponse>()
+            .await
+            .context("parsing reextract response")
+    }
+
+    /// POST /api/v1/admin/inbox/pause
+    pub async fn inbox_pause(&self) -> Result<InboxPauseResponse> {
+        self.client
+            .post(self.url("/api/v1/admin/inbox/pause"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("POST /api/v1/admin/inbox/pause")?
+            .error_for_status()
+            .context("inbox pause status")?
+            .json::<InboxPauseResponse>()
+            .await
+            .context("parsing inbox pause response")
+    }
+
+    /// POST /api/v1/admin/inbox/resume
+    pub async fn inbox_resume(&self) -> Result<InboxResumeResponse> {
+        self.client
+            .post(self.url("/api/v1/admin/inbox/resume"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("POST /api/v1/admin/inbox/resume")?
+            .error_for_status()
+            .context("inbox resume status")?
+            .json::<InboxResumeResponse>()
+            .await
+            .context("parsing inbox resume response")
+    }
+
+    /// GET /api/v1/admin/maintenance
+    pub async fn maintenance_status(&self) -> Result<MaintenanceStatus> {
+        self.client
+            .get(self.url("/api/v1/admin/maintenance"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("GET /api/v1/admin/maintenance")?
+            .error_for_status()
+            .context("maintenance status")?
+            .json::<MaintenanceStatus>()
+            .await
+            .context("parsing maintenance response")
+    }
+
+    /// POST /api/v1/admin/maintenance
+    pub async fn set_maintenance(&self, req: &MaintenanceRequest) -> Result<MaintenanceStatus> {
+        self.client
+            .post(self.url("/api/v1/admin/maintenance"))
+            .bearer_auth(&self.token)
+            .json(req)
+            .send()
+            .await
+            .context("POST /api/v1/admin/maintenance")?
+            .error_for_status()
+            .context("maintenance status")?
+            .json::<MaintenanceStatus>()
+            .await
+            .context("parsing maintenance response")
+    }
+
+    /// POST /api/v1/client-metrics — push a health report, with `host` set
+    /// to this machine's name.
+    pub async fn report_metrics(&self, mut metrics: ClientMetrics) -> Result<()> {
+        metrics.host = host_name().to_string();
+        self.client
+            .post(self.url("/api/v1/client-metrics"))
+            .bearer_auth(&self.token)
+            .json(&metrics)
+            .send()
+            .await
+            .context("POST /api/v1/client-metrics")?
+            .error_for_status()
+            .context("client metrics status")?;
+        Ok(())
+    }
+
+    /// POST /api/v1/upload — initiate a resumable upload.
+    pub async fn upload_init(
+        &self,
+        source: &str,
+        rel_path: &str,
+        mtime: i64,
+        size: u64,
+        scan_hints: UploadScanHints,
+    ) -> Result<UploadInitResponse> {
+        let req = UploadInitRequest {
+            source: source.to_string(),
+            rel_path: rel_path.to_string(),
+            mtime,
+            size,
+            scan_hints: Some(scan_hints),
+        };
+        self.client
+            .post(self.url("/api/v1/upload"))
+            .bearer_auth(&self.token)
+            .json(&req)
+            .send()
+            .await
+            .context("POST /api/v1/upload")?
+            .error_for_status()
+            .context("upload init status")?
+            .json::<UploadInitResponse>()
+            .await
+            .context("parsing upload init response")
+    }
+
+    /// PATCH /api/v1/upload/{id} — send a chunk.
+    pub async fn upload_patch(
+        &self,
+        upload_id: &str,
+        content_range: &str,
+        data: Vec<u8>,
+    ) -> Result<UploadPatchResponse> {
+        self.throttle(data.len()).await;
+        self.client
+            .patch(self.url(&format!("/api/v1/upload/{upload_id}")))
+            .bearer_auth(&self.token)
+            .header("Content-Range", content_range)
+            .header("Content-Type", "application/octet-stream")
+            .body(data)
+            .send()
+            .await
+            .context("PATCH /api/v1/upload")?
+            .error_for_status()
+            .context("upload patch status")?
+            .json::<UploadPatchResponse>()
+            .await
+            .context("parsing upload patch response")
+    }
+
+    /// HEAD /api/v1/upload/{id} — query upload progress.
+    pub async fn upload_status(&self, upload_id: &str) -> Result<UploadStatusResponse> {
+        self.client
+            .head(self.url(&format!("/api/v1/upload/{upload_id}")))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("HEAD /api/v1/upload")?
+            .error_for_status()
+            .context("upload status")?
+            .json::<UploadStatusResponse>()
+            .await
+            .context("parsing upload status response")
+    }
+
+    /// HEAD /api/v1/originals/{hash} — whether the server already stores this
+    /// file's original bytes.
+    pub async fn original_status(&self, hash: &str) -> Result<OriginalUpload> {
+        let resp = self.client
+            .head(self.url(&format!("/api/v1/originals/{hash}")))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("HEAD /api/v1/originals")?;
+        match resp.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(OriginalUpload::Missing),
+            reqwest::StatusCode::FORBIDDEN => Ok(OriginalUpload::Disabled),
+            _ => {
+                resp.error_for_status().context("originals status")?;
+                Ok(OriginalUpload::Stored)
+            }
+        }
+    }
+
+    /// PUT /api/v1/originals/{hash} — stream a file's original bytes to the server.
+    pub async fn upload_original(&self, hash: &str, path: &std::path::Path) -> Result<OriginalUpload> {
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("opening {}", path.display()))?;
+        let len = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+        // The body is streamed in one request; its bytes are charged to the
+        // rate limit as a whole, which holds back the uploads that follow.
+        self.throttle(len as usize).await;
+        let resp = self.client
+            .put(self.url(&format!("/api/v1/originals/{hash}")))
+            .bearer_auth(&self.token)
+            .header("Content-Type", "application/octet-stream")
+            .header("Content-Length", len)
+            .body(file)
+            .send()
+            .await
+            .context("PUT /api/v1/originals")?;
+        match resp.status() {
+            reqwest::StatusCode::PAYLOAD_TOO_LARGE => Ok(OriginalUpload::TooLarge),
+            reqwest::StatusCode::FORBIDDEN => Ok(OriginalUpload::Disabled),
+            _ => {
+                resp.error_for_status().context("originals upload status")?;
+                Ok(OriginalUpload::Stored)
+            }
+        }
+    }
+
+    /// Check that this client meets the server's minimum version requirement.
+    /// Returns an error with a human-readable message if the client is too old.
+    /// Silently succeeds if the server does not advertise a minimum version or
+    /// if the version strings cannot be parsed (fail-open).
+    pub async fn check_server_version(&self) -> Result<()> {
+        let settings = self.get_settings().await
+            .context("fetching server settings for version check")?;
+        let client_ver = env!("CARGO_PKG_VERSION");
+        let min_ver = &settings.min_client_version;
+        if !version_meets_minimum(client_ver, min_ver) {
+            anyhow::bail!(
+                "client version {client_ver} is too old — server requires >= {min_ver}.\n\
+                 Please upgrade find-anything."
+            );
+        }
+        Ok(())
+    }
+
+    /// URL the server serves a search hit's file from: `/api/v1/raw` for a
+    /// plain file, `/api/v1/export` for an archive member.
+    pub fn resource_url(&self, source: &str, path: &str, archive_path: Option<&str>) -> String {
+        let (route, path) = match archive_path {
+            Some(inner) => ("/api/v1/export", format!("{path}::{inner}")),
+            None => ("/api/v1/raw", path.to_string()),
+        };
+        reqwest::Url::parse_with_params(&self.url(route), [("source", source), ("path", path.as_str())])
+            .map(String::from)
+            .unwrap_or_else(|_| self.url(route))
+    }
+
+    /// GET /api/v1/search
+    pub async fn search(
+        &self,
+        query: &str,
+        mode: &str,
+        sources: &[String],
+        limit: usize,
+        offset: usize,
+        filters: &SearchFilters,
+    ) -> Result<SearchResponse> {
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/search"))
+            .bearer_auth(&self.token)
+            .query(&[
+                ("q", query),
+                ("mode", mode),
+                ("limit", &limit.to_string()),
+                ("offset", &offset.to_string()),
+            ]);
+        for s in sources {
+            req = req.query(&[("source", s.as_str())]);
+        }
+        req = req.query(&filters.query());
+        let resp = req
+            .send()
+            .await
+            .context("GET /api/v1/search")?
+            .error_for_status()
+            .context("search status")?;
+        let stale_sources = resp
+            .headers()
+            .get(STALE_SOURCES_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(parse_stale_sources)
+            .unwrap_or_default();
+        let mut search = resp.json::<SearchResponse>().await.context("parsing search response")?;
+        search.stale_sources = stale_sources;
+        Ok(search)
+    }
+
+    /// GET /api/v1/search/summary — per-source match counts and, with
+    /// `files`, the matching files, without fetching results.
+    pub async fn search_summary(
+        &self,
+        query: &str,
+        mode: &str,
+        sources: &[String],
+        filters: &SearchFilters,
+        files: bool,
+    ) -> Result<SearchSummaryResponse> {
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/search/summary"))
+            .bearer_auth(&self.token)
+            .query(&[("q", query), ("mode", mode), ("files", if files { "true" } else { "false" })]);
+        for s in sources {
+            req = req.query(&[("source", s.as_str())]);
+        }
+        req = req.query(&filters.query());
+        req.send()
+            .await
+            .context("GET /api/v1/search/summary")?
+            .error_for_status()
+            .context("search summary status")?
+            .json::<SearchSummaryResponse>()
+            .await
+            .context("parsing search summary response")
+    }
+
+    /// GET /api/v1/search/export — every matching line as `format` (`csv`
+    /// or `ndjson`), written to `out` as it arrives.  `filters.sort` is
+    /// ignored.  Returns the number of lines exported.
+    pub async fn search_export(
+        &self,
+        query: &str,
+        mode: &str,
+        sources: &[String],
+        filters: &SearchFilters,
+        format: &str,
+        out: &mut impl std::io::Write,
+    ) -> Result<usize> {
+        let mut req = self
+            .client
+            .get(self.url("/api/v1/search/export"))
+            .bearer_auth(&self.token)
+            .query(&[("q", query), ("mode", mode), ("format", format)]);
+        for s in sources {
+            req = req.query(&[("source", s.as_str())]);
+        }
+        req = req.query(&filters.query());
+        let mut resp = req
+            .send()
+            .await
+            .context("GET /api/v1/search/export")?
+            .error_for_status()
+            .context("search export status")?;
+        // Count records by their terminating newlines; a CSV field may hold
+        // a newline of its own, but only inside quotes.
+        let csv = format == "csv";
+        let (mut records, mut in_quotes) = (0usize, false);
+        while let Some(chunk) = resp.chunk().await.context("reading search export")? {
+            for &b in chunk.iter() {
+                match b {
+                    b'"' if csv => in_quotes = !in_quotes,
+                    b'\n' if !in_quotes => records += 1,
+                    _ => {}
+                }
+            }
+            out.write_all(&chunk).context("writing search export")?;
+        }
+        out.flush().context("writing search export")?;
+        // The CSV header is not a match.
+        Ok(if csv { records.saturating_sub(1) } else { records })
+    }
+}
+
+/// Optional filters for [`ApiClient::search`] and [`ApiClient::search_summary`].
+#[derive(Debug, Default)]
+pub struct SearchFilters {
+    /// File kinds, e.g. `pdf`, `code`. Empty = any.
+    pub kinds: Vec<String>,
+    /// Extensions without the dot. Empty = any.
+    pub exts: Vec<String>,
+    pub path_prefix: Option<String>,
+    /// Unix timestamps bounding the file's mtime.
+    pub date_from: Option<i64>,
+    pub date_to: Option<i64>,
+    /// Bytes, inclusive.
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Skip files whose path contains any of these substrings.
+    pub exclude_paths: Vec<String>,
+    /// Skip files with any of these extensions (without the dot).
+    pub exclude_exts: Vec<String>,
+    /// Result order: `score` (default), `mtime`, `path` or `size`.
+    /// Ignored by [`ApiClient::search_summary`].
+    pub sort: Option<String>,
+    /// Ask [`ApiClient::search`] for per-source costs in
+    /// `SearchResponse::explain`.
+    pub explain: bool,
+}
+
+impl SearchFilters {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut q = Vec::new();
+        q.extend(self.kinds.iter().map(|k| ("kind", k.clone())));
+        q.extend(self.exts.iter().map(|e| ("ext", e.clone())));
+        if let Some(p) = &self.path_prefix { q.push(("path_prefix", p.clone())); }
+        if let Some(from) = self.date_from { q.push(("date_from", from.to_string())); }
+        if let Some(to) = self.date_to { q.push(("date_to", to.to_string())); }
+        if let Some(min) = self.min_size { q.push(("min_size", min.to_string())); }
+        if let Some(max) = self.max_size { q.push(("max_size", max.to_string())); }
+        q.extend(self.exclude_paths.iter().map(|p| ("exclude_path", p.clone())));
+        q.extend(self.exclude_exts.iter().map(|e| ("exclude_ext", e.clone())));
+        if let Some(sort) = &self.sort { q.push(("sort", sort.clone())); }
+        if self.explain { q.push(("explain", "true".to_string())); }
+        q
+    }
+}
+
+/// Returns true if `client_ver` satisfies `>= min_ver` using semver ordering.
+/// Fails open (returns true) if either string cannot be parsed.
+fn version_meets_minimum(client_ver: &str, min_ver: &str) -> bool {
+    fn parse(v: &str) -> Option<(u64, u64, u64)> {
+        let mut parts = v.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next()?.parse().ok()?;
+        Some((major, minor, patch))
+    }
+    match (parse(client_ver), parse(min_ver)) {
+        (Some(c), Some(m)) => c >= m,
+        _ => true,
+    }
+}
+
+/// Whether `e` means the server could not be reached or is temporarily
+/// unavailable, as opposed to refusing the request.
+fn is_unreachable(e: &anyhow::Error) -> bool {
+    e.chain()
+        .filter_map(|c| c.downcast_ref::<reqwest::Error>())
+        .any(|re| {
+            re.is_connect()
+                || re.is_timeout()
+                || re.is_request()
+                || re.status().is_some_and(|s| {
+                    s.is_server_error()
+                        || s == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || s == reqwest::StatusCode::TOO_MANY_REQUESTS
+                })
+        })
+}
+
+/// Find the position of the first `\n\n` sequence in `buf`.
+fn find_double_newline(buf: &[u8]) -> Option<usize> {
+    buf.windows(2).position(|w| w == b"\n\n")
+}
+
+/// This machine's name, as reported to the server: `%COMPUTERNAME%` on
+/// Windows, else what `hostname` prints.
+pub fn host_name() -> &'static str {
+    static HOST: OnceLock<String> = OnceLock::new();
+    HOST.get_or_init(|| {
+        std::env::var("COMPUTERNAME")
+            .ok()
+            .or_else(|| {
+                let out = std::process::Command::new("hostname").output().ok()?;
+                out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+            })
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    })
+}
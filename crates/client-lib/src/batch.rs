@@ -1,11 +1,10 @@
-#![allow(dead_code)] // functions are used by different binaries in this crate
-
 use std::collections::HashMap;
 use std::io::Read;
 use std::path::Path;
 
 use anyhow::Result;
-use find_common::api::{BulkRequest, FileKind, IndexFile, IndexingFailure, IndexLine, SCANNER_VERSION, LINE_PATH, LINE_METADATA, LINE_CONTENT_START};
+use find_common::api::{BulkRequest, FileKind, FileRecord, IndexFile, IndexingFailure, IndexLine, SCANNER_VERSION, LINE_PATH, LINE_METADATA, LINE_CONTENT_START};
+use find_common::config::ScanConfig;
 
 use crate::api::ApiClient;
 
@@ -14,7 +13,7 @@ use crate::api::ApiClient;
 /// extraction logic produces a new key, so old blobs become orphaned and
 /// compaction can remove them while fresh content gets stored.
 /// Returns `None` for empty files.
-pub(crate) fn hash_file(path: &Path) -> Option<String> {
+pub fn hash_file(path: &Path) -> Option<String> {
     let mut file = std::fs::File::open(path).ok()?;
     let mut hasher = blake3::Hasher::new();
     let mut buf = [0u8; 65536];
@@ -30,6 +29,36 @@ pub(crate) fn hash_file(path: &Path) -> Option<String> {
     Some(hasher.finalize().to_hex().to_string())
 }
 
+/// Number of lines in the version of `path` the server indexed, if the file
+/// has only grown by appending since then.  That version is `base_size` bytes
+/// with hash `base_hash`.  The check passes when the file's first `base_size`
+/// bytes hash the way [`hash_file`] would to `base_hash`, and end with a
+/// newline, so every indexed line is unchanged.  `None` means the file must be
+/// sent in full.
+pub fn appended_since(path: &Path, base_size: u64, base_hash: &str) -> Option<usize> {
+    let file = std::fs::File::open(path).ok()?;
+    if base_size == 0 || file.metadata().ok()?.len() <= base_size {
+        return None;
+    }
+    let mut prefix = file.take(base_size);
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = [0u8; 65536];
+    let mut lines = 0usize;
+    let mut last = 0u8;
+    loop {
+        let n = prefix.read(&mut buf).ok()?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+        lines += buf[..n].iter().filter(|&&b| b == b'\n').count();
+        last = buf[n - 1];
+    }
+    if last != b'\n' {
+        return None;
+    }
+    hasher.update(&SCANNER_VERSION.to_le_bytes());
+    (hasher.finalize().to_hex().as_str() == base_hash).then_some(lines)
+}
+
 /// Ensure the metadata slot (line 1) is present, inserting an empty placeholder if needed.
 ///
 /// The server stores inline content as a `'\n'`-joined string indexed by position:
@@ -62,6 +91,24 @@ fn ensure_metadata_slot(lines: &mut Vec<IndexLine>) {
     }
 }
 
+/// With `scan.symlink_targets`, append `[LINK:target] <path>` to the metadata
+/// line of `file` when `abs_path` is a symbolic link.  No-op otherwise.
+pub fn add_symlink_target(file: &mut IndexFile, abs_path: &Path, scan: &ScanConfig) {
+    if !scan.symlink_targets {
+        return;
+    }
+    let Ok(target) = std::fs::read_link(abs_path) else { return };
+    let entry = format!("[LINK:target] {}", target.display());
+    match file.lines.iter_mut().find(|l| l.line_number == LINE_METADATA) {
+        Some(l) if l.content.is_empty() => l.content = entry,
+        Some(l) => {
+            l.content.push(' ');
+            l.content.push_str(&entry);
+        }
+        None => file.lines.push(IndexLine { archive_path: None, line_number: LINE_METADATA, content: entry }),
+    }
+}
+
 /// Convert extracted lines for one filesystem file into one or more IndexFiles.
 ///
 /// For non-archive files: one IndexFile with path = rel_path.
@@ -87,7 +134,7 @@ pub fn build_index_files(
             content: format!("[PATH] {}", rel_path),
         });
         ensure_metadata_slot(&mut all_lines);
-        return vec![IndexFile { path: rel_path, mtime, size: Some(size), kind, lines: all_lines, extract_ms: None, file_hash: None, scanner_version: SCANNER_VERSION, is_new: false, force: false }];
+        return vec![IndexFile { path: rel_path, mtime, size: Some(size), kind, lines: all_lines, extract_ms: None, file_hash: None, scanner_version: SCANNER_VERSION, is_new: false, force: false, append_to: None, reuse_content: false, provenance: None }];
     }
 
     // Group by archive_path.
@@ -122,6 +169,9 @@ pub fn build_index_files(
         scanner_version: SCANNER_VERSION,
         is_new: false,
         force: false,
+        append_to: None,
+        reuse_content: false,
+        provenance: None,
     });
 
     // One IndexFile per archive member, with composite path "zip::member".
@@ -145,6 +195,9 @@ pub fn build_index_files(
             scanner_version: SCANNER_VERSION,
             is_new: false,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         });
     }
 
@@ -190,7 +243,7 @@ pub fn build_member_index_files(
         if member_kind == FileKind::Text || member_kind == FileKind::Unknown {
             if let Some(mime_line) = lines.iter().find(|l| l.line_number == LINE_METADATA && l.content.starts_with("[FILE:mime] ")) {
                 let mime = &mime_line.content["[FILE:mime] ".len()..];
-                member_kind = FileKind::from(find_extract_dispatch::mime_to_kind(mime));
+                member_kind = FileKind::from(find_extract_types::mime_to_kind(mime));
             } else if lines.iter().any(|l| l.line_number >= LINE_CONTENT_START) {
                 member_kind = FileKind::Text;
             }
@@ -206,11 +259,54 @@ pub fn build_member_index_files(
             scanner_version: SCANNER_VERSION,
             is_new: false,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         });
     }
     result
 }
 
+/// The members of an archive the server already holds, so that re-indexing a
+/// changed archive sends only the members that were added or changed and
+/// deletes the ones that are gone, instead of replacing every member.
+#[derive(Debug, Default)]
+pub struct KnownMembers {
+    /// Composite path → content hash of the indexed version.
+    hashes: HashMap<String, Option<String>>,
+    seen: std::collections::HashSet<String>,
+}
+
+impl KnownMembers {
+    pub fn new(members: impl IntoIterator<Item = FileRecord>) -> Self {
+        KnownMembers {
+            hashes: members.into_iter().map(|f| (f.path, f.file_hash)).collect(),
+            seen: Default::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Note that `member` is still in the archive, and return whether the
+    /// server already holds this exact content for it.  Members without a
+    /// hash (filename-only entries, nested archives) always count as changed.
+    pub fn unchanged(&mut self, member: &IndexFile) -> bool {
+        self.seen.insert(member.path.clone());
+        member.file_hash.is_some()
+            && self.hashes.get(&member.path).is_some_and(|h| *h == member.file_hash)
+    }
+
+    /// Indexed members not seen in the archive since `new`.
+    pub fn removed(self) -> Vec<String> {
+        let seen = self.seen;
+        let mut removed: Vec<String> = self.hashes.into_keys().filter(|p| !seen.contains(p)).collect();
+        removed.sort();
+        removed
+    }
+}
+
 /// Returns the total byte size of all content lines in an `IndexFile`.
 ///
 /// Used by the scan loop to enforce the byte-budget flush threshold
@@ -220,6 +316,67 @@ pub fn index_file_bytes(file: &IndexFile) -> usize {
     file.lines.iter().map(|l| l.content.len()).sum()
 }
 
+/// Bounds on the file-count limit chosen by [`BatchSizer`].
+const MIN_BATCH_FILES: usize = 16;
+const MAX_BATCH_FILES: usize = 20_000;
+/// Upper bound on the content-byte limit chosen by [`BatchSizer`].
+const MAX_BATCH_BYTES: usize = 256 * 1024 * 1024;
+
+/// Flush thresholds for the scan batch.  Fixed at `batch_size` /
+/// `batch_bytes`, or, with `[scan] batch_target_kb`, re-estimated after every
+/// request from its measured compressed size so requests approach the
+/// target: collections of tiny files get larger batches, poorly compressing
+/// content smaller ones.
+#[derive(Debug, Clone)]
+pub struct BatchSizer {
+    /// Target compressed request size in bytes; 0 = fixed limits.
+    target: usize,
+    files: usize,
+    bytes: usize,
+}
+
+impl BatchSizer {
+    pub fn from_scan(scan: &ScanConfig) -> Self {
+        BatchSizer {
+            target: scan.batch_target_kb * 1024,
+            files: scan.batch_size.max(1),
+            bytes: scan.batch_bytes.max(1),
+        }
+    }
+
+    /// Submit once the batch holds this many files.
+    pub fn file_limit(&self) -> usize {
+        self.files
+    }
+
+    /// Submit once the batch holds this many content bytes
+    /// ([`index_file_bytes`]).
+    pub fn byte_limit(&self) -> usize {
+        self.bytes
+    }
+
+    /// Whether a file of `bytes` content bytes should be sent in a request
+    /// of its own rather than joining a batch.
+    pub fn is_oversized(&self, bytes: usize) -> bool {
+        self.target > 0 && bytes >= self.bytes
+    }
+
+    /// Record a request of `files` files and `content_bytes` content bytes
+    /// that compressed to `payload_bytes`, and move the limits halfway
+    /// towards the values that would have hit the target.
+    pub fn observe(&mut self, files: usize, content_bytes: usize, payload_bytes: usize) {
+        if self.target == 0 || files == 0 || payload_bytes == 0 {
+            return;
+        }
+        let ideal_files = (self.target as u128 * files as u128 / payload_bytes as u128) as usize;
+        self.files = (self.files + ideal_files.clamp(MIN_BATCH_FILES, MAX_BATCH_FILES)).div_ceil(2);
+        if content_bytes > 0 {
+            let ideal_bytes = (self.target as u128 * content_bytes as u128 / payload_bytes as u128) as usize;
+            self.bytes = (self.bytes + ideal_bytes.clamp(self.target, MAX_BATCH_BYTES)).div_ceil(2);
+        }
+    }
+}
+
 pub async fn submit_batch(
     api: &ApiClient,
     source_name: &str,
@@ -256,6 +413,78 @@ mod tests {
         }
     }
 
+    // ── BatchSizer ─────────────────────────────────────────────────────────
+
+    fn sizer(target_kb: usize) -> BatchSizer {
+        BatchSizer::from_scan(&ScanConfig {
+            batch_target_kb: target_kb,
+            batch_size: 200,
+            batch_bytes: 8 * 1024 * 1024,
+            ..ScanConfig::default()
+        })
+    }
+
+    #[test]
+    fn sizer_fixed_ignores_observations() {
+        let mut s = sizer(0);
+        s.observe(200, 1000, 500);
+        assert_eq!((s.file_limit(), s.byte_limit()), (200, 8 * 1024 * 1024));
+        assert!(!s.is_oversized(100 * 1024 * 1024));
+    }
+
+    #[test]
+    fn sizer_grows_batches_of_tiny_files() {
+        let mut s = sizer(1024);
+        // 200 files compressed to 20 KB: ~100 bytes each, so ~10k fit in 1 MB.
+        for _ in 0..10 {
+            s.observe(s.file_limit(), 40_000, 20_000);
+        }
+        assert!(s.file_limit() > 5_000, "file limit {}", s.file_limit());
+    }
+
+    #[test]
+    fn sizer_shrinks_batches_that_compress_poorly() {
+        let mut s = sizer(1024);
+        // 8 MB of content compressed to 7 MB: stay near 1 MB of content.
+        for _ in 0..10 {
+            s.observe(50, 8 * 1024 * 1024, 7 * 1024 * 1024);
+        }
+        assert!(s.byte_limit() < 2 * 1024 * 1024, "byte limit {}", s.byte_limit());
+        assert!(s.byte_limit() >= 1024 * 1024);
+        assert!(s.is_oversized(4 * 1024 * 1024));
+        assert!(s.file_limit() >= MIN_BATCH_FILES);
+    }
+
+    // ── KnownMembers ───────────────────────────────────────────────────────
+
+    #[test]
+    fn known_members_sorts_unchanged_changed_and_removed() {
+        let record = |path: &str, hash: Option<&str>| FileRecord {
+            path: path.to_string(),
+            mtime: 0,
+            kind: FileKind::Text,
+            scanner_version: SCANNER_VERSION,
+            indexed_at: None,
+            size: None,
+            file_hash: hash.map(String::from),
+        };
+        let mut known = KnownMembers::new([
+            record("a.zip::same.txt", Some("h1")),
+            record("a.zip::edited.txt", Some("h2")),
+            record("a.zip::gone.txt", Some("h3")),
+            record("a.zip::nohash.txt", None),
+        ]);
+        let member = |path: &str, hash: Option<&str>| {
+            let mut f = build_member_index_files("a.zip", 0, None, vec![line(Some(path), LINE_CONTENT_START, "x")], hash.map(String::from));
+            f.remove(0)
+        };
+        assert!(known.unchanged(&member("same.txt", Some("h1"))));
+        assert!(!known.unchanged(&member("edited.txt", Some("h2b"))));
+        assert!(!known.unchanged(&member("nohash.txt", None)));
+        assert!(!known.unchanged(&member("added.txt", Some("h4"))));
+        assert_eq!(known.removed(), ["a.zip::gone.txt"]);
+    }
+
     // ── Non-archive files ──────────────────────────────────────────────────
 
     #[test]
@@ -413,4 +642,27 @@ mod tests {
         let inner = files.iter().find(|f| f.path == "pkg.tar.gz::inner.txt").unwrap();
         assert!(inner.lines.iter().all(|l| l.content != "outer-only content"));
     }
+
+    // ── appended_since ─────────────────────────────────────────────────────
+
+    #[test]
+    fn appended_since_detects_pure_appends() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let base_hash = hash_file(&path).unwrap();
+
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        assert_eq!(appended_since(&path, 8, &base_hash), Some(2));
+
+        // Indexed version changed in place, or did not end in a newline.
+        std::fs::write(&path, "ONE\ntwo\nthree\n").unwrap();
+        assert_eq!(appended_since(&path, 8, &base_hash), None);
+        std::fs::write(&path, "one\ntw").unwrap();
+        let partial_hash = hash_file(&path).unwrap();
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        assert_eq!(appended_since(&path, 6, &partial_hash), None);
+        // Not grown.
+        assert_eq!(appended_since(&path, 8, &base_hash), None);
+    }
 }
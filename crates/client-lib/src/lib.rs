@@ -0,0 +1,89 @@
+//! Async client for the find-anything server.
+//!
+//! This is the HTTP layer the command-line tools (`find-scan`, `find-watch`,
+//! `find-anything`, `find-admin`, `find-mcp`) are built on, for Rust programs
+//! that want to search or feed the index without shelling out to them.
+//!
+//! - [`ApiClient`] wraps every `/api/v1` endpoint: search, file and context
+//!   reads, bulk indexing, uploads and the admin routes.
+//! - [`batch`] turns extracted lines into the [`IndexFile`] records a bulk
+//!   request carries and sizes batches.
+//! - [`load_config`] reads a `client.toml` the way the tools do, and
+//!   [`apply_remote_config`] merges in the server's settings when the file
+//!   asks for them.
+//!
+//! Bulk uploads honour `[scan]` quiet hours ([`pause`]), bandwidth limits
+//! ([`throttle`]) and spool to disk while the server is down ([`spool`]) once
+//! those are attached with the `ApiClient::with_*` builders.
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use find_client_lib::{load_config, ApiClient, SearchFilters};
+//!
+//! let (config, _warnings) = load_config(&find_common::config::default_config_path())?;
+//! let client = ApiClient::new(&config.server.url, &config.server.token);
+//! let results = client.search("invoice", "fuzzy", &[], 20, 0, &SearchFilters::default()).await?;
+//! for hit in &results.results {
+//!     println!("{}/{}:{}: {}", hit.source, hit.path, hit.line_number, hit.snippet);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`IndexFile`]: find_common::api::IndexFile
+
+pub mod api;
+pub mod batch;
+pub mod pause;
+pub mod spool;
+pub mod throttle;
+
+pub use api::{ApiClient, SearchFilters};
+
+use anyhow::{Context, Result};
+
+use find_common::config::{default_state_dir, parse_client_config, ClientConfig, RemoteClientConfig};
+
+/// Read and parse the client config at `path`.  Also returns warnings about
+/// the file (unknown keys, deprecated settings) for the caller to show.
+pub fn load_config(path: &str) -> Result<(ClientConfig, Vec<String>)> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading config {path}"))?;
+    parse_client_config(&text)
+}
+
+/// With `[server] remote_config = true`, fetch the server's scan settings
+/// and apply them to `config`.  The last copy fetched is kept in the state
+/// directory and used while the server can't be reached, so a client
+/// started offline still scans with the same settings.
+pub async fn apply_remote_config(config: &mut ClientConfig, api: &ApiClient) {
+    if !config.server.remote_config {
+        return;
+    }
+    let cache = default_state_dir().join("client-config.json");
+    let remote = match api.client_config().await {
+        Ok(remote) => {
+            if let Err(e) = save_remote_config(&cache, &remote) {
+                tracing::warn!("caching server config: {e:#}");
+            }
+            remote
+        }
+        Err(e) => match std::fs::read(&cache).ok().and_then(|data| serde_json::from_slice(&data).ok()) {
+            Some(remote) => {
+                tracing::warn!("fetching server config: {e:#}; using the copy from {}", cache.display());
+                remote
+            }
+            None => {
+                tracing::warn!("fetching server config: {e:#}; using the local config only");
+                return;
+            }
+        },
+    };
+    config.apply_remote(&remote);
+}
+
+fn save_remote_config(path: &std::path::Path, remote: &RemoteClientConfig) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_vec(remote)?).with_context(|| format!("writing {}", path.display()))
+}
@@ -0,0 +1,194 @@
+//! Quiet hours (`[scan] quiet_hours`) and on-demand pauses (`find-admin
+//! pause`, see `find_common::pause`).
+//!
+//! While either applies, bulk uploads wait, `find-scan` starts no new
+//! extractions, and `find-watch` keeps collecting changes without indexing
+//! them.  Everything carries on where it stopped once the pause lifts.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{Local, Timelike};
+use tracing::{info, warn};
+
+use find_common::config::ScanConfig;
+use find_common::pause::{pause_path, read_pause, PauseFile};
+
+use crate::throttle::UploadWindow;
+
+/// How long a read of the pause file is trusted before it is read again.
+const FILE_RECHECK: Duration = Duration::from_secs(5);
+/// Longest single sleep while paused, so a `find-admin resume` is noticed
+/// promptly.
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Why indexing is currently paused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseReason {
+    /// Inside a `[scan] quiet_hours` window that closes after `remaining`.
+    QuietHours { remaining: Duration },
+    /// `find-admin pause`, until the given Unix time or until resumed.
+    OnDemand { until: Option<i64> },
+}
+
+impl std::fmt::Display for PauseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PauseReason::QuietHours { remaining } => {
+                write!(f, "quiet hours, {}m left", remaining.as_secs().div_ceil(60))
+            }
+            PauseReason::OnDemand { until: None } => write!(f, "paused until resumed"),
+            PauseReason::OnDemand { until: Some(t) } => {
+                let local = chrono::DateTime::from_timestamp(*t, 0)
+                    .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| t.to_string());
+                write!(f, "paused until {local}")
+            }
+        }
+    }
+}
+
+pub struct PauseControl {
+    quiet_hours: Vec<UploadWindow>,
+    file: PathBuf,
+    /// Last read of the pause file and when it was made.
+    cached: Mutex<Option<(Instant, Option<PauseFile>)>>,
+    /// Whether the last check found indexing paused; only changes are logged.
+    was_paused: AtomicBool,
+}
+
+impl PauseControl {
+    /// Build the pause control for `scan`.  Fails on a malformed
+    /// `quiet_hours` entry.
+    pub fn from_scan(scan: &ScanConfig) -> Result<Self> {
+        let quiet_hours = scan.quiet_hours.iter()
+            .map(|s| UploadWindow::parse(s).ok_or_else(|| {
+                anyhow::anyhow!("invalid [scan] quiet_hours entry {s:?}: expected \"HH:MM-HH:MM\"")
+            }))
+            .collect::<Result<_>>()?;
+        Ok(PauseControl {
+            quiet_hours,
+            file: pause_path(scan),
+            cached: Mutex::new(None),
+            was_paused: AtomicBool::new(false),
+        })
+    }
+
+    /// Why indexing is paused right now, or `None` if it may go ahead.
+    pub fn reason(&self) -> Option<PauseReason> {
+        let now = Local::now();
+        if let Some(pause) = self.pause_file() {
+            if pause.active(now.timestamp()) {
+                return Some(PauseReason::OnDemand { until: pause.until });
+            }
+        }
+        let (minute, second) = (now.hour() * 60 + now.minute(), now.second());
+        self.quiet_hours.iter()
+            .filter_map(|w| w.remaining_from(minute, second))
+            .max()
+            .map(|remaining| PauseReason::QuietHours { remaining })
+    }
+
+    /// Whether indexing is paused right now.  Logs when that changes.
+    pub fn is_paused(&self) -> bool {
+        let reason = self.reason();
+        let paused = reason.is_some();
+        if self.was_paused.swap(paused, Ordering::Relaxed) != paused {
+            match reason {
+                Some(r) => info!("indexing paused ({r})"),
+                None => info!("indexing resumed"),
+            }
+        }
+        paused
+    }
+
+    /// Wait until indexing is no longer paused.
+    pub async fn wait(&self) {
+        while self.is_paused() {
+            let wait = match self.reason() {
+                Some(PauseReason::QuietHours { remaining }) => remaining.min(MAX_WAIT),
+                _ => MAX_WAIT,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn pause_file(&self) -> Option<PauseFile> {
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((read_at, pause)) = cached.as_ref() {
+            if read_at.elapsed() < FILE_RECHECK {
+                return pause.clone();
+            }
+        }
+        let pause = read_pause(&self.file).unwrap_or_else(|e| {
+            warn!("ignoring unreadable pause file: {e:#}");
+            None
+        });
+        *cached = Some((Instant::now(), pause.clone()));
+        pause
+    }
+}
+
+/// Parse a pause length such as `"90s"`, `"30m"`, `"2h"` or `"1d"`.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let n: u64 = s[..split].parse().ok()?;
+    let unit = match s[split..].trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => return None,
+    };
+    (n > 0).then(|| Duration::from_secs(n * unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn control(quiet_hours: &[&str], tmp: &TempDir) -> Result<PauseControl> {
+        PauseControl::from_scan(&ScanConfig {
+            quiet_hours: quiet_hours.iter().map(|s| s.to_string()).collect(),
+            pause_file: Some(tmp.path().join("pause.json").to_string_lossy().into_owned()),
+            ..ScanConfig::default()
+        })
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86_400)));
+        assert_eq!(parse_duration("2"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("0m"), None);
+    }
+
+    #[test]
+    fn invalid_quiet_hours_rejected() {
+        let tmp = TempDir::new().unwrap();
+        assert!(control(&["22:00-06:00"], &tmp).is_ok());
+        assert!(control(&["22:00"], &tmp).is_err());
+    }
+
+    #[test]
+    fn pause_file_pauses_until_expiry() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("pause.json");
+        let now = Local::now().timestamp();
+
+        find_common::pause::write_pause(&path, Some(now + 3600)).unwrap();
+        let paused = control(&[], &tmp).unwrap();
+        assert_eq!(paused.reason(), Some(PauseReason::OnDemand { until: Some(now + 3600) }));
+        assert!(paused.is_paused());
+
+        find_common::pause::write_pause(&path, Some(now - 1)).unwrap();
+        assert_eq!(control(&[], &tmp).unwrap().reason(), None);
+    }
+}
@@ -0,0 +1,266 @@
+//! Local spool for bulk requests the server could not be reached for.
+//!
+//! When a `POST /api/v1/bulk` fails because the server is down or
+//! unreachable, [`crate::api::ApiClient::bulk`] writes the request here
+//! instead of failing, and every later batch queues behind it so the server
+//! still applies them in order.  Spooled batches are resent oldest first,
+//! with exponential backoff between attempts, once the server answers again.
+//!
+//! Each batch is one gzip-compressed JSON file named by a sequence number.
+//! `find-scan` and `find-watch` use separate spool directories.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tracing::warn;
+
+use find_common::api::BulkRequest;
+use find_common::config::ScanConfig;
+
+/// Delay before the first retry after the server is found unreachable.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+/// Upper bound on the delay between retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+const EXT: &str = "json.gz";
+/// Extension given to batches the server refused, so they are kept for
+/// inspection but no longer block the queue.
+const REJECTED_EXT: &str = "rejected";
+
+pub struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    backoff: Mutex<Backoff>,
+}
+
+struct Backoff {
+    delay: Duration,
+    next_attempt: Instant,
+}
+
+impl Spool {
+    /// Open (creating if needed) the spool in `dir`.
+    pub fn open(dir: PathBuf, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("creating spool directory {}", dir.display()))?;
+        Ok(Spool {
+            dir,
+            max_bytes,
+            backoff: Mutex::new(Backoff { delay: INITIAL_BACKOFF, next_attempt: Instant::now() }),
+        })
+    }
+
+    /// The spool configured in `scan`, stored in `dir`, or `None` when
+    /// `[scan] spool = false`.
+    pub fn from_scan(scan: &ScanConfig, dir: PathBuf) -> Result<Option<Self>> {
+        if !scan.spool {
+            return Ok(None);
+        }
+        Self::open(dir, scan.spool_max_mb * 1024 * 1024).map(Some)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Spooled batches, oldest first.
+    pub fn entries(&self) -> Vec<PathBuf> {
+        let Ok(rd) = std::fs::read_dir(&self.dir) else { return vec![] };
+        let mut entries: Vec<PathBuf> = rd
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.ends_with(EXT)))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Append `req` to the spool.  Fails once the spool would exceed
+    /// `[scan] spool_max_mb`.
+    pub fn push(&self, req: &BulkRequest) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, req).context("serialising spooled batch")?;
+        let data = encoder.finish().context("compressing spooled batch")?;
+
+        let entries = self.entries();
+        let used: u64 = entries.iter()
+            .filter_map(|p| p.metadata().ok())
+            .map(|m| m.len())
+            .sum();
+        if used + data.len() as u64 > self.max_bytes {
+            anyhow::bail!(
+                "spool {} is full ({} MB); not accepting more batches until the server is reachable",
+                self.dir.display(),
+                used / (1024 * 1024),
+            );
+        }
+
+        let seq = self.last_sequence() + 1;
+        let path = self.dir.join(format!("{seq:012}.{EXT}"));
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, &data).with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))?;
+        Ok(())
+    }
+
+    /// Highest sequence number in use, including rejected batches.
+    fn last_sequence(&self) -> u64 {
+        let Ok(rd) = std::fs::read_dir(&self.dir) else { return 0 };
+        rd.filter_map(|e| sequence_of(&e.ok()?.path())).max().unwrap_or(0)
+    }
+
+    /// Read a spooled batch.
+    pub fn load(&self, path: &Path) -> Result<BulkRequest> {
+        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut json = Vec::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_end(&mut json)
+            .with_context(|| format!("decompressing {}", path.display()))?;
+        serde_json::from_slice(&json).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Drop a batch the server has accepted.
+    pub fn remove(&self, path: &Path) {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("failed to remove spooled batch {}: {e}", path.display());
+        }
+    }
+
+    /// Set aside a batch the server refused so later batches can proceed.
+    pub fn reject(&self, path: &Path) {
+        let target = path.with_extension(REJECTED_EXT);
+        if let Err(e) = std::fs::rename(path, &target) {
+            warn!("failed to set aside spooled batch {}: {e}", path.display());
+            self.remove(path);
+        }
+    }
+
+    /// Whether the backoff delay since the last failed attempt has elapsed.
+    pub fn ready(&self) -> bool {
+        self.backoff.lock().unwrap_or_else(|e| e.into_inner()).next_attempt <= Instant::now()
+    }
+
+    /// Time until the next retry is due, or `None` when nothing is spooled.
+    pub fn retry_in(&self) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        let backoff = self.backoff.lock().unwrap_or_else(|e| e.into_inner());
+        Some(backoff.next_attempt.saturating_duration_since(Instant::now()))
+    }
+
+    /// The server was unreachable: wait longer before the next attempt.
+    /// When the server said how long to wait (`Retry-After`), wait exactly
+    /// that instead.
+    pub fn record_failure(&self, retry_after: Option<Duration>) {
+        let mut backoff = self.backoff.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(wait) = retry_after {
+            backoff.next_attempt = Instant::now() + wait;
+            return;
+        }
+        backoff.next_attempt = Instant::now() + backoff.delay;
+        backoff.delay = (backoff.delay * 2).min(MAX_BACKOFF);
+    }
+
+    /// The server accepted a batch: retry immediately from now on.
+    pub fn record_success(&self) {
+        let mut backoff = self.backoff.lock().unwrap_or_else(|e| e.into_inner());
+        backoff.delay = INITIAL_BACKOFF;
+        backoff.next_attempt = Instant::now();
+    }
+}
+
+/// Sequence number of a spool file (`000000000042.json.gz` → 42).
+fn sequence_of(path: &Path) -> Option<u64> {
+    path.file_name()?.to_str()?.split('.').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(source: &str) -> BulkRequest {
+        BulkRequest {
+            source: source.to_string(),
+            files: vec![],
+            delete_paths: vec!["gone.txt".to_string()],
+            scan_timestamp: None,
+            indexing_failures: vec![],
+            rename_paths: vec![],
+        }
+    }
+
+    #[test]
+    fn batches_come_back_in_order() {
+        let tmp = TempDir::new().unwrap();
+        let spool = Spool::open(tmp.path().join("spool"), 1024 * 1024).unwrap();
+        assert!(spool.is_empty());
+        spool.push(&request("first")).unwrap();
+        spool.push(&request("second")).unwrap();
+
+        let entries = spool.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(spool.load(&entries[0]).unwrap().source, "first");
+        spool.remove(&entries[0]);
+        spool.push(&request("third")).unwrap();
+
+        let sources: Vec<String> = spool.entries().iter().map(|p| spool.load(p).unwrap().source).collect();
+        assert_eq!(sources, ["second", "third"]);
+    }
+
+    #[test]
+    fn rejected_batches_leave_the_queue() {
+        let tmp = TempDir::new().unwrap();
+        let spool = Spool::open(tmp.path().to_path_buf(), 1024 * 1024).unwrap();
+        spool.push(&request("bad")).unwrap();
+        spool.reject(&spool.entries()[0]);
+        assert!(spool.is_empty());
+        spool.push(&request("next")).unwrap();
+        assert_eq!(sequence_of(&spool.entries()[0]), Some(2));
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn push_fails_when_full() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().to_path_buf();
+        Spool::open(dir.clone(), 1024 * 1024).unwrap().push(&request("a")).unwrap();
+        let used = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().metadata().unwrap().len()).sum::<u64>();
+
+        let spool = Spool::open(dir, used + 8).unwrap();
+        assert!(spool.push(&request("b")).is_err());
+        assert_eq!(spool.entries().len(), 1);
+    }
+
+    #[test]
+    fn backoff_grows_and_resets() {
+        let tmp = TempDir::new().unwrap();
+        let spool = Spool::open(tmp.path().to_path_buf(), 1024 * 1024).unwrap();
+        assert!(spool.ready());
+        spool.record_failure(None);
+        assert!(!spool.ready());
+        spool.record_failure(None);
+        assert_eq!(spool.backoff.lock().unwrap().delay, INITIAL_BACKOFF * 4);
+        spool.record_success();
+        assert!(spool.ready());
+    }
+
+    #[test]
+    fn retry_after_replaces_backoff() {
+        let tmp = TempDir::new().unwrap();
+        let spool = Spool::open(tmp.path().to_path_buf(), 1024 * 1024).unwrap();
+        spool.record_failure(Some(Duration::from_secs(600)));
+        let wait = spool.backoff.lock().unwrap().next_attempt - Instant::now();
+        assert!(wait > MAX_BACKOFF, "Retry-After is not capped by the backoff limit");
+        assert_eq!(spool.backoff.lock().unwrap().delay, INITIAL_BACKOFF);
+    }
+}
@@ -0,0 +1,185 @@
+//! Upload bandwidth limits from `[scan] upload_rate_limit_kbps` and
+//! `[scan] upload_window`.
+//!
+//! Every request body the client uploads (bulk batches, resumable chunks,
+//! server-fallback files, originals) first waits on the process-wide
+//! [`UploadThrottle`]: until the upload window is open, then until the bytes
+//! sent before it have drained at the configured rate.  Bodies are split into
+//! chunks of about one second's worth of data so the link is never saturated
+//! for long.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Local, Timelike};
+use tokio::time::Instant;
+use tracing::info;
+
+use find_common::config::ScanConfig;
+
+/// Smallest chunk a throttled upload is split into.
+const MIN_CHUNK: usize = 16 * 1024;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// A daily local-time window, `HH:MM-HH:MM`, that may wrap midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadWindow {
+    /// Minutes since midnight at which the window opens (inclusive).
+    start: u32,
+    /// Minutes since midnight at which the window closes (exclusive).
+    end: u32,
+}
+
+impl UploadWindow {
+    /// Parse `"22:00-06:00"`.  `None` on bad input or an empty window.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (start, end) = s.split_once('-')?;
+        let window = UploadWindow { start: parse_hhmm(start)?, end: parse_hhmm(end)? };
+        (window.start != window.end).then_some(window)
+    }
+
+    fn contains(&self, minute: u32) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Time from `minute:second` (local) until the window opens; zero when
+    /// it is open.
+    fn wait_from(&self, minute: u32, second: u32) -> Duration {
+        if self.contains(minute) {
+            return Duration::ZERO;
+        }
+        let minutes = (self.start + MINUTES_PER_DAY - minute) % MINUTES_PER_DAY;
+        Duration::from_secs(u64::from(minutes * 60 - second))
+    }
+
+    /// Time from `minute:second` (local) until the window closes; `None`
+    /// when it is not open.
+    pub fn remaining_from(&self, minute: u32, second: u32) -> Option<Duration> {
+        if !self.contains(minute) {
+            return None;
+        }
+        let minutes = (self.end + MINUTES_PER_DAY - minute) % MINUTES_PER_DAY;
+        Some(Duration::from_secs(u64::from(minutes * 60 - second)))
+    }
+}
+
+/// Parse `"HH:MM"` into minutes since midnight.
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let h: u32 = h.trim().parse().ok()?;
+    let m: u32 = m.trim().parse().ok()?;
+    if h > 23 || m > 59 { return None; }
+    Some(h * 60 + m)
+}
+
+/// Process-wide pacing for uploads to the server.
+pub struct UploadThrottle {
+    /// 0 = no rate limit.
+    bytes_per_sec: u64,
+    window: Option<UploadWindow>,
+    /// When everything admitted so far will have drained at `bytes_per_sec`.
+    next_free: Mutex<Instant>,
+}
+
+impl UploadThrottle {
+    /// Build the throttle configured in `scan`, or `None` when uploads are
+    /// unrestricted.  Fails on a malformed `upload_window`.
+    pub fn from_scan(scan: &ScanConfig) -> Result<Option<Self>> {
+        let window = match scan.upload_window.as_deref() {
+            None | Some("") => None,
+            Some(s) => Some(UploadWindow::parse(s).ok_or_else(|| {
+                anyhow::anyhow!("invalid [scan] upload_window {s:?}: expected \"HH:MM-HH:MM\"")
+            })?),
+        };
+        if scan.upload_rate_limit_kbps == 0 && window.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(UploadThrottle {
+            bytes_per_sec: scan.upload_rate_limit_kbps * 1000 / 8,
+            window,
+            next_free: Mutex::new(Instant::now()),
+        }))
+    }
+
+    /// Largest body to send in one request: about one second of data at the
+    /// configured rate, capped at `max`.
+    pub fn chunk_size(&self, max: usize) -> usize {
+        if self.bytes_per_sec == 0 {
+            return max;
+        }
+        (self.bytes_per_sec as usize).clamp(MIN_CHUNK, max.max(MIN_CHUNK))
+    }
+
+    /// Wait until `bytes` may be sent.
+    pub async fn acquire(&self, bytes: usize) {
+        if let Some(window) = &self.window {
+            let now = Local::now();
+            let wait = window.wait_from(now.hour() * 60 + now.minute(), now.second());
+            if !wait.is_zero() {
+                info!("outside the upload window; holding uploads for {}m", wait.as_secs().div_ceil(60));
+                tokio::time::sleep(wait).await;
+            }
+        }
+        if self.bytes_per_sec == 0 {
+            return;
+        }
+        let start = {
+            let mut next = self.next_free.lock().unwrap_or_else(|e| e.into_inner());
+            let start = (*next).max(Instant::now());
+            *next = start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_parse() {
+        assert_eq!(UploadWindow::parse("22:00-06:30"), Some(UploadWindow { start: 1320, end: 390 }));
+        assert_eq!(UploadWindow::parse(" 9:05 - 17:00 "), Some(UploadWindow { start: 545, end: 1020 }));
+        assert_eq!(UploadWindow::parse("22:00"), None);
+        assert_eq!(UploadWindow::parse("24:00-06:00"), None);
+        assert_eq!(UploadWindow::parse("08:00-08:00"), None);
+    }
+
+    #[test]
+    fn window_wait() {
+        let day = UploadWindow::parse("09:00-17:00").unwrap();
+        assert_eq!(day.wait_from(9 * 60, 0), Duration::ZERO);
+        assert_eq!(day.wait_from(17 * 60, 0), Duration::from_secs(16 * 3600));
+        assert_eq!(day.wait_from(8 * 60 + 59, 30), Duration::from_secs(30));
+
+        let night = UploadWindow::parse("22:00-06:00").unwrap();
+        assert_eq!(night.wait_from(23 * 60, 0), Duration::ZERO);
+        assert_eq!(night.wait_from(3 * 60, 0), Duration::ZERO);
+        assert_eq!(night.wait_from(6 * 60, 0), Duration::from_secs(16 * 3600));
+    }
+
+    #[test]
+    fn window_remaining() {
+        let night = UploadWindow::parse("22:00-06:00").unwrap();
+        assert_eq!(night.remaining_from(23 * 60, 0), Some(Duration::from_secs(7 * 3600)));
+        assert_eq!(night.remaining_from(5 * 60 + 59, 30), Some(Duration::from_secs(30)));
+        assert_eq!(night.remaining_from(6 * 60, 0), None);
+    }
+
+    #[test]
+    fn chunk_size_tracks_rate() {
+        let scan = |kbps| ScanConfig { upload_rate_limit_kbps: kbps, ..ScanConfig::default() };
+        let t = UploadThrottle::from_scan(&scan(8_000)).unwrap().unwrap();
+        assert_eq!(t.chunk_size(2 * 1024 * 1024), 1_000_000);
+        let slow = UploadThrottle::from_scan(&scan(8)).unwrap().unwrap();
+        assert_eq!(slow.chunk_size(2 * 1024 * 1024), MIN_CHUNK);
+        assert!(UploadThrottle::from_scan(&scan(0)).unwrap().is_none());
+    }
+}
@@ -2,10 +2,18 @@ use anyhow::{Context, Result};
 use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use colored::Colorize;
 
-use find_common::api::{RecentAction, WorkerQueueSlot, WorkerStatus};
-use find_common::config::{default_config_path, parse_client_config};
+use find_client_lib::{api, pause};
+use find_common::api::{FileKind, MaintainRequest, MaintenanceRequest, RecentAction, ReextractRequest, TrashRestoreRequest, WorkerQueueSlot, WorkerStatus};
+use find_common::config::{default_config_path, parse_client_config, validate_client_config, ClientConfig, DiagnosticSeverity};
+use find_common::control::{self, ControlRequest, ControlResponse};
 
-mod api;
+mod cloud_auth;
+mod completions;
+mod ignore_files;
+mod ingest_view;
+mod path_util;
+mod verify;
+mod walk;
 
 #[derive(Parser)]
 #[command(name = "find-admin", about = "Administrative utilities for find-anything", version)]
@@ -23,7 +31,13 @@ struct Args {
 #[derive(Subcommand)]
 enum Command {
     /// Print effective client configuration with defaults filled in
-    Config,
+    Config {
+        /// Check the config file instead: report unknown keys, type errors,
+        /// duplicate source names and unreachable source paths with line
+        /// numbers, exiting 1 if there are errors
+        #[arg(long)]
+        check: bool,
+    },
     /// Print per-source statistics from the server
     Status {
         /// Refresh statistics every 2 seconds until Ctrl+C
@@ -61,12 +75,70 @@ enum Command {
     InboxPause,
     /// Resume inbox processing after a pause
     InboxResume,
+    /// Show or switch the server's maintenance mode: while on, new indexing
+    /// requests are refused (find-scan and find-watch spool them) and
+    /// search keeps working
+    Maintenance {
+        #[command(subcommand)]
+        action: Option<MaintenanceCommand>,
+    },
     /// Remove orphaned chunks from the content store to reclaim disk space
     Compact {
         /// Report what would be freed without modifying any files
         #[arg(long)]
         dry_run: bool,
     },
+    /// Reclaim space in the source databases and the content store.
+    /// With no step flags, all three steps run.
+    Maintain {
+        /// Only maintain this source (content chunks are always collected server-wide)
+        #[arg(long)]
+        source: Option<String>,
+        /// Rebuild each source DB to return free pages to the filesystem
+        #[arg(long)]
+        vacuum: bool,
+        /// Merge the full-text index segments of each source DB
+        #[arg(long)]
+        optimize_fts: bool,
+        /// Remove orphaned content chunks (same as `compact`)
+        #[arg(long)]
+        gc_chunks: bool,
+    },
+    /// Queue files extracted by an older release for re-extraction by the
+    /// next `find-scan --upgrade`
+    Reextract {
+        /// Only files in this source
+        #[arg(long)]
+        source: Option<String>,
+        /// Only files of this kind (pdf, document, image, ...)
+        #[arg(long)]
+        kind: Option<String>,
+        /// Only files extracted by this extractor, e.g. find-extract-pdf
+        #[arg(long)]
+        extractor: Option<String>,
+        /// Queue files extracted by a release older than this (default: the server's version)
+        #[arg(long, value_name = "VERSION")]
+        before: Option<String>,
+        /// Also queue files indexed before extractor versions were recorded
+        #[arg(long)]
+        include_unrecorded: bool,
+        /// Count the files without queueing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Compare local sources on disk with the index and report missing,
+    /// stale (modified since indexed) and orphaned (deleted on disk) files
+    Verify {
+        /// Only verify this source (default: every local source in client.toml)
+        #[arg(long)]
+        source: Option<String>,
+        /// Run `find-scan --source` for each source with differences
+        #[arg(long)]
+        fix: bool,
+        /// Paths listed per category (0 = all)
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
     /// Show the contents of a named inbox item (searches pending and failed queues)
     InboxShow {
         /// Inbox filename, with or without .gz extension
@@ -84,13 +156,115 @@ enum Command {
         #[arg(long, short = 'f')]
         follow: bool,
     },
-    /// Delete all indexed data for a source (DB + content chunks)
+    /// Live view of the server's inbox worker: current file, inbox depth,
+    /// throughput, recent activity and recent failures (Ctrl+C to stop)
+    Watch,
+    /// Show what the local find-watch is doing (asked over its control
+    /// socket, or read from its heartbeat file)
+    WatchStatus,
+    /// Pause local indexing: find-scan and find-watch hold off until resumed
+    Pause {
+        /// Resume automatically after this long, e.g. "2h", "30m", "1d"
+        #[arg(long = "for", value_name = "DURATION")]
+        duration: Option<String>,
+    },
+    /// Resume local indexing after `find-admin pause`
+    Resume,
+    /// Have the local find-watch start find-scan now
+    ScanNow {
+        /// Scan only this source
+        #[arg(long)]
+        source: Option<String>,
+        /// Re-index every file, not just those that changed
+        #[arg(long)]
+        force: bool,
+    },
+    /// Have the local find-watch re-read its config file
+    Reload,
+    /// Sign in to the cloud drive of a source and save its refresh token
+    CloudLogin {
+        /// Name of a source whose path is gdrive:, onedrive: or dropbox:
+        source: String,
+    },
+    /// Delete or rename an indexed source
+    Source {
+        #[command(subcommand)]
+        action: SourceCommand,
+    },
+    /// List or restore deleted files kept in a source's trash
+    /// (server setting `trash_days`)
+    Trash {
+        #[command(subcommand)]
+        action: TrashCommand,
+    },
+    /// Same as `source delete` (kept for existing scripts)
+    #[command(hide = true)]
     DeleteSource {
+        source: String,
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SourceCommand {
+    /// Delete all indexed data for a source (DB + content chunks)
+    Delete {
         /// Name of the source to delete
         source: String,
         /// Skip confirmation prompt
+        #[arg(long, short, alias = "force")]
+        yes: bool,
+    },
+    /// Rename a source, keeping its index, annotations and share links
+    Rename {
+        /// Current name of the source
+        source: String,
+        /// New name
+        new_name: String,
+        /// Skip confirmation prompt
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintenanceCommand {
+    /// Start refusing indexing requests
+    On {
+        /// Shown to clients whose requests are refused, e.g. "nightly backup"
         #[arg(long)]
-        force: bool,
+        reason: Option<String>,
+        /// Seconds clients wait before retrying (default: 300)
+        #[arg(long, value_name = "SECS")]
+        retry_after: Option<u64>,
+    },
+    /// Accept indexing requests again
+    Off,
+}
+
+#[derive(Subcommand)]
+enum TrashCommand {
+    /// List deleted files, most recent first
+    List {
+        /// Source to list
+        source: String,
+        /// Only files under this directory
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Files to show
+        #[arg(long, short, default_value = "50")]
+        limit: usize,
+    },
+    /// Put deleted files back in the index, with their archive members
+    Restore {
+        /// Source to restore into
+        source: String,
+        /// Paths of the files to restore
+        paths: Vec<String>,
+        /// Restore every file under this directory ("" for the whole trash)
+        #[arg(long)]
+        prefix: Option<String>,
     },
 }
 
@@ -101,22 +275,29 @@ async fn main() -> Result<()> {
         .with_writer(std::io::stderr)
         .init();
 
+    completions::handle(Args::command());
     let args = Args::from_arg_matches(&Args::command().version(find_common::tool_version!()).get_matches()).unwrap_or_else(|e| e.exit());
 
     let config_path = args.config.clone().unwrap_or_else(default_config_path);
     let config_str = std::fs::read_to_string(&config_path)
         .with_context(|| format!("reading config: {config_path}"))?;
-    let (config, config_warnings) = parse_client_config(&config_str)?;
+    // Checked before loading: the point is to explain why loading fails.
+    if let Command::Config { check: true } = args.command {
+        let ok = check_config(&config_path, &config_str, args.json)?;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    let (config, config_warnings) = parse_client_config(&config_str)
+        .with_context(|| format!("{config_path} is invalid (run `find-admin config --check` for details)"))?;
     for w in &config_warnings { eprintln!("Warning: {w}"); }
 
     // Check version compatibility for all commands that talk to the server.
-    if !matches!(args.command, Command::Config) {
+    if !matches!(args.command, Command::Config { .. } | Command::WatchStatus | Command::Pause { .. } | Command::Resume | Command::ScanNow { .. } | Command::Reload | Command::CloudLogin { .. }) {
         let client = api::ApiClient::new(&config.server.url, &config.server.token);
         client.check_server_version().await?;
     }
 
     match args.command {
-        Command::Config => {
+        Command::Config { .. } => {
             if args.json {
                 let json = serde_json::to_string_pretty(&config)
                     .context("serializing config to JSON")?;
@@ -157,6 +338,50 @@ async fn main() -> Result<()> {
             }
         }
 
+        Command::Watch => {
+            use std::io::Write;
+            use std::sync::{Arc, Mutex};
+            use std::time::{Duration, Instant};
+
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            let view = Arc::new(Mutex::new(ingest_view::IngestView::default()));
+
+            let stats = client.stream_stats({
+                let view = Arc::clone(&view);
+                move |event| view.lock().unwrap().on_stats(event, Instant::now())
+            });
+            let activity = client.stream_recent(ingest_view::RECENT_SHOWN, false, {
+                let view = Arc::clone(&view);
+                move |file| view.lock().unwrap().on_file(file)
+            });
+            let redraw = async {
+                let mut tick = tokio::time::interval(Duration::from_secs(1));
+                let mut last_inbox_poll: Option<Instant> = None;
+                loop {
+                    tick.tick().await;
+                    if last_inbox_poll.is_none_or(|t| t.elapsed() >= Duration::from_secs(5)) {
+                        if let Ok(status) = client.inbox_status().await {
+                            view.lock().unwrap().set_failures(status.failed);
+                        }
+                        last_inbox_poll = Some(Instant::now());
+                    }
+                    let output = view.lock().unwrap().render(Instant::now());
+                    print!("\x1b[H\x1b[J{output}");
+                    std::io::stdout().flush().ok();
+                }
+            };
+            tokio::select! {
+                result = stats => {
+                    if let Err(e) = result { eprintln!("Stream error: {e:#}"); }
+                }
+                result = activity => {
+                    if let Err(e) = result { eprintln!("Stream error: {e:#}"); }
+                }
+                _ = redraw => {}
+                _ = tokio::signal::ctrl_c() => { println!(); }
+            }
+        }
+
         Command::Sources => {
             let client = api::ApiClient::new(&config.server.url, &config.server.token);
             let sources = client.get_sources().await.context("fetching sources")?;
@@ -243,6 +468,15 @@ async fn main() -> Result<()> {
                         format_bytes(item.size_bytes),
                         format_age(item.age_secs),
                     );
+                    if let Some(f) = &item.failure {
+                        let at = match (&f.source, &f.file) {
+                            (Some(source), Some(file)) => format!(" ({source}: {file})"),
+                            (Some(source), None) => format!(" ({source})"),
+                            (None, Some(file)) => format!(" ({file})"),
+                            (None, None) => String::new(),
+                        };
+                        println!("    {} after {} attempt(s){at}: {}", "error".red(), f.attempts, f.error);
+                    }
                 }
             }
         }
@@ -312,6 +546,34 @@ async fn main() -> Result<()> {
             println!("Inbox resumed.");
         }
 
+        Command::Maintenance { action } => {
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            let status = match action {
+                None => client.maintenance_status().await.context("fetching maintenance mode")?,
+                Some(MaintenanceCommand::On { reason, retry_after }) => {
+                    let req = MaintenanceRequest { enabled: true, reason, retry_after_secs: retry_after };
+                    client.set_maintenance(&req).await.context("turning maintenance mode on")?
+                }
+                Some(MaintenanceCommand::Off) => {
+                    let req = MaintenanceRequest { enabled: false, reason: None, retry_after_secs: None };
+                    client.set_maintenance(&req).await.context("turning maintenance mode off")?
+                }
+            };
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else if status.enabled {
+                let since = status.since
+                    .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                    .map(|utc| chrono::DateTime::<chrono::Local>::from(utc).format(" since %Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                let reason = status.reason.as_deref().map(|r| format!(" ({r})")).unwrap_or_default();
+                println!("Maintenance mode is on{since}{reason}: indexing requests are refused, clients retry after {}s.",
+                    status.retry_after_secs);
+            } else {
+                println!("Maintenance mode is off.");
+            }
+        }
+
         Command::Compact { dry_run } => {
             let client = api::ApiClient::new(&config.server.url, &config.server.token);
             if dry_run {
@@ -320,7 +582,7 @@ async fn main() -> Result<()> {
                 println!("Compacting content store...");
             }
             let resp = client.compact(dry_run).await.context("running compact")?;
-            let nothing_to_do = resp.chunks_removed == 0 && resp.units_deleted == 0;
+            let nothing_to_do = resp.chunks_removed == 0 && resp.units_deleted == 0 && resp.originals_removed == 0;
             if nothing_to_do {
                 println!("No orphaned chunks found across {} storage unit(s).", resp.units_scanned);
             } else if dry_run {
@@ -333,6 +595,9 @@ async fn main() -> Result<()> {
                     would_delete,
                     resp.units_scanned,
                 );
+                if resp.originals_removed > 0 {
+                    println!("Would remove {} unreferenced original file(s).", resp.originals_removed);
+                }
                 println!("Run without --dry-run to apply.");
             } else {
                 let mut parts: Vec<String> = Vec::new();
@@ -348,24 +613,160 @@ async fn main() -> Result<()> {
                 if resp.chunks_removed > 0 {
                     parts.push(format!("removed {} orphaned chunk(s)", resp.chunks_removed));
                 }
+                if resp.originals_removed > 0 {
+                    parts.push(format!("removed {} unreferenced original(s)", resp.originals_removed));
+                }
                 println!("{}.", parts.join(", ").replace("freed", "Freed"));
             }
         }
 
-        Command::DeleteSource { source, force } => {
+        Command::Maintain { source, vacuum, optimize_fts, gc_chunks } => {
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            let all = !(vacuum || optimize_fts || gc_chunks);
+            let req = MaintainRequest {
+                source,
+                vacuum: vacuum || all,
+                optimize_fts: optimize_fts || all,
+                gc_chunks: gc_chunks || all,
+            };
+            if !args.json {
+                eprintln!("Running maintenance (indexing waits while each source is processed)...");
+            }
+            let resp = client.maintain(&req).await.context("running maintenance")?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+                return Ok(());
+            }
+
+            if !resp.sources.is_empty() {
+                let width = resp.sources.iter().map(|s| s.source.len()).max().unwrap_or(0).max(6);
+                println!("{:<width$}  {:>10}  {:>10}  {:>10}", "Source", "Before", "After", "Reclaimed");
+                let mut total: u64 = 0;
+                for s in &resp.sources {
+                    let reclaimed = s.bytes_before.saturating_sub(s.bytes_after);
+                    total += reclaimed;
+                    println!(
+                        "{:<width$}  {:>10}  {:>10}  {:>10}",
+                        s.source,
+                        format_bytes(s.bytes_before),
+                        format_bytes(s.bytes_after),
+                        format_bytes(reclaimed),
+                    );
+                }
+                if resp.sources.len() > 1 {
+                    println!("{:<width$}  {:>10}  {:>10}  {:>10}", "Total", "", "", format_bytes(total));
+                }
+            }
+            if let Some(c) = &resp.compact {
+                println!(
+                    "Content store: freed {}, removed {} orphaned chunk(s) and {} unreferenced original(s).",
+                    format_bytes(c.bytes_freed),
+                    c.chunks_removed,
+                    c.originals_removed,
+                );
+            }
+        }
+
+        Command::Reextract { source, kind, extractor, before, include_unrecorded, dry_run } => {
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            let kind = match kind.as_deref() {
+                None => None,
+                Some(k) => match FileKind::from(k) {
+                    FileKind::Unknown if k != "unknown" => anyhow::bail!("unknown file kind {k:?}"),
+                    kind => Some(kind),
+                },
+            };
+            let req = ReextractRequest { source, kind, extractor, before, include_unrecorded, dry_run };
+            let resp = client.reextract(&req).await.context("queueing re-extraction")?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+                return Ok(());
+            }
+
+            let total: usize = resp.sources.iter().map(|s| s.files).sum();
+            for s in resp.sources.iter().filter(|s| s.files > 0) {
+                println!("{:<24} {:>8}", s.source, s.files);
+            }
+            if total == 0 {
+                println!("No files extracted before {} match.", resp.before);
+            } else if dry_run {
+                println!("Would queue {total} file(s) extracted before {}. Run without --dry-run to apply.", resp.before);
+            } else {
+                println!("Queued {total} file(s) extracted before {}. Run `find-scan --upgrade` to re-extract them.", resp.before);
+            }
+        }
+
+        Command::Verify { source, fix, limit } => {
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            if let Some(name) = &source {
+                if !config.sources.iter().any(|s| s.name == *name) {
+                    anyhow::bail!("no source named {name:?} in {config_path}");
+                }
+            }
+            let sources: Vec<_> = config.sources.iter()
+                .filter(|s| source.as_ref().is_none_or(|n| s.name == *n))
+                .filter(|s| !s.is_remote())
+                .collect();
+            if sources.is_empty() {
+                anyhow::bail!("no local sources to verify (S3 and cloud drive sources are skipped)");
+            }
+
+            if let Ok(status) = client.inbox_status().await {
+                if !status.pending.is_empty() {
+                    eprintln!(
+                        "Note: the server inbox has {} pending batch(es); files in them show as missing or stale until indexed.",
+                        status.pending.len()
+                    );
+                }
+            }
+
+            let mut reports = Vec::new();
+            for s in sources {
+                if !args.json {
+                    eprintln!("Verifying {} ({})...", s.name, s.path);
+                }
+                let report = verify::verify_source(&client, s, &s.scan_config(&config.scan))
+                    .await
+                    .with_context(|| format!("verifying source {}", s.name))?;
+                if !args.json {
+                    print_verify_report(&report, limit);
+                }
+                reports.push(report);
+            }
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&reports)?);
+            }
+
+            let dirty: Vec<&verify::VerifyReport> = reports.iter().filter(|r| !r.is_clean()).collect();
+            if dirty.is_empty() {
+                return Ok(());
+            }
+            if !fix {
+                if !args.json {
+                    eprintln!("Run again with --fix, or run find-scan, to bring the index up to date.");
+                }
+                std::process::exit(1);
+            }
+            let find_scan = verify::find_scan_exe();
+            for r in dirty {
+                eprintln!("Running {} --source {}...", find_scan.display(), r.source);
+                let status = tokio::process::Command::new(&find_scan)
+                    .args(["--config", &config_path, "--source", &r.source])
+                    .status()
+                    .await
+                    .with_context(|| format!("running {}", find_scan.display()))?;
+                if !status.success() {
+                    anyhow::bail!("find-scan --source {} exited with {status}", r.source);
+                }
+            }
+        }
+
+        Command::Source { action: SourceCommand::Delete { source, yes } }
+        | Command::DeleteSource { source, force: yes } => {
             let client = api::ApiClient::new(&config.server.url, &config.server.token);
 
-            if !force {
-                let sources = client.get_sources().await.context("fetching sources")?;
-                if !sources.iter().any(|s| s.name == source) {
-                    eprintln!("Source '{}' not found.", source);
-                    std::process::exit(1);
-                }
-                let stats = client.get_stats(false).await.context("fetching stats")?;
-                let file_count = stats.sources.iter()
-                    .find(|s| s.name == source)
-                    .map(|s| s.total_files)
-                    .unwrap_or(0);
+            if !yes {
+                let file_count = source_file_count(&client, &source).await?;
                 eprint!(
                     "Delete source '{}' ({} files)? This cannot be undone. [y/N] ",
                     source, file_count
@@ -388,6 +789,82 @@ async fn main() -> Result<()> {
             );
         }
 
+        Command::Source { action: SourceCommand::Rename { source, new_name, yes } } => {
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+
+            if !yes {
+                let file_count = source_file_count(&client, &source).await?;
+                eprint!(
+                    "Rename source '{}' ({} files) to '{}'? [y/N] ",
+                    source, file_count, new_name
+                );
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).context("reading confirmation")?;
+                match input.trim() {
+                    "y" | "Y" => {}
+                    _ => {
+                        eprintln!("Aborted.");
+                        return Ok(());
+                    }
+                }
+            }
+
+            let resp = client.rename_source(&source, &new_name).await.context("renaming source")?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+            } else {
+                println!(
+                    "Renamed source '{}' to '{}': {} files, {} annotations, {} share links.",
+                    source, new_name, resp.files, resp.annotations, resp.links,
+                );
+                println!(
+                    "Update name = \"{}\" in [[sources]] of each client.toml that scans it, or the next scan will re-create '{}'.",
+                    new_name, source,
+                );
+            }
+        }
+
+        Command::Trash { action: TrashCommand::List { source, prefix, limit } } => {
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            let resp = client.trash(&source, prefix.as_deref(), limit).await.context("fetching trash")?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+                return Ok(());
+            }
+            if resp.files.is_empty() {
+                println!("The trash of '{source}' is empty.");
+                return Ok(());
+            }
+            let date = |ts: i64| chrono::DateTime::from_timestamp(ts, 0)
+                .map(|utc| chrono::DateTime::<chrono::Local>::from(utc).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| ts.to_string());
+            println!("{:<16}  {:<16}  {:>10}  Path", "Deleted", "Purged", "Size");
+            for f in &resp.files {
+                let size = f.size.map(|s| format_bytes(s.max(0) as u64)).unwrap_or_default();
+                println!("{:<16}  {:<16}  {:>10}  {}", date(f.deleted_at), date(f.purge_at), size, f.path);
+            }
+            if resp.total > resp.files.len() {
+                println!("... and {} more (use --limit or --prefix)", resp.total - resp.files.len());
+            }
+        }
+
+        Command::Trash { action: TrashCommand::Restore { source, paths, prefix } } => {
+            if paths.is_empty() && prefix.is_none() {
+                anyhow::bail!("give the paths to restore, or --prefix");
+            }
+            let client = api::ApiClient::new(&config.server.url, &config.server.token);
+            let req = TrashRestoreRequest { source: source.clone(), paths, prefix };
+            let resp = client.restore_trash(&req).await.context("restoring from trash")?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+            } else {
+                println!("Restored {} files to '{}'.", resp.restored, source);
+                if resp.skipped > 0 {
+                    println!("{} files were left in the trash because they have been indexed again.", resp.skipped);
+                }
+            }
+        }
+
         Command::InboxShow { name } => {
             let client = api::ApiClient::new(&config.server.url, &config.server.token);
             let resp = client.inbox_show(&name).await.context("fetching inbox item")?;
@@ -440,6 +917,74 @@ async fn main() -> Result<()> {
             }
         }
 
+        Command::WatchStatus => {
+            let path = find_common::watch_status::status_path(&config.watch);
+            let status = match ask_watcher(&config, &ControlRequest::Status)? {
+                Some(resp) => resp.status,
+                None => find_common::watch_status::read_status(&path)?,
+            };
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                match status {
+                    Some(status) => print!("{}", format_watch_status(&status)),
+                    None => println!("find-watch has not written {} — it is not running, or runs as another user with a different [watch] status_file.", path.display()),
+                }
+            }
+        }
+
+        Command::Pause { duration } => {
+            let until = match duration.as_deref() {
+                None => None,
+                Some(d) => {
+                    let d = pause::parse_duration(d)
+                        .with_context(|| format!("invalid duration {d:?} (try \"90s\", \"30m\", \"2h\" or \"1d\")"))?;
+                    Some(chrono::Utc::now().timestamp() + d.as_secs() as i64)
+                }
+            };
+            if let Some(resp) = ask_watcher(&config, &ControlRequest::Pause { until })? {
+                println!("{}", resp.message.unwrap_or_default());
+                return Ok(());
+            }
+            let path = find_common::pause::pause_path(&config.scan);
+            find_common::pause::write_pause(&path, until)?;
+            let reason = pause::PauseReason::OnDemand { until };
+            println!("Indexing {reason}.  find-scan and find-watch pick this up within a minute.");
+        }
+
+        Command::Resume => {
+            if let Some(resp) = ask_watcher(&config, &ControlRequest::Resume)? {
+                println!("{}", resp.message.unwrap_or_default());
+                return Ok(());
+            }
+            let path = find_common::pause::pause_path(&config.scan);
+            if find_common::pause::clear_pause(&path)? {
+                println!("Indexing resumed.");
+            } else {
+                println!("Indexing was not paused ({} does not exist).", path.display());
+            }
+        }
+
+        Command::ScanNow { source, force } => {
+            let resp = ask_watcher(&config, &ControlRequest::Scan { source, force })?
+                .context("find-watch is not running, or its control socket is turned off; run find-scan instead")?;
+            println!("{}", resp.message.unwrap_or_default());
+        }
+
+        Command::Reload => {
+            let resp = ask_watcher(&config, &ControlRequest::Reload)?
+                .context("find-watch is not running, or its control socket is turned off")?;
+            println!("{}", resp.message.unwrap_or_default());
+        }
+
+        Command::CloudLogin { source } => {
+            let source = config.sources.iter().find(|s| s.name == source)
+                .with_context(|| format!("no source named {source:?}"))?;
+            let saved = cloud_auth::login(source).await?;
+            println!("Signed in; refresh token saved to {}.", saved.display());
+            println!("Run find-scan to index {}.", source.name);
+        }
+
         Command::Recent { limit, mtime, follow } => {
             let client = api::ApiClient::new(&config.server.url, &config.server.token);
             if follow {
@@ -457,7 +1002,7 @@ async fn main() -> Result<()> {
                     _ = tokio::signal::ctrl_c() => { eprintln!(); }
                 }
             } else {
-                let files = client.get_recent(limit, mtime).await.context("fetching recent files")?;
+                let files = client.get_recent(limit, mtime, &[], None, &[]).await.context("fetching recent files")?;
                 if args.json {
                     println!("{}", serde_json::to_string_pretty(&files)?);
                 } else if files.is_empty() {
@@ -476,6 +1021,68 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Print the problems `validate_client_config` finds in `config_str`.
+/// Returns false if any of them is an error.
+fn check_config(config_path: &str, config_str: &str, json: bool) -> Result<bool> {
+    let diagnostics = validate_client_config(config_str);
+    let ok = !diagnostics.iter().any(|d| d.severity == DiagnosticSeverity::Error);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+        return Ok(ok);
+    }
+    for d in &diagnostics {
+        let location = d.line.map_or(config_path.to_string(), |line| format!("{config_path}:{line}"));
+        let severity = match d.severity {
+            DiagnosticSeverity::Error => "error".red(),
+            DiagnosticSeverity::Warning => "warning".yellow(),
+        };
+        println!("{location}: {severity}: {}", d.message);
+    }
+    if diagnostics.is_empty() {
+        println!("{config_path}: {}", "OK".green());
+    }
+    Ok(ok)
+}
+
+fn print_verify_report(report: &verify::VerifyReport, limit: usize) {
+    if report.is_clean() {
+        println!("{}: {} ({} files)", report.source, "OK".green(), report.local_files);
+        return;
+    }
+    println!(
+        "{}: {} missing, {} stale, {} orphaned ({} on disk, {} indexed)",
+        report.source,
+        report.missing.len(),
+        report.stale.len(),
+        report.orphaned.len(),
+        report.local_files,
+        report.indexed_files,
+    );
+    for (label, paths) in [("missing", &report.missing), ("stale", &report.stale), ("orphaned", &report.orphaned)] {
+        let shown = if limit == 0 { paths.len() } else { paths.len().min(limit) };
+        for path in &paths[..shown] {
+            println!("  {label:<9} {path}");
+        }
+        if shown < paths.len() {
+            println!("  {label:<9} ... and {} more", paths.len() - shown);
+        }
+    }
+}
+
+/// File count of `source`, exiting with an error when the server has no such source.
+async fn source_file_count(client: &api::ApiClient, source: &str) -> Result<usize> {
+    let sources = client.get_sources().await.context("fetching sources")?;
+    if !sources.iter().any(|s| s.name == source) {
+        eprintln!("Source '{}' not found.", source);
+        std::process::exit(1);
+    }
+    let stats = client.get_stats(false).await.context("fetching stats")?;
+    Ok(stats.sources.iter()
+        .find(|s| s.name == source)
+        .map(|s| s.total_files)
+        .unwrap_or(0))
+}
+
 fn print_recent_line(f: &find_common::api::RecentFile) {
     let ts = chrono::DateTime::from_timestamp(f.indexed_at, 0)
         .map(|utc| chrono::DateTime::<chrono::Local>::from(utc)
@@ -527,6 +1134,9 @@ fn format_status(stats: &find_common::api::StatsResponse) -> String {
         writeln!(out, "Inbox:    {} pending, {} failed, {} awaiting write",
             stats.inbox_pending, stats.failed_requests, stats.archive_queue).unwrap();
     }
+    if stats.maintenance {
+        writeln!(out, "Server:   {}  (indexing requests are refused)", "MAINTENANCE".yellow()).unwrap();
+    }
     writeln!(out, "Index size:   {}", format_bytes(stats.db_size_bytes)).unwrap();
     writeln!(out, "Content size: {}", format_bytes(stats.content_size_bytes)).unwrap();
     match (stats.orphaned_bytes, stats.orphaned_stats_age_secs) {
@@ -544,6 +1154,12 @@ fn format_status(stats: &find_common::api::StatsResponse) -> String {
     }
     match &stats.worker_status {
         WorkerStatus::Idle => writeln!(out, "Worker:   idle").unwrap(),
+        WorkerStatus::Processing { .. } if stats.worker_lanes.len() > 1 => {
+            writeln!(out, "Worker:   {} processing {} requests", "●".cyan(), stats.worker_lanes.len()).unwrap();
+            for lane in &stats.worker_lanes {
+                writeln!(out, "            {}/{}", lane.source, lane.file).unwrap();
+            }
+        }
         WorkerStatus::Processing { source, file } =>
             writeln!(out, "Worker:   {} processing {}/{}", "●".cyan(), source, file).unwrap(),
     }
@@ -595,12 +1211,68 @@ fn format_stream_status(event: &find_common::api::StatsStreamEvent) -> String {
     }
     match &event.worker_status {
         WorkerStatus::Idle => writeln!(out, "Worker:   idle").unwrap(),
+        WorkerStatus::Processing { .. } if event.worker_lanes.len() > 1 => {
+            writeln!(out, "Worker:   {} processing {} requests", "●".cyan(), event.worker_lanes.len()).unwrap();
+            for lane in &event.worker_lanes {
+                writeln!(out, "            {}/{}", lane.source, lane.file).unwrap();
+            }
+        }
         WorkerStatus::Processing { source, file } =>
             writeln!(out, "Worker:   {} processing {}/{}", "●".cyan(), source, file).unwrap(),
     }
     out
 }
 
+/// Send `request` to the local find-watch over its control socket.  `None`
+/// when no watcher is listening; an error when it refused the request.
+fn ask_watcher(config: &ClientConfig, request: &ControlRequest) -> Result<Option<ControlResponse>> {
+    let Some(addr) = control::control_address(&config.watch) else {
+        return Ok(None);
+    };
+    match control::send(&addr, request) {
+        Ok(resp) => resp.into_result().map(Some),
+        Err(e) => {
+            tracing::debug!("{e:#}");
+            Ok(None)
+        }
+    }
+}
+
+fn format_watch_status(status: &find_common::watch_status::WatchStatus) -> String {
+    use std::fmt::Write as _;
+    let mut out = String::new();
+    let age = |ts: Option<i64>| ts.map_or_else(|| "never".to_string(), |t| format_age(chrono_age_secs(t)));
+    let now = chrono::Utc::now().timestamp();
+    if status.is_stale(now) {
+        writeln!(out, "find-watch: {} (last heartbeat {})", "not running".red(), age(Some(status.updated_at))).unwrap();
+    } else {
+        writeln!(
+            out,
+            "find-watch: {} (pid {}, {}, started {})",
+            "running".green(), status.pid, status.version, age(Some(status.started_at)),
+        ).unwrap();
+    }
+    writeln!(out, "Sources:").unwrap();
+    for s in &status.sources {
+        writeln!(out, "  {:20}  {:7}  {}", s.name, s.backend, s.path).unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "Queue:        {} pending paths, {} queued events", status.pending_paths, status.queued_events).unwrap();
+    writeln!(out, "Last event:   {}", age(status.last_event_at)).unwrap();
+    writeln!(out, "Last upload:  {}", age(status.last_upload_at)).unwrap();
+    writeln!(out, "Indexed:      {} updated, {} deleted", status.files_updated, status.files_deleted).unwrap();
+    if let Some(reason) = &status.paused {
+        writeln!(out, "Paused:       {}", reason.yellow()).unwrap();
+    }
+    if status.spooled_batches > 0 {
+        writeln!(out, "Spooled:      {} batch(es) waiting for the server", status.spooled_batches.to_string().yellow()).unwrap();
+    }
+    if let Some(err) = &status.last_error {
+        writeln!(out, "Errors:       {} (last {}: {})", status.errors, age(status.last_error_at), err.red()).unwrap();
+    }
+    out
+}
+
 fn format_bytes(bytes: u64) -> String {
     if bytes >= 1024 * 1024 * 1024 {
         format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
@@ -0,0 +1,114 @@
+//! Per-source scan checkpoints.
+//!
+//! `find-scan` processes files in sorted walk order and saves a checkpoint
+//! after every batch the server accepts, naming the last file handled before
+//! that batch was sent.  If the scan is interrupted, the next run with the same
+//! options skips every file up to that path instead of starting over.  The
+//! checkpoint is removed when a scan completes; `find-scan --full` discards it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Progress of an unfinished scan of one source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    /// Source root paths that were walked.
+    pub paths: Vec<String>,
+    /// `--upgrade` and `--force` of the interrupted run.  A run with different
+    /// options does not resume from this checkpoint.
+    pub upgrade: bool,
+    pub force_since: Option<i64>,
+    /// When the interrupted run started (Unix seconds).
+    pub started_at: i64,
+    /// Every file up to and including this relative path, in walk order, has
+    /// been submitted to the server (or needed no submission).
+    pub done_through: String,
+}
+
+impl Checkpoint {
+    /// Whether a run over `paths` with these options may resume from here.
+    pub fn matches(&self, paths: &[String], upgrade: bool, force_since: Option<i64>) -> bool {
+        self.paths == paths && self.upgrade == upgrade && self.force_since == force_since
+    }
+}
+
+/// Checkpoint file for `source` in `dir`.
+pub(crate) fn checkpoint_path(dir: &Path, source: &str) -> PathBuf {
+    dir.join(format!("{source}.json"))
+}
+
+/// Read the checkpoint at `path`.  `None` if there is none or it is unreadable.
+pub(crate) fn load(path: &Path) -> Option<Checkpoint> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(cp) => Some(cp),
+        Err(e) => {
+            warn!("ignoring invalid scan checkpoint {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Write `cp` to `path`, replacing it atomically so an interruption mid-write
+/// never leaves a truncated checkpoint.
+pub(crate) fn save(path: &Path, cp: &Checkpoint) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(cp)?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))?;
+    Ok(())
+}
+
+/// Remove the checkpoint at `path`, if any.
+pub(crate) fn clear(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("failed to remove scan checkpoint {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> Checkpoint {
+        Checkpoint {
+            paths: vec!["/data".to_string()],
+            upgrade: false,
+            force_since: None,
+            started_at: 1_700_000_000,
+            done_through: "docs/report.pdf".to_string(),
+        }
+    }
+
+    #[test]
+    fn save_load_clear_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let path = checkpoint_path(&tmp.path().join("checkpoints"), "docs");
+        assert_eq!(load(&path), None);
+        save(&path, &sample()).unwrap();
+        assert_eq!(load(&path), Some(sample()));
+        clear(&path);
+        assert_eq!(load(&path), None);
+    }
+
+    #[test]
+    fn only_matching_runs_resume() {
+        let cp = sample();
+        let paths = vec!["/data".to_string()];
+        assert!(cp.matches(&paths, false, None));
+        assert!(!cp.matches(&paths, true, None));
+        assert!(!cp.matches(&paths, false, Some(1)));
+        assert!(!cp.matches(&["/other".to_string()], false, None));
+    }
+}
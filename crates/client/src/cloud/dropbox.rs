@@ -0,0 +1,174 @@
+//! Dropbox (API v2).  The source folder is listed recursively; the cursor
+//! returned with the listing fetches only what changed.  Items are keyed by
+//! their lowercased path, which is all a deletion reports.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{check, parse_time, Change, CloudHttp, Entry, Listing};
+
+const API: &str = "https://api.dropboxapi.com/2";
+const CONTENT_API: &str = "https://content.dropboxapi.com/2";
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = ".tag", rename_all = "lowercase")]
+enum Metadata {
+    File {
+        path_lower: String,
+        path_display: String,
+        name: String,
+        #[serde(default)]
+        size: u64,
+        #[serde(default)]
+        server_modified: Option<String>,
+        #[serde(default)]
+        content_hash: Option<String>,
+        #[serde(default)]
+        rev: Option<String>,
+    },
+    Folder {
+        path_lower: String,
+        path_display: String,
+        name: String,
+    },
+    Deleted {
+        path_lower: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct ListFolderResult {
+    #[serde(default)]
+    entries: Vec<Metadata>,
+    cursor: String,
+    #[serde(default)]
+    has_more: bool,
+}
+
+fn change(meta: Metadata) -> Change {
+    match meta {
+        Metadata::File { path_lower, path_display, name, size, server_modified, content_hash, rev } => {
+            Change::Upsert(Entry {
+                id: path_lower,
+                name,
+                path: Some(path_display),
+                size,
+                modified: parse_time(server_modified.as_deref()),
+                version: content_hash.or(rev).unwrap_or_default(),
+                ..Entry::default()
+            })
+        }
+        Metadata::Folder { path_lower, path_display, name } => Change::Upsert(Entry {
+            id: path_lower,
+            name,
+            path: Some(path_display),
+            folder: true,
+            ..Entry::default()
+        }),
+        Metadata::Deleted { path_lower } => Change::Removed { id: path_lower },
+    }
+}
+
+/// JSON for the `Dropbox-API-Arg` header, which must be ASCII: other
+/// characters are sent as `\u` escapes.
+fn api_arg(value: &serde_json::Value) -> String {
+    let mut out = String::new();
+    for c in value.to_string().chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                out.push_str(&format!("\\u{unit:04x}"));
+            }
+        }
+    }
+    out
+}
+
+pub struct Dropbox {
+    http: CloudHttp,
+    /// Folder to list: `""` for the whole Dropbox, else `/<folder>`.
+    folder: String,
+}
+
+impl Dropbox {
+    pub fn new(http: CloudHttp, folder: &str) -> Self {
+        let folder = if folder.is_empty() { String::new() } else { format!("/{folder}") };
+        Dropbox { http, folder }
+    }
+
+    pub async fn list(&self, cursor: Option<&str>) -> Result<Option<Listing>> {
+        let (mut url, mut body) = match cursor {
+            None => (
+                format!("{API}/files/list_folder"),
+                serde_json::json!({ "path": self.folder, "recursive": true }),
+            ),
+            Some(c) => (format!("{API}/files/list_folder/continue"), serde_json::json!({ "cursor": c })),
+        };
+        let mut changes = Vec::new();
+        loop {
+            let resp = self.http.send(self.http.post(&url).json(&body)).await?;
+            if resp.status() == reqwest::StatusCode::CONFLICT {
+                let text = resp.text().await.unwrap_or_default();
+                // The cursor is no longer valid: start over from a full listing.
+                if cursor.is_some() && text.contains("\"reset") {
+                    return Ok(None);
+                }
+                anyhow::bail!("{url}: {}", text.chars().take(300).collect::<String>());
+            }
+            let page: ListFolderResult = check(resp).await?.json().await.context("parsing Dropbox folder listing")?;
+            changes.extend(page.entries.into_iter().map(change));
+            if !page.has_more {
+                return Ok(Some(Listing { changes, cursor: page.cursor, complete: cursor.is_none() }));
+            }
+            url = format!("{API}/files/list_folder/continue");
+            body = serde_json::json!({ "cursor": page.cursor });
+        }
+    }
+
+    pub async fn download(&self, id: &str, dest: &Path) -> Result<()> {
+        let arg = api_arg(&serde_json::json!({ "path": id }));
+        let req = self.http.post(&format!("{CONTENT_API}/files/download")).header("Dropbox-API-Arg", arg);
+        let resp = check(self.http.send(req).await?).await?;
+        crate::remote::save_response(resp, dest).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listing_becomes_changes() {
+        let page: ListFolderResult = serde_json::from_str(r#"{
+            "entries": [
+                {".tag": "folder", "name": "Work", "path_lower": "/work", "path_display": "/Work", "id": "id:1"},
+                {".tag": "file", "name": "Plan.md", "path_lower": "/work/plan.md", "path_display": "/Work/Plan.md",
+                 "id": "id:2", "server_modified": "2024-03-01T12:00:00Z", "size": 10, "rev": "a1",
+                 "content_hash": "e3b0"},
+                {".tag": "deleted", "name": "old.txt", "path_lower": "/work/old.txt", "path_display": "/Work/old.txt"}
+            ],
+            "cursor": "AAE",
+            "has_more": false
+        }"#).unwrap();
+        assert_eq!(page.cursor, "AAE");
+        let changes: Vec<Change> = page.entries.into_iter().map(change).collect();
+        assert_eq!(changes[1], Change::Upsert(Entry {
+            id: "/work/plan.md".into(),
+            name: "Plan.md".into(),
+            path: Some("/Work/Plan.md".into()),
+            size: 10,
+            modified: 1_709_294_400,
+            version: "e3b0".into(),
+            ..Entry::default()
+        }));
+        assert_eq!(changes[2], Change::Removed { id: "/work/old.txt".into() });
+    }
+
+    #[test]
+    fn api_arg_is_ascii() {
+        assert_eq!(api_arg(&serde_json::json!({ "path": "/café/😀" })), r#"{"path":"/caf\u00e9/\ud83d\ude00"}"#);
+    }
+}
@@ -0,0 +1,228 @@
+//! Google Drive (Drive API v3).  The first listing reads every file;
+//! later ones follow the changes feed from its page token.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{check, parse_time, Change, CloudHttp, Entry, Export, Listing};
+
+const API: &str = "https://www.googleapis.com/drive/v3";
+const FILE_FIELDS: &str = "id,name,mimeType,parents,size,modifiedTime,md5Checksum,version,trashed";
+const FOLDER_MIME: &str = "application/vnd.google-apps.folder";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct File {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    parents: Vec<String>,
+    /// Sizes are sent as strings.
+    #[serde(default)]
+    size: Option<String>,
+    #[serde(default)]
+    modified_time: Option<String>,
+    #[serde(default)]
+    md5_checksum: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    trashed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList {
+    #[serde(default)]
+    files: Vec<File>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeList {
+    #[serde(default)]
+    changes: Vec<FileChange>,
+    next_page_token: Option<String>,
+    new_start_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileChange {
+    file_id: String,
+    #[serde(default)]
+    removed: bool,
+    file: Option<File>,
+}
+
+/// How a Google-native document is exported, or `None` for types with no
+/// useful text (drawings, forms, shortcuts, …).
+fn export_for(mime: &str) -> Option<Export> {
+    let (mime, ext) = match mime {
+        "application/vnd.google-apps.document" | "application/vnd.google-apps.presentation" => ("text/plain", "txt"),
+        "application/vnd.google-apps.spreadsheet" => ("text/csv", "csv"),
+        _ => return None,
+    };
+    Some(Export { mime: mime.to_string(), ext: ext.to_string() })
+}
+
+/// The change a listed file amounts to; `None` for files that are skipped.
+fn change(file: File) -> Option<Change> {
+    if file.trashed {
+        return Some(Change::Removed { id: file.id });
+    }
+    let folder = file.mime_type == FOLDER_MIME;
+    let export = match file.mime_type.strip_prefix("application/vnd.google-apps.") {
+        Some(_) if folder => None,
+        Some(_) => Some(export_for(&file.mime_type)?),
+        None => None,
+    };
+    // Native documents have no checksum; their version number changes on
+    // every edit instead.
+    let version = file.md5_checksum.or(file.version).unwrap_or_default();
+    Some(Change::Upsert(Entry {
+        id: file.id,
+        parent: file.parents.into_iter().next(),
+        name: file.name,
+        path: None,
+        folder,
+        root: false,
+        size: file.size.and_then(|s| s.parse().ok()).unwrap_or(0),
+        modified: parse_time(file.modified_time.as_deref()),
+        version,
+        export,
+    }))
+}
+
+pub struct GoogleDrive {
+    http: CloudHttp,
+}
+
+impl GoogleDrive {
+    pub fn new(http: CloudHttp) -> Self {
+        GoogleDrive { http }
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        let resp = check(self.http.send(self.http.get(url).query(query)).await?).await?;
+        resp.json().await.with_context(|| format!("parsing response from {url}"))
+    }
+
+    pub async fn list(&self, cursor: Option<&str>) -> Result<Option<Listing>> {
+        match cursor {
+            None => self.list_all().await.map(Some),
+            Some(token) => self.list_changes(token).await.map(Some),
+        }
+    }
+
+    async fn list_all(&self) -> Result<Listing> {
+        // Taken first, so that nothing changed during the listing is missed.
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct StartToken {
+            start_page_token: String,
+        }
+        let start: StartToken = self.get_json(&format!("{API}/changes/startPageToken"), &[]).await?;
+
+        #[derive(Deserialize)]
+        struct Root {
+            id: String,
+        }
+        let root: Root = self.get_json(&format!("{API}/files/root"), &[("fields", "id")]).await?;
+        let mut changes = vec![Change::Upsert(Entry { id: root.id, folder: true, root: true, ..Entry::default() })];
+
+        let fields = format!("nextPageToken,files({FILE_FIELDS})");
+        let mut page: Option<String> = None;
+        loop {
+            let mut query = vec![("q", "trashed = false"), ("pageSize", "1000"), ("fields", fields.as_str())];
+            if let Some(p) = &page {
+                query.push(("pageToken", p.as_str()));
+            }
+            let list: FileList = self.get_json(&format!("{API}/files"), &query).await?;
+            changes.extend(list.files.into_iter().filter_map(change));
+            match list.next_page_token {
+                Some(p) => page = Some(p),
+                None => break,
+            }
+        }
+        Ok(Listing { changes, cursor: start.start_page_token, complete: true })
+    }
+
+    async fn list_changes(&self, token: &str) -> Result<Listing> {
+        let fields = format!("nextPageToken,newStartPageToken,changes(fileId,removed,file({FILE_FIELDS}))");
+        let mut changes = Vec::new();
+        let mut page = token.to_string();
+        loop {
+            let query = [
+                ("pageToken", page.as_str()),
+                ("pageSize", "1000"),
+                ("includeRemoved", "true"),
+                ("fields", fields.as_str()),
+            ];
+            let list: ChangeList = self.get_json(&format!("{API}/changes"), &query).await?;
+            for c in list.changes {
+                match c.file {
+                    Some(file) if !c.removed => changes.extend(change(file)),
+                    _ => changes.push(Change::Removed { id: c.file_id }),
+                }
+            }
+            match (list.next_page_token, list.new_start_page_token) {
+                (Some(next), _) => page = next,
+                (None, Some(cursor)) => return Ok(Listing { changes, cursor, complete: false }),
+                (None, None) => anyhow::bail!("Drive changes list ended without a new page token"),
+            }
+        }
+    }
+
+    pub async fn download(&self, id: &str, export: Option<&Export>, dest: &Path) -> Result<()> {
+        let req = match export {
+            Some(e) => self.http.get(&format!("{API}/files/{id}/export")).query(&[("mimeType", e.mime.as_str())]),
+            None => self.http.get(&format!("{API}/files/{id}")).query(&[("alt", "media")]),
+        };
+        let resp = check(self.http.send(req).await?).await?;
+        crate::remote::save_response(resp, dest).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_become_entries() {
+        let list: FileList = serde_json::from_str(r#"{
+            "files": [
+                {"id": "a", "name": "report.pdf", "mimeType": "application/pdf", "parents": ["f"],
+                 "size": "2048", "modifiedTime": "2024-03-01T12:00:00.000Z", "md5Checksum": "abc", "version": "7"},
+                {"id": "b", "name": "Budget", "mimeType": "application/vnd.google-apps.spreadsheet",
+                 "parents": ["f"], "version": "12"},
+                {"id": "f", "name": "Work", "mimeType": "application/vnd.google-apps.folder", "parents": ["r"]},
+                {"id": "d", "name": "Sketch", "mimeType": "application/vnd.google-apps.drawing", "parents": ["f"]},
+                {"id": "t", "name": "old.txt", "mimeType": "text/plain", "trashed": true}
+            ]
+        }"#).unwrap();
+        let changes: Vec<Change> = list.files.into_iter().filter_map(change).collect();
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0], Change::Upsert(Entry {
+            id: "a".into(),
+            parent: Some("f".into()),
+            name: "report.pdf".into(),
+            size: 2048,
+            modified: 1_709_294_400,
+            version: "abc".into(),
+            ..Entry::default()
+        }));
+        let Change::Upsert(sheet) = &changes[1] else { panic!("{:?}", changes[1]) };
+        assert_eq!(sheet.export.as_ref().map(|e| e.ext.as_str()), Some("csv"));
+        assert_eq!(sheet.version, "12");
+        let Change::Upsert(folder) = &changes[2] else { panic!("{:?}", changes[2]) };
+        assert!(folder.folder && folder.export.is_none());
+        assert_eq!(changes[3], Change::Removed { id: "t".into() });
+    }
+}
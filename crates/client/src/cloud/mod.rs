@@ -0,0 +1,605 @@
+//! Cloud drive sources: Google Drive (`path = "gdrive:<folder>"`), OneDrive
+//! (`onedrive:<folder>`) and Dropbox (`dropbox:<folder>`).
+//!
+//! `find-scan` reads the drive through the provider's API with the OAuth
+//! token saved by `find-admin cloud-login` (see `cloud_auth`), downloads new
+//! and changed files to a temporary directory and runs the usual extractors
+//! on them.  Google Docs, Slides and Sheets have no file to download and are
+//! exported instead, as text and CSV.
+//!
+//! The first scan lists the whole drive; later scans ask only for what
+//! changed since the provider's change token saved in `cloud/<source>.json`
+//! in the state directory.  That file mirrors the drive's folder tree, which
+//! is how Google Drive and OneDrive items (known only by parent and name)
+//! get their paths, and records the version of every file indexed so far.
+//! A file whose version is not recorded — new, changed, moved, or failed
+//! last time — is indexed; files gone from the drive are deleted from the
+//! index as for a filesystem source.
+
+mod dropbox;
+mod gdrive;
+mod onedrive;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use find_common::{
+    api::IndexFile,
+    build_globset,
+    config::{default_state_dir, CloudProvider, ScanConfig, SourceConfig},
+    path::is_composite,
+};
+
+use crate::api::ApiClient;
+use crate::cloud_auth::CloudAuth;
+use crate::remote::{self, RemoteBatch};
+use crate::scan::ScanOptions;
+
+const DEFAULT_MAX_FILE_MB: u64 = 64;
+/// Deepest folder nesting followed when resolving paths; guards against
+/// parent cycles in a corrupt listing.
+const MAX_DEPTH: usize = 256;
+
+/// A document with no downloadable content (Google Docs and the like),
+/// exported to another format for indexing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Export {
+    /// MIME type to export as.
+    pub mime: String,
+    /// Extension added to the document's name, which picks the extractor.
+    pub ext: String,
+}
+
+/// A file or folder as reported by the provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub id: String,
+    /// Id of the containing folder, for providers that give items by parent.
+    pub parent: Option<String>,
+    pub name: String,
+    /// Full path from the drive root, for providers that report one
+    /// (Dropbox); otherwise built from `parent` and `name`.
+    pub path: Option<String>,
+    pub folder: bool,
+    /// The drive's root folder.
+    pub root: bool,
+    pub size: u64,
+    /// Unix seconds.
+    pub modified: i64,
+    /// Changes whenever the content does.
+    pub version: String,
+    pub export: Option<Export>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Upsert(Entry),
+    Removed { id: String },
+}
+
+/// Result of listing a drive.
+pub struct Listing {
+    pub changes: Vec<Change>,
+    /// Change token to list from next time.
+    pub cursor: String,
+    /// Whether `changes` is the whole drive rather than changes since the
+    /// previous cursor.
+    pub complete: bool,
+}
+
+/// An API client for one provider.
+enum Drive {
+    GoogleDrive(gdrive::GoogleDrive),
+    OneDrive(onedrive::OneDrive),
+    Dropbox(dropbox::Dropbox),
+}
+
+impl Drive {
+    fn new(provider: CloudProvider, folder: &str, http: CloudHttp) -> Self {
+        match provider {
+            CloudProvider::GoogleDrive => Drive::GoogleDrive(gdrive::GoogleDrive::new(http)),
+            CloudProvider::OneDrive => Drive::OneDrive(onedrive::OneDrive::new(http)),
+            CloudProvider::Dropbox => Drive::Dropbox(dropbox::Dropbox::new(http, folder)),
+        }
+    }
+
+    /// Everything on the drive when `cursor` is `None`, else the changes
+    /// since `cursor`.  `Ok(None)` means the provider no longer accepts
+    /// `cursor` and the drive must be listed again in full.
+    async fn list(&self, cursor: Option<&str>) -> Result<Option<Listing>> {
+        match self {
+            Drive::GoogleDrive(d) => d.list(cursor).await,
+            Drive::OneDrive(d) => d.list(cursor).await,
+            Drive::Dropbox(d) => d.list(cursor).await,
+        }
+    }
+
+    async fn download(&self, id: &str, item: &Item, dest: &Path) -> Result<()> {
+        match self {
+            Drive::GoogleDrive(d) => d.download(id, item.export.as_ref(), dest).await,
+            Drive::OneDrive(d) => d.download(id, dest).await,
+            Drive::Dropbox(d) => d.download(id, dest).await,
+        }
+    }
+}
+
+/// Authenticated requests to a provider's API.
+pub struct CloudHttp {
+    http: reqwest::Client,
+    auth: CloudAuth,
+}
+
+impl CloudHttp {
+    /// Send `req` with the access token.  The status is left to the caller.
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let req = req.bearer_auth(self.auth.token().await?).build()?;
+        let what = format!("{} {}", req.method(), req.url());
+        self.http.execute(req).await.with_context(|| what)
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        self.http.get(url)
+    }
+
+    fn post(&self, url: &str) -> reqwest::RequestBuilder {
+        self.http.post(url)
+    }
+}
+
+/// Fail on a non-success status, with the start of the error body.
+async fn check(resp: reqwest::Response) -> Result<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let url = resp.url().clone();
+    let body = resp.text().await.unwrap_or_default();
+    anyhow::bail!("{url}: {status}: {}", body.chars().take(300).collect::<String>())
+}
+
+/// Parse an RFC 3339 timestamp into Unix seconds; 0 when missing or invalid.
+fn parse_time(s: Option<&str>) -> i64 {
+    s.and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map_or(0, |t| t.timestamp())
+}
+
+/// A drive item as remembered between scans.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Item {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    name: String,
+    /// Path reported by the provider, if it reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fixed_path: Option<String>,
+    #[serde(default)]
+    folder: bool,
+    #[serde(default)]
+    root: bool,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified: i64,
+    #[serde(default)]
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    export: Option<Export>,
+    /// Path from the drive root as of the last scan; `None` while it cannot
+    /// be resolved (outside the listed part of the drive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// `version` as of the last successful indexing at `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    indexed: Option<String>,
+}
+
+impl Item {
+    fn from_entry(e: Entry) -> Self {
+        let name = match &e.export {
+            Some(export) => format!("{}.{}", e.name, export.ext),
+            None => e.name,
+        };
+        Item {
+            parent: e.parent,
+            name,
+            fixed_path: e.path.map(|p| p.trim_matches('/').to_string()),
+            folder: e.folder,
+            root: e.root,
+            size: e.size,
+            modified: e.modified,
+            version: e.version,
+            export: e.export,
+            path: None,
+            indexed: None,
+        }
+    }
+}
+
+/// The drive tree and change token saved between scans.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DriveState {
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    items: HashMap<String, Item>,
+}
+
+impl DriveState {
+    /// Load the state at `path`; missing or unreadable state starts empty,
+    /// which lists the whole drive and re-indexes every file once.
+    fn load(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+                warn!("ignoring unreadable {}: {e}", path.display());
+                DriveState::default()
+            }),
+            Err(_) => DriveState::default(),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(self)?)
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))
+    }
+
+    /// Apply a listing to the tree and re-resolve every path.  Files whose
+    /// path changed lose their `indexed` version, so they are indexed again
+    /// under the new path.
+    fn apply(&mut self, listing: Listing) {
+        if listing.complete {
+            // Keep what was indexed for files that are still there.
+            let previous = std::mem::take(&mut self.items);
+            for change in listing.changes {
+                if let Change::Upsert(e) = change {
+                    let id = e.id.clone();
+                    let mut item = Item::from_entry(e);
+                    if let Some(old) = previous.get(&id) {
+                        item.path = old.path.clone();
+                        item.indexed = old.indexed.clone();
+                    }
+                    self.items.insert(id, item);
+                }
+            }
+        } else {
+            for change in listing.changes {
+                match change {
+                    Change::Upsert(e) => {
+                        let id = e.id.clone();
+                        let mut item = Item::from_entry(e);
+                        if let Some(old) = self.items.get(&id) {
+                            item.path = old.path.clone();
+                            item.indexed = old.indexed.clone();
+                        }
+                        self.items.insert(id, item);
+                    }
+                    Change::Removed { id } => self.remove(&id),
+                }
+            }
+        }
+        self.cursor = Some(listing.cursor);
+        self.resolve_paths();
+    }
+
+    /// Remove an item and, for a folder, everything that was under it.
+    fn remove(&mut self, id: &str) {
+        let Some(old) = self.items.remove(id) else { return };
+        if let (true, Some(path)) = (old.folder, old.path) {
+            let prefix = format!("{path}/");
+            self.items.retain(|_, item| !item.path.as_deref().is_some_and(|p| p.starts_with(&prefix)));
+        }
+    }
+
+    fn resolve_paths(&mut self) {
+        let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+        let ids: Vec<String> = self.items.keys().cloned().collect();
+        for id in &ids {
+            resolve(&self.items, id, &mut resolved, 0);
+        }
+        // Items that no longer resolve (their folder is gone) are dropped.
+        self.items.retain(|id, _| resolved.get(id).is_some_and(Option::is_some));
+        for (id, item) in self.items.iter_mut() {
+            let path = resolved.remove(id).flatten();
+            if item.path != path {
+                item.indexed = None;
+                item.path = path;
+            }
+        }
+    }
+}
+
+/// Resolve the path of `id` from the drive root, memoised in `resolved`.
+fn resolve(
+    items: &HashMap<String, Item>,
+    id: &str,
+    resolved: &mut HashMap<String, Option<String>>,
+    depth: usize,
+) -> Option<String> {
+    if let Some(path) = resolved.get(id) {
+        return path.clone();
+    }
+    let item = items.get(id)?;
+    let path = if item.root {
+        Some(String::new())
+    } else if let Some(fixed) = &item.fixed_path {
+        Some(fixed.clone())
+    } else if depth >= MAX_DEPTH {
+        None
+    } else {
+        item.parent.as_deref()
+            .and_then(|parent| resolve(items, parent, resolved, depth + 1))
+            .map(|dir| if dir.is_empty() { item.name.clone() } else { format!("{dir}/{}", item.name) })
+    };
+    resolved.insert(id.to_string(), path.clone());
+    path
+}
+
+/// `path` relative to `folder`, if it lies inside it.
+fn relative<'a>(path: &'a str, folder: &str) -> Option<&'a str> {
+    if folder.is_empty() {
+        return Some(path);
+    }
+    path.strip_prefix(folder)?.strip_prefix('/')
+}
+
+/// Index the cloud drive source `source`: upload new and changed files and
+/// delete the ones that are gone.
+pub async fn scan_source(api: &ApiClient, source: &SourceConfig, scan: &ScanConfig, opts: &ScanOptions) -> Result<()> {
+    let (provider, folder) = source.cloud_location().context("source path does not name a cloud drive")?;
+    let max_bytes = source.cloud.as_ref().and_then(|c| c.max_file_mb).unwrap_or(DEFAULT_MAX_FILE_MB) * 1024 * 1024;
+    let http = CloudHttp { http: reqwest::Client::new(), auth: CloudAuth::for_source(source)? };
+    let drive = Drive::new(provider, folder, http);
+    let excludes = build_globset(&scan.exclude)?;
+    let includes = build_globset(&source.include)?;
+    let scan_start = chrono::Utc::now().timestamp();
+
+    if !opts.dry_run {
+        api.drain_spool(std::time::Duration::from_secs(600)).await?;
+    }
+
+    let state_path: PathBuf = default_state_dir().join("cloud").join(format!("{}.json", source.name));
+    let mut state = DriveState::load(&state_path);
+    let listing = match drive.list(state.cursor.as_deref()).await? {
+        Some(listing) => listing,
+        None => {
+            info!("{}: change token expired; listing the whole drive", source.name);
+            drive.list(None).await?.context("full listing returned no results")?
+        }
+    };
+    state.apply(listing);
+
+    let mut files: Vec<(String, String)> = state.items.iter()
+        .filter(|(_, item)| !item.folder)
+        .filter_map(|(id, item)| Some((relative(item.path.as_deref()?, folder)?.to_string(), id.clone())))
+        .filter(|(rel, _)| !excludes.is_match(rel) && (source.include.is_empty() || includes.is_match(rel)))
+        .collect();
+    files.sort_unstable();
+
+    let server_paths: HashSet<String> = api.list_files(&source.name).await?
+        .into_iter()
+        .map(|f| f.path)
+        .filter(|p| !is_composite(p))
+        .collect();
+    let listed: HashSet<&str> = files.iter().map(|(rel, _)| rel.as_str()).collect();
+    let mut deleted: Vec<String> = server_paths.iter().filter(|p| !listed.contains(p.as_str())).cloned().collect();
+    deleted.sort();
+
+    let changed: Vec<&(String, String)> = files.iter()
+        .filter(|(_, id)| {
+            let item = &state.items[id];
+            opts.force_since.is_some() || item.indexed.as_ref() != Some(&item.version)
+        })
+        .collect();
+    info!(
+        "{}: {} files in {}{folder}, {} new or changed, {} deleted",
+        source.name, files.len(), provider.scheme(), changed.len(), deleted.len(),
+    );
+    if opts.dry_run {
+        return Ok(());
+    }
+    // The tree is saved before indexing: files that fail keep no `indexed`
+    // version and are retried by the next scan.
+    state.save(&state_path)?;
+
+    let tmp = tempfile::TempDir::new().context("creating download directory")?;
+    let mut batch = RemoteBatch::new(api, &source.name, scan);
+    let mut indexed = 0usize;
+
+    for (rel, id) in changed {
+        api.wait_while_paused().await;
+        let item = &state.items[id];
+        match index_item(&drive, id, item, rel, scan, max_bytes, tmp.path()).await {
+            Ok(mut files) => {
+                if let Some(f) = files.first_mut() {
+                    f.is_new = !server_paths.contains(rel);
+                    f.force = opts.force_index;
+                }
+                batch.add(files, id.clone(), item.version.clone());
+                indexed += 1;
+            }
+            Err(e) => {
+                warn!("{}: failed to index {rel}: {e:#}", source.name);
+                batch.fail(rel, &e);
+            }
+        }
+        if batch.is_full() {
+            let sent = batch.submit(vec![], None).await?;
+            record_indexed(&mut state, sent);
+            state.save(&state_path)?;
+        }
+    }
+
+    let sent = batch.submit(deleted.clone(), Some(scan_start)).await?;
+    record_indexed(&mut state, sent);
+    state.save(&state_path)?;
+    info!("{}: {indexed} files indexed, {} deleted", source.name, deleted.len());
+    Ok(())
+}
+
+fn record_indexed(state: &mut DriveState, sent: Vec<(String, String)>) {
+    for (id, version) in sent {
+        if let Some(item) = state.items.get_mut(&id) {
+            item.indexed = Some(version);
+        }
+    }
+}
+
+/// Download (or export) and extract one file into the `IndexFile`s to
+/// upload.
+async fn index_item(
+    drive: &Drive,
+    id: &str,
+    item: &Item,
+    rel: &str,
+    scan: &ScanConfig,
+    max_bytes: u64,
+    dir: &Path,
+) -> Result<Vec<IndexFile>> {
+    if item.export.is_none() && item.size > max_bytes {
+        info!("{rel}: {} MB is over max_file_mb; indexing by name only", item.size / (1024 * 1024));
+        return Ok(remote::index_name_only(rel, item.modified, item.size));
+    }
+    let local = remote::download_path(dir, &item.name);
+    drive.download(id, item, &local).await?;
+    let size = match item.export {
+        Some(_) => std::fs::metadata(&local).map_or(0, |m| m.len()),
+        None => item.size,
+    };
+    remote::index_download(&local, rel, item.modified, size, scan).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(id: &str, parent: &str, name: &str) -> Change {
+        Change::Upsert(Entry {
+            id: id.into(),
+            parent: Some(parent.into()),
+            name: name.into(),
+            folder: true,
+            ..Entry::default()
+        })
+    }
+
+    fn file(id: &str, parent: &str, name: &str, version: &str) -> Change {
+        Change::Upsert(Entry {
+            id: id.into(),
+            parent: Some(parent.into()),
+            name: name.into(),
+            version: version.into(),
+            ..Entry::default()
+        })
+    }
+
+    fn listing(changes: Vec<Change>, complete: bool) -> Listing {
+        Listing { changes, cursor: "c".into(), complete }
+    }
+
+    fn path(state: &DriveState, id: &str) -> Option<String> {
+        state.items.get(id).and_then(|i| i.path.clone())
+    }
+
+    fn root() -> Change {
+        Change::Upsert(Entry { id: "root".into(), folder: true, root: true, ..Entry::default() })
+    }
+
+    #[test]
+    fn paths_resolve_through_parents_in_any_order() {
+        let mut state = DriveState::default();
+        state.apply(listing(vec![
+            file("f1", "d2", "notes.txt", "v1"),
+            folder("d2", "d1", "Reports"),
+            folder("d1", "root", "Work"),
+            root(),
+            // Shared with the user but not in their drive.
+            file("f2", "elsewhere", "shared.txt", "v1"),
+        ], true));
+        assert_eq!(path(&state, "f1").as_deref(), Some("Work/Reports/notes.txt"));
+        assert_eq!(path(&state, "root").as_deref(), Some(""));
+        assert!(!state.items.contains_key("f2"));
+    }
+
+    #[test]
+    fn folder_move_reindexes_files_under_it() {
+        let mut state = DriveState::default();
+        state.apply(listing(vec![
+            root(),
+            folder("d1", "root", "Work"),
+            folder("d2", "root", "Archive"),
+            file("f1", "d1", "a.txt", "v1"),
+        ], true));
+        record_indexed(&mut state, vec![("f1".into(), "v1".into())]);
+
+        state.apply(listing(vec![folder("d1", "d2", "Work")], false));
+        assert_eq!(path(&state, "f1").as_deref(), Some("Archive/Work/a.txt"));
+        assert_eq!(state.items["f1"].indexed, None);
+    }
+
+    #[test]
+    fn removed_folder_takes_its_files() {
+        let mut state = DriveState::default();
+        state.apply(listing(vec![
+            root(),
+            folder("d1", "root", "Work"),
+            file("f1", "d1", "a.txt", "v1"),
+            file("f2", "root", "b.txt", "v1"),
+        ], true));
+        state.apply(listing(vec![Change::Removed { id: "d1".into() }], false));
+        assert!(!state.items.contains_key("f1"));
+        assert!(state.items.contains_key("f2"));
+    }
+
+    #[test]
+    fn unchanged_files_stay_indexed_across_full_listing() {
+        let entries = || vec![root(), file("f1", "root", "a.txt", "v1"), file("f2", "root", "b.txt", "v1")];
+        let mut state = DriveState::default();
+        state.apply(listing(entries(), true));
+        record_indexed(&mut state, vec![("f1".into(), "v1".into()), ("f2".into(), "v1".into())]);
+
+        let mut again = entries();
+        again[2] = file("f2", "root", "b.txt", "v2");
+        state.apply(listing(again, true));
+        assert_eq!(state.items["f1"].indexed.as_deref(), Some("v1"));
+        assert_ne!(state.items["f2"].indexed.as_ref(), Some(&state.items["f2"].version));
+    }
+
+    #[test]
+    fn fixed_paths_and_exports() {
+        let mut state = DriveState::default();
+        state.apply(listing(vec![
+            Change::Upsert(Entry {
+                id: "/work/a.pdf".into(),
+                name: "a.pdf".into(),
+                path: Some("/Work/a.pdf".into()),
+                ..Entry::default()
+            }),
+            root(),
+            Change::Upsert(Entry {
+                id: "doc".into(),
+                parent: Some("root".into()),
+                name: "Plan".into(),
+                export: Some(Export { mime: "text/plain".into(), ext: "txt".into() }),
+                ..Entry::default()
+            }),
+        ], true));
+        assert_eq!(path(&state, "/work/a.pdf").as_deref(), Some("Work/a.pdf"));
+        assert_eq!(path(&state, "doc").as_deref(), Some("Plan.txt"));
+    }
+
+    #[test]
+    fn relative_to_folder() {
+        assert_eq!(relative("Work/Reports/a.txt", "Work"), Some("Reports/a.txt"));
+        assert_eq!(relative("Workshop/a.txt", "Work"), None);
+        assert_eq!(relative("a.txt", ""), Some("a.txt"));
+    }
+}
@@ -0,0 +1,150 @@
+//! OneDrive (Microsoft Graph).  Both the first listing and later ones use
+//! the drive's delta query; the delta link it ends with is the cursor.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{check, parse_time, Change, CloudHttp, Entry, Listing};
+
+const API: &str = "https://graph.microsoft.com/v1.0/me/drive";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveItem {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    parent_reference: Option<ParentReference>,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    last_modified_date_time: Option<String>,
+    /// Changes with the content only, unlike `eTag`.
+    #[serde(default)]
+    c_tag: Option<String>,
+    #[serde(default)]
+    e_tag: Option<String>,
+    // Facets: present (as objects) or absent.
+    #[serde(default)]
+    file: Option<serde_json::Value>,
+    #[serde(default)]
+    folder: Option<serde_json::Value>,
+    #[serde(default)]
+    root: Option<serde_json::Value>,
+    #[serde(default)]
+    deleted: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParentReference {
+    id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeltaPage {
+    #[serde(default)]
+    value: Vec<DriveItem>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
+/// The change an item amounts to; `None` for items that are neither files
+/// nor folders (OneNote notebooks and other packages).
+fn change(item: DriveItem) -> Option<Change> {
+    if item.deleted.is_some() {
+        return Some(Change::Removed { id: item.id });
+    }
+    let root = item.root.is_some();
+    let folder = root || item.folder.is_some();
+    if !folder && item.file.is_none() {
+        return None;
+    }
+    Some(Change::Upsert(Entry {
+        id: item.id,
+        parent: item.parent_reference.and_then(|p| p.id),
+        name: item.name,
+        path: None,
+        folder,
+        root,
+        size: item.size,
+        modified: parse_time(item.last_modified_date_time.as_deref()),
+        version: item.c_tag.or(item.e_tag).unwrap_or_default(),
+        export: None,
+    }))
+}
+
+pub struct OneDrive {
+    http: CloudHttp,
+}
+
+impl OneDrive {
+    pub fn new(http: CloudHttp) -> Self {
+        OneDrive { http }
+    }
+
+    pub async fn list(&self, cursor: Option<&str>) -> Result<Option<Listing>> {
+        let mut url = cursor.map_or_else(|| format!("{API}/root/delta"), str::to_string);
+        let mut changes = Vec::new();
+        loop {
+            let resp = self.http.send(self.http.get(&url)).await?;
+            // The delta link has expired: start over from a full listing.
+            if cursor.is_some() && resp.status() == reqwest::StatusCode::GONE {
+                return Ok(None);
+            }
+            let page: DeltaPage = check(resp).await?.json().await.context("parsing OneDrive delta page")?;
+            changes.extend(page.value.into_iter().filter_map(change));
+            match (page.next_link, page.delta_link) {
+                (Some(next), _) => url = next,
+                (None, Some(cursor_link)) => {
+                    return Ok(Some(Listing { changes, cursor: cursor_link, complete: cursor.is_none() }));
+                }
+                (None, None) => anyhow::bail!("OneDrive delta query ended without a delta link"),
+            }
+        }
+    }
+
+    pub async fn download(&self, id: &str, dest: &Path) -> Result<()> {
+        // Redirects to a pre-authenticated download URL.
+        let resp = check(self.http.send(self.http.get(&format!("{API}/items/{id}/content"))).await?).await?;
+        crate::remote::save_response(resp, dest).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_page_becomes_changes() {
+        let page: DeltaPage = serde_json::from_str(r#"{
+            "@odata.deltaLink": "https://graph.microsoft.com/v1.0/me/drive/root/delta?token=abc",
+            "value": [
+                {"id": "R", "name": "root", "root": {}, "folder": {"childCount": 1}},
+                {"id": "A", "name": "notes.docx", "parentReference": {"id": "R"}, "size": 512,
+                 "lastModifiedDateTime": "2024-03-01T12:00:00Z", "cTag": "c1", "eTag": "e1", "file": {}},
+                {"id": "N", "name": "Notebook", "parentReference": {"id": "R"}, "package": {"type": "oneNote"}},
+                {"id": "G", "deleted": {"state": "deleted"}}
+            ]
+        }"#).unwrap();
+        assert_eq!(page.delta_link.as_deref(), Some("https://graph.microsoft.com/v1.0/me/drive/root/delta?token=abc"));
+        let changes: Vec<Change> = page.value.into_iter().filter_map(change).collect();
+        assert_eq!(changes.len(), 3);
+        let Change::Upsert(root) = &changes[0] else { panic!("{:?}", changes[0]) };
+        assert!(root.root && root.folder);
+        assert_eq!(changes[1], Change::Upsert(Entry {
+            id: "A".into(),
+            parent: Some("R".into()),
+            name: "notes.docx".into(),
+            size: 512,
+            modified: 1_709_294_400,
+            version: "c1".into(),
+            ..Entry::default()
+        }));
+        assert_eq!(changes[2], Change::Removed { id: "G".into() });
+    }
+}
@@ -0,0 +1,272 @@
+#![allow(dead_code)] // login is used by find-admin, access tokens by find-scan
+
+//! OAuth sign-in for cloud drive sources.
+//!
+//! `find-admin cloud-login <source>` runs the authorization-code flow with
+//! PKCE against the app configured in `[sources.cloud]`: it prints the
+//! provider's sign-in URL, receives the redirect on
+//! `http://localhost:<redirect_port>/`, and saves the refresh token to
+//! `cloud-tokens.json` in the state directory.  `find-scan` then exchanges
+//! that token for short-lived access tokens as it needs them.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use find_common::config::{default_state_dir, CloudProvider, CloudSourceConfig, SourceConfig};
+
+const DEFAULT_REDIRECT_PORT: u16 = 53682;
+/// Access tokens are refreshed this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// A provider's OAuth endpoints and the read-only scope requested.
+struct Endpoints {
+    auth_url: &'static str,
+    token_url: &'static str,
+    scope: &'static str,
+    /// Extra sign-in parameters needed to be issued a refresh token.
+    extra: &'static [(&'static str, &'static str)],
+}
+
+fn endpoints(provider: CloudProvider) -> Endpoints {
+    match provider {
+        CloudProvider::GoogleDrive => Endpoints {
+            auth_url: "https://accounts.google.com/o/oauth2/v2/auth",
+            token_url: "https://oauth2.googleapis.com/token",
+            scope: "https://www.googleapis.com/auth/drive.readonly",
+            extra: &[("access_type", "offline"), ("prompt", "consent")],
+        },
+        CloudProvider::OneDrive => Endpoints {
+            auth_url: "https://login.microsoftonline.com/common/oauth2/v2.0/authorize",
+            token_url: "https://login.microsoftonline.com/common/oauth2/v2.0/token",
+            scope: "Files.Read.All offline_access",
+            extra: &[],
+        },
+        CloudProvider::Dropbox => Endpoints {
+            auth_url: "https://www.dropbox.com/oauth2/authorize",
+            token_url: "https://api.dropboxapi.com/oauth2/token",
+            scope: "files.metadata.read files.content.read",
+            extra: &[("token_access_type", "offline")],
+        },
+    }
+}
+
+/// The provider and `[sources.cloud]` settings of a cloud source.
+fn cloud_settings(source: &SourceConfig) -> Result<(CloudProvider, &CloudSourceConfig)> {
+    let (provider, _) = source.cloud_location()
+        .with_context(|| format!("source {:?} is not a cloud drive", source.name))?;
+    let settings = source.cloud.as_ref()
+        .with_context(|| format!("source {:?} needs a [sources.cloud] section with client_id", source.name))?;
+    Ok((provider, settings))
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// Refresh tokens saved by `cloud-login`, keyed by source name.
+fn token_store_path() -> PathBuf {
+    default_state_dir().join("cloud-tokens.json")
+}
+
+fn load_refresh_tokens() -> HashMap<String, String> {
+    std::fs::read(token_store_path())
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_refresh_token(source: &str, token: &str) -> Result<PathBuf> {
+    let path = token_store_path();
+    let mut tokens = load_refresh_tokens();
+    tokens.insert(source.to_string(), token.to_string());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&tokens)?).with_context(|| format!("writing {}", tmp.display()))?;
+    // The tokens grant read access to the whole drive.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, &path).with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))?;
+    Ok(path)
+}
+
+/// Sign in to the drive of `source` interactively and save the refresh
+/// token.  Returns where it was saved.
+pub async fn login(source: &SourceConfig) -> Result<PathBuf> {
+    let (provider, settings) = cloud_settings(source)?;
+    let ep = endpoints(provider);
+    let port = settings.redirect_port.unwrap_or(DEFAULT_REDIRECT_PORT);
+    let redirect_uri = format!("http://localhost:{port}/");
+    let verifier = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = uuid::Uuid::new_v4().simple().to_string();
+
+    let mut params = vec![
+        ("client_id", settings.client_id.as_str()),
+        ("response_type", "code"),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("scope", ep.scope),
+        ("state", state.as_str()),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ];
+    params.extend_from_slice(ep.extra);
+    let url = reqwest::Url::parse_with_params(ep.auth_url, &params)?;
+
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await
+        .with_context(|| format!("listening on port {port} for the sign-in redirect"))?;
+    println!("Open this URL in a browser on this machine and sign in:\n\n  {url}\n");
+    println!("Waiting for the redirect to {redirect_uri} ...");
+    let code = receive_code(&listener, &state).await?;
+
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("client_id", settings.client_id.as_str()),
+        ("code_verifier", verifier.as_str()),
+    ];
+    if let Some(secret) = &settings.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+    let token = request_token(ep.token_url, &form).await?;
+    let refresh = token.refresh_token.context("the provider issued no refresh token")?;
+    save_refresh_token(&source.name, &refresh)
+}
+
+/// Accept redirects on `listener` until one carries the authorization
+/// code for `state`.
+async fn receive_code(listener: &tokio::net::TcpListener, state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = vec![0u8; 8192];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let target = request.split_whitespace().nth(1).unwrap_or("/");
+        let url = reqwest::Url::parse(&format!("http://localhost{target}"))?;
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+        let (status, message) = match (query.get("code"), query.get("error")) {
+            (_, Some(err)) => ("400 Bad Request", format!("Sign-in failed: {err}")),
+            (Some(_), None) if query.get("state").map(String::as_str) != Some(state) => {
+                ("400 Bad Request", "Sign-in failed: unexpected state".to_string())
+            }
+            (Some(_), None) => ("200 OK", "Signed in to find-anything. You can close this window.".to_string()),
+            // Favicon requests and the like.
+            (None, None) => ("404 Not Found", String::new()),
+        };
+        let body = format!("<html><body><p>{message}</p></body></html>");
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len(),
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+
+        if let Some(err) = query.get("error") {
+            let detail = query.get("error_description").map_or("", String::as_str);
+            anyhow::bail!("sign-in failed: {err} {detail}");
+        }
+        if status.starts_with("200") {
+            return Ok(query["code"].clone());
+        }
+    }
+}
+
+async fn request_token(token_url: &str, form: &[(&str, &str)]) -> Result<TokenResponse> {
+    let resp = reqwest::Client::new()
+        .post(token_url)
+        .form(form)
+        .send()
+        .await
+        .with_context(|| format!("POST {token_url}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("POST {token_url}: {status}: {}", body.chars().take(300).collect::<String>());
+    }
+    resp.json().await.context("parsing token response")
+}
+
+/// Access tokens for one cloud source, refreshed as they expire.
+pub struct CloudAuth {
+    source: String,
+    token_url: &'static str,
+    client_id: String,
+    client_secret: Option<String>,
+    /// Whether the refresh token came from the config file (and so is not
+    /// rewritten when the provider rotates it).
+    from_config: bool,
+    refresh_token: Mutex<String>,
+    access: Mutex<Option<(String, Instant)>>,
+}
+
+impl CloudAuth {
+    pub fn for_source(source: &SourceConfig) -> Result<Self> {
+        let (provider, settings) = cloud_settings(source)?;
+        let (refresh_token, from_config) = match &settings.refresh_token {
+            Some(t) => (t.clone(), true),
+            None => (
+                load_refresh_tokens().remove(&source.name).with_context(|| {
+                    format!("source {:?} is not signed in; run `find-admin cloud-login {}`", source.name, source.name)
+                })?,
+                false,
+            ),
+        };
+        Ok(CloudAuth {
+            source: source.name.clone(),
+            token_url: endpoints(provider).token_url,
+            client_id: settings.client_id.clone(),
+            client_secret: settings.client_secret.clone(),
+            from_config,
+            refresh_token: Mutex::new(refresh_token),
+            access: Mutex::new(None),
+        })
+    }
+
+    /// A valid access token, refreshed first if it is about to expire.
+    pub async fn token(&self) -> Result<String> {
+        let mut access = self.access.lock().await;
+        if let Some((token, expires)) = access.as_ref() {
+            if Instant::now() + EXPIRY_MARGIN < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let mut refresh = self.refresh_token.lock().await;
+        let mut form = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh.as_str()),
+            ("client_id", self.client_id.as_str()),
+        ];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let resp = request_token(self.token_url, &form).await
+            .with_context(|| format!("refreshing the access token for {:?}", self.source))?;
+        if let Some(rotated) = resp.refresh_token.filter(|t| *t != *refresh) {
+            if !self.from_config {
+                save_refresh_token(&self.source, &rotated)?;
+            }
+            *refresh = rotated;
+        }
+        let expires = Instant::now() + Duration::from_secs(resp.expires_in.unwrap_or(3600));
+        *access = Some((resp.access_token.clone(), expires));
+        Ok(resp.access_token)
+    }
+}
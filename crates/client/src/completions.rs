@@ -0,0 +1,99 @@
+//! `<tool> completions <shell> [--config PATH]`: print a shell completion
+//! script.  Hidden from `--help`.  The names of the sources in the client
+//! config are completed wherever a source is expected, so loading the script
+//! at shell startup keeps them current.
+
+use clap::builder::PossibleValuesParser;
+use clap::Command;
+use clap_complete::Shell;
+
+use find_common::config::{default_config_path, parse_client_config};
+
+/// Print the completion script and exit when the command line asks for
+/// one; otherwise do nothing.  Call before parsing `cmd`'s arguments.
+pub fn handle(cmd: Command) {
+    let args: Vec<String> = std::env::args().collect();
+    let Some((shell, config)) = parse_request(&args) else {
+        return;
+    };
+    let bin = std::path::Path::new(&args[0])
+        .file_stem()
+        .map_or_else(|| cmd.get_name().to_string(), |s| s.to_string_lossy().into_owned());
+    let mut cmd = with_source_names(cmd, &source_names(config));
+    clap_complete::generate(shell, &mut cmd, bin, &mut std::io::stdout());
+    std::process::exit(0);
+}
+
+/// `(shell, config path)` when `args` is `<bin> completions <shell>`,
+/// optionally followed by `--config <path>`.
+fn parse_request(args: &[String]) -> Option<(Shell, Option<&str>)> {
+    match args {
+        [_, cmd, shell] if cmd == "completions" => Some((shell.parse().ok()?, None)),
+        [_, cmd, shell, flag, path] if cmd == "completions" && flag == "--config" => {
+            Some((shell.parse().ok()?, Some(path.as_str())))
+        }
+        _ => None,
+    }
+}
+
+/// Source names from the client config; none if it can't be read.
+fn source_names(config: Option<&str>) -> Vec<String> {
+    let path = config.map_or_else(default_config_path, str::to_string);
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| parse_client_config(&s).ok())
+        .map(|(c, _)| c.sources.into_iter().map(|s| s.name).collect())
+        .unwrap_or_default()
+}
+
+/// Offer `names` as the values of every `source`/`sources` argument in
+/// `cmd` and its subcommands.
+fn with_source_names(mut cmd: Command, names: &[String]) -> Command {
+    if names.is_empty() {
+        return cmd;
+    }
+    for id in ["source", "sources"] {
+        if cmd.get_arguments().any(|a| a.get_id() == id) {
+            cmd = cmd.mut_arg(id, |a| a.value_parser(PossibleValuesParser::new(names.to_vec())));
+        }
+    }
+    let subcommands: Vec<String> = cmd.get_subcommands().map(|s| s.get_name().to_string()).collect();
+    for name in subcommands {
+        cmd = cmd.mut_subcommand(name, |s| with_source_names(s, names));
+    }
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn only_completions_requests_are_handled() {
+        assert!(matches!(parse_request(&args("find completions bash")), Some((Shell::Bash, None))));
+        assert!(matches!(
+            parse_request(&args("find completions zsh --config /tmp/c.toml")),
+            Some((Shell::Zsh, Some("/tmp/c.toml")))
+        ));
+        // A search for the word, or an unknown shell, is left to the tool.
+        assert!(parse_request(&args("find completions")).is_none());
+        assert!(parse_request(&args("find completions cmd")).is_none());
+    }
+
+    #[test]
+    fn source_args_get_configured_names() {
+        let cmd = Command::new("find-admin")
+            .subcommand(Command::new("delete-source").arg(Arg::new("source")))
+            .subcommand(Command::new("status"));
+        let cmd = with_source_names(cmd, &["code".to_string(), "docs".to_string()]);
+        let sub = cmd.find_subcommand("delete-source").unwrap();
+        let arg = sub.get_arguments().find(|a| a.get_id() == "source").unwrap();
+        let values: Vec<String> = arg.get_possible_values().iter().map(|v| v.get_name().to_string()).collect();
+        assert_eq!(values, ["code", "docs"]);
+    }
+}
@@ -0,0 +1,283 @@
+//! The watcher's end of the control socket (`find_common::control`).
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use find_common::config::ClientConfig;
+use find_common::control::{ControlRequest, ControlResponse};
+use find_common::watch_status::WatchStatus;
+
+use crate::api::ApiClient;
+use crate::pause::PauseReason;
+use crate::watch::WatchActivity;
+
+/// Longest request line accepted.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// What the control socket acts on, shared with the watch loop.
+pub(crate) struct Control {
+    config_path: String,
+    reload: Arc<Notify>,
+    activity: Arc<WatchActivity>,
+    state: Mutex<ControlState>,
+}
+
+#[derive(Default)]
+struct ControlState {
+    pause_path: PathBuf,
+    log_dir: String,
+    sources: Vec<String>,
+    /// The running session's status and API client.
+    session: Option<(WatchStatus, Arc<ApiClient>)>,
+    /// The last scan started over the socket.
+    scan: Option<tokio::process::Child>,
+}
+
+impl Control {
+    pub(crate) fn new(config: &ClientConfig, config_path: &str, reload: Arc<Notify>, activity: Arc<WatchActivity>) -> Self {
+        let control = Self {
+            config_path: config_path.to_string(),
+            reload,
+            activity,
+            state: Mutex::default(),
+        };
+        control.configure(config);
+        control
+    }
+
+    /// Pick up a (re)loaded configuration.
+    pub(crate) fn configure(&self, config: &ClientConfig) {
+        let mut state = self.lock();
+        state.pause_path = find_common::pause::pause_path(&config.scan);
+        state.log_dir = config.log.dir.clone();
+        state.sources = config.sources.iter().map(|s| s.name.clone()).collect();
+    }
+
+    /// A watch session started; status requests report on it from now on.
+    pub(crate) fn set_session(&self, status: WatchStatus, api: Arc<ApiClient>) {
+        self.lock().session = Some((status, api));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ControlState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn handle(&self, request: ControlRequest) -> ControlResponse {
+        let mut state = self.lock();
+        match request {
+            ControlRequest::Status => {
+                let Some((status, api)) = &state.session else {
+                    return ControlResponse::failed("the watcher is starting");
+                };
+                let mut status = status.clone();
+                self.activity.fill(&mut status, api);
+                status.updated_at = chrono::Utc::now().timestamp();
+                ControlResponse { ok: true, status: Some(status), ..Default::default() }
+            }
+            ControlRequest::Pause { until } => match find_common::pause::write_pause(&state.pause_path, until) {
+                Ok(()) => ControlResponse::done(format!("Indexing {}.", PauseReason::OnDemand { until })),
+                Err(e) => ControlResponse::failed(format!("{e:#}")),
+            },
+            ControlRequest::Resume => match find_common::pause::clear_pause(&state.pause_path) {
+                Ok(true) => ControlResponse::done("Indexing resumed."),
+                Ok(false) => ControlResponse::done("Indexing was not paused."),
+                Err(e) => ControlResponse::failed(format!("{e:#}")),
+            },
+            ControlRequest::Scan { source, force } => {
+                if let Some(source) = &source {
+                    if !state.sources.contains(source) {
+                        return ControlResponse::failed(format!("no source named {source:?}"));
+                    }
+                }
+                if let Some(child) = state.scan.as_mut() {
+                    if matches!(child.try_wait(), Ok(None)) {
+                        let pid = child.id().map_or(String::new(), |p| format!(" (pid {p})"));
+                        return ControlResponse::failed(format!("the last requested scan is still running{pid}"));
+                    }
+                }
+                let mut args = Vec::new();
+                if let Some(source) = &source {
+                    args.extend(["--source", source.as_str()]);
+                }
+                if force {
+                    args.push("--force");
+                }
+                let Some(child) = crate::watch::spawn_scan(&self.config_path, &state.log_dir, &args) else {
+                    return ControlResponse::failed("failed to start find-scan; see the watcher's log");
+                };
+                let pid = child.id().map_or(String::new(), |p| format!(" (pid {p})"));
+                state.scan = Some(child);
+                ControlResponse::done(format!("find-scan started{pid}."))
+            }
+            ControlRequest::Reload => {
+                self.reload.notify_one();
+                ControlResponse::done("Reloading the configuration.")
+            }
+        }
+    }
+}
+
+/// Answer one request on `stream`.
+async fn serve_client<S: AsyncRead + AsyncWrite + Unpin>(stream: S, control: Arc<Control>) {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut line = String::new();
+    let mut read = BufReader::new(read).take(MAX_REQUEST_BYTES);
+    let response = match tokio::time::timeout(find_common::control::TIMEOUT, read.read_line(&mut line)).await {
+        Ok(Ok(_)) => match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => {
+                tracing::debug!("control request: {request:?}");
+                control.handle(request)
+            }
+            Err(e) => ControlResponse::failed(format!("invalid request: {e}")),
+        },
+        Ok(Err(e)) => {
+            tracing::debug!("control socket: reading request: {e}");
+            return;
+        }
+        Err(_) => return,
+    };
+    let Ok(mut reply) = serde_json::to_string(&response) else { return };
+    reply.push('\n');
+    let _ = write.write_all(reply.as_bytes()).await;
+    let _ = write.shutdown().await;
+}
+
+/// Listen on the Unix socket at `addr` until the process exits.
+#[cfg(unix)]
+pub(crate) async fn serve(addr: String, control: Arc<Control>) {
+    use std::os::unix::fs::PermissionsExt;
+
+    // A socket left behind by a watcher that was killed is in the way, but
+    // one another watcher still answers on is not ours to take.
+    if std::os::unix::net::UnixStream::connect(&addr).is_ok() {
+        warn!("control socket {addr} is in use by another find-watch; not listening");
+        return;
+    }
+    let _ = std::fs::remove_file(&addr);
+    if let Some(parent) = std::path::Path::new(&addr).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let listener = match tokio::net::UnixListener::bind(&addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("control socket {addr}: {e}");
+            return;
+        }
+    };
+    // Only the watcher's user and group may send it requests.
+    if let Err(e) = std::fs::set_permissions(&addr, std::fs::Permissions::from_mode(0o660)) {
+        warn!("control socket {addr}: setting permissions: {e}");
+    }
+    info!("control socket listening on {addr}");
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve_client(stream, control.clone()));
+            }
+            Err(e) => {
+                warn!("control socket: {e}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// Listen on the named pipe `addr` until the process exits.
+#[cfg(windows)]
+pub(crate) async fn serve(addr: String, control: Arc<Control>) {
+    let security = match PipeSecurity::new() {
+        Ok(security) => security,
+        Err(e) => {
+            warn!("control pipe {addr}: {e}");
+            return;
+        }
+    };
+    let mut server = match security.create(&addr, true) {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("control pipe {addr}: {e} (is another find-watch running?)");
+            return;
+        }
+    };
+    info!("control pipe listening on {addr}");
+    loop {
+        let connected = server.connect().await;
+        // The next instance is created before this one is handed off, so
+        // there is always one waiting for a client.
+        let next = match security.create(&addr, false) {
+            Ok(next) => next,
+            Err(e) => {
+                warn!("control pipe {addr}: {e}");
+                return;
+            }
+        };
+        let client = std::mem::replace(&mut server, next);
+        match connected {
+            Ok(()) => {
+                tokio::spawn(serve_client(client, control.clone()));
+            }
+            Err(e) => {
+                warn!("control pipe: {e}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// A security descriptor letting signed-in users talk to a watcher that
+/// runs as a service account.  The default one only lets them read.
+#[cfg(windows)]
+struct PipeSecurity(windows_sys::Win32::Security::PSECURITY_DESCRIPTOR);
+
+#[cfg(windows)]
+impl PipeSecurity {
+    /// Full access for SYSTEM and administrators, read/write for
+    /// authenticated users.
+    const SDDL: &'static str = "D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)";
+
+    fn new() -> std::io::Result<Self> {
+        use windows_sys::Win32::Security::Authorization::{
+            ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+        };
+        let sddl: Vec<u16> = Self::SDDL.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut descriptor = std::ptr::null_mut();
+        let ok = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self(descriptor))
+    }
+
+    fn create(&self, addr: &str, first: bool) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeServer> {
+        use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+        let mut attrs = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: self.0,
+            bInheritHandle: 0,
+        };
+        unsafe {
+            tokio::net::windows::named_pipe::ServerOptions::new()
+                .first_pipe_instance(first)
+                .reject_remote_clients(true)
+                .create_with_security_attributes_raw(addr, &mut attrs as *mut _ as *mut std::ffi::c_void)
+        }
+    }
+}
+
+// The descriptor is only read after it is built, and lives for the process.
+#[cfg(windows)]
+unsafe impl Send for PipeSecurity {}
+#[cfg(windows)]
+unsafe impl Sync for PipeSecurity {}
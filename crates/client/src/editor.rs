@@ -0,0 +1,101 @@
+//! Opening search results in a local editor, or in the browser through a
+//! `[cli.path_map]` URL template (`find-anything --open`).
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// The editor command line for `path` at `line`.  `template` is split on
+/// whitespace before `{path}` and `{line}` are replaced, so paths with spaces
+/// stay one argument.  With no template, `$VISUAL` or `$EDITOR` is run with
+/// `+<line> <path>`, which vi, emacs, nano and most terminal editors accept.
+pub fn command_line(template: Option<&str>, path: &Path, line: usize) -> Vec<String> {
+    let template = match template {
+        Some(t) => t.to_string(),
+        None => match std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")) {
+            Ok(editor) if !editor.trim().is_empty() => format!("{editor} +{{line}} {{path}}"),
+            _ if cfg!(windows) => "notepad {path}".to_string(),
+            _ => "vi +{line} {path}".to_string(),
+        },
+    };
+    let path = path.to_string_lossy();
+    let line = line.max(1).to_string();
+    template
+        .split_whitespace()
+        .map(|arg| arg.replace("{path}", &path).replace("{line}", &line))
+        .collect()
+}
+
+/// Run the editor on `path` and wait for it to exit.
+pub fn open(template: Option<&str>, path: &Path, line: usize) -> Result<()> {
+    let argv = command_line(template, path, line);
+    let (program, args) = argv.split_first().context("editor command is empty")?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("running {program}"))?;
+    if !status.success() {
+        anyhow::bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+/// Open `url` in the default browser without waiting for it.
+pub fn open_url(url: &str) -> Result<()> {
+    let mut cmd = if cfg!(windows) {
+        // `start` treats its first quoted argument as a window title.
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(url).spawn().with_context(|| format!("opening {url}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use find_common::config::PathMapEntry;
+    use find_common::path_map::PathMapper;
+
+    fn local_path(config: &find_common::config::ClientConfig, source: &str, rel_path: &str) -> Option<PathBuf> {
+        PathMapper::new(config).local_path(source, rel_path)
+    }
+
+    #[test]
+    fn template_placeholders_are_replaced_per_argument() {
+        let argv = command_line(Some("code -g {path}:{line}"), Path::new("/home/me/My Docs/a.rs"), 42);
+        assert_eq!(argv, ["code", "-g", "/home/me/My Docs/a.rs:42"]);
+    }
+
+    #[test]
+    fn path_map_overrides_source_path() {
+        let (mut config, _) = find_common::config::parse_client_config(r#"
+            [server]
+            url = "http://localhost:8765"
+            token = "t"
+
+            [[sources]]
+            name = "code"
+            path = "/srv/code"
+
+            [[sources]]
+            name = "docs"
+            path = "/srv/docs"
+
+            [cli.path_map]
+            docs = "/mnt/nas/docs"
+        "#).unwrap();
+        assert_eq!(local_path(&config, "code", "src/main.rs"), Some(PathBuf::from("/srv/code/src/main.rs")));
+        assert_eq!(local_path(&config, "docs", "a/b.md"), Some(PathBuf::from("/mnt/nas/docs/a/b.md")));
+        assert_eq!(local_path(&config, "photos", "x.jpg"), None);
+        config.cli.path_map.insert("photos".into(), PathMapEntry::Root("/pics".into()));
+        assert_eq!(local_path(&config, "photos", "x.jpg"), Some(PathBuf::from("/pics/x.jpg")));
+    }
+}
@@ -0,0 +1,204 @@
+//! Local cache of extracted content, keyed by content hash.
+//!
+//! `find-scan` stores the lines each builtin extractor produced for a file
+//! under a key derived from the file's content hash and everything else the
+//! output depends on (extractor, extension, content limit, tool version).  A
+//! file seen again with the same content (a duplicate, or an unchanged file
+//! that was renamed or moved) is indexed from the cache instead of being
+//! parsed again.
+//!
+//! Each entry is one gzip-compressed JSON file.  Reading an entry refreshes
+//! its modification time, and once the cache outgrows `[scan]
+//! extract_cache_mb` the least recently used entries are removed.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tracing::warn;
+
+use find_common::api::IndexLine;
+use find_common::config::ScanConfig;
+
+const EXT: &str = "json.gz";
+
+pub(crate) struct ExtractCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Total size of the entries, kept up to date by `put` and `evict`.
+    used: Mutex<u64>,
+}
+
+impl ExtractCache {
+    /// Open (creating if needed) the cache in `dir`.
+    pub fn open(dir: PathBuf, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("creating extraction cache directory {}", dir.display()))?;
+        let used = entries(&dir).iter().map(|(_, len, _)| len).sum();
+        Ok(ExtractCache { dir, max_bytes, used: Mutex::new(used) })
+    }
+
+    /// The cache configured in `scan`, stored in `dir`, or `None` when
+    /// `[scan] extract_cache_mb = 0`.
+    pub fn from_scan(scan: &ScanConfig, dir: PathBuf) -> Result<Option<Self>> {
+        if scan.extract_cache_mb == 0 {
+            return Ok(None);
+        }
+        Self::open(dir, scan.extract_cache_mb * 1024 * 1024).map(Some)
+    }
+
+    /// Cache key for content hashed as `file_hash`, extracted in `context`:
+    /// whatever besides the content determines the extractor's output.
+    pub fn key(file_hash: &str, context: &str) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(file_hash.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(context.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{EXT}"))
+    }
+
+    /// The lines stored under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<Vec<IndexLine>> {
+        let path = self.path(key);
+        let data = std::fs::read(&path).ok()?;
+        let mut json = Vec::new();
+        let lines = GzDecoder::new(data.as_slice())
+            .read_to_end(&mut json)
+            .ok()
+            .and_then(|_| serde_json::from_slice(&json).ok());
+        if lines.is_none() {
+            warn!("discarding unreadable extraction cache entry {}", path.display());
+            self.remove(&path, data.len() as u64);
+            return None;
+        }
+        // Mark the entry as recently used.
+        if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        lines
+    }
+
+    /// Store `lines` under `key`, evicting the least recently used entries
+    /// if the cache would outgrow its limit.  Failures are logged; the cache
+    /// never fails a scan.
+    pub fn put(&self, key: &str, lines: &[IndexLine]) {
+        if let Err(e) = self.try_put(key, lines) {
+            warn!("failed to write extraction cache entry: {e:#}");
+        }
+    }
+
+    fn try_put(&self, key: &str, lines: &[IndexLine]) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        serde_json::to_writer(&mut encoder, lines).context("serialising extracted lines")?;
+        let data = encoder.finish().context("compressing extracted lines")?;
+        // Entries bigger than a tenth of the cache would evict too much.
+        if data.len() as u64 > self.max_bytes / 10 {
+            return Ok(());
+        }
+
+        let path = self.path(key);
+        let replaced = path.metadata().map(|m| m.len()).unwrap_or(0);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, &data).with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))?;
+
+        let over = {
+            let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+            *used = (*used + data.len() as u64).saturating_sub(replaced);
+            *used > self.max_bytes
+        };
+        if over {
+            self.evict();
+        }
+        Ok(())
+    }
+
+    /// Remove the least recently used entries until the cache is back to
+    /// 90% of its limit, leaving room for the next few entries.
+    fn evict(&self) {
+        let mut entries = entries(&self.dir);
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        let target = self.max_bytes / 10 * 9;
+        let mut used: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (path, len, _) in entries {
+            if used <= target {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                used -= len;
+            }
+        }
+        *self.used.lock().unwrap_or_else(|e| e.into_inner()) = used;
+    }
+
+    fn remove(&self, path: &Path, len: u64) {
+        if std::fs::remove_file(path).is_ok() {
+            let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+            *used = used.saturating_sub(len);
+        }
+    }
+}
+
+/// Path, size and modification time of each entry in `dir`.
+fn entries(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(rd) = std::fs::read_dir(dir) else { return vec![] };
+    rd.filter_map(|e| {
+        let e = e.ok()?;
+        let path = e.path();
+        if !path.file_name()?.to_str()?.ends_with(EXT) {
+            return None;
+        }
+        let meta = e.metadata().ok()?;
+        Some((path, meta.len(), meta.modified().ok()?))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn lines(content: &str) -> Vec<IndexLine> {
+        vec![IndexLine { archive_path: None, line_number: 2, content: content.to_string() }]
+    }
+
+    #[test]
+    fn put_get_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ExtractCache::open(tmp.path().join("cache"), 1024 * 1024).unwrap();
+        let key = ExtractCache::key("abc", "pdf");
+        assert!(cache.get(&key).is_none());
+        cache.put(&key, &lines("hello"));
+        assert_eq!(cache.get(&key).unwrap()[0].content, "hello");
+        assert_ne!(ExtractCache::key("abc", "txt"), key, "context is part of the key");
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entries() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ExtractCache::open(tmp.path().to_path_buf(), 20_000).unwrap();
+        // Incompressible content so each entry takes ~1.5 KB on disk.
+        let noise = |i: u64| (0..1000u64).map(|n| blake3::hash(&(i * 1000 + n).to_le_bytes()).as_bytes()[0] as char).collect::<String>();
+        let first = ExtractCache::key("0", "");
+        cache.put(&first, &lines(&noise(0)));
+        let old = SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options().write(true).open(cache.path(&first)).unwrap().set_modified(old).unwrap();
+        for i in 1..20 {
+            cache.put(&ExtractCache::key(&i.to_string(), ""), &lines(&noise(i)));
+        }
+        assert!(cache.get(&first).is_none(), "the oldest entry is evicted");
+        assert!(cache.get(&ExtractCache::key("19", "")).is_some());
+        let on_disk: u64 = entries(tmp.path()).iter().map(|(_, len, _)| len).sum();
+        assert!(on_disk <= 20_000);
+        assert_eq!(*cache.used.lock().unwrap(), on_disk);
+    }
+}
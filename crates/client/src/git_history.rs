@@ -0,0 +1,317 @@
+//! Git history indexing (`[scan.git_history]`, or `git_history` on a
+//! source).
+//!
+//! After the working tree, `find-scan` indexes the history of every git
+//! repository it finds in the source, under paths that do not exist on
+//! disk:
+//!
+//! - `<repo>@log` — the commit log, as `git log` prints it;
+//! - `<repo>@<tag>` — the files at each matching tag, as an archive whose
+//!   members are `<repo>@<tag>::<path>`.
+//!
+//! `<repo>` is the repository's path in the source, or the source root's
+//! own name when the root is the repository.  Searching for a string then
+//! shows which releases still contain it.  Tag trees are taken with `git
+//! archive` and run through the archive extractor, so the usual size limits
+//! and extractors apply to their files.
+//!
+//! `git/<source>.json` in the state directory records the commit each path
+//! was indexed at; only new or moved tags, and a log whose HEAD moved, are
+//! indexed again.  Tags that are deleted or fall outside `max_tags` are
+//! removed from the index.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{Context, Result};
+use globset::GlobSet;
+use tracing::{debug, info, warn};
+
+use find_common::{
+    build_globset,
+    config::{default_state_dir, GitHistoryConfig, ScanConfig},
+    path::is_composite,
+};
+
+use crate::api::ApiClient;
+use crate::remote::{self, RemoteBatch};
+use crate::scan::ScanOptions;
+use crate::walk::{walk_source_tree, WalkItem};
+
+/// A git work tree found in a source.
+struct Repo {
+    dir: PathBuf,
+    /// Path prefix of the repository's history in the index.
+    name: String,
+}
+
+/// One history path to index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Revision {
+    repo: PathBuf,
+    /// `<repo>@log` or `<repo>@<tag>`.
+    path: String,
+    /// Commit the path is indexed at.
+    commit: String,
+    /// Unix seconds.
+    mtime: i64,
+    /// Tagged tree to archive, or `None` for the log.
+    tag: Option<String>,
+}
+
+/// Name of the repository at `dir` under the source root `root`.
+fn repo_name(root: &Path, dir: &Path) -> String {
+    match dir.strip_prefix(root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => rel.to_string_lossy().replace('\\', "/"),
+        _ => root.file_name().map_or_else(|| "repo".to_string(), |n| n.to_string_lossy().into_owned()),
+    }
+}
+
+/// Git work trees in the source, found with the same walk rules as its
+/// files.
+fn find_repos(paths: &[String], scan: &ScanConfig, excludes: &GlobSet) -> Vec<Repo> {
+    let mut repos = Vec::new();
+    for root in paths.iter().map(Path::new) {
+        walk_source_tree(root, root, scan, excludes, None, |item| {
+            if let WalkItem::Dir(dir) = item {
+                if dir.join(".git").exists() {
+                    repos.push(Repo { name: repo_name(root, &dir), dir });
+                }
+            }
+        });
+    }
+    repos
+}
+
+pub(crate) use crate::path_util::is_history_path;
+
+fn git_command(repo: &Path, args: &[&str]) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.arg("-C").arg(repo).args(args).stdin(Stdio::null()).kill_on_drop(true);
+    cmd
+}
+
+/// Run git in `repo` and return its output.
+async fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let out = git_command(repo, args).output().await.context("running git; is it installed?")?;
+    if !out.status.success() {
+        anyhow::bail!("git {}: {}", args.join(" "), String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Run git in `repo` with its output written to `dest`.
+async fn git_to_file(repo: &Path, args: &[&str], dest: &Path) -> Result<()> {
+    let file = std::fs::File::create(dest).with_context(|| format!("creating {}", dest.display()))?;
+    let out = git_command(repo, args).stdout(file).output().await.context("running git; is it installed?")?;
+    if !out.status.success() {
+        anyhow::bail!("git {}: {}", args.join(" "), String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Parse `git for-each-ref` output (`name`, object, peeled object, date;
+/// tab-separated) into `(tag, commit, date)`.
+fn parse_tags(out: &str) -> Vec<(String, String, i64)> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let (name, object, peeled, date) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+            // Annotated tags peel to their commit; lightweight tags are one.
+            let commit = if peeled.is_empty() { object } else { peeled };
+            Some((name.to_string(), commit.to_string(), date.trim().parse().unwrap_or(0)))
+        })
+        .collect()
+}
+
+/// The history paths of `repo` to index under `cfg`.  Empty for a
+/// repository with no commits yet.
+async fn revisions(repo: &Repo, cfg: &GitHistoryConfig, tags: &GlobSet) -> Result<Vec<Revision>> {
+    let head = match git(&repo.dir, &["log", "-1", "--format=%H%x09%ct"]).await {
+        Ok(head) => head,
+        Err(e) => {
+            debug!("{}: no history to index: {e:#}", repo.dir.display());
+            return Ok(Vec::new());
+        }
+    };
+    let mut out = Vec::new();
+    if cfg.commits {
+        if let Some((commit, time)) = head.trim().split_once('\t') {
+            out.push(Revision {
+                repo: repo.dir.clone(),
+                path: format!("{}@log", repo.name),
+                commit: commit.to_string(),
+                mtime: time.parse().unwrap_or(0),
+                tag: None,
+            });
+        }
+    }
+    let refs = git(&repo.dir, &[
+        "for-each-ref",
+        "--sort=-creatordate",
+        "--format=%(refname:short)%09%(objectname)%09%(*objectname)%09%(creatordate:unix)",
+        "refs/tags",
+    ]).await?;
+    out.extend(
+        parse_tags(&refs)
+            .into_iter()
+            .filter(|(name, _, _)| tags.is_match(name))
+            .take(cfg.max_tags)
+            .map(|(name, commit, mtime)| Revision {
+                repo: repo.dir.clone(),
+                path: format!("{}@{name}", repo.name),
+                commit,
+                mtime,
+                tag: Some(name),
+            }),
+    );
+    Ok(out)
+}
+
+/// Commits the history paths of one source were indexed at.
+fn load_state(path: &Path) -> HashMap<String, String> {
+    match std::fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+            warn!("ignoring unreadable {}: {e}", path.display());
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_state(path: &Path, state: &HashMap<String, String>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(state)?).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))
+}
+
+/// Index the git history of the repositories in the source `source_name`
+/// at `paths`, when `scan.git_history` is set.
+pub async fn scan_history(api: &ApiClient, source_name: &str, paths: &[String], scan: &ScanConfig, opts: &ScanOptions) -> Result<()> {
+    let Some(cfg) = &scan.git_history else { return Ok(()) };
+    let excludes = build_globset(&scan.exclude)?;
+    let tags = build_globset(&cfg.tags)?;
+    let scan_start = chrono::Utc::now().timestamp();
+
+    let repos = find_repos(paths, scan, &excludes);
+    let mut wanted = Vec::new();
+    for repo in &repos {
+        match revisions(repo, cfg, &tags).await {
+            Ok(revs) => wanted.extend(revs),
+            Err(e) => warn!("{source_name}: skipping the history of {}: {e:#}", repo.dir.display()),
+        }
+    }
+    let wanted_paths: HashSet<&str> = wanted.iter().map(|r| r.path.as_str()).collect();
+
+    let server_paths: HashSet<String> = api.list_files(source_name).await?
+        .into_iter()
+        .map(|f| f.path)
+        .filter(|p| !is_composite(p) && is_history_path(paths, p))
+        .collect();
+    let mut deleted: Vec<String> = server_paths.iter().filter(|p| !wanted_paths.contains(p.as_str())).cloned().collect();
+    deleted.sort();
+
+    let state_path = default_state_dir().join("git").join(format!("{source_name}.json"));
+    let mut state = load_state(&state_path);
+    let changed: Vec<&Revision> = wanted.iter()
+        .filter(|r| opts.force_since.is_some() || state.get(&r.path) != Some(&r.commit))
+        .collect();
+    info!(
+        "{source_name}: git history of {} repositories: {} revisions, {} new or changed, {} deleted",
+        repos.len(), wanted.len(), changed.len(), deleted.len(),
+    );
+    if opts.dry_run {
+        return Ok(());
+    }
+
+    let tmp = tempfile::TempDir::new().context("creating git archive directory")?;
+    let mut batch = RemoteBatch::new(api, source_name, scan);
+    for rev in changed {
+        api.wait_while_paused().await;
+        match index_revision(rev, scan, tmp.path()).await {
+            Ok(mut files) => {
+                if let Some(f) = files.first_mut() {
+                    f.is_new = !server_paths.contains(&rev.path);
+                    f.force = opts.force_index;
+                }
+                batch.add(files, rev.path.clone(), rev.commit.clone());
+            }
+            Err(e) => {
+                warn!("{source_name}: failed to index {}: {e:#}", rev.path);
+                batch.fail(&rev.path, &e);
+            }
+        }
+        if batch.is_full() {
+            state.extend(batch.submit(vec![], None).await?);
+            save_state(&state_path, &state)?;
+        }
+    }
+
+    state.extend(batch.submit(deleted.clone(), Some(scan_start)).await?);
+    state.retain(|path, _| wanted_paths.contains(path.as_str()));
+    save_state(&state_path, &state)
+}
+
+/// Write out and extract one revision: the log as text, a tag as a tar
+/// archive of its tree.
+async fn index_revision(rev: &Revision, scan: &ScanConfig, dir: &Path) -> Result<Vec<find_common::api::IndexFile>> {
+    let local = match &rev.tag {
+        None => {
+            let local = dir.join("log.txt");
+            git_to_file(&rev.repo, &["log", "--no-color", "--pretty=medium", "--date=iso-strict", &rev.commit], &local).await?;
+            local
+        }
+        Some(_) => {
+            let local = dir.join("tree.tar");
+            git_to_file(&rev.repo, &["archive", "--format=tar", &rev.commit], &local).await?;
+            local
+        }
+    };
+    let size = std::fs::metadata(&local).map_or(0, |m| m.len());
+    remote::index_download(&local, &rev.path, rev.mtime, size, scan).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn tags_peel_to_commits() {
+        let out = "v1.1.0\taaaa\tcccc\t1709294400\nv1.0.0\tbbbb\t\t1700000000\n";
+        assert_eq!(parse_tags(out), vec![
+            ("v1.1.0".to_string(), "cccc".to_string(), 1_709_294_400),
+            ("v1.0.0".to_string(), "bbbb".to_string(), 1_700_000_000),
+        ]);
+    }
+
+    #[test]
+    fn history_paths_need_a_repository() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("code/app/.git")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("code/notes")).unwrap();
+        let paths = vec![tmp.path().join("code").to_string_lossy().into_owned()];
+
+        assert!(is_history_path(&paths, "app@v1.2.0"));
+        assert!(is_history_path(&paths, "app@release/2.0"));
+        assert!(is_history_path(&paths, "app@log"));
+        assert!(!is_history_path(&paths, "notes@log"));
+        assert!(!is_history_path(&paths, "app@"));
+        assert!(!is_history_path(&paths, "app/readme.md"));
+
+        // The source root is itself the repository.
+        std::fs::create_dir_all(tmp.path().join("code/.git")).unwrap();
+        assert!(is_history_path(&paths, "code@v1"));
+    }
+
+    #[test]
+    fn repo_names() {
+        let root = Path::new("/home/alice/code");
+        assert_eq!(repo_name(root, Path::new("/home/alice/code/tools/app")), "tools/app");
+        assert_eq!(repo_name(root, root), "code");
+    }
+}
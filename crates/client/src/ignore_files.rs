@@ -0,0 +1,184 @@
+//! `.gitignore`-style ignore files, honoured during traversal when
+//! `ignore_files` is enabled for a source.
+//!
+//! Each directory may hold `.gitignore`, `.ignore` and `.fdignore`; their
+//! rules apply to the directory's subtree.  Matching follows gitignore:
+//!
+//! * blank lines and `#` comments are skipped; `\#` and `\!` escape;
+//! * `!pattern` re-includes a path an earlier rule ignored;
+//! * a trailing `/` matches directories only;
+//! * a pattern with a `/` before its end is anchored to the ignore file's
+//!   directory, otherwise it matches a name at any depth;
+//! * within a file the last matching rule wins, and files deeper in the tree
+//!   (and `.ignore`/`.fdignore` over `.gitignore`) override shallower ones.
+//!
+//! As in git, a file inside an ignored directory cannot be re-included: the
+//! walk never descends into the directory.  Unlike git, `.gitignore` files
+//! are honoured whether or not the tree is a git repository, and global
+//! excludes (`core.excludesFile`, `.git/info/exclude`) are not read.
+
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// Ignore file names, lowest precedence first.
+pub(crate) const IGNORE_FILE_NAMES: [&str; 3] = [".gitignore", ".ignore", ".fdignore"];
+
+struct Rule {
+    matcher: GlobMatcher,
+    negate: bool,
+    dir_only: bool,
+}
+
+/// The rules of one directory's ignore files.
+pub(crate) struct DirRules {
+    /// The directory, relative to the walk's strip root ("" for the root).
+    dir_rel: String,
+    rules: Vec<Rule>,
+}
+
+impl DirRules {
+    /// Read the ignore files in `dir`, whose path relative to the strip root
+    /// is `dir_rel`.  `None` when it has none (or none with rules).
+    pub(crate) fn load(dir: &Path, dir_rel: &str) -> Option<Self> {
+        let mut rules = Vec::new();
+        for name in IGNORE_FILE_NAMES {
+            let path = dir.join(name);
+            // Cheap existence check first: most directories have none.
+            if !path.is_file() {
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(text) => rules.extend(text.lines().filter_map(|l| parse_line(l, &path))),
+                Err(e) => tracing::warn!("walk: cannot read {}: {e}", path.display()),
+            }
+        }
+        (!rules.is_empty()).then(|| DirRules { dir_rel: dir_rel.to_string(), rules })
+    }
+
+    /// `Some(true)` if the last rule matching `rel` (relative to the strip
+    /// root) ignores it, `Some(false)` if it re-includes it, `None` if no
+    /// rule matches.
+    pub(crate) fn matched(&self, rel: &str, is_dir: bool) -> Option<bool> {
+        let local = if self.dir_rel.is_empty() {
+            rel
+        } else {
+            rel.strip_prefix(&self.dir_rel)?.strip_prefix('/')?
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| (is_dir || !r.dir_only) && r.matcher.is_match(local))
+            .map(|r| !r.negate)
+    }
+}
+
+/// Whether the innermost matching rule in `stack` (ordered shallow to deep)
+/// ignores `rel`.
+pub(crate) fn is_ignored<'a>(stack: impl DoubleEndedIterator<Item = &'a DirRules>, rel: &str, is_dir: bool) -> bool {
+    stack.rev().find_map(|r| r.matched(rel, is_dir)).unwrap_or(false)
+}
+
+/// True when an ignore file between `root` and `rel` ignores `rel` or one of
+/// its parent directories.  Loads every ignore file on the way, so this is
+/// for one-off checks (watch events), not for walks.
+#[allow(dead_code)] // used by find-watch only
+pub(crate) fn is_path_ignored(root: &Path, rel: &str, is_dir: bool) -> bool {
+    let mut stack: Vec<DirRules> = DirRules::load(root, "").into_iter().collect();
+    let parts: Vec<&str> = rel.split('/').collect();
+    for i in 1..=parts.len() {
+        let prefix = parts[..i].join("/");
+        let last = i == parts.len();
+        if is_ignored(stack.iter(), &prefix, is_dir || !last) {
+            return true;
+        }
+        if !last {
+            stack.extend(DirRules::load(&root.join(&prefix), &prefix));
+        }
+    }
+    false
+}
+
+fn parse_line(line: &str, file: &Path) -> Option<Rule> {
+    // Trailing spaces are dropped unless escaped with a backslash.
+    let mut pat = line.trim_end_matches(['\r', ' ']);
+    if pat.ends_with('\\') && line[pat.len()..].starts_with(' ') {
+        pat = &line[..pat.len() + 1];
+    }
+    if pat.is_empty() || pat.starts_with('#') {
+        return None;
+    }
+    let (negate, pat) = match pat.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pat.strip_prefix('\\').filter(|p| p.starts_with(['#', '!'])).unwrap_or(pat)),
+    };
+    let (dir_only, pat) = match pat.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pat),
+    };
+    let glob = match pat.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pat.contains('/') => pat.to_string(),
+        None => format!("**/{pat}"),
+    };
+    if glob.is_empty() {
+        return None;
+    }
+    match GlobBuilder::new(&glob).literal_separator(true).backslash_escape(true).build() {
+        Ok(g) => Some(Rule { matcher: g.compile_matcher(), negate, dir_only }),
+        Err(e) => {
+            tracing::debug!("walk: skipping pattern {line:?} in {}: {e}", file.display());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rules(dir_rel: &str, text: &str) -> DirRules {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join(".gitignore"), text).unwrap();
+        DirRules::load(tmp.path(), dir_rel).unwrap()
+    }
+
+    #[test]
+    fn gitignore_pattern_forms() {
+        let r = rules("", "# comment\n\n*.log\n!keep.log\n/build\ndocs/*.tmp\nout/\n\\#hash\n");
+        assert_eq!(r.matched("a/b/x.log", false), Some(true), "unanchored matches at any depth");
+        assert_eq!(r.matched("a/keep.log", false), Some(false), "negation re-includes");
+        assert_eq!(r.matched("build", true), Some(true));
+        assert_eq!(r.matched("src/build", true), None, "leading slash anchors");
+        assert_eq!(r.matched("docs/a.tmp", false), Some(true));
+        assert_eq!(r.matched("docs/sub/a.tmp", false), None, "* does not cross /");
+        assert_eq!(r.matched("x/out", true), Some(true));
+        assert_eq!(r.matched("x/out", false), None, "trailing slash is dirs only");
+        assert_eq!(r.matched("#hash", false), Some(true));
+    }
+
+    #[test]
+    fn nested_rules_are_relative_and_deeper_wins() {
+        let outer = rules("", "*.gen\n");
+        let inner = rules("sub", "!keep.gen\n/local\n");
+        let stack = [outer, inner];
+        assert!(is_ignored(stack.iter(), "a.gen", false));
+        assert!(is_ignored(stack.iter(), "sub/x.gen", false));
+        assert!(!is_ignored(stack.iter(), "sub/keep.gen", false));
+        assert!(is_ignored(stack.iter(), "sub/local", false));
+        assert!(!is_ignored(stack.iter(), "local", false));
+    }
+
+    #[test]
+    fn path_check_honours_ignored_parent_dirs() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("pkg/node_modules/lib")).unwrap();
+        std::fs::write(tmp.path().join(".gitignore"), "node_modules/\n").unwrap();
+        std::fs::write(tmp.path().join("pkg/.fdignore"), "*.bak\n").unwrap();
+        assert!(is_path_ignored(tmp.path(), "pkg/node_modules/lib/index.js", false));
+        assert!(is_path_ignored(tmp.path(), "pkg/x.bak", false));
+        assert!(!is_path_ignored(tmp.path(), "x.bak", false));
+        assert!(!is_path_ignored(tmp.path(), "pkg/index.js", false));
+    }
+}
@@ -0,0 +1,171 @@
+//! `find-admin watch`: a live view of what the server's inbox worker is doing,
+//! redrawn from the stats stream, the activity stream and the inbox status.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+use find_common::api::{InboxItem, RecentAction, RecentFile, StatsStreamEvent, WorkerStatus};
+
+/// Throughput is averaged over this much recent history.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Files shown under "Recent activity".
+pub const RECENT_SHOWN: usize = 8;
+/// Failed requests shown under "Recent failures".
+const FAILURES_SHOWN: usize = 5;
+
+#[derive(Default)]
+pub struct IngestView {
+    stats: Option<StatsStreamEvent>,
+    /// `(when, files_indexed, lines_indexed)` from each stats event in the
+    /// rate window, oldest first.
+    samples: VecDeque<(Instant, u64, u64)>,
+    recent: VecDeque<RecentFile>,
+    failures: Vec<InboxItem>,
+}
+
+impl IngestView {
+    pub fn on_stats(&mut self, event: StatsStreamEvent, now: Instant) {
+        // A restarted server starts counting from zero again.
+        if self.samples.back().is_some_and(|&(_, files, _)| event.files_indexed < files) {
+            self.samples.clear();
+        }
+        self.samples.push_back((now, event.files_indexed, event.lines_indexed));
+        self.stats = Some(event);
+    }
+
+    pub fn on_file(&mut self, file: RecentFile) {
+        self.recent.push_front(file);
+        self.recent.truncate(RECENT_SHOWN);
+    }
+
+    /// The failed inbox requests, newest first.
+    pub fn set_failures(&mut self, mut failed: Vec<InboxItem>) {
+        failed.sort_by_key(|f| f.age_secs);
+        failed.truncate(FAILURES_SHOWN);
+        self.failures = failed;
+    }
+
+    /// `(files/s, lines/s)` over the rate window ending at `now`.  The stats
+    /// stream only sends an event when something changes, so an idle worker
+    /// decays to zero rather than showing its last rate.
+    pub fn rates(&mut self, now: Instant) -> (f64, f64) {
+        // Keep one sample older than the window as the baseline.
+        while self.samples.len() > 1 && now.duration_since(self.samples[1].0) > RATE_WINDOW {
+            self.samples.pop_front();
+        }
+        let (Some(&(t0, f0, l0)), Some(&(_, f1, l1))) = (self.samples.front(), self.samples.back()) else {
+            return (0.0, 0.0);
+        };
+        let secs = now.duration_since(t0).as_secs_f64().max(1.0);
+        ((f1 - f0) as f64 / secs, (l1 - l0) as f64 / secs)
+    }
+
+    pub fn render(&mut self, now: Instant) -> String {
+        let (files_rate, lines_rate) = self.rates(now);
+        let mut out = String::new();
+        let Some(stats) = &self.stats else {
+            return "Waiting for the server...\n".to_string();
+        };
+
+        match &stats.worker_status {
+            WorkerStatus::Idle => writeln!(out, "Worker:      idle").unwrap(),
+            WorkerStatus::Processing { .. } if stats.worker_lanes.len() > 1 => {
+                for (i, lane) in stats.worker_lanes.iter().enumerate() {
+                    let label = if i == 0 { "Worker:" } else { "" };
+                    writeln!(out, "{label:<13}{} {}/{}", "●".cyan(), lane.source, lane.file).unwrap();
+                }
+            }
+            WorkerStatus::Processing { source, file } => {
+                writeln!(out, "Worker:      {} {}/{}", "●".cyan(), source, file).unwrap()
+            }
+        }
+        let paused = if stats.inbox_paused { format!("  {}", "PAUSED".yellow()) } else { String::new() };
+        writeln!(
+            out,
+            "Inbox:       {} pending, {} failed, {} awaiting write{paused}",
+            stats.inbox_pending, stats.failed_requests, stats.archive_queue,
+        ).unwrap();
+        writeln!(
+            out,
+            "Throughput:  {files_rate:.1} files/s, {lines_rate:.0} lines/s  (last {}s)",
+            RATE_WINDOW.as_secs(),
+        ).unwrap();
+        writeln!(
+            out,
+            "Since start: {} files, {} lines",
+            stats.files_indexed, stats.lines_indexed,
+        ).unwrap();
+
+        writeln!(out, "\nRecent activity:").unwrap();
+        if self.recent.is_empty() {
+            writeln!(out, "  (none yet)").unwrap();
+        }
+        for f in &self.recent {
+            let action = match f.action {
+                RecentAction::Added => "added   ".green(),
+                RecentAction::Modified => "modified".cyan(),
+                RecentAction::Deleted => "deleted ".red(),
+                RecentAction::Renamed => "renamed ".yellow(),
+            };
+            let path = match &f.new_path {
+                Some(new) => format!("{} → {new}", f.path),
+                None => f.path.clone(),
+            };
+            writeln!(out, "  {action}  {}/{path}", f.source).unwrap();
+        }
+
+        if !self.failures.is_empty() {
+            writeln!(out, "\nRecent failures:").unwrap();
+            for item in &self.failures {
+                let error = item.failure.as_ref().map_or("(no diagnostics)", |f| f.error.as_str());
+                writeln!(out, "  {}  {}", item.filename.red(), error).unwrap();
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(files: u64, lines: u64) -> StatsStreamEvent {
+        serde_json::from_value(serde_json::json!({
+            "sources": [],
+            "inbox_pending": 0,
+            "failed_requests": 0,
+            "content_file_count": 0,
+            "db_size_bytes": 0,
+            "content_size_bytes": 0,
+            "worker_status": { "state": "idle" },
+            "files_indexed": files,
+            "lines_indexed": lines,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn rates_cover_the_window_and_decay_when_idle() {
+        let start = Instant::now();
+        let mut view = IngestView::default();
+        view.on_stats(event(100, 10_000), start);
+        view.on_stats(event(150, 15_000), start + Duration::from_secs(5));
+        let (files, lines) = view.rates(start + Duration::from_secs(5));
+        assert_eq!((files, lines), (10.0, 1000.0));
+
+        // No events for a while: the old samples age out.
+        assert_eq!(view.rates(start + Duration::from_secs(60)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn server_restart_resets_the_baseline() {
+        let start = Instant::now();
+        let mut view = IngestView::default();
+        view.on_stats(event(500, 50_000), start);
+        view.on_stats(event(20, 2_000), start + Duration::from_secs(2));
+        assert_eq!(view.rates(start + Duration::from_secs(2)), (0.0, 0.0));
+    }
+}
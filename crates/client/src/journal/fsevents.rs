@@ -0,0 +1,164 @@
+//! macOS FSEvents backend.
+//!
+//! Unlike notify's stream, which starts at "now", this one is created with
+//! the last event id seen, so FSEvents replays its on-disk history of the
+//! changes made while `find-watch` was stopped before delivering live
+//! events.  Where history was lost or events were coalesced (the
+//! `MustScanSubDirs` family of flags), the whole directory is re-walked by
+//! sending it as a new directory.
+//!
+//! The position is the last FSEvents event id.
+
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use fsevent_sys as fs;
+use fsevent_sys::core_foundation as cf;
+use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind, RenameMode};
+use notify::{Event, EventKind};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use super::Position;
+
+/// Seconds FSEvents may hold events back to coalesce them.
+const LATENCY_SECS: f64 = 1.0;
+
+/// Flags meaning individual events were lost under the directory.
+const RESCAN_FLAGS: u32 = fs::kFSEventStreamEventFlagMustScanSubDirs
+    | fs::kFSEventStreamEventFlagUserDropped
+    | fs::kFSEventStreamEventFlagKernelDropped
+    | fs::kFSEventStreamEventFlagEventIdsWrapped
+    | fs::kFSEventStreamEventFlagRootChanged;
+
+struct StreamContext {
+    tx: mpsc::Sender<notify::Result<Event>>,
+    position: Position,
+}
+
+pub fn start(root: &Path, since: Option<&str>, tx: mpsc::Sender<notify::Result<Event>>) -> Result<Position> {
+    let root_str = root.to_str().context("source path is not valid UTF-8")?.to_string();
+    let saved = since.and_then(|s| s.parse::<u64>().ok());
+    let since_id = match saved {
+        Some(id) => {
+            info!("FSEvents for {:?}: replaying history since event {id}", root);
+            id
+        }
+        None => unsafe { fs::FSEventsGetCurrentEventId() },
+    };
+    let position = Arc::new(Mutex::new(since_id.to_string()));
+    let context = Box::new(StreamContext { tx, position: position.clone() });
+    std::thread::Builder::new()
+        .name("fsevents".into())
+        .spawn(move || unsafe { run_stream(&root_str, since_id, context) })
+        .context("starting the FSEvents reader")?;
+    Ok(position)
+}
+
+/// Create the stream on this thread's run loop and run it until the event
+/// loop shuts down.
+unsafe fn run_stream(root: &str, since: u64, context: Box<StreamContext>) {
+    let mut err: cf::CFErrorRef = std::ptr::null_mut();
+    let cf_root = cf::str_path_to_cfstring_ref(root, &mut err);
+    if cf_root.is_null() {
+        warn!("FSEvents: cannot watch {root}");
+        return;
+    }
+    let paths = cf::CFArrayCreateMutable(cf::kCFAllocatorDefault, 0, &cf::kCFTypeArrayCallBacks);
+    cf::CFArrayAppendValue(paths, cf_root);
+    cf::CFRelease(cf_root);
+
+    // Leaked on purpose: the stream lives as long as the thread.
+    let info = Box::into_raw(context) as *mut c_void;
+    let stream_context = fs::FSEventStreamContext {
+        version: 0,
+        info,
+        retain: None,
+        release: None,
+        copy_description: None,
+    };
+    let stream = fs::FSEventStreamCreate(
+        cf::kCFAllocatorDefault,
+        callback,
+        &stream_context,
+        paths,
+        since,
+        LATENCY_SECS,
+        fs::kFSEventStreamCreateFlagFileEvents
+            | fs::kFSEventStreamCreateFlagNoDefer
+            | fs::kFSEventStreamCreateFlagWatchRoot,
+    );
+    cf::CFRelease(paths);
+    fs::FSEventStreamScheduleWithRunLoop(stream, cf::CFRunLoopGetCurrent(), cf::kCFRunLoopDefaultMode);
+    if fs::FSEventStreamStart(stream) == 0 {
+        warn!("FSEvents: failed to start the stream for {root}");
+        return;
+    }
+    cf::CFRunLoopRun();
+}
+
+extern "C" fn callback(
+    _stream: fs::FSEventStreamRef,
+    info: *mut c_void,
+    num_events: usize,
+    event_paths: *mut c_void,
+    event_flags: *const fs::FSEventStreamEventFlags,
+    event_ids: *const fs::FSEventStreamEventId,
+) {
+    let (context, paths, flags, ids) = unsafe {
+        (
+            &*(info as *const StreamContext),
+            std::slice::from_raw_parts(event_paths as *const *const c_char, num_events),
+            std::slice::from_raw_parts(event_flags, num_events),
+            std::slice::from_raw_parts(event_ids, num_events),
+        )
+    };
+    let mut last_id = None;
+    for i in 0..num_events {
+        let path = PathBuf::from(unsafe { CStr::from_ptr(paths[i]) }.to_string_lossy().into_owned());
+        if let Some(kind) = event_kind(flags[i], &path) {
+            if context.tx.blocking_send(Ok(Event::new(kind).add_path(path))).is_err() {
+                unsafe { cf::CFRunLoopStop(cf::CFRunLoopGetCurrent()) };
+                return;
+            }
+        }
+        if ids[i] != 0 {
+            last_id = Some(ids[i]);
+        }
+    }
+    if let Some(id) = last_id {
+        *context.position.lock().unwrap_or_else(|e| e.into_inner()) = id.to_string();
+    }
+}
+
+fn event_kind(flags: u32, path: &Path) -> Option<EventKind> {
+    if flags & fs::kFSEventStreamEventFlagHistoryDone != 0 {
+        debug!("FSEvents: history replay complete");
+        return None;
+    }
+    if flags & RESCAN_FLAGS != 0 {
+        // Individual events were lost: re-walk the directory as if new.
+        debug!("FSEvents: rescanning {}", path.display());
+        return path.is_dir().then_some(EventKind::Create(CreateKind::Folder));
+    }
+    let is_dir = flags & fs::kFSEventStreamEventFlagItemIsDir != 0;
+    // History replay coalesces flags (created + modified + removed), so the
+    // file's current state decides what happened to it.
+    Some(if flags & fs::kFSEventStreamEventFlagItemRenamed != 0 {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Any))
+    } else if !path.exists() {
+        if flags & fs::kFSEventStreamEventFlagItemRemoved == 0 {
+            return None;
+        }
+        EventKind::Remove(if is_dir { RemoveKind::Folder } else { RemoveKind::File })
+    } else if flags & fs::kFSEventStreamEventFlagItemCreated != 0 {
+        EventKind::Create(if is_dir { CreateKind::Folder } else { CreateKind::File })
+    } else if flags & fs::kFSEventStreamEventFlagItemModified != 0 && !is_dir {
+        EventKind::Modify(ModifyKind::Data(DataChange::Content))
+    } else {
+        return None;
+    })
+}
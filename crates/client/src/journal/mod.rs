@@ -0,0 +1,221 @@
+//! Native change-journal watch backends (`[watch] mode = "native"`).
+//!
+//! notify's backends (ReadDirectoryChangesW on Windows, a live FSEvents
+//! stream on macOS) drop events when the kernel buffer overflows under heavy
+//! churn, and see nothing at all while `find-watch` is not running.  The
+//! native backends read the filesystem's own change log instead — the NTFS
+//! USN journal on Windows, the FSEvents event history on macOS — and record
+//! how far they got, so a restarted watcher replays the changes it missed.
+//! Like the polling backend they feed synthetic `notify` events into the
+//! event loop.
+//!
+//! Positions are saved in `watch-cursors.json` in the state directory, keyed
+//! by source root.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use notify::Event;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+#[cfg(target_os = "macos")]
+mod fsevents;
+#[cfg(windows)]
+mod usn;
+
+#[cfg(target_os = "macos")]
+use fsevents as platform;
+#[cfg(windows)]
+use usn as platform;
+
+/// How often the backend's position is checked for saving.
+const CURSOR_TICK: Duration = Duration::from_secs(5);
+
+/// Journal position reached by a backend, updated as it reads.
+type Position = Arc<Mutex<String>>;
+
+/// Start following the change journal for `root`, resuming from the
+/// position saved in `store`.  Fails when the platform or filesystem has no
+/// usable journal; the caller then falls back to OS events.
+///
+/// A position is only saved once it is `lag` old, so events still waiting
+/// in the event loop's batch window when the watcher stops are replayed on
+/// the next start rather than lost.
+pub fn start(
+    root: &Path,
+    store: &Arc<CursorStore>,
+    lag: Duration,
+    tx: mpsc::Sender<notify::Result<Event>>,
+) -> Result<()> {
+    let since = store.get(root);
+    let position = platform_start(root, since.as_deref(), tx.clone())?;
+    tokio::spawn(save_positions(root.to_path_buf(), store.clone(), position, lag, tx));
+    Ok(())
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn platform_start(
+    root: &Path,
+    since: Option<&str>,
+    tx: mpsc::Sender<notify::Result<Event>>,
+) -> Result<Position> {
+    platform::start(root, since, tx)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_start(
+    _root: &Path,
+    _since: Option<&str>,
+    _tx: mpsc::Sender<notify::Result<Event>>,
+) -> Result<Position> {
+    anyhow::bail!("change journals are only supported on Windows (NTFS) and macOS")
+}
+
+async fn save_positions(
+    root: PathBuf,
+    store: Arc<CursorStore>,
+    position: Position,
+    lag: Duration,
+    tx: mpsc::Sender<notify::Result<Event>>,
+) {
+    let mut lagged = LaggedCursor::new(lag);
+    while !tx.is_closed() {
+        tokio::time::sleep(CURSOR_TICK).await;
+        let current = position.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(cursor) = lagged.advance(current, Instant::now()) {
+            debug!("journal position for {:?}: {cursor}", root);
+            store.set(&root, cursor);
+        }
+    }
+}
+
+/// Holds back positions until they are old enough to save.
+struct LaggedCursor {
+    lag: Duration,
+    pending: VecDeque<(Instant, String)>,
+    saved: Option<String>,
+}
+
+impl LaggedCursor {
+    fn new(lag: Duration) -> Self {
+        LaggedCursor { lag, pending: VecDeque::new(), saved: None }
+    }
+
+    /// Record `current` as seen at `now` and return the newest position
+    /// that has become old enough to save, if it differs from the last one.
+    fn advance(&mut self, current: String, now: Instant) -> Option<String> {
+        if self.pending.back().map(|(_, c)| c) != Some(&current) {
+            self.pending.push_back((now, current));
+        }
+        let mut ready = None;
+        while self.pending.front().is_some_and(|(seen, _)| now.duration_since(*seen) >= self.lag) {
+            ready = self.pending.pop_front().map(|(_, c)| c);
+        }
+        // Keep the ready position as the floor for the next round.
+        if let Some(c) = &ready {
+            if self.pending.is_empty() {
+                self.pending.push_back((now.checked_sub(self.lag).unwrap_or(now), c.clone()));
+            }
+        }
+        match ready {
+            Some(c) if self.saved.as_ref() != Some(&c) => {
+                self.saved = Some(c.clone());
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Saved journal positions, one per source root.
+pub struct CursorStore {
+    path: PathBuf,
+    cursors: Mutex<HashMap<String, String>>,
+}
+
+impl CursorStore {
+    /// Load the positions saved at `path`.  A missing or unreadable file
+    /// starts empty: every source then begins at the current position.
+    pub fn open(path: PathBuf) -> Self {
+        let cursors = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+                warn!("ignoring unreadable {}: {e}", path.display());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        CursorStore { path, cursors: Mutex::new(cursors) }
+    }
+
+    pub fn get(&self, root: &Path) -> Option<String> {
+        self.cursors.lock().unwrap_or_else(|e| e.into_inner()).get(&key(root)).cloned()
+    }
+
+    pub fn set(&self, root: &Path, cursor: String) {
+        let mut cursors = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        cursors.insert(key(root), cursor);
+        if let Err(e) = self.write(&cursors) {
+            warn!("failed to save journal positions to {}: {e:#}", self.path.display());
+        }
+    }
+
+    fn write(&self, cursors: &HashMap<String, String>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(cursors)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn key(root: &Path) -> String {
+    root.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn cursor_is_saved_only_once_old_enough() {
+        let lag = Duration::from_secs(30);
+        let mut lagged = LaggedCursor::new(lag);
+        let t0 = Instant::now();
+        assert_eq!(lagged.advance("1".into(), t0), None);
+        assert_eq!(lagged.advance("2".into(), t0 + Duration::from_secs(20)), None);
+        assert_eq!(lagged.advance("2".into(), t0 + Duration::from_secs(31)).as_deref(), Some("1"));
+        // Nothing new is old enough yet, and "1" is not saved twice.
+        assert_eq!(lagged.advance("3".into(), t0 + Duration::from_secs(40)), None);
+        assert_eq!(lagged.advance("3".into(), t0 + Duration::from_secs(75)).as_deref(), Some("3"));
+        assert_eq!(lagged.advance("3".into(), t0 + Duration::from_secs(200)), None);
+    }
+
+    #[test]
+    fn cursor_store_round_trips() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("state").join("watch-cursors.json");
+        let store = CursorStore::open(path.clone());
+        assert_eq!(store.get(Path::new("/data")), None);
+        store.set(Path::new("/data"), "42".into());
+        store.set(Path::new("/other"), "7".into());
+
+        let reopened = CursorStore::open(path);
+        assert_eq!(reopened.get(Path::new("/data")).as_deref(), Some("42"));
+        assert_eq!(reopened.get(Path::new("/other")).as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn unreadable_store_starts_empty() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("watch-cursors.json");
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(CursorStore::open(path).get(Path::new("/data")), None);
+    }
+}
@@ -0,0 +1,368 @@
+//! NTFS USN change journal backend.
+//!
+//! Reads the volume's update sequence number journal once a second.  Records
+//! carry a file name and the reference number of its parent directory, which
+//! is resolved to a path with `OpenFileById`; records outside the source
+//! root are dropped.  Opening the volume requires administrator rights (the
+//! Windows service runs as LocalSystem).
+//!
+//! The position is `<journal id>:<next usn>`.  When the journal was
+//! recreated or has wrapped past the saved position the backend starts from
+//! the current end, and the changes in between are left to the next
+//! scheduled scan.
+
+use std::collections::HashMap;
+use std::ffi::{c_void, OsString};
+use std::io;
+use std::mem::size_of;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind, RenameMode};
+use notify::{Event, EventKind};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+use windows_sys::Win32::Foundation::{CloseHandle, GENERIC_READ, HANDLE, INVALID_HANDLE_VALUE};
+use windows_sys::Win32::Storage::FileSystem::{
+    CreateFileW, FileIdType, GetFinalPathNameByHandleW, OpenFileById, FILE_ATTRIBUTE_DIRECTORY,
+    FILE_FLAG_BACKUP_SEMANTICS, FILE_ID_DESCRIPTOR, FILE_ID_DESCRIPTOR_0, FILE_NAME_NORMALIZED,
+    FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+};
+use windows_sys::Win32::System::Ioctl::{
+    FSCTL_QUERY_USN_JOURNAL, FSCTL_READ_USN_JOURNAL, READ_USN_JOURNAL_DATA_V0, USN_JOURNAL_DATA_V0,
+    USN_REASON_CLOSE, USN_REASON_DATA_EXTEND, USN_REASON_DATA_OVERWRITE, USN_REASON_DATA_TRUNCATION,
+    USN_REASON_FILE_CREATE, USN_REASON_FILE_DELETE, USN_REASON_RENAME_NEW_NAME,
+    USN_REASON_RENAME_OLD_NAME, USN_RECORD_V2,
+};
+use windows_sys::Win32::System::IO::DeviceIoControl;
+
+use super::Position;
+
+const READ_INTERVAL: Duration = Duration::from_secs(1);
+/// Bytes read from the journal per call (a `u64` buffer keeps records aligned).
+const BUFFER_WORDS: usize = 8 * 1024;
+/// Directory paths cached by reference number before the cache is reset.
+const MAX_CACHED_DIRS: usize = 100_000;
+
+const DATA_REASONS: u32 = USN_REASON_DATA_OVERWRITE | USN_REASON_DATA_EXTEND | USN_REASON_DATA_TRUNCATION;
+const REASONS: u32 = DATA_REASONS
+    | USN_REASON_FILE_CREATE
+    | USN_REASON_FILE_DELETE
+    | USN_REASON_RENAME_OLD_NAME
+    | USN_REASON_RENAME_NEW_NAME
+    | USN_REASON_CLOSE;
+
+pub fn start(root: &Path, since: Option<&str>, tx: mpsc::Sender<notify::Result<Event>>) -> Result<Position> {
+    let mut journal = Journal::open(root, since)?;
+    let position = Arc::new(Mutex::new(journal.cursor()));
+    let shared = position.clone();
+    std::thread::Builder::new()
+        .name("usn-journal".into())
+        .spawn(move || loop {
+            match journal.read() {
+                Ok(events) => {
+                    for ev in events {
+                        if tx.blocking_send(Ok(ev)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("USN journal for {:?}: {e:#}; continuing from the current end", journal.root);
+                    if let Err(e) = journal.restart() {
+                        warn!("USN journal for {:?} is unavailable: {e:#}", journal.root);
+                        return;
+                    }
+                }
+            }
+            *shared.lock().unwrap_or_else(|e| e.into_inner()) = journal.cursor();
+            if tx.is_closed() {
+                return;
+            }
+            std::thread::sleep(READ_INTERVAL);
+        })
+        .context("starting the USN journal reader")?;
+    Ok(position)
+}
+
+struct Volume(HANDLE);
+
+// The handle is only used from the reader thread after it is opened.
+unsafe impl Send for Volume {}
+
+impl Drop for Volume {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+struct Journal {
+    /// Source root as configured; emitted paths are built on it.
+    root: PathBuf,
+    /// Final (resolved) path of the root, lowercased, without `\\?\`.
+    root_key: String,
+    volume: Volume,
+    journal_id: u64,
+    next_usn: i64,
+    /// Directory reference number → path, `None` for directories that could
+    /// not be opened.
+    dirs: HashMap<u64, Option<PathBuf>>,
+    buf: Vec<u64>,
+}
+
+impl Journal {
+    fn open(root: &Path, since: Option<&str>) -> Result<Self> {
+        let canonical = std::fs::canonicalize(root)
+            .with_context(|| format!("resolving {}", root.display()))?;
+        let drive = match canonical.components().next() {
+            Some(Component::Prefix(p)) => match p.kind() {
+                Prefix::VerbatimDisk(d) | Prefix::Disk(d) => d as char,
+                _ => bail!("{} is not on a local drive", root.display()),
+            },
+            _ => bail!("{} is not on a local drive", root.display()),
+        };
+        let volume_name = format!(r"\\.\{drive}:");
+        let wide: Vec<u16> = std::ffi::OsStr::new(&volume_name).encode_wide().chain([0]).collect();
+        let handle = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                GENERIC_READ,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error())
+                .with_context(|| format!("opening volume {volume_name} (reading the USN journal requires administrator rights)"));
+        }
+        let volume = Volume(handle);
+        let data = query(&volume).with_context(|| format!("querying the USN journal on {volume_name}"))?;
+
+        let saved = since.and_then(parse_cursor);
+        let next_usn = match saved {
+            Some((id, usn)) if id == data.UsnJournalID && usn >= data.FirstUsn && usn <= data.NextUsn => {
+                info!("USN journal for {:?}: resuming from {usn}", root);
+                usn
+            }
+            Some(_) => {
+                warn!(
+                    "USN journal on {volume_name} was recreated or has wrapped since find-watch last ran; \
+                     changes made meanwhile are picked up by the next scan"
+                );
+                data.NextUsn
+            }
+            None => data.NextUsn,
+        };
+
+        Ok(Journal {
+            root: root.to_path_buf(),
+            root_key: strip_verbatim(&canonical.to_string_lossy()).trim_end_matches('\\').to_ascii_lowercase(),
+            volume,
+            journal_id: data.UsnJournalID,
+            next_usn,
+            dirs: HashMap::new(),
+            buf: vec![0; BUFFER_WORDS],
+        })
+    }
+
+    fn cursor(&self) -> String {
+        format!("{:x}:{}", self.journal_id, self.next_usn)
+    }
+
+    /// Re-query the journal and continue from its current end.
+    fn restart(&mut self) -> Result<()> {
+        let data = query(&self.volume)?;
+        self.journal_id = data.UsnJournalID;
+        self.next_usn = data.NextUsn;
+        self.dirs.clear();
+        Ok(())
+    }
+
+    /// Read every record written since the last call.
+    fn read(&mut self) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
+        loop {
+            let request = READ_USN_JOURNAL_DATA_V0 {
+                StartUsn: self.next_usn,
+                ReasonMask: REASONS,
+                ReturnOnlyOnClose: 0,
+                Timeout: 0,
+                BytesToWaitFor: 0,
+                UsnJournalID: self.journal_id,
+            };
+            let mut returned = 0u32;
+            let ok = unsafe {
+                DeviceIoControl(
+                    self.volume.0,
+                    FSCTL_READ_USN_JOURNAL,
+                    &request as *const _ as *const c_void,
+                    size_of::<READ_USN_JOURNAL_DATA_V0>() as u32,
+                    self.buf.as_mut_ptr() as *mut c_void,
+                    (self.buf.len() * 8) as u32,
+                    &mut returned,
+                    std::ptr::null_mut(),
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error()).context("reading the USN journal");
+            }
+            let returned = returned as usize;
+            if returned < 8 {
+                break;
+            }
+            // The output starts with the USN to continue from, then records.
+            let next = self.buf[0] as i64;
+            let mut offset = 8;
+            while offset + size_of::<USN_RECORD_V2>() <= returned {
+                let base = unsafe { (self.buf.as_ptr() as *const u8).add(offset) };
+                let record = unsafe { &*(base as *const USN_RECORD_V2) };
+                let len = record.RecordLength as usize;
+                if len == 0 || offset + len > returned {
+                    break;
+                }
+                if record.MajorVersion == 2 {
+                    let name = unsafe {
+                        std::slice::from_raw_parts(
+                            base.add(record.FileNameOffset as usize) as *const u16,
+                            record.FileNameLength as usize / 2,
+                        )
+                    };
+                    let name = OsString::from_wide(name);
+                    let (reason, attrs, parent) = (record.Reason, record.FileAttributes, record.ParentFileReferenceNumber);
+                    self.record(reason, attrs, parent, name, &mut events);
+                }
+                offset += len;
+            }
+            let advanced = next != self.next_usn;
+            self.next_usn = next;
+            if !advanced || returned == 8 {
+                break;
+            }
+        }
+        Ok(events)
+    }
+
+    fn record(&mut self, reason: u32, attrs: u32, parent: u64, name: OsString, events: &mut Vec<Event>) {
+        let is_dir = attrs & FILE_ATTRIBUTE_DIRECTORY != 0;
+        if is_dir && reason & (USN_REASON_RENAME_NEW_NAME | USN_REASON_FILE_DELETE) != 0 {
+            // Cached paths below this directory are stale now.
+            self.dirs.clear();
+        }
+        let Some(kind) = event_kind(reason, is_dir) else { return };
+        let Some(dir) = self.dir_path(parent) else { return };
+        let Some(path) = self.within_root(&dir.join(name)) else { return };
+        events.push(Event::new(kind).add_path(path));
+    }
+
+    fn dir_path(&mut self, frn: u64) -> Option<PathBuf> {
+        if let Some(p) = self.dirs.get(&frn) {
+            return p.clone();
+        }
+        if self.dirs.len() >= MAX_CACHED_DIRS {
+            self.dirs.clear();
+        }
+        let path = path_by_id(&self.volume, frn);
+        self.dirs.insert(frn, path.clone());
+        path
+    }
+
+    /// `path` re-based on the configured root, or `None` outside it.
+    fn within_root(&self, path: &Path) -> Option<PathBuf> {
+        let s = path.to_string_lossy();
+        let s = strip_verbatim(&s);
+        if s.len() < self.root_key.len() || !s.is_char_boundary(self.root_key.len()) {
+            return None;
+        }
+        let (head, rest) = s.split_at(self.root_key.len());
+        if !head.eq_ignore_ascii_case(&self.root_key) || !(rest.is_empty() || rest.starts_with('\\')) {
+            return None;
+        }
+        Some(self.root.join(rest.trim_start_matches('\\')))
+    }
+}
+
+fn event_kind(reason: u32, is_dir: bool) -> Option<EventKind> {
+    Some(if reason & USN_REASON_FILE_DELETE != 0 {
+        EventKind::Remove(if is_dir { RemoveKind::Folder } else { RemoveKind::File })
+    } else if reason & USN_REASON_RENAME_OLD_NAME != 0 {
+        EventKind::Modify(ModifyKind::Name(RenameMode::From))
+    } else if reason & USN_REASON_RENAME_NEW_NAME != 0 {
+        EventKind::Modify(ModifyKind::Name(RenameMode::To))
+    } else if reason & USN_REASON_FILE_CREATE != 0 {
+        EventKind::Create(if is_dir { CreateKind::Folder } else { CreateKind::File })
+    } else if reason & DATA_REASONS != 0 && !is_dir {
+        EventKind::Modify(ModifyKind::Data(DataChange::Content))
+    } else {
+        return None;
+    })
+}
+
+fn query(volume: &Volume) -> Result<USN_JOURNAL_DATA_V0> {
+    let mut data: USN_JOURNAL_DATA_V0 = unsafe { std::mem::zeroed() };
+    let mut returned = 0u32;
+    let ok = unsafe {
+        DeviceIoControl(
+            volume.0,
+            FSCTL_QUERY_USN_JOURNAL,
+            std::ptr::null(),
+            0,
+            &mut data as *mut _ as *mut c_void,
+            size_of::<USN_JOURNAL_DATA_V0>() as u32,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error()).context("the volume has no active USN journal (is it NTFS?)");
+    }
+    Ok(data)
+}
+
+/// Path of the file or directory with reference number `frn`.
+fn path_by_id(volume: &Volume, frn: u64) -> Option<PathBuf> {
+    let descriptor = FILE_ID_DESCRIPTOR {
+        dwSize: size_of::<FILE_ID_DESCRIPTOR>() as u32,
+        Type: FileIdType,
+        Anonymous: FILE_ID_DESCRIPTOR_0 { FileId: frn as i64 },
+    };
+    let handle = unsafe {
+        OpenFileById(
+            volume.0,
+            &descriptor,
+            FILE_READ_ATTRIBUTES,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            std::ptr::null(),
+            FILE_FLAG_BACKUP_SEMANTICS,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return None;
+    }
+    let file = Volume(handle);
+    let mut buf = vec![0u16; 512];
+    loop {
+        let n = unsafe { GetFinalPathNameByHandleW(file.0, buf.as_mut_ptr(), buf.len() as u32, FILE_NAME_NORMALIZED) } as usize;
+        if n == 0 {
+            return None;
+        }
+        if n < buf.len() {
+            return Some(PathBuf::from(OsString::from_wide(&buf[..n])));
+        }
+        buf.resize(n + 1, 0);
+    }
+}
+
+fn parse_cursor(s: &str) -> Option<(u64, i64)> {
+    let (id, usn) = s.split_once(':')?;
+    Some((u64::from_str_radix(id, 16).ok()?, usn.parse().ok()?))
+}
+
+fn strip_verbatim(s: &str) -> &str {
+    s.strip_prefix(r"\\?\").unwrap_or(s)
+}
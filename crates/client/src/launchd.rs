@@ -0,0 +1,169 @@
+//! `find-watch install` / `uninstall` on macOS: a per-user LaunchAgent.
+//!
+//! The agent starts at login, is restarted by launchd if the watcher exits
+//! with an error (no more often than every `ThrottleInterval` seconds), runs
+//! as a background process with low-priority I/O, and sends its output to
+//! `~/Library/Logs/find-watch.log`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+/// Default launchd label; also the plist's file name.
+pub const DEFAULT_LABEL: &str = "com.jamietre.find-watch";
+
+/// What goes into the agent's plist.
+pub struct Agent {
+    pub label: String,
+    pub program: PathBuf,
+    pub config_path: PathBuf,
+    pub log_path: PathBuf,
+    /// Minimum seconds between restarts after a failure.
+    pub throttle_secs: u32,
+}
+
+impl Agent {
+    pub fn plist(&self) -> String {
+        let s = |p: &Path| xml_escape(&p.to_string_lossy());
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{label}</string>
+  <key>ProgramArguments</key>
+  <array>
+    <string>{program}</string>
+    <string>--config</string>
+    <string>{config}</string>
+  </array>
+  <key>RunAtLoad</key>
+  <true/>
+  <key>KeepAlive</key>
+  <dict>
+    <key>SuccessfulExit</key>
+    <false/>
+  </dict>
+  <key>ThrottleInterval</key>
+  <integer>{throttle}</integer>
+  <key>ProcessType</key>
+  <string>Background</string>
+  <key>LowPriorityIO</key>
+  <true/>
+  <key>StandardOutPath</key>
+  <string>{log}</string>
+  <key>StandardErrorPath</key>
+  <string>{log}</string>
+</dict>
+</plist>
+"#,
+            label = xml_escape(&self.label),
+            program = s(&self.program),
+            config = s(&self.config_path),
+            throttle = self.throttle_secs,
+            log = s(&self.log_path),
+        )
+    }
+}
+
+/// `~/Library/LaunchAgents/<label>.plist`.
+pub fn plist_path(label: &str) -> Result<PathBuf> {
+    Ok(home()?.join("Library/LaunchAgents").join(format!("{label}.plist")))
+}
+
+/// `~/Library/Logs/find-watch.log`.
+pub fn default_log_path() -> Result<PathBuf> {
+    Ok(home()?.join("Library/Logs/find-watch.log"))
+}
+
+/// Write the agent's plist and (re)load it into the user's launchd domain,
+/// which starts the watcher.
+pub fn install(agent: &Agent) -> Result<()> {
+    let plist = plist_path(&agent.label)?;
+    if let Some(dir) = plist.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    if let Some(dir) = agent.log_path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let domain = gui_domain()?;
+    // Replacing an installed agent: unload the old definition first.
+    let _ = launchctl(&["bootout", &format!("{domain}/{}", agent.label)]);
+    std::fs::write(&plist, agent.plist()).with_context(|| format!("writing {}", plist.display()))?;
+    launchctl(&["bootstrap", &domain, &plist.to_string_lossy()])?;
+
+    println!("Installed LaunchAgent {} ({})", agent.label, plist.display());
+    println!("  Status:  launchctl print {domain}/{}", agent.label);
+    println!("  Logs:    tail -f {}", agent.log_path.display());
+    Ok(())
+}
+
+/// Stop the agent and remove its plist.
+pub fn uninstall(label: &str) -> Result<()> {
+    let plist = plist_path(label)?;
+    let loaded = launchctl(&["bootout", &format!("{}/{label}", gui_domain()?)]).is_ok();
+    let existed = match std::fs::remove_file(&plist) {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(e).with_context(|| format!("removing {}", plist.display())),
+    };
+    if !loaded && !existed {
+        anyhow::bail!("LaunchAgent {label} is not installed");
+    }
+    println!("Uninstalled LaunchAgent {label}");
+    Ok(())
+}
+
+fn launchctl(args: &[&str]) -> Result<()> {
+    let out = Command::new("launchctl").args(args).output().context("running launchctl")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "launchctl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// `gui/<uid>`, the launchd domain of the logged-in user's agents.
+fn gui_domain() -> Result<String> {
+    let out = Command::new("id").arg("-u").output().context("running id -u")?;
+    let uid = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if !out.status.success() || uid.is_empty() {
+        anyhow::bail!("could not determine the current user id");
+    }
+    Ok(format!("gui/{uid}"))
+}
+
+fn home() -> Result<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from).context("HOME is not set")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plist_has_keepalive_throttle_and_logs() {
+        let agent = Agent {
+            label: DEFAULT_LABEL.to_string(),
+            program: PathBuf::from("/usr/local/bin/find-watch"),
+            config_path: PathBuf::from("/Users/a&b/.config/find-anything/client.toml"),
+            log_path: PathBuf::from("/Users/ab/Library/Logs/find-watch.log"),
+            throttle_secs: 30,
+        };
+        let plist = agent.plist();
+        assert!(plist.contains("<string>com.jamietre.find-watch</string>"));
+        assert!(plist.contains("<string>/Users/a&amp;b/.config/find-anything/client.toml</string>"));
+        assert!(plist.contains("<key>SuccessfulExit</key>\n    <false/>"));
+        assert!(plist.contains("<key>ThrottleInterval</key>\n  <integer>30</integer>"));
+        assert_eq!(plist.matches("<string>/Users/ab/Library/Logs/find-watch.log</string>").count(), 2);
+    }
+}
@@ -1,10 +1,27 @@
-pub mod api;
-pub mod batch;
+pub use find_client_lib::{api, batch, pause, spool, throttle};
+
+pub mod checkpoint;
+pub mod cloud;
+pub mod cloud_auth;
+pub mod completions;
+pub mod control;
+pub mod editor;
 pub mod extract;
+pub mod extract_cache;
+pub mod git_history;
+pub mod ignore_files;
+pub mod ingest_view;
+pub mod journal;
 pub mod lazy_header;
+pub mod mcp;
+pub mod mount_check;
 pub mod path_util;
+pub mod poll;
+pub mod remote;
+pub mod s3;
 pub mod scan;
 pub mod subprocess;
 pub mod upload;
+pub mod verify;
 pub mod walk;
 pub mod watch;
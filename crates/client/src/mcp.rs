@@ -0,0 +1,393 @@
+//! `find-mcp`: a Model Context Protocol server, so AI assistants and IDE
+//! agents can query the index directly.
+//!
+//! Speaks JSON-RPC 2.0 over stdio, one message per line.  The tools are
+//! `list_sources`, `search`, `get_file` and `get_context`; each returns its
+//! result as a JSON text block.  Results are paged (`offset`/`limit`, with
+//! `next_offset` set while there is more) and can be confined to a set of
+//! sources with `find-mcp --source`.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::api::{ApiClient, SearchFilters};
+
+/// Protocol revisions this server speaks, newest first.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+const DEFAULT_FILE_LINES: usize = 200;
+const MAX_FILE_LINES: usize = 1000;
+const DEFAULT_CONTEXT_WINDOW: usize = 5;
+const MAX_CONTEXT_WINDOW: usize = 50;
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub struct McpServer {
+    api: ApiClient,
+    /// Sources the assistant may see.  Empty = all of them.
+    allowed_sources: Vec<String>,
+}
+
+impl McpServer {
+    pub fn new(api: ApiClient, allowed_sources: Vec<String>) -> Self {
+        Self { api, allowed_sources }
+    }
+
+    /// Handle one line from the client.  Returns the response to write back,
+    /// or `None` for notifications (and stray responses), which get none.
+    pub async fn handle_message(&self, line: &str) -> Option<Value> {
+        let msg: Value = match serde_json::from_str(line) {
+            Ok(msg) => msg,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, format!("parse error: {e}"))),
+        };
+        let method = msg.get("method").and_then(Value::as_str)?;
+        let id = msg.get("id").cloned()?;
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("method not found: {method}"))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    /// `tools/call`.  An unknown tool is a protocol error; a tool that fails
+    /// reports it in its result (`isError`) so the assistant can see why.
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+        let args = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+        let output = match name {
+            "list_sources" => self.list_sources().await,
+            "search" => self.search(args).await,
+            "get_file" => self.get_file(args).await,
+            "get_context" => self.get_context(args).await,
+            _ => return Err((INVALID_PARAMS, format!("unknown tool: {name}"))),
+        };
+        let (text, is_error) = match output {
+            Ok(value) => (value.to_string(), false),
+            Err(e) => (format!("{e:#}"), true),
+        };
+        Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+    }
+
+    async fn list_sources(&self) -> Result<Value> {
+        let sources = self.api.get_sources().await?;
+        let names: Vec<String> = sources.into_iter().map(|s| s.name).filter(|s| self.allows(s)).collect();
+        Ok(json!({ "sources": names }))
+    }
+
+    async fn search(&self, args: Value) -> Result<Value> {
+        let args: SearchArgs = serde_json::from_value(args).context("invalid arguments")?;
+        let sources = self.scope(&args.sources)?;
+        let limit = args.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+        let filters = SearchFilters {
+            kinds: args.kinds,
+            exts: args.exts,
+            path_prefix: args.path_prefix,
+            ..Default::default()
+        };
+        let resp = self.api.search(&args.query, &args.mode, &sources, limit, args.offset, &filters).await?;
+        let results: Vec<Value> = resp
+            .results
+            .iter()
+            .map(|r| {
+                json!({
+                    "source": r.source,
+                    "path": r.path,
+                    "archive_path": r.archive_path,
+                    "line": r.line_number,
+                    "snippet": r.snippet,
+                    "kind": r.kind,
+                    "mtime": r.mtime,
+                })
+            })
+            .collect();
+        let next = args.offset + resp.results.len();
+        Ok(json!({
+            "total": resp.total,
+            "offset": args.offset,
+            "next_offset": (next < resp.total && !resp.results.is_empty()).then_some(next),
+            "timed_out": resp.timed_out,
+            "results": results,
+        }))
+    }
+
+    async fn get_file(&self, args: Value) -> Result<Value> {
+        let args: FileArgs = serde_json::from_value(args).context("invalid arguments")?;
+        self.check_source(&args.source)?;
+        let limit = args.limit.unwrap_or(DEFAULT_FILE_LINES).clamp(1, MAX_FILE_LINES);
+        let resp = self
+            .api
+            .get_file(&args.source, &args.path, args.archive_path.as_deref(), args.offset, limit)
+            .await?;
+        // Line numbers are sequential from the offset unless the server says otherwise.
+        let lines: Vec<Value> = resp
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let line = resp.line_offsets.get(i).copied().unwrap_or(args.offset + i + 1);
+                json!({ "line": line, "text": text })
+            })
+            .collect();
+        let next = args.offset + resp.lines.len();
+        Ok(json!({
+            "source": args.source,
+            "path": args.path,
+            "kind": resp.file_kind,
+            "total_lines": resp.total_lines,
+            "offset": args.offset,
+            "next_offset": (next < resp.total_lines && !resp.lines.is_empty()).then_some(next),
+            "metadata": resp.metadata,
+            "indexing_error": resp.indexing_error,
+            "content_unavailable": resp.content_unavailable,
+            "lines": lines,
+        }))
+    }
+
+    async fn get_context(&self, args: Value) -> Result<Value> {
+        let args: ContextArgs = serde_json::from_value(args).context("invalid arguments")?;
+        self.check_source(&args.source)?;
+        let window = args.window.unwrap_or(DEFAULT_CONTEXT_WINDOW).min(MAX_CONTEXT_WINDOW);
+        let resp = self
+            .api
+            .context(&args.source, &args.path, args.archive_path.as_deref(), args.line, window)
+            .await?;
+        let lines: Vec<Value> = resp
+            .lines
+            .iter()
+            .map(|l| json!({ "line": l.line_number, "text": l.content }))
+            .collect();
+        Ok(json!({
+            "source": args.source,
+            "path": args.path,
+            "kind": resp.kind,
+            "match_line": resp.match_index.and_then(|i| resp.lines.get(i)).map(|l| l.line_number),
+            "lines": lines,
+        }))
+    }
+
+    fn allows(&self, source: &str) -> bool {
+        self.allowed_sources.is_empty() || self.allowed_sources.iter().any(|s| s == source)
+    }
+
+    fn check_source(&self, source: &str) -> Result<()> {
+        if !self.allows(source) {
+            bail!("source {source:?} is not available; use list_sources to see the sources you can search");
+        }
+        Ok(())
+    }
+
+    /// The sources a search covers: the requested ones, each of which must be
+    /// allowed, or every allowed source when none were requested.
+    fn scope(&self, requested: &[String]) -> Result<Vec<String>> {
+        for source in requested {
+            self.check_source(source)?;
+        }
+        Ok(if requested.is_empty() { self.allowed_sources.clone() } else { requested.to_vec() })
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    query: String,
+    #[serde(default = "default_mode")]
+    mode: String,
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    kinds: Vec<String>,
+    #[serde(default)]
+    exts: Vec<String>,
+    path_prefix: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_mode() -> String {
+    "fuzzy".to_string()
+}
+
+#[derive(Deserialize)]
+struct FileArgs {
+    source: String,
+    path: String,
+    archive_path: Option<String>,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ContextArgs {
+    source: String,
+    path: String,
+    archive_path: Option<String>,
+    line: usize,
+    window: Option<usize>,
+}
+
+/// Agree on the client's protocol revision if we speak it, else offer ours.
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "find-anything", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Search the find-anything index of the user's files. Use search to find \
+            matches, then get_context or get_file to read around them. Results are paged: pass \
+            next_offset back as offset to continue.",
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "list_sources",
+            "description": "List the indexed sources that can be searched.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "search",
+            "description": "Search indexed file contents and names. Returns one result per file \
+                with the best matching line; page with offset/next_offset.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search terms or pattern" },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["fuzzy", "exact", "regex", "document", "doc-exact", "doc-regex",
+                                 "file-fuzzy", "file-exact", "file-regex"],
+                        "description": "Matching mode (default fuzzy). document modes match terms \
+                            anywhere in a file; file modes match file names.",
+                    },
+                    "sources": { "type": "array", "items": { "type": "string" }, "description": "Only these sources" },
+                    "kinds": { "type": "array", "items": { "type": "string" }, "description": "Only these file kinds, e.g. pdf, code" },
+                    "exts": { "type": "array", "items": { "type": "string" }, "description": "Only these extensions, e.g. rs, md" },
+                    "path_prefix": { "type": "string", "description": "Only files under this directory" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_SEARCH_LIMIT, "default": DEFAULT_SEARCH_LIMIT },
+                    "offset": { "type": "integer", "minimum": 0, "default": 0 },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "get_file",
+            "description": "Read the indexed text of a file (or archive member), a page of lines at a time.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source": { "type": "string" },
+                    "path": { "type": "string", "description": "Path as returned by search" },
+                    "archive_path": { "type": "string", "description": "Member path inside an archive, if any" },
+                    "offset": { "type": "integer", "minimum": 0, "default": 0, "description": "Index of the first line to return" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_FILE_LINES, "default": DEFAULT_FILE_LINES },
+                },
+                "required": ["source", "path"],
+            },
+        },
+        {
+            "name": "get_context",
+            "description": "Read the lines around one line of a file, e.g. a search match.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source": { "type": "string" },
+                    "path": { "type": "string" },
+                    "archive_path": { "type": "string" },
+                    "line": { "type": "integer", "minimum": 0 },
+                    "window": { "type": "integer", "minimum": 0, "maximum": MAX_CONTEXT_WINDOW, "default": DEFAULT_CONTEXT_WINDOW, "description": "Lines either side" },
+                },
+                "required": ["source", "path", "line"],
+            },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(allowed: &[&str]) -> McpServer {
+        // Nothing listens here; these tests never reach the network.
+        let api = ApiClient::new("http://127.0.0.1:9", "token");
+        McpServer::new(api, allowed.iter().map(|s| s.to_string()).collect())
+    }
+
+    async fn call(server: &McpServer, msg: Value) -> Option<Value> {
+        server.handle_message(&msg.to_string()).await
+    }
+
+    #[tokio::test]
+    async fn initialize_and_list_tools() {
+        let s = server(&[]);
+        let resp = call(&s, json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": { "protocolVersion": "2024-11-05", "capabilities": {} } })).await.unwrap();
+        assert_eq!(resp["id"], 1);
+        assert_eq!(resp["result"]["protocolVersion"], "2024-11-05");
+        assert!(resp["result"]["capabilities"]["tools"].is_object());
+
+        // Notifications get no reply.
+        assert!(call(&s, json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await.is_none());
+
+        let resp = call(&s, json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" })).await.unwrap();
+        let names: Vec<&str> = resp["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["list_sources", "search", "get_file", "get_context"]);
+    }
+
+    #[tokio::test]
+    async fn protocol_errors() {
+        let s = server(&[]);
+        let resp = s.handle_message("{not json").await.unwrap();
+        assert_eq!(resp["error"]["code"], PARSE_ERROR);
+
+        let resp = call(&s, json!({ "jsonrpc": "2.0", "id": "a", "method": "resources/list" })).await.unwrap();
+        assert_eq!((resp["id"].as_str(), resp["error"]["code"].as_i64()), (Some("a"), Some(METHOD_NOT_FOUND)));
+
+        let resp = call(&s, json!({ "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+            "params": { "name": "delete_everything" } })).await.unwrap();
+        assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn sources_outside_the_scope_are_refused() {
+        let s = server(&["docs"]);
+        for (tool, args) in [
+            ("search", json!({ "query": "x", "sources": ["code"] })),
+            ("get_file", json!({ "source": "code", "path": "a.rs" })),
+            ("get_context", json!({ "source": "code", "path": "a.rs", "line": 3 })),
+        ] {
+            let resp = call(&s, json!({ "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                "params": { "name": tool, "arguments": args } })).await.unwrap();
+            assert_eq!(resp["result"]["isError"], true, "{tool}");
+            assert!(resp["result"]["content"][0]["text"].as_str().unwrap().contains("not available"));
+        }
+        assert_eq!(s.scope(&[]).unwrap(), ["docs"]);
+    }
+}
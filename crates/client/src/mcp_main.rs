@@ -0,0 +1,63 @@
+mod completions;
+mod mcp;
+
+use anyhow::{Context, Result};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use find_client_lib::{api, load_config};
+use find_common::config::default_config_path;
+
+#[derive(Parser)]
+#[command(
+    name = "find-mcp",
+    about = "Serve the find-anything index to AI assistants over the Model Context Protocol (stdio)",
+    version
+)]
+struct Args {
+    /// Only expose this source (repeatable; default: all sources)
+    #[arg(long = "source")]
+    sources: Vec<String>,
+
+    /// Path to client config file (default: /etc/find-anything/client.toml as root, else ~/.config/find-anything/client.toml)
+    #[arg(long)]
+    config: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // stdout carries the protocol, so logs go to stderr.
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "warn,find_mcp=info".into()),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    completions::handle(Args::command());
+    let args = Args::from_arg_matches(&Args::command().version(find_common::tool_version!()).get_matches()).unwrap_or_else(|e| e.exit());
+
+    let config_path = args.config.unwrap_or_else(default_config_path);
+    let (config, config_warnings) = load_config(&config_path)?;
+    for w in &config_warnings { eprintln!("Warning: {w}"); }
+
+    let client = api::ApiClient::new(&config.server.url, &config.server.token);
+    client.check_server_version().await?;
+    let server = mcp::McpServer::new(client, args.sources);
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await.context("reading stdin")? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_message(&line).await {
+            let mut out = response.to_string();
+            out.push('\n');
+            stdout.write_all(out.as_bytes()).await.context("writing stdout")?;
+            stdout.flush().await.context("writing stdout")?;
+        }
+    }
+    Ok(())
+}
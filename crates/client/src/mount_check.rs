@@ -0,0 +1,87 @@
+//! Guard against scanning a source whose volume is not mounted.
+//!
+//! An unmounted network share usually leaves an empty directory behind (the
+//! mount point), or nothing at all.  Scanning it would report every indexed
+//! file as deleted, so `find-scan` checks the source root first and skips
+//! the source when it looks unmounted:
+//!
+//! - the root is missing, not a directory, or cannot be listed;
+//! - `require_file` is set and that file is missing under the root;
+//! - `require_mount` is set and the root is on the same device as its
+//!   parent directory (Unix only).
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use find_common::config::SourceConfig;
+
+/// Check that `source`'s root is reachable and, as far as its settings
+/// allow telling, mounted.  The error explains why the scan must not run.
+pub fn check_source_root(source: &SourceConfig) -> Result<()> {
+    check_root(Path::new(&source.path), source.require_file.as_deref(), source.require_mount)
+}
+
+fn check_root(root: &Path, require_file: Option<&str>, require_mount: bool) -> Result<()> {
+    if !root.is_dir() {
+        bail!("source path {} does not exist or is not a directory; is the volume mounted?", root.display());
+    }
+    if let Err(e) = std::fs::read_dir(root) {
+        bail!("cannot list source path {}: {e}; is the volume mounted?", root.display());
+    }
+    if let Some(file) = require_file {
+        if !root.join(file).exists() {
+            bail!("{file} not found in {} (require_file); is the volume mounted?", root.display());
+        }
+    }
+    if require_mount && !is_mount_point(root)? {
+        bail!("source path {} is not a mount point (require_mount); is the volume mounted?", root.display());
+    }
+    Ok(())
+}
+
+/// Whether `path` is on a different device from its parent, or is the
+/// filesystem root.
+#[cfg(unix)]
+fn is_mount_point(path: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let here = std::fs::metadata(path)?;
+    let parent = std::fs::metadata(path.join(".."))?;
+    Ok(here.dev() != parent.dev() || here.ino() == parent.ino())
+}
+
+#[cfg(not(unix))]
+fn is_mount_point(_path: &Path) -> Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_root_is_refused() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let err = check_root(&dir.path().join("gone"), None, false).unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn require_file_must_exist() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(check_root(dir.path(), Some(".find-anything"), false).is_err());
+        std::fs::write(dir.path().join(".find-anything"), "").unwrap();
+        check_root(dir.path(), Some(".find-anything"), false).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn require_mount_rejects_plain_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let sub = dir.path().join("share");
+        std::fs::create_dir(&sub).unwrap();
+        assert!(check_root(&sub, None, true).is_err());
+        check_root(Path::new("/"), None, true).unwrap();
+    }
+}
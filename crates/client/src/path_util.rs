@@ -33,6 +33,46 @@ pub fn normalise_root(s: &str) -> String {
     s.to_string()
 }
 
+/// Normalise a user-supplied subdirectory of a source root (`find-scan
+/// --path`) to the forward-slash relative form stored by the server:
+/// `"./docs//2024/"` → `"docs/2024"`.  The source root itself yields `""`.
+///
+/// Returns `None` for absolute paths and paths containing `..`.
+#[allow(dead_code)] // used by find-scan only
+pub fn normalise_subdir(s: &str) -> Option<String> {
+    let s = normalise_path_sep(s);
+    if s.starts_with('/') || std::path::Path::new(&s).has_root() {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for part in s.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            p => parts.push(p),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Whether the indexed `path` is a history path of a repository in the
+/// source: `<repo>@…` where `<repo>` is still a git work tree.  `find-scan`
+/// keeps these instead of deleting them as missing files.
+#[allow(dead_code)] // used by find-scan and find-admin verify
+pub fn is_history_path(paths: &[String], path: &str) -> bool {
+    path.match_indices('@').any(|(i, _)| {
+        let repo = &path[..i];
+        i + 1 < path.len() && paths.iter().map(std::path::Path::new).any(|root| {
+            let dir = if root.file_name().is_some_and(|n| n.to_string_lossy() == repo) {
+                root.to_path_buf()
+            } else {
+                root.join(repo)
+            };
+            dir.join(".git").exists()
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,6 +94,19 @@ mod tests {
         assert_eq!(normalise_path_sep(composite), composite);
     }
 
+    #[test]
+    fn normalise_subdir_strips_redundant_components() {
+        assert_eq!(normalise_subdir("docs/2024").as_deref(), Some("docs/2024"));
+        assert_eq!(normalise_subdir("./docs//2024/").as_deref(), Some("docs/2024"));
+        assert_eq!(normalise_subdir(".").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn normalise_subdir_rejects_escapes() {
+        assert_eq!(normalise_subdir("/etc"), None);
+        assert_eq!(normalise_subdir("docs/../../etc"), None);
+    }
+
     #[cfg(not(windows))]
     #[test]
     fn normalise_root_unix_no_change() {
@@ -0,0 +1,416 @@
+//! Polling watch backend for sources on network filesystems.
+//!
+//! inotify, FSEvents and ReadDirectoryChangesW only see changes made through
+//! the local kernel, so edits made on the server side of an SMB/CIFS or NFS
+//! mount never produce events.  For those sources `find-watch` polls instead:
+//! every `[watch] poll_interval_secs` it compares the tree with the previous
+//! pass and feeds the differences into the event loop as synthetic `notify`
+//! events, so batching, filtering and indexing are shared with the
+//! event-driven backend.
+//!
+//! Directory mtimes change when entries are added, removed or renamed, so
+//! only directories whose mtime moved are re-listed; the files already known
+//! in every directory are re-statted to catch in-place modifications.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use globset::GlobSet;
+use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+use notify::{Event, EventKind};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use find_common::config::{ScanConfig, WatchMode};
+
+use crate::path_util::normalise_path_sep;
+use crate::walk::{walk_source_tree, WalkItem};
+
+/// Filesystem types whose changes local change notification cannot see.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb", "smb2", "smb3", "smbfs", "afpfs", "webdav", "davfs",
+    "fuse.sshfs", "sshfs", "9p", "ceph", "glusterfs", "fuse.glusterfs", "lustre", "afs",
+    "fuse.rclone", "ncpfs",
+];
+
+/// Whether a source rooted at `root` should be polled under `mode`.
+pub fn use_polling(mode: WatchMode, root: &Path) -> bool {
+    match mode {
+        WatchMode::Events | WatchMode::Native => false,
+        WatchMode::Poll => true,
+        WatchMode::Auto => is_network_path(root),
+    }
+}
+
+/// Best-effort check for a path on a network filesystem: the mount table on
+/// Linux and macOS, UNC paths on Windows.  Mapped network drives on Windows
+/// are not detected; set `watch_mode = "poll"` for those sources.
+pub fn is_network_path(path: &Path) -> bool {
+    #[cfg(windows)]
+    {
+        let s = path.to_string_lossy();
+        (s.starts_with(r"\\") && !s.starts_with(r"\\?\")) || s.starts_with(r"\\?\UNC\")
+    }
+    #[cfg(not(windows))]
+    {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        mount_table()
+            .into_iter()
+            .filter(|(mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+            .is_some_and(|(_, fs_type)| NETWORK_FS_TYPES.contains(&fs_type.as_str()))
+    }
+}
+
+/// `(mount point, filesystem type)` for every mounted filesystem.
+#[cfg(target_os = "linux")]
+fn mount_table() -> Vec<(PathBuf, String)> {
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else { return vec![] };
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            Some((PathBuf::from(mount_point), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// `(mount point, filesystem type)` for every mounted filesystem, parsed from
+/// `mount` output (`//user@nas/share on /Volumes/share (smbfs, nodev, ...)`).
+#[cfg(all(unix, not(target_os = "linux")))]
+fn mount_table() -> Vec<(PathBuf, String)> {
+    let Ok(out) = std::process::Command::new("mount").output() else { return vec![] };
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, opts) = rest.rsplit_once(" (")?;
+            let fs_type = opts.split([',', ')']).next()?.trim();
+            Some((PathBuf::from(mount_point), fs_type.to_string()))
+        })
+        .collect()
+}
+
+/// What the previous pass saw in one directory.
+#[derive(Default)]
+struct DirState {
+    mtime: Option<i64>,
+    /// File name → (mtime, size).
+    files: HashMap<OsString, (i64, u64)>,
+    subdirs: HashSet<OsString>,
+}
+
+/// Snapshot of one source tree, compared against the filesystem on each pass.
+pub struct Poller {
+    root: PathBuf,
+    scan: ScanConfig,
+    excludes: GlobSet,
+    dirs: HashMap<PathBuf, DirState>,
+}
+
+impl Poller {
+    /// Walk `root` (with the same filters as `find-scan`) to take the initial
+    /// snapshot.  No events are produced for what already exists.
+    pub fn new(root: PathBuf, terminals: Option<&HashSet<String>>, excludes: GlobSet, scan: ScanConfig) -> Self {
+        let mut poller = Poller { root, scan, excludes, dirs: HashMap::new() };
+        let root = poller.root.clone();
+        poller.add_tree(&root, terminals, None);
+        poller
+    }
+
+    /// Number of directories in the snapshot.
+    pub fn dir_count(&self) -> usize {
+        self.dirs.len()
+    }
+
+    /// Record the tree under `start`, pushing a create event for each file
+    /// when `events` is given.
+    fn add_tree(&mut self, start: &Path, terminals: Option<&HashSet<String>>, mut events: Option<&mut Vec<Event>>) {
+        let mut found: Vec<WalkItem> = Vec::new();
+        walk_source_tree(start, &self.root, &self.scan, &self.excludes, terminals, |item| found.push(item));
+        for item in found {
+            match item {
+                WalkItem::Dir(dir) => {
+                    if let (Some(parent), Some(name)) = (dir.parent(), dir.file_name()) {
+                        if dir != self.root {
+                            self.dirs.entry(parent.to_path_buf()).or_default().subdirs.insert(name.to_owned());
+                        }
+                    }
+                    self.dirs.entry(dir.clone()).or_default().mtime = mtime_of(&dir);
+                }
+                WalkItem::File { abs, .. } => {
+                    let (Some(parent), Some(name)) = (abs.parent(), abs.file_name()) else { continue };
+                    let Ok(state) = stat(&abs) else { continue };
+                    self.dirs.entry(parent.to_path_buf()).or_default().files.insert(name.to_owned(), state);
+                    if let Some(events) = events.as_deref_mut() {
+                        events.push(event(EventKind::Create(CreateKind::File), abs));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget `dir` and everything below it, pushing a remove event for each
+    /// file it held.
+    fn remove_tree(&mut self, dir: &Path, events: &mut Vec<Event>) {
+        let gone: Vec<PathBuf> = self.dirs.keys().filter(|d| d.starts_with(dir)).cloned().collect();
+        for d in gone {
+            if let Some(state) = self.dirs.remove(&d) {
+                for name in state.files.into_keys() {
+                    events.push(event(EventKind::Remove(RemoveKind::File), d.join(name)));
+                }
+            }
+        }
+    }
+
+    /// Compare the tree with the previous pass and return the changes.
+    /// Paths that cannot be read for any reason other than being gone (an
+    /// unreachable share, a stale handle) are left alone until a later pass.
+    pub fn poll(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        if !self.root.is_dir() {
+            warn!("poll: source root {} is not reachable; skipping this pass", self.root.display());
+            return events;
+        }
+        let mut dirs: Vec<PathBuf> = self.dirs.keys().cloned().collect();
+        dirs.sort();
+        for dir in dirs {
+            // Already dropped along with a removed parent.
+            if !self.dirs.contains_key(&dir) {
+                continue;
+            }
+            match std::fs::metadata(&dir) {
+                Ok(meta) if meta.is_dir() => {}
+                Err(e) if e.kind() != ErrorKind::NotFound => continue,
+                _ => {
+                    self.remove_tree(&dir, &mut events);
+                    continue;
+                }
+            }
+            let mtime = mtime_of(&dir);
+            if self.dirs.get(&dir).is_some_and(|s| s.mtime != mtime) {
+                self.relist(&dir, mtime, &mut events);
+            }
+            let Some(state) = self.dirs.get_mut(&dir) else { continue };
+            let mut removed = Vec::new();
+            for (name, seen) in state.files.iter_mut() {
+                let path = dir.join(name);
+                match stat(&path) {
+                    Ok(now) if now != *seen => {
+                        *seen = now;
+                        events.push(event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), path));
+                    }
+                    Ok(_) => {}
+                    Err(kind) if kind == ErrorKind::NotFound => removed.push(name.clone()),
+                    Err(_) => {}
+                }
+            }
+            for name in removed {
+                state.files.remove(&name);
+                events.push(event(EventKind::Remove(RemoveKind::File), dir.join(name)));
+            }
+        }
+        events
+    }
+
+    /// `dir`'s mtime moved: list it again to find added and removed entries.
+    fn relist(&mut self, dir: &Path, mtime: Option<i64>, events: &mut Vec<Event>) {
+        let Ok(rd) = std::fs::read_dir(dir) else { return };
+        let mut files = HashSet::new();
+        let mut subdirs = Vec::new();
+        for entry in rd.flatten() {
+            let Ok(file_type) = entry.file_type() else { continue };
+            let is_dir = file_type.is_dir() || (self.scan.follow_symlinks && file_type.is_symlink() && entry.path().is_dir());
+            if is_dir {
+                subdirs.push(entry.file_name());
+            } else if file_type.is_file() || (self.scan.follow_symlinks && entry.path().is_file()) {
+                files.insert(entry.file_name());
+            }
+        }
+
+        let state = self.dirs.entry(dir.to_path_buf()).or_default();
+        state.mtime = mtime;
+        let gone: Vec<OsString> = state.files.keys().filter(|n| !files.contains(*n)).cloned().collect();
+        for name in gone {
+            state.files.remove(&name);
+            events.push(event(EventKind::Remove(RemoveKind::File), dir.join(name)));
+        }
+        for name in files {
+            if state.files.contains_key(&name) || name == self.scan.index_file.as_str() {
+                continue;
+            }
+            let path = dir.join(&name);
+            if let Ok(seen) = stat(&path) {
+                state.files.insert(name, seen);
+                events.push(event(EventKind::Create(CreateKind::File), path));
+            }
+        }
+
+        let known = std::mem::take(&mut state.subdirs);
+        for name in &known {
+            if !subdirs.contains(name) {
+                self.remove_tree(&dir.join(name), events);
+            }
+        }
+        let mut kept = HashSet::new();
+        for name in subdirs {
+            let path = dir.join(&name);
+            if known.contains(&name) {
+                kept.insert(name);
+                continue;
+            }
+            let rel = path.strip_prefix(&self.root).map(|r| normalise_path_sep(&r.to_string_lossy()));
+            if rel.is_ok_and(|rel| self.excludes.is_match(&*rel)) {
+                continue;
+            }
+            debug!("poll: new directory {}", path.display());
+            self.add_tree(&path, None, Some(events));
+            kept.insert(name);
+        }
+        if let Some(state) = self.dirs.get_mut(dir) {
+            state.subdirs.extend(kept);
+        }
+    }
+}
+
+/// Take the initial snapshot of `root`, then poll it every `interval` and
+/// send the changes to the watch event loop until it shuts down.
+pub async fn run(
+    root: PathBuf,
+    terminals: Option<HashSet<String>>,
+    excludes: GlobSet,
+    scan: ScanConfig,
+    interval: Duration,
+    tx: mpsc::Sender<notify::Result<Event>>,
+) {
+    let snapshot_root = root.clone();
+    let Ok(mut poller) = tokio::task::spawn_blocking(move || {
+        Poller::new(snapshot_root, terminals.as_ref(), excludes, scan)
+    })
+    .await
+    else {
+        return;
+    };
+    info!("polling {:?} every {}s ({} directories)", root, interval.as_secs(), poller.dir_count());
+    loop {
+        tokio::time::sleep(interval).await;
+        let Ok((p, events)) = tokio::task::spawn_blocking(move || {
+            let events = poller.poll();
+            (poller, events)
+        })
+        .await
+        else {
+            return;
+        };
+        poller = p;
+        if !events.is_empty() {
+            debug!("poll {:?}: {} changes", root, events.len());
+        }
+        for ev in events {
+            if tx.send(Ok(ev)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn event(kind: EventKind, path: PathBuf) -> Event {
+    Event::new(kind).add_path(path)
+}
+
+fn mtime_of(path: &Path) -> Option<i64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as i64)
+}
+
+/// `(mtime, size)` of a file.
+fn stat(path: &Path) -> Result<(i64, u64), ErrorKind> {
+    let meta = path.metadata().map_err(|e| e.kind())?;
+    let mtime = meta.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as i64);
+    Ok((mtime, meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn poller(root: &Path) -> Poller {
+        Poller::new(root.to_path_buf(), None, GlobSet::empty(), ScanConfig::default())
+    }
+
+    fn changes(events: &[Event]) -> Vec<(&'static str, String)> {
+        let mut out: Vec<_> = events
+            .iter()
+            .map(|e| {
+                let kind = match e.kind {
+                    EventKind::Create(_) => "create",
+                    EventKind::Modify(_) => "modify",
+                    EventKind::Remove(_) => "remove",
+                    _ => "other",
+                };
+                (kind, e.paths[0].file_name().unwrap().to_string_lossy().into_owned())
+            })
+            .collect();
+        out.sort();
+        out
+    }
+
+    /// Make sure the next write lands on a different mtime, even on
+    /// filesystems with coarse timestamps.
+    fn bump_mtime(path: &Path) {
+        let later = std::time::SystemTime::now() + Duration::from_secs(5);
+        filetime::set_file_mtime(path, filetime::FileTime::from_system_time(later)).unwrap();
+    }
+
+    #[test]
+    fn detects_created_modified_and_removed_files() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("keep.txt"), "a").unwrap();
+        std::fs::write(tmp.path().join("edit.txt"), "a").unwrap();
+        std::fs::write(tmp.path().join("gone.txt"), "a").unwrap();
+        let mut p = poller(tmp.path());
+        assert!(p.poll().is_empty());
+
+        std::fs::write(tmp.path().join("edit.txt"), "changed").unwrap();
+        std::fs::remove_file(tmp.path().join("gone.txt")).unwrap();
+        std::fs::write(tmp.path().join("new.txt"), "a").unwrap();
+        bump_mtime(tmp.path());
+        assert_eq!(
+            changes(&p.poll()),
+            [("create", "new.txt".into()), ("modify", "edit.txt".into()), ("remove", "gone.txt".into())],
+        );
+        assert!(p.poll().is_empty());
+    }
+
+    #[test]
+    fn tracks_new_and_removed_directories() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("old")).unwrap();
+        std::fs::write(tmp.path().join("old/one.txt"), "a").unwrap();
+        let mut p = poller(tmp.path());
+
+        std::fs::create_dir_all(tmp.path().join("new/deep")).unwrap();
+        std::fs::write(tmp.path().join("new/deep/two.txt"), "a").unwrap();
+        std::fs::remove_dir_all(tmp.path().join("old")).unwrap();
+        bump_mtime(tmp.path());
+        assert_eq!(changes(&p.poll()), [("create", "two.txt".into()), ("remove", "one.txt".into())]);
+
+        // Files in the new directory are tracked from now on.
+        std::fs::write(tmp.path().join("new/deep/two.txt"), "changed").unwrap();
+        assert_eq!(changes(&p.poll()), [("modify", "two.txt".into())]);
+    }
+
+    #[test]
+    fn explicit_modes_ignore_the_filesystem() {
+        let tmp = TempDir::new().unwrap();
+        assert!(use_polling(WatchMode::Poll, tmp.path()));
+        assert!(!use_polling(WatchMode::Events, tmp.path()));
+    }
+}
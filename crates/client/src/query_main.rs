@@ -1,21 +1,83 @@
-mod api;
+mod completions;
+mod editor;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap::{CommandFactory, FromArgMatches, Parser};
 use colored::Colorize;
+use serde::Serialize;
 
-use find_common::config::{default_config_path, parse_client_config};
+use find_client_lib::{api, load_config};
+use find_common::api::{ContextBatchItem, ContextBatchResult, RecentAction, SearchExplain, SearchResponse, SearchResult};
+use find_common::config::{default_config_path, ClientConfig};
+use find_common::path_map::PathMapper;
 
 #[derive(Parser)]
 #[command(name = "find", about = "Search the find-anything index", version)]
 struct Args {
     /// Search pattern
-    pattern: String,
+    #[arg(required_unless_present = "recent")]
+    pattern: Option<String>,
+
+    /// List recently indexed files instead of searching (newest first)
+    #[arg(long, conflicts_with = "pattern")]
+    recent: bool,
+
+    /// With --recent: order by file modification time instead of index time
+    #[arg(long)]
+    mtime: bool,
+
+    /// With --recent: only entries since this time: a duration ("24h", "7d"),
+    /// a date (2024-03-01) or a unix timestamp
+    #[arg(long, value_parser = parse_time)]
+    since: Option<i64>,
+
+    /// Only files of this kind, e.g. pdf, code (repeatable)
+    #[arg(long = "kind")]
+    kinds: Vec<String>,
+
+    /// Only files with this extension, e.g. rs, md (repeatable)
+    #[arg(long = "ext", conflicts_with = "recent")]
+    exts: Vec<String>,
+
+    /// Only files under this directory, relative to the source root
+    #[arg(long, conflicts_with = "recent")]
+    path_prefix: Option<String>,
+
+    /// Only files modified after this time: a duration ago ("7d"), a date
+    /// (2024-03-01) or a unix timestamp
+    #[arg(long, value_parser = parse_time, conflicts_with = "recent")]
+    after: Option<i64>,
+
+    /// Only files modified before this time (same forms as --after)
+    #[arg(long, value_parser = parse_time, conflicts_with = "recent")]
+    before: Option<i64>,
+
+    /// Only files at least this big, e.g. 10k, 5M, 1G
+    #[arg(long, value_parser = parse_size, conflicts_with = "recent")]
+    min_size: Option<u64>,
+
+    /// Only files at most this big (same forms as --min-size)
+    #[arg(long, value_parser = parse_size, conflicts_with = "recent")]
+    max_size: Option<u64>,
+
+    /// Skip files whose path contains this text, e.g. test/ (repeatable)
+    #[arg(long = "exclude-path", conflicts_with = "recent")]
+    exclude_paths: Vec<String>,
+
+    /// Skip files with this extension, e.g. lock (repeatable)
+    #[arg(long = "exclude-ext", conflicts_with = "recent")]
+    exclude_exts: Vec<String>,
 
     /// Matching mode
     #[arg(long, default_value = "fuzzy")]
     mode: String,
 
+    /// Order results by relevance (default), modification time (newest
+    /// first), path, size (largest first), or matches per file (most first;
+    /// exact and regex modes)
+    #[arg(long, value_parser = ["score", "mtime", "path", "size", "hits"], conflicts_with = "recent")]
+    sort: Option<String>,
+
     /// Only search these sources (repeatable)
     #[arg(long = "source")]
     sources: Vec<String>,
@@ -36,75 +98,176 @@ struct Args {
     #[arg(long)]
     no_color: bool,
 
+    /// Print results as one JSON document
+    #[arg(long, conflicts_with = "ndjson")]
+    json: bool,
+
+    /// Print results as newline-delimited JSON, one object per line
+    #[arg(long)]
+    ndjson: bool,
+
+    /// Print matches as grep does: path:line:content, without color
+    #[arg(long, conflicts_with_all = ["json", "ndjson"])]
+    grep_format: bool,
+
+    /// Print only the paths of files with matches (like grep -l), without
+    /// fetching results
+    #[arg(short = 'l', long, conflicts_with_all = ["json", "ndjson", "grep_format", "context", "count", "recent"])]
+    files_with_matches: bool,
+
+    /// Print the number of matches in each source, without fetching results
+    #[arg(short = 'c', long, conflicts_with_all = ["json", "ndjson", "grep_format", "context", "recent"])]
+    count: bool,
+
+    /// Print how long each source took and how many candidates it read to
+    /// stderr, for tuning search.fts_candidate_limit
+    #[arg(long, conflicts_with_all = ["files_with_matches", "count", "export", "recent"])]
+    explain: bool,
+
+    /// Write every match (not just one page) to stdout as CSV or NDJSON;
+    /// --limit, --offset and --sort are ignored
+    #[arg(long, value_name = "FORMAT", value_parser = ["csv", "ndjson"],
+          conflicts_with_all = ["json", "ndjson", "grep_format", "context", "files_with_matches", "count", "recent"])]
+    export: Option<String>,
+
+    /// Open the Nth result (default: the first) in your editor at its line
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1",
+          conflicts_with_all = ["json", "ndjson", "grep_format", "files_with_matches", "count", "export", "recent"])]
+    open: Option<usize>,
+
     /// Path to client config file (default: /etc/find-anything/client.toml as root, else ~/.config/find-anything/client.toml)
     #[arg(long)]
     config: Option<String>,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::WARN)
         .with_writer(std::io::stderr)
         .init();
 
+    completions::handle(Args::command());
     let args = Args::from_arg_matches(&Args::command().version(find_common::tool_version!()).get_matches()).unwrap_or_else(|e| e.exit());
 
-    if args.no_color {
+    // `requires = "recent"` is met by the flag's default value, so check here.
+    if !args.recent && (args.mtime || args.since.is_some()) {
+        Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "--mtime and --since require --recent")
+            .exit();
+    }
+
+    if args.no_color || args.grep_format || args.files_with_matches || args.count || args.export.is_some() {
         colored::control::set_override(false);
     }
 
-    let config_path = args.config.unwrap_or_else(default_config_path);
-    let config_str = std::fs::read_to_string(&config_path)
-        .with_context(|| format!("reading config {config_path}"))?;
-    let (config, config_warnings) = parse_client_config(&config_str)?;
+    // As grep: 0 when something matched, 1 when nothing did, 2 on error.
+    let code = match run(&args).await {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            2
+        }
+    };
+    std::process::exit(code);
+}
+
+/// Run the search (or `--recent` listing); returns whether anything matched.
+async fn run(args: &Args) -> Result<bool> {
+    let config_path = args.config.clone().unwrap_or_else(default_config_path);
+    let (config, config_warnings) = load_config(&config_path)?;
     for w in &config_warnings { eprintln!("Warning: {w}"); }
 
     let client = api::ApiClient::new(&config.server.url, &config.server.token);
     client.check_server_version().await?;
 
+    let Some(pattern) = &args.pattern else {
+        return print_recent(&client, args).await;
+    };
+
+    let filters = api::SearchFilters {
+        kinds: args.kinds.clone(),
+        exts: args.exts.clone(),
+        path_prefix: args.path_prefix.clone(),
+        date_from: args.after,
+        date_to: args.before,
+        min_size: args.min_size,
+        max_size: args.max_size,
+        exclude_paths: args.exclude_paths.clone(),
+        exclude_exts: args.exclude_exts.clone(),
+        sort: args.sort.clone(),
+        explain: args.explain,
+    };
+    if args.files_with_matches || args.count {
+        return print_summary(&client, args, pattern, &filters).await;
+    }
+    if let Some(format) = &args.export {
+        let exported = client.search_export(pattern, &args.mode, &args.sources, &filters, format, &mut std::io::stdout()).await?;
+        return Ok(exported > 0);
+    }
+
     let resp = client
-        .search(
-            &args.pattern,
-            &args.mode,
-            &args.sources,
-            args.limit,
-            args.offset,
-        )
+        .search(pattern, &args.mode, &args.sources, args.limit, args.offset, &filters)
         .await?;
 
-    if resp.results.is_empty() {
+    if resp.timed_out {
+        eprintln!("warning: search timed out on the server; results are incomplete");
+    }
+    if !resp.stale_sources.is_empty() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        for (source, last_scan) in &resp.stale_sources {
+            let days = (now - last_scan).max(0) / 86_400;
+            eprintln!("warning: source '{source}' was last scanned {days} days ago; results may be stale");
+        }
+    }
+    if let Some(explain) = &resp.explain {
+        print_explain(explain);
+    }
+    let matched = !resp.results.is_empty();
+
+    if let Some(n) = args.open {
+        if !matched {
+            eprintln!("no results");
+            return Ok(false);
+        }
+        open_hit(&config, &resp, n)?;
+        return Ok(true);
+    }
+    if args.json || args.ndjson {
+        print_json(&client, &PathMapper::new(&config), args, resp).await?;
+        return Ok(matched);
+    }
+    if args.grep_format {
+        print_grep(&client, args, &resp).await?;
+        return Ok(matched);
+    }
+
+    if !matched {
         eprintln!("no results");
-        return Ok(());
+        return Ok(false);
     }
 
     let separator = "──".repeat(30).dimmed().to_string();
+    let contexts = fetch_contexts(&client, args, &resp.results).await?;
 
-    for hit in &resp.results {
+    for (hit, ctx) in resp.results.iter().zip(&contexts) {
         let source_tag = format!("[{}]", hit.source).cyan().to_string();
-        let path_str = match &hit.archive_path {
-            Some(inner) => format!("{}::{}", hit.path, inner),
-            None => hit.path.clone(),
-        };
-        let loc = format!("{}:{}", path_str, hit.line_number).green().to_string();
+        let loc = format!("{}:{}", hit_path(hit), hit.line_number).green().to_string();
 
         if args.context == 0 {
             let snippet = hit.snippet.trim();
             println!("{} {}  {}", source_tag, loc, snippet);
         } else {
             println!("{}", separator);
-            println!("{} {}", source_tag, loc);
-
-            let ctx = client
-                .context(
-                    &hit.source,
-                    &hit.path,
-                    hit.archive_path.as_deref(),
-                    hit.line_number,
-                    args.context,
-                )
-                .await?;
+            match hit.hit_count {
+                Some(n) => println!("{} {}  {}", source_tag, loc, format!("({n} matches)").dimmed()),
+                None => println!("{} {}", source_tag, loc),
+            }
 
+            let Some(ctx) = ctx else { continue };
             for (i, line) in ctx.lines.iter().enumerate() {
                 if Some(i) == ctx.match_index {
                     // Matching line: highlighted
@@ -121,5 +284,280 @@ async fn main() -> Result<()> {
     }
 
     eprintln!("({} total)", resp.total);
+    Ok(true)
+}
+
+/// `--open`: open the `n`th result (1-based) in the configured editor, or
+/// in the browser when only a `[cli.path_map]` URL template covers it.
+fn open_hit(config: &ClientConfig, resp: &SearchResponse, n: usize) -> Result<()> {
+    let Some(hit) = n.checked_sub(1).and_then(|i| resp.results.get(i)) else {
+        anyhow::bail!("--open {n}: there are {} results", resp.results.len());
+    };
+    if hit.archive_path.is_some() {
+        anyhow::bail!("{} is inside an archive and can't be opened in an editor", hit_path(hit));
+    }
+    let mapper = PathMapper::new(config);
+    let url = mapper.url(&hit.source, &hit.path, hit.line_number);
+    match (mapper.local_path(&hit.source, &hit.path), url) {
+        (Some(path), _) if path.exists() => editor::open(config.cli.editor.as_deref(), &path, hit.line_number),
+        (_, Some(url)) => editor::open_url(&url),
+        (Some(path), None) => anyhow::bail!(
+            "{} not found; if source \"{}\" lives elsewhere on this machine, map it with [cli.path_map]",
+            path.display(),
+            hit.source
+        ),
+        (None, None) => anyhow::bail!(
+            "no local path for source \"{}\"; map it with [cli.path_map] in your client config",
+            hit.source
+        ),
+    }
+}
+
+/// With `-C`, the context window around each of `hits`, in the same order,
+/// fetched in a single `/api/v1/context-batch` request.  `None` for hits the
+/// server had no context for, and for every hit without `-C`.
+async fn fetch_contexts(
+    client: &api::ApiClient,
+    args: &Args,
+    hits: &[SearchResult],
+) -> Result<Vec<Option<ContextBatchResult>>> {
+    let mut slots: Vec<Option<ContextBatchResult>> = hits.iter().map(|_| None).collect();
+    if args.context == 0 || hits.is_empty() {
+        return Ok(slots);
+    }
+    let requests = hits
+        .iter()
+        .map(|h| ContextBatchItem {
+            source: h.source.clone(),
+            path: h.path.clone(),
+            archive_path: h.archive_path.clone(),
+            line: h.line_number,
+            window: args.context,
+        })
+        .collect();
+    // The server groups results by source, so match them back to the hits.
+    for ctx in client.context_batch(requests).await? {
+        let slot = hits.iter().zip(slots.iter_mut()).find(|(h, slot)| {
+            slot.is_none()
+                && h.source == ctx.source
+                && h.path == ctx.path
+                && h.archive_path == ctx.archive_path
+                && h.line_number == ctx.line
+        });
+        if let Some((_, slot)) = slot {
+            *slot = Some(ctx);
+        }
+    }
+    Ok(slots)
+}
+
+/// A hit's path, with the archive member appended after `::`.
+fn hit_path(hit: &SearchResult) -> String {
+    match &hit.archive_path {
+        Some(inner) => format!("{}::{}", hit.path, inner),
+        None => hit.path.clone(),
+    }
+}
+
+/// `--grep-format`: `path:line:content` per match.  With `-C`, context lines
+/// are printed as `path-line-content` and groups are separated by `--`.
+async fn print_grep(client: &api::ApiClient, args: &Args, resp: &SearchResponse) -> Result<()> {
+    let contexts = fetch_contexts(client, args, &resp.results).await?;
+    for (n, (hit, ctx)) in resp.results.iter().zip(&contexts).enumerate() {
+        let path = hit_path(hit);
+        if args.context == 0 {
+            println!("{}:{}:{}", path, hit.line_number, hit.snippet.trim_end());
+            continue;
+        }
+        if n > 0 {
+            println!("--");
+        }
+        let Some(ctx) = ctx else { continue };
+        for (i, line) in ctx.lines.iter().enumerate() {
+            let sep = if Some(i) == ctx.match_index { ':' } else { '-' };
+            println!("{}{}{}{}{}", path, sep, line.line_number, sep, line.content);
+        }
+    }
     Ok(())
 }
+
+/// `--explain`: one line per source on stderr, so it never mixes with the
+/// results on stdout.
+fn print_explain(explain: &SearchExplain) {
+    eprintln!(
+        "{:<20} {:>8} {:>8} {:>8} {:>12} {:>8} {:>10} {:>8}",
+        "source", "queued", "ms", "fts ms", "candidates", "dropped", "lines read", "reads"
+    );
+    for s in &explain.sources {
+        let candidates = if s.capped { format!("{}+", s.candidates) } else { s.candidates.to_string() };
+        let note = match (&s.error, s.skipped) {
+            (Some(e), _) => format!("  {e}"),
+            (None, true) => "  skipped: term filter".to_string(),
+            (None, false) => String::new(),
+        };
+        eprintln!(
+            "{:<20} {:>8} {:>8} {:>8} {:>12} {:>8} {:>10} {:>8}{note}",
+            s.source, s.queued_ms, s.elapsed_ms, s.fts_ms, candidates, s.dropped, s.lines_read, s.content_reads
+        );
+    }
+    eprintln!(
+        "total {} ms; candidates shown as N+ reached their limit (search.fts_candidate_limit = {})",
+        explain.elapsed_ms, explain.fts_candidate_limit
+    );
+}
+
+/// `-l` and `-c`: matching files or per-source counts from the server's
+/// summary endpoint, which answers from the index without fetching results.
+async fn print_summary(client: &api::ApiClient, args: &Args, pattern: &str, filters: &api::SearchFilters) -> Result<bool> {
+    let summary = client
+        .search_summary(pattern, &args.mode, &args.sources, filters, args.files_with_matches)
+        .await?;
+    if args.count {
+        for s in &summary.sources {
+            println!("{}:{}{}", s.source, s.count, if s.capped { "+" } else { "" });
+        }
+        if summary.approximate {
+            eprintln!("note: regex and case-sensitive counts are upper bounds");
+        }
+    } else {
+        for f in &summary.files {
+            println!("{}", f.path);
+        }
+    }
+    Ok(!summary.sources.is_empty())
+}
+
+/// Parse `--since`, `--after` and `--before`: a unix timestamp, a date
+/// (`2024-03-01`, local midnight), or a duration like `"24h"` counted back
+/// from now.
+fn parse_time(s: &str) -> Result<i64, String> {
+    if let Ok(epoch) = s.parse::<i64>() {
+        return Ok(epoch);
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+            .map(|t| t.timestamp())
+            .ok_or_else(|| format!("invalid date: {s}"));
+    }
+    let secs = find_common::config::parse_ttl(s)?;
+    Ok(chrono::Utc::now().timestamp() - secs as i64)
+}
+
+/// Parse `--min-size` and `--max-size`: bytes, or a number with a `k`, `M`
+/// or `G` suffix (powers of 1024; a trailing `B` or `iB` is allowed).
+fn parse_size(s: &str) -> Result<u64, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let digits = lower.trim_end_matches("ib").trim_end_matches('b');
+    let (num, mult) = match digits.char_indices().last() {
+        Some((i, 'k')) => (&digits[..i], 1u64 << 10),
+        Some((i, 'm')) => (&digits[..i], 1 << 20),
+        Some((i, 'g')) => (&digits[..i], 1 << 30),
+        _ => (digits, 1),
+    };
+    let n: f64 = num.trim().parse().map_err(|_| format!("invalid size: {s}"))?;
+    if n < 0.0 {
+        return Err(format!("invalid size: {s}"));
+    }
+    Ok((n * mult as f64) as u64)
+}
+
+/// A search hit as printed by `--json` and `--ndjson`.
+#[derive(Serialize)]
+struct JsonHit {
+    #[serde(flatten)]
+    hit: SearchResult,
+    /// Where to open the file: its `[cli.path_map]` URL template, else where
+    /// the server serves the file (or archive member) from.
+    resource_url: String,
+}
+
+#[derive(Serialize)]
+struct JsonResults {
+    results: Vec<JsonHit>,
+    total: usize,
+    capped: bool,
+    timed_out: bool,
+}
+
+/// Print search results as JSON.  With `-C`, each hit's `context_lines` is
+/// filled in from the context-batch endpoint.
+async fn print_json(client: &api::ApiClient, mapper: &PathMapper, args: &Args, resp: SearchResponse) -> Result<()> {
+    let contexts = fetch_contexts(client, args, &resp.results).await?;
+    let mut results = Vec::new();
+    for (mut hit, ctx) in resp.results.into_iter().zip(contexts) {
+        if let Some(ctx) = ctx {
+            hit.context_lines = ctx.lines;
+        }
+        let mapped = match hit.archive_path {
+            None => mapper.url(&hit.source, &hit.path, hit.line_number),
+            Some(_) => None,
+        };
+        let resource_url = mapped
+            .unwrap_or_else(|| client.resource_url(&hit.source, &hit.path, hit.archive_path.as_deref()));
+        let hit = JsonHit { hit, resource_url };
+        if args.ndjson {
+            println!("{}", serde_json::to_string(&hit)?);
+        } else {
+            results.push(hit);
+        }
+    }
+    if args.json {
+        let out = JsonResults { results, total: resp.total, capped: resp.capped, timed_out: resp.timed_out };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    }
+    Ok(())
+}
+
+async fn print_recent(client: &api::ApiClient, args: &Args) -> Result<bool> {
+    let files = client
+        .get_recent(args.limit, args.mtime, &args.sources, args.since, &args.kinds)
+        .await?;
+
+    if args.ndjson {
+        for f in &files {
+            println!("{}", serde_json::to_string(f)?);
+        }
+        return Ok(!files.is_empty());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "files": files }))?);
+        return Ok(!files.is_empty());
+    }
+
+    if files.is_empty() {
+        eprintln!("no recent files");
+        return Ok(false);
+    }
+
+    for f in &files {
+        let ts = chrono::DateTime::from_timestamp(f.indexed_at, 0)
+            .map(|utc| chrono::DateTime::<chrono::Local>::from(utc)
+                .format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| f.indexed_at.to_string());
+        let source_tag = format!("[{}]", f.source).cyan().to_string();
+        let path = match &f.new_path {
+            Some(new_path) => format!("{} → {}", f.path, new_path),
+            None => f.path.clone(),
+        };
+        let path = if f.action == RecentAction::Deleted {
+            path.dimmed().to_string()
+        } else {
+            path.green().to_string()
+        };
+        // Sorting by mtime lists files, not events, so the action is moot.
+        if args.mtime {
+            println!("{}  {} {}", ts.dimmed(), source_tag, path);
+        } else {
+            let action = match f.action {
+                RecentAction::Added    => "added",
+                RecentAction::Modified => "modified",
+                RecentAction::Deleted  => "deleted",
+                RecentAction::Renamed  => "renamed",
+            };
+            println!("{}  {} {:<8} {}", ts.dimmed(), source_tag, action, path);
+        }
+    }
+    Ok(true)
+}
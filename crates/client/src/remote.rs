@@ -0,0 +1,159 @@
+//! Shared by sources fetched over an API rather than walked on disk (S3
+//! buckets, cloud drives) and by git history indexing: extracting a
+//! downloaded copy of a file, and batching the results so that each file's
+//! remote version is only recorded once the server has accepted it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::io::AsyncWriteExt;
+
+use find_common::{
+    api::{FileKind, IndexFile, IndexLine, IndexingFailure},
+    config::{extractor_config_from_scan, ExternalExtractorMode, ScanConfig},
+};
+
+use crate::api::ApiClient;
+use crate::batch::{build_index_files, index_file_bytes, submit_batch, BatchSizer};
+use crate::subprocess::{self, ExtractorRoute, InlineKind};
+
+/// Downloads are untrusted input of any kind, so only plain text is
+/// extracted in-process, as in `find-watch`.
+const REMOTE_INLINE_SET: &[InlineKind] = &[InlineKind::Text];
+
+/// Where to download a file named `name` in `dir`.  Only the extension is
+/// kept: it decides which extractor runs.
+pub fn download_path(dir: &Path, name: &str) -> PathBuf {
+    match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some(ext) => dir.join(format!("download.{ext}")),
+        None => dir.join("download"),
+    }
+}
+
+/// Stream the body of `resp` into a new file at `dest`.
+pub async fn save_response(mut resp: reqwest::Response, dest: &Path) -> Result<()> {
+    let url = resp.url().clone();
+    let mut file = tokio::fs::File::create(dest).await
+        .with_context(|| format!("creating {}", dest.display()))?;
+    while let Some(chunk) = resp.chunk().await.with_context(|| format!("downloading {url}"))? {
+        file.write_all(&chunk).await.with_context(|| format!("writing {}", dest.display()))?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Whether `path` is extracted as plain text, so that its first part alone
+/// is still worth indexing.
+pub fn is_plain_text(path: &Path, scan: &ScanConfig) -> bool {
+    matches!(
+        subprocess::resolve_extractor(path, scan, &scan.extractor_dir, REMOTE_INLINE_SET),
+        ExtractorRoute::Inline(InlineKind::Text)
+    )
+}
+
+/// Extract the downloaded copy at `local` into the `IndexFile`s for `rel`,
+/// then delete it.  The kind comes from `local`'s extension, which for an
+/// exported document differs from `rel`'s.
+pub async fn index_download(local: &Path, rel: &str, mtime: i64, size: u64, scan: &ScanConfig) -> Result<Vec<IndexFile>> {
+    let lines = extract(local, scan).await;
+    let _ = std::fs::remove_file(local);
+    Ok(index_files(local, rel, mtime, size, lines?))
+}
+
+/// The `IndexFile`s for a file indexed by name only.
+pub fn index_name_only(rel: &str, mtime: i64, size: u64) -> Vec<IndexFile> {
+    index_files(Path::new(rel), rel, mtime, size, Vec::new())
+}
+
+fn index_files(local: &Path, rel: &str, mtime: i64, size: u64, lines: Vec<IndexLine>) -> Vec<IndexFile> {
+    let kind = if lines.iter().any(|l| l.archive_path.is_some()) {
+        FileKind::Archive
+    } else {
+        FileKind::from_extension(local.extension().and_then(|e| e.to_str()).unwrap_or(""))
+    };
+    build_index_files(rel.to_string(), mtime, size as i64, kind, lines)
+}
+
+async fn extract(path: &Path, scan: &ScanConfig) -> Result<Vec<IndexLine>> {
+    let outcome = match subprocess::resolve_extractor(path, scan, &scan.extractor_dir, REMOTE_INLINE_SET) {
+        ExtractorRoute::Inline(kind) => {
+            return Ok(subprocess::extract_inline(kind, path, &extractor_config_from_scan(scan)));
+        }
+        ExtractorRoute::Archive => {
+            subprocess::extract_via_subprocess(path, scan, &subprocess::resolve_binary_for_archive(&scan.extractor_dir)).await
+        }
+        ExtractorRoute::Subprocess(binary) => subprocess::extract_via_subprocess(path, scan, &binary).await,
+        ExtractorRoute::External(cfg) if matches!(cfg.mode, ExternalExtractorMode::Stdout) => {
+            return match subprocess::run_external_stdout(path, &cfg, scan).await {
+                subprocess::ExternalOutcome::Ok(lines) => Ok(lines),
+                subprocess::ExternalOutcome::BinaryMissing => anyhow::bail!("extractor {:?} not found", cfg.bin),
+                _ => Ok(Vec::new()),
+            };
+        }
+        // Tempdir-mode and server-side extractors are not run on downloads;
+        // those files are indexed by name.
+        ExtractorRoute::External(_) | ExtractorRoute::ServerOnly => return Ok(Vec::new()),
+    };
+    match outcome {
+        subprocess::SubprocessOutcome::Ok(lines) => Ok(lines),
+        subprocess::SubprocessOutcome::Failed => Ok(Vec::new()),
+        subprocess::SubprocessOutcome::BinaryMissing => anyhow::bail!("extractor binary not found"),
+    }
+}
+
+/// Files indexed from a remote source, waiting to be sent.
+pub struct RemoteBatch<'a> {
+    api: &'a ApiClient,
+    source: &'a str,
+    sizer: BatchSizer,
+    files: Vec<IndexFile>,
+    bytes: usize,
+    failures: Vec<IndexingFailure>,
+    /// Key and remote version of each file in `files`.
+    versions: Vec<(String, String)>,
+}
+
+impl<'a> RemoteBatch<'a> {
+    pub fn new(api: &'a ApiClient, source: &'a str, scan: &ScanConfig) -> Self {
+        RemoteBatch {
+            api,
+            source,
+            sizer: BatchSizer::from_scan(scan),
+            files: Vec::new(),
+            bytes: 0,
+            failures: Vec::new(),
+            versions: Vec::new(),
+        }
+    }
+
+    /// Add the `IndexFile`s for the remote file `key` at `version`.
+    pub fn add(&mut self, files: Vec<IndexFile>, key: String, version: String) {
+        for f in files {
+            self.bytes += index_file_bytes(&f);
+            self.files.push(f);
+        }
+        self.versions.push((key, version));
+    }
+
+    /// Report a file that could not be indexed.  Its version is not
+    /// recorded, so the next scan tries it again.
+    pub fn fail(&mut self, path: &str, e: &anyhow::Error) {
+        self.failures.push(IndexingFailure { path: path.to_string(), error: format!("{e:#}") });
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.files.len() >= self.sizer.file_limit() || self.bytes >= self.sizer.byte_limit()
+    }
+
+    /// Send the collected files together with `delete_paths`.  Returns the
+    /// keys and versions of the files sent, now safe to record as indexed.
+    pub async fn submit(&mut self, delete_paths: Vec<String>, scan_timestamp: Option<i64>) -> Result<Vec<(String, String)>> {
+        let files = self.files.len();
+        submit_batch(self.api, self.source, &mut self.files, &mut self.failures, delete_paths, scan_timestamp).await?;
+        if let Some(payload) = self.api.take_last_bulk_size() {
+            self.sizer.observe(files, self.bytes, payload);
+        }
+        self.bytes = 0;
+        Ok(std::mem::take(&mut self.versions))
+    }
+}
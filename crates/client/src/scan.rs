@@ -1,22 +1,27 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use globset::GlobSet;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
 use tracing::{info, warn};
 
 use find_common::{
-    api::{FileKind, IndexFile, IndexLine, IndexingFailure, SCANNER_VERSION, LINE_METADATA, LINE_CONTENT_START},
-    config::{extractor_config_from_scan, load_dir_override, ExternalExtractorMode, ScanConfig},
+    api::{AppendBase, ClientMetrics, FileKind, FileRecord, IndexFile, IndexLine, IndexingFailure, Percentiles, Provenance, ScanMetrics, SCANNER_VERSION, LINE_METADATA, LINE_CONTENT_START},
+    config::{extractor_config_from_scan, load_dir_override, ChangeDetection, ExternalExtractorMode, ScanConfig},
     path::is_composite,
 };
 
-use crate::api::ApiClient;
-use crate::batch::{build_index_files, build_member_index_files, index_file_bytes, submit_batch};
+use crate::api::{ApiClient, OriginalUpload};
+use crate::checkpoint::{self, Checkpoint};
+use crate::extract_cache::ExtractCache;
+use crate::batch::{add_symlink_target, build_index_files, build_member_index_files, index_file_bytes, submit_batch, BatchSizer, KnownMembers};
 use crate::extract;
+use crate::git_history;
 use crate::lazy_header;
 use crate::subprocess;
 use crate::upload::{self, hints_from_scan};
@@ -24,9 +29,14 @@ use crate::upload::{self, hints_from_scan};
 
 
 
-use crate::batch::hash_file;
+use crate::batch::{appended_since, hash_file};
 const MAX_FAILURES_PER_BATCH: usize = 100;
 const MAX_ERROR_LEN: usize = 500;
+/// Files per `known_hashes` request during hash negotiation.
+const NEGOTIATE_CHUNK: usize = 1000;
+/// How long a scan keeps retrying batches spooled while the server was
+/// unreachable before giving up and leaving them for the next run.
+const SPOOL_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
 
 /// Per-invocation options for `run_scan` and `scan_single_file`.
 pub struct ScanOptions {
@@ -49,6 +59,17 @@ pub struct ScanOptions {
     /// Bypass the server-side stale-mtime guard for all submitted IndexFiles.
     /// Implied by `--force`; also set directly by the upload delegation path.
     pub force_index: bool,
+    /// Directory for per-source scan checkpoints (see `crate::checkpoint`).
+    /// `None` disables checkpointing.  Subdir scans and dry runs never use it.
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Directory for the extraction cache (see `crate::extract_cache`).
+    /// `None` disables it, as does `[scan] extract_cache_mb = 0`.
+    pub extract_cache_dir: Option<PathBuf>,
+    /// Ignore and discard any checkpoint left by an interrupted scan (`--full`).
+    pub full: bool,
+    /// Send the server a health report (`ClientMetrics`) after each
+    /// completed scan of a whole source.
+    pub report_metrics: bool,
 }
 
 /// Source-specific parameters for `run_scan` and `scan_single_file`.
@@ -60,26 +81,34 @@ pub struct ScanSource<'a> {
     /// If set, restrict the scan to this subdirectory (relative path within the
     /// source root, forward-slash normalised). The walk is scoped to this
     /// directory; only server files under this prefix are considered for
-    /// deletion; mtime checking is skipped (all files are re-indexed).
+    /// deletion; mtime checking is skipped (all files are re-indexed) unless
+    /// `rescan_subdir` is false.
     pub subdir: Option<String>,
+    /// With `subdir` set, re-index every file under it regardless of mtime.
+    /// False for `--source`/`--path` partial scans, which skip unchanged files
+    /// like a full scan and only reconcile additions and deletions.
+    pub rescan_subdir: bool,
 }
 
 /// Decide whether a local file needs to be (re-)indexed, given what the server
 /// already knows about it.
 ///
 /// `server_entry` is `Some((mtime, scanner_version, indexed_at))` if the server
-/// has a record for this path, `None` if it is new.
+/// has a record for this path, `None` if it is new.  `modified` is the
+/// change-detection policy's verdict when it has one (see `detect_changes`);
+/// otherwise a newer `local_mtime` means the file was modified.
 ///
 /// Returns `(should_index, is_new)`.
 pub(crate) fn needs_reindex(
     server_entry: Option<(i64, u32, Option<i64>)>,
     local_mtime: i64,
+    modified: Option<bool>,
     upgrade: bool,
     force_since: Option<i64>,
 ) -> (bool, bool) {
     match server_entry {
         None                                                          => (true,  true),
-        Some((sm, _, _))  if local_mtime > sm                        => (true,  false),
+        Some((sm, _, _))  if modified.unwrap_or(local_mtime > sm)    => (true,  false),
         Some((_, sv, _))  if upgrade && sv < SCANNER_VERSION         => (true,  false),
         Some((_, _, ia))  if force_since.is_some_and(|fs| ia.is_none_or(|t| t < fs))
                                                                       => (true,  false),
@@ -94,6 +123,7 @@ pub async fn run_scan(
     opts: &ScanOptions,
 ) -> Result<()> {
     let (source_name, paths) = (source.name, source.paths);
+    let started = std::time::Instant::now();
     // Build global exclusion GlobSet for the walk phase.
     let excludes = build_globset(&scan.exclude)?;
     // Build include GlobSet (empty = include everything).
@@ -103,8 +133,15 @@ pub async fn run_scan(
     } else {
         include_dir_prefixes(source.include)
     };
-    // When a subdir is set, always re-index all files (no mtime skip).
-    let subdir_rescan = source.subdir.is_some();
+    // When a subdir is set, re-index all files (no mtime skip) unless this is
+    // an incremental partial scan.
+    let subdir_rescan = source.subdir.is_some() && source.rescan_subdir;
+
+    // Batches spooled while the server was unreachable go first, so the file
+    // list below reflects them.
+    if !opts.dry_run {
+        api.drain_spool(SPOOL_DRAIN_TIMEOUT).await?;
+    }
 
     // Warn if the server inbox is not empty — the file list will reflect only
     // files the worker has already committed, so pending batches from a recent
@@ -130,32 +167,38 @@ pub async fn run_scan(
 
     // Fetch what the server already knows about this source.
     // Only consider outer files (no "::" in path) for deletion/mtime comparison;
-    // inner archive members are managed server-side.
+    // inner archive members are managed server-side, and only used to send
+    // the members of a changed archive that differ.
     // When scanning a subdir, restrict to files under that prefix only.
     info!("fetching existing file list from server...");
-    let server_files: HashMap<String, (i64, u32, Option<i64>)> = api
+    let (members, server_list): (Vec<_>, Vec<_>) = api
         .list_files(source_name)
         .await?
         .into_iter()
-        .filter(|f| !is_composite(&f.path))
         .filter(|f| match &source.subdir {
             None => true,
             Some(sub) => f.path == *sub || f.path.starts_with(&format!("{sub}/")),
         })
-        .map(|f| (f.path, (f.mtime, f.scanner_version, f.indexed_at)))
+        .partition(|f| is_composite(&f.path));
+    let server_files: HashMap<String, (i64, u32, Option<i64>)> = server_list
+        .iter()
+        .map(|f| (f.path.clone(), (f.mtime, f.scanner_version, f.indexed_at)))
         .collect();
 
     // Walk all configured paths (or just the subdir) and build the local file map.
     info!("walking filesystem...");
-    let local_files = walk_paths(paths, scan, &excludes, &includes, include_dirs.as_ref(), source.subdir.as_deref());
+    let local_files = crate::walk::walk_paths(paths, scan, &excludes, &includes, include_dirs.as_ref(), source.subdir.as_deref());
     info!("walk complete: {} files found", local_files.len());
 
     // Compute deletions (pure set diff — no I/O).
     let server_paths: HashSet<&str> = server_files.keys().map(|s| s.as_str()).collect();
     let local_paths: HashSet<&str> = local_files.keys().map(|s| s.as_str()).collect();
 
+    // Git history paths (`repo@v1.2.0`) are never on disk; they are
+    // reconciled by `git_history::scan_history` instead.
     let to_delete: Vec<String> = server_paths
         .difference(&local_paths)
+        .filter(|p| scan.git_history.is_none() || !git_history::is_history_path(paths, p))
         .map(|s| s.to_string())
         .collect();
 
@@ -168,6 +211,72 @@ pub async fn run_scan(
 
     let mut ctx = ScanContext::new(api, source_name, paths, scan, opts.quiet, source.subdir.is_none(), opts.force_since.is_some() || opts.force_index);
 
+    // Pick up where an interrupted scan of this source left off.
+    let checkpoint_file = opts.checkpoint_dir.as_deref()
+        .filter(|_| source.subdir.is_none() && !opts.dry_run)
+        .map(|dir| checkpoint::checkpoint_path(dir, source_name));
+    let mut resume_after: Option<String> = None;
+    if let Some(file) = &checkpoint_file {
+        let previous = if opts.full {
+            checkpoint::clear(file);
+            None
+        } else {
+            checkpoint::load(file)
+        };
+        let cp = match previous {
+            Some(cp) if cp.matches(paths, opts.upgrade, opts.force_since) => {
+                info!("resuming interrupted scan after {} (started {})", cp.done_through, cp.started_at);
+                resume_after = Some(cp.done_through.clone());
+                cp
+            }
+            previous => {
+                if previous.is_some() {
+                    info!("ignoring checkpoint of an interrupted scan with different options");
+                }
+                Checkpoint {
+                    paths: paths.to_vec(),
+                    upgrade: opts.upgrade,
+                    force_since: opts.force_since,
+                    started_at: ctx.scan_start,
+                    done_through: String::new(),
+                }
+            }
+        };
+        ctx.checkpoint = Some((file.clone(), cp));
+    }
+
+    // Files whose content the server already stores are indexed from it
+    // instead of being extracted and uploaded again.
+    let allow_reuse = !opts.dry_run && !ctx.force && !opts.upgrade;
+    let mut modified = HashMap::new();
+    if !subdir_rescan {
+        let policy = scan.change_detection;
+        (modified, ctx.reuse) =
+            detect_changes(policy, &local_files, &server_list, resume_after.as_deref(), allow_reuse).await;
+        if scan.hash_negotiation && allow_reuse {
+            let same_path = policy == ChangeDetection::Mtime;
+            let negotiated = negotiate_hashes(api, &local_files, &server_list, resume_after.as_deref(), same_path).await;
+            ctx.reuse.extend(negotiated);
+        }
+        if !ctx.reuse.is_empty() {
+            info!("{} files can be indexed from content the server already stores", ctx.reuse.len());
+        }
+    }
+
+    if !ctx.force {
+        let mut by_archive: HashMap<String, Vec<FileRecord>> = HashMap::new();
+        for member in members {
+            if let Some((outer, _)) = member.path.split_once("::") {
+                by_archive.entry(outer.to_string()).or_default().push(member);
+            }
+        }
+        ctx.archive_members = by_archive.into_iter().map(|(outer, m)| (outer, KnownMembers::new(m))).collect();
+        ctx.append_bases = server_list
+            .into_iter()
+            .filter_map(|f| Some((f.path, (u64::try_from(f.size?).ok()?, f.file_hash?))))
+            .collect();
+    }
+
     // Submit deletions immediately so removed files are gone before new/modified
     // files are indexed.  This also ensures renames (delete + add) don't leave a
     // stale entry visible while the new path is being indexed.
@@ -176,26 +285,21 @@ pub async fn run_scan(
         ctx.submit(to_delete).await?;
     }
 
-    let mut indexed: usize = 0;
-    let mut skipped: usize = 0;
-    let mut excluded: usize = 0;    // went through process_file but excluded by filter/missing extractor
-    let mut new_files: usize = 0;   // in local but absent from server DB
-    let mut modified: usize = 0;    // mtime changed since last scan
-    let mut upgraded: usize = 0;    // mtime unchanged but scanner_version outdated
-
-    // Build the "N unchanged[, M new][, P modified][, Q upgraded]" summary,
-    // omitting new/modified/upgraded when they are zero.
-    let fmt_changes = |skipped: usize, new_files: usize, modified: usize, upgraded: usize, excluded: usize| -> String {
-        let mut parts = vec![format!("{skipped} unchanged")];
-        if new_files > 0 { parts.push(format!("{new_files} new")); }
-        if modified  > 0 { parts.push(format!("{modified} modified")); }
-        if upgraded  > 0 { parts.push(format!("{upgraded} upgraded")); }
-        if excluded  > 0 { parts.push(format!("{excluded} excluded")); }
-        parts.join(", ")
-    };
+    let mut counts = ScanCounts::default();
     let log_interval = std::time::Duration::from_secs(5);
     let mut last_log = std::time::Instant::now();
 
+    // Extraction runs on the pool's worker tasks; results are committed to the
+    // batch in walk order from this task, so batches stay deterministic.
+    let mut pool = ExtractPool::new(scan);
+    if let Some(dir) = opts.extract_cache_dir.clone().filter(|_| !opts.dry_run) {
+        match ExtractCache::from_scan(scan, dir) {
+            Ok(cache) => pool.cache = cache.map(|c| (Arc::new(c), !ctx.force)),
+            Err(e) => warn!("extraction cache disabled: {e:#}"),
+        }
+    }
+    let mut pending: VecDeque<PendingFile> = VecDeque::new();
+
     // Sort by relative path for deterministic, reproducible processing order.
     // HashMap iteration order is randomised per-process, so without this the
     // same crash would hit a different file each run and logs would differ.
@@ -203,18 +307,23 @@ pub async fn run_scan(
     local_entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
 
     for (rel_path, abs_path) in local_entries {
+        // Already handled by the interrupted run this scan resumes.
+        if resume_after.as_deref().is_some_and(|done| rel_path.as_str() <= done) {
+            counts.skipped += 1;
+            continue;
+        }
         // Check mtime before any further work so unchanged files are skipped cheaply.
         let mtime = mtime_of(abs_path).unwrap_or(0);
         let mut is_new = false; // set inside the !subdir_rescan block when server_entry is known
         let mut is_upgraded_file = false;
         if !subdir_rescan {
             let server_entry = server_files.get(rel_path.as_str()).copied();
-            let (should_index, file_is_new) = needs_reindex(server_entry, mtime, opts.upgrade, opts.force_since);
+            let verdict = modified.get(rel_path.as_str()).copied();
+            let (should_index, file_is_new) = needs_reindex(server_entry, mtime, verdict, opts.upgrade, opts.force_since);
             if !should_index {
-                skipped += 1;
+                counts.skipped += 1;
                 if last_log.elapsed() >= log_interval {
-                    let total = indexed + skipped;
-                    info!("processed {total} files ({}) so far...", fmt_changes(skipped, new_files, modified, upgraded, excluded));
+                    info!("processed {} files ({}) so far...", counts.processed(), counts.summary());
                     last_log = std::time::Instant::now();
                 }
                 continue;
@@ -224,31 +333,40 @@ pub async fn run_scan(
         }
 
         if !opts.dry_run {
-            if process_file(&mut ctx, rel_path, abs_path, mtime, is_new).await? {
-                indexed += 1;
-                if is_new { new_files += 1; }
-                else if is_upgraded_file { upgraded += 1; }
-                else if !subdir_rescan { modified += 1; }
-            } else {
-                excluded += 1;
+            // Quiet hours or `find-admin pause`: start no new extractions.
+            ctx.api.wait_while_paused().await;
+            match plan_file(&mut ctx, rel_path, abs_path, mtime, is_new)? {
+                Some(job) => pending.push_back(PendingFile {
+                    task: pool.spawn(job, ctx.quiet),
+                    is_new,
+                    is_upgraded: is_upgraded_file,
+                }),
+                None => counts.excluded += 1,
+            }
+            // Commit finished files in order once the queue is full.
+            while pending.len() >= pool.queue_len {
+                if let Some(file) = pending.pop_front() {
+                    file.commit(&mut ctx, &mut counts, subdir_rescan).await?;
+                }
             }
         } else {
-            indexed += 1;
-            if is_new { new_files += 1; }
-            else if is_upgraded_file { upgraded += 1; }
-            else if !subdir_rescan { modified += 1; }
+            counts.record(true, is_new, is_upgraded_file, subdir_rescan);
         }
         if last_log.elapsed() >= log_interval {
-            let total = indexed + skipped;
             info!(
-                "processed {total} files ({}) so far, {} in current batch...",
-                fmt_changes(skipped, new_files, modified, upgraded, excluded),
+                "processed {} files ({}) so far, {} in current batch...",
+                counts.processed(),
+                counts.summary(),
                 ctx.batch.len(),
             );
             last_log = std::time::Instant::now();
         }
     }
+    while let Some(file) = pending.pop_front() {
+        file.commit(&mut ctx, &mut counts, subdir_rescan).await?;
+    }
 
+    let ScanCounts { indexed, skipped, excluded, new_files, modified, upgraded } = counts;
     if opts.dry_run {
         if subdir_rescan {
             info!(
@@ -267,17 +385,170 @@ pub async fn run_scan(
                 deleted
             );
         }
+        if source.subdir.is_none() {
+            git_history::scan_history(api, source_name, paths, scan, opts).await?;
+        }
         return Ok(());
     }
 
     // Final batch: flush any remaining indexed files.
     ctx.submit(vec![]).await?;
+    if let Some(file) = &checkpoint_file {
+        checkpoint::clear(file);
+    }
+    api.drain_spool(SPOOL_DRAIN_TIMEOUT).await?;
 
     let excluded_msg = if excluded > 0 { format!(", {excluded} excluded by filter") } else { String::new() };
     info!("scan complete — {indexed} indexed ({new_files} new, {modified} modified, {upgraded} upgraded), {skipped} unchanged, {deleted} deleted{excluded_msg}");
+    if opts.report_metrics && source.subdir.is_none() {
+        let metrics = ClientMetrics {
+            tool: "find-scan".to_string(),
+            version: find_common::tool_version!().to_string(),
+            scan: Some(ScanMetrics {
+                source: source_name.to_string(),
+                started_at: ctx.scan_start,
+                duration_secs: started.elapsed().as_secs_f64(),
+                files_indexed: indexed,
+                files_unchanged: skipped,
+                files_excluded: excluded,
+                files_deleted: deleted,
+                failures: ctx.failure_count,
+                extract_ms: Percentiles::of(std::mem::take(&mut ctx.extract_times)),
+            }),
+            ..Default::default()
+        };
+        // Servers older than this client have no metrics endpoint.
+        if let Err(e) = api.report_metrics(metrics).await {
+            tracing::debug!("not reporting scan metrics: {e:#}");
+        }
+    }
+    if source.subdir.is_none() {
+        git_history::scan_history(api, source_name, paths, scan, opts).await?;
+    }
     Ok(())
 }
 
+/// Per-scan file counters for progress and summary logs.
+#[derive(Default)]
+struct ScanCounts {
+    indexed:   usize,
+    skipped:   usize,
+    /// Went through process_file but excluded by filter/missing extractor.
+    excluded:  usize,
+    /// In local but absent from server DB.
+    new_files: usize,
+    /// Mtime changed since last scan.
+    modified:  usize,
+    /// Mtime unchanged but scanner_version outdated.
+    upgraded:  usize,
+}
+
+impl ScanCounts {
+    /// Count one file that was processed: `submitted` is false when it was
+    /// excluded.  Full subdir rescans don't count modifications.
+    fn record(&mut self, submitted: bool, is_new: bool, is_upgraded: bool, subdir_rescan: bool) {
+        if !submitted {
+            self.excluded += 1;
+            return;
+        }
+        self.indexed += 1;
+        if is_new { self.new_files += 1; }
+        else if is_upgraded { self.upgraded += 1; }
+        else if !subdir_rescan { self.modified += 1; }
+    }
+
+    fn processed(&self) -> usize {
+        self.indexed + self.skipped
+    }
+
+    /// "N unchanged[, M new][, P modified][, Q upgraded][, R excluded]",
+    /// omitting the counts that are zero.
+    fn summary(&self) -> String {
+        let mut parts = vec![format!("{} unchanged", self.skipped)];
+        if self.new_files > 0 { parts.push(format!("{} new", self.new_files)); }
+        if self.modified  > 0 { parts.push(format!("{} modified", self.modified)); }
+        if self.upgraded  > 0 { parts.push(format!("{} upgraded", self.upgraded)); }
+        if self.excluded  > 0 { parts.push(format!("{} excluded", self.excluded)); }
+        parts.join(", ")
+    }
+}
+
+/// Extra queued files per worker, so other kinds keep the workers busy while
+/// files of a capped kind wait for their turn.
+const QUEUE_PER_WORKER: usize = 4;
+
+/// Bounded pool that runs `extract_job` on worker tasks (`scan.workers`).
+/// A shared semaphore caps concurrent extractions overall, and per-kind
+/// semaphores (`scan.worker_limits`) stop one slow kind such as PDF from
+/// occupying every worker.
+struct ExtractPool {
+    workers:   Arc<Semaphore>,
+    kinds:     HashMap<String, Arc<Semaphore>>,
+    /// Files that may be queued or extracting before the oldest is committed.
+    queue_len: usize,
+    /// Extraction cache, and whether to read from it (not under `--force`,
+    /// which re-extracts everything but still refreshes the cache).
+    cache:     Option<(Arc<ExtractCache>, bool)>,
+}
+
+impl ExtractPool {
+    fn new(scan: &ScanConfig) -> Self {
+        let workers = scan.workers.max(1);
+        let kinds = scan.worker_limits.iter()
+            .filter(|(_, n)| **n > 0)
+            .map(|(kind, n)| (kind.to_lowercase(), Arc::new(Semaphore::new(*n))))
+            .collect();
+        ExtractPool {
+            workers: Arc::new(Semaphore::new(workers)),
+            kinds,
+            // One worker means plain sequential processing.
+            queue_len: if workers == 1 { 1 } else { workers * QUEUE_PER_WORKER },
+            cache: None,
+        }
+    }
+
+    fn spawn(&self, job: FileJob, quiet: bool) -> JoinHandle<(FileJob, Extracted)> {
+        let workers = Arc::clone(&self.workers);
+        let kind_limit = self.kinds.get(&job.kind.to_string()).cloned();
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            if job.reuse.is_some() {
+                return (job, Extracted::Reused);
+            }
+            // Wait for the kind's slot first so that a file held back by its
+            // kind cap does not tie up a worker.
+            let _kind_permit = match &kind_limit {
+                Some(sem) => sem.acquire().await.ok(),
+                None => None,
+            };
+            let _permit = workers.acquire().await;
+            let extracted = match cache {
+                Some((cache, read)) => extract_job_cached(&job, quiet, &cache, read).await,
+                None => extract_job(&job, quiet).await,
+            };
+            (job, extracted)
+        })
+    }
+}
+
+/// A file handed to the `ExtractPool`, waiting to be committed in walk order.
+struct PendingFile {
+    task:        JoinHandle<(FileJob, Extracted)>,
+    is_new:      bool,
+    is_upgraded: bool,
+}
+
+impl PendingFile {
+    async fn commit(self, ctx: &mut ScanContext<'_>, counts: &mut ScanCounts, subdir_rescan: bool) -> Result<()> {
+        let (job, extracted) = self.task.await.context("extraction task failed")?;
+        let rel_path = job.rel_path.clone();
+        let submitted = commit_job(ctx, job, extracted).await?;
+        ctx.last_done = Some(rel_path);
+        counts.record(submitted, self.is_new, self.is_upgraded, subdir_rescan);
+        Ok(())
+    }
+}
+
 /// Shared state used by `process_file` so it can be called from both the
 /// `run_scan` loop and the single-file entry point without threading a long
 /// parameter list through every call.
@@ -297,14 +568,35 @@ struct ScanContext<'a> {
     batch_bytes: usize,
     failures: Vec<IndexingFailure>,
     last_submit: std::time::Instant,
-    batch_size: usize,
-    batch_bytes_limit: usize,
+    /// Flush thresholds, adapted to measured request sizes.
+    sizer: BatchSizer,
     batch_interval: std::time::Duration,
     scan_arc: Arc<ScanConfig>,
     /// Keyed by raw Arc pointer — valid as long as the Arc lives in dir_scan_cache.
     dir_scan_cache: HashMap<PathBuf, Arc<ScanConfig>>,
     dir_excludes_cache: HashMap<*const ScanConfig, Arc<GlobSet>>,
     dir_includes_cache: HashMap<*const ScanConfig, Arc<GlobSet>>,
+    /// Upload original file bytes to the server's originals store.  Cleared
+    /// for the rest of the scan if the server reports the store as disabled.
+    upload_originals: bool,
+    /// Size and hash of each file's indexed version, for sending files that
+    /// only grew by appending as deltas.  Empty when every file is resent in
+    /// full (`--force`, single-file scans).
+    append_bases: HashMap<String, (u64, String)>,
+    /// Files to index from content the server already stores, found by
+    /// `negotiate_hashes`.
+    reuse: HashMap<String, Reuse>,
+    /// Indexed members of each archive, so a changed archive sends only the
+    /// members that differ.  Empty under `--force`.
+    archive_members: HashMap<String, KnownMembers>,
+    /// Checkpoint file and progress, saved after each submitted batch.
+    checkpoint: Option<(PathBuf, Checkpoint)>,
+    /// Last file, in walk order, whose content has been added to the batch.
+    last_done: Option<String>,
+    /// Extraction times of the files submitted so far, and how many files
+    /// failed, for the scan's health report.
+    extract_times: Vec<u64>,
+    failure_count: usize,
 }
 
 impl<'a> ScanContext<'a> {
@@ -333,13 +625,41 @@ impl<'a> ScanContext<'a> {
             batch_bytes: 0,
             failures: Vec::new(),
             last_submit: std::time::Instant::now(),
-            batch_size: scan.batch_size,
-            batch_bytes_limit: scan.batch_bytes,
+            sizer: BatchSizer::from_scan(scan),
             batch_interval: std::time::Duration::from_secs(scan.batch_interval_secs),
             scan_arc: Arc::new(scan.clone()),
             dir_scan_cache: HashMap::new(),
             dir_excludes_cache: HashMap::new(),
             dir_includes_cache: HashMap::new(),
+            upload_originals: scan.upload_originals,
+            append_bases: HashMap::new(),
+            reuse: HashMap::new(),
+            archive_members: HashMap::new(),
+            checkpoint: None,
+            last_done: None,
+            extract_times: Vec::new(),
+            failure_count: 0,
+        }
+    }
+
+    /// Upload `abs_path`'s original bytes under `hash` unless the server
+    /// already has them.  Failures are logged and never abort the scan.
+    async fn upload_original(&mut self, hash: Option<&str>, abs_path: &Path) {
+        let Some(hash) = hash.filter(|_| self.upload_originals) else { return };
+        let result = match self.api.original_status(hash).await {
+            Ok(OriginalUpload::Missing) => self.api.upload_original(hash, abs_path).await,
+            other => other,
+        };
+        match result {
+            Ok(OriginalUpload::Stored | OriginalUpload::Missing) => {}
+            Ok(OriginalUpload::TooLarge) => {
+                info!("not uploading original of {}: exceeds the server's size limit", abs_path.display());
+            }
+            Ok(OriginalUpload::Disabled) => {
+                warn!("server does not accept original uploads ([originals] enabled = false); skipping for the rest of this scan");
+                self.upload_originals = false;
+            }
+            Err(e) => warn!("failed to upload original of {}: {e:#}", abs_path.display()),
         }
     }
 
@@ -357,19 +677,45 @@ impl<'a> ScanContext<'a> {
             }
         }
         let scan_ts = self.emit_scan_timestamp.then_some(self.scan_start);
+        let files = self.batch.len();
+        self.extract_times.extend(self.batch.iter().filter_map(|f| f.extract_ms));
+        self.failure_count += self.failures.len();
         submit_batch(
             self.api, self.source_name,
             &mut self.batch, &mut self.failures,
             delete_paths, scan_ts,
         ).await?;
+        if let Some(payload) = self.api.take_last_bulk_size() {
+            self.sizer.observe(files, self.batch_bytes, payload);
+        }
         self.batch_bytes = 0;
         self.last_submit = std::time::Instant::now();
+        if let (Some((path, cp)), Some(done)) = (&mut self.checkpoint, &self.last_done) {
+            if cp.done_through != *done {
+                cp.done_through.clone_from(done);
+                if let Err(e) = checkpoint::save(path, cp) {
+                    warn!("failed to save scan checkpoint: {e:#}");
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Add `file` to the batch, submitting as the limits require.  A file
+    /// larger than the byte limit goes in a request of its own.
+    async fn add(&mut self, file: IndexFile) -> Result<()> {
+        let bytes = index_file_bytes(&file);
+        if self.sizer.is_oversized(bytes) && !self.batch.is_empty() {
+            self.submit(vec![]).await?;
+        }
+        self.batch_bytes += bytes;
+        self.batch.push(file);
+        self.maybe_flush().await
+    }
+
     async fn maybe_flush(&mut self) -> Result<()> {
-        if self.batch.len() >= self.batch_size
-            || self.batch_bytes >= self.batch_bytes_limit
+        if self.batch.len() >= self.sizer.file_limit()
+            || self.batch_bytes >= self.sizer.byte_limit()
             || (!self.batch.is_empty() && self.last_submit.elapsed() >= self.batch_interval)
         {
             self.submit(vec![]).await?;
@@ -389,12 +735,16 @@ pub struct ExtractedFile {
     pub lines:      Vec<IndexLine>,
     pub extract_ms: u64,
     pub is_new:     bool,
+    /// Content hash, computed alongside extraction (see `content_hash`).
+    pub file_hash:  Option<String>,
+    /// The extractor that produced `lines` (see `ExtractorRoute::provenance`).
+    pub provenance: Option<Provenance>,
 }
 
 /// Shared post-processing for non-archive extraction (both builtin and external-stdout).
 ///
-/// Applies kind refinement from `[FILE:mime]` lines, computes the content hash,
-/// builds `IndexFile`s, and pushes them into the batch.
+/// Applies kind refinement from `[FILE:mime]` lines, builds `IndexFile`s, and
+/// pushes them into the batch.
 async fn push_non_archive_files(
     ctx: &mut ScanContext<'_>,
     file: &ExtractedFile,
@@ -423,24 +773,35 @@ async fn push_non_archive_files(
     } else {
         file.kind.clone()
     };
-    // Hash raw file bytes for dedup (streaming to avoid OOM on large files).
-    // Skip only disk-image extensions that block File::open on Windows
-    // (live VHDX held by Hyper-V, mounted VMDK, etc.). Media files are always hashed.
-    let file_hash = if find_extract_dispatch::is_open_blocking_ext_path(&file.abs_path) {
-        None
-    } else {
-        hash_file(&file.abs_path)
-    };
+    let file_hash = file.file_hash.clone();
+    ctx.upload_original(file_hash.as_deref(), &file.abs_path).await;
+    let within_limit = (file.size as u64) <= ctx.scan_arc.max_content_size_mb * 1024 * 1024;
     let mut index_files = build_index_files(file.rel_path.clone(), file.mtime, file.size, kind, file.lines.clone());
     if let Some(f) = index_files.first_mut() {
         f.extract_ms = Some(file.extract_ms);
+        f.provenance = file.provenance.clone().map(|p| Provenance { truncated: !within_limit, ..p });
         f.file_hash = file_hash;
         f.is_new = file.is_new;
+        add_symlink_target(f, &file.abs_path, &ctx.scan_arc);
+    }
+    // A file that only grew since it was indexed (a log, typically) is sent
+    // as a delta holding just the new lines.  Not when extraction may have
+    // been truncated by the content limit or does not map lines one-to-one
+    // (Markdown front matter).
+    if let ([f], Some((base_size, base_hash))) = (index_files.as_mut_slice(), ctx.append_bases.get(&file.rel_path)) {
+        let is_markdown = file.abs_path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"));
+        if matches!(f.kind, FileKind::Text | FileKind::Code) && within_limit && !is_markdown {
+            if let Some(base_lines) = appended_since(&file.abs_path, *base_size, base_hash) {
+                f.lines.retain(|l| l.line_number >= LINE_CONTENT_START + base_lines);
+                f.append_to = Some(AppendBase { file_hash: base_hash.clone() });
+                tracing::debug!("{}: sending {} appended lines", file.rel_path, f.lines.len());
+            }
+        }
     }
     for f in index_files {
-        ctx.batch_bytes += index_file_bytes(&f);
-        ctx.batch.push(f);
-        ctx.maybe_flush().await?;
+        ctx.add(f).await?;
     }
     Ok(())
 }
@@ -462,7 +823,6 @@ fn detect_kind_from_magic(path: &std::path::Path) -> FileKind {
     FileKind::Unknown
 }
 
-/// Process one file: resolve its effective config, extract content via
 const SCAN_INLINE_SET: &[subprocess::InlineKind] = &[
     subprocess::InlineKind::Text,
     subprocess::InlineKind::Html,
@@ -470,11 +830,64 @@ const SCAN_INLINE_SET: &[subprocess::InlineKind] = &[
     subprocess::InlineKind::Office,
 ];
 
+/// A file whose effective config, kind and extraction route have been
+/// resolved by `plan_file`.  Owns everything `extract_job` needs so that
+/// extraction can run on a worker task.
+struct FileJob {
+    rel_path:     String,
+    abs_path:     PathBuf,
+    mtime:        i64,
+    size:         i64,
+    kind:         FileKind,
+    is_new:       bool,
+    eff_scan:     Arc<ScanConfig>,
+    eff_excludes: Arc<GlobSet>,
+    route:        subprocess::ExtractorRoute,
+    /// Index from stored content instead of extracting (see `negotiate_hashes`).
+    reuse:        Option<Reuse>,
+}
+
+/// Content the server already stores for a file, found by `negotiate_hashes`.
+struct Reuse {
+    hash:            String,
+    kind:            FileKind,
+    /// Scanner version to record: the stored one when the path itself was
+    /// indexed before, otherwise 0 so `--upgrade` re-extracts the file.
+    scanner_version: u32,
+}
+
+/// Result of `extract_job`.
+enum Extracted {
+    /// Output of a builtin subprocess or inline extractor, with the
+    /// extraction time in ms and the file's content hash.
+    Builtin(subprocess::SubprocessOutcome, u64, Option<String>),
+    /// Output of an external stdout-mode extractor, likewise.
+    External(subprocess::ExternalOutcome, u64, Option<String>),
+    /// Archives, tempdir-mode extractors and server-only files submit while
+    /// they extract, so `commit_job` extracts them in walk order.
+    Deferred,
+    /// Not extracted: the server already stores the content (`FileJob::reuse`).
+    Reused,
+}
+
+/// Process one file: resolve its effective config, extract content via
 /// subprocess, handle OOM server-fallback, and accumulate the result in the
-/// batch. Called from both the `run_scan` loop and `scan_single_file`.
+/// batch. Called from `scan_single_file`; `run_scan` runs the same three
+/// steps with extraction on an [`ExtractPool`].
 /// Returns `true` if the file was actually submitted to the server, `false` if
 /// it was excluded by a filter or skipped due to a missing extractor.
 async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path, mtime: i64, is_new: bool) -> Result<bool> {
+    let Some(job) = plan_file(ctx, rel_path, abs_path, mtime, is_new)? else {
+        return Ok(false);
+    };
+    let extracted = extract_job(&job, ctx.quiet).await;
+    commit_job(ctx, job, extracted).await
+}
+
+/// Resolve a file's effective config and kind, apply the per-directory
+/// include filter and pick its extraction route.  Cheap; runs in walk order.
+/// Returns `None` if the file is excluded.
+fn plan_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path, mtime: i64, is_new: bool) -> Result<Option<FileJob>> {
     // Resolve effective config for this file's directory (cached).
     let eff_scan = resolve_effective_scan(abs_path, ctx.paths, &ctx.scan_arc, &mut ctx.dir_scan_cache);
 
@@ -502,7 +915,7 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                 .map(|p| normalise_path_sep(&p.to_string_lossy()))
                 .unwrap_or_default();
             if !dir_includes.is_match(&*rel_to_dir) {
-                return Ok(false);
+                return Ok(None);
             }
         }
     }
@@ -534,18 +947,303 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
         info!("Processing {rel_path}");
     }
 
-    let resolved_route = magic_override_route.unwrap_or_else(|| {
+    let route = magic_override_route.unwrap_or_else(|| {
         subprocess::resolve_extractor(abs_path, &eff_scan, &eff_scan.extractor_dir, SCAN_INLINE_SET)
     });
-    match resolved_route {
+    Ok(Some(FileJob {
+        rel_path: rel_path.to_string(),
+        abs_path: abs_path.to_path_buf(),
+        mtime,
+        size,
+        kind,
+        is_new,
+        eff_scan,
+        eff_excludes,
+        route,
+        reuse: ctx.reuse.remove(rel_path),
+    }))
+}
+
+/// Run the extraction step of `job` for the routes that only produce lines:
+/// builtin subprocess, inline and external stdout extractors.  Touches no
+/// shared state, so it can run on a worker task.
+async fn extract_job(job: &FileJob, quiet: bool) -> Extracted {
+    if job.reuse.is_some() {
+        return Extracted::Reused;
+    }
+    let abs_path = job.abs_path.as_path();
+    match &job.route {
+        subprocess::ExtractorRoute::Subprocess(binary) => {
+            // dispatch_from_path handles MIME detection internally: it emits a
+            // [FILE:mime] line when no extractor matched the bytes, which
+            // push_non_archive_files uses to update the kind.
+            let t0 = std::time::Instant::now();
+            if quiet { lazy_header::set_pending(&abs_path.to_string_lossy()); }
+            let outcome = subprocess::extract_via_subprocess(abs_path, &job.eff_scan, binary).await;
+            if quiet { lazy_header::clear_pending(); }
+            Extracted::Builtin(outcome, t0.elapsed().as_millis() as u64, content_hash(abs_path))
+        }
+        subprocess::ExtractorRoute::Inline(inline_kind) => {
+            let t0 = std::time::Instant::now();
+            let (inline_kind, path) = (*inline_kind, job.abs_path.clone());
+            let ext_config = extractor_config_from_scan(&job.eff_scan);
+            // Inline extractors are synchronous; keep them off the async workers.
+            let lines = tokio::task::spawn_blocking(move || {
+                if quiet { lazy_header::set_pending(&path.to_string_lossy()); }
+                let lines = subprocess::extract_inline(inline_kind, &path, &ext_config);
+                if quiet { lazy_header::clear_pending(); }
+                lines
+            })
+            .await
+            .unwrap_or_default();
+            let extract_ms = t0.elapsed().as_millis() as u64;
+            Extracted::Builtin(subprocess::SubprocessOutcome::Ok(lines), extract_ms, content_hash(abs_path))
+        }
+        subprocess::ExtractorRoute::External(ext_cfg) if matches!(ext_cfg.mode, ExternalExtractorMode::Stdout) => {
+            let t0 = std::time::Instant::now();
+            if quiet { lazy_header::set_pending(&abs_path.to_string_lossy()); }
+            let outcome = subprocess::run_external_stdout(abs_path, ext_cfg, &job.eff_scan).await;
+            if quiet { lazy_header::clear_pending(); }
+            Extracted::External(outcome, t0.elapsed().as_millis() as u64, content_hash(abs_path))
+        }
+        _ => Extracted::Deferred,
+    }
+}
+
+/// `extract_job` through `cache`: files of the builtin subprocess and inline
+/// routes whose content was extracted before (as a duplicate, or under
+/// another name) are taken from the cache when `read` is set, and new
+/// successful extractions are added to it.
+async fn extract_job_cached(job: &FileJob, quiet: bool, cache: &Arc<ExtractCache>, read: bool) -> Extracted {
+    let route = match &job.route {
+        subprocess::ExtractorRoute::Subprocess(_) | subprocess::ExtractorRoute::Inline(_) if job.reuse.is_none() => &job.route,
+        _ => return extract_job(job, quiet).await,
+    };
+    let t0 = std::time::Instant::now();
+    let Some(file_hash) = content_hash(&job.abs_path) else {
+        return extract_job(job, quiet).await;
+    };
+    // Everything besides the content that the extracted lines depend on.
+    let ext = job.abs_path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let context = format!(
+        "{}|{route:?}|{ext}|{}|{}",
+        find_common::tool_version!(),
+        job.eff_scan.max_content_size_mb,
+        job.eff_scan.ffprobe_path.as_deref().unwrap_or(""),
+    );
+    let key = ExtractCache::key(&file_hash, &context);
+
+    if read {
+        let (cache, key) = (Arc::clone(cache), key.clone());
+        if let Ok(Some(lines)) = tokio::task::spawn_blocking(move || cache.get(&key)).await {
+            tracing::debug!("{}: extracted content taken from the cache", job.rel_path);
+            let extract_ms = t0.elapsed().as_millis() as u64;
+            return Extracted::Builtin(subprocess::SubprocessOutcome::Ok(lines), extract_ms, Some(file_hash));
+        }
+    }
+    let extracted = extract_job(job, quiet).await;
+    if let Extracted::Builtin(subprocess::SubprocessOutcome::Ok(lines), _, Some(hash)) = &extracted {
+        // extract_job hashes the file again after extracting it; a mismatch
+        // means it changed meanwhile and the lines may match neither hash.
+        if *hash == file_hash {
+            let (cache, lines) = (Arc::clone(cache), lines.clone());
+            let _ = tokio::task::spawn_blocking(move || cache.put(&key, &lines)).await;
+        }
+    }
+    extracted
+}
+
+/// Apply the `[scan] change_detection` policy to the files the server has
+/// indexed.  Returns the verdict for each file whose policy decision differs
+/// from "newer mtime means modified" (see `needs_reindex`), and, when
+/// `allow_reuse`, the files whose content is unchanged although their mtime
+/// moved: those are re-recorded from the stored content so the server learns
+/// the new mtime without extracting them again.
+async fn detect_changes(
+    policy: ChangeDetection,
+    local_files: &HashMap<String, PathBuf>,
+    server_list: &[FileRecord],
+    resume_after: Option<&str>,
+    allow_reuse: bool,
+) -> (HashMap<String, bool>, HashMap<String, Reuse>) {
+    let mut modified = HashMap::new();
+    let mut to_hash: Vec<(&FileRecord, PathBuf, bool)> = Vec::new();
+    for rec in server_list {
+        if resume_after.is_some_and(|done| rec.path.as_str() <= done) {
+            continue;
+        }
+        let Some(abs) = local_files.get(&rec.path) else { continue };
+        let (Some(local_size), Some(size)) = (size_of(abs), rec.size) else { continue };
+        if local_size != size {
+            modified.insert(rec.path.clone(), true);
+            continue;
+        }
+        let mtime_changed = mtime_of(abs).unwrap_or(0) != rec.mtime;
+        let hash = match policy {
+            ChangeDetection::Mtime => false,
+            ChangeDetection::Hybrid => mtime_changed,
+            ChangeDetection::Hash => true,
+        };
+        if hash && rec.file_hash.is_some() && rec.kind != FileKind::Archive {
+            to_hash.push((rec, abs.clone(), mtime_changed));
+        }
+    }
+
+    let mut reuse = HashMap::new();
+    if to_hash.is_empty() {
+        return (modified, reuse);
+    }
+    info!("hashing {} files to detect changes", to_hash.len());
+    let hashes = hash_all(to_hash.iter().map(|(_, abs, _)| abs.clone()).collect()).await;
+    for ((rec, _, mtime_changed), hash) in to_hash.into_iter().zip(hashes) {
+        let Some(hash) = hash else { continue };
+        if rec.file_hash.as_ref() != Some(&hash) {
+            modified.insert(rec.path.clone(), true);
+        } else if mtime_changed && allow_reuse {
+            modified.insert(rec.path.clone(), true);
+            reuse.insert(rec.path.clone(), Reuse {
+                hash,
+                kind: rec.kind.clone(),
+                scanner_version: rec.scanner_version,
+            });
+        } else {
+            modified.insert(rec.path.clone(), false);
+        }
+    }
+    (modified, reuse)
+}
+
+/// Hash the new and modified files this scan is about to extract and find
+/// those whose content the server already stores (`[scan] hash_negotiation`):
+/// the indexed version of the same path when only its mtime changed, or the
+/// same bytes indexed under another path or source.
+async fn negotiate_hashes(
+    api: &ApiClient,
+    local_files: &HashMap<String, PathBuf>,
+    server_list: &[FileRecord],
+    resume_after: Option<&str>,
+    same_path: bool,
+) -> HashMap<String, Reuse> {
+    let server: HashMap<&str, &FileRecord> = server_list.iter().map(|f| (f.path.as_str(), f)).collect();
+    let mut same_path: Vec<(&str, PathBuf, &FileRecord)> = Vec::new();
+    let mut new_path: Vec<(&str, PathBuf, FileKind)> = Vec::new();
+    let mut entries: Vec<(&String, &PathBuf)> = local_files.iter()
+        .filter(|(rel, _)| resume_after.is_none_or(|done| rel.as_str() > done))
+        .collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    for (rel, abs) in entries {
+        match server.get(rel.as_str()) {
+            Some(rec) => {
+                let modified = mtime_of(abs).unwrap_or(0) > rec.mtime;
+                if same_path && modified && rec.kind != FileKind::Archive && rec.file_hash.is_some() && size_of(abs) == rec.size {
+                    same_path.push((rel, abs.clone(), rec));
+                }
+            }
+            None => {
+                let kind = FileKind::from(extract::detect_kind(abs));
+                if !matches!(kind, FileKind::Archive | FileKind::Unknown) {
+                    new_path.push((rel, abs.clone(), kind));
+                }
+            }
+        }
+    }
+
+    let mut reuse = HashMap::new();
+    // Only the mtime changed (a restore or copy that did not keep mtimes).
+    let hashes = hash_all(same_path.iter().map(|(_, abs, _)| abs.clone()).collect()).await;
+    for ((rel, _, rec), hash) in same_path.into_iter().zip(hashes) {
+        if let Some(hash) = hash.filter(|h| rec.file_hash.as_ref() == Some(h)) {
+            reuse.insert(rel.to_string(), Reuse {
+                hash,
+                kind: rec.kind.clone(),
+                scanner_version: rec.scanner_version,
+            });
+        }
+    }
+
+    // New paths: ask the server, a chunk at a time.
+    let mut any_known = false;
+    for chunk in new_path.chunks(NEGOTIATE_CHUNK) {
+        let hashes = hash_all(chunk.iter().map(|(_, abs, _)| abs.clone()).collect()).await;
+        let known = match api.known_hashes(hashes.iter().flatten().cloned().collect()).await {
+            Ok(known) => known,
+            Err(e) => {
+                warn!("hash negotiation failed; extracting files as usual: {e:#}");
+                break;
+            }
+        };
+        if known.is_empty() && !any_known {
+            // The server holds none of this content (a fresh server,
+            // typically): extract the rest without hashing it twice.
+            break;
+        }
+        any_known = true;
+        for ((rel, _, kind), hash) in chunk.iter().zip(hashes) {
+            if let Some(hash) = hash.filter(|h| known.contains(h)) {
+                reuse.insert(rel.to_string(), Reuse { hash, kind: kind.clone(), scanner_version: 0 });
+            }
+        }
+    }
+    reuse
+}
+
+/// `content_hash` of each path, off the async workers.
+async fn hash_all(paths: Vec<PathBuf>) -> Vec<Option<String>> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+    let n = paths.len();
+    tokio::task::spawn_blocking(move || paths.iter().map(|p| content_hash(p)).collect())
+        .await
+        .unwrap_or_else(|_| vec![None; n])
+}
+
+/// Hash raw file bytes for dedup (streaming to avoid OOM on large files).
+/// Skip only disk-image extensions that block File::open on Windows
+/// (live VHDX held by Hyper-V, mounted VMDK, etc.). Media files are always hashed.
+fn content_hash(abs_path: &Path) -> Option<String> {
+    if find_extract_dispatch::is_open_blocking_ext_path(abs_path) {
+        None
+    } else {
+        hash_file(abs_path)
+    }
+}
+
+/// Accumulate the result of `job` in the batch: record failures, fall back to
+/// server-side extraction, and run the streaming routes (`Extracted::Deferred`).
+/// Must be called in walk order from the task that owns the batch.
+async fn commit_job(ctx: &mut ScanContext<'_>, job: FileJob, extracted: Extracted) -> Result<bool> {
+    let FileJob { rel_path, abs_path, mtime, size, kind, is_new, eff_scan, eff_excludes, route, reuse } = job;
+    let (rel_path, abs_path) = (rel_path.as_str(), abs_path.as_path());
+    if let Some(reuse) = reuse {
+        tracing::debug!("{rel_path}: indexing from stored content {}", reuse.hash);
+        ctx.upload_original(Some(&reuse.hash), abs_path).await;
+        let file = IndexFile {
+            path: rel_path.to_string(),
+            mtime,
+            size: Some(size),
+            kind: reuse.kind,
+            lines: vec![],
+            extract_ms: None,
+            file_hash: Some(reuse.hash),
+            scanner_version: reuse.scanner_version,
+            is_new,
+            force: false,
+            append_to: None,
+            reuse_content: true,
+            provenance: None,
+        };
+        ctx.add(file).await?;
+        return Ok(true);
+    }
+    match route {
         subprocess::ExtractorRoute::External(ref ext_cfg) => {
             match ext_cfg.mode {
                 ExternalExtractorMode::Stdout => {
-                    // ── External stdout extraction ────────────────────────────────
-                    let t0 = std::time::Instant::now();
-                    if ctx.quiet { lazy_header::set_pending(&abs_path.to_string_lossy()); }
-                    let outcome = subprocess::run_external_stdout(abs_path, ext_cfg, &eff_scan).await;
-                    if ctx.quiet { lazy_header::clear_pending(); }
+                    // ── External stdout extraction (done by extract_job) ──────────
+                    let Extracted::External(outcome, extract_ms, file_hash) = extracted else {
+                        unreachable!("stdout-mode extractors run in extract_job");
+                    };
 
                     let lines = match outcome {
                         subprocess::ExternalOutcome::Ok(lines) => lines,
@@ -575,7 +1273,6 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                         }
                     };
 
-                    let extract_ms = t0.elapsed().as_millis() as u64;
                     push_non_archive_files(ctx, &ExtractedFile {
                         rel_path: rel_path.to_string(),
                         abs_path: abs_path.to_path_buf(),
@@ -585,6 +1282,8 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                         lines,
                         extract_ms,
                         is_new,
+                        file_hash,
+                        provenance: route.provenance(),
                     }).await?;
                 }
                 ExternalExtractorMode::TempDir => {
@@ -607,6 +1306,9 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                         scanner_version: SCANNER_VERSION,
                         is_new,
                         force: false,
+                        append_to: None,
+                        reuse_content: false,
+                        provenance: None,
                     };
                     ctx.batch.push(outer_start);
                     ctx.submit(vec![]).await?;
@@ -645,10 +1347,8 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                     let mut members_submitted: usize = 0;
                     for batch in member_batches {
                         for file in build_member_index_files(rel_path, mtime, batch.size, batch.lines, batch.file_hash) {
-                            ctx.batch_bytes += index_file_bytes(&file);
                             members_submitted += 1;
-                            ctx.batch.push(file);
-                            ctx.maybe_flush().await?;
+                            ctx.add(file).await?;
                         }
                     }
 
@@ -658,6 +1358,8 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                         ctx.submit(vec![]).await?;
                     }
 
+                    ctx.upload_original(outer_hash.as_deref(), abs_path).await;
+
                     // Completion upsert: real mtime so next scan skips re-indexing.
                     ctx.batch.push(IndexFile {
                         path: rel_path.to_string(),
@@ -670,6 +1372,9 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                         scanner_version: SCANNER_VERSION,
                         is_new,
                         force: false,
+                        append_to: None,
+                        reuse_content: false,
+                        provenance: route.provenance(),
                     });
                 }
             }
@@ -686,25 +1391,36 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                 // Hash the outer archive file for dedup (streaming to avoid OOM on large archives).
                 let outer_hash = hash_file(abs_path);
 
-                // Submit the outer archive file with mtime=0 (sentinel: members not yet indexed).
-                // The server deletes stale inner members when it receives mtime=0 for an outer
-                // archive, so this must arrive before member batches.  Using mtime=0 means that
-                // if indexing is interrupted before the completion upsert below, the next scan
-                // will see a mtime mismatch (any real mtime > 0) and re-index the archive.
-                let outer_start = IndexFile {
-                    path: rel_path.to_string(),
-                    mtime: 0,
-                    size: Some(size),
-                    kind: kind.clone(),
-                    lines: vec![IndexLine { archive_path: None, line_number: 0, content: format!("[PATH] {}", rel_path) }],
-                    extract_ms: None,
-                    file_hash: None, // no hash on start sentinel — avoids premature dedup alias
-                    scanner_version: SCANNER_VERSION,
-                    is_new,
-                    force: false,
-                };
-                ctx.batch.push(outer_start);
-                ctx.submit(vec![]).await?;
+                // A changed archive whose members the server already holds is
+                // updated in place: unchanged members are skipped and the ones
+                // that are gone deleted, instead of replacing every member.
+                let mut known = ctx.archive_members.remove(rel_path).filter(|k| !is_new && !k.is_empty());
+                let mut unchanged_members: usize = 0;
+
+                // Otherwise submit the outer archive file with mtime=0 (sentinel: members not
+                // yet indexed).  The server deletes stale inner members when it receives mtime=0
+                // for an outer archive, so this must arrive before member batches.  Using mtime=0
+                // means that if indexing is interrupted before the completion upsert below, the
+                // next scan will see a mtime mismatch (any real mtime > 0) and re-index the archive.
+                if known.is_none() {
+                    let outer_start = IndexFile {
+                        path: rel_path.to_string(),
+                        mtime: 0,
+                        size: Some(size),
+                        kind: kind.clone(),
+                        lines: vec![IndexLine { archive_path: None, line_number: 0, content: format!("[PATH] {}", rel_path) }],
+                        extract_ms: None,
+                        file_hash: None, // no hash on start sentinel — avoids premature dedup alias
+                        scanner_version: SCANNER_VERSION,
+                        is_new,
+                        force: false,
+                        append_to: None,
+                        reuse_content: false,
+                        provenance: None,
+                    };
+                    ctx.batch.push(outer_start);
+                    ctx.submit(vec![]).await?;
+                }
 
                 if ctx.quiet { lazy_header::set_pending(&abs_path.to_string_lossy()); }
                 let (mut member_rx, subprocess_task) = subprocess::start_archive_subprocess(
@@ -760,20 +1476,25 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                     let member_archive_path = member_batch.lines.first()
                         .and_then(|l| l.archive_path.as_deref())
                         .map(|ap| format!("{rel_path}::{ap}"));
+                    let mut member_changed = false;
                     for file in build_member_index_files(rel_path, member_mtime, member_batch.size, member_batch.lines, file_hash) {
-                        ctx.batch_bytes += index_file_bytes(&file);
+                        if known.as_mut().is_some_and(|k| k.unchanged(&file)) {
+                            unchanged_members += 1;
+                            continue;
+                        }
+                        member_changed = true;
                         members_submitted += 1;
-                        ctx.batch.push(file);
-                        ctx.maybe_flush().await?;
+                        ctx.add(file).await?;
                     }
                     // Upload delegated members to the server for server-side extraction.
                     // This runs after the filename-only batch is submitted so the member
                     // is already findable by name even if the upload fails.
                     if let (Some(tmp), Some(composite_path)) = (delegate_temp_path, member_archive_path) {
                         let tmp_path = std::path::Path::new(&tmp);
-                        match upload::upload_file(ctx.api, tmp_path, &composite_path, member_mtime, ctx.source_name, hints_from_scan(&eff_scan)).await {
-                            Ok(()) => {}
-                            Err(e) => warn!("server-only member upload failed for {composite_path}: {e:#}"),
+                        if member_changed {
+                            if let Err(e) = upload::upload_file(ctx.api, tmp_path, &composite_path, member_mtime, ctx.source_name, hints_from_scan(&eff_scan)).await {
+                                warn!("server-only member upload failed for {composite_path}: {e:#}");
+                            }
                         }
                         // Always clean up the temp dir (contains the single member file).
                         if let Some(parent) = tmp_path.parent() {
@@ -785,17 +1506,28 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                 if ctx.quiet { lazy_header::clear_pending(); }
 
                 // Check whether the subprocess exited successfully.
-                if !subprocess_task.await.unwrap_or(false) && ctx.failures.len() < MAX_FAILURES_PER_BATCH {
+                let extracted_ok = subprocess_task.await.unwrap_or(false);
+                if !extracted_ok && ctx.failures.len() < MAX_FAILURES_PER_BATCH {
                     ctx.failures.push(IndexingFailure {
                         path: rel_path.to_string(),
                         error: "archive extraction subprocess failed".to_string(),
                     });
                 }
 
+                // Members the archive no longer holds.  After a failed extraction the
+                // unseen members may simply not have been reached, so keep them.
+                let removed_members = match known {
+                    Some(known) if extracted_ok => known.removed(),
+                    _ => vec![],
+                };
+                if unchanged_members > 0 || !removed_members.is_empty() {
+                    info!("{rel_path}: {unchanged_members} members unchanged, {members_submitted} sent, {} removed", removed_members.len());
+                }
+
                 // Flush any remaining archive members (partial final batch).
-                if !ctx.batch.is_empty() {
+                if !ctx.batch.is_empty() || !removed_members.is_empty() {
                     info!("submitting batch — extracting {rel_path} ({} members, {members_submitted} total)", ctx.batch.len());
-                    ctx.submit(vec![]).await?;
+                    ctx.submit(removed_members).await?;
                 }
 
                 // Completion upsert: update the outer file with its real mtime now that
@@ -825,6 +1557,7 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                 } else {
                     kind
                 };
+                ctx.upload_original(outer_hash.as_deref(), abs_path).await;
                 ctx.batch.push(IndexFile {
                     path: rel_path.to_string(),
                     mtime,
@@ -836,18 +1569,16 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                     scanner_version: SCANNER_VERSION,
                     is_new,
                     force: false,
+                    append_to: None,
+                    reuse_content: false,
+                    provenance: route.provenance(),
                 });
         }
-        subprocess::ExtractorRoute::Subprocess(ref binary) => {
-            // ── Non-archive extraction ────────────────────────────────────────────
-            // dispatch_from_path handles MIME detection internally: it emits a
-            // [FILE:mime] line when no extractor matched the bytes, so we check
-            // for that line below to update the kind accordingly.
-            let t0 = std::time::Instant::now();
-            if ctx.quiet { lazy_header::set_pending(&abs_path.to_string_lossy()); }
-            let outcome = subprocess::extract_via_subprocess(
-                abs_path, &eff_scan, binary).await;
-            if ctx.quiet { lazy_header::clear_pending(); }
+        subprocess::ExtractorRoute::Subprocess(_) | subprocess::ExtractorRoute::Inline(_) => {
+            // ── Non-archive extraction (done by extract_job) ──────────────────────
+            let Extracted::Builtin(outcome, extract_ms, file_hash) = extracted else {
+                unreachable!("builtin and inline extractors run in extract_job");
+            };
 
             let lines = match outcome {
                 subprocess::SubprocessOutcome::Ok(lines) => lines,
@@ -872,7 +1603,6 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                 }
             };
 
-            let extract_ms = t0.elapsed().as_millis() as u64;
             push_non_archive_files(ctx, &ExtractedFile {
                 rel_path: rel_path.to_string(),
                 abs_path: abs_path.to_path_buf(),
@@ -882,6 +1612,8 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
                 lines,
                 extract_ms,
                 is_new,
+                file_hash,
+                provenance: route.provenance(),
             }).await?;
         }
         subprocess::ExtractorRoute::ServerOnly => {
@@ -891,28 +1623,6 @@ async fn process_file(ctx: &mut ScanContext<'_>, rel_path: &str, abs_path: &Path
             }
             return Ok(true);
         }
-        subprocess::ExtractorRoute::Inline(inline_kind) => {
-            // `inline_kind` is the InlineKind enum variant (bound here to avoid shadowing
-            // the outer `kind: String` computed from detect_kind on line 473).
-            let t0 = std::time::Instant::now();
-            if ctx.quiet { lazy_header::set_pending(&abs_path.to_string_lossy()); }
-            let ext_config = extractor_config_from_scan(&eff_scan);
-            let lines = subprocess::extract_inline(inline_kind, abs_path, &ext_config);
-            if ctx.quiet { lazy_header::clear_pending(); }
-
-            let extract_ms = t0.elapsed().as_millis() as u64;
-            // `kind` here is the outer FileKind variable, not the InlineKind.
-            push_non_archive_files(ctx, &ExtractedFile {
-                rel_path: rel_path.to_string(),
-                abs_path: abs_path.to_path_buf(),
-                mtime,
-                size,
-                kind,
-                lines,
-                extract_ms,
-                is_new,
-            }).await?;
-        }
     }
 
     ctx.maybe_flush().await?;
@@ -1045,62 +1755,6 @@ fn resolve_effective_scan(
     eff
 }
 
-/// Returns a map of relative_path → absolute_path for all files under `paths`.
-///
-/// `includes` is empty when no include filter is configured (all files pass).
-/// `include_dirs` is the terminal set from `include_dir_prefixes`; if `None`,
-/// no directory pruning is applied (patterns like `**/*.rs` can match anywhere).
-fn walk_paths(
-    paths: &[String],
-    scan: &ScanConfig,
-    excludes: &GlobSet,
-    includes: &GlobSet,
-    include_dirs: Option<&std::collections::HashSet<String>>,
-    subdir: Option<&str>,
-) -> HashMap<String, PathBuf> {
-    let mut map = HashMap::new();
-    let log_interval = std::time::Duration::from_secs(5);
-    let mut last_log = std::time::Instant::now();
-
-    for root_str in paths {
-        let root_str = normalise_root(root_str);
-        let root = PathBuf::from(&root_str);
-        // When scanning a subdir, walk from root/subdir but compute rel-paths
-        // relative to root so they match what the server already stores.
-        let walk_start = match subdir {
-            Some(sub) => { let mut p = root.clone(); p.push(sub); p }
-            None => root.clone(),
-        };
-
-        crate::walk::walk_source_tree(
-            &walk_start,
-            &root,
-            scan,
-            excludes,
-            include_dirs,
-            |item| {
-                let crate::walk::WalkItem::File { abs, rel, name, depth } = item else { return; };
-                // Hidden files (hidden directories already pruned in walk_source_tree).
-                if !scan.include_hidden && name.starts_with('.') && depth > 0 {
-                    return;
-                }
-                // Apply source-level include filter.
-                if !includes.is_empty() && !includes.is_match(&*rel) {
-                    return;
-                }
-                map.insert(rel, abs);
-                if last_log.elapsed() >= log_interval {
-                    info!("walking filesystem... {} files found so far", map.len());
-                    last_log = std::time::Instant::now();
-                }
-            },
-        );
-    }
-
-    map
-}
-
-
 use crate::path_util::{normalise_path_sep, normalise_root};
 
 fn mtime_of(path: &Path) -> Option<i64> {
@@ -1270,7 +1924,7 @@ mod tests {
     #[test]
     fn needs_reindex_new_file() {
         // File not on server → should index, is_new = true
-        let (idx, is_new) = needs_reindex(None, 1000, false, None);
+        let (idx, is_new) = needs_reindex(None, 1000, None, false, None);
         assert!(idx);
         assert!(is_new);
     }
@@ -1278,7 +1932,7 @@ mod tests {
     #[test]
     fn needs_reindex_mtime_newer() {
         // Local mtime is newer than server mtime → re-index, not new
-        let (idx, is_new) = needs_reindex(Some((500, 1, None)), 1000, false, None);
+        let (idx, is_new) = needs_reindex(Some((500, 1, None)), 1000, None, false, None);
         assert!(idx);
         assert!(!is_new);
     }
@@ -1286,21 +1940,21 @@ mod tests {
     #[test]
     fn needs_reindex_mtime_equal() {
         // Same mtime → skip
-        let (idx, _) = needs_reindex(Some((1000, 1, None)), 1000, false, None);
+        let (idx, _) = needs_reindex(Some((1000, 1, None)), 1000, None, false, None);
         assert!(!idx);
     }
 
     #[test]
     fn needs_reindex_mtime_older() {
         // Local mtime is older than server (clock skew / rollback) → skip
-        let (idx, _) = needs_reindex(Some((2000, 1, None)), 1000, false, None);
+        let (idx, _) = needs_reindex(Some((2000, 1, None)), 1000, None, false, None);
         assert!(!idx);
     }
 
     #[test]
     fn needs_reindex_upgrade_outdated_scanner() {
         // upgrade=true and server has an older scanner version → re-index
-        let (idx, is_new) = needs_reindex(Some((1000, 0, None)), 1000, true, None);
+        let (idx, is_new) = needs_reindex(Some((1000, 0, None)), 1000, None, true, None);
         assert!(idx);
         assert!(!is_new);
     }
@@ -1308,14 +1962,14 @@ mod tests {
     #[test]
     fn needs_reindex_upgrade_current_scanner() {
         // upgrade=true but scanner version is current → skip
-        let (idx, _) = needs_reindex(Some((1000, SCANNER_VERSION, None)), 1000, true, None);
+        let (idx, _) = needs_reindex(Some((1000, SCANNER_VERSION, None)), 1000, None, true, None);
         assert!(!idx);
     }
 
     #[test]
     fn needs_reindex_no_upgrade_flag_ignores_scanner_version() {
         // upgrade=false → scanner version difference is ignored
-        let (idx, _) = needs_reindex(Some((1000, 0, None)), 1000, false, None);
+        let (idx, _) = needs_reindex(Some((1000, 0, None)), 1000, None, false, None);
         assert!(!idx);
     }
 
@@ -1324,7 +1978,7 @@ mod tests {
         // Composite paths (archive members) are never in server_files (filtered
         // out before building the map), so they would always arrive as None.
         // Confirm that needs_reindex treats them as new files.
-        let (idx, is_new) = needs_reindex(None, 500, false, None);
+        let (idx, is_new) = needs_reindex(None, 500, None, false, None);
         assert!(idx);
         assert!(is_new);
     }
@@ -1332,7 +1986,7 @@ mod tests {
     #[test]
     fn needs_reindex_force_since_no_indexed_at() {
         // force_since set, file has no indexed_at (never force-indexed) → re-index
-        let (idx, is_new) = needs_reindex(Some((1000, SCANNER_VERSION, None)), 1000, false, Some(1_000_000));
+        let (idx, is_new) = needs_reindex(Some((1000, SCANNER_VERSION, None)), 1000, None, false, Some(1_000_000));
         assert!(idx);
         assert!(!is_new);
     }
@@ -1340,14 +1994,14 @@ mod tests {
     #[test]
     fn needs_reindex_force_since_already_done() {
         // force_since set, indexed_at >= force_since → skip (already done this run)
-        let (idx, _) = needs_reindex(Some((1000, SCANNER_VERSION, Some(1_000_001))), 1000, false, Some(1_000_000));
+        let (idx, _) = needs_reindex(Some((1000, SCANNER_VERSION, Some(1_000_001))), 1000, None, false, Some(1_000_000));
         assert!(!idx);
     }
 
     #[test]
     fn needs_reindex_force_since_not_yet_done() {
         // force_since set, indexed_at < force_since → re-index
-        let (idx, is_new) = needs_reindex(Some((1000, SCANNER_VERSION, Some(999_999))), 1000, false, Some(1_000_000));
+        let (idx, is_new) = needs_reindex(Some((1000, SCANNER_VERSION, Some(999_999))), 1000, None, false, Some(1_000_000));
         assert!(idx);
         assert!(!is_new);
     }
@@ -1355,7 +2009,21 @@ mod tests {
     #[test]
     fn needs_reindex_force_none_does_not_force() {
         // force_since = None → indexed_at is irrelevant, mtime-equal file skipped
-        let (idx, _) = needs_reindex(Some((1000, SCANNER_VERSION, Some(1))), 1000, false, None);
+        let (idx, _) = needs_reindex(Some((1000, SCANNER_VERSION, Some(1))), 1000, None, false, None);
         assert!(!idx);
     }
+
+    #[test]
+    fn needs_reindex_policy_verdict_overrides_mtime() {
+        // A hash policy found an older-mtime file modified (restored backup)…
+        let (idx, is_new) = needs_reindex(Some((2000, 1, None)), 1000, Some(true), false, None);
+        assert!(idx);
+        assert!(!is_new);
+        // …or a newer-mtime file unchanged (touched).
+        let (idx, _) = needs_reindex(Some((1000, 1, None)), 2000, Some(false), false, None);
+        assert!(!idx);
+        // Upgrade still applies to unchanged files.
+        let (idx, _) = needs_reindex(Some((1000, 0, None)), 2000, Some(false), true, None);
+        assert!(idx);
+    }
 }
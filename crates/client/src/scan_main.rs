@@ -1,8 +1,16 @@
-mod api;
-mod batch;
+mod checkpoint;
+mod cloud;
+mod cloud_auth;
+mod completions;
 mod extract;
+mod extract_cache;
+mod git_history;
+mod ignore_files;
 mod lazy_header;
+mod mount_check;
 mod path_util;
+mod remote;
+mod s3;
 mod scan;
 mod subprocess;
 mod upload;
@@ -15,7 +23,8 @@ use clap::{CommandFactory, FromArgMatches, Parser};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
-use find_common::config::{default_config_path, parse_client_config};
+use find_client_lib::{api, apply_remote_config, batch, load_config, pause, spool, throttle};
+use find_common::config::{default_config_path, default_state_dir};
 use find_common::logging::LogIgnoreFilter;
 use scan::{ScanOptions, ScanSource};
 
@@ -43,6 +52,12 @@ struct Args {
     #[arg(long, value_name = "TIME", num_args = 0..=1, default_missing_value = "now")]
     force: Option<String>,
 
+    /// Start from the beginning even if a previous scan was interrupted.
+    /// By default an interrupted scan resumes after the last batch the
+    /// server accepted; this discards that checkpoint.
+    #[arg(long)]
+    full: bool,
+
     /// Suppress per-file processing logs (only log warnings, errors, and summary)
     #[arg(long)]
     quiet: bool,
@@ -61,6 +76,23 @@ struct Args {
     #[arg(value_name = "PATH")]
     path: Option<PathBuf>,
 
+    /// Scan only the configured source with this name.
+    #[arg(long, value_name = "NAME", conflicts_with = "path")]
+    source: Option<String>,
+
+    /// Leave out sources that have their own `scan_schedule`.  find-watch's
+    /// `[watch] scan_interval_hours` scan passes this, since it runs those
+    /// sources on their schedule instead.
+    #[arg(long, conflicts_with_all = ["path", "source"])]
+    skip_scheduled: bool,
+
+    /// With --source, scan only this subdirectory (relative to the source
+    /// root) and reconcile deletions beneath it — e.g. after moving files
+    /// around. Unlike the PATH argument, unchanged files are skipped, and the
+    /// directory need not exist any more (its indexed files are then removed).
+    #[arg(long = "path", value_name = "SUBDIR", requires = "source", conflicts_with = "path")]
+    subtree: Option<String>,
+
     /// Override the mtime stored for the indexed file (Unix seconds).
     /// Only valid with a single-file PATH argument.
     /// Used by the upload delegation path so find-scan stores the original
@@ -120,20 +152,23 @@ async fn main() -> Result<()> {
         .with(tracing_subscriber::fmt::layer().with_filter(LogIgnoreFilter))
         .init();
 
+    completions::handle(Args::command());
     let args = Args::from_arg_matches(&Args::command().version(find_common::tool_version!()).get_matches()).unwrap_or_else(|e| e.exit());
 
     let config_path = args.config.unwrap_or_else(default_config_path);
-    let config_str = std::fs::read_to_string(&config_path)
-        .with_context(|| format!("reading config {config_path}"))?;
-    let (config, config_warnings) = parse_client_config(&config_str)?;
+    let (mut config, config_warnings) = load_config(&config_path)?;
     for w in &config_warnings { eprintln!("Warning: {w}"); }
 
     if let Err(e) = find_common::logging::set_ignore_patterns(&config.log.ignore) {
         tracing::warn!("invalid log ignore pattern: {e}");
     }
 
-    let client = api::ApiClient::new(&config.server.url, &config.server.token);
+    let client = api::ApiClient::new(&config.server.url, &config.server.token)
+        .with_throttle(throttle::UploadThrottle::from_scan(&config.scan)?)
+        .with_pause(pause::PauseControl::from_scan(&config.scan)?)
+        .with_spool(spool::Spool::from_scan(&config.scan, default_state_dir().join("spool").join("find-scan"))?);
     client.check_server_version().await?;
+    apply_remote_config(&mut config, &client).await;
 
     if config.sources.is_empty() {
         tracing::info!("No sources configured — nothing to scan.");
@@ -174,6 +209,10 @@ async fn main() -> Result<()> {
         force_since,
         mtime_override: args.mtime,
         force_index: force_since.is_some(),
+        checkpoint_dir: Some(default_state_dir().join("checkpoints")),
+        extract_cache_dir: Some(default_state_dir().join("extract-cache")),
+        full: args.full,
+        report_metrics: config.server.report_metrics,
     };
 
     // Single-file mode: scan one specific file and exit.
@@ -220,10 +259,12 @@ async fn main() -> Result<()> {
                 paths: std::slice::from_ref(&source.path),
                 include: &source.include,
                 subdir: None,
+                rescan_subdir: false,
             };
-            scan::scan_single_file(&client, &scan_source, &rel_path, &abs, &config.scan, &opts).await?;
+            scan::scan_single_file(&client, &scan_source, &rel_path, &abs, &source.scan_config(&config.scan), &opts).await?;
         } else {
             // Directory: rescan all files under it, ignoring mtime.
+            mount_check::check_source_root(source)?;
             let rel_path = path_util::normalise_path_sep(&rel.to_string_lossy());
             let subdir = if rel_path.is_empty() { None } else { Some(rel_path.clone()) };
             let subdir_label = if rel_path.is_empty() { "(source root)" } else { &rel_path };
@@ -236,23 +277,92 @@ async fn main() -> Result<()> {
                 paths: std::slice::from_ref(&source.path),
                 include: &source.include,
                 subdir,
+                rescan_subdir: true,
             };
-            scan::run_scan(&client, &scan_source, &config.scan, &opts).await?;
+            scan::run_scan(&client, &scan_source, &source.scan_config(&config.scan), &opts).await?;
         }
         return Ok(());
     }
 
-    // Scan all configured sources
+    // Partial scan: one named source, optionally restricted to a subtree.
+    if let Some(name) = &args.source {
+        let source = config.sources.iter().find(|s| s.name == *name).ok_or_else(|| {
+            let names = config.sources.iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            anyhow::anyhow!("no source named {name:?}\nConfigured sources: {names}")
+        })?;
+        if source.s3_location().is_some() {
+            anyhow::ensure!(args.subtree.is_none(), "--path is not supported for S3 sources");
+            tracing::info!("Scanning source: {} ({})", source.name, source.path);
+            return s3::scan_source(&client, source, &source.scan_config(&config.scan), &opts).await;
+        }
+        if source.cloud_location().is_some() {
+            anyhow::ensure!(args.subtree.is_none(), "--path is not supported for cloud drive sources");
+            tracing::info!("Scanning source: {} ({})", source.name, source.path);
+            return cloud::scan_source(&client, source, &source.scan_config(&config.scan), &opts).await;
+        }
+        // A missing subtree is fine (its files get deleted), but a missing
+        // or unmounted source root would wrongly delete the whole subtree
+        // from the index.
+        mount_check::check_source_root(source)?;
+        let subdir = match args.subtree.as_deref() {
+            None => None,
+            Some(p) => {
+                let sub = path_util::normalise_subdir(p).ok_or_else(|| {
+                    anyhow::anyhow!("--path {p:?} must be relative to the source root and must not contain '..'")
+                })?;
+                (!sub.is_empty()).then_some(sub)
+            }
+        };
+        tracing::info!(
+            "Scanning source: {} (subdir: {})",
+            source.name, subdir.as_deref().unwrap_or("(source root)")
+        );
+        let scan_source = ScanSource {
+            name: &source.name,
+            paths: std::slice::from_ref(&source.path),
+            include: &source.include,
+            subdir,
+            rescan_subdir: false,
+        };
+        scan::run_scan(&client, &scan_source, &source.scan_config(&config.scan), &opts).await?;
+        return Ok(());
+    }
+
+    // Scan all configured sources.  One that looks unmounted is skipped, so
+    // its files are not all deleted from the index, and the others still run.
+    let mut unavailable = Vec::new();
     for source in &config.sources {
+        if args.skip_scheduled && source.scan_schedule.is_some() {
+            tracing::info!("Skipping source {} (it has its own scan_schedule)", source.name);
+            continue;
+        }
         tracing::info!("Scanning source: {}", source.name);
+        if source.s3_location().is_some() {
+            s3::scan_source(&client, source, &source.scan_config(&config.scan), &opts).await?;
+            continue;
+        }
+        if source.cloud_location().is_some() {
+            cloud::scan_source(&client, source, &source.scan_config(&config.scan), &opts).await?;
+            continue;
+        }
+        if let Err(e) = mount_check::check_source_root(source) {
+            tracing::error!("Skipping source {}: {e:#}", source.name);
+            unavailable.push(source.name.as_str());
+            continue;
+        }
         let scan_source = ScanSource {
             name: &source.name,
             paths: std::slice::from_ref(&source.path),
             include: &source.include,
             subdir: None,
+            rescan_subdir: false,
         };
-        scan::run_scan(&client, &scan_source, &config.scan, &opts).await?;
+        scan::run_scan(&client, &scan_source, &source.scan_config(&config.scan), &opts).await?;
     }
 
+    anyhow::ensure!(unavailable.is_empty(), "skipped unavailable source(s): {}", unavailable.join(", "));
     Ok(())
 }
@@ -13,7 +13,7 @@ fn missing_binaries_warned() -> &'static Mutex<HashSet<String>> {
 }
 
 use find_common::{
-    api::IndexLine,
+    api::{IndexLine, Provenance},
     config::{ExternalExtractorConfig, ExtractorConfig, ExtractorEntry, ScanConfig},
 };
 use find_extract_archive::MemberBatch;
@@ -59,8 +59,31 @@ pub enum ExtractorRoute {
     ServerOnly,
 }
 
+impl ExtractorRoute {
+    /// The provenance recorded for lines this route extracts: the extractor
+    /// binary's name (or the inline library's), with the release version for
+    /// built-in extractors.  `truncated` is left for the caller.
+    pub fn provenance(&self) -> Option<Provenance> {
+        let builtin = |extractor: String| Provenance {
+            extractor,
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            truncated: false,
+        };
+        let file_stem = |bin: &str| {
+            Path::new(bin).file_stem().map_or_else(|| bin.to_string(), |s| s.to_string_lossy().into_owned())
+        };
+        match self {
+            ExtractorRoute::Inline(kind) => Some(builtin(format!("find-extract-{}", format!("{kind:?}").to_lowercase()))),
+            ExtractorRoute::Archive => Some(builtin("find-extract-archive".to_string())),
+            ExtractorRoute::Subprocess(binary) => Some(builtin(file_stem(binary))),
+            ExtractorRoute::External(cfg) => Some(Provenance { extractor: file_stem(&cfg.bin), version: None, truncated: false }),
+            ExtractorRoute::ServerOnly => None,
+        }
+    }
+}
+
 /// Identifies which in-process extractor library to call.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum InlineKind {
     /// Text/code files — routed through find_extract_dispatch::dispatch_from_path.
     Text,
@@ -605,6 +628,9 @@ pub fn start_archive_subprocess(
         serde_json::to_string(&server_only_exts).unwrap_or_default()
     };
 
+    let max_7z_solid_block_mb = scan.archives.max_7z_solid_block_mb.to_string();
+    let stream_oversized_7z_blocks = scan.archives.stream_oversized_7z_blocks.to_string();
+
     let (tx, rx) = mpsc::channel(8);
 
     let handle = tokio::spawn(async move {
@@ -612,13 +638,13 @@ pub fn start_archive_subprocess(
         cmd.arg(&abs_path)
             .arg(&max_content_kb)
             .arg(&max_depth)
-            .arg(&max_line_length);
-        if !exclude_patterns_json.is_empty() {
-            cmd.arg(&exclude_patterns_json);
-        }
-        if !server_only_json.is_empty() {
-            cmd.arg(&server_only_json);
-        }
+            .arg(&max_line_length)
+            // Empty JSON arguments parse as "none" in the subprocess; they are
+            // passed anyway so the 7z settings after them keep their positions.
+            .arg(&exclude_patterns_json)
+            .arg(&server_only_json)
+            .arg(&max_7z_solid_block_mb)
+            .arg(&stream_oversized_7z_blocks);
         cmd.stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
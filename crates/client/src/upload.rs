@@ -58,7 +58,7 @@ pub async fn upload_file(
         file.seek(SeekFrom::Start(offset))
             .context("seeking in upload file")?;
 
-        let chunk_size = (total_size - offset).min(CHUNK_SIZE as u64) as usize;
+        let chunk_size = (total_size - offset).min(api.upload_chunk_size(CHUNK_SIZE) as u64) as usize;
         let mut buf = vec![0u8; chunk_size];
         file.read_exact(&mut buf).context("reading chunk for upload")?;
 
@@ -1,4 +1,3 @@
-mod api;
 mod upload;
 
 use std::path::PathBuf;
@@ -6,8 +5,9 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use clap::{CommandFactory, FromArgMatches, Parser};
 
+use find_client_lib::{api, apply_remote_config, load_config, throttle};
 use find_common::api::UploadScanHints;
-use find_common::config::{default_config_path, parse_client_config};
+use find_common::config::default_config_path;
 
 #[derive(Parser)]
 #[command(
@@ -44,9 +44,7 @@ async fn main() -> Result<()> {
     let args = Args::from_arg_matches(&Args::command().version(find_common::tool_version!()).get_matches()).unwrap_or_else(|e| e.exit());
 
     let config_path = args.config.unwrap_or_else(default_config_path);
-    let config_str = std::fs::read_to_string(&config_path)
-        .with_context(|| format!("reading config {config_path}"))?;
-    let (config, config_warnings) = parse_client_config(&config_str)?;
+    let (mut config, config_warnings) = load_config(&config_path)?;
     for w in &config_warnings { eprintln!("Warning: {w}"); }
 
     let abs_path = args.file.canonicalize().context("resolving file path")?;
@@ -66,8 +64,10 @@ async fn main() -> Result<()> {
             .unwrap_or_else(|| abs_path.to_string_lossy().into_owned())
     });
 
-    let client = api::ApiClient::new(&config.server.url, &config.server.token);
+    let client = api::ApiClient::new(&config.server.url, &config.server.token)
+        .with_throttle(throttle::UploadThrottle::from_scan(&config.scan)?);
     client.check_server_version().await?;
+    apply_remote_config(&mut config, &client).await;
 
     let scan_hints = UploadScanHints {
         exclude: config.scan.exclude.clone(),
@@ -0,0 +1,157 @@
+//! `find-admin verify`: compare the files of a local source with what the
+//! server has indexed for it, without changing either.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use find_common::api::FileRecord;
+use find_common::config::{ScanConfig, SourceConfig};
+use find_common::path::is_composite;
+
+use crate::api::ApiClient;
+use crate::path_util::{include_dir_prefixes, is_history_path};
+use crate::walk::{build_globset, walk_paths};
+
+/// Differences between a source on disk and its index, each list sorted.
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub source: String,
+    /// Files found on disk.
+    pub local_files: usize,
+    /// Files the server has indexed (archive members not counted).
+    pub indexed_files: usize,
+    /// On disk but not indexed.
+    pub missing: Vec<String>,
+    /// Modified on disk since they were indexed.
+    pub stale: Vec<String>,
+    /// Indexed but no longer on disk.
+    pub orphaned: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.stale.is_empty() && self.orphaned.is_empty()
+    }
+}
+
+/// Walk `source` the way `find-scan` does and compare it with the server's
+/// file list.  A file is stale when its mtime is newer than the indexed one.
+pub async fn verify_source(api: &ApiClient, source: &SourceConfig, scan: &ScanConfig) -> Result<VerifyReport> {
+    anyhow::ensure!(
+        Path::new(&source.path).is_dir(),
+        "source path {} is not an accessible directory", source.path
+    );
+    let paths = std::slice::from_ref(&source.path);
+    let excludes = build_globset(&scan.exclude)?;
+    let includes = build_globset(&source.include)?;
+    let include_dirs = if source.include.is_empty() { None } else { include_dir_prefixes(&source.include) };
+
+    let server = api.list_files(&source.name).await.context("fetching file list")?;
+    let local = walk_paths(paths, scan, &excludes, &includes, include_dirs.as_ref(), None);
+    let local: HashMap<String, i64> = local
+        .into_iter()
+        .map(|(rel, abs)| {
+            let mtime = mtime_of(&abs).unwrap_or(0);
+            (rel, mtime)
+        })
+        .collect();
+
+    let mut report = compare(&local, &server);
+    report.source = source.name.clone();
+    // Git history paths (`repo@v1.2.0`) are never on disk.
+    if scan.git_history.is_some() {
+        report.orphaned.retain(|p| !is_history_path(paths, p));
+    }
+    Ok(report)
+}
+
+/// Compare local `(relative path → mtime)` with the server's records.
+fn compare(local: &HashMap<String, i64>, server: &[FileRecord]) -> VerifyReport {
+    let indexed: HashMap<&str, i64> = server
+        .iter()
+        .filter(|f| !is_composite(&f.path))
+        .map(|f| (f.path.as_str(), f.mtime))
+        .collect();
+
+    let mut report = VerifyReport {
+        local_files: local.len(),
+        indexed_files: indexed.len(),
+        ..Default::default()
+    };
+    for (path, &mtime) in local {
+        match indexed.get(path.as_str()) {
+            None => report.missing.push(path.clone()),
+            Some(&indexed_mtime) if mtime > indexed_mtime => report.stale.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    let local_paths: HashSet<&str> = local.keys().map(String::as_str).collect();
+    report.orphaned = indexed
+        .keys()
+        .filter(|p| !local_paths.contains(*p))
+        .map(|p| p.to_string())
+        .collect();
+
+    report.missing.sort_unstable();
+    report.stale.sort_unstable();
+    report.orphaned.sort_unstable();
+    report
+}
+
+/// `find-scan` next to this executable, else whichever one is on `PATH`.
+pub fn find_scan_exe() -> PathBuf {
+    let name = if cfg!(windows) { "find-scan.exe" } else { "find-scan" };
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(name)))
+        .filter(|p| p.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+fn mtime_of(path: &Path) -> Option<i64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use find_common::api::FileKind;
+
+    fn record(path: &str, mtime: i64) -> FileRecord {
+        FileRecord {
+            path: path.to_string(),
+            mtime,
+            kind: FileKind::Text,
+            scanner_version: 0,
+            indexed_at: None,
+            size: None,
+            file_hash: None,
+        }
+    }
+
+    #[test]
+    fn compare_sorts_files_into_missing_stale_and_orphaned() {
+        let local: HashMap<String, i64> = [("new.txt", 5), ("same.txt", 10), ("edited.txt", 20), ("a.zip", 1)]
+            .into_iter()
+            .map(|(p, m)| (p.to_string(), m))
+            .collect();
+        let server = [
+            record("same.txt", 10),
+            record("edited.txt", 15),
+            record("gone.txt", 1),
+            record("a.zip", 1),
+            record("a.zip::inner.txt", 1),
+        ];
+        let report = compare(&local, &server);
+        assert_eq!(report.missing, ["new.txt"]);
+        assert_eq!(report.stale, ["edited.txt"]);
+        assert_eq!(report.orphaned, ["gone.txt"]);
+        assert_eq!((report.local_files, report.indexed_files), (4, 4));
+        assert!(!report.is_clean());
+    }
+}
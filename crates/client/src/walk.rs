@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use globset::GlobSet;
@@ -7,7 +7,8 @@ use walkdir::WalkDir;
 use find_common::config::{load_dir_override, ScanConfig};
 pub(crate) use find_common::build_globset;
 
-use crate::path_util::{include_dir_prefixes, normalise_path_sep};
+use crate::ignore_files::{self, DirRules};
+use crate::path_util::{include_dir_prefixes, normalise_path_sep, normalise_root};
 
 /// A single item yielded to the callback by [`walk_source_tree`].
 // Each binary uses only one variant (Dir for find-watch, File for find-scan).
@@ -43,7 +44,7 @@ pub(crate) enum WalkItem {
 ///   and the `rel` field in `WalkItem::File`.  Usually equal to `walk_root`;
 ///   set to the source root when a subdir is provided.
 /// * `scan`       — effective `ScanConfig`; controls `follow_symlinks`,
-///   `include_hidden`, and `noindex_file`.
+///   `include_hidden`, `ignore_files`, and `noindex_file`.
 /// * `excludes`   — compiled globset of `scan.exclude` patterns, relative
 ///   to `strip_root`.
 /// * `terminals`  — from [`crate::path_util::include_dir_prefixes`]; prunes
@@ -55,6 +56,15 @@ pub(crate) enum WalkItem {
 /// field, only sub-paths that match those patterns are descended into.  This
 /// ensures `find-scan` and `find-watch` share identical directory-pruning
 /// behaviour.
+///
+/// With `scan.follow_symlinks`, each directory is walked at most once (by
+/// device and inode), so link cycles and several links to one directory do
+/// not repeat work, and links to directories inside `strip_root` are skipped
+/// because the real directory is walked under its own path.
+///
+/// With `scan.ignore_files`, `.gitignore`/`.ignore`/`.fdignore` files (see
+/// [`crate::ignore_files`]) prune the paths they match, including those in
+/// the directories between `strip_root` and `walk_root`.
 /// * `callback`   — receives each `WalkItem` that passes all filters.
 ///
 /// Walk errors are logged at `warn`/`debug` level and skipped — the walk
@@ -74,6 +84,33 @@ pub(crate) fn walk_source_tree(
     // before we descend into it.
     let mut override_stack: Vec<(usize, HashSet<String>)> = Vec::new();
 
+    // Stack of (depth, rules) from ignore files, kept like override_stack.
+    // Rules of `walk_root` and its ancestors up to `strip_root` sit at
+    // depth 0 so they apply to every entry.
+    let mut ignore_stack: Vec<(usize, DirRules)> = Vec::new();
+    if scan.ignore_files {
+        ignore_stack.extend(DirRules::load(strip_root, "").map(|r| (0, r)));
+        if let Ok(rel) = walk_root.strip_prefix(strip_root) {
+            let (mut dir, mut dir_rel) = (strip_root.to_path_buf(), String::new());
+            for part in rel.iter() {
+                dir.push(part);
+                let part = part.to_string_lossy();
+                dir_rel = if dir_rel.is_empty() { part.into_owned() } else { format!("{dir_rel}/{part}") };
+                ignore_stack.extend(DirRules::load(&dir, &dir_rel).map(|r| (0, r)));
+            }
+        }
+    }
+
+    // Directories already walked and the canonical strip root, for symlink
+    // cycle and duplicate detection.  Only populated when following links.
+    let mut visited: HashSet<DirId> = HashSet::new();
+    let strip_canon = if scan.follow_symlinks {
+        visited.extend(dir_id(walk_root));
+        std::fs::canonicalize(strip_root).ok()
+    } else {
+        None
+    };
+
     // Device ID of the walk root, captured once for filesystem-boundary checks.
     // None when cross_filesystems = true (check disabled) or on non-Unix.
     let root_dev: Option<u64> = if !scan.cross_filesystems {
@@ -102,6 +139,7 @@ pub(crate) fn walk_source_tree(
                 // current was pushed by a sibling (or its descendant) and is
                 // no longer an ancestor of this directory.
                 override_stack.retain(|(d, _)| *d < depth);
+                ignore_stack.retain(|(d, _)| *d < depth);
 
                 // Skip hidden directories when include_hidden is false.
                 // Hidden files are intentionally left for the callback so
@@ -135,6 +173,30 @@ pub(crate) fn walk_source_tree(
                     }
                 }
 
+                if scan.follow_symlinks {
+                    if e.path_is_symlink() {
+                        let target = std::fs::canonicalize(e.path()).ok();
+                        if let (Some(t), Some(root)) = (&target, &strip_canon) {
+                            if t.starts_with(root) {
+                                tracing::debug!(
+                                    "walk: skipping {} (links into the source tree)",
+                                    e.path().display()
+                                );
+                                return false;
+                            }
+                        }
+                    }
+                    if let Some(id) = dir_id(e.path()) {
+                        if !visited.insert(id) {
+                            tracing::debug!(
+                                "walk: skipping {} (directory already walked)",
+                                e.path().display()
+                            );
+                            return false;
+                        }
+                    }
+                }
+
                 if let Ok(rel) = e.path().strip_prefix(strip_root) {
                     let rel_str = normalise_path_sep(&rel.to_string_lossy());
 
@@ -199,6 +261,20 @@ pub(crate) fn walk_source_tree(
                 if excludes.is_match(&*rel_str) {
                     return false;
                 }
+                if scan.ignore_files {
+                    // Only ancestors' rules: a file's siblings may have left
+                    // entries at its own depth on the stack.
+                    let depth = e.depth();
+                    let is_dir = e.file_type().is_dir();
+                    let ancestors = ignore_stack.iter().filter(|(d, _)| *d < depth).map(|(_, r)| r);
+                    if ignore_files::is_ignored(ancestors, &rel_str, is_dir) {
+                        tracing::debug!("walk: skipping {} (ignore file)", e.path().display());
+                        return false;
+                    }
+                    if is_dir {
+                        ignore_stack.extend(DirRules::load(e.path(), &rel_str).map(|r| (depth, r)));
+                    }
+                }
             }
             true
         })
@@ -235,6 +311,8 @@ pub(crate) fn walk_source_tree(
                     .unwrap_or(false);
                 if excluded {
                     tracing::debug!("walk: skipping excluded path: {e}");
+                } else if e.loop_ancestor().is_some() {
+                    tracing::debug!("walk: skipping symlink loop: {e}");
                 } else if access_denied {
                     tracing::warn!("walk: skipping inaccessible path: {e}");
                 } else {
@@ -245,6 +323,81 @@ pub(crate) fn walk_source_tree(
     }
 }
 
+/// Returns a map of relative_path → absolute_path for all files under `paths`.
+///
+/// `includes` is empty when no include filter is configured (all files pass).
+/// `include_dirs` is the terminal set from `include_dir_prefixes`; if `None`,
+/// no directory pruning is applied (patterns like `**/*.rs` can match anywhere).
+#[allow(dead_code)] // used by find-scan and find-admin verify
+pub(crate) fn walk_paths(
+    paths: &[String],
+    scan: &ScanConfig,
+    excludes: &GlobSet,
+    includes: &GlobSet,
+    include_dirs: Option<&HashSet<String>>,
+    subdir: Option<&str>,
+) -> HashMap<String, PathBuf> {
+    let mut map = HashMap::new();
+    let log_interval = std::time::Duration::from_secs(5);
+    let mut last_log = std::time::Instant::now();
+
+    for root_str in paths {
+        let root_str = normalise_root(root_str);
+        let root = PathBuf::from(&root_str);
+        // When scanning a subdir, walk from root/subdir but compute rel-paths
+        // relative to root so they match what the server already stores.
+        let walk_start = match subdir {
+            Some(sub) => { let mut p = root.clone(); p.push(sub); p }
+            None => root.clone(),
+        };
+
+        walk_source_tree(
+            &walk_start,
+            &root,
+            scan,
+            excludes,
+            include_dirs,
+            |item| {
+                let WalkItem::File { abs, rel, name, depth } = item else { return; };
+                // Hidden files (hidden directories already pruned in walk_source_tree).
+                if !scan.include_hidden && name.starts_with('.') && depth > 0 {
+                    return;
+                }
+                // Apply source-level include filter.
+                if !includes.is_empty() && !includes.is_match(&*rel) {
+                    return;
+                }
+                map.insert(rel, abs);
+                if last_log.elapsed() >= log_interval {
+                    tracing::info!("walking filesystem... {} files found so far", map.len());
+                    last_log = std::time::Instant::now();
+                }
+            },
+        );
+    }
+
+    map
+}
+
+/// Identity of a directory for symlink cycle detection: device and inode on
+/// Unix, the canonical path elsewhere.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+/// Identity of the directory at `path`, following links.
+fn dir_id(path: &Path) -> Option<DirId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::canonicalize(path).ok()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -593,6 +746,76 @@ mod tests {
         assert_eq!(files, vec!["src/main.rs"]);
     }
 
+    // ── ignore files ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn ignore_files_prune_matches_only_when_enabled() {
+        let tmp = TempDir::new().unwrap();
+        mktree(tmp.path(), &[
+            "src/main.rs",
+            "target/debug/app",
+            "web/node_modules/x/index.js",
+            "web/app.js",
+            "web/app.js.map",
+            "notes.bak",
+        ]);
+        std::fs::write(tmp.path().join(".gitignore"), "/target/\nnode_modules/\n").unwrap();
+        std::fs::write(tmp.path().join("web/.ignore"), "*.map\n").unwrap();
+        std::fs::write(tmp.path().join(".fdignore"), "*.bak\n").unwrap();
+
+        let all = walk_files(tmp.path(), &bare_scan(), &empty_gs(), None);
+        assert!(all.contains(&"target/debug/app".to_string()), "off by default");
+
+        let mut scan = bare_scan();
+        scan.ignore_files = true;
+        assert_eq!(walk_files(tmp.path(), &scan, &empty_gs(), None),
+                   vec![".fdignore", ".gitignore", "src/main.rs", "web/.ignore", "web/app.js"]);
+        let dirs = walk_dirs(tmp.path(), &scan, &empty_gs(), None);
+        assert!(!dirs.iter().any(|d| d.starts_with("target") || d.contains("node_modules")));
+    }
+
+    #[test]
+    fn ignore_files_above_walk_root_apply_to_subdir_walks() {
+        let tmp = TempDir::new().unwrap();
+        mktree(tmp.path(), &["sub/a.txt", "sub/gen/b.txt", "sub/c.log"]);
+        std::fs::write(tmp.path().join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(tmp.path().join("sub/.gitignore"), "/gen\n").unwrap();
+        let mut scan = bare_scan();
+        scan.ignore_files = true;
+        let mut out = vec![];
+        walk_source_tree(&tmp.path().join("sub"), tmp.path(), &scan, &empty_gs(), None, |item| {
+            if let WalkItem::File { rel, .. } = item {
+                out.push(rel);
+            }
+        });
+        out.sort();
+        assert_eq!(out, vec!["sub/.gitignore", "sub/a.txt"]);
+    }
+
+    // ── symlinks ──────────────────────────────────────────────────────────────
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlinks_walk_each_directory_once() {
+        use std::os::unix::fs::symlink;
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        mktree(tmp.path(), &["real/a.txt"]);
+        mktree(outside.path(), &["shared/b.txt"]);
+        symlink(tmp.path().join("real"), tmp.path().join("alias")).unwrap();
+        symlink(outside.path().join("shared"), tmp.path().join("farm1")).unwrap();
+        symlink(outside.path().join("shared"), tmp.path().join("farm2")).unwrap();
+        symlink(tmp.path(), tmp.path().join("real/loop")).unwrap();
+
+        assert_eq!(walk_files(tmp.path(), &bare_scan(), &empty_gs(), None), vec!["real/a.txt"]);
+
+        let mut scan = bare_scan();
+        scan.follow_symlinks = true;
+        let files = walk_files(tmp.path(), &scan, &empty_gs(), None);
+        // alias/ and real/loop point into the tree; farm1 and farm2 share a target.
+        assert!(files == ["farm1/b.txt", "real/a.txt"] || files == ["farm2/b.txt", "real/a.txt"]);
+    }
+
     // ── terminal pruning (from include patterns) ─────────────────────────────
 
     #[test]
@@ -1,32 +1,53 @@
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, UNIX_EPOCH};
 
 use anyhow::Result;
 use globset::GlobSet;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
 use tracing::{info, warn};
 
 use find_common::{
-    api::{BulkRequest, FileKind, IndexFile, PathRename},
-    config::{extractor_config_from_scan, load_dir_override, ClientConfig, ExternalExtractorMode, ScanConfig, SourceConfig},
+    api::{BulkRequest, ClientMetrics, FileKind, IndexFile, IndexLine, PathRename, WatchMetrics, SCANNER_VERSION},
+    config::{default_state_dir, extractor_config_from_scan, load_dir_override, parse_client_config, ClientConfig, ExternalExtractorMode, ScanConfig, SourceConfig, WatchMode},
     path::is_composite,
+    schedule::Schedule,
+    watch_status::{self, WatchSourceStatus, WatchStatus},
 };
 
 use walkdir::WalkDir;
 use crate::api::ApiClient;
-use crate::batch::{build_index_files, hash_file};
+use crate::batch::{add_symlink_target, build_index_files, build_member_index_files, hash_file, KnownMembers};
+use crate::control::Control;
+use crate::pause::PauseControl;
+use crate::spool::Spool;
 use crate::subprocess;
+use crate::throttle::UploadThrottle;
 use crate::upload::{self, hints_from_scan};
 
+/// How long a change-journal position must outlive the batch window before
+/// it is saved, so events still being indexed at shutdown are replayed.
+const JOURNAL_CURSOR_MARGIN: Duration = Duration::from_secs(60);
+
+/// How often spooled batches are looked at while indexing is paused.
+const PAUSED_SPOOL_RECHECK: Duration = Duration::from_secs(30);
+
+/// How often the watcher sends the server its counters (`[server] report_metrics`).
+const METRICS_INTERVAL: Duration = Duration::from_secs(300);
+
 /// Options passed to `run_watch` from the CLI entry point.
 pub struct WatchOptions {
     /// Path to the client config file; forwarded to scheduled `find-scan` invocations.
     pub config_path: String,
     /// If true, run one `find-scan` immediately at startup before the interval begins.
     pub scan_now: bool,
+    /// Notified to re-read the config file (the Windows service's
+    /// parameter-change control).  Edits to the file and SIGHUP also do.
+    pub reload: Arc<Notify>,
 }
 
 /// One configured source as used by the watcher.
@@ -34,7 +55,6 @@ pub(crate) struct WatchSource {
     root:        PathBuf,
     source_name: String,
     /// Normalised root path as a `String` (forward-slash separators).
-    #[allow(dead_code)] // stored but only used during construction
     root_str:    String,
     /// Compiled include glob patterns (empty = include everything).
     includes:    GlobSet,
@@ -42,6 +62,9 @@ pub(crate) struct WatchSource {
     /// `None` when patterns can't be reduced to a terminal set (e.g. `**/*.rs`),
     /// meaning every subdirectory must be watched.
     terminals:   Option<std::collections::HashSet<String>>,
+    /// Global `[scan]` config with this source's overrides applied
+    /// (`follow_symlinks`, `ignore_files`).
+    scan:        ScanConfig,
 }
 
 type SourceMap = Vec<WatchSource>;
@@ -74,60 +97,460 @@ fn collapse(existing: &AccumulatedKind, new: &AccumulatedKind) -> AccumulatedKin
     }
 }
 
+/// Events collected in the current batch window but not yet indexed.  Kept
+/// across config reloads, so a reload does not drop changes.
+#[derive(Default)]
+pub(crate) struct PendingEvents {
+    /// Accumulator: path → what to do.
+    paths: HashMap<PathBuf, AccumulatedKind>,
+    /// Paths whose very first event in this window was a Create (i.e. never previously indexed).
+    /// Used by rename detection to avoid sending a rename when the old path was ephemeral.
+    first_seen_creates: HashSet<PathBuf>,
+}
+
 pub async fn run_watch(config: &ClientConfig, opts: &WatchOptions) -> Result<()> {
-    // Spawn the periodic find-scan scheduler as a background task.
-    {
-        let config_path = opts.config_path.clone();
-        let scan_now = opts.scan_now;
-        let interval_hours = config.watch.scan_interval_hours;
-        let log_dir = config.log.dir.clone();
-        tokio::spawn(async move {
-            run_scan_scheduler(interval_hours, &config_path, &log_dir, scan_now).await;
-        });
+    let mut config = with_remote_config(config.clone()).await;
+    let mut scheduler = spawn_scan_scheduler(&config, &opts.config_path, opts.scan_now);
+
+    let (reload_tx, mut reload_rx) = mpsc::channel::<String>(1);
+    tokio::spawn(find_common::reload::watch_config_file(
+        PathBuf::from(&opts.config_path),
+        opts.reload.clone(),
+        reload_tx,
+    ));
+
+    let cursors = Arc::new(crate::journal::CursorStore::open(default_state_dir().join("watch-cursors.json")));
+    let activity = Arc::new(WatchActivity::default());
+    let started_at = now_secs();
+    let mut pending = PendingEvents::default();
+    let mut session = WatchSession::start(&config, &cursors, &activity, started_at)?;
+
+    let control = Arc::new(Control::new(&config, &opts.config_path, opts.reload.clone(), activity.clone()));
+    control.set_session(session.status.clone(), session.api.clone());
+    let control_addr = find_common::control::control_address(&config.watch);
+    if let Some(addr) = control_addr.clone() {
+        tokio::spawn(crate::control::serve(addr, control.clone()));
     }
 
+    loop {
+        let Some(text) = session.run(&mut reload_rx, &mut pending, &activity).await? else {
+            return Ok(());
+        };
+        let new_config = match parse_client_config(&text) {
+            Ok((new_config, warnings)) => {
+                for w in &warnings {
+                    warn!("config reload: {w}");
+                }
+                with_remote_config(new_config).await
+            }
+            Err(e) => {
+                warn!("config reload: {e:#}; keeping the running configuration");
+                continue;
+            }
+        };
+        // The new session is watching before the old one is dropped, and
+        // whatever the old one had queued is carried over, so no change
+        // falls between the two.
+        match WatchSession::start(&new_config, &cursors, &activity, started_at) {
+            Ok(new_session) => {
+                while let Ok(ev) = session.rx.try_recv() {
+                    accumulate(&mut pending.paths, &mut pending.first_seen_creates, ev);
+                }
+                session = new_session;
+                control.set_session(session.status.clone(), session.api.clone());
+            }
+            Err(e) => {
+                warn!("config reload: {e:#}; keeping the running configuration");
+                continue;
+            }
+        }
+        if let Err(e) = find_common::logging::set_ignore_patterns(&new_config.log.ignore) {
+            warn!("config reload: invalid log ignore pattern: {e}");
+        }
+        if scheduler_settings(&new_config) != scheduler_settings(&config) {
+            // A scan already running is left to finish.
+            scheduler.abort();
+            scheduler = spawn_scan_scheduler(&new_config, &opts.config_path, false);
+        }
+        if new_config.log.compact != config.log.compact || new_config.log.dir != config.log.dir {
+            warn!("config reload: restart find-watch to apply [log] compact and dir to its own log output");
+        }
+        control.configure(&new_config);
+        if find_common::control::control_address(&new_config.watch) != control_addr {
+            warn!("config reload: restart find-watch to apply [watch] control_socket");
+        }
+        info!("config reloaded from {}", opts.config_path);
+        config = new_config;
+    }
+}
+
+/// `config` with the server's scan settings applied, when it asks for them
+/// (`[server] remote_config`).
+async fn with_remote_config(mut config: ClientConfig) -> ClientConfig {
     let api = ApiClient::new(&config.server.url, &config.server.token);
-    let source_map = build_source_map(&config.sources);
+    find_client_lib::apply_remote_config(&mut config, &api).await;
+    config
+}
+
+/// Spawn the interval scan and the per-source scheduled scans as one task.
+fn spawn_scan_scheduler(config: &ClientConfig, config_path: &str, scan_now: bool) -> tokio::task::JoinHandle<()> {
+    let config_path = config_path.to_string();
+    let log_dir = config.log.dir.clone();
+    let jobs = scheduled_scans(&config.sources);
+    // Sources with their own `scan_schedule` are left to it, and when every
+    // source has one there is nothing left for the interval scan.
+    let skip_scheduled = config.sources.iter().any(|s| s.scan_schedule.is_some());
+    let interval_hours = if config.sources.iter().all(|s| s.scan_schedule.is_some()) {
+        0.0
+    } else {
+        config.watch.scan_interval_hours
+    };
+    tokio::spawn(async move {
+        tokio::join!(
+            run_scan_scheduler(interval_hours, &config_path, &log_dir, scan_now, skip_scheduled),
+            run_source_schedules(jobs, &config_path, &log_dir),
+        );
+    })
+}
+
+/// What the scan schedulers are started from; a config reload that changes
+/// any of it restarts them.
+fn scheduler_settings(config: &ClientConfig) -> (f64, &str, Vec<(&str, Option<&str>, Option<&str>)>) {
+    let sources = config.sources.iter()
+        .map(|s| (s.name.as_str(), s.scan_schedule.as_deref(), s.full_scan_schedule.as_deref()))
+        .collect();
+    (config.watch.scan_interval_hours, config.log.dir.as_str(), sources)
+}
 
-    if source_map.is_empty() {
-        anyhow::bail!("no source paths configured");
+/// The watches, pollers and change journals set up for one configuration.
+/// A config reload starts a new session and drops the old one.
+struct WatchSession {
+    api: Arc<ApiClient>,
+    watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<notify::Result<Event>>,
+    source_map: SourceMap,
+    /// Roots followed by a poller or change journal rather than notify.
+    unwatched_roots: Vec<PathBuf>,
+    global_excludes: GlobSet,
+    scan: ScanConfig,
+    extractor_dir: Option<String>,
+    batch_window: Duration,
+    batch_limit: usize,
+    /// What the heartbeat starts from each time; the control socket's
+    /// status requests too.
+    status: WatchStatus,
+    /// Pollers and the heartbeat writer.  Change journal readers stop on
+    /// their own once `rx` is gone.
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl Drop for WatchSession {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
     }
+}
+
+impl WatchSession {
+    fn start(
+        config: &ClientConfig,
+        cursors: &Arc<crate::journal::CursorStore>,
+        activity: &Arc<WatchActivity>,
+        started_at: i64,
+    ) -> Result<Self> {
+        let api = Arc::new(ApiClient::new(&config.server.url, &config.server.token)
+            .with_throttle(UploadThrottle::from_scan(&config.scan)?)
+            .with_pause(PauseControl::from_scan(&config.scan)?)
+            .with_spool(Spool::from_scan(&config.scan, default_state_dir().join("spool").join("find-watch"))?));
+        // S3 buckets and cloud drives have no change events; find-scan keeps
+        // them up to date.
+        let (remote_sources, sources): (Vec<SourceConfig>, Vec<SourceConfig>) =
+            config.sources.iter().cloned().partition(SourceConfig::is_remote);
+        for src in &remote_sources {
+            info!("source {:?} ({}) is indexed by find-scan only", src.name, src.path);
+        }
+        let source_map = build_source_map(&sources, &config.scan);
+
+        if source_map.is_empty() {
+            anyhow::bail!("no source paths configured");
+        }
 
-    info!("find-watch starting — watching {} source(s):", config.sources.len());
-    for src in &config.sources {
-        info!("  source {:?}: {:?}", src.name, src.path);
+        info!("find-watch starting — watching {} source(s):", sources.len());
+        for src in &sources {
+            info!("  source {:?}: {:?}", src.name, src.path);
+        }
+
+        let batch_window = std::time::Duration::from_secs_f64(config.watch.batch_window_secs);
+
+        // Channel: notify (blocking thread) and pollers → tokio event loop.
+        let (tx, rx) = mpsc::channel::<notify::Result<Event>>(1000);
+        let poll_tx = tx.clone();
+
+        let mut watcher = RecommendedWatcher::new(
+            move |res| {
+                let _ = tx.blocking_send(res);
+            },
+            notify::Config::default(),
+        )?;
+
+        let global_excludes = build_globset(&config.scan.exclude).unwrap_or_default();
+        let poll_interval = Duration::from_secs(config.watch.poll_interval_secs.max(1));
+        let mut unwatched_roots: Vec<PathBuf> = Vec::new();
+        let mut tasks = Vec::new();
+        let mut source_status: Vec<WatchSourceStatus> = Vec::new();
+        // `build_source_map` keeps the order of `sources`.
+        for (src, src_cfg) in source_map.iter().zip(&sources) {
+            tracing::debug!(
+                "source {:?}: root={:?} terminals={:?}",
+                src.source_name, src.root, src.terminals
+            );
+            let mode = src_cfg.watch_mode.unwrap_or(config.watch.mode);
+            if crate::poll::use_polling(mode, &src.root) {
+                info!("source {:?} is polled every {}s (network filesystem or watch_mode = \"poll\")", src.source_name, poll_interval.as_secs());
+                tasks.push(tokio::spawn(crate::poll::run(
+                    src.root.clone(),
+                    src.terminals.clone(),
+                    global_excludes.clone(),
+                    src.scan.clone(),
+                    poll_interval,
+                    poll_tx.clone(),
+                )));
+                unwatched_roots.push(src.root.clone());
+                source_status.push(source_entry(src, "poll"));
+                continue;
+            }
+            if mode == WatchMode::Native {
+                match crate::journal::start(&src.root, cursors, batch_window + JOURNAL_CURSOR_MARGIN, poll_tx.clone()) {
+                    Ok(()) => {
+                        info!("source {:?} follows the filesystem change journal", src.source_name);
+                        unwatched_roots.push(src.root.clone());
+                        source_status.push(source_entry(src, "native"));
+                        continue;
+                    }
+                    Err(e) => warn!("source {:?}: change journal unavailable ({e:#}); using OS events", src.source_name),
+                }
+            }
+            let n = watch_tree(&mut watcher, &src.root, src.terminals.as_ref(), &global_excludes, &src.scan);
+            info!("watching {:?} ({n} directories registered)", src.root);
+            source_status.push(source_entry(src, "events"));
+        }
+        drop(poll_tx);
+
+        let status_path = watch_status::status_path(&config.watch);
+        let status = WatchStatus {
+            pid: std::process::id(),
+            version: find_common::tool_version!().to_string(),
+            started_at,
+            sources: source_status,
+            ..Default::default()
+        };
+        tasks.push(tokio::spawn(write_heartbeats(status_path, status.clone(), activity.clone(), api.clone())));
+        if config.server.report_metrics {
+            tasks.push(tokio::spawn(report_metrics(status.clone(), activity.clone(), api.clone())));
+        }
+
+        Ok(WatchSession {
+            api,
+            watcher,
+            rx,
+            source_map,
+            unwatched_roots,
+            global_excludes,
+            scan: config.scan.clone(),
+            extractor_dir: config.watch.extractor_dir.clone(),
+            batch_window,
+            batch_limit: config.scan.batch_size,
+            status,
+            tasks,
+        })
     }
 
-    let batch_window = std::time::Duration::from_secs_f64(config.watch.batch_window_secs);
-    let batch_limit  = config.scan.batch_size;
+    /// Run the event loop until the watcher stops (`None`) or the config
+    /// file is re-read (its new contents).
+    async fn run(
+        &mut self,
+        reload: &mut mpsc::Receiver<String>,
+        pending: &mut PendingEvents,
+        activity: &WatchActivity,
+    ) -> Result<Option<String>> {
+        let watcher = &mut self.watcher;
+        let unwatched_roots = &self.unwatched_roots;
+        let global_excludes = &self.global_excludes;
+        let scan = &self.scan;
+        let mut register_dir = |path: &Path| {
+            if unwatched_roots.iter().any(|r| path.starts_with(r)) {
+                return;
+            }
+            watch_tree(watcher, path, None, global_excludes, scan);
+        };
 
-    // Channel: notify (blocking thread) → tokio event loop.
-    let (tx, rx) = mpsc::channel::<notify::Result<Event>>(1000);
+        run_event_loop(
+            &mut self.rx,
+            reload,
+            pending,
+            &self.api,
+            &self.source_map,
+            self.batch_window,
+            self.batch_limit,
+            scan,
+            &self.extractor_dir,
+            &mut register_dir,
+            activity,
+        )
+        .await
+    }
+}
 
-    let mut watcher = RecommendedWatcher::new(
-        move |res| {
-            let _ = tx.blocking_send(res);
-        },
-        notify::Config::default(),
-    )?;
-
-    let global_excludes = build_globset(&config.scan.exclude).unwrap_or_default();
-    for src in &source_map {
-        tracing::debug!(
-            "source {:?}: root={:?} terminals={:?}",
-            src.source_name, src.root, src.terminals
-        );
-        let n = watch_tree(&mut watcher, &src.root, src.terminals.as_ref(), &global_excludes, &config.scan);
-        info!("watching {:?} ({n} directories registered)", src.root);
+fn source_entry(src: &WatchSource, backend: &str) -> WatchSourceStatus {
+    WatchSourceStatus {
+        name: src.source_name.clone(),
+        path: src.root_str.clone(),
+        backend: backend.to_string(),
     }
+}
 
-    let scan = config.scan.clone();
-    let extractor_dir = config.watch.extractor_dir.clone();
-    let mut register_dir = |path: &Path| {
-        watch_tree(&mut watcher, path, None, &global_excludes, &scan);
-    };
+// ── Status heartbeat ──────────────────────────────────────────────────────────
+
+/// What the event loop has done, reported in the heartbeat file.
+#[derive(Default)]
+pub(crate) struct WatchActivity {
+    pending_paths: AtomicUsize,
+    queued_events: AtomicUsize,
+    last_event_at: AtomicI64,
+    events_received: AtomicU64,
+    files_updated: AtomicU64,
+    files_deleted: AtomicU64,
+    errors: AtomicU64,
+    last_error: Mutex<Option<(i64, String)>>,
+}
+
+impl WatchActivity {
+    fn event_received(&self) {
+        self.last_event_at.store(now_secs(), Ordering::Relaxed);
+        self.events_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn set_queue(&self, pending_paths: usize, queued_events: usize) {
+        self.pending_paths.store(pending_paths, Ordering::Relaxed);
+        self.queued_events.store(queued_events, Ordering::Relaxed);
+    }
 
-    run_event_loop(rx, &api, &source_map, batch_window, batch_limit, &scan, &extractor_dir, &mut register_dir).await
+    fn updated(&self) {
+        self.files_updated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn deleted(&self) {
+        self.files_deleted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn failed(&self, e: &anyhow::Error) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some((now_secs(), format!("{e:#}")));
+    }
+
+    /// Copy the counters into `status`, together with the upload state of `api`.
+    pub(crate) fn fill(&self, status: &mut WatchStatus, api: &ApiClient) {
+        status.pending_paths = self.pending_paths.load(Ordering::Relaxed);
+        status.queued_events = self.queued_events.load(Ordering::Relaxed);
+        status.last_event_at = Some(self.last_event_at.load(Ordering::Relaxed)).filter(|&t| t > 0);
+        status.events_received = self.events_received.load(Ordering::Relaxed);
+        status.files_updated = self.files_updated.load(Ordering::Relaxed);
+        status.files_deleted = self.files_deleted.load(Ordering::Relaxed);
+        status.errors = self.errors.load(Ordering::Relaxed);
+        status.last_upload_at = api.last_bulk_ok_at();
+        status.spooled_batches = api.spooled_batches();
+        status.paused = api.pause_reason();
+        // The most recent of an indexing error and a failed upload (which
+        // the spool may have absorbed without an indexing error).
+        let local = self.last_error.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let latest = match (local, api.last_bulk_error()) {
+            (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+            (a, b) => a.or(b),
+        };
+        (status.last_error_at, status.last_error) = latest.map_or((None, None), |(t, e)| (Some(t), Some(e)));
+    }
+}
+
+/// Rewrite the heartbeat file every `HEARTBEAT_SECS` until the process exits.
+async fn write_heartbeats(path: PathBuf, mut status: WatchStatus, activity: Arc<WatchActivity>, api: Arc<ApiClient>) {
+    info!("writing watcher status to {}", path.display());
+    let mut interval = tokio::time::interval(Duration::from_secs(watch_status::HEARTBEAT_SECS));
+    let mut warned = false;
+    loop {
+        interval.tick().await;
+        activity.fill(&mut status, &api);
+        status.updated_at = now_secs();
+        match watch_status::write_status(&path, &status) {
+            Ok(()) => warned = false,
+            Err(e) if !warned => {
+                warn!("failed to write watcher status: {e:#}");
+                warned = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Send the server the watcher's counters every `METRICS_INTERVAL`.
+async fn report_metrics(mut status: WatchStatus, activity: Arc<WatchActivity>, api: Arc<ApiClient>) {
+    let mut interval = tokio::time::interval(METRICS_INTERVAL);
+    interval.tick().await; // nothing to report yet
+    loop {
+        interval.tick().await;
+        activity.fill(&mut status, &api);
+        let metrics = ClientMetrics {
+            tool: "find-watch".to_string(),
+            version: status.version.clone(),
+            watch: Some(WatchMetrics {
+                started_at: status.started_at,
+                events_received: status.events_received,
+                files_updated: status.files_updated,
+                files_deleted: status.files_deleted,
+                errors: status.errors,
+                pending_paths: status.pending_paths,
+                spooled_batches: status.spooled_batches,
+                last_event_at: status.last_event_at,
+                last_error: status.last_error.clone(),
+            }),
+            ..Default::default()
+        };
+        // Servers older than this client have no metrics endpoint.
+        if let Err(e) = api.report_metrics(metrics).await {
+            tracing::debug!("not reporting watcher metrics: {e:#}");
+        }
+    }
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+/// What woke the event loop.
+enum Wake {
+    Event(Option<notify::Result<Event>>),
+    Reload(String),
+    Timeout,
+}
+
+/// Wait for the next filesystem event or re-read config file, giving up
+/// after `wait` if set.
+async fn next_wake(
+    rx: &mut mpsc::Receiver<notify::Result<Event>>,
+    reload: &mut mpsc::Receiver<String>,
+    wait: Option<Duration>,
+) -> Wake {
+    let timeout = async {
+        match wait {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        ev = rx.recv() => Wake::Event(ev),
+        Some(text) = reload.recv() => Wake::Reload(text),
+        _ = timeout => Wake::Timeout,
+    }
 }
 
 /// The inner event-processing loop, separated from watcher setup so it can be
@@ -135,11 +558,16 @@ pub async fn run_watch(config: &ClientConfig, opts: &WatchOptions) -> Result<()>
 ///
 /// Reads from `rx` until the channel is closed, accumulating filesystem events
 /// into debounce windows and flushing batches to the server via `api`.
+/// Returns early with the new config text when one arrives on `reload`;
+/// events not yet flushed stay in `pending` for the next session.
 /// `register_dir` is called when a new directory is created; in production it
-/// registers an inotify watch; in tests it is a no-op.
+/// registers an inotify watch; in tests it is a no-op.  Progress is recorded
+/// in `activity` for the status heartbeat.
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_event_loop<F>(
-    mut rx: mpsc::Receiver<notify::Result<Event>>,
+    rx: &mut mpsc::Receiver<notify::Result<Event>>,
+    reload: &mut mpsc::Receiver<String>,
+    pending: &mut PendingEvents,
     api: &ApiClient,
     source_map: &SourceMap,
     batch_window: Duration,
@@ -147,29 +575,39 @@ pub(crate) async fn run_event_loop<F>(
     scan: &ScanConfig,
     extractor_dir: &Option<String>,
     register_dir: &mut F,
-) -> Result<()>
+    activity: &WatchActivity,
+) -> Result<Option<String>>
 where
     F: FnMut(&Path),
 {
-    // Accumulator: path → what to do.
-    let mut pending: HashMap<PathBuf, AccumulatedKind> = HashMap::new();
-    // Paths whose very first event in this window was a Create (i.e. never previously indexed).
-    // Used by rename detection to avoid sending a rename when the old path was ephemeral.
-    let mut first_seen_creates: HashSet<PathBuf> = HashSet::new();
-    // When the batch window opened (i.e. when the first event in this batch arrived).
-    let mut window_start: Option<tokio::time::Instant> = None;
+    let PendingEvents { paths: pending, first_seen_creates } = pending;
+    // When the batch window opened (i.e. when the first event in this batch
+    // arrived, or the loop started with events left by a previous session).
+    let mut window_start = (!pending.is_empty()).then(tokio::time::Instant::now);
 
     loop {
         // Decide whether to flush before waiting for the next event.
         let flush = if pending.is_empty() {
-            // Nothing pending — block indefinitely waiting for the first event.
-            match rx.recv().await {
-                Some(ev) => {
-                    accumulate(&mut pending, &mut first_seen_creates, ev);
+            // Nothing pending — wait for the first event, waking up to retry
+            // batches spooled while the server was unreachable.
+            // While paused, only look in now and then to see if it lifted.
+            let retry_in = api.spool_retry_in()
+                .map(|wait| if api.is_paused() { wait.max(PAUSED_SPOOL_RECHECK) } else { wait });
+            match next_wake(rx, reload, retry_in).await {
+                Wake::Event(Some(ev)) => {
+                    activity.event_received();
+                    accumulate(pending, first_seen_creates, ev);
                     window_start = Some(tokio::time::Instant::now());
                     false
                 }
-                None => break, // channel closed
+                Wake::Event(None) => break, // channel closed
+                Wake::Reload(text) => return Ok(Some(text)),
+                Wake::Timeout => {
+                    if !api.is_paused() {
+                        api.flush_spool().await;
+                    }
+                    continue;
+                }
             }
         } else {
             // Events are buffered. Compute how much of the window remains.
@@ -180,18 +618,24 @@ where
                 true // window expired — flush now
             } else {
                 // Wait for either a new event or the window to expire.
-                match tokio::time::timeout(remaining, rx.recv()).await {
-                    Ok(Some(ev)) => { accumulate(&mut pending, &mut first_seen_creates, ev); false }
-                    Ok(None)     => break, // channel closed
-                    Err(_)       => true,  // window expired
+                match next_wake(rx, reload, Some(remaining)).await {
+                    Wake::Event(Some(ev)) => {
+                        activity.event_received();
+                        accumulate(pending, first_seen_creates, ev);
+                        false
+                    }
+                    Wake::Event(None)    => break, // channel closed
+                    Wake::Reload(text)   => return Ok(Some(text)),
+                    Wake::Timeout        => true,  // window expired
                 }
             }
         };
 
         // Drain any immediately-available events before deciding to flush.
         while let Ok(ev) = rx.try_recv() {
-            accumulate(&mut pending, &mut first_seen_creates, ev);
+            accumulate(pending, first_seen_creates, ev);
         }
+        activity.set_queue(pending.len(), rx.len());
 
         // Flush if the window expired or the batch has hit its size limit.
         if !flush && pending.len() < batch_limit {
@@ -203,11 +647,18 @@ where
             continue;
         }
 
+        // Quiet hours or `find-admin pause`: keep collecting events and
+        // index them once the pause lifts.
+        if api.is_paused() {
+            window_start = Some(tokio::time::Instant::now());
+            continue;
+        }
+
         window_start = None;
 
         // Flush accumulated events.
-        let mut batch = std::mem::take(&mut pending);
-        let fresh_creates = std::mem::take(&mut first_seen_creates);
+        let mut batch = std::mem::take(pending);
+        let fresh_creates = std::mem::take(first_seen_creates);
 
         // Detect rename pairs and process them; removes paired entries from batch.
         // Returns new directory paths from dir-rename pairs that need inotify watches.
@@ -240,7 +691,8 @@ where
 
             // Resolve per-directory effective config: check for .noindex and .index files
             // on the ancestor chain. No caching is needed for watch (events are infrequent).
-            let (eff_scan, skip) = resolve_watch_config(&abs_path, &source_root, scan);
+            let src_scan = best_source(&abs_path, source_map).map_or(scan, |s| &s.scan);
+            let (eff_scan, skip) = resolve_watch_config(&abs_path, &source_root, src_scan);
             if skip {
                 tracing::debug!("skipping {} (in .noindex subtree)", abs_path.display());
                 continue;
@@ -274,6 +726,10 @@ where
             if is_excluded(&abs_path, source_map, &eff_excludes) {
                 continue;
             }
+            if is_ignored_by_files(&abs_path, source_map) {
+                tracing::debug!("skipping {} (ignore file)", abs_path.display());
+                continue;
+            }
 
             match kind {
                 AccumulatedKind::Create | AccumulatedKind::Update => {
@@ -310,7 +766,7 @@ where
                             },
                         );
                         for (file_abs, file_rel) in to_index {
-                            if let Err(e) = handle_update(
+                            match handle_update(
                                 api,
                                 &source_name,
                                 &file_abs,
@@ -321,7 +777,11 @@ where
                             )
                             .await
                             {
-                                warn!("update {}: {e:#}", file_abs.display());
+                                Ok(()) => activity.updated(),
+                                Err(e) => {
+                                    activity.failed(&e);
+                                    warn!("update {}: {e:#}", file_abs.display());
+                                }
                             }
                         }
                         continue;
@@ -331,7 +791,7 @@ where
                         continue;
                     }
                     let is_new = matches!(kind, AccumulatedKind::Create);
-                    if let Err(e) = handle_update(
+                    match handle_update(
                         api,
                         &source_name,
                         &abs_path,
@@ -342,24 +802,32 @@ where
                     )
                     .await
                     {
-                        warn!("update {}: {e:#}", abs_path.display());
+                        Ok(()) => activity.updated(),
+                        Err(e) => {
+                            activity.failed(&e);
+                            warn!("update {}: {e:#}", abs_path.display());
+                        }
                     }
                 }
                 AccumulatedKind::Delete => {
-                    if let Err(e) = handle_delete(api, &source_name, &rel_path).await {
-                        warn!("delete {}: {e:#}", abs_path.display());
+                    match handle_delete(api, &source_name, &rel_path).await {
+                        Ok(()) => activity.deleted(),
+                        Err(e) => {
+                            activity.failed(&e);
+                            warn!("delete {}: {e:#}", abs_path.display());
+                        }
                     }
                 }
             }
         }
     }
 
-    Ok(())
+    Ok(None)
 }
 
 // ── Source map ────────────────────────────────────────────────────────────────
 
-fn build_source_map(sources: &[SourceConfig]) -> SourceMap {
+fn build_source_map(sources: &[SourceConfig], global_scan: &ScanConfig) -> SourceMap {
     let mut map = Vec::new();
     for src in sources {
         let root_str = normalise_root(&src.path);
@@ -370,7 +838,8 @@ fn build_source_map(sources: &[SourceConfig]) -> SourceMap {
         } else {
             crate::path_util::include_dir_prefixes(&src.include)
         };
-        map.push(WatchSource { root, source_name: src.name.clone(), root_str, includes, terminals });
+        let scan = src.scan_config(global_scan);
+        map.push(WatchSource { root, source_name: src.name.clone(), root_str, includes, terminals, scan });
     }
     map
 }
@@ -378,6 +847,14 @@ fn build_source_map(sources: &[SourceConfig]) -> SourceMap {
 /// Return `(source_name, rel_path, source_root, include_globset)` for a given absolute path.
 /// Picks the most-specific (longest) matching root.
 fn find_source<'a>(path: &Path, map: &'a SourceMap) -> Option<(String, String, PathBuf, &'a GlobSet)> {
+    best_source(path, map).map(|src| {
+        let rel = normalise_path_sep(&path.strip_prefix(&src.root).unwrap().to_string_lossy());
+        (src.source_name.clone(), rel, src.root.clone(), &src.includes)
+    })
+}
+
+/// The source with the most specific (longest) root containing `path`.
+fn best_source<'a>(path: &Path, map: &'a SourceMap) -> Option<&'a WatchSource> {
     let mut best: Option<&'a WatchSource> = None;
     for src in map {
         if path.starts_with(&src.root)
@@ -386,10 +863,7 @@ fn find_source<'a>(path: &Path, map: &'a SourceMap) -> Option<(String, String, P
             best = Some(src);
         }
     }
-    best.map(|src| {
-        let rel = normalise_path_sep(&path.strip_prefix(&src.root).unwrap().to_string_lossy());
-        (src.source_name.clone(), rel, src.root.clone(), &src.includes)
-    })
+    best
 }
 
 // ── Exclusion ─────────────────────────────────────────────────────────────────
@@ -409,6 +883,17 @@ fn is_excluded(abs_path: &Path, source_map: &SourceMap, excludes: &GlobSet) -> b
     false
 }
 
+/// True when the source containing `abs_path` honours ignore files and one
+/// of them ignores the path or a directory above it.
+fn is_ignored_by_files(abs_path: &Path, source_map: &SourceMap) -> bool {
+    let Some(src) = best_source(abs_path, source_map).filter(|s| s.scan.ignore_files) else {
+        return false;
+    };
+    let Ok(rel) = abs_path.strip_prefix(&src.root) else { return false };
+    let rel = normalise_path_sep(&rel.to_string_lossy());
+    !rel.is_empty() && crate::ignore_files::is_path_ignored(&src.root, &rel, abs_path.is_dir())
+}
+
 use crate::path_util::{normalise_path_sep, normalise_root};
 
 /// Walk `root` registering a `NonRecursive` inotify watch for every accessible,
@@ -609,7 +1094,19 @@ async fn handle_update(
 ) -> Result<()> {
     info!("update: {}", rel_path);
 
-    let lines = match subprocess::resolve_extractor(abs_path, eff_scan, extractor_dir, WATCH_INLINE_SET) {
+    let route = subprocess::resolve_extractor(abs_path, eff_scan, extractor_dir, WATCH_INLINE_SET);
+    if matches!(route, subprocess::ExtractorRoute::Archive) && !is_new {
+        match api.list_archive_members(source_name, rel_path).await {
+            Ok(members) if !members.is_empty() => {
+                let known = KnownMembers::new(members);
+                return update_archive(api, source_name, abs_path, rel_path, eff_scan, extractor_dir, known).await;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("listing members of {rel_path} failed ({e:#}); re-indexing the whole archive"),
+        }
+    }
+
+    let lines = match route {
         subprocess::ExtractorRoute::External(ref ext_cfg) => match ext_cfg.mode {
             ExternalExtractorMode::Stdout => {
                 match subprocess::run_external_stdout(abs_path, ext_cfg, eff_scan).await {
@@ -681,6 +1178,7 @@ async fn handle_update(
     if let Some(f) = files.first_mut() {
         f.is_new = is_new;
         f.file_hash = file_hash;
+        add_symlink_target(f, abs_path, eff_scan);
     }
 
     api.bulk(&BulkRequest {
@@ -694,6 +1192,99 @@ async fn handle_update(
     .await
 }
 
+/// Re-index a changed archive whose members the server already holds:
+/// send only the members that were added or changed, and delete the ones
+/// that are gone, rather than the whole archive.
+async fn update_archive(
+    api: &ApiClient,
+    source_name: &str,
+    abs_path: &Path,
+    rel_path: &str,
+    eff_scan: &ScanConfig,
+    extractor_dir: &Option<String>,
+    mut known: KnownMembers,
+) -> Result<()> {
+    let mtime = mtime_of(abs_path).unwrap_or(0);
+    let size = size_of(abs_path).unwrap_or(0);
+    let (mut member_rx, subprocess_task) = subprocess::start_archive_subprocess(
+        abs_path.to_path_buf(), eff_scan, &subprocess::resolve_binary_for_archive(extractor_dir));
+
+    let mut files = Vec::new();
+    let mut outer_content_lines: Vec<IndexLine> = Vec::new();
+    let mut unchanged = 0usize;
+    while let Some(member_batch) = member_rx.recv().await {
+        outer_content_lines.extend(member_batch.outer_lines.iter().cloned());
+        let member_mtime = member_batch.mtime.unwrap_or(mtime);
+        let composite_path = member_batch.lines.first()
+            .and_then(|l| l.archive_path.as_deref())
+            .map(|ap| format!("{rel_path}::{ap}"));
+        let mut member_changed = false;
+        for file in build_member_index_files(rel_path, member_mtime, member_batch.size, member_batch.lines, member_batch.file_hash) {
+            if known.unchanged(&file) {
+                unchanged += 1;
+            } else {
+                member_changed = true;
+                files.push(file);
+            }
+        }
+        if let (Some(tmp), Some(composite_path)) = (member_batch.delegate_temp_path, composite_path) {
+            let tmp_path = Path::new(&tmp);
+            if member_changed {
+                if let Err(e) = upload::upload_file(api, tmp_path, &composite_path, member_mtime, source_name, hints_from_scan(eff_scan)).await {
+                    warn!("server-only member upload failed for {composite_path}: {e:#}");
+                }
+            }
+            if let Some(parent) = tmp_path.parent() {
+                let _ = std::fs::remove_dir_all(parent);
+            }
+        }
+    }
+    // After a failed extraction the unseen members may simply not have been
+    // reached, so only delete members when it completed.
+    let delete_paths = if subprocess_task.await.unwrap_or(false) {
+        known.removed()
+    } else {
+        warn!("archive extraction failed for {rel_path}; keeping its unseen members");
+        vec![]
+    };
+    info!("{rel_path}: {unchanged} members unchanged, {} sent, {} removed", files.len(), delete_paths.len());
+
+    let mut outer_lines = vec![IndexLine { archive_path: None, line_number: 0, content: format!("[PATH] {rel_path}") }];
+    for (i, mut line) in outer_content_lines.into_iter().enumerate() {
+        line.line_number = i + 1;
+        outer_lines.push(line);
+    }
+    let ext_lc = abs_path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let kind = if find_extract_archive::is_iwork_ext(&ext_lc) { FileKind::Document } else { FileKind::Archive };
+    let mut outer = IndexFile {
+        path: rel_path.to_string(),
+        mtime,
+        size: Some(size),
+        kind,
+        lines: outer_lines,
+        extract_ms: None,
+        file_hash: hash_file(abs_path),
+        scanner_version: SCANNER_VERSION,
+        is_new: false,
+        force: false,
+        append_to: None,
+        reuse_content: false,
+        provenance: subprocess::ExtractorRoute::Archive.provenance(),
+    };
+    add_symlink_target(&mut outer, abs_path, eff_scan);
+    files.push(outer);
+
+    api.bulk(&BulkRequest {
+        source: source_name.to_string(),
+        files,
+        delete_paths,
+        scan_timestamp: None,
+        indexing_failures: vec![],
+        rename_paths: vec![],
+    })
+    .await
+}
+
 async fn handle_delete(
     api: &ApiClient,
     source_name: &str,
@@ -797,7 +1388,7 @@ async fn process_renames(
             &new_rel_dir,
             &source_root,
             source_includes,
-            global_scan,
+            best_source(new_dir, source_map).map_or(global_scan, |s| &s.scan),
             extractor_dir,
         )
         .await
@@ -877,7 +1468,8 @@ async fn process_renames(
         }
 
         // Check per-directory config for new path.
-        let (eff_scan, skip) = resolve_watch_config(new_path, &source_root, global_scan);
+        let src_scan = best_source(new_path, source_map).map_or(global_scan, |s| &s.scan);
+        let (eff_scan, skip) = resolve_watch_config(new_path, &source_root, src_scan);
         if skip {
             continue; // .noindex subtree — fall back to plain delete
         }
@@ -888,6 +1480,9 @@ async fn process_renames(
         if is_excluded(new_path, source_map, &eff_excludes) {
             continue; // excluded by per-dir glob — fall back to plain delete
         }
+        if is_ignored_by_files(new_path, source_map) {
+            continue; // ignored by an ignore file — fall back to plain delete
+        }
 
         // If the old path was first seen as a Create in this window, it was never
         // previously indexed (e.g. a browser's temporary download file that was
@@ -942,14 +1537,14 @@ async fn handle_dir_rename(
     new_rel_dir: &str,
     source_root: &Path,
     source_includes: &GlobSet,
-    global_scan: &ScanConfig,
+    source_scan: &ScanConfig,
     extractor_dir: &Option<String>,
 ) -> Result<()> {
     let mut rename_paths: Vec<PathRename> = Vec::new();
     let mut delete_paths: Vec<String> = Vec::new();
     let mut new_files: Vec<IndexFile> = Vec::new();
 
-    for entry in WalkDir::new(new_dir).follow_links(false).into_iter().filter_map(|e| e.ok()) {
+    for entry in WalkDir::new(new_dir).follow_links(source_scan.follow_symlinks).into_iter().filter_map(|e| e.ok()) {
         if !entry.file_type().is_file() {
             continue;
         }
@@ -973,10 +1568,13 @@ async fn handle_dir_rename(
         // Evaluate inclusion for the new path.
         let new_source_included =
             source_includes.is_empty() || source_includes.is_match(&*new_rel);
-        let (new_eff_scan, new_skip) = resolve_watch_config(new_abs, source_root, global_scan);
+        let (new_eff_scan, new_skip) = resolve_watch_config(new_abs, source_root, source_scan);
         let new_eff_excludes = build_globset(&new_eff_scan.exclude).unwrap_or_default();
-        let new_included =
-            new_source_included && !new_skip && !new_eff_excludes.is_match(&*new_rel);
+        let new_included = new_source_included
+            && !new_skip
+            && !new_eff_excludes.is_match(&*new_rel)
+            && (!source_scan.ignore_files
+                || !crate::ignore_files::is_path_ignored(source_root, &new_rel, false));
 
         // Evaluate source-level inclusion for the old path (old dir is gone; only
         // source-glob check is possible — .noindex/.index files move with the rename).
@@ -1051,6 +1649,9 @@ async fn handle_dir_rename(
                 FileKind::from_extension(ext)
             };
             let mut built = build_index_files(new_rel, mtime, size, kind, lines);
+            if let Some(f) = built.first_mut() {
+                add_symlink_target(f, new_abs, &new_eff_scan);
+            }
             new_files.append(&mut built);
         }
         // else: was excluded, still excluded — nothing to do.
@@ -1076,10 +1677,13 @@ async fn handle_dir_rename(
 /// Background task that spawns `find-scan --config <path>` on a fixed interval.
 ///
 /// - `interval_hours == 0.0` → disabled, returns immediately.
-/// - `scan_now == true` → one scan is spawned immediately before the interval starts.
+/// - `scan_now == true` → one scan of every source is spawned immediately
+///   before the interval starts.
+/// - `skip_scheduled == true` → the interval scans pass `--skip-scheduled`,
+///   leaving out sources with their own `scan_schedule`.
 /// - Overlap: if the previous scan is still running when the next tick fires,
 ///   that tick is skipped and a warning is logged.
-async fn run_scan_scheduler(interval_hours: f64, config_path: &str, log_dir: &str, scan_now: bool) {
+async fn run_scan_scheduler(interval_hours: f64, config_path: &str, log_dir: &str, scan_now: bool, skip_scheduled: bool) {
     if interval_hours <= 0.0 {
         return;
     }
@@ -1087,8 +1691,9 @@ async fn run_scan_scheduler(interval_hours: f64, config_path: &str, log_dir: &st
     let mut child: Option<tokio::process::Child> = None;
 
     if scan_now {
-        child = spawn_scan(config_path, log_dir);
+        child = spawn_scan(config_path, log_dir, &[]);
     }
+    let args: &[&str] = if skip_scheduled { &["--skip-scheduled"] } else { &[] };
 
     let dur = Duration::from_secs_f64(interval_hours * 3600.0);
     let mut ticker = tokio::time::interval(dur);
@@ -1113,15 +1718,95 @@ async fn run_scan_scheduler(interval_hours: f64, config_path: &str, log_dir: &st
             continue;
         }
 
-        child = spawn_scan(config_path, log_dir);
+        child = spawn_scan(config_path, log_dir, args);
+    }
+}
+
+/// One source's `scan_schedule` or `full_scan_schedule`.
+struct ScheduledScan {
+    source: String,
+    /// Re-index every file (`find-scan --force`).
+    full: bool,
+    schedule: Schedule,
+}
+
+fn scheduled_scans(sources: &[SourceConfig]) -> Vec<ScheduledScan> {
+    let mut jobs = Vec::new();
+    for src in sources {
+        for (expr, full) in [(&src.scan_schedule, false), (&src.full_scan_schedule, true)] {
+            let Some(expr) = expr else { continue };
+            match expr.parse::<Schedule>() {
+                Ok(schedule) => jobs.push(ScheduledScan { source: src.name.clone(), full, schedule }),
+                Err(e) => warn!("source {:?}: ignoring schedule {expr:?}: {e}", src.name),
+            }
+        }
+    }
+    jobs
+}
+
+/// Background task that runs `find-scan --source <name>` (with `--force`
+/// for a full scan) whenever a source's schedule fires, in local time.
+///
+/// - A full and an incremental scan of a source due together run as one
+///   full scan.
+/// - Overlap: a scan due while the previous one of the same source is still
+///   running is skipped and a warning is logged.
+async fn run_source_schedules(jobs: Vec<ScheduledScan>, config_path: &str, log_dir: &str) {
+    let now = chrono::Local::now().naive_local();
+    let mut next: Vec<_> = jobs.iter().map(|j| j.schedule.next_after(now)).collect();
+    for (job, when) in jobs.iter().zip(&next) {
+        let kind = if job.full { "full scan" } else { "scan" };
+        match when {
+            Some(when) => info!("source {:?}: next scheduled {kind} at {when}", job.source),
+            None => warn!("source {:?}: scheduled {kind} never fires", job.source),
+        }
+    }
+    let mut running: HashMap<&str, tokio::process::Child> = HashMap::new();
+
+    loop {
+        let Some(earliest) = next.iter().flatten().min().copied() else {
+            return;
+        };
+        let now = chrono::Local::now().naive_local();
+        if now < earliest {
+            // Wake at least once a minute, so a suspended machine or a clock
+            // change does not push scans back.
+            let wait = (earliest - now).to_std().unwrap_or_default();
+            tokio::time::sleep(wait.min(Duration::from_secs(60))).await;
+            continue;
+        }
+
+        let mut due: Vec<usize> = (0..jobs.len()).filter(|&i| next[i].is_some_and(|t| t <= now)).collect();
+        due.sort_by_key(|&i| !jobs[i].full);
+        let mut started: HashSet<&str> = HashSet::new();
+        for i in due {
+            next[i] = jobs[i].schedule.next_after(now);
+            let job = &jobs[i];
+            if !started.insert(job.source.as_str()) {
+                continue;
+            }
+            if let Some(child) = running.get_mut(job.source.as_str()) {
+                if matches!(child.try_wait(), Ok(None)) {
+                    warn!("scheduled scan: previous scan of {:?} still running, skipping", job.source);
+                    continue;
+                }
+            }
+            let mut args = vec!["--source", &job.source];
+            if job.full {
+                args.push("--force");
+            }
+            if let Some(child) = spawn_scan(config_path, log_dir, &args) {
+                running.insert(job.source.as_str(), child);
+            }
+        }
     }
 }
 
-/// Spawn `find-scan --config <config_path>` and return the child handle.
-fn spawn_scan(config_path: &str, log_dir: &str) -> Option<tokio::process::Child> {
+/// Spawn `find-scan --config <config_path> <args>` and return the child handle.
+pub(crate) fn spawn_scan(config_path: &str, log_dir: &str, args: &[&str]) -> Option<tokio::process::Child> {
     let binary = find_scan_binary();
     let mut cmd = tokio::process::Command::new(&binary);
-    cmd.arg("--config").arg(config_path);
+    cmd.arg("--config").arg(config_path).args(args);
 
     if !log_dir.is_empty() {
         let today = chrono::Local::now().format("%Y-%m-%d");
@@ -1318,6 +2003,7 @@ mod tests {
             root_str:    path.to_string(),
             includes:    build_globset(&[]).unwrap_or_default(),
             terminals:   None,
+            scan:        ScanConfig::default(),
         }).collect()
     }
 
@@ -1393,7 +2079,7 @@ mod tests {
              [scan]\nexclude=[]\n",
         ).unwrap();
 
-        let (tx, rx) = mpsc::channel(64);
+        let (tx, mut rx) = mpsc::channel(64);
         for ev in events {
             tx.send(ev).await.unwrap();
         }
@@ -1402,9 +2088,12 @@ mod tests {
             tokio::time::sleep(std::time::Duration::from_millis(150)).await;
             drop(tx);
         });
+        let (_reload_tx, mut reload_rx) = mpsc::channel(1);
 
         run_event_loop(
-            rx,
+            &mut rx,
+            &mut reload_rx,
+            &mut PendingEvents::default(),
             &api,
             source_map,
             std::time::Duration::from_millis(10),
@@ -1412,6 +2101,7 @@ mod tests {
             &config.scan,
             &None,
             &mut register_dir_fn,
+            &WatchActivity::default(),
         ).await.ok();
 
         call_count.load(Ordering::Relaxed)
@@ -1465,4 +2155,42 @@ mod tests {
         assert_eq!(calls, 1,
             "register_dir must be called exactly once for a directory Create event");
     }
+
+    /// A config reload ends the loop at once; events still inside the batch
+    /// window are left for the next session rather than flushed or dropped.
+    #[tokio::test]
+    async fn reload_returns_new_config_and_keeps_pending_events() {
+        let api = crate::api::ApiClient::new("http://127.0.0.1:1", "fake-token");
+        let source_map = make_source_map_raw(&[("test", "/t")]);
+        let (tx, mut rx) = mpsc::channel(64);
+        let (reload_tx, mut reload_rx) = mpsc::channel(1);
+        tx.send(Ok(notify::Event {
+            kind: notify::EventKind::Create(notify::event::CreateKind::File),
+            paths: vec![PathBuf::from("/t/a.txt")],
+            attrs: Default::default(),
+        })).await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            reload_tx.send("new config".to_string()).await.unwrap();
+        });
+
+        let mut pending = PendingEvents::default();
+        let reloaded = run_event_loop(
+            &mut rx,
+            &mut reload_rx,
+            &mut pending,
+            &api,
+            &source_map,
+            std::time::Duration::from_secs(60),
+            1000,
+            &ScanConfig::default(),
+            &None,
+            &mut |_: &Path| {},
+            &WatchActivity::default(),
+        ).await.unwrap();
+
+        assert_eq!(reloaded.as_deref(), Some("new config"));
+        assert_eq!(pending.paths.get(Path::new("/t/a.txt")), Some(&AccumulatedKind::Create));
+        assert!(pending.first_seen_creates.contains(Path::new("/t/a.txt")));
+    }
 }
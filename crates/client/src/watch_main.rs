@@ -1,6 +1,11 @@
-mod api;
-mod batch;
+mod completions;
+mod control;
+mod ignore_files;
+mod journal;
+#[cfg(target_os = "macos")]
+mod launchd;
 mod path_util;
+mod poll;
 mod subprocess;
 mod upload;
 mod walk;
@@ -8,10 +13,11 @@ mod watch;
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, FromArgMatches, Parser};
-#[cfg(windows)]
+#[cfg(any(windows, target_os = "macos"))]
 use clap::Subcommand;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer as _};
 
+use find_client_lib::{api, batch, pause, spool, throttle};
 use find_common::config::{default_config_path, parse_client_config};
 use find_common::logging::LogIgnoreFilter;
 #[cfg(windows)]
@@ -76,11 +82,18 @@ fn service_entry(args: Vec<std::ffi::OsString>) {
             }
         };
 
+        // `sc control <service> paramchange` re-reads the config file.
+        let reload = std::sync::Arc::new(tokio::sync::Notify::new());
+        let reload_handler = reload.clone();
         let event_handler = move |ctrl| match ctrl {
-            ServiceControl::Stop | ServiceControl::Shutdown => {
+            ServiceControl::Stop | ServiceControl::Preshutdown | ServiceControl::Shutdown => {
                 SERVICE_STOP.store(true, Ordering::Relaxed);
                 ServiceControlHandlerResult::NoError
             }
+            ServiceControl::Paramchange => {
+                reload_handler.notify_one();
+                ServiceControlHandlerResult::NoError
+            }
             ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
             _ => ServiceControlHandlerResult::NotImplemented,
         };
@@ -99,7 +112,9 @@ fn service_entry(args: Vec<std::ffi::OsString>) {
         let _ = status_handle.set_service_status(ServiceStatus {
             service_type: ServiceType::OWN_PROCESS,
             current_state: ServiceState::Running,
-            controls_accepted: ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+            controls_accepted: ServiceControlAccept::STOP
+                | ServiceControlAccept::PRESHUTDOWN
+                | ServiceControlAccept::PARAM_CHANGE,
             exit_code: ServiceExitCode::Win32(0),
             checkpoint: 0,
             wait_hint: Duration::default(),
@@ -112,22 +127,30 @@ fn service_entry(args: Vec<std::ffi::OsString>) {
             .as_ref()
             .map(|p| p.to_string_lossy().to_string())
             .unwrap_or_default();
-        let svc_opts = watch::WatchOptions { config_path: svc_config_path, scan_now: false };
-        tokio::select! {
-            _ = watch::run_watch(&config, &svc_opts) => {}
+        let svc_opts = watch::WatchOptions { config_path: svc_config_path, scan_now: false, reload };
+        // The watcher only returns on its own when it gives up; report that
+        // as a failure so the SCM recovery actions restart the service.
+        let failed = tokio::select! {
+            result = watch::run_watch(&config, &svc_opts) => {
+                match result {
+                    Ok(()) => tracing::error!("service: watcher stopped unexpectedly"),
+                    Err(e) => tracing::error!("service: watcher failed: {e:#}"),
+                }
+                true
+            }
             _ = async {
                 loop {
                     tokio::time::sleep(Duration::from_millis(200)).await;
                     if SERVICE_STOP.load(Ordering::Relaxed) { break; }
                 }
-            } => {}
-        }
+            } => false,
+        };
 
         let _ = status_handle.set_service_status(ServiceStatus {
             service_type: ServiceType::OWN_PROCESS,
             current_state: ServiceState::Stopped,
             controls_accepted: ServiceControlAccept::empty(),
-            exit_code: ServiceExitCode::Win32(0),
+            exit_code: if failed { ServiceExitCode::ServiceSpecific(1) } else { ServiceExitCode::Win32(0) },
             checkpoint: 0,
             wait_hint: Duration::default(),
             process_id: None,
@@ -161,6 +184,10 @@ struct Args {
     #[cfg(windows)]
     #[command(subcommand)]
     command: Option<WindowsCommand>,
+
+    #[cfg(target_os = "macos")]
+    #[command(subcommand)]
+    command: Option<MacCommand>,
 }
 
 fn resolve_config(config: Option<String>) -> String {
@@ -182,6 +209,24 @@ enum WindowsCommand {
         /// Windows service name.
         #[arg(long, default_value = find_windows_service::SERVICE_NAME)]
         service_name: String,
+        /// Account to run as: LocalSystem, LocalService, NetworkService,
+        /// virtual (NT SERVICE\<service name>), or a user such as
+        /// .\indexer or DOMAIN\indexer. Use a user account to index network
+        /// shares.
+        #[arg(long, default_value = "LocalSystem")]
+        account: String,
+        /// Password for a user --account (default: the FIND_SERVICE_PASSWORD
+        /// environment variable, which keeps it out of the process list)
+        #[arg(long)]
+        password: Option<String>,
+        /// Also schedule a full scan (`find-scan --full`) with Task
+        /// Scheduler: daily, weekly (Sundays) or monthly (the 1st). Catches
+        /// up on changes the watcher missed.
+        #[arg(long, value_name = "CADENCE")]
+        full_scan: Option<String>,
+        /// Local time of day for --full-scan.
+        #[arg(long, value_name = "HH:MM", default_value = "03:00", requires = "full_scan")]
+        full_scan_at: String,
     },
     /// Uninstall the find-watch Windows Service (requires admin).
     Uninstall {
@@ -194,19 +239,49 @@ enum WindowsCommand {
     ServiceRun,
 }
 
+/// macOS-only subcommands for LaunchAgent management.
+#[cfg(target_os = "macos")]
+#[derive(Subcommand)]
+enum MacCommand {
+    /// Install and start find-watch as a LaunchAgent for the current user.
+    Install {
+        /// launchd label (also the plist file name).
+        #[arg(long, default_value = launchd::DEFAULT_LABEL)]
+        label: String,
+        /// Minimum seconds between restarts after the watcher fails.
+        #[arg(long, default_value_t = 30)]
+        throttle: u32,
+        /// Log file for the watcher's output (default:
+        /// ~/Library/Logs/find-watch.log).
+        #[arg(long)]
+        log: Option<std::path::PathBuf>,
+    },
+    /// Stop and remove the find-watch LaunchAgent.
+    Uninstall {
+        /// launchd label (also the plist file name).
+        #[arg(long, default_value = launchd::DEFAULT_LABEL)]
+        label: String,
+    },
+}
+
 // ── Entry point ───────────────────────────────────────────────────────────────
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    completions::handle(Args::command());
     let args = Args::from_arg_matches(&Args::command().version(find_common::tool_version!()).get_matches()).unwrap_or_else(|e| e.exit());
     let config_path = resolve_config(args.config);
 
-    // On Windows, Install/Uninstall commands don't need config or logging —
-    // handle them before the config read.
+    // Install/Uninstall (Windows service, macOS LaunchAgent) don't need config
+    // or logging — handle them before the config read.
     #[cfg(windows)]
     if let Some(cmd @ (WindowsCommand::Install { .. } | WindowsCommand::Uninstall { .. })) = args.command {
         return run_windows_command(cmd, &config_path);
     }
+    #[cfg(target_os = "macos")]
+    if let Some(cmd) = args.command {
+        return run_mac_command(cmd, &config_path);
+    }
 
     // Read config before logging init so [log] compact = true takes effect.
     // Config errors go to stderr via `?`; no logging needed for that.
@@ -268,6 +343,7 @@ async fn main() -> Result<()> {
     let opts = watch::WatchOptions {
         config_path: config_path.clone(),
         scan_now: args.scan_now,
+        reload: Default::default(),
     };
     watch::run_watch(&config, &opts).await
 }
@@ -275,13 +351,30 @@ async fn main() -> Result<()> {
 #[cfg(windows)]
 fn run_windows_command(cmd: WindowsCommand, config_path: &str) -> Result<()> {
     match cmd {
-        WindowsCommand::Install { service_name } => {
-            find_windows_service::install_service(
-                std::path::Path::new(config_path),
-                &service_name,
-            )
+        WindowsCommand::Install { service_name, account, password, full_scan, full_scan_at } => {
+            let account = find_windows_service::ServiceAccount::parse(&account, password)?;
+            let schedule = full_scan
+                .map(|cadence| find_windows_service::FullScanSchedule::parse(&cadence, &full_scan_at))
+                .transpose()?;
+            let config_path = std::path::Path::new(config_path);
+            find_windows_service::install_service(config_path, &service_name, &account)?;
+            match schedule {
+                Some(schedule) => find_windows_service::install_full_scan_task(
+                    config_path,
+                    &service_name,
+                    &account,
+                    &schedule,
+                ),
+                // Reinstalling without --full-scan drops a previous schedule.
+                None => find_windows_service::uninstall_full_scan_task(&service_name).map(|_| ()),
+            }
         }
         WindowsCommand::Uninstall { service_name } => {
+            // The task can outlive a service removed by other means, so
+            // remove it first and regardless of the service's state.
+            if let Err(e) = find_windows_service::uninstall_full_scan_task(&service_name) {
+                eprintln!("Warning: {e:#}");
+            }
             find_windows_service::uninstall_service(&service_name)
         }
         WindowsCommand::ServiceRun => {
@@ -299,3 +392,26 @@ fn run_windows_command(cmd: WindowsCommand, config_path: &str) -> Result<()> {
         }
     }
 }
+
+#[cfg(target_os = "macos")]
+fn run_mac_command(cmd: MacCommand, config_path: &str) -> Result<()> {
+    match cmd {
+        MacCommand::Install { label, throttle, log } => {
+            // launchd does not expand `~` or relative paths, so pin both down.
+            let config_path = std::fs::canonicalize(config_path)
+                .with_context(|| format!("reading config {config_path}"))?;
+            let agent = launchd::Agent {
+                label,
+                program: std::env::current_exe().context("locating find-watch")?,
+                config_path,
+                log_path: match log {
+                    Some(path) => path,
+                    None => launchd::default_log_path()?,
+                },
+                throttle_secs: throttle,
+            };
+            launchd::install(&agent)
+        }
+        MacCommand::Uninstall { label } => launchd::uninstall(&label),
+    }
+}
@@ -217,11 +217,26 @@ impl TestEnv {
             server: ServerConfig {
                 url: self.server.base_url.clone(),
                 token: TEST_TOKEN.to_string(),
+                report_metrics: false,
+                remote_config: false,
             },
             sources: vec![SourceConfig {
                 name: self.source_name.clone(),
                 path: self.source_dir.path().to_string_lossy().to_string(),
                 include: vec![],
+                ignore_files: None,
+                follow_symlinks: None,
+                change_detection: None,
+                watch_mode: None,
+                s3: None,
+                cloud: None,
+                presets: vec![],
+                git_history: None,
+                scan_schedule: None,
+                full_scan_schedule: None,
+                require_file: None,
+                require_mount: false,
+                remote: None,
             }],
             scan: self.scan_config(),
             watch,
@@ -245,6 +260,7 @@ impl TestEnv {
             paths: &paths,
             include: &[],
             subdir: None,
+            rescan_subdir: false,
         };
         let opts = find_client::scan::ScanOptions {
             upgrade: false,
@@ -253,6 +269,10 @@ impl TestEnv {
             force_since: None,
             mtime_override: None,
             force_index: false,
+            checkpoint_dir: None,
+            extract_cache_dir: None,
+            full: false,
+            report_metrics: false,
         };
         find_client::scan::run_scan(&api, &source, &scan, &opts)
             .await
@@ -263,7 +283,7 @@ impl TestEnv {
     /// Search via the server API and return results.
     pub async fn search(&self, query: &str) -> Vec<SearchResult> {
         let api = self.api_client();
-        api.search(query, "fts", &[self.source_name.clone()], 50, 0)
+        api.search(query, "fts", &[self.source_name.clone()], 50, 0, &Default::default())
             .await
             .expect("search failed")
             .results
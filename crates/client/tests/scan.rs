@@ -301,6 +301,7 @@ async fn s10_force_reindexes_all_files() {
         paths: &paths,
         include: &[],
         subdir: None,
+        rescan_subdir: false,
     };
     let opts = find_client::scan::ScanOptions {
         upgrade: false,
@@ -309,6 +310,10 @@ async fn s10_force_reindexes_all_files() {
         force_since: Some(force_since),
         mtime_override: None,
         force_index: false,
+        checkpoint_dir: None,
+        extract_cache_dir: None,
+        full: false,
+        report_metrics: false,
     };
     find_client::scan::run_scan(&api, &source, &env.scan_config(), &opts)
         .await
@@ -358,6 +363,9 @@ async fn s11_upgrade_reindexes_old_scanner_version() {
             scanner_version: 0, // intentionally old
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: None,
@@ -403,6 +411,7 @@ async fn s11_upgrade_reindexes_old_scanner_version() {
         paths: &paths,
         include: &[],
         subdir: None,
+        rescan_subdir: false,
     };
     let opts = find_client::scan::ScanOptions {
         upgrade: true,
@@ -411,6 +420,10 @@ async fn s11_upgrade_reindexes_old_scanner_version() {
         force_since: None,
         mtime_override: None,
         force_index: false,
+        checkpoint_dir: None,
+        extract_cache_dir: None,
+        full: false,
+        report_metrics: false,
     };
     find_client::scan::run_scan(&api, &source, &env.scan_config(), &opts)
         .await
@@ -428,3 +441,291 @@ async fn s11_upgrade_reindexes_old_scanner_version() {
         "expected scanner_version={SCANNER_VERSION} after --upgrade"
     );
 }
+
+// ── S12 — Appended file is sent as a delta ───────────────────────────────────
+
+#[tokio::test]
+async fn s12_appended_file_indexed_as_delta() {
+    let env = TestEnv::new().await;
+    let path = env.write_file("app.log", "boot sequence_alpha\nready\n");
+    env.run_scan().await;
+    let before = env.list_files().await.into_iter().find(|f| f.path == "app.log").unwrap();
+
+    let new_mtime = std::time::SystemTime::now() + std::time::Duration::from_secs(2);
+    std::fs::write(&path, "boot sequence_alpha\nready\nshutdown sequence_omega\n").expect("append");
+    filetime::set_file_mtime(&path, filetime::FileTime::from_system_time(new_mtime)).expect("set mtime");
+    env.run_scan().await;
+
+    let after = env.list_files().await.into_iter().find(|f| f.path == "app.log").unwrap();
+    assert_ne!(before.file_hash, after.file_hash);
+    assert_eq!(after.size, Some(50));
+    assert_eq!(
+        env.get_file_lines("app.log").await,
+        ["boot sequence_alpha", "ready", "shutdown sequence_omega"],
+    );
+    assert!(!env.search("sequence_alpha").await.is_empty());
+    assert!(!env.search("sequence_omega").await.is_empty());
+}
+
+// ── S13 — Partial scan reconciles only its subtree ───────────────────────────
+
+#[tokio::test]
+async fn s13_partial_scan_reconciles_subtree() {
+    let env = TestEnv::new().await;
+    env.write_file("moved/one.txt", "partial scan one");
+    env.write_file("moved/keep.txt", "partial scan keep");
+    env.write_file("other/two.txt", "partial scan two");
+    env.run_scan().await;
+
+    // Move moved/one.txt elsewhere and delete a file outside the subtree.
+    env.remove_file("moved/one.txt");
+    env.write_file("elsewhere/one.txt", "partial scan one");
+    env.remove_file("other/two.txt");
+
+    let api = env.api_client();
+    let paths = vec![env.source_dir.path().to_string_lossy().to_string()];
+    let source = find_client::scan::ScanSource {
+        name: &env.source_name,
+        paths: &paths,
+        include: &[],
+        subdir: Some("moved".to_string()),
+        rescan_subdir: false,
+    };
+    let opts = find_client::scan::ScanOptions {
+        upgrade: false,
+        quiet: true,
+        dry_run: false,
+        force_since: None,
+        mtime_override: None,
+        force_index: false,
+        checkpoint_dir: None,
+        extract_cache_dir: None,
+        full: false,
+        report_metrics: false,
+    };
+    find_client::scan::run_scan(&api, &source, &env.scan_config(), &opts)
+        .await
+        .expect("partial scan failed");
+    env.server.wait_for_idle().await;
+
+    let mut paths: Vec<String> = env.list_files().await.into_iter().map(|f| f.path).collect();
+    paths.sort();
+    // Only the subtree is reconciled: other/two.txt and elsewhere/ are untouched.
+    assert_eq!(paths, ["moved/keep.txt", "other/two.txt"]);
+}
+
+// ── S14 — Parallel extraction indexes every file ─────────────────────────────
+
+#[tokio::test]
+async fn s14_parallel_extraction_indexes_all_files() {
+    let env = TestEnv::new().await;
+    for i in 0..30 {
+        env.write_file(&format!("dir{}/file{i:02}.txt", i % 3), &format!("parallel_token_{i:02} content"));
+    }
+    env.run_scan_with(env.scan_config_with(|s| {
+        s.workers = 4;
+        s.worker_limits.insert("text".to_string(), 1);
+    }))
+    .await;
+
+    let files = env.list_files().await;
+    assert_eq!(files.len(), 30);
+    assert_eq!(env.search("parallel_token_17").await.len(), 1);
+}
+
+// ── S15 — Interrupted scan resumes from its checkpoint ───────────────────────
+
+#[tokio::test]
+async fn s15_scan_resumes_from_checkpoint() {
+    let env = TestEnv::new().await;
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        env.write_file(name, &format!("checkpoint test {name}"));
+    }
+    let paths = vec![env.source_dir.path().to_string_lossy().to_string()];
+
+    // An interrupted run got as far as b.txt.
+    let state = tempfile::TempDir::new().unwrap();
+    let cp_file = state.path().join(format!("{}.json", env.source_name));
+    let cp = serde_json::json!({
+        "paths": paths,
+        "upgrade": false,
+        "force_since": null,
+        "started_at": 1_700_000_000,
+        "done_through": "b.txt",
+    });
+    std::fs::write(&cp_file, cp.to_string()).unwrap();
+
+    let api = env.api_client();
+    let source = find_client::scan::ScanSource {
+        name: &env.source_name,
+        paths: &paths,
+        include: &[],
+        subdir: None,
+        rescan_subdir: false,
+    };
+    let mut opts = find_client::scan::ScanOptions {
+        upgrade: false,
+        quiet: true,
+        dry_run: false,
+        force_since: None,
+        mtime_override: None,
+        force_index: false,
+        checkpoint_dir: Some(state.path().to_path_buf()),
+        extract_cache_dir: None,
+        full: false,
+        report_metrics: false,
+    };
+    find_client::scan::run_scan(&api, &source, &env.scan_config(), &opts)
+        .await
+        .expect("resumed scan failed");
+    env.server.wait_for_idle().await;
+
+    let mut indexed: Vec<String> = env.list_files().await.into_iter().map(|f| f.path).collect();
+    indexed.sort();
+    assert_eq!(indexed, ["c.txt", "d.txt"]);
+    assert!(!cp_file.exists(), "checkpoint should be removed after a completed scan");
+
+    // --full ignores a stale checkpoint and scans everything.
+    std::fs::write(&cp_file, cp.to_string()).unwrap();
+    opts.full = true;
+    find_client::scan::run_scan(&api, &source, &env.scan_config(), &opts)
+        .await
+        .expect("full scan failed");
+    env.server.wait_for_idle().await;
+    assert_eq!(env.list_files().await.len(), 4);
+}
+
+// ── S16 — Batches spooled while the server is down are sent later ────────────
+
+#[tokio::test]
+async fn s16_spooled_batches_are_forwarded() {
+    use find_client::spool::Spool;
+
+    let env = TestEnv::new().await;
+    let spool_dir = tempfile::TempDir::new().unwrap();
+    let content = find_common::api::IndexLine {
+        archive_path: None,
+        line_number: find_common::api::LINE_CONTENT_START,
+        content: "spooled_phrase_quux".to_string(),
+    };
+    let req = find_common::api::BulkRequest {
+        source: env.source_name.clone(),
+        files: find_client::batch::build_index_files("spooled.txt".into(), 1, 19, FileKind::Text, vec![content]),
+        delete_paths: vec![],
+        scan_timestamp: None,
+        indexing_failures: vec![],
+        rename_paths: vec![],
+    };
+
+    // Nothing listens on port 1: the batch is spooled instead of failing.
+    let offline = find_client::api::ApiClient::new("http://127.0.0.1:1", "token")
+        .with_spool(Some(Spool::open(spool_dir.path().to_path_buf(), 1024 * 1024).unwrap()));
+    offline.bulk(&req).await.expect("bulk should spool while the server is down");
+    assert!(!offline.flush_spool().await);
+
+    let online = env.api_client()
+        .with_spool(Some(Spool::open(spool_dir.path().to_path_buf(), 1024 * 1024).unwrap()));
+    assert!(online.flush_spool().await);
+    env.server.wait_for_idle().await;
+
+    let results = env.search("spooled_phrase_quux").await;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "spooled.txt");
+    assert!(Spool::open(spool_dir.path().to_path_buf(), 1).unwrap().is_empty());
+}
+
+// ── S17 — Content the server already stores is not extracted again ──────────
+
+#[tokio::test]
+async fn s17_known_content_indexed_from_server() {
+    let env = TestEnv::new().await;
+    env.write_file("a.txt", "negotiated_phrase_plugh\nsecond line\n");
+    env.run_scan().await;
+
+    env.write_file("copies/b.txt", "negotiated_phrase_plugh\nsecond line\n");
+    env.run_scan().await;
+
+    let files = env.list_files().await;
+    let a = files.iter().find(|f| f.path == "a.txt").expect("a.txt missing");
+    let b = files.iter().find(|f| f.path == "copies/b.txt").expect("copies/b.txt missing");
+    assert_eq!(a.file_hash, b.file_hash);
+    // Reused content is recorded as unextracted so `--upgrade` revisits it.
+    assert_eq!(b.scanner_version, 0);
+    assert_eq!(env.get_file_lines("copies/b.txt").await, ["negotiated_phrase_plugh", "second line"]);
+
+    let mut paths: Vec<String> = env.search("negotiated_phrase_plugh").await.into_iter().map(|r| r.path).collect();
+    paths.sort();
+    assert_eq!(paths, ["a.txt", "copies/b.txt"]);
+    let metadata = env.get_file_metadata("copies/b.txt").await;
+    assert!(metadata.iter().any(|l| l.contains("copies/b.txt")), "path line should name the new path: {metadata:?}");
+}
+
+// ── S18 — Hash change detection catches restores with preserved mtimes ──────
+
+#[tokio::test]
+async fn s18_hash_change_detection_catches_preserved_mtime() {
+    use find_common::config::ChangeDetection;
+
+    let env = TestEnv::new().await;
+    let path = env.write_file("restored.txt", "restored_alpha_one");
+    env.run_scan().await;
+    let mtime = filetime::FileTime::from_last_modification_time(&std::fs::metadata(&path).unwrap());
+
+    // Same size, same mtime: invisible to the default policy.
+    std::fs::write(&path, "restored_omega_one").unwrap();
+    filetime::set_file_mtime(&path, mtime).unwrap();
+    env.run_scan().await;
+    assert!(env.search("restored_omega_one").await.is_empty());
+
+    let mut cfg = env.scan_config();
+    cfg.change_detection = ChangeDetection::Hash;
+    env.run_scan_with(cfg).await;
+    assert_eq!(env.search("restored_omega_one").await.len(), 1);
+    assert!(env.search("restored_alpha_one").await.is_empty());
+}
+
+// ── S19 — Changed archive re-indexes only its changed members ────────────────
+
+fn build_zip(members: &[(&str, &str)]) -> Vec<u8> {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in members {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[tokio::test]
+async fn s19_archive_update_replaces_changed_and_removed_members() {
+    let env = TestEnv::new().await;
+    let path = env.write_file_bytes("bundle.zip", &build_zip(&[
+        ("keep.txt", "bundle_keep_one"),
+        ("edit.txt", "bundle_edit_before"),
+        ("drop.txt", "bundle_drop_one"),
+    ]));
+    env.run_scan().await;
+    assert_eq!(env.search("bundle_drop_one").await.len(), 1);
+
+    env.write_file_bytes("bundle.zip", &build_zip(&[
+        ("keep.txt", "bundle_keep_one"),
+        ("edit.txt", "bundle_edit_after"),
+        ("new.txt", "bundle_new_one"),
+    ]));
+    filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(2_000_000_000, 0)).unwrap();
+    env.run_scan().await;
+
+    assert_eq!(env.search("bundle_keep_one").await.len(), 1);
+    assert_eq!(env.search("bundle_edit_after").await.len(), 1);
+    assert_eq!(env.search("bundle_new_one").await.len(), 1);
+    assert!(env.search("bundle_edit_before").await.is_empty());
+    assert!(env.search("bundle_drop_one").await.is_empty());
+
+    let files = env.list_files().await;
+    let mut members: Vec<&str> = files.iter()
+        .map(|f| f.path.as_str())
+        .filter(|p| p.starts_with("bundle.zip::"))
+        .collect();
+    members.sort();
+    assert_eq!(members, ["bundle.zip::edit.txt", "bundle.zip::keep.txt", "bundle.zip::new.txt"]);
+}
@@ -13,6 +13,7 @@ async fn start_watcher_with_config(config: find_common::config::ClientConfig) ->
     let opts = WatchOptions {
         config_path: String::new(),
         scan_now: false,
+        reload: Default::default(),
     };
     let handle = tokio::spawn(async move {
         let _ = run_watch(&config, &opts).await;
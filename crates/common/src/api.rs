@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 pub use find_extract_types::index_line::{
     detect_kind_from_ext, IndexLine, SCANNER_VERSION,
-    LINE_PATH, LINE_METADATA, LINE_CONTENT_START,
+    LINE_PATH, LINE_METADATA, LINE_CONTENT_START, SHEET_MARKER, SLIDE_MARKER,
 };
 
 /// Typed representation of a file's kind — replaces the stringly-typed `kind: String`
@@ -113,6 +113,29 @@ pub enum SearchMode {
     Fuzzy,
 }
 
+/// Order of search results (`?sort=` on `GET /api/v1/search`).
+///
+/// `#[serde(other)]` on `Score` — an unrecognised value falls back to
+/// relevance order, as for [`SearchMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// Most recently modified first.
+    Mtime,
+    /// By path, A to Z.
+    Path,
+    /// Largest first; files of unknown size last.
+    Size,
+    /// Files with the most matching lines first (exact and regex modes,
+    /// see `SearchResult::hit_count`); other modes fall back to score.
+    Hits,
+    /// Most relevant first.  Default; also the catch-all for any
+    /// unrecognised value.
+    #[default]
+    #[serde(other)]
+    Score,
+}
+
 /// Action recorded in the activity log and broadcast on `GET /api/v1/recent`.
 ///
 /// No `#[serde(other)]` — the server is the sole producer; an unknown value
@@ -198,6 +221,50 @@ pub struct IndexFile {
     /// file's own mtime (e.g. after a failed first extraction run).
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub force: bool,
+    /// Delta upload: the file grew by appending to the version the server
+    /// indexed, and `lines` holds only the appended content lines (numbered
+    /// from `LINE_CONTENT_START`).  The server keeps the indexed lines and
+    /// adds these after them.  `None` (the default) means `lines` is the
+    /// whole file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub append_to: Option<AppendBase>,
+    /// The server already stores content for `file_hash` (see
+    /// `POST /api/v1/hashes/known`), so `lines` is empty and the server
+    /// indexes the stored content under this path instead.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reuse_content: bool,
+    /// Which extractor produced `lines`.  Set on the outer file by the
+    /// client; `None` for archive members, server-side extraction and
+    /// older clients.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// Which extractor produced a file's indexed lines, stored with the file and
+/// returned by `GET /api/v1/file` for tracking down garbled or missing text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The extractor: a built-in one (`find-extract-pdf`, …) or the
+    /// command configured in `[scan.extractors]`.
+    pub extractor: String,
+    /// Version of the extractor: the find-anything release for built-in
+    /// extractors, `None` for external commands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The file is larger than `scan.max_content_size_mb`, so only its
+    /// beginning was extracted.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// The indexed version an appended-lines delta extends (see
+/// [`IndexFile::append_to`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppendBase {
+    /// `file_hash` of the indexed version.  The server rejects the delta if
+    /// it no longer holds this version; the client then resends the file in
+    /// full on its next scan.
+    pub file_hash: String,
 }
 
 /// One extraction failure reported by the client.
@@ -242,6 +309,52 @@ pub struct BulkRequest {
     pub rename_paths: Vec<PathRename>,
 }
 
+/// `POST /api/v1/bulk/validate` response: counts for a bulk payload and
+/// advisory lint problems, without applying it.  Ingest does not enforce
+/// these checks.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BulkValidateResponse {
+    /// `true` when the payload decoded and no lint problems were found.
+    pub valid: bool,
+    /// Source named by the payload (absent when it could not be decoded).
+    #[serde(default)]
+    pub source: Option<String>,
+    pub files: usize,
+    /// Total index lines across all files, including the path and metadata lines.
+    pub lines: usize,
+    /// Total bytes of line content.
+    pub content_bytes: usize,
+    pub delete_paths: usize,
+    pub rename_paths: usize,
+    pub indexing_failures: usize,
+    /// Problems found, in payload order (capped at 100).
+    #[serde(default)]
+    pub problems: Vec<BulkProblem>,
+}
+
+/// `POST /api/v1/hashes/known` request: content hashes the client is about
+/// to upload.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KnownHashesRequest {
+    pub hashes: Vec<String>,
+}
+
+/// `POST /api/v1/hashes/known` response: the subset of the requested hashes
+/// whose extracted content the server already stores.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KnownHashesResponse {
+    pub known: Vec<String>,
+}
+
+/// One problem found by `POST /api/v1/bulk/validate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkProblem {
+    /// Path the problem relates to, when it concerns a single entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub message: String,
+}
+
 /// One search result.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -257,6 +370,14 @@ pub struct SearchResult {
     /// File size in bytes. None for archive members whose individual sizes are unknown.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub size: Option<i64>,
+    /// When the server last indexed this file (Unix seconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexed_at: Option<i64>,
+    /// When `find-scan` last completed a full scan of the source (Unix
+    /// seconds); `None` if it never has.  Files changed since then may be
+    /// missing or out of date unless `find-watch` picked them up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_last_scan: Option<i64>,
     /// Populated when ?context=N is passed to the search endpoint.
     #[serde(default)]
     pub context_lines: Vec<ContextLine>,
@@ -271,6 +392,18 @@ pub struct SearchResult {
     /// True when this file had more matching lines than the display cap (document mode only).
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub hits_truncated: bool,
+    /// Number of lines in this file that match (exact and regex modes only;
+    /// at most `search.fts_candidate_limit` lines are counted per source).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hit_count: Option<usize>,
+    /// True when the hit is on the file's metadata line (EXIF, audio tags,
+    /// etc.); `snippet` then holds the matching tags as readable text.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub metadata_match: bool,
+    /// Name of the federation peer that produced this hit.  `None` for hits
+    /// from the server that answered the request; omitted from JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
 }
 
 /// GET /api/v1/search response.
@@ -282,6 +415,134 @@ pub struct SearchResponse {
     /// The UI should display "N+" rather than "N" when this is set.
     #[serde(default)]
     pub capped: bool,
+    /// True when the search hit the server's `search.timeout_ms` budget before
+    /// every source finished; `results` holds only the sources that completed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// With `?explain=true`: what the search cost in each local source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<SearchExplain>,
+    /// Sources with results whose last scan is older than the server's
+    /// `search.stale_scan_days`, with that scan's time (Unix seconds).  Sent
+    /// in the [`STALE_SOURCES_HEADER`] header rather than the body;
+    /// `ApiClient::search` fills it in from there.
+    #[serde(skip)]
+    pub stale_sources: Vec<(String, i64)>,
+}
+
+/// Response header of `GET /api/v1/search` listing sources whose results
+/// may be stale, as `name=unix_seconds` pairs separated by `", "`.
+pub const STALE_SOURCES_HEADER: &str = "X-Find-Stale-Sources";
+
+/// Format `stale_sources` for [`STALE_SOURCES_HEADER`].
+pub fn format_stale_sources(stale_sources: &[(String, i64)]) -> String {
+    stale_sources.iter().map(|(source, ts)| format!("{source}={ts}")).collect::<Vec<_>>().join(", ")
+}
+
+/// Parse a [`STALE_SOURCES_HEADER`] value; malformed entries are skipped.
+pub fn parse_stale_sources(header: &str) -> Vec<(String, i64)> {
+    header
+        .split(',')
+        .filter_map(|entry| {
+            let (source, ts) = entry.trim().split_once('=')?;
+            Some((source.to_string(), ts.parse().ok()?))
+        })
+        .collect()
+}
+
+/// How a search spent its time, returned with `?explain=true` to help tune
+/// `search.fts_candidate_limit` and spot slow sources.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchExplain {
+    /// Wall time of the whole request, federation peers included.
+    pub elapsed_ms: u64,
+    /// The server's `search.fts_candidate_limit`.
+    pub fts_candidate_limit: usize,
+    /// One entry per local source searched, by name.
+    pub sources: Vec<SourceExplain>,
+}
+
+/// One source's share of a search.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SourceExplain {
+    pub source: String,
+    /// Wall time spent on this source, once it had a search slot.
+    pub elapsed_ms: u64,
+    /// Time spent waiting for a search slot (`search.max_concurrent_sources`
+    /// and `max_sources_per_request`).
+    #[serde(default)]
+    pub queued_ms: u64,
+    /// Time spent in full-text index queries.
+    pub fts_ms: u64,
+    /// The source was not queried: its term filter rules out a match.
+    #[serde(default)]
+    pub skipped: bool,
+    /// Rows the full-text index returned: lines, or files in `doc-regex` mode.
+    pub candidates: usize,
+    /// The most rows the index query could return for this page.
+    pub candidate_limit: usize,
+    /// `candidates` reached `candidate_limit`, so later matches were not
+    /// considered.
+    #[serde(default)]
+    pub capped: bool,
+    /// Candidates rejected after the index query: by the case-sensitive,
+    /// regex or phrase checks, or by the fuzzy scorer.
+    pub dropped: usize,
+    /// Lines read from the content store for post-filtering, scoring and
+    /// hit counts.
+    pub lines_read: usize,
+    /// Content store reads those lines took, one per file.
+    pub content_reads: usize,
+    /// Why the source returned no results: an error, or the time budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// GET /api/v1/search/summary response: match counts and matching files,
+/// taken from the full-text index without reading or scoring content.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchSummaryResponse {
+    /// Sources with at least one match, by name.
+    pub sources: Vec<SourceMatchCount>,
+    /// With `?files=true`: every file with a match, by source then path.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<MatchingFile>,
+    /// True when the counts can include lines the full search would reject
+    /// (regex and case-sensitive searches), making them upper bounds.
+    #[serde(default)]
+    pub approximate: bool,
+}
+
+/// Matches in one source: lines, or files in the document modes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourceMatchCount {
+    pub source: String,
+    pub count: usize,
+    /// True when counting stopped at the server's `search.fts_candidate_limit`.
+    #[serde(default)]
+    pub capped: bool,
+}
+
+/// A file with at least one match.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchingFile {
+    pub source: String,
+    /// Composite (`outer.zip::member`) for archive members.
+    pub path: String,
+}
+
+/// One matching line in `GET /api/v1/search/export` output: an NDJSON
+/// record, or a CSV row with these fields as columns, in this order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchExportRow {
+    pub source: String,
+    pub path: String,
+    pub archive_path: Option<String>,
+    pub line_number: usize,
+    pub kind: FileKind,
+    pub mtime: i64,
+    pub size: Option<i64>,
+    pub content: String,
 }
 
 /// One line in a context window.
@@ -303,6 +564,9 @@ pub struct ContextResponse {
     /// than reconstructing positions via `start + index`.
     pub lines: Vec<ContextLine>,
     pub kind: FileKind,
+    /// File-level notes plus notes on lines within the returned window.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
 }
 
 /// GET /api/v1/file response.
@@ -338,6 +602,40 @@ pub struct FileResponse {
     /// Populated from the `duplicates` table; empty when there are no duplicates.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub duplicate_paths: Vec<String>,
+    /// Notes attached to this file or its lines.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// Milliseconds the client took to extract the file, if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract_ms: Option<i64>,
+    /// Which extractor produced the lines, if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// GET /api/v1/file/preview response: a bounded window of a file's content
+/// with a syntax-highlighting hint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewResponse {
+    /// Line number of the first element in `lines` (server-space, as in
+    /// `ContextResponse`); 0 when the window is empty.
+    pub start: usize,
+    /// Each line carries its own line number; lines may be sparse (PDFs).
+    pub lines: Vec<ContextLine>,
+    /// Number of content lines in the whole file.
+    pub total_lines: usize,
+    pub kind: FileKind,
+    /// highlight.js language id detected from the file name or its `#!`
+    /// line, or null when the content is not recognisable source code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// True when the file has content outside the returned window.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// True when the file's content has not yet been written by the
+    /// background worker (see `FileResponse::content_unavailable`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_unavailable: bool,
 }
 
 /// GET /api/v1/files response entry (for deletion detection / Ctrl+P).
@@ -355,6 +653,13 @@ pub struct FileRecord {
     /// prior interrupted run.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub indexed_at: Option<i64>,
+    /// Size in bytes and content hash of the indexed version.  Used by
+    /// `find-scan` to detect files that only grew by appending (log files)
+    /// and send just the new lines.  Absent from older servers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_hash: Option<String>,
 }
 
 /// One entry in a directory listing.
@@ -415,6 +720,10 @@ pub struct ContextBatchRequest {
 pub struct ContextBatchResult {
     pub source: String,
     pub path: String,
+    /// Echoes the request's `archive_path`, so that results for members of
+    /// the same archive can be told apart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_path: Option<String>,
     pub line: usize,
     pub start: usize,
     pub match_index: Option<usize>,
@@ -422,6 +731,9 @@ pub struct ContextBatchResult {
     /// than reconstructing positions via `start + index`.
     pub lines: Vec<ContextLine>,
     pub kind: FileKind,
+    /// File-level notes plus notes on lines within the returned window.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
 }
 
 /// POST /api/v1/context-batch response.
@@ -490,6 +802,21 @@ pub struct ExtStat {
     pub size: i64,
 }
 
+/// Index space taken by one kind or one top-level directory of a source, in
+/// `SourceStats::space_by_kind` and `space_by_dir`.  Sorted by
+/// `content_bytes` descending.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpaceStat {
+    /// The kind (`"pdf"`), or the directory (`"Projects"`; `""` for files at
+    /// the source root).
+    pub name: String,
+    pub files: usize,
+    /// Size of the original files.
+    pub size: i64,
+    /// Their extracted content in the content store, compressed.
+    pub content_bytes: u64,
+}
+
 /// One point in the scan history time series.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanHistoryPoint {
@@ -498,6 +825,14 @@ pub struct ScanHistoryPoint {
     pub total_size: i64,
 }
 
+/// `GET /api/v1/history` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanHistoryResponse {
+    pub source: String,
+    /// Downsampled history points, oldest first.
+    pub points: Vec<ScanHistoryPoint>,
+}
+
 /// One row from the server's `indexing_errors` table.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexingError {
@@ -542,6 +877,23 @@ pub struct SourceStats {
     /// backlog at the DB level).  Zero once fully indexed.
     #[serde(default)]
     pub files_pending_content: usize,
+    /// On-disk size of the source's database, with its write-ahead log.
+    #[serde(default)]
+    pub db_size_bytes: u64,
+    /// The source's content in the content store, compressed.  Content
+    /// shared with other sources counts in each of them.
+    #[serde(default)]
+    pub content_bytes: u64,
+    /// `[sources.<name>] max_index_mb`, in bytes: the most `db_size_bytes`
+    /// plus `content_bytes` may reach before new files are refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_budget_bytes: Option<u64>,
+    /// Where `content_bytes` goes, by kind and by top-level directory
+    /// (outer files, with their archive members).
+    #[serde(default)]
+    pub space_by_kind: Vec<SpaceStat>,
+    #[serde(default)]
+    pub space_by_dir: Vec<SpaceStat>,
 }
 
 /// Current processing state of the inbox worker.
@@ -559,6 +911,17 @@ pub enum WorkerStatus {
     },
 }
 
+/// One in-flight inbox request.  The worker runs up to
+/// `[worker] concurrency` requests at once, at most one per source.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkerLane {
+    /// Source the request writes to.
+    pub source: String,
+    /// Relative path of the file currently being processed, or a progress
+    /// note such as `(0/120)` between files.
+    pub file: String,
+}
+
 
 /// `GET /api/v1/stats` response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -577,9 +940,17 @@ pub struct StatsResponse {
     pub content_size_bytes: u64,
     /// Current state of the inbox worker.
     pub worker_status: WorkerStatus,
+    /// Every in-flight request, ordered by source.  `worker_status` shows
+    /// the first of these.
+    #[serde(default)]
+    pub worker_lanes: Vec<WorkerLane>,
     /// True when inbox processing has been paused via `POST /api/v1/admin/inbox/pause`.
     #[serde(default)]
     pub inbox_paused: bool,
+    /// True while maintenance mode refuses new indexing requests
+    /// (`POST /api/v1/admin/maintenance`).
+    #[serde(default)]
+    pub maintenance: bool,
     /// Total compressed size of orphaned chunks in the content store (bytes).
     /// `None` if the background scanner has not yet run.
     #[serde(default)]
@@ -605,11 +976,19 @@ pub struct StatsStreamEvent {
     pub content_size_bytes: u64,
     pub worker_status: WorkerStatus,
     #[serde(default)]
+    pub worker_lanes: Vec<WorkerLane>,
+    #[serde(default)]
     pub inbox_paused: bool,
     #[serde(default)]
     pub orphaned_bytes: Option<u64>,
     #[serde(default)]
     pub orphaned_stats_age_secs: Option<u64>,
+    /// Files and lines indexed since the server started.  Rates come
+    /// from the difference between two events.
+    #[serde(default)]
+    pub files_indexed: u64,
+    #[serde(default)]
+    pub lines_indexed: u64,
 }
 
 /// Per-source snapshot for SSE streaming.
@@ -624,6 +1003,131 @@ pub struct SourceStreamSnapshot {
     pub files_pending_content: usize,
 }
 
+// ── Replication ──────────────────────────────────────────────────────────────
+
+/// One source database listed in a replication manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaSourceEntry {
+    pub name: String,
+    /// Opaque change marker; replicas re-fetch the database when it differs
+    /// from the value recorded at the last sync.
+    pub version: String,
+    /// Size of the database file on the primary, in bytes.
+    pub size: u64,
+}
+
+/// `GET /api/v1/replica/manifest` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaManifest {
+    pub sources: Vec<ReplicaSourceEntry>,
+}
+
+/// `POST /api/v1/replica/blobs` request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaBlobsRequest {
+    /// Content keys (`file_hash` values) to fetch.
+    pub keys: Vec<String>,
+}
+
+/// One content blob returned to a replica: all lines joined with `'\n'`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaBlob {
+    pub key: String,
+    pub content: String,
+}
+
+/// `POST /api/v1/replica/blobs` response. Keys unknown to the primary are omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaBlobsResponse {
+    pub blobs: Vec<ReplicaBlob>,
+}
+
+// ── Client metrics ────────────────────────────────────────────────────────────
+
+/// `POST /api/v1/client-metrics` request body: how a `find-scan` run or a
+/// running `find-watch` is doing, so the server can show client-side health
+/// next to its own.  `GET /api/v1/client-metrics` returns the latest report
+/// per host, tool and source.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientMetrics {
+    pub host: String,
+    /// `"find-scan"` or `"find-watch"`.
+    pub tool: String,
+    pub version: String,
+    /// When the server received the report (Unix seconds); set by the server.
+    #[serde(default)]
+    pub received_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ScanMetrics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch: Option<WatchMetrics>,
+}
+
+/// One completed scan of a whole source.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanMetrics {
+    pub source: String,
+    pub started_at: i64,
+    pub duration_secs: f64,
+    pub files_indexed: usize,
+    /// Files whose mtime had not changed.
+    pub files_unchanged: usize,
+    /// Files left out by a filter or for want of an extractor.
+    pub files_excluded: usize,
+    pub files_deleted: usize,
+    /// Files whose extraction failed.
+    pub failures: usize,
+    /// Per-file extraction time in milliseconds; `None` when nothing was
+    /// extracted.
+    pub extract_ms: Option<Percentiles>,
+}
+
+/// A running watcher's counters since it started.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchMetrics {
+    pub started_at: i64,
+    pub events_received: u64,
+    pub files_updated: u64,
+    pub files_deleted: u64,
+    pub errors: u64,
+    /// Paths waiting for the current batch window.
+    pub pending_paths: usize,
+    /// Batches held locally while the server was unreachable.
+    pub spooled_batches: usize,
+    pub last_event_at: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+/// Summary of a set of samples.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `samples`; `None` when there are none.
+    pub fn of(mut samples: Vec<u64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            count: samples.len(),
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
 // ── Inbox admin types ─────────────────────────────────────────────────────────
 
 /// One item in the inbox (pending or failed), returned by `GET /api/v1/admin/inbox`.
@@ -632,6 +1136,28 @@ pub struct InboxItem {
     pub filename: String,
     pub size_bytes: u64,
     pub age_secs: u64,
+    /// Why the request was quarantined (failed items only; absent for
+    /// requests that failed before diagnostics were recorded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<InboxFailure>,
+}
+
+/// Diagnostics written next to a quarantined request as
+/// `inbox/failed/<name>.error.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxFailure {
+    /// The error that caused the request to be quarantined.
+    pub error: String,
+    /// Source named by the request, when it could be decoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// File entry being indexed when the request failed, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Number of times the worker attempted the request.
+    pub attempts: u32,
+    /// Unix timestamp (seconds) when the request was quarantined.
+    pub failed_at: i64,
 }
 
 /// `GET /api/v1/admin/inbox` response.
@@ -671,6 +1197,33 @@ pub struct InboxPauseResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InboxResumeResponse {}
 
+/// `POST /api/v1/admin/maintenance` request: turn maintenance mode on or off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    /// Shown to clients whose requests are refused, e.g. "nightly backup".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Seconds clients are told to wait before retrying (`Retry-After`).
+    /// Defaults to 300.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// `GET`/`POST /api/v1/admin/maintenance` response.  While enabled, bulk
+/// ingestion and uploads are refused with 503 and search keeps working.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// When maintenance mode was turned on (Unix seconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub retry_after_secs: u64,
+}
+
 /// `POST /api/v1/admin/compact` response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactResponse {
@@ -681,9 +1234,85 @@ pub struct CompactResponse {
     pub units_deleted: usize,
     pub chunks_removed: usize,
     pub bytes_freed: u64,
+    /// Stored original files removed because no source references them.
+    #[serde(default)]
+    pub originals_removed: usize,
+    pub dry_run: bool,
+}
+
+/// `POST /api/v1/admin/maintain` request body.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintainRequest {
+    /// Only this source; every source when absent.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Rebuild each source DB to return free pages to the filesystem.
+    #[serde(default)]
+    pub vacuum: bool,
+    /// Merge the full-text index segments of each source DB.
+    #[serde(default)]
+    pub optimize_fts: bool,
+    /// Remove orphaned content chunks (the same pass as `/admin/compact`).
+    #[serde(default)]
+    pub gc_chunks: bool,
+}
+
+/// On-disk size of one source DB before and after maintenance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceMaintainResult {
+    pub source: String,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// `POST /api/v1/admin/maintain` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintainResponse {
+    pub sources: Vec<SourceMaintainResult>,
+    /// Present when `gc_chunks` was requested.
+    pub compact: Option<CompactResponse>,
+}
+
+/// `POST /api/v1/admin/reextract` request body.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReextractRequest {
+    /// Only this source; every source when absent.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Only files of this kind.
+    #[serde(default)]
+    pub kind: Option<FileKind>,
+    /// Only files extracted by this extractor (as in [`Provenance::extractor`]).
+    #[serde(default)]
+    pub extractor: Option<String>,
+    /// Queue files extracted by a version older than this; the server's own
+    /// version when absent.
+    #[serde(default)]
+    pub before: Option<String>,
+    /// Also queue files indexed without provenance, i.e. by clients older
+    /// than the one that started recording it.
+    #[serde(default)]
+    pub include_unrecorded: bool,
+    /// Count the files without queueing them.
+    #[serde(default)]
     pub dry_run: bool,
 }
 
+/// Files queued for re-extraction in one source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReextractResult {
+    pub source: String,
+    pub files: usize,
+}
+
+/// `POST /api/v1/admin/reextract` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReextractResponse {
+    /// The version files were compared against.
+    pub before: String,
+    pub sources: Vec<SourceReextractResult>,
+}
+
 /// `DELETE /api/v1/admin/source` response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceDeleteResponse {
@@ -691,6 +1320,66 @@ pub struct SourceDeleteResponse {
     pub chunks_removed: usize,
 }
 
+/// `POST /api/v1/admin/source/rename` request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRenameRequest {
+    pub from: String,
+    pub to: String,
+}
+
+/// `POST /api/v1/admin/source/rename` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRenameResponse {
+    pub files: usize,
+    /// Annotations moved to the new name.
+    pub annotations: usize,
+    /// Share links moved to the new name.
+    pub links: usize,
+}
+
+/// A deleted file held in its source's trash (`[server] trash_days`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedFile {
+    pub path: String,
+    pub kind: FileKind,
+    pub size: Option<i64>,
+    pub mtime: i64,
+    /// When the deletion was received (Unix seconds).
+    pub deleted_at: i64,
+    /// When it is removed for good (Unix seconds).
+    pub purge_at: i64,
+}
+
+/// `GET /api/v1/admin/trash` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashResponse {
+    /// Outer files only, most recently deleted first.
+    pub files: Vec<TrashedFile>,
+    /// Outer files in the trash matching the query, before `limit`.
+    pub total: usize,
+}
+
+/// `POST /api/v1/admin/trash/restore` request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashRestoreRequest {
+    pub source: String,
+    /// Outer paths to restore, with their archive members.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Restore everything under this directory as well (`""` = the whole trash).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+/// `POST /api/v1/admin/trash/restore` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashRestoreResponse {
+    /// Outer files put back in the index.
+    pub restored: usize,
+    /// Outer files left in the trash because the path has been indexed again.
+    pub skipped: usize,
+}
+
 /// Summary of one file within an inbox batch, returned by `GET /api/v1/admin/inbox/show`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InboxShowFile {
@@ -754,6 +1443,10 @@ pub struct RecentFile {
     /// For `action = "renamed"`: the new (post-rename) path.  `None` for all other actions.
     #[serde(default)]
     pub new_path: Option<String>,
+    /// Kind of the file, when it is still indexed.  `None` for deletions and
+    /// from older servers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<FileKind>,
 }
 
 
@@ -804,6 +1497,45 @@ pub struct ResolveLinkResponse {
     pub expires_at: i64,
 }
 
+// ── Annotation types ──────────────────────────────────────────────────────────
+
+/// A short note attached to a file, or to one line of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: i64,
+    pub source: String,
+    /// Full (possibly composite) file path.
+    pub path: String,
+    /// Line the note is attached to, numbered like `ContextLine::line_number`;
+    /// `None` for a note on the whole file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<usize>,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Unix timestamps (seconds).
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// `POST /api/v1/annotations` request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAnnotationRequest {
+    pub source: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<usize>,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+/// `PATCH /api/v1/annotations/{id}` request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateAnnotationRequest {
+    pub body: String,
+}
+
 // ── Upload API types ───────────────────────────────────────────────────────────
 
 /// Client-side scan settings forwarded with an upload request so the server
@@ -866,6 +1598,32 @@ pub struct UploadPatchResponse {
     pub received: u64,
 }
 
+/// `POST /api/v1/bulk/sessions` request — open a resumable bulk upload.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkSessionInitRequest {
+    /// The payload is a v2 NDJSON stream (the `/api/v2/bulk` body) rather
+    /// than a gzip-compressed [`BulkRequest`].
+    #[serde(default)]
+    pub stream: bool,
+    /// Total payload size in bytes, if known.  Commit is refused until
+    /// exactly this many bytes have been received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// State of a resumable bulk upload session (`POST /api/v1/bulk/sessions`,
+/// `GET /api/v1/bulk/sessions/{id}`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkSessionResponse {
+    pub session_id: String,
+    /// Bytes received so far; the next `PATCH` must start at this offset.
+    pub received: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
 #[cfg(test)]
 mod file_kind_tests {
     use super::*;
@@ -957,6 +1715,20 @@ mod file_kind_tests {
     }
 }
 
+#[cfg(test)]
+mod percentile_tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        assert_eq!(Percentiles::of(vec![]), None);
+        let p = Percentiles::of((1..=100).rev().collect()).unwrap();
+        assert_eq!((p.count, p.p50, p.p90, p.p99, p.max), (100, 50, 90, 99, 100));
+        let p = Percentiles::of(vec![7]).unwrap();
+        assert_eq!((p.p50, p.p99, p.max), (7, 7, 7));
+    }
+}
+
 #[cfg(test)]
 mod search_mode_tests {
     use super::*;
@@ -12,6 +12,7 @@ use tracing::warn;
 #[derive(Deserialize)]
 struct ClientDefaults {
     scan: ScanDefaults,
+    presets: std::collections::BTreeMap<String, Vec<String>>,
     watch: WatchDefaults,
     log: LogDefaults,
 }
@@ -26,6 +27,11 @@ struct ScanDefaults {
     batch_size: usize,
     batch_bytes: usize,
     batch_interval_secs: u64,
+    batch_target_kb: usize,
+    workers: usize,
+    worker_limits: std::collections::HashMap<String, usize>,
+    spool_max_mb: u64,
+    extract_cache_mb: u64,
     archives: ArchiveDefaults,
 }
 
@@ -34,12 +40,14 @@ struct ArchiveDefaults {
     max_depth: usize,
     max_temp_file_mb: usize,
     max_7z_solid_block_mb: usize,
+    stream_oversized_7z_blocks: bool,
 }
 
 #[derive(Deserialize)]
 struct WatchDefaults {
     batch_window_secs: f64,
     scan_interval_hours: f64,
+    poll_interval_secs: u64,
 }
 
 #[derive(Deserialize)]
@@ -52,6 +60,9 @@ struct ServerDefaults {
     server: ServerSettingsDefaults,
     search: SearchDefaults,
     extraction: ExtractionDefaults,
+    /// `[server] profile` presets: partial configs whose keys fill in what
+    /// server.toml leaves unset.  See [`apply_server_profile`].
+    profiles: std::collections::BTreeMap<String, toml::Table>,
     // log.ignore shares the same default_log_ignore() function as the client;
     // both files have identical values.  Parsed by serde but not stored here.
 }
@@ -73,6 +84,10 @@ struct SearchDefaults {
     max_limit: usize,
     fts_candidate_limit: usize,
     context_window: usize,
+    timeout_ms: u64,
+    max_concurrent_sources: usize,
+    max_sources_per_request: usize,
+    stale_scan_days: u64,
 }
 
 #[derive(Deserialize)]
@@ -120,6 +135,15 @@ pub struct ClientConfig {
 pub struct ServerConfig {
     pub url: String,
     pub token: String,
+    /// Send `find-scan` and `find-watch` health reports (scan durations,
+    /// extraction times, watcher counters) to the server.  Default: true.
+    #[serde(default = "default_true")]
+    pub report_metrics: bool,
+    /// Fetch scan settings from the server at startup
+    /// (`GET /api/v1/client-config`) and apply them on top of this file.
+    /// Default: false.
+    #[serde(default)]
+    pub remote_config: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,6 +164,240 @@ pub struct SourceConfig {
     /// ```
     #[serde(default)]
     pub include: Vec<String>,
+
+    /// Honour `.gitignore`, `.ignore` and `.fdignore` files in this source.
+    /// `None` = use `[scan] ignore_files`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_files: Option<bool>,
+
+    /// Follow symbolic links in this source.  `None` = use
+    /// `[scan] follow_symlinks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_symlinks: Option<bool>,
+
+    /// How to tell whether a file changed since it was indexed.  `None` =
+    /// use `[scan] change_detection`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_detection: Option<ChangeDetection>,
+
+    /// How `find-watch` follows this source.  `None` = use `[watch] mode`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch_mode: Option<WatchMode>,
+
+    /// Connection settings when `path` is an S3 URL, `s3://bucket/prefix`.
+    /// Only needed for credentials in the file or a non-AWS endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3: Option<S3SourceConfig>,
+
+    /// OAuth settings when `path` names a cloud drive (`gdrive:`,
+    /// `onedrive:` or `dropbox:`, optionally followed by a folder).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<CloudSourceConfig>,
+
+    /// Built-in exclude sets added to `scan.exclude` for this source, e.g.
+    /// `["dev", "os"]`.  See [`exclude_preset_names`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<String>,
+
+    /// Index the history of git repositories in this source.  `None` = use
+    /// `[scan.git_history]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_history: Option<GitHistoryConfig>,
+
+    /// When `find-watch` runs `find-scan` on this source, as a cron
+    /// expression in local time (`"0 * * * *"`, `"@weekly"`; see
+    /// [`crate::schedule`]).  The source is then left out of the
+    /// `[watch] scan_interval_hours` scan.  `None` = that scan only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_schedule: Option<String>,
+
+    /// When `find-watch` re-indexes every file of this source
+    /// (`find-scan --force`), as a cron expression in local time.
+    /// `None` = never.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_scan_schedule: Option<String>,
+
+    /// A file, relative to `path`, that must exist for `find-scan` to scan
+    /// this source, e.g. a `.find-anything` file placed on a network share.
+    /// Without it the volume is taken to be unmounted and the source is
+    /// skipped, instead of every indexed file being deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_file: Option<String>,
+
+    /// Only scan this source while `path` is a mount point: on a different
+    /// device from its parent directory (Unix only).  Default: false.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_mount: bool,
+
+    /// Settings the server hands out for this source
+    /// ([`ClientConfig::apply_remote`]).
+    #[serde(skip)]
+    pub remote: Option<RemoteScanSettings>,
+}
+
+/// What `find-scan` indexes from the history of each git repository it
+/// finds, besides the working tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitHistoryConfig {
+    /// Index the commit log as `<repo>@log`.  Default: true.
+    #[serde(default = "default_true")]
+    pub commits: bool,
+
+    /// Glob patterns of the tags whose files are indexed, each as an
+    /// archive `<repo>@<tag>` with members `<repo>@<tag>::<path>`.  Default:
+    /// `["*"]`.
+    #[serde(default = "default_git_tags")]
+    pub tags: Vec<String>,
+
+    /// Only the newest this many matching tags are indexed.  Default: 20.
+    #[serde(default = "default_git_max_tags")]
+    pub max_tags: usize,
+}
+
+impl Default for GitHistoryConfig {
+    fn default() -> Self {
+        Self { commits: true, tags: default_git_tags(), max_tags: default_git_max_tags() }
+    }
+}
+
+/// A cloud drive provider, named by the scheme of a source's `path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudProvider {
+    GoogleDrive,
+    OneDrive,
+    Dropbox,
+}
+
+impl CloudProvider {
+    /// The `path` scheme naming this provider, with its colon.
+    pub fn scheme(self) -> &'static str {
+        match self {
+            CloudProvider::GoogleDrive => "gdrive:",
+            CloudProvider::OneDrive => "onedrive:",
+            CloudProvider::Dropbox => "dropbox:",
+        }
+    }
+}
+
+/// How to sign in to the drive of a cloud source.  The app is registered
+/// with the provider by the user; `find-admin cloud-login` then obtains a
+/// refresh token for it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloudSourceConfig {
+    /// OAuth client ID of the registered app.
+    pub client_id: String,
+
+    /// OAuth client secret, for providers that issue one to desktop apps
+    /// (Google).  Others use PKCE alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+
+    /// Refresh token to use instead of the one saved by `find-admin
+    /// cloud-login` — e.g. when `find-scan` runs as a service account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+
+    /// Port of the redirect URI `http://localhost:<port>/` that `find-admin
+    /// cloud-login` listens on; register this URI with the app.  Default:
+    /// 53682.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_port: Option<u16>,
+
+    /// Files larger than this are indexed by name only.  Default: 64.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_mb: Option<u64>,
+}
+
+/// How to reach the bucket of an S3 source (`path = "s3://bucket/prefix"`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct S3SourceConfig {
+    /// Base URL of an S3-compatible service (MinIO, Backblaze B2, …).
+    /// Default: AWS, `https://s3.<region>.amazonaws.com`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Region used to sign requests.  Default: `AWS_REGION`, else
+    /// `"us-east-1"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// Default: the `AWS_ACCESS_KEY_ID` environment variable.  With no key
+    /// at all, requests are sent unsigned (public buckets).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+
+    /// Default: the `AWS_SECRET_ACCESS_KEY` environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+
+    /// Default: the `AWS_SESSION_TOKEN` environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+
+    /// Address the bucket as `<endpoint>/<bucket>` rather than
+    /// `<bucket>.<endpoint host>`.  Most self-hosted services need this.
+    /// Default: true when `endpoint` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_style: Option<bool>,
+
+    /// Objects larger than this are not downloaded whole: for text the first
+    /// `max_object_mb` is fetched with a ranged GET and indexed, other
+    /// objects are indexed by name only.  Default: 64.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_object_mb: Option<u64>,
+}
+
+impl SourceConfig {
+    /// `(bucket, prefix)` when `path` is an `s3://bucket/prefix` URL.  The
+    /// prefix has no leading slash and is empty for a whole bucket.
+    pub fn s3_location(&self) -> Option<(&str, &str)> {
+        let rest = self.path.strip_prefix("s3://")?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        (!bucket.is_empty()).then_some((bucket, prefix.trim_start_matches('/')))
+    }
+
+    /// `(provider, folder)` when `path` names a cloud drive, e.g.
+    /// `"dropbox:/Work/Reports"`.  The folder has no surrounding slashes and
+    /// is empty for the whole drive.
+    pub fn cloud_location(&self) -> Option<(CloudProvider, &str)> {
+        [CloudProvider::GoogleDrive, CloudProvider::OneDrive, CloudProvider::Dropbox]
+            .into_iter()
+            .find_map(|p| self.path.strip_prefix(p.scheme()).map(|folder| (p, folder.trim_matches('/'))))
+    }
+
+    /// Whether the source is fetched over an API (S3 or a cloud drive)
+    /// rather than read from the filesystem.  `find-watch` skips these.
+    pub fn is_remote(&self) -> bool {
+        self.s3_location().is_some() || self.cloud_location().is_some()
+    }
+
+    /// The scan settings for this source: `global` with the source's own
+    /// overrides applied.
+    pub fn scan_config(&self, global: &ScanConfig) -> ScanConfig {
+        let mut scan = global.clone();
+        if let Some(v) = self.ignore_files {
+            scan.ignore_files = v;
+        }
+        if let Some(v) = self.follow_symlinks {
+            scan.follow_symlinks = v;
+        }
+        if let Some(v) = self.change_detection {
+            scan.change_detection = v;
+        }
+        if let Some(v) = &self.git_history {
+            scan.git_history = Some(v.clone());
+        }
+        for name in &self.presets {
+            if let Some(patterns) = exclude_preset(name) {
+                let added: Vec<String> = patterns.iter().filter(|p| !scan.exclude.contains(p)).cloned().collect();
+                scan.exclude.extend(added);
+            }
+        }
+        match &self.remote {
+            Some(remote) => remote.apply(&scan),
+            None => scan,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -167,9 +425,19 @@ pub struct ScanConfig {
     #[serde(default = "default_max_content_size_mb", alias = "max_file_size_mb")]
     pub max_content_size_mb: u64,
 
+    /// Follow symbolic links to files and directories.  Each directory is
+    /// walked once however many links reach it, links to directories inside
+    /// the source's own tree are skipped (the real path is walked instead),
+    /// and link cycles are broken.  Default: false (symlinks are not indexed).
     #[serde(default)]
     pub follow_symlinks: bool,
 
+    /// When following symlinks, add a `[LINK:target]` metadata entry holding
+    /// the link's target path to each symlinked file, so it is searchable.
+    /// Default: false.
+    #[serde(default)]
+    pub symlink_targets: bool,
+
     /// When false (default), do not descend into directories that reside on a
     /// different filesystem than the walk root.  This prevents accidentally
     /// traversing mounted backup volumes, network shares, bind mounts, etc.
@@ -180,6 +448,12 @@ pub struct ScanConfig {
     #[serde(default)]
     pub include_hidden: bool,
 
+    /// When true, `.gitignore`, `.ignore` and `.fdignore` files found during
+    /// traversal exclude the paths they match, as in git.  Per-source
+    /// `ignore_files` takes precedence.  Default: false.
+    #[serde(default)]
+    pub ignore_files: bool,
+
     #[serde(default)]
     pub archives: ArchiveConfig,
 
@@ -212,6 +486,13 @@ pub struct ScanConfig {
     #[serde(default)]
     pub server_fallback: bool,
 
+    /// When true, upload each file's original bytes alongside its extracted
+    /// content so the server can serve `/api/v1/raw` downloads without a
+    /// mounted source path (requires `[originals] enabled = true` on the
+    /// server).  Default: false.
+    #[serde(default)]
+    pub upload_originals: bool,
+
     /// Maximum number of seconds to wait for a single file's extraction
     /// subprocess before killing it and recording a failure.
     /// Default: 300 (5 minutes).
@@ -219,11 +500,13 @@ pub struct ScanConfig {
     pub subprocess_timeout_secs: u64,
 
     /// Maximum number of files in a single batch submitted to the server.
+    /// With `batch_target_kb` set, only the starting point for auto-tuning.
     /// Default: 200.
     #[serde(default = "default_batch_size")]
     pub batch_size: usize,
 
     /// Maximum total content bytes in a single batch submitted to the server.
+    /// With `batch_target_kb` set, only the starting point for auto-tuning.
     /// Default: 8388608 (8 MB).
     #[serde(default = "default_batch_bytes")]
     pub batch_bytes: usize,
@@ -235,6 +518,91 @@ pub struct ScanConfig {
     #[serde(default = "default_batch_interval_secs")]
     pub batch_interval_secs: u64,
 
+    /// Target compressed size of each bulk request in KB.  `find-scan`
+    /// measures every request it sends and adjusts the file-count and
+    /// content-byte limits so requests approach this size, and sends a file
+    /// larger than the target on its own.  0 = fixed `batch_size` /
+    /// `batch_bytes`.  Default: 4096 (4 MB).
+    #[serde(default = "default_batch_target_kb")]
+    pub batch_target_kb: usize,
+
+    /// Number of files `find-scan` extracts concurrently.  A single task still
+    /// assembles and uploads batches in walk order.  Archives and tempdir-mode
+    /// external extractors are always extracted one at a time.  Default: 1.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+
+    /// Per-kind caps on concurrent extractions when `workers > 1`, keyed by
+    /// file kind (`pdf`, `image`, `video`, …), so one slow kind cannot occupy
+    /// every worker.  Default: `{ pdf = 2 }`.
+    #[serde(default = "default_worker_limits")]
+    pub worker_limits: std::collections::HashMap<String, usize>,
+
+    /// Cap on upload bandwidth to the server in kilobits per second, shared
+    /// by everything one client process uploads (batches, server-fallback
+    /// files, originals).  `find-watch` honours it too.  0 = unlimited
+    /// (default).
+    #[serde(default)]
+    pub upload_rate_limit_kbps: u64,
+
+    /// Daily local-time window in which uploads are sent, as `"HH:MM-HH:MM"`;
+    /// it may wrap midnight (`"22:00-06:00"`).  Outside the window, uploads
+    /// wait for it to open while extraction continues.  Default: unset (any
+    /// time).
+    #[serde(default)]
+    pub upload_window: Option<String>,
+
+    /// Daily local-time windows, `"HH:MM-HH:MM"` like `upload_window`, in
+    /// which indexing pauses entirely: `find-scan` starts no extractions and
+    /// holds its batches, and `find-watch` collects changes without
+    /// indexing them until the window closes.  Default: none.
+    #[serde(default)]
+    pub quiet_hours: Vec<String>,
+
+    /// File whose presence pauses indexing on demand; written by
+    /// `find-admin pause` and removed by `find-admin resume`.  Default:
+    /// `pause.json` in the state directory.  Set it when `find-watch` runs
+    /// as a different user than `find-admin`.
+    #[serde(default)]
+    pub pause_file: Option<String>,
+
+    /// Also index the history of git repositories found in each source: the
+    /// commit log and the files at tagged releases.  Default: unset (off).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_history: Option<GitHistoryConfig>,
+
+    /// How `find-scan` decides that an indexed file changed.  Default:
+    /// `"mtime"`.
+    #[serde(default)]
+    pub change_detection: ChangeDetection,
+
+    /// Before extracting new or changed files, hash them and ask the server
+    /// which contents it already stores (from this path's previous version,
+    /// a renamed source, or a copy elsewhere).  Those files are recorded
+    /// from the stored content instead of being extracted and uploaded
+    /// again.  Default: true.
+    #[serde(default = "default_true")]
+    pub hash_negotiation: bool,
+
+    /// Spool bulk batches to a local directory while the server is
+    /// unreachable and resend them, oldest first, once it is back.  When
+    /// false, an unreachable server fails the scan.  Default: true.
+    #[serde(default = "default_true")]
+    pub spool: bool,
+
+    /// Maximum size of the spool directory in MB.  Once full, further
+    /// batches fail as if no spool were configured.  Default: 1024.
+    #[serde(default = "default_spool_max_mb")]
+    pub spool_max_mb: u64,
+
+    /// Maximum size in MB of `find-scan`'s local extraction cache, which
+    /// keeps the extracted lines of recently indexed files by content hash
+    /// so duplicates and renamed files are not parsed again.  Least recently
+    /// used entries are evicted beyond it.  0 disables the cache.
+    /// Default: 256.
+    #[serde(default = "default_extract_cache_mb")]
+    pub extract_cache_mb: u64,
+
     /// Extension → extractor override map. Key = lowercase extension (without dot).
     /// Set a value to `"builtin"` to use built-in routing, or provide an external tool config.
     #[serde(default)]
@@ -258,18 +626,34 @@ impl Default for ScanConfig {
             exclude_extra: vec![],
             max_content_size_mb: default_max_content_size_mb(),
             follow_symlinks: false,
+            symlink_targets: false,
             cross_filesystems: false,
             include_hidden: false,
+            ignore_files: false,
             archives: ArchiveConfig::default(),
             noindex_file: default_noindex_file(),
             index_file: default_index_file(),
             dir_include: None,
             extractor_dir: None,
             server_fallback: false,
+            upload_originals: false,
             subprocess_timeout_secs: default_subprocess_timeout_secs(),
             batch_size: default_batch_size(),
             batch_bytes: default_batch_bytes(),
             batch_interval_secs: default_batch_interval_secs(),
+            batch_target_kb: default_batch_target_kb(),
+            workers: default_workers(),
+            worker_limits: default_worker_limits(),
+            upload_rate_limit_kbps: 0,
+            upload_window: None,
+            quiet_hours: Vec::new(),
+            pause_file: None,
+            git_history: None,
+            change_detection: ChangeDetection::default(),
+            hash_negotiation: true,
+            spool: true,
+            spool_max_mb: default_spool_max_mb(),
+            extract_cache_mb: default_extract_cache_mb(),
             extractors: std::collections::HashMap::new(),
             ffprobe_path: None,
         }
@@ -343,6 +727,72 @@ pub struct ScanOverride {
     pub archives: Option<ArchiveOverride>,
 }
 
+/// Scan settings a server hands out to its clients: `[client_config]` in
+/// `server.toml`, served at `GET /api/v1/client-config` and applied by
+/// clients with `[server] remote_config = true` on top of their own file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteClientConfig {
+    /// Applied to every source.
+    #[serde(default)]
+    pub scan: RemoteScanSettings,
+    /// Applied to the source of that name, after `scan`.
+    #[serde(default)]
+    pub sources: std::collections::HashMap<String, RemoteScanSettings>,
+}
+
+/// One set of [`RemoteClientConfig`] settings.  As in a `.index` file,
+/// `exclude` adds to the client's patterns and the rest replace its values.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteScanSettings {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_content_size_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_hidden: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_symlinks: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archives: Option<ArchiveOverride>,
+    /// Extension → `"builtin"` or `"server_only"`.  External extractor
+    /// commands can only be set in the client's own file: the server does
+    /// not get to choose what its clients run.
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub extractors: std::collections::HashMap<String, String>,
+}
+
+impl RemoteScanSettings {
+    fn apply(&self, scan: &ScanConfig) -> ScanConfig {
+        let mut scan = scan.apply_override(&ScanOverride {
+            exclude: Some(self.exclude.clone()),
+            max_content_size_mb: self.max_content_size_mb,
+            include_hidden: self.include_hidden,
+            follow_symlinks: self.follow_symlinks,
+            archives: self.archives.clone(),
+            ..ScanOverride::default()
+        });
+        for (ext, route) in &self.extractors {
+            if matches!(route.as_str(), "builtin" | "server_only") {
+                scan.extractors.insert(ext.to_lowercase(), ExtractorEntry::Builtin(route.clone()));
+            } else {
+                warn!("server config: ignoring extractor {route:?} for .{ext}; only \"builtin\" and \"server_only\" can be set remotely");
+            }
+        }
+        scan
+    }
+}
+
+impl ClientConfig {
+    /// Apply settings fetched from the server: `remote.scan` to `[scan]`,
+    /// and each source's own settings to that source.
+    pub fn apply_remote(&mut self, remote: &RemoteClientConfig) {
+        self.scan = remote.scan.apply(&self.scan);
+        for source in &mut self.sources {
+            source.remote = remote.sources.get(&source.name).cloned();
+        }
+    }
+}
+
 /// Archive-specific fields for a `ScanOverride`.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ArchiveOverride {
@@ -390,6 +840,17 @@ pub struct ArchiveConfig {
     /// Default: 256 MB.
     #[serde(default = "default_max_7z_solid_block_mb")]
     pub max_7z_solid_block_mb: usize,
+    /// Decode oversized 7z solid blocks anyway when the decoder's dictionary
+    /// window fits within `max_7z_solid_block_mb`.
+    ///
+    /// LZMA only looks back as far as its dictionary, which is often far
+    /// smaller than the block (e.g. a 16 MB dictionary for a 2 GB block), so
+    /// such a block can be streamed through a single-threaded decoder in
+    /// constant memory and its members' content indexed.  This is slower
+    /// than the normal path, so blocks over the limit are still indexed by
+    /// filename only unless this is set.  Default: false.
+    #[serde(default = "default_stream_oversized_7z_blocks")]
+    pub stream_oversized_7z_blocks: bool,
 }
 
 impl Default for ArchiveConfig {
@@ -399,6 +860,7 @@ impl Default for ArchiveConfig {
             max_depth: default_max_archive_depth(),
             max_temp_file_mb: default_max_archive_temp_file_mb(),
             max_7z_solid_block_mb: default_max_7z_solid_block_mb(),
+            stream_oversized_7z_blocks: default_stream_oversized_7z_blocks(),
         }
     }
 }
@@ -406,6 +868,7 @@ impl Default for ArchiveConfig {
 fn default_max_archive_depth() -> usize       { client_defaults().scan.archives.max_depth }
 fn default_max_archive_temp_file_mb() -> usize { client_defaults().scan.archives.max_temp_file_mb }
 fn default_max_7z_solid_block_mb() -> usize   { client_defaults().scan.archives.max_7z_solid_block_mb }
+fn default_stream_oversized_7z_blocks() -> bool { client_defaults().scan.archives.stream_oversized_7z_blocks }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WatchConfig {
@@ -427,6 +890,32 @@ pub struct WatchConfig {
     /// Set to 0.0 to disable scheduled scanning entirely.
     #[serde(default = "default_scan_interval_hours")]
     pub scan_interval_hours: f64,
+
+    /// How `find-watch` learns about changes: OS change notification
+    /// (`"events"`), periodic polling (`"poll"`), or polling only for sources
+    /// on network filesystems such as SMB/CIFS and NFS mounts, whose remote
+    /// changes produce no events (`"auto"`, default).  Overridable per source
+    /// with `watch_mode`.
+    #[serde(default)]
+    pub mode: WatchMode,
+
+    /// Seconds between passes over a polled source.  Default: 60.
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+
+    /// Heartbeat file `find-watch` keeps up to date for `find-admin
+    /// watch-status` and the tray.  None = `find-watch-status.json` in the
+    /// state directory; set it explicitly when the watcher runs as a
+    /// different user (e.g. a service) from the tools reading it.
+    #[serde(default)]
+    pub status_file: Option<String>,
+
+    /// Control socket `find-watch` listens on for `find-admin` and the tray
+    /// (see `find_common::control`).  None = `find-watch.sock` in the state
+    /// directory, or the named pipe `\\.\pipe\find-watch` on Windows;
+    /// `""` turns it off.
+    #[serde(default)]
+    pub control_socket: Option<String>,
 }
 
 impl Default for WatchConfig {
@@ -435,10 +924,31 @@ impl Default for WatchConfig {
             batch_window_secs: default_batch_window_secs(),
             extractor_dir: None,
             scan_interval_hours: default_scan_interval_hours(),
+            mode: WatchMode::default(),
+            poll_interval_secs: default_poll_interval_secs(),
+            status_file: None,
+            control_socket: None,
         }
     }
 }
 
+/// Change-detection backend for `find-watch` (`[watch] mode`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchMode {
+    /// Poll sources on network filesystems; use OS events for the rest.
+    #[default]
+    Auto,
+    /// OS change notification (inotify, FSEvents, ReadDirectoryChangesW).
+    Events,
+    /// Walk the source every `poll_interval_secs` and diff it.
+    Poll,
+    /// Read the filesystem's change journal (USN journal on NTFS, FSEvents
+    /// history on macOS), resuming where the last run stopped.  Falls back
+    /// to `events` on other platforms.
+    Native,
+}
+
 /// Windows system tray configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrayConfig {
@@ -446,17 +956,60 @@ pub struct TrayConfig {
     /// Default: 1000.
     #[serde(default = "default_tray_poll_interval_ms")]
     pub poll_interval_ms: u64,
+
+    /// Show notifications when a scan finishes, indexing errors spike or the
+    /// server's inbox backs up.  `false` starts the tray silenced; the menu
+    /// toggles it either way.  Default: true.
+    #[serde(default = "default_true")]
+    pub notifications: bool,
+
+    /// Seconds between the `/stats` checks behind notifications while the
+    /// popup is closed.  0 disables background checks.  Default: 60.
+    #[serde(default = "default_tray_notify_interval_secs")]
+    pub notify_interval_secs: u64,
+
+    /// New indexing errors between two checks that count as a spike.
+    /// 0 disables the notification.  Default: 10.
+    #[serde(default = "default_tray_error_spike")]
+    pub error_spike_threshold: usize,
+
+    /// Pending inbox requests on the server above which the tray warns.
+    /// 0 disables the notification.  Default: 1000.
+    #[serde(default = "default_tray_inbox_backlog")]
+    pub inbox_backlog_threshold: usize,
+
+    /// Global hotkey that opens the quick search window, e.g.
+    /// `"Ctrl+Alt+Space"` or `"Win+Shift+F"`.  Empty disables it.
+    /// Default: `"Ctrl+Alt+Space"`.
+    #[serde(default = "default_tray_hotkey")]
+    pub hotkey: String,
+
+    /// Indexing errors listed in the tray's "Recent Errors" submenu.
+    /// 0 hides it.  Default: 10.
+    #[serde(default = "default_tray_recent_errors")]
+    pub recent_errors: usize,
 }
 
 impl Default for TrayConfig {
     fn default() -> Self {
         Self {
             poll_interval_ms: default_tray_poll_interval_ms(),
+            notifications: true,
+            notify_interval_secs: default_tray_notify_interval_secs(),
+            error_spike_threshold: default_tray_error_spike(),
+            inbox_backlog_threshold: default_tray_inbox_backlog(),
+            hotkey: default_tray_hotkey(),
+            recent_errors: default_tray_recent_errors(),
         }
     }
 }
 
 fn default_tray_poll_interval_ms() -> u64 { 1000 }
+fn default_tray_notify_interval_secs() -> u64 { 60 }
+fn default_tray_error_spike() -> usize { 10 }
+fn default_tray_inbox_backlog() -> usize { 1000 }
+fn default_tray_hotkey() -> String { "Ctrl+Alt+Space".to_string() }
+fn default_tray_recent_errors() -> usize { 10 }
 
 /// CLI tool configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -464,11 +1017,55 @@ pub struct CliConfig {
     /// Poll interval for `--follow` / `--watch` modes (seconds). Default: 2.0.
     #[serde(default = "default_cli_poll_interval_secs")]
     pub poll_interval_secs: f64,
+    /// Command `find-anything --open` runs, with `{path}` and `{line}`
+    /// replaced, e.g. `"code -g {path}:{line}"`.  `None` = `$VISUAL` or
+    /// `$EDITOR` with `+{line} {path}`.
+    #[serde(default)]
+    pub editor: Option<String>,
+    /// Where each source's files are on this machine, by source name, for
+    /// sources indexed elsewhere: a local directory, or prefix rules mapping
+    /// parts of the source to local/UNC directories and URL templates (see
+    /// [`crate::path_map`]).  Sources in `[[sources]]` default to their
+    /// `path`.
+    #[serde(default)]
+    pub path_map: std::collections::HashMap<String, PathMapEntry>,
+}
+
+/// Value in the `[cli.path_map]` table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum PathMapEntry {
+    /// Local directory holding the whole source.
+    Root(String),
+    /// Rules for parts of the source; the longest matching prefix wins.
+    Rules(Vec<PathMapRule>),
+}
+
+/// One `[[cli.path_map.<source>]]` rule.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PathMapRule {
+    /// Leading directories of the indexed path (as the scanner saw it,
+    /// relative to the source root) this rule covers.  Empty = the whole
+    /// source.
+    #[serde(default)]
+    pub prefix: String,
+    /// Local or UNC directory those files are in on this machine.
+    #[serde(default)]
+    pub local: Option<String>,
+    /// URL template for opening those files in a browser, with `{path}`
+    /// replaced by the rest of the path after `prefix` (URL-encoded),
+    /// `{source}` by the source name and `{line}` by the line number.
+    #[serde(default)]
+    pub url: Option<String>,
 }
 
 impl Default for CliConfig {
     fn default() -> Self {
-        Self { poll_interval_secs: default_cli_poll_interval_secs() }
+        Self {
+            poll_interval_secs: default_cli_poll_interval_secs(),
+            editor: None,
+            path_map: std::collections::HashMap::new(),
+        }
     }
 }
 
@@ -476,6 +1073,7 @@ fn default_cli_poll_interval_secs() -> f64 { 2.0 }
 
 fn default_batch_window_secs() -> f64       { client_defaults().watch.batch_window_secs }
 fn default_scan_interval_hours() -> f64     { client_defaults().watch.scan_interval_hours }
+fn default_poll_interval_secs() -> u64      { client_defaults().watch.poll_interval_secs }
 fn default_excludes() -> Vec<String>         { client_defaults().scan.exclude.clone() }
 fn default_max_content_size_mb() -> u64      { client_defaults().scan.max_content_size_mb }
 fn default_noindex_file() -> String          { client_defaults().scan.noindex_file.clone() }
@@ -484,8 +1082,25 @@ fn default_subprocess_timeout_secs() -> u64  { client_defaults().scan.subprocess
 fn default_batch_size() -> usize             { client_defaults().scan.batch_size }
 fn default_batch_bytes() -> usize            { client_defaults().scan.batch_bytes }
 fn default_batch_interval_secs() -> u64      { client_defaults().scan.batch_interval_secs }
+fn default_batch_target_kb() -> usize        { client_defaults().scan.batch_target_kb }
+fn default_workers() -> usize                { client_defaults().scan.workers }
+fn default_worker_limits() -> std::collections::HashMap<String, usize> { client_defaults().scan.worker_limits.clone() }
+fn default_spool_max_mb() -> u64        { client_defaults().scan.spool_max_mb }
+fn default_extract_cache_mb() -> u64    { client_defaults().scan.extract_cache_mb }
 fn default_true() -> bool               { true }
 
+/// The exclude patterns of the built-in preset `name`, if there is one.
+pub fn exclude_preset(name: &str) -> Option<&'static [String]> {
+    client_defaults().presets.get(name).map(Vec::as_slice)
+}
+
+/// Names of the built-in exclude presets, sorted.
+pub fn exclude_preset_names() -> impl Iterator<Item = &'static str> {
+    client_defaults().presets.keys().map(String::as_str)
+}
+fn default_git_tags() -> Vec<String>    { vec!["*".to_string()] }
+fn default_git_max_tags() -> usize      { 20 }
+
 pub use find_extract_types::ExtractorConfig;
 
 /// Resolve the effective ffprobe binary path from the configured value.
@@ -546,6 +1161,7 @@ pub fn extractor_config_from_scan(scan: &ScanConfig) -> ExtractorConfig {
         max_temp_file_mb: scan.archives.max_temp_file_mb,
         include_hidden: scan.include_hidden,
         max_7z_solid_block_mb: scan.archives.max_7z_solid_block_mb,
+        stream_oversized_7z_blocks: scan.archives.stream_oversized_7z_blocks,
         exclude_patterns: scan.exclude.clone(),
         external_dispatch,
         ffprobe_path,
@@ -563,6 +1179,25 @@ pub fn extractor_config_from_extraction(extraction: &ExtractionSettings) -> Extr
     }
 }
 
+/// How `find-scan` detects that an indexed file changed (`[scan]
+/// change_detection`, overridable per source).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeDetection {
+    /// Re-index when the mtime is newer or the size differs.  Cheapest, but
+    /// misses files restored with their old timestamps and re-indexes files
+    /// that were only touched.
+    #[default]
+    Mtime,
+    /// Hash every indexed file on each scan and re-index when the hash
+    /// differs.  Catches every change at the cost of reading all files.
+    Hash,
+    /// Hash a file when its mtime changed (in either direction) but its size
+    /// did not, and re-index only if the hash differs.  Files that were
+    /// merely touched are not extracted again.
+    Hybrid,
+}
+
 /// Which storage backend type to use for a content store instance.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -586,8 +1221,43 @@ pub struct BackendInstanceConfig {
     /// Defaults to 100.
     pub max_read_connections: Option<u32>,
     /// Gzip-compress chunk data before storing. Only applies to SQLite backends.
-    /// Defaults to false.
+    /// Defaults to false. Superseded by `compression` when that is set.
     pub compress: Option<bool>,
+    /// Chunk compression format for new writes: `"none"`, `"gzip"`, or `"zstd"`.
+    /// Existing chunks stay readable whatever this is set to. Only applies to
+    /// SQLite backends.
+    #[serde(default)]
+    pub compression: Option<ChunkCompression>,
+    /// Size in MB of the in-memory cache of decoded chunks shared by all
+    /// readers; 0 disables it. Only applies to SQLite backends. Defaults to 64.
+    pub chunk_cache_mb: Option<u32>,
+    /// Blobs that fit in one chunk are packed together into shared rows of
+    /// up to this many KB, so small files do not each cost a row of their
+    /// own; 0 disables packing. Only applies to SQLite backends. Defaults to 32.
+    pub pack_size_kb: Option<u32>,
+}
+
+impl BackendInstanceConfig {
+    /// Effective compression for new chunks, honouring the legacy `compress` flag.
+    pub fn effective_compression(&self) -> ChunkCompression {
+        match (self.compression, self.compress) {
+            (Some(c), _)          => c,
+            (None, Some(true))    => ChunkCompression::Gzip,
+            (None, _)             => ChunkCompression::None,
+        }
+    }
+}
+
+/// Compression applied to content store chunks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkCompression {
+    #[default]
+    None,
+    Gzip,
+    /// zstd with a dictionary trained on the store's first chunks.  The
+    /// dictionary is shared by all sources, as the store's blobs are.
+    Zstd,
 }
 
 /// Top-level `[storage]` config section.
@@ -608,6 +1278,9 @@ impl StorageConfig {
             chunk_size_kb: None,
             max_read_connections: None,
             compress: None,
+            compression: None,
+            chunk_cache_mb: None,
+            pack_size_kb: None,
         }]
     }
 }
@@ -630,6 +1303,8 @@ pub struct ServerAppConfig {
     #[serde(default)]
     pub normalization: NormalizationSettings,
     #[serde(default)]
+    pub tokens: TokenSettings,
+    #[serde(default)]
     pub compaction: CompactionConfig,
     #[serde(default)]
     pub links: LinksConfig,
@@ -639,6 +1314,21 @@ pub struct ServerAppConfig {
     pub storage: StorageConfig,
     #[serde(default)]
     pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub replica: ReplicaConfig,
+    #[serde(default)]
+    pub federation: FederationConfig,
+    #[serde(default)]
+    pub originals: OriginalsConfig,
+    #[serde(default)]
+    pub worker: WorkerSettings,
+    /// Scan settings for clients with `[server] remote_config = true`.
+    #[serde(default)]
+    pub client_config: RemoteClientConfig,
     /// Per-source server configuration (e.g. filesystem root for raw file serving).
     #[serde(default)]
     pub sources: std::collections::HashMap<String, ServerSourceConfig>,
@@ -760,12 +1450,208 @@ impl Default for CompactionConfig {
 fn default_compaction_threshold_pct() -> f64 { 10.0 }
 fn default_compaction_start_time() -> String { "02:00".to_string() }
 
+/// Retention policy for the per-source `scan_history` table.
+///
+/// Applied by the inbox worker each time a scan completes.  Recent points are
+/// kept at full resolution; older points are thinned to one per day, and points
+/// older than `retention_days` are deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Delete scan history points older than this many days. 0 = keep forever.
+    /// Default: 365.
+    #[serde(default = "default_history_retention_days")]
+    pub retention_days: u64,
+    /// Points newer than this many days are kept at full resolution; older
+    /// points are reduced to the last scan of each day. Default: 30.
+    #[serde(default = "default_history_full_resolution_days")]
+    pub full_resolution_days: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_history_retention_days(),
+            full_resolution_days: default_history_full_resolution_days(),
+        }
+    }
+}
+
+fn default_history_retention_days() -> u64 { 365 }
+fn default_history_full_resolution_days() -> u64 { 30 }
+
+/// Read-only replica mode.
+///
+/// When `primary_url` is set, this server periodically pulls source databases
+/// and content blobs from the primary and serves search traffic read-only:
+/// bulk uploads and admin write endpoints are rejected and the inbox worker
+/// is not started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaConfig {
+    /// Base URL of the primary find-server (e.g. `"http://indexer:8080"`).
+    #[serde(default)]
+    pub primary_url: Option<String>,
+    /// Bearer token for the primary. Defaults to this server's own `server.token`.
+    #[serde(default)]
+    pub primary_token: Option<String>,
+    /// Seconds between sync passes. Default: 300.
+    #[serde(default = "default_replica_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+impl ReplicaConfig {
+    pub fn is_replica(&self) -> bool {
+        self.primary_url.is_some()
+    }
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            primary_url: None,
+            primary_token: None,
+            sync_interval_secs: default_replica_sync_interval_secs(),
+        }
+    }
+}
+
+fn default_replica_sync_interval_secs() -> u64 { 300 }
+
+/// One peer server queried by search federation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationPeer {
+    /// Label attached to every hit from this peer (`SearchResult::origin`).
+    pub name: String,
+    /// Base URL of the peer find-server (e.g. `"http://nas.lan:8080"`).
+    pub url: String,
+    /// Bearer token for the peer.
+    pub token: String,
+}
+
+/// Search federation: fan each search out to peer servers and merge the hits.
+///
+/// Example:
+/// ```toml
+/// [[federation.peers]]
+/// name  = "nas"
+/// url   = "http://nas.lan:8080"
+/// token = "nas-token"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationConfig {
+    #[serde(default)]
+    pub peers: Vec<FederationPeer>,
+    /// Per-peer HTTP timeout in seconds.  A peer that does not answer in time
+    /// is left out of the results. Default: 5.
+    #[serde(default = "default_federation_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            peers: Vec::new(),
+            timeout_secs: default_federation_timeout_secs(),
+        }
+    }
+}
+
+fn default_federation_timeout_secs() -> u64 { 5 }
+
+/// Original-content store: keep a copy of each file's bytes uploaded by the
+/// scanner so `/api/v1/raw` can serve files from sources that have no
+/// mounted `path` on the server.
+///
+/// Example:
+/// ```toml
+/// [originals]
+/// enabled = true
+/// max_file_size_mb = 50
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OriginalsConfig {
+    /// Accept original-file uploads from clients. Default: false.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Largest original file the server will store, in MB. Larger uploads
+    /// are rejected with 413 and the file is served from the mount only.
+    /// Default: 100.
+    #[serde(default = "default_originals_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+}
+
+impl Default for OriginalsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_file_size_mb: default_originals_max_file_size_mb(),
+        }
+    }
+}
+
+fn default_originals_max_file_size_mb() -> u64 { 100 }
+
+/// Inbox worker settings.
+///
+/// Example:
+/// ```toml
+/// [worker]
+/// concurrency  = 4
+/// max_attempts = 3
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerSettings {
+    /// Maximum number of inbox requests indexed at the same time.  Requests
+    /// for the same source are never processed concurrently (each source has
+    /// its own SQLite database and writes to it are serialized), so extra
+    /// slots only help when several sources are uploading at once.
+    /// Default: 1.
+    #[serde(default = "default_worker_concurrency")]
+    pub concurrency: usize,
+    /// Number of times a request is attempted before it is quarantined to
+    /// `inbox/failed/` with an `.error.json` describing the failure.  Counts
+    /// attempts that crashed or restarted the server as well as retryable
+    /// errors such as a locked database; other errors quarantine the request
+    /// immediately.  0 = retry forever. Default: 3.
+    #[serde(default = "default_worker_max_attempts")]
+    pub max_attempts: u32,
+    /// Maximum line content, in MB, of each part a streamed request is split
+    /// into before indexing.  Bounds how much of one upload the worker holds
+    /// in memory at a time.  Default: 16.
+    #[serde(default = "default_worker_part_max_mb")]
+    pub part_max_mb: u64,
+    /// `/api/v1/bulk` requests larger than this many MB (compressed) are
+    /// decoded incrementally and split into parts like `/api/v2/bulk`
+    /// streams, rather than decoded whole.  0 = never split.  Default: 0.
+    #[serde(default)]
+    pub split_requests_over_mb: u64,
+}
+
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: default_worker_concurrency(),
+            max_attempts: default_worker_max_attempts(),
+            part_max_mb: default_worker_part_max_mb(),
+            split_requests_over_mb: 0,
+        }
+    }
+}
+
+fn default_worker_concurrency() -> usize { 1 }
+fn default_worker_max_attempts() -> u32 { 3 }
+fn default_worker_part_max_mb() -> u64 { 16 }
+
 /// Server-side configuration for a named source.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ServerSourceConfig {
     /// Filesystem root for this source. When set, the server can serve
     /// original files via GET /api/v1/raw.
     pub path: Option<String>,
+    /// Most space the source's index (its database plus its content in the
+    /// content store) may take, in MB.  Past it, files the source doesn't
+    /// already hold are refused.  `None` = no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_index_mb: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -844,6 +1730,17 @@ pub struct ServerAppSettings {
     /// Default: 5.
     #[serde(default = "default_inbox_timeout_circuit_breaker")]
     pub inbox_timeout_circuit_breaker: u32,
+    /// Days a deleted file is kept in its source's trash, hidden from search
+    /// but restorable with `POST /api/v1/admin/trash/restore`, before it is
+    /// removed and its content reclaimed by compaction.  0 = deletions are
+    /// final at once.  Default: 0.
+    #[serde(default)]
+    pub trash_days: u32,
+    /// Built-in preset that supplies defaults for settings this file leaves
+    /// unset, e.g. `"low-memory"` for small NAS and ARM boxes.  Explicit
+    /// settings always win.  See [`server_profile_names`].
+    #[serde(default)]
+    pub profile: Option<String>,
 }
 
 fn default_max_markdown_render_kb() -> usize { 512 }
@@ -932,6 +1829,86 @@ impl Default for AlertsConfig {
 
 fn default_smtp_port() -> u16 { 587 }
 
+// ── Webhooks ──────────────────────────────────────────────────────────────────
+
+/// Event types that can be delivered to a webhook endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A client finished a scan of a source (bulk request with `scan_timestamp`).
+    ScanCompleted,
+    /// A single bulk request reported at least `error_spike_threshold` indexing failures.
+    ErrorSpike,
+    /// The number of pending inbox requests reached `backlog_threshold`.
+    InboxBacklog,
+}
+
+/// One webhook receiver.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    /// URL that receives a JSON `POST` for each subscribed event.
+    pub url: String,
+    /// Events delivered to this endpoint. Empty = all events.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Shared secret used to sign the request body with HMAC-SHA256.
+    /// The signature is sent as `X-Find-Signature: sha256=<hex>`.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl WebhookEndpoint {
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// Outgoing webhook notifications fired by the inbox worker.
+///
+/// Example:
+/// ```toml
+/// [webhooks]
+/// error_spike_threshold = 50
+/// backlog_threshold     = 500
+///
+/// [[webhooks.endpoints]]
+/// url    = "https://hooks.example.com/find-anything"
+/// events = ["scan_completed", "error_spike", "inbox_backlog"]
+/// secret = "s3cr3t"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhooksConfig {
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Fire `error_spike` when one bulk request carries at least this many
+    /// indexing failures. 0 = disabled. Default: 50.
+    #[serde(default = "default_webhook_error_spike_threshold")]
+    pub error_spike_threshold: usize,
+    /// Fire `inbox_backlog` when the inbox holds at least this many pending
+    /// requests.  Fires once per crossing; re-arms when the backlog drops below
+    /// the threshold. 0 = disabled. Default: 500.
+    #[serde(default = "default_webhook_backlog_threshold")]
+    pub backlog_threshold: usize,
+    /// Per-delivery HTTP timeout in seconds. Default: 10.
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            error_spike_threshold: default_webhook_error_spike_threshold(),
+            backlog_threshold: default_webhook_backlog_threshold(),
+            timeout_secs: default_webhook_timeout_secs(),
+        }
+    }
+}
+
+fn default_webhook_error_spike_threshold() -> usize { 50 }
+fn default_webhook_backlog_threshold() -> usize { 500 }
+fn default_webhook_timeout_secs() -> u64 { 10 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchSettings {
     #[serde(default = "default_search_limit")]
@@ -944,6 +1921,28 @@ pub struct SearchSettings {
     /// Total lines displayed = 2 × context_window + 1. Default: 1 (3 lines total).
     #[serde(default = "default_context_window")]
     pub context_window: usize,
+    /// Time budget for one search request in milliseconds, shared by all
+    /// sources.  Sources still running when it expires are abandoned and the
+    /// response is marked `timed_out`.  0 disables the limit.  Default: 10000.
+    #[serde(default = "default_search_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Source searches that may run at once across all requests, each on a
+    /// blocking thread; the rest wait their turn.  Default: 16.
+    #[serde(default = "default_max_concurrent_sources")]
+    pub max_concurrent_sources: usize,
+    /// Source searches one request may run at once, so that a search over
+    /// many sources leaves room for others.  Default: 4.
+    #[serde(default = "default_max_sources_per_request")]
+    pub max_sources_per_request: usize,
+    /// Sources whose last `find-scan` is older than this many days are
+    /// listed in the `X-Find-Stale-Sources` header of search responses
+    /// that include their results, so clients can warn.  0 disables the
+    /// header.  Default: 7.
+    #[serde(default = "default_stale_scan_days")]
+    pub stale_scan_days: u64,
+    /// How fuzzy-mode hits are ranked (`[search.scoring]`).
+    #[serde(default)]
+    pub scoring: ScoringSettings,
 }
 
 impl Default for SearchSettings {
@@ -953,6 +1952,11 @@ impl Default for SearchSettings {
             max_limit: default_max_limit(),
             fts_candidate_limit: default_fts_candidate_limit(),
             context_window: default_context_window(),
+            timeout_ms: default_search_timeout_ms(),
+            max_concurrent_sources: default_max_concurrent_sources(),
+            max_sources_per_request: default_max_sources_per_request(),
+            stale_scan_days: default_stale_scan_days(),
+            scoring: ScoringSettings::default(),
         }
     }
 }
@@ -961,6 +1965,104 @@ fn default_search_limit() -> usize    { server_defaults().search.default_limit }
 fn default_max_limit() -> usize       { server_defaults().search.max_limit }
 fn default_fts_candidate_limit() -> usize { server_defaults().search.fts_candidate_limit }
 fn default_context_window() -> usize  { server_defaults().search.context_window }
+fn default_search_timeout_ms() -> u64 { server_defaults().search.timeout_ms }
+fn default_max_concurrent_sources() -> usize { server_defaults().search.max_concurrent_sources }
+fn default_max_sources_per_request() -> usize { server_defaults().search.max_sources_per_request }
+fn default_stale_scan_days() -> u64 { server_defaults().search.stale_scan_days }
+
+/// Matcher used to score fuzzy-mode hits (`[search.scoring] algorithm`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringAlgorithm {
+    /// nucleo's fzf-style matcher.
+    #[default]
+    Nucleo,
+    /// Smith-Waterman local alignment: every query character must match in
+    /// order; gaps between matches cost points and matches at word starts
+    /// earn a bonus.  Favours compact matches over scattered ones.
+    SmithWaterman,
+}
+
+/// Fuzzy ranking parameters (`[search.scoring]`).  The defaults rank hits by
+/// the matcher's score alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringSettings {
+    #[serde(default)]
+    pub algorithm: ScoringAlgorithm,
+    /// Score multiplier for hits on a file's name (the path line).
+    /// Default: 1.0.
+    #[serde(default = "default_scoring_boost")]
+    pub filename_boost: f64,
+    /// Score multiplier for hits on a file's metadata line (EXIF, audio
+    /// tags, etc.).  Default: 1.0.
+    #[serde(default = "default_scoring_boost")]
+    pub metadata_boost: f64,
+    /// Points added for each matched character that directly follows the
+    /// previous matched character.  Default: 0.
+    #[serde(default)]
+    pub consecutive_bonus: u32,
+    /// Points subtracted per character of the scored text, so shorter lines
+    /// rank above long ones with the same match.  Default: 0.0.
+    #[serde(default)]
+    pub length_penalty: f64,
+}
+
+impl Default for ScoringSettings {
+    fn default() -> Self {
+        Self {
+            algorithm: ScoringAlgorithm::default(),
+            filename_boost: default_scoring_boost(),
+            metadata_boost: default_scoring_boost(),
+            consecutive_bonus: 0,
+            length_penalty: 0.0,
+        }
+    }
+}
+
+fn default_scoring_boost() -> f64 { 1.0 }
+
+/// Unicode normalization form applied to indexed text and queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    #[default]
+    None,
+    /// Canonical composition: `e` + combining acute becomes `é`.
+    Nfc,
+    /// Compatibility composition: also folds ligatures, full-width and
+    /// superscript forms (`ﬁ` → `fi`, `Ａ` → `A`, `²` → `2`).
+    Nfkc,
+}
+
+/// Segmentation of Chinese, Japanese and Korean text for the trigram index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CjkSegmentation {
+    #[default]
+    None,
+    /// Index every pair of adjacent CJK characters as its own token, so
+    /// two-character words (most CJK words) can be found.
+    Bigram,
+}
+
+/// Token normalization (`[tokens]`), applied to text as it enters the
+/// full-text index and to queries, so that e.g. `café` and `cafe` match.
+///
+/// A source database keeps the settings it was created with; changing them
+/// only affects sources whose database is rebuilt.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenSettings {
+    #[serde(default)]
+    pub unicode: UnicodeForm,
+    /// Remove accents and other combining marks (`naïve` → `naive`).
+    #[serde(default)]
+    pub strip_diacritics: bool,
+    /// Words dropped from indexed text and queries, matched case-insensitively.
+    #[serde(default)]
+    pub stopwords: Vec<String>,
+    #[serde(default)]
+    pub cjk: CjkSegmentation,
+}
 
 /// Extraction settings for the server (used for server-side file indexing).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1185,6 +2287,33 @@ pub fn default_config_path() -> String {
     format!("{home}/.config/find-anything/client.toml")
 }
 
+/// Resolves the directory for client-side state (scan checkpoints) using the
+/// following priority:
+///
+/// 1. `FIND_ANYTHING_STATE_DIR` environment variable (if set)
+/// 2. `$XDG_STATE_HOME/find-anything` (if `XDG_STATE_HOME` is set)
+/// 3. `/var/lib/find-anything` (when running as root, e.g. system service) [Unix only]
+/// 4. `%LOCALAPPDATA%\FindAnything` [Windows]
+/// 5. `~/.local/state/find-anything` [Unix default]
+pub fn default_state_dir() -> PathBuf {
+    if let Ok(p) = std::env::var("FIND_ANYTHING_STATE_DIR") {
+        return PathBuf::from(p);
+    }
+    if let Ok(xdg) = std::env::var("XDG_STATE_HOME") {
+        return PathBuf::from(format!("{xdg}/find-anything"));
+    }
+    #[cfg(unix)]
+    if unsafe { libc::getuid() } == 0 {
+        return PathBuf::from("/var/lib/find-anything");
+    }
+    #[cfg(windows)]
+    if let Ok(local) = std::env::var("LOCALAPPDATA") {
+        return PathBuf::from(format!("{local}\\FindAnything"));
+    }
+    let home = std::env::var("HOME").unwrap_or_default();
+    PathBuf::from(format!("{home}/.local/state/find-anything"))
+}
+
 // ── Config loaders with unknown-field warnings ─────────────────────────────
 
 /// Parse a client `client.toml` string.
@@ -1213,6 +2342,12 @@ pub fn parse_client_config(toml_str: &str) -> Result<(ClientConfig, Vec<String>)
     for key in &unknown {
         warnings.push(format!("unknown config key: \"{key}\""));
     }
+    for source in &cfg.sources {
+        for name in source.presets.iter().filter(|n| exclude_preset(n).is_none()) {
+            let known = exclude_preset_names().collect::<Vec<_>>().join(", ");
+            warnings.push(format!("source \"{}\": unknown preset \"{name}\" (known presets: {known})", source.name));
+        }
+    }
     // Merge exclude_extra into exclude so the rest of the codebase only
     // needs to look at one field.
     cfg.scan.exclude.extend(std::mem::take(&mut cfg.scan.exclude_extra));
@@ -1225,7 +2360,8 @@ pub fn parse_client_config(toml_str: &str) -> Result<(ClientConfig, Vec<String>)
 /// warning strings for unknown keys.  The server passes these through `warn!`
 /// since it runs as a daemon; CLI tools should print them to stderr directly.
 pub fn parse_server_config(toml_str: &str) -> Result<(ServerAppConfig, Vec<String>)> {
-    let value: toml::Value = toml::from_str(toml_str).context("invalid TOML")?;
+    let mut value: toml::Value = toml::from_str(toml_str).context("invalid TOML")?;
+    apply_server_profile(&mut value)?;
     let mut unknown = Vec::new();
     let cfg = serde_ignored::deserialize(value, |path| {
         unknown.push(path.to_string());
@@ -1238,6 +2374,244 @@ pub fn parse_server_config(toml_str: &str) -> Result<(ServerAppConfig, Vec<Strin
     Ok((cfg, warnings))
 }
 
+/// Names of the built-in `[server] profile` presets, sorted.
+pub fn server_profile_names() -> impl Iterator<Item = &'static str> {
+    server_defaults().profiles.keys().map(String::as_str)
+}
+
+/// Fill in the settings `value` leaves unset from the preset named by its
+/// `[server] profile`, if any.  A table given for an array of tables (such
+/// as `[storage.backends]`) applies to every element; when the array itself
+/// is unset, to the default backend.
+fn apply_server_profile(value: &mut toml::Value) -> Result<()> {
+    let Some(name) = value.get("server").and_then(|s| s.get("profile")).and_then(|p| p.as_str()) else {
+        return Ok(());
+    };
+    let Some(preset) = server_defaults().profiles.get(name) else {
+        let known = server_profile_names().collect::<Vec<_>>().join(", ");
+        anyhow::bail!("unknown server profile \"{name}\" (known profiles: {known})");
+    };
+    let Some(table) = value.as_table_mut() else { return Ok(()) };
+    if preset.get("storage").and_then(|s| s.get("backends")).is_some() {
+        let storage = table.entry("storage").or_insert_with(|| toml::Table::new().into());
+        if let Some(storage) = storage.as_table_mut() {
+            if !storage.contains_key("backends") {
+                let defaults = toml::Value::try_from(StorageConfig::default_backends())
+                    .context("serializing default storage backends")?;
+                storage.insert("backends".to_string(), defaults);
+            }
+        }
+    }
+    fill_unset(table, preset);
+    Ok(())
+}
+
+fn fill_unset(target: &mut toml::Table, preset: &toml::Table) {
+    for (key, preset_value) in preset {
+        match (target.get_mut(key), preset_value) {
+            (None, _) => {
+                target.insert(key.clone(), preset_value.clone());
+            }
+            (Some(toml::Value::Table(t)), toml::Value::Table(p)) => fill_unset(t, p),
+            (Some(toml::Value::Array(items)), toml::Value::Table(p)) => {
+                for t in items.iter_mut().filter_map(toml::Value::as_table_mut) {
+                    fill_unset(t, p);
+                }
+            }
+            (Some(_), _) => {}
+        }
+    }
+}
+
+// ── Config validation ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+/// One problem found by [`validate_client_config`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigDiagnostic {
+    pub severity: DiagnosticSeverity,
+    /// 1-based line in the config file, when the problem can be placed.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Check a `client.toml` string without loading it: TOML syntax and type
+/// errors, unknown keys, and the settings that would otherwise only fail once
+/// a tool starts (duplicate or invalid source names, source paths that are
+/// not accessible directories, a missing token).  Sorted by line.
+pub fn validate_client_config(toml_str: &str) -> Vec<ConfigDiagnostic> {
+    let lines = key_lines(toml_str);
+    // An unknown or misplaced key is reported on its own line, or failing
+    // that on the line of the nearest enclosing table.
+    let line_of = |path: &str| {
+        let mut path = path;
+        loop {
+            if let Some(&n) = lines.get(path) {
+                return Some(n);
+            }
+            path = path.rsplit_once('.')?.0;
+        }
+    };
+    let diag = |severity, line, message| ConfigDiagnostic { severity, line, message };
+    let mut out = Vec::new();
+
+    let mut unknown = Vec::new();
+    let parsed: std::result::Result<ClientConfig, toml::de::Error> =
+        serde_ignored::deserialize(toml::de::Deserializer::new(toml_str), |path| {
+            unknown.push(path.to_string());
+        });
+    for key in unknown {
+        out.push(diag(DiagnosticSeverity::Warning, line_of(&key), format!("unknown config key \"{key}\"")));
+    }
+    let cfg = match parsed {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            let line = e.span().map(|span| line_at(toml_str, span.start));
+            out.push(diag(DiagnosticSeverity::Error, line, e.message().trim().to_string()));
+            out.sort_by_key(|d| d.line);
+            return out;
+        }
+    };
+
+    if lines.contains_key("scan.max_file_size_mb") {
+        out.push(diag(
+            DiagnosticSeverity::Warning,
+            line_of("scan.max_file_size_mb"),
+            "max_file_size_mb is deprecated; rename to max_content_size_mb".to_string(),
+        ));
+    }
+    if !cfg.server.url.starts_with("http://") && !cfg.server.url.starts_with("https://") {
+        out.push(diag(
+            DiagnosticSeverity::Error,
+            line_of("server.url"),
+            format!("server url \"{}\" must start with http:// or https://", cfg.server.url),
+        ));
+    }
+    if cfg.server.token.trim().is_empty() {
+        out.push(diag(
+            DiagnosticSeverity::Error,
+            line_of("server.token"),
+            "server token is empty; every request will be rejected".to_string(),
+        ));
+    }
+
+    let mut first_seen: std::collections::HashMap<&str, Option<usize>> = std::collections::HashMap::new();
+    for (i, source) in cfg.sources.iter().enumerate() {
+        let name_line = line_of(&format!("sources.{i}.name"));
+        let name = source.name.as_str();
+        if let Some(&first) = first_seen.get(name) {
+            let first = first.map_or(String::new(), |n| format!(" (first defined on line {n})"));
+            out.push(diag(DiagnosticSeverity::Error, name_line, format!("duplicate source name \"{name}\"{first}")));
+        } else {
+            first_seen.insert(name, name_line);
+        }
+        // Mirrors the check the server applies before opening a source db.
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            out.push(diag(
+                DiagnosticSeverity::Error,
+                name_line,
+                format!("source name \"{name}\" may only contain letters, digits, '-' and '_'"),
+            ));
+        }
+        if !source.is_remote() && !Path::new(&source.path).is_dir() {
+            out.push(diag(
+                DiagnosticSeverity::Error,
+                line_of(&format!("sources.{i}.path")),
+                format!("source \"{name}\": path {} is not an accessible directory", source.path),
+            ));
+        } else if let Some(file) = source
+            .require_file
+            .as_deref()
+            .filter(|f| !source.is_remote() && !Path::new(&source.path).join(f).exists())
+        {
+            out.push(diag(
+                DiagnosticSeverity::Warning,
+                line_of(&format!("sources.{i}.require_file")),
+                format!("source \"{name}\": {file} not found in {}; find-scan will skip this source", source.path),
+            ));
+        }
+        for (key, expr) in [("scan_schedule", &source.scan_schedule), ("full_scan_schedule", &source.full_scan_schedule)] {
+            let Some(expr) = expr else { continue };
+            let problem = match expr.parse::<crate::schedule::Schedule>() {
+                Err(e) => e,
+                Ok(schedule) if schedule.next_after(chrono::Local::now().naive_local()).is_none() => {
+                    "it never fires".to_string()
+                }
+                Ok(_) => continue,
+            };
+            out.push(diag(
+                DiagnosticSeverity::Error,
+                line_of(&format!("sources.{i}.{key}")),
+                format!("source \"{name}\": invalid {key} \"{expr}\": {problem}"),
+            ));
+        }
+        for preset in source.presets.iter().filter(|n| exclude_preset(n).is_none()) {
+            let known = exclude_preset_names().collect::<Vec<_>>().join(", ");
+            out.push(diag(
+                DiagnosticSeverity::Warning,
+                line_of(&format!("sources.{i}.presets")),
+                format!("source \"{name}\": unknown preset \"{preset}\" (known presets: {known})"),
+            ));
+        }
+    }
+    out.sort_by_key(|d| d.line);
+    out
+}
+
+/// 1-based line of byte `offset` in `s`.
+fn line_at(s: &str, offset: usize) -> usize {
+    s.as_bytes()[..offset.min(s.len())].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+/// The line each key and table header starts on, keyed by the dotted path
+/// `serde_ignored` reports (`scan.exclude`, `sources.1.name`).  A line scan
+/// rather than a parser: it follows `[table]` and `[[array]]` headers and
+/// `key = value` lines, which is all a config file uses.
+fn key_lines(toml_str: &str) -> std::collections::HashMap<String, usize> {
+    let mut lines = std::collections::HashMap::new();
+    let mut array_len: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let mut table = String::new();
+    for (i, line) in toml_str.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("[[").and_then(|l| l.split("]]").next()) {
+            let name = name.trim().to_string();
+            let index = array_len.entry(name.clone()).or_insert(0);
+            table = format!("{name}.{index}");
+            *index += 1;
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.split(']').next()) {
+            // `[sources.s3]` belongs to the latest `[[sources]]` entry.
+            let name = name.trim();
+            table = match name.split_once('.') {
+                Some((parent, rest)) if array_len.contains_key(parent) => {
+                    format!("{parent}.{}.{rest}", array_len[parent] - 1)
+                }
+                _ => name.to_string(),
+            };
+        } else if let Some((key, _)) = line.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                continue;
+            }
+            let path = if table.is_empty() { key.to_string() } else { format!("{table}.{key}") };
+            lines.entry(path).or_insert(i + 1);
+            continue;
+        } else {
+            continue;
+        }
+        lines.entry(table.clone()).or_insert(i + 1);
+    }
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1338,6 +2712,161 @@ enabled = false
         assert_eq!(ov.archives.as_ref().unwrap().enabled, Some(false));
     }
 
+    #[test]
+    fn s3_source_location() {
+        let toml = r#"
+[server]
+url = "http://localhost:8080"
+token = "t"
+
+[[sources]]
+name = "logs"
+path = "s3://my-bucket/app/logs/"
+
+[sources.s3]
+endpoint = "http://minio:9000"
+
+[[sources]]
+name = "home"
+path = "/home/alice"
+"#;
+        let (cfg, warnings) = parse_client_config(toml).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(cfg.sources[0].s3_location(), Some(("my-bucket", "app/logs/")));
+        assert_eq!(cfg.sources[0].s3.as_ref().unwrap().endpoint.as_deref(), Some("http://minio:9000"));
+        assert_eq!(cfg.sources[1].s3_location(), None);
+    }
+
+    #[test]
+    fn presets_add_to_source_excludes() {
+        let toml = r#"
+[server]
+url = "http://localhost:8080"
+token = "t"
+
+[scan]
+exclude = ["**/*.log"]
+
+[[sources]]
+name = "home"
+path = "/home/alice"
+presets = ["dev", "browser-cache", "nonsense"]
+"#;
+        let (cfg, warnings) = parse_client_config(toml).unwrap();
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("unknown preset \"nonsense\""), "{}", warnings[0]);
+        let scan = cfg.sources[0].scan_config(&cfg.scan);
+        assert_eq!(scan.exclude[0], "**/*.log");
+        assert!(scan.exclude.iter().any(|p| p == "**/node_modules/**"));
+        assert!(scan.exclude.iter().any(|p| p == "**/.cache/mozilla/**"));
+        assert_eq!(cfg.scan.exclude, vec!["**/*.log"]);
+        let names: Vec<_> = exclude_preset_names().collect();
+        assert_eq!(names, vec!["browser-cache", "dev", "os", "package-cache"]);
+    }
+
+    #[test]
+    fn git_history_per_source_overrides_scan() {
+        let toml = r#"
+[server]
+url = "http://localhost:8080"
+token = "t"
+
+[scan.git_history]
+commits = false
+
+[[sources]]
+name = "code"
+path = "/home/alice/code"
+git_history = { tags = ["v*"], max_tags = 5 }
+
+[[sources]]
+name = "docs"
+path = "/home/alice/docs"
+"#;
+        let (cfg, warnings) = parse_client_config(toml).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let code = cfg.sources[0].scan_config(&cfg.scan).git_history.unwrap();
+        assert_eq!(code, GitHistoryConfig { commits: true, tags: vec!["v*".into()], max_tags: 5 });
+        let docs = cfg.sources[1].scan_config(&cfg.scan).git_history.unwrap();
+        assert_eq!(docs, GitHistoryConfig { commits: false, ..GitHistoryConfig::default() });
+        assert_eq!(ScanConfig::default().git_history, None);
+    }
+
+    #[test]
+    fn cloud_source_location() {
+        let source = |path: &str| SourceConfig {
+            name: "drive".into(),
+            path: path.into(),
+            include: vec![],
+            ignore_files: None,
+            follow_symlinks: None,
+            change_detection: None,
+            watch_mode: None,
+            s3: None,
+            cloud: None,
+            presets: vec![],
+            git_history: None,
+            scan_schedule: None,
+            full_scan_schedule: None,
+            require_file: None,
+            require_mount: false,
+            remote: None,
+        };
+        assert_eq!(source("gdrive:").cloud_location(), Some((CloudProvider::GoogleDrive, "")));
+        assert_eq!(source("dropbox:/Work/Reports/").cloud_location(), Some((CloudProvider::Dropbox, "Work/Reports")));
+        assert_eq!(source("onedrive:Documents").cloud_location(), Some((CloudProvider::OneDrive, "Documents")));
+        assert_eq!(source("/home/alice").cloud_location(), None);
+        assert!(source("s3://bucket").is_remote());
+    }
+
+    #[test]
+    fn remote_config_applies_globally_then_per_source() {
+        let (mut cfg, _) = parse_client_config(r#"
+[server]
+url = "http://localhost:8765"
+token = "t"
+remote_config = true
+
+[[sources]]
+name = "docs"
+path = "/docs"
+
+[[sources]]
+name = "photos"
+path = "/photos"
+
+[scan]
+exclude = ["**/.git/**"]
+
+[scan.extractors]
+pdf = { mode = "stdout", bin = "pdftotext", args = ["{file}", "-"] }
+"#).unwrap();
+        let remote: RemoteClientConfig = toml::from_str(r#"
+[scan]
+exclude = ["**/*.tmp"]
+max_content_size_mb = 50
+
+[scan.extractors]
+pdf = "server_only"
+exe = "/usr/bin/evil {file}"
+
+[sources.photos]
+archives = { enabled = false }
+"#).unwrap();
+        cfg.apply_remote(&remote);
+        assert!(cfg.server.remote_config);
+        assert_eq!(cfg.scan.exclude, ["**/.git/**", "**/*.tmp"]);
+        assert_eq!(cfg.scan.max_content_size_mb, 50);
+        assert!(matches!(&cfg.scan.extractors["pdf"], ExtractorEntry::Builtin(s) if s == "server_only"));
+        assert!(!cfg.scan.extractors.contains_key("exe"), "commands are never taken from the server");
+
+        let docs = cfg.sources[0].scan_config(&cfg.scan);
+        let photos = cfg.sources[1].scan_config(&cfg.scan);
+        assert!(docs.archives.enabled);
+        assert!(!photos.archives.enabled);
+        assert_eq!(photos.max_content_size_mb, 50);
+    }
+
     #[test]
     fn exclude_extra_appends_to_defaults() {
         let toml = r#"
@@ -1500,4 +3029,92 @@ max_file_size_mb = 50
         let serialised = toml::to_string(&cfg).unwrap();
         assert!(!serialised.contains("dir_include"));
     }
+
+    // ── validate_client_config ───────────────────────────────────────────────
+
+    #[test]
+    fn validate_reports_problems_with_lines() {
+        let dir = std::env::temp_dir();
+        let toml = format!(
+            r#"[server]
+url = "localhost:8080"
+token = ""
+
+[scan]
+exclude_extras = ["*.tmp"]
+
+[[sources]]
+name = "docs"
+path = {dir:?}
+
+[[sources]]
+name = "docs"
+path = "/no/such/dir"
+colour = "red"
+"#
+        );
+        let found: Vec<_> = validate_client_config(&toml)
+            .into_iter()
+            .map(|d| (d.line, d.severity, d.message))
+            .collect();
+        let lines: Vec<_> = found.iter().map(|(line, severity, _)| (line.unwrap(), *severity)).collect();
+        assert_eq!(
+            lines,
+            [
+                (2, DiagnosticSeverity::Error),
+                (3, DiagnosticSeverity::Error),
+                (6, DiagnosticSeverity::Warning),
+                (13, DiagnosticSeverity::Error),
+                (14, DiagnosticSeverity::Error),
+                (15, DiagnosticSeverity::Warning),
+            ],
+            "{found:?}"
+        );
+        assert!(found[2].2.contains("scan.exclude_extras"), "{}", found[2].2);
+        assert!(found[3].2.contains("first defined on line 9"), "{}", found[3].2);
+        assert!(found[4].2.contains("/no/such/dir"), "{}", found[4].2);
+    }
+
+    #[test]
+    fn validate_places_type_and_missing_field_errors() {
+        let toml = "[server]\nurl = \"http://x\"\ntoken = \"t\"\n\n[scan]\nmax_content_size_mb = \"big\"\n";
+        let found = validate_client_config(toml);
+        assert_eq!(found.len(), 1, "{found:?}");
+        assert_eq!((found[0].line, found[0].severity), (Some(6), DiagnosticSeverity::Error));
+
+        let found = validate_client_config("[server]\nurl = \"http://x\"\n");
+        assert_eq!(found.len(), 1, "{found:?}");
+        assert!(found[0].message.contains("token"), "{}", found[0].message);
+    }
+
+    #[test]
+    fn low_memory_profile_fills_unset_settings() {
+        let base = "[server]\ndata_dir = \"/d\"\ntoken = \"t\"\nprofile = \"low-memory\"\n";
+        let (cfg, warnings) = parse_server_config(base).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(cfg.search.fts_candidate_limit, 500);
+        assert_eq!(cfg.server.archive_batch_size, 20);
+        assert_eq!(cfg.worker.split_requests_over_mb, 1);
+        assert_eq!(cfg.storage.backends.len(), 1);
+        assert_eq!(cfg.storage.backends[0].name, "default");
+        assert_eq!(cfg.storage.backends[0].chunk_cache_mb, Some(0));
+
+        let explicit = format!(
+            "{base}[search]\nfts_candidate_limit = 900\n\n[[storage.backends]]\nname = \"a\"\ntype = \"sqlite\"\nchunk_cache_mb = 8\n\n[[storage.backends]]\nname = \"b\"\ntype = \"sqlite\"\n"
+        );
+        let (cfg, _) = parse_server_config(&explicit).unwrap();
+        assert_eq!(cfg.search.fts_candidate_limit, 900, "explicit settings win");
+        assert_eq!(cfg.search.max_concurrent_sources, 4);
+        let caches: Vec<_> = cfg.storage.backends.iter().map(|b| b.chunk_cache_mb).collect();
+        assert_eq!(caches, [Some(8), Some(0)]);
+    }
+
+    #[test]
+    fn unknown_server_profile_is_an_error() {
+        let err = parse_server_config("[server]\ndata_dir = \"/d\"\ntoken = \"t\"\nprofile = \"tiny\"\n").unwrap_err();
+        assert!(err.to_string().contains("low-memory"), "{err}");
+        let (cfg, _) = parse_server_config("[server]\ndata_dir = \"/d\"\ntoken = \"t\"\n").unwrap();
+        assert_eq!(cfg.search.fts_candidate_limit, 2000);
+        assert_eq!(cfg.storage.backends[0].chunk_cache_mb, None);
+    }
 }
@@ -0,0 +1,181 @@
+//! `find-watch`'s local control socket.
+//!
+//! A running watcher listens on a Unix socket (a named pipe on Windows) for
+//! one-line JSON requests and answers each with one line of JSON:
+//!
+//! ```text
+//! → {"command":"pause","until":1718200000}
+//! ← {"ok":true,"message":"Indexing paused until 2024-06-12 14:26."}
+//! ```
+//!
+//! `find-admin` and the tray use it to pause and resume indexing, start a
+//! scan, reload the configuration and read the watcher's status, without
+//! needing write access to the watcher's files or signalling its process.
+//! Scripts can speak the same protocol, e.g. with `nc -U`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::WatchConfig;
+use crate::watch_status::WatchStatus;
+
+/// How long a client waits for the watcher to answer.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A request to the watcher.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum ControlRequest {
+    /// The watcher's live status, as in its heartbeat file.
+    Status,
+    /// Pause indexing until `until` (Unix seconds), or until resumed.
+    Pause {
+        #[serde(default)]
+        until: Option<i64>,
+    },
+    /// Lift an on-demand pause.
+    Resume,
+    /// Start `find-scan`, of one source or of all of them.
+    Scan {
+        #[serde(default)]
+        source: Option<String>,
+        /// Re-index every file (`find-scan --force`).
+        #[serde(default)]
+        force: bool,
+    },
+    /// Re-read the config file.
+    Reload,
+}
+
+/// The watcher's answer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What was done, for showing to the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Answer to [`ControlRequest::Status`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<WatchStatus>,
+}
+
+impl ControlResponse {
+    pub fn done(message: impl Into<String>) -> Self {
+        Self { ok: true, message: Some(message.into()), ..Default::default() }
+    }
+
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self { ok: false, error: Some(error.into()), ..Default::default() }
+    }
+
+    /// The response as a `Result`: the message, or the watcher's error.
+    pub fn into_result(self) -> Result<Self> {
+        if self.ok {
+            Ok(self)
+        } else {
+            anyhow::bail!("find-watch: {}", self.error.as_deref().unwrap_or("request failed"))
+        }
+    }
+}
+
+/// Where the control socket is: `[watch] control_socket`, or
+/// `find-watch.sock` in the state directory (`\\.\pipe\find-watch` on
+/// Windows).  `None` when it is turned off.
+pub fn control_address(watch: &WatchConfig) -> Option<String> {
+    match watch.control_socket.as_deref() {
+        Some("") => None,
+        Some(addr) => Some(addr.to_string()),
+        None if cfg!(windows) => Some(r"\\.\pipe\find-watch".to_string()),
+        None => Some(crate::config::default_state_dir().join("find-watch.sock").to_string_lossy().into_owned()),
+    }
+}
+
+/// Send `request` to the watcher at `addr` and wait for its answer.  Fails
+/// if no watcher is listening; a request the watcher refused comes back as
+/// a response with `ok == false`.
+pub fn send(addr: &str, request: &ControlRequest) -> Result<ControlResponse> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    let mut stream = connect(addr).with_context(|| format!("connecting to find-watch at {addr}"))?;
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).context("reading find-watch's reply")?;
+    if reply.is_empty() {
+        anyhow::bail!("find-watch closed the connection without replying");
+    }
+    serde_json::from_str(&reply).context("parsing find-watch's reply")
+}
+
+#[cfg(unix)]
+fn connect(addr: &str) -> std::io::Result<std::os::unix::net::UnixStream> {
+    let stream = std::os::unix::net::UnixStream::connect(addr)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    Ok(stream)
+}
+
+#[cfg(windows)]
+fn connect(addr: &str) -> std::io::Result<std::fs::File> {
+    /// `ERROR_PIPE_BUSY`: every instance of the pipe is serving a client.
+    const ERROR_PIPE_BUSY: i32 = 231;
+    let deadline = std::time::Instant::now() + TIMEOUT;
+    loop {
+        match std::fs::OpenOptions::new().read(true).write(true).open(addr) {
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && std::time::Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_tagged_by_command() {
+        let json = serde_json::to_string(&ControlRequest::Scan { source: Some("docs".into()), force: false }).unwrap();
+        assert_eq!(json, r#"{"command":"scan","source":"docs","force":false}"#);
+        // Scripts may leave out optional fields.
+        let parsed: ControlRequest = serde_json::from_str(r#"{"command":"scan"}"#).unwrap();
+        assert_eq!(parsed, ControlRequest::Scan { source: None, force: false });
+        let parsed: ControlRequest = serde_json::from_str(r#"{"command":"pause"}"#).unwrap();
+        assert_eq!(parsed, ControlRequest::Pause { until: None });
+        assert!(serde_json::from_str::<ControlRequest>(r#"{"command":"explode"}"#).is_err());
+    }
+
+    #[test]
+    fn empty_address_turns_the_socket_off() {
+        let mut watch = WatchConfig { control_socket: Some(String::new()), ..Default::default() };
+        assert_eq!(control_address(&watch), None);
+        watch.control_socket = Some("/run/fw.sock".into());
+        assert_eq!(control_address(&watch).as_deref(), Some("/run/fw.sock"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn send_round_trips_over_a_unix_socket() {
+        let path = std::env::temp_dir().join(format!("find-control-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            assert_eq!(serde_json::from_str::<ControlRequest>(&line).unwrap(), ControlRequest::Resume);
+            writeln!(&stream, "{}", serde_json::to_string(&ControlResponse::done("Indexing resumed.")).unwrap()).unwrap();
+        });
+        let resp = send(&path.to_string_lossy(), &ControlRequest::Resume).unwrap();
+        server.join().unwrap();
+        assert_eq!(resp.into_result().unwrap().message.as_deref(), Some("Indexing resumed."));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
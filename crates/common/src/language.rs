@@ -0,0 +1,109 @@
+//! Language detection for syntax highlighting.
+//!
+//! Language ids are highlight.js names, so a client can pass them straight
+//! to its highlighter.  The extension table mirrors `EXT_MAP` in
+//! `web/src/lib/highlight.ts`; keep the two in step.
+
+/// Highlighting language for `path` (a plain or composite path), falling
+/// back to the `#!` line when the file name has no known extension.
+pub fn detect_language(path: &str, first_line: Option<&str>) -> Option<&'static str> {
+    language_for_path(path).or_else(|| first_line.and_then(language_for_shebang))
+}
+
+/// Highlighting language for `path` by file name.  For a composite path the
+/// innermost member's name is used.
+pub fn language_for_path(path: &str) -> Option<&'static str> {
+    let member = path.rsplit("::").next().unwrap_or(path);
+    let name = member.rsplit(['/', '\\']).next().unwrap_or(member).to_ascii_lowercase();
+    match name.as_str() {
+        "dockerfile" => return Some("dockerfile"),
+        "makefile" | "gnumakefile" => return Some("makefile"),
+        _ => {}
+    }
+    let (_, ext) = name.rsplit_once('.')?;
+    Some(match ext {
+        // Systems
+        "rs" => "rust",
+        "c" | "h" => "c",
+        "cpp" | "cxx" | "cc" | "hpp" => "cpp",
+        "cs" => "csharp",
+        // JVM
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "scala" => "scala",
+        // Web
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "css" => "css",
+        "html" | "htm" | "xml" | "svg" => "xml",
+        // Scripting
+        "py" => "python",
+        "rb" => "ruby",
+        "lua" => "lua",
+        "php" => "php",
+        "r" => "r",
+        "go" => "go",
+        "swift" => "swift",
+        // Shell
+        "sh" | "bash" => "bash",
+        "zsh" | "fish" | "ps1" => "shell",
+        // Data / config
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" | "ini" | "cfg" | "conf" | "env" => "ini",
+        // Markup
+        "md" | "markdown" => "markdown",
+        "sql" => "sql",
+        "dockerfile" => "dockerfile",
+        "mk" => "makefile",
+        "vim" => "vim",
+        _ => return None,
+    })
+}
+
+/// Highlighting language named by a `#!` interpreter line, e.g.
+/// `#!/usr/bin/env python3` or `#!/bin/bash`.
+fn language_for_shebang(line: &str) -> Option<&'static str> {
+    let rest = line.strip_prefix("#!")?;
+    let mut words = rest.split_whitespace();
+    let mut interp = words.next()?.rsplit('/').next()?;
+    if interp == "env" {
+        interp = words.find(|w| !w.starts_with('-'))?;
+    }
+    let interp = interp.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(match interp {
+        "sh" | "bash" | "dash" | "ksh" => "bash",
+        "zsh" | "fish" => "shell",
+        "python" => "python",
+        "ruby" => "ruby",
+        "node" | "deno" => "javascript",
+        "lua" => "lua",
+        "php" => "php",
+        "Rscript" => "r",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_by_extension_and_name() {
+        assert_eq!(language_for_path("src/main.rs"), Some("rust"));
+        assert_eq!(language_for_path("web/App.TSX"), Some("typescript"));
+        assert_eq!(language_for_path("build/Dockerfile"), Some("dockerfile"));
+        assert_eq!(language_for_path("src.zip::lib/util.py"), Some("python"));
+        assert_eq!(language_for_path("report.pdf"), None);
+        assert_eq!(language_for_path("README"), None);
+    }
+
+    #[test]
+    fn falls_back_to_shebang() {
+        assert_eq!(detect_language("bin/deploy", Some("#!/usr/bin/env python3")), Some("python"));
+        assert_eq!(detect_language("bin/run", Some("#!/bin/bash -e")), Some("bash"));
+        assert_eq!(detect_language("bin/run", Some("#!/usr/bin/env -S node --inspect")), Some("javascript"));
+        assert_eq!(detect_language("notes", Some("hello")), None);
+        assert_eq!(detect_language("a.rs", Some("#!/bin/sh")), Some("rust"));
+    }
+}
@@ -1,9 +1,16 @@
 pub mod api;
 pub mod config;
+pub mod control;
+pub mod language;
 pub mod logging;
 pub mod mem;
 pub mod path;
+pub mod path_map;
+pub mod pause;
+pub mod reload;
+pub mod schedule;
 pub mod subprocess;
+pub mod watch_status;
 
 pub use find_extract_types::build_globset;
 
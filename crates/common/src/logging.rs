@@ -1,13 +1,13 @@
-use std::sync::OnceLock;
+use std::sync::RwLock;
 
 use tracing::{field::Visit, Metadata, Subscriber};
 use tracing_subscriber::layer::Context;
 
-static IGNORE_PATTERNS: OnceLock<Vec<regex::Regex>> = OnceLock::new();
+static IGNORE_PATTERNS: RwLock<Vec<regex::Regex>> = RwLock::new(Vec::new());
 
 /// Returns `true` if `msg` matches any pattern in `patterns`.
 ///
-/// This is the core matching logic, separated from the global `OnceLock` so
+/// This is the core matching logic, separated from the global pattern set so
 /// it can be called directly in tests with an explicit pattern set.
 pub fn is_ignored_with(patterns: &[regex::Regex], msg: &str) -> bool {
     patterns.iter().any(|p| p.is_match(msg))
@@ -19,26 +19,24 @@ pub fn is_ignored_with(patterns: &[regex::Regex], msg: &str) -> bool {
 /// are re-emitted as tracing events, ensuring the same patterns work for
 /// both in-process and subprocess log sources.
 pub fn is_ignored(msg: &str) -> bool {
-    let Some(patterns) = IGNORE_PATTERNS.get() else {
-        return false;
-    };
-    is_ignored_with(patterns, msg)
+    let patterns = IGNORE_PATTERNS.read().unwrap_or_else(|e| e.into_inner());
+    is_ignored_with(&patterns, msg)
 }
 
 /// Compile and activate the log-ignore patterns from config.
 ///
-/// Should be called once, after the tracing subscriber is initialised but
-/// before any work that would produce the noisy log messages.  Subsequent
-/// calls (e.g. if somehow invoked twice) are silently ignored — the first
-/// call wins.
+/// Should be called after the tracing subscriber is initialised but before
+/// any work that would produce the noisy log messages.  A later call (on a
+/// config reload) replaces the patterns.
 ///
-/// Returns an error if any pattern is not a valid regular expression.
+/// Returns an error if any pattern is not a valid regular expression; the
+/// patterns in force are then left unchanged.
 pub fn set_ignore_patterns(patterns: &[String]) -> Result<(), regex::Error> {
     let compiled = patterns
         .iter()
         .map(|p| regex::Regex::new(p))
         .collect::<Result<Vec<_>, _>>()?;
-    let _ = IGNORE_PATTERNS.set(compiled);
+    *IGNORE_PATTERNS.write().unwrap_or_else(|e| e.into_inner()) = compiled;
     Ok(())
 }
 
@@ -63,9 +61,7 @@ impl<S: Subscriber> tracing_subscriber::layer::Filter<S> for LogIgnoreFilter {
         event: &tracing::Event<'_>,
         _cx: &Context<'_, S>,
     ) -> bool {
-        let Some(patterns) = IGNORE_PATTERNS.get() else {
-            return true;
-        };
+        let patterns = IGNORE_PATTERNS.read().unwrap_or_else(|e| e.into_inner());
         if patterns.is_empty() {
             return true;
         }
@@ -82,7 +78,7 @@ impl<S: Subscriber> tracing_subscriber::layer::Filter<S> for LogIgnoreFilter {
         // against the in-process log target (e.g. "pdf_extract: unknown glyph")
         // continue to work when the same message arrives via subprocess relay
         // (where the tracing target becomes "subprocess").
-        !is_ignored_with(patterns, &candidate) && !is_ignored_with(patterns, &visitor.message)
+        !is_ignored_with(&patterns, &candidate) && !is_ignored_with(&patterns, &visitor.message)
     }
 }
 
@@ -0,0 +1,204 @@
+//! Mapping indexed paths to where the files are on this machine.
+//!
+//! Hit paths are relative to their source's root as the scanner saw it —
+//! often a Linux server.  `[cli.path_map]` says where a source's files are
+//! here: either one local directory for the whole source, or prefix rules:
+//!
+//! ```toml
+//! [cli.path_map]
+//! code = '/home/me/code'
+//!
+//! [[cli.path_map.nas]]
+//! prefix = ""                          # the whole source…
+//! local  = '\\nas\share'
+//!
+//! [[cli.path_map.nas]]
+//! prefix = "photos"                    # …except photos/, which is on P:
+//! local  = 'P:\'
+//! url    = "https://photos.example/browse/{path}"
+//! ```
+//!
+//! The longest prefix (whole path segments) with the wanted kind of target
+//! wins.  Sources in `[[sources]]` without a `local` rule fall back to their
+//! own `path`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ClientConfig, PathMapEntry, PathMapRule};
+
+/// Resolved `[cli.path_map]`, plus the roots of this machine's own sources.
+#[derive(Debug, Clone, Default)]
+pub struct PathMapper {
+    rules: HashMap<String, Vec<PathMapRule>>,
+}
+
+impl PathMapper {
+    pub fn new(config: &ClientConfig) -> Self {
+        let mut rules: HashMap<String, Vec<PathMapRule>> = HashMap::new();
+        for (source, entry) in &config.cli.path_map {
+            let entry = match entry {
+                PathMapEntry::Root(root) => vec![PathMapRule { local: Some(root.clone()), ..Default::default() }],
+                PathMapEntry::Rules(rules) => rules.clone(),
+            };
+            rules.insert(source.clone(), entry);
+        }
+        for source in &config.sources {
+            let source_rules = rules.entry(source.name.clone()).or_default();
+            if !source_rules.iter().any(|r| r.local.is_some() && r.prefix.trim_matches('/').is_empty()) {
+                source_rules.push(PathMapRule { local: Some(source.path.clone()), ..Default::default() });
+            }
+        }
+        Self { rules }
+    }
+
+    /// The local file `rel_path` in `source` is, or `None` when no rule with
+    /// a `local` directory covers it.
+    pub fn local_path(&self, source: &str, rel_path: &str) -> Option<PathBuf> {
+        let (rule, rest) = self.matching(source, rel_path, |r| r.local.is_some())?;
+        let mut path = PathBuf::from(rule.local.as_deref()?);
+        path.extend(rest);
+        Some(path)
+    }
+
+    /// The URL template's URL for `rel_path` in `source` at `line`, or `None`
+    /// when no rule with a `url` covers it.
+    pub fn url(&self, source: &str, rel_path: &str, line: usize) -> Option<String> {
+        let (rule, rest) = self.matching(source, rel_path, |r| r.url.is_some())?;
+        let path = rest.iter().map(|c| encode(c)).collect::<Vec<_>>().join("/");
+        Some(
+            rule.url
+                .as_deref()?
+                .replace("{path}", &path)
+                .replace("{source}", &encode(source))
+                .replace("{line}", &line.to_string()),
+        )
+    }
+
+    /// The source and indexed path a local file or folder is at — the
+    /// reverse of [`local_path`](Self::local_path), comparing components
+    /// case-insensitively as Windows does.  The deepest directory wins, so a
+    /// source nested inside another maps to itself.
+    pub fn source_path(&self, local: &Path) -> Option<(String, String)> {
+        let local = local.to_string_lossy();
+        let local = components(&local);
+        self.rules
+            .iter()
+            .flat_map(|(source, rules)| rules.iter().map(move |r| (source, r)))
+            .filter_map(|(source, rule)| {
+                let root = components(rule.local.as_deref()?);
+                let inside = root.len() <= local.len()
+                    && root.iter().zip(&local).all(|(a, b)| a.to_lowercase() == b.to_lowercase());
+                if !inside {
+                    return None;
+                }
+                let mut path: Vec<&str> = components(&rule.prefix);
+                path.extend(&local[root.len()..]);
+                Some((root.len(), source, path.join("/")))
+            })
+            .max_by_key(|&(depth, ..)| depth)
+            .map(|(_, source, path)| (source.clone(), path))
+    }
+
+    /// The rule for `source` with the longest prefix of `rel_path` among
+    /// those `wanted`, and the path components after that prefix.
+    fn matching<'a>(
+        &'a self,
+        source: &str,
+        rel_path: &'a str,
+        wanted: impl Fn(&PathMapRule) -> bool,
+    ) -> Option<(&'a PathMapRule, Vec<&'a str>)> {
+        let path = components(rel_path);
+        self.rules
+            .get(source)?
+            .iter()
+            .filter(|r| wanted(r))
+            .filter_map(|r| {
+                let prefix = components(&r.prefix);
+                (prefix.len() <= path.len() && prefix.iter().zip(&path).all(|(a, b)| a == b))
+                    .then_some((prefix.len(), r))
+            })
+            .max_by_key(|&(len, _)| len)
+            .map(|(len, r)| (r, path[len..].to_vec()))
+    }
+}
+
+/// Non-empty components of `path`, split on `/` and `\`.
+fn components(path: &str) -> Vec<&str> {
+    path.split(['/', '\\']).filter(|c| !c.is_empty()).collect()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_client_config;
+
+    fn mapper() -> PathMapper {
+        let (config, _) = parse_client_config(r#"
+            [server]
+            url = "http://localhost:8765"
+            token = "t"
+
+            [[sources]]
+            name = "code"
+            path = "/srv/code"
+
+            [cli.path_map]
+            docs = "/mnt/docs"
+
+            [[cli.path_map.nas]]
+            local = "/mnt/nas"
+
+            [[cli.path_map.nas]]
+            prefix = "photos/"
+            local = "/media/photos"
+            url = "https://photos.example/{source}/{path}#L{line}"
+        "#).unwrap();
+        PathMapper::new(&config)
+    }
+
+    #[test]
+    fn longest_prefix_with_a_target_wins() {
+        let m = mapper();
+        assert_eq!(m.local_path("code", "src/main.rs"), Some(PathBuf::from("/srv/code/src/main.rs")));
+        assert_eq!(m.local_path("docs", "a/b.md"), Some(PathBuf::from("/mnt/docs/a/b.md")));
+        assert_eq!(m.local_path("nas", "music/x.mp3"), Some(PathBuf::from("/mnt/nas/music/x.mp3")));
+        assert_eq!(m.local_path("nas", "photos/2020/a.jpg"), Some(PathBuf::from("/media/photos/2020/a.jpg")));
+        // Prefixes match whole segments only.
+        assert_eq!(m.local_path("nas", "photosets/a.jpg"), Some(PathBuf::from("/mnt/nas/photosets/a.jpg")));
+        assert_eq!(m.local_path("other", "x"), None);
+    }
+
+    #[test]
+    fn url_templates_encode_the_rest_of_the_path() {
+        let m = mapper();
+        assert_eq!(
+            m.url("nas", "photos/My Trip/a#1.jpg", 3).as_deref(),
+            Some("https://photos.example/nas/My%20Trip/a%231.jpg#L3")
+        );
+        assert_eq!(m.url("nas", "music/x.mp3", 0), None);
+        assert_eq!(m.url("code", "src/main.rs", 0), None);
+    }
+
+    #[test]
+    fn local_folders_map_back_to_their_source() {
+        let m = mapper();
+        assert_eq!(m.source_path(Path::new("/media/photos/2020")), Some(("nas".into(), "photos/2020".into())));
+        assert_eq!(m.source_path(Path::new("/MNT/nas/music")), Some(("nas".into(), "music".into())));
+        assert_eq!(m.source_path(Path::new("/srv/code")), Some(("code".into(), String::new())));
+        assert_eq!(m.source_path(Path::new("/srv/codex")), None);
+    }
+}
@@ -0,0 +1,81 @@
+//! On-demand pausing of indexing.
+//!
+//! While the pause file exists (`[scan] pause_file`, default `pause.json` in
+//! the state directory) and has not expired, `find-scan` and `find-watch`
+//! hold off on extraction and uploads, as they do during `[scan]
+//! quiet_hours`.  `find-admin pause` writes it, optionally with an expiry,
+//! and `find-admin resume` removes it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{default_state_dir, ScanConfig};
+
+/// Contents of the pause file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PauseFile {
+    /// Unix seconds at which the pause lapses; `None` = until resumed.
+    #[serde(default)]
+    pub until: Option<i64>,
+}
+
+impl PauseFile {
+    /// Whether the pause is still in force at `now` (Unix seconds).
+    pub fn active(&self, now: i64) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+}
+
+/// Where the pause file lives: `[scan] pause_file`, or `pause.json` in the
+/// state directory.
+pub fn pause_path(scan: &ScanConfig) -> PathBuf {
+    scan.pause_file.as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_state_dir().join("pause.json"))
+}
+
+/// Read the pause file; `None` when indexing is not paused on demand.
+pub fn read_pause(path: &Path) -> Result<Option<PauseFile>> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    serde_json::from_slice(&data)
+        .map(Some)
+        .with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Pause indexing until `until` (Unix seconds), or until resumed.
+pub fn write_pause(path: &Path, until: Option<i64>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(&PauseFile { until })?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))
+}
+
+/// Lift an on-demand pause.  Returns false if indexing was not paused.
+pub fn clear_pause(path: &Path) -> Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("removing {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_file_expires() {
+        assert!(PauseFile { until: None }.active(i64::MAX));
+        assert!(PauseFile { until: Some(100) }.active(99));
+        assert!(!PauseFile { until: Some(100) }.active(100));
+    }
+}
@@ -0,0 +1,117 @@
+//! Config reloading for the long-running processes (`find-watch`,
+//! `find-server`).
+//!
+//! The config file is re-read when its modification time changes, on
+//! SIGHUP (Unix), or when the process asks for it through a [`Notify`] (the
+//! Windows service's parameter-change control).  Each process parses and
+//! applies the new text itself; a file that does not parse is reported and
+//! the running configuration kept.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use tokio::sync::{mpsc, Notify};
+use tracing::warn;
+
+/// How often the config file's modification time is checked.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Send the contents of the config file at `path` on `tx` each time it
+/// should be re-read, until `tx` is closed.
+pub async fn watch_config_file(path: PathBuf, trigger: Arc<Notify>, tx: mpsc::Sender<String>) {
+    #[cfg(unix)]
+    forward_hangup(trigger.clone());
+
+    let mut last_modified = modified(&path);
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    ticker.tick().await; // consume the initial immediate tick
+
+    while !tx.is_closed() {
+        let requested = tokio::select! {
+            _ = ticker.tick() => false,
+            _ = trigger.notified() => true,
+        };
+        let current = modified(&path);
+        if !requested && current == last_modified {
+            continue;
+        }
+        last_modified = current;
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                if tx.send(text).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => warn!("config reload: reading {}: {e}", path.display()),
+        }
+    }
+}
+
+/// Turn SIGHUP into a reload request.  Without a handler SIGHUP would
+/// terminate the process.
+#[cfg(unix)]
+fn forward_hangup(trigger: Arc<Notify>) {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::hangup()) {
+        Ok(mut hangup) => {
+            tokio::spawn(async move {
+                while hangup.recv().await.is_some() {
+                    trigger.notify_one();
+                }
+            });
+        }
+        Err(e) => warn!("config reload: cannot listen for SIGHUP: {e}"),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().ok()?.modified().ok()
+}
+
+/// The top-level config sections (`server`, `search`, ...) whose values
+/// differ between `old` and `new`, in declaration order.  Used to tell the
+/// user which edits need a restart to take effect.
+pub fn changed_sections<T: Serialize>(old: &T, new: &T) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn trigger_rereads_the_file() {
+        let dir = std::env::temp_dir().join(format!("find-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("client.toml");
+        std::fs::write(&path, "a = 1").unwrap();
+
+        let trigger = Arc::new(Notify::new());
+        let (tx, mut rx) = mpsc::channel(1);
+        tokio::spawn(watch_config_file(path.clone(), trigger.clone(), tx));
+
+        std::fs::write(&path, "a = 2").unwrap();
+        trigger.notify_one();
+        let text = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap();
+        assert_eq!(text.as_deref(), Some("a = 2"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn changed_sections_lists_differing_keys() {
+        let old = serde_json::json!({ "server": { "bind": "a" }, "log": { "ignore": [] }, "search": {} });
+        let new = serde_json::json!({ "server": { "bind": "b" }, "log": { "ignore": ["x"] }, "search": {} });
+        assert_eq!(changed_sections(&old, &new), ["server", "log"]);
+    }
+}
@@ -0,0 +1,173 @@
+//! Cron-style schedules for the per-source scans `find-watch` runs
+//! (`scan_schedule` and `full_scan_schedule` on a source), in local time.
+//!
+//! Five fields — minute, hour, day of month, month, day of week — each `*`,
+//! a number, a range `a-b`, a list `a,b` or a step `*/n`, `a-b/n`.  Months
+//! and weekdays also take English names (`jan`, `mon`); Sunday is 0 or 7.
+//! As in cron, when both day fields are restricted a day matching either
+//! one fires.  `@hourly`, `@daily`, `@weekly` and `@monthly` are shorthands.
+
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed schedule: one bit per allowed value of each field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Both day fields are restricted, so either may match.
+    day_or_weekday: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), found {}",
+                fields.len()
+            ));
+        };
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAYS)?;
+        Ok(Schedule {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTHS)?,
+            // 7 is another name for Sunday.
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            day_or_weekday: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+}
+
+impl Schedule {
+    /// The first time after `after` (to the minute) that the schedule fires,
+    /// or `None` if it never does (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let mut date = start.date();
+        // Long enough to reach the next 29 February.
+        for _ in 0..366 * 5 {
+            if self.matches_day(date) {
+                let from = if date == start.date() { start.time() } else { NaiveTime::MIN };
+                for hour in (from.hour()..24).filter(|&h| self.hours & (1 << h) != 0) {
+                    let first_minute = if hour == from.hour() { from.minute() } else { 0 };
+                    if let Some(minute) = (first_minute..60).find(|&m| self.minutes & (1 << m) != 0) {
+                        return date.and_hms_opt(hour, minute, 0);
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.day_or_weekday { day || weekday } else { day && weekday }
+    }
+}
+
+/// Parse one field into a bit set of the values it allows.  `names`, when
+/// given, name the values from `min` upwards.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        if let Ok(n) = s.parse::<u32>() {
+            return Ok(n);
+        }
+        let lower = s.to_ascii_lowercase();
+        names
+            .iter()
+            .position(|name| *name == lower)
+            .map(|i| min + i as u32)
+            .ok_or_else(|| format!("invalid value {s:?}"))
+    };
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse::<u32>().ok().filter(|&s| s > 0);
+                (range, step.ok_or_else(|| format!("invalid step in {part:?}"))?)
+            }
+            None => (part, 1),
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (value(a)?, value(b)?)
+        } else {
+            // `5/15` means from 5 to the end in steps of 15.
+            let v = value(range)?;
+            (v, if step > 1 { max } else { v })
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(format!("{part:?} is outside {min}-{max}"));
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> Option<NaiveDateTime> {
+        expr.parse::<Schedule>().unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn next_after_steps_to_the_following_match() {
+        assert_eq!(next("@hourly", "2026-03-10 14:00"), Some(at("2026-03-10 15:00")));
+        assert_eq!(next("*/15 * * * *", "2026-03-10 14:07"), Some(at("2026-03-10 14:15")));
+        assert_eq!(next("30 2 * * *", "2026-03-10 02:30"), Some(at("2026-03-11 02:30")));
+        // 2026-03-10 is a Tuesday; the next Sunday is the 15th.
+        assert_eq!(next("0 3 * * sun", "2026-03-10 14:00"), Some(at("2026-03-15 03:00")));
+        assert_eq!(next("0 3 * * 7", "2026-03-10 14:00"), Some(at("2026-03-15 03:00")));
+        assert_eq!(next("0 0 1 jan-jun/3 *", "2026-05-02 00:00"), Some(at("2027-01-01 00:00")));
+        assert_eq!(next("0 0 29 2 *", "2026-03-01 00:00"), Some(at("2028-02-29 00:00")));
+        assert_eq!(next("0 0 31 2 *", "2026-03-01 00:00"), None);
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 13th, or any Friday: 2026-03-13 is itself a Friday, so the
+        // one after it is Friday the 20th.
+        assert_eq!(next("0 9 13 * fri", "2026-03-10 00:00"), Some(at("2026-03-13 09:00")));
+        assert_eq!(next("0 9 13 * fri", "2026-03-13 09:00"), Some(at("2026-03-20 09:00")));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expr in ["", "* * * *", "60 * * * *", "* * 0 * *", "*/0 * * * *", "0 0 * * funday", "5-1 * * * *"] {
+            assert!(expr.parse::<Schedule>().is_err(), "{expr:?} should not parse");
+        }
+    }
+}
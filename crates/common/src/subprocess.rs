@@ -42,6 +42,7 @@ pub async fn extract_lines_via_subprocess(
     let is_archive = matches!(
         ext.as_str(),
         "zip" | "tar" | "gz" | "bz2" | "xz" | "tgz" | "tbz2" | "txz" | "7z"
+        | "whl" | "nupkg" | "crx" | "xpi" | "vsix" | "svgz"
     );
     let is_pdf = ext == "pdf";
 
@@ -93,7 +94,8 @@ pub fn extractor_binary_for(path: &Path, extractor_dir: &Option<String>) -> Stri
         .to_lowercase();
 
     let name = match ext.as_str() {
-        "zip" | "tar" | "gz" | "bz2" | "xz" | "tgz" | "tbz2" | "txz" | "7z" => {
+        "zip" | "tar" | "gz" | "bz2" | "xz" | "tgz" | "tbz2" | "txz" | "7z"
+        | "whl" | "nupkg" | "crx" | "xpi" | "vsix" | "svgz" => {
             "find-extract-archive"
         }
         "pdf" => "find-extract-pdf",
@@ -0,0 +1,115 @@
+//! Heartbeat file written by `find-watch`.
+//!
+//! `find-watch` usually runs headless or as a service, so every
+//! [`HEARTBEAT_SECS`] it rewrites a small JSON file describing what it is
+//! doing.  `find-admin watch-status` and the tray read it.  A file whose
+//! `updated_at` is older than [`STALE_AFTER_SECS`] means the watcher has
+//! stopped or hung.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{default_state_dir, WatchConfig};
+
+/// Seconds between heartbeat writes.
+pub const HEARTBEAT_SECS: u64 = 10;
+/// Age after which a heartbeat means the watcher is no longer running.
+pub const STALE_AFTER_SECS: i64 = 3 * HEARTBEAT_SECS as i64;
+
+/// Contents of the heartbeat file.  Timestamps are Unix seconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchStatus {
+    pub pid: u32,
+    pub version: String,
+    pub started_at: i64,
+    pub updated_at: i64,
+    pub sources: Vec<WatchSourceStatus>,
+    /// Paths collected in the current batch window, not yet indexed.
+    pub pending_paths: usize,
+    /// Filesystem events received but not yet read by the event loop.
+    pub queued_events: usize,
+    pub last_event_at: Option<i64>,
+    /// Filesystem events received since the watcher started.
+    pub events_received: u64,
+    /// Last time the server accepted a batch.
+    pub last_upload_at: Option<i64>,
+    pub files_updated: u64,
+    pub files_deleted: u64,
+    pub errors: u64,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<i64>,
+    /// Batches held in the local spool while the server is unreachable.
+    pub spooled_batches: usize,
+    /// Why indexing is paused (quiet hours or `find-admin pause`), if it is.
+    pub paused: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchSourceStatus {
+    pub name: String,
+    pub path: String,
+    /// How changes are followed: `"events"`, `"poll"` or `"native"`.
+    pub backend: String,
+}
+
+impl WatchStatus {
+    /// Whether the heartbeat is too old for the watcher to still be running.
+    pub fn is_stale(&self, now: i64) -> bool {
+        now - self.updated_at > STALE_AFTER_SECS
+    }
+}
+
+/// Where the heartbeat file lives: `[watch] status_file`, or
+/// `find-watch-status.json` in the state directory.
+pub fn status_path(watch: &WatchConfig) -> PathBuf {
+    watch.status_file.as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_state_dir().join("find-watch-status.json"))
+}
+
+/// Read the heartbeat at `path`; `None` if the watcher has never written one.
+pub fn read_status(path: &Path) -> Result<Option<WatchStatus>> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    serde_json::from_slice(&data)
+        .map(Some)
+        .with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Replace the heartbeat at `path` in one step, so readers never see a
+/// partly written file.
+pub fn write_status(path: &Path, status: &WatchStatus) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(status)?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_round_trips_and_goes_stale() {
+        let dir = std::env::temp_dir().join(format!("find-watch-status-{}", std::process::id()));
+        let path = dir.join("status.json");
+        assert!(read_status(&path).unwrap().is_none());
+
+        let status = WatchStatus { pid: 42, updated_at: 1_000, pending_paths: 3, ..Default::default() };
+        write_status(&path, &status).unwrap();
+        let read = read_status(&path).unwrap().unwrap();
+        assert_eq!((read.pid, read.pending_paths), (42, 3));
+        assert!(!read.is_stale(1_000 + STALE_AFTER_SECS));
+        assert!(read.is_stale(1_001 + STALE_AFTER_SECS));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
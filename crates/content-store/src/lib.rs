@@ -20,8 +20,13 @@ use find_common::config::BackendInstanceConfig;
 /// `dir` is the data directory for this backend (the caller decides whether
 /// to use `data_dir` directly or a per-backend subdirectory).
 pub fn open_backend(b: &BackendInstanceConfig, dir: &Path) -> Result<Arc<dyn ContentStore>> {
-    Ok(Arc::new(
-        SqliteContentStore::open(dir, b.chunk_size_kb, b.max_read_connections, b.compress)
-            .map_err(|e| anyhow::anyhow!("opening sqlite store '{}': {e:#}", b.name))?,
-    ))
+    let mut store = SqliteContentStore::open(dir, b.chunk_size_kb, b.max_read_connections, Some(b.effective_compression()))
+        .map_err(|e| anyhow::anyhow!("opening sqlite store '{}': {e:#}", b.name))?;
+    if let Some(mb) = b.chunk_cache_mb {
+        store = store.with_chunk_cache_mb(mb);
+    }
+    if let Some(kb) = b.pack_size_kb {
+        store = store.with_pack_size_kb(kb);
+    }
+    Ok(Arc::new(store))
 }
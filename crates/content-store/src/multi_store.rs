@@ -55,6 +55,15 @@ impl ContentStore for MultiContentStore {
         Ok(false)
     }
 
+    fn stored_size(&self, key: &ContentKey) -> Result<Option<u64>> {
+        for s in &self.stores {
+            if let Some(bytes) = s.stored_size(key)? {
+                return Ok(Some(bytes));
+            }
+        }
+        Ok(None)
+    }
+
     fn compact(&self, live_keys: &HashSet<ContentKey>, dry_run: bool) -> Result<CompactResult> {
         let mut total = CompactResult {
             units_scanned: 0,
@@ -0,0 +1,172 @@
+//! Bounded LRU of decoded chunks, shared by all readers of one store.
+//!
+//! Search and context requests read the same few chunks of hot files over
+//! and over; caching the decoded text saves both the row read and the
+//! gzip/zstd decode.  Entries are keyed by `(content key, chunk_num)` and
+//! remember the chunk's `start_line`, so a blob re-chunked under a different
+//! `chunk_size_kb` can never be served from an entry of the old layout.
+//!
+//! Writers invalidate: `delete` drops the key's chunks and `compact` clears
+//! everything.  Each invalidation also bumps a generation counter; a reader
+//! records the generation before querying and its insert is discarded if an
+//! invalidation happened in between, so a read racing a delete cannot put
+//! stale text back.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+struct Entry {
+    start_line: i64,
+    text: Arc<str>,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    keys: HashMap<String, HashMap<i64, Entry>>,
+    /// Recency order: tick of last use → (key, chunk_num).
+    lru: BTreeMap<u64, (String, i64)>,
+    tick: u64,
+    bytes: usize,
+    generation: u64,
+}
+
+pub(super) struct ChunkCache {
+    inner: Mutex<Inner>,
+    capacity_bytes: usize,
+}
+
+impl ChunkCache {
+    /// A cache holding up to `capacity_bytes` of chunk text; 0 disables it.
+    pub(super) fn new(capacity_bytes: usize) -> Self {
+        Self { inner: Mutex::new(Inner::default()), capacity_bytes }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current generation; pass it back to [`insert`](Self::insert).
+    pub(super) fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+
+    pub(super) fn get(&self, key: &str, chunk_num: i64, start_line: i64) -> Option<Arc<str>> {
+        if self.capacity_bytes == 0 {
+            return None;
+        }
+        let mut guard = self.lock();
+        let inner = &mut *guard;
+        inner.tick += 1;
+        let tick = inner.tick;
+        let entry = inner.keys.get_mut(key)?.get_mut(&chunk_num)?;
+        if entry.start_line != start_line {
+            return None;
+        }
+        let old_tick = std::mem::replace(&mut entry.tick, tick);
+        let text = Arc::clone(&entry.text);
+        if let Some(id) = inner.lru.remove(&old_tick) {
+            inner.lru.insert(tick, id);
+        }
+        Some(text)
+    }
+
+    pub(super) fn insert(&self, generation: u64, key: &str, chunk_num: i64, start_line: i64, text: Arc<str>) {
+        if text.len() > self.capacity_bytes {
+            return;
+        }
+        let mut guard = self.lock();
+        let inner = &mut *guard;
+        if inner.generation != generation {
+            return;
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        let size = text.len();
+        let replaced = inner.keys
+            .entry(key.to_string())
+            .or_default()
+            .insert(chunk_num, Entry { start_line, text, tick });
+        if let Some(old) = replaced {
+            inner.lru.remove(&old.tick);
+            inner.bytes -= old.text.len();
+        }
+        inner.lru.insert(tick, (key.to_string(), chunk_num));
+        inner.bytes += size;
+
+        while inner.bytes > self.capacity_bytes {
+            let Some((_, (k, c))) = inner.lru.pop_first() else { break };
+            inner.remove_chunk(&k, c);
+        }
+    }
+
+    /// Drop every cached chunk of `key`.
+    pub(super) fn invalidate(&self, key: &str) {
+        let mut guard = self.lock();
+        let inner = &mut *guard;
+        inner.generation += 1;
+        if let Some(chunks) = inner.keys.remove(key) {
+            for entry in chunks.into_values() {
+                inner.lru.remove(&entry.tick);
+                inner.bytes -= entry.text.len();
+            }
+        }
+    }
+
+    pub(super) fn clear(&self) {
+        let mut inner = self.lock();
+        let generation = inner.generation + 1;
+        *inner = Inner { generation, ..Inner::default() };
+    }
+}
+
+impl Inner {
+    fn remove_chunk(&mut self, key: &str, chunk_num: i64) {
+        let Some(chunks) = self.keys.get_mut(key) else { return };
+        if let Some(entry) = chunks.remove(&chunk_num) {
+            self.bytes -= entry.text.len();
+        }
+        if chunks.is_empty() {
+            self.keys.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Arc<str> {
+        Arc::from(s)
+    }
+
+    #[test]
+    fn evicts_least_recently_used_within_budget() {
+        let cache = ChunkCache::new(8);
+        let g = cache.generation();
+        cache.insert(g, "a", 0, 0, text("aaaa"));
+        cache.insert(g, "b", 0, 0, text("bbbb"));
+        assert!(cache.get("a", 0, 0).is_some()); // touch a → b is now oldest
+        cache.insert(g, "c", 0, 0, text("cccc"));
+        assert!(cache.get("b", 0, 0).is_none());
+        assert!(cache.get("a", 0, 0).is_some());
+        assert!(cache.get("c", 0, 0).is_some());
+    }
+
+    #[test]
+    fn invalidation_drops_entries_and_racing_inserts() {
+        let cache = ChunkCache::new(1024);
+        let before = cache.generation();
+        cache.insert(before, "k", 0, 0, text("old"));
+        cache.invalidate("k");
+        assert!(cache.get("k", 0, 0).is_none());
+
+        // A reader that started before the invalidation must not repopulate.
+        cache.insert(before, "k", 0, 0, text("old"));
+        assert!(cache.get("k", 0, 0).is_none());
+
+        cache.insert(cache.generation(), "k", 0, 0, text("new"));
+        assert_eq!(cache.get("k", 0, 0).as_deref(), Some("new"));
+        assert!(cache.get("k", 0, 5).is_none(), "different layout is a miss");
+    }
+}
@@ -6,14 +6,15 @@
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 
 pub const SCHEMA_SQL: &str = "
 PRAGMA journal_mode = WAL;
 PRAGMA synchronous = NORMAL;
 PRAGMA cache_size = -16384;
 
--- One row per chunk per blob. Data is stored uncompressed.
+-- One row per chunk per blob. Data is plain UTF-8 or, depending on the store's
+-- compression setting at write time, a gzip stream or a zstd frame.
 -- Chunk positions are 0-based line indices into the original blob
 -- (position 0 = first line, i.e. the file path itself).
 CREATE TABLE IF NOT EXISTS blobs (
@@ -21,11 +22,39 @@ CREATE TABLE IF NOT EXISTS blobs (
     chunk_num  INTEGER NOT NULL,   -- 0-based chunk index
     start_line INTEGER NOT NULL,   -- first line position in this chunk
     end_line   INTEGER NOT NULL,   -- last line position in this chunk (inclusive)
-    data       BLOB    NOT NULL,   -- raw chunk bytes: plain UTF-8 or gzip-compressed UTF-8
+    data       BLOB    NOT NULL,   -- raw chunk bytes: plain UTF-8, gzip, or zstd frame
     PRIMARY KEY (key, chunk_num)
 );
 
 CREATE INDEX IF NOT EXISTS idx_blobs_key_start ON blobs(key, start_line);
+
+-- Small blobs packed together: each pack holds the text of many blobs back
+-- to back, encoded like a chunk.  Packs are written once and only replaced
+-- by compaction.
+CREATE TABLE IF NOT EXISTS packs (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    raw_len    INTEGER NOT NULL,   -- decoded length in bytes
+    data       BLOB    NOT NULL    -- plain UTF-8, gzip, or zstd frame
+);
+
+-- Where each packed blob sits in its pack's decoded text.  A key is stored
+-- either here or in `blobs`, never both.
+CREATE TABLE IF NOT EXISTS packed_blobs (
+    key        TEXT    PRIMARY KEY,
+    pack_id    INTEGER NOT NULL,
+    byte_start INTEGER NOT NULL,
+    byte_len   INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_packed_blobs_pack ON packed_blobs(pack_id);
+
+-- Trained zstd dictionaries, keyed by the dictionary ID embedded in every
+-- zstd frame that uses them.  Never deleted: old chunks may still refer to them.
+CREATE TABLE IF NOT EXISTS zstd_dicts (
+    id         INTEGER PRIMARY KEY,
+    data       BLOB    NOT NULL,
+    created_at INTEGER NOT NULL
+);
 ";
 
 /// Open `blobs.db` read-only with a 1 s busy timeout.
@@ -51,18 +80,39 @@ pub fn open_write(data_dir: &Path) -> Result<Connection> {
     Ok(conn)
 }
 
-/// Check whether any chunk exists for `key`.
+/// Check whether `key` is stored, in chunks or in a pack.
 pub fn blob_exists(conn: &Connection, key: &str) -> Result<bool> {
-    let n: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM blobs WHERE key = ?1 LIMIT 1",
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM blobs WHERE key = ?1)
+             OR EXISTS(SELECT 1 FROM packed_blobs WHERE key = ?1)",
         rusqlite::params![key],
         |r| r.get(0),
     )?;
-    Ok(n > 0)
+    Ok(exists)
+}
+
+/// Total bytes of `key`'s chunk data as stored (compressed), or `None` if
+/// the key is not stored.  A packed blob is charged its share of the pack.
+pub fn stored_size(conn: &Connection, key: &str) -> Result<Option<u64>> {
+    let bytes: Option<i64> = conn.query_row(
+        "SELECT SUM(length(data)) FROM blobs WHERE key = ?1",
+        rusqlite::params![key],
+        |r| r.get(0),
+    )?;
+    if bytes.is_some() {
+        return Ok(bytes.map(|b| b as u64));
+    }
+    let share: Option<i64> = conn.query_row(
+        "SELECT length(p.data) * b.byte_len / MAX(p.raw_len, 1)
+         FROM packed_blobs b JOIN packs p ON p.id = b.pack_id WHERE b.key = ?1",
+        rusqlite::params![key],
+        |r| r.get(0),
+    ).optional()?;
+    Ok(share.map(|b| b as u64))
 }
 
 /// Insert a single chunk row. Ignores conflicts (idempotent).
-/// `data` is the raw bytes to store — either plain UTF-8 or gzip-compressed.
+/// `data` is the raw bytes to store — plain UTF-8, gzip, or a zstd frame.
 pub fn insert_chunk(
     tx: &rusqlite::Transaction,
     key: &str,
@@ -79,27 +129,131 @@ pub fn insert_chunk(
     Ok(())
 }
 
-/// Delete all chunks for `key`.
+/// Delete all chunks for `key`, or its place in a pack.  The pack's bytes
+/// are reclaimed by compaction.
 pub fn delete_blob(conn: &Connection, key: &str) -> Result<()> {
     conn.execute("DELETE FROM blobs WHERE key = ?1", rusqlite::params![key])?;
+    conn.execute("DELETE FROM packed_blobs WHERE key = ?1", rusqlite::params![key])?;
+    Ok(())
+}
+
+/// Where a packed blob's text sits in its pack.
+pub struct PackedBlob {
+    pub pack_id: i64,
+    pub byte_start: usize,
+    pub byte_len: usize,
+}
+
+/// The pack holding `key`, if it is packed.
+pub fn packed_location(conn: &Connection, key: &str) -> Result<Option<PackedBlob>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT pack_id, byte_start, byte_len FROM packed_blobs WHERE key = ?1",
+    )?;
+    Ok(stmt.query_row(rusqlite::params![key], |row| {
+        Ok(PackedBlob {
+            pack_id: row.get(0)?,
+            byte_start: row.get::<_, i64>(1)? as usize,
+            byte_len: row.get::<_, i64>(2)? as usize,
+        })
+    }).optional()?)
+}
+
+/// Read the stored (encoded) data of one pack.
+pub fn read_pack(conn: &Connection, pack_id: i64) -> Result<Vec<u8>> {
+    let mut stmt = conn.prepare_cached("SELECT data FROM packs WHERE id = ?1")?;
+    Ok(stmt.query_row(rusqlite::params![pack_id], |row| row.get(0))?)
+}
+
+/// Insert a pack and the location of each blob in it, given as
+/// `(key, byte_start, byte_len)`.  Returns the new pack's id.
+pub fn insert_pack(
+    tx: &rusqlite::Transaction,
+    raw_len: usize,
+    data: &[u8],
+    members: &[(&str, usize, usize)],
+) -> Result<i64> {
+    tx.execute(
+        "INSERT INTO packs(raw_len, data) VALUES(?1, ?2)",
+        rusqlite::params![raw_len as i64, data],
+    )?;
+    let pack_id = tx.last_insert_rowid();
+    let mut stmt = tx.prepare_cached(
+        "INSERT OR REPLACE INTO packed_blobs(key, pack_id, byte_start, byte_len) VALUES(?1, ?2, ?3, ?4)",
+    )?;
+    for (key, start, len) in members {
+        stmt.execute(rusqlite::params![key, pack_id, *start as i64, *len as i64])?;
+    }
+    Ok(pack_id)
+}
+
+/// Delete a pack and whatever blobs still point into it.
+pub fn delete_pack(conn: &Connection, pack_id: i64) -> Result<()> {
+    conn.execute("DELETE FROM packed_blobs WHERE pack_id = ?1", rusqlite::params![pack_id])?;
+    conn.execute("DELETE FROM packs WHERE id = ?1", rusqlite::params![pack_id])?;
     Ok(())
 }
 
-/// A chunk row returned by a range query.
-pub struct ChunkRow {
+/// Packs less than half of whose text still belongs to a stored blob.
+pub fn sparse_packs(conn: &Connection) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.id FROM packs p LEFT JOIN packed_blobs b ON b.pack_id = p.id
+         GROUP BY p.id HAVING COALESCE(SUM(b.byte_len), 0) * 2 < p.raw_len",
+    )?;
+    let ids = stmt.query_map([], |r| r.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(ids)
+}
+
+/// `(key, byte_start, byte_len)` of the blobs still stored in a pack.
+pub fn pack_members(conn: &Connection, pack_id: i64) -> Result<Vec<(String, usize, usize)>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT key, byte_start, byte_len FROM packed_blobs WHERE pack_id = ?1 ORDER BY byte_start",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![pack_id], |r| {
+            Ok((r.get(0)?, r.get::<_, i64>(1)? as usize, r.get::<_, i64>(2)? as usize))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+/// Keys stored as a single non-empty chunk of at most `max_bytes` as stored:
+/// the blobs compaction packs.
+pub fn loose_small_blobs(conn: &Connection, max_bytes: usize) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT key FROM blobs GROUP BY key
+         HAVING COUNT(*) = 1 AND SUM(length(data)) BETWEEN 1 AND ?1",
+    )?;
+    let keys = stmt
+        .query_map(rusqlite::params![max_bytes as i64], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(keys)
+}
+
+/// The data of `key` if it is stored as exactly one chunk.
+pub fn read_single_chunk(conn: &Connection, key: &str) -> Result<Option<Vec<u8>>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT data FROM blobs WHERE key = ?1 AND chunk_num = 0
+           AND NOT EXISTS(SELECT 1 FROM blobs WHERE key = ?1 AND chunk_num > 0)",
+    )?;
+    Ok(stmt.query_row(rusqlite::params![key], |row| row.get(0)).optional()?)
+}
+
+/// Position of one chunk, returned by a range query.
+pub struct ChunkSpan {
+    pub chunk_num: i64,
     pub start_line: i64,
-    pub data: Vec<u8>,
 }
 
-/// Return all chunks for `key` whose line range overlaps `[lo, hi]`.
-pub fn query_chunks_for_range(
+/// Return the chunks of `key` whose line range overlaps `[lo, hi]`, without
+/// their data, so cached chunks need not be read.
+pub fn query_chunk_spans(
     conn: &Connection,
     key: &str,
     lo: usize,
     hi: usize,
-) -> Result<Vec<ChunkRow>> {
+) -> Result<Vec<ChunkSpan>> {
     let mut stmt = conn.prepare_cached(
-        "SELECT start_line, data
+        "SELECT chunk_num, start_line
          FROM blobs
          WHERE key = ?1 AND start_line <= ?2 AND end_line >= ?3
          ORDER BY chunk_num",
@@ -107,12 +261,20 @@ pub fn query_chunks_for_range(
     let rows = stmt
         .query_map(
             rusqlite::params![key, hi as i64, lo as i64],
-            |row| Ok(ChunkRow { start_line: row.get(0)?, data: row.get(1)? }),
+            |row| Ok(ChunkSpan { chunk_num: row.get(0)?, start_line: row.get(1)? }),
         )?
         .collect::<rusqlite::Result<_>>()?;
     Ok(rows)
 }
 
+/// Read the stored (compressed) data of one chunk.
+pub fn read_chunk(conn: &Connection, key: &str, chunk_num: i64) -> Result<Vec<u8>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT data FROM blobs WHERE key = ?1 AND chunk_num = ?2",
+    )?;
+    Ok(stmt.query_row(rusqlite::params![key, chunk_num], |row| row.get(0))?)
+}
+
 /// Delete all blobs not in `live_keys`. Returns the number of rows deleted.
 /// Uses a temp table to handle large key sets efficiently.
 pub fn delete_orphan_blobs(conn: &Connection, live_keys: &[&str]) -> Result<usize> {
@@ -129,6 +291,9 @@ pub fn delete_orphan_blobs(conn: &Connection, live_keys: &[&str]) -> Result<usiz
     let deleted = conn.execute(
         "DELETE FROM blobs WHERE key NOT IN (SELECT key FROM _live_keys)",
         [],
+    )? + conn.execute(
+        "DELETE FROM packed_blobs WHERE key NOT IN (SELECT key FROM _live_keys)",
+        [],
     )?;
 
     conn.execute_batch("DROP TABLE IF EXISTS _live_keys")?;
@@ -152,10 +317,41 @@ pub fn orphaned_stats(conn: &Connection, live_keys: &[&str]) -> Result<(usize, u
         [],
         |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
     )?;
+    // Packed blobs count as one row each, charged their share of the pack.
+    let (packed, packed_bytes): (i64, i64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(length(p.data) * b.byte_len / MAX(p.raw_len, 1)), 0)
+         FROM packed_blobs b JOIN packs p ON p.id = b.pack_id
+         WHERE b.key NOT IN (SELECT key FROM _live_keys2)",
+        [],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )?;
+    let (rows, keys, bytes) = (rows + packed, keys + packed, bytes + packed_bytes);
     conn.execute_batch("DROP TABLE IF EXISTS _live_keys2")?;
     Ok((rows as usize, keys as usize, bytes as u64))
 }
 
+/// Load all trained zstd dictionaries as `(id, data)` pairs, oldest first.
+pub fn load_zstd_dicts(conn: &Connection) -> Result<Vec<(u32, Vec<u8>)>> {
+    let mut stmt = conn.prepare("SELECT id, data FROM zstd_dicts ORDER BY created_at, rowid")?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get::<_, i64>(0)? as u32, r.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+/// Persist a newly trained zstd dictionary.
+pub fn insert_zstd_dict(conn: &Connection, id: u32, data: &[u8]) -> Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    conn.execute(
+        "INSERT INTO zstd_dicts(id, data, created_at) VALUES(?1, ?2, ?3)",
+        rusqlite::params![id as i64, data, now],
+    )?;
+    Ok(())
+}
+
 /// Return the on-disk size of `blobs.db` in bytes.
 pub fn db_size_bytes(data_dir: &Path) -> u64 {
     std::fs::metadata(data_dir.join("blobs.db"))
@@ -1,18 +1,24 @@
+mod chunk_cache;
 mod db;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read as _, Write as _};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, RwLock};
 
 use anyhow::{Context, Result};
 use flate2::Compression;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+
+use find_common::config::ChunkCompression;
 
 use crate::key::ContentKey;
 use crate::store::{CompactResult, ContentStore};
 
+use chunk_cache::ChunkCache;
+
 // ── Read connection pool ──────────────────────────────────────────────────────
 
 /// How many idle connections to retain between calls.
@@ -21,6 +27,21 @@ const MAX_IDLE_READ_CONNS: usize = 16;
 /// Default hard cap on total open read connections (idle + in-use).
 pub const DEFAULT_MAX_READ_CONNECTIONS: u32 = 16;
 
+/// Default size of the decoded-chunk cache in MB.
+pub const DEFAULT_CHUNK_CACHE_MB: u32 = 64;
+
+/// Default upper bound on a pack of small blobs in KB.
+pub const DEFAULT_PACK_SIZE_KB: u32 = 32;
+
+/// Blobs longer than this many chunks are split into chunks four times the
+/// configured size, so a large log does not turn into tens of thousands of
+/// rows.
+const LARGE_BLOB_CHUNKS: usize = 256;
+
+/// Chunk cache key under which decoded packs are cached, by pack id.  Not a
+/// valid content key, so it cannot collide with a blob's chunks.
+const PACK_CACHE_KEY: &str = "#pack";
+
 struct PoolState {
     idle: Vec<rusqlite::Connection>,
     /// Total open connections: idle + currently borrowed.
@@ -115,13 +136,23 @@ impl Drop for PooledConn<'_> {
 /// Content-addressable SQLite blob store.
 ///
 /// Stores all chunks in a single `blobs.db` SQLite database under `data_dir`.
-/// Each chunk is a plain-text slice of the original file content stored
-/// directly as a TEXT column — no ZIP archives, no separate metadata DB.
+/// Each chunk is a slice of the original file content stored directly in a
+/// BLOB column — plain text, gzip or zstd depending on the configured
+/// compression — with no ZIP archives and no separate metadata DB.
 ///
 /// The key read advantage over `ZipContentStore`: `get_lines` resolves to a
 /// PK-indexed range query returning only the 1–2 rows needed, rather than
 /// loading an entire 10 MB ZIP archive.
 ///
+/// # Packing
+///
+/// A blob that fits in one chunk is first written as an ordinary chunk row
+/// and queued.  Once the queue holds `pack_size` bytes, the queued blobs are
+/// moved into one `packs` row (their text back to back, encoded like a chunk)
+/// and `packed_blobs` records where each one sits.  Blobs still queued when
+/// the store closes simply stay unpacked until the next compaction, which
+/// also rebuilds packs left mostly empty by deletions.
+///
 /// # Thread safety
 ///
 /// - **Writes** (`put`, `delete`, `compact`) use a single `Mutex<Connection>`
@@ -137,8 +168,25 @@ pub struct SqliteContentStore {
     /// Target chunk size in bytes.  Configurable per instance to allow
     /// side-by-side benchmarking of 1 KB / 4 KB / 12 KB configurations.
     chunk_size: usize,
-    /// Whether to gzip-compress chunk data before storing.
-    compress: bool,
+    /// Compression applied to newly written chunks.
+    compression: ChunkCompression,
+    /// Trained zstd dictionaries.  Loaded regardless of `compression` so that
+    /// chunks written under an earlier setting stay readable.
+    zstd: RwLock<ZstdDicts>,
+    /// Decoded chunks of recently read blobs (see `chunk_cache.rs`).
+    chunk_cache: ChunkCache,
+    /// Upper bound on a pack's decoded size in bytes; 0 disables packing.
+    pack_size: usize,
+    /// Small blobs written since the last pack, waiting to be packed.  Only
+    /// touched while holding `write_conn`.
+    pending_pack: Mutex<PendingPack>,
+}
+
+/// Small blobs waiting to be packed, with their text as stored.
+#[derive(Default)]
+struct PendingPack {
+    blobs: Vec<(String, String)>,
+    bytes: usize,
 }
 
 impl SqliteContentStore {
@@ -146,23 +194,199 @@ impl SqliteContentStore {
     ///
     /// `chunk_size_kb` controls how large each chunk can grow before a new
     /// one is started.  Defaults to 1 KB (matching `ZipContentStore`) if
-    /// `None` is passed.
+    /// `None` is passed.  `compression` defaults to none.
     pub fn open(
         data_dir: &Path,
         chunk_size_kb: Option<u32>,
         max_read_connections: Option<u32>,
-        compress: Option<bool>,
+        compression: Option<ChunkCompression>,
     ) -> Result<Self> {
         let write_conn = db::open_write(data_dir).context("opening blobs.db")?;
+        let zstd = ZstdDicts::load(&write_conn).context("loading zstd dictionaries")?;
         let max_conns = max_read_connections.unwrap_or(DEFAULT_MAX_READ_CONNECTIONS) as usize;
         Ok(Self {
             data_dir: data_dir.to_path_buf(),
             write_conn: Mutex::new(write_conn),
             read_pool: ReadPool::new(data_dir.to_path_buf(), max_conns),
             chunk_size: chunk_size_kb.unwrap_or(1) as usize * 1024,
-            compress: compress.unwrap_or(false),
+            compression: compression.unwrap_or_default(),
+            zstd: RwLock::new(zstd),
+            chunk_cache: ChunkCache::new(DEFAULT_CHUNK_CACHE_MB as usize * 1024 * 1024),
+            pack_size: DEFAULT_PACK_SIZE_KB as usize * 1024,
+            pending_pack: Mutex::new(PendingPack::default()),
         })
     }
+
+    /// Size the decoded-chunk cache; 0 disables it.  Defaults to
+    /// `DEFAULT_CHUNK_CACHE_MB`.
+    pub fn with_chunk_cache_mb(mut self, mb: u32) -> Self {
+        self.chunk_cache = ChunkCache::new(mb as usize * 1024 * 1024);
+        self
+    }
+
+    /// Set the upper bound on a pack of small blobs; 0 disables packing.
+    /// Defaults to `DEFAULT_PACK_SIZE_KB`.
+    pub fn with_pack_size_kb(mut self, kb: u32) -> Self {
+        self.pack_size = kb as usize * 1024;
+        self
+    }
+
+    /// Chunk size for a blob of `len` bytes.
+    fn chunk_size_for(&self, len: usize) -> usize {
+        if len > self.chunk_size * LARGE_BLOB_CHUNKS {
+            self.chunk_size * 4
+        } else {
+            self.chunk_size
+        }
+    }
+
+    /// Queue a blob just written as a single chunk for packing, and pack the
+    /// queue once it is full.  Packing failures leave the blobs unpacked,
+    /// which is harmless: compaction packs them later.
+    fn queue_for_pack(&self, conn: &rusqlite::Connection, key: &str, text: String) -> Result<()> {
+        let blobs = {
+            let mut pending = self.pending_pack.lock().map_err(|_| anyhow::anyhow!("pack lock poisoned"))?;
+            pending.bytes += text.len();
+            pending.blobs.push((key.to_string(), text));
+            if pending.bytes < self.pack_size {
+                return Ok(());
+            }
+            std::mem::take(&mut *pending).blobs
+        };
+        if let Err(e) = self.write_pack(conn, &blobs) {
+            tracing::warn!("packing {} small blobs failed: {e:#}", blobs.len());
+        }
+        Ok(())
+    }
+
+    /// Write `blobs` (key and text) as one pack, replacing however each was
+    /// stored before.
+    fn write_pack(&self, conn: &rusqlite::Connection, blobs: &[(String, String)]) -> Result<()> {
+        let mut text = String::with_capacity(blobs.iter().map(|(_, t)| t.len()).sum());
+        let mut members = Vec::with_capacity(blobs.len());
+        for (key, blob) in blobs {
+            members.push((key.as_str(), text.len(), blob.len()));
+            text.push_str(blob);
+        }
+        let data = self.encode_chunk(&text)?;
+        let tx = conn.unchecked_transaction()?;
+        for (key, ..) in &members {
+            db::delete_blob(&tx, key)?;
+        }
+        db::insert_pack(&tx, text.len(), &data, &members)?;
+        tx.commit()?;
+        for (key, ..) in &members {
+            self.chunk_cache.invalidate(key);
+        }
+        Ok(())
+    }
+
+    /// Rebuild packs mostly made of deleted blobs, and pack small blobs
+    /// still stored as loose chunks (written before packing was enabled, or
+    /// queued when the store last closed).  Each step commits on its own, so
+    /// an interrupted compaction leaves every blob readable.  Returns the
+    /// number of packs written.
+    fn repack(&self, conn: &rusqlite::Connection) -> Result<usize> {
+        if self.pack_size == 0 {
+            return Ok(0);
+        }
+
+        // Unpack sparse packs back into loose chunks; the pass below packs
+        // them again together with the other loose blobs.
+        for pack_id in db::sparse_packs(conn)? {
+            let text = self.decode_chunk(&db::read_pack(conn, pack_id)?)?;
+            let tx = conn.unchecked_transaction()?;
+            for (key, start, len) in db::pack_members(&tx, pack_id)? {
+                let blob = text.get(start..start + len)
+                    .with_context(|| format!("pack {pack_id} is shorter than its blobs"))?;
+                let end_line = blob.lines().count().saturating_sub(1);
+                db::insert_chunk(&tx, &key, 0, 0, end_line, &self.encode_chunk(blob)?)?;
+            }
+            db::delete_pack(&tx, pack_id)?;
+            tx.commit()?;
+        }
+
+        // Compressed frames of tiny chunks can outgrow their text, hence the
+        // slack in the size filter; the decoded length decides.
+        let mut packs = 0;
+        let mut batch: Vec<(String, String)> = Vec::new();
+        let mut batch_bytes = 0;
+        for key in db::loose_small_blobs(conn, self.chunk_size * 2)? {
+            let Some(data) = db::read_single_chunk(conn, &key)? else { continue };
+            let text = self.decode_chunk(&data)?;
+            if text.is_empty() || text.len() > self.chunk_size {
+                continue;
+            }
+            batch_bytes += text.len();
+            batch.push((key, text));
+            if batch_bytes >= self.pack_size {
+                self.write_pack(conn, &batch)?;
+                packs += 1;
+                batch.clear();
+                batch_bytes = 0;
+            }
+        }
+        if batch.len() > 1 {
+            self.write_pack(conn, &batch)?;
+            packs += 1;
+        }
+        Ok(packs)
+    }
+
+    /// Encode one chunk for storage according to `self.compression`.
+    fn encode_chunk(&self, data: &str) -> Result<Vec<u8>> {
+        match self.compression {
+            ChunkCompression::None => Ok(data.as_bytes().to_vec()),
+            ChunkCompression::Gzip => gzip_compress(data),
+            ChunkCompression::Zstd => {
+                let mut z = self.zstd.write().map_err(|_| anyhow::anyhow!("zstd lock poisoned"))?;
+                z.compress(data.as_bytes())
+            }
+        }
+    }
+
+    /// Decode a stored chunk, whatever format it was written in.
+    fn decode_chunk(&self, bytes: &[u8]) -> Result<String> {
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            let z = self.zstd.read().map_err(|_| anyhow::anyhow!("zstd lock poisoned"))?;
+            return z.decompress(bytes);
+        }
+        decode_chunk(bytes)
+    }
+
+    /// Train and persist a zstd dictionary once enough samples have been
+    /// collected.  Called after each `put` commits, so a dictionary is never
+    /// referenced by a chunk before it is durably stored.
+    fn maybe_train_zstd_dict(&self, conn: &rusqlite::Connection) {
+        let mut z = match self.zstd.write() {
+            Ok(z) => z,
+            Err(_) => return,
+        };
+        if z.encoder.is_some() || z.samples.len() < ZSTD_TRAIN_SAMPLES {
+            return;
+        }
+        let samples = std::mem::take(&mut z.samples);
+        let total: usize = samples.iter().map(Vec::len).sum();
+        let max_size = (total / 10).clamp(1024, ZSTD_DICT_MAX_SIZE);
+        let trained = zstd::dict::from_samples(&samples, max_size)
+            .map_err(anyhow::Error::from)
+            .and_then(|dict| {
+                let id = zstd::zstd_safe::get_dict_id_from_dict(&dict)
+                    .ok_or_else(|| anyhow::anyhow!("trained dictionary has no ID"))?
+                    .get();
+                db::insert_zstd_dict(conn, id, &dict)?;
+                Ok((id, dict))
+            });
+        match trained {
+            Ok((id, dict)) => {
+                tracing::info!("Trained zstd dictionary {id} ({} bytes) from {} samples", dict.len(), samples.len());
+                z.add(id, &dict);
+                z.encoder = Some((id, EncoderDictionary::copy(&dict, ZSTD_LEVEL)));
+            }
+            // Keep writing dictionary-less frames and try again with fresh samples.
+            Err(e) => tracing::warn!("zstd dictionary training failed: {e:#}"),
+        }
+    }
 }
 
 // ── Chunking ─────────────────────────────────────────────────────────────────
@@ -243,6 +467,8 @@ fn gzip_compress(data: &str) -> Result<Vec<u8>> {
 }
 
 /// Decompress bytes if they look like gzip; otherwise interpret as UTF-8.
+/// zstd frames are handled by [`SqliteContentStore::decode_chunk`], which owns
+/// the dictionaries.
 fn decode_chunk(bytes: &[u8]) -> Result<String> {
     if bytes.starts_with(&GZIP_MAGIC) {
         let mut out = String::new();
@@ -253,6 +479,77 @@ fn decode_chunk(bytes: &[u8]) -> Result<String> {
     }
 }
 
+// ── zstd ──────────────────────────────────────────────────────────────────────
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_LEVEL: i32 = 3;
+/// Chunks collected before training the store's dictionary.
+const ZSTD_TRAIN_SAMPLES: usize = 2000;
+const ZSTD_DICT_MAX_SIZE: usize = 64 * 1024;
+
+/// zstd dictionary state for one store.
+///
+/// There is one dictionary per store, not one per source: blobs are keyed by
+/// content hash and shared by every source that holds the same file, and
+/// `put` is never told which source a blob came from.
+///
+/// Every chunk is an independent zstd frame, so reads decompress only the
+/// chunks covering the requested range.  Frames written before a dictionary
+/// exists carry dictionary ID 0; later frames embed the ID of the dictionary
+/// they were compressed with.
+#[derive(Default)]
+struct ZstdDicts {
+    /// Dictionary used for new writes, once trained.
+    encoder: Option<(u32, EncoderDictionary<'static>)>,
+    /// Every known dictionary by ID.
+    decoders: HashMap<u32, DecoderDictionary<'static>>,
+    /// Training samples gathered while no dictionary exists yet.
+    samples: Vec<Vec<u8>>,
+}
+
+impl ZstdDicts {
+    /// Load persisted dictionaries.  The most recently trained one becomes
+    /// the encoder.
+    fn load(conn: &rusqlite::Connection) -> Result<Self> {
+        let mut z = Self::default();
+        for (id, data) in db::load_zstd_dicts(conn)? {
+            z.add(id, &data);
+            z.encoder = Some((id, EncoderDictionary::copy(&data, ZSTD_LEVEL)));
+        }
+        Ok(z)
+    }
+
+    fn add(&mut self, id: u32, data: &[u8]) {
+        self.decoders.insert(id, DecoderDictionary::copy(data));
+    }
+
+    fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        if let Some((_, dict)) = &self.encoder {
+            return Ok(zstd::bulk::Compressor::with_prepared_dictionary(dict)?.compress(data)?);
+        }
+        if self.samples.len() < ZSTD_TRAIN_SAMPLES && !data.is_empty() {
+            self.samples.push(data.to_vec());
+        }
+        Ok(zstd::bulk::compress(data, ZSTD_LEVEL)?)
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> Result<String> {
+        let mut out = String::new();
+        match zstd::zstd_safe::get_dict_id_from_frame(bytes) {
+            Some(id) => {
+                let dict = self.decoders.get(&id.get())
+                    .ok_or_else(|| anyhow::anyhow!("chunk uses unknown zstd dictionary {id}"))?;
+                zstd::stream::read::Decoder::with_prepared_dictionary(bytes, dict)?
+                    .read_to_string(&mut out)?;
+            }
+            None => {
+                zstd::stream::read::Decoder::with_buffer(bytes)?.read_to_string(&mut out)?;
+            }
+        }
+        Ok(out)
+    }
+}
+
 // ── ContentStore impl ─────────────────────────────────────────────────────────
 
 impl ContentStore for SqliteContentStore {
@@ -264,44 +561,88 @@ impl ContentStore for SqliteContentStore {
             return Ok(false);
         }
 
-        let chunks = chunk_blob(blob, self.chunk_size);
+        let chunks = chunk_blob(blob, self.chunk_size_for(blob.len()));
         let tx = conn.unchecked_transaction()?;
 
         if chunks.is_empty() {
             db::insert_chunk(&tx, key_str, 0, 0, 0, b"")?;
         } else {
             for chunk in &chunks {
-                let bytes: Vec<u8> = if self.compress {
-                    gzip_compress(&chunk.data)?
-                } else {
-                    chunk.data.as_bytes().to_vec()
-                };
+                let bytes = self.encode_chunk(&chunk.data)?;
                 db::insert_chunk(&tx, key_str, chunk.chunk_num, chunk.start_line, chunk.end_line, &bytes)?;
             }
         }
 
         tx.commit()?;
+        if self.compression == ChunkCompression::Zstd {
+            self.maybe_train_zstd_dict(&conn);
+        }
+        if let [chunk] = chunks.as_slice() {
+            if self.pack_size > 0 && chunk.data.len() <= self.chunk_size {
+                self.queue_for_pack(&conn, key_str, chunks.into_iter().next().unwrap().data)?;
+            }
+        }
         Ok(true)
     }
 
     fn delete(&self, key: &ContentKey) -> Result<()> {
         let conn = self.write_conn.lock().map_err(|_| anyhow::anyhow!("write lock poisoned"))?;
-        db::delete_blob(&conn, key.as_str())
+        db::delete_blob(&conn, key.as_str())?;
+        self.chunk_cache.invalidate(key.as_str());
+        let mut pending = self.pending_pack.lock().map_err(|_| anyhow::anyhow!("pack lock poisoned"))?;
+        if let Some(i) = pending.blobs.iter().position(|(k, _)| k == key.as_str()) {
+            let (_, text) = pending.blobs.swap_remove(i);
+            pending.bytes -= text.len();
+        }
+        Ok(())
     }
 
     fn get_lines(&self, key: &ContentKey, lo: usize, hi: usize) -> Result<Option<Vec<(usize, String)>>> {
         let conn = self.read_pool.acquire()?;
+        // Taken before reading so a concurrent delete voids our cache inserts.
+        let generation = self.chunk_cache.generation();
+        // One snapshot for the span query and the chunk reads below, so an
+        // overwrite landing in between cannot remove the rows under us.
+        let conn = conn.unchecked_transaction()?;
+
+        if let Some(packed) = db::packed_location(&conn, key.as_str())? {
+            let text = match self.chunk_cache.get(PACK_CACHE_KEY, packed.pack_id, 0) {
+                Some(text) => text,
+                None => {
+                    let data = db::read_pack(&conn, packed.pack_id)?;
+                    let text: Arc<str> = self.decode_chunk(&data)?.into();
+                    self.chunk_cache.insert(generation, PACK_CACHE_KEY, packed.pack_id, 0, Arc::clone(&text));
+                    text
+                }
+            };
+            let blob = text.get(packed.byte_start..packed.byte_start + packed.byte_len)
+                .with_context(|| format!("pack {} is shorter than blob {}", packed.pack_id, key.as_str()))?;
+            let lines = blob.lines().enumerate()
+                .skip(lo)
+                .take_while(|(pos, _)| *pos <= hi)
+                .map(|(pos, line)| (pos, line.to_owned()))
+                .collect();
+            return Ok(Some(lines));
+        }
 
         if !db::blob_exists(&conn, key.as_str())? {
             return Ok(None);
         }
 
-        let rows = db::query_chunks_for_range(&conn, key.as_str(), lo, hi)?;
+        let spans = db::query_chunk_spans(&conn, key.as_str(), lo, hi)?;
         let mut result: Vec<(usize, String)> = Vec::new();
 
-        for row in rows {
-            let base = row.start_line as usize;
-            let text = decode_chunk(&row.data)?;
+        for span in spans {
+            let base = span.start_line as usize;
+            let text = match self.chunk_cache.get(key.as_str(), span.chunk_num, span.start_line) {
+                Some(text) => text,
+                None => {
+                    let data = db::read_chunk(&conn, key.as_str(), span.chunk_num)?;
+                    let text: Arc<str> = self.decode_chunk(&data)?.into();
+                    self.chunk_cache.insert(generation, key.as_str(), span.chunk_num, span.start_line, Arc::clone(&text));
+                    text
+                }
+            };
             if text.is_empty() {
                 continue; // sentinel row for empty blobs
             }
@@ -321,6 +662,11 @@ impl ContentStore for SqliteContentStore {
         db::blob_exists(&conn, key.as_str())
     }
 
+    fn stored_size(&self, key: &ContentKey) -> Result<Option<u64>> {
+        let conn = self.read_pool.acquire()?;
+        db::stored_size(&conn, key.as_str())
+    }
+
     fn compact(&self, live_keys: &HashSet<ContentKey>, dry_run: bool) -> Result<CompactResult> {
         let conn = self.write_conn.lock().map_err(|_| anyhow::anyhow!("write lock poisoned"))?;
         let live: Vec<&str> = live_keys.iter().map(|k| k.as_str()).collect();
@@ -338,18 +684,22 @@ impl ContentStore for SqliteContentStore {
             });
         }
 
+        // Whatever is still queued is packed below, or was just orphaned.
+        *self.pending_pack.lock().map_err(|_| anyhow::anyhow!("pack lock poisoned"))? = PendingPack::default();
         let deleted_rows = db::delete_orphan_blobs(&conn, &live)?;
+        let packs_written = self.repack(&conn)?;
+        self.chunk_cache.clear();
 
         // VACUUM reclaims freed pages on disk. Run in a separate statement batch
         // so it executes outside of any implicit transaction.
-        if deleted_rows > 0 {
+        if deleted_rows > 0 || packs_written > 0 {
             conn.execute_batch("VACUUM")?;
         }
         conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
 
         Ok(CompactResult {
             units_scanned: 1,
-            units_rewritten: 0,
+            units_rewritten: packs_written,
             units_deleted: orphaned_keys,
             chunks_removed: deleted_rows,
             // Report the logical data bytes removed rather than the physical file
@@ -389,6 +739,107 @@ mod tests {
         }
     }
 
+    /// Chunks written before the zstd dictionary is trained (plain frames) and
+    /// after (dictionary frames) must both survive a reopen.
+    #[test]
+    fn zstd_dictionary_trained_and_persisted() {
+        let dir = TempDir::new().unwrap();
+        let key = |i: usize| ContentKey::new(format!("{i:064x}"));
+        let content = |i: usize| {
+            (0..40).map(|l| format!("fn handler_{i}_{l}(req: Request) -> Response {{ todo!() }}"))
+                .collect::<Vec<_>>().join("\n")
+        };
+        let after_dict = {
+            let store = SqliteContentStore::open(dir.path(), Some(0), None, Some(ChunkCompression::Zstd)).unwrap();
+            let mut i = 0;
+            while store.zstd.read().unwrap().encoder.is_none() {
+                assert!(i < 1000, "dictionary never trained");
+                store.put(&key(i), &content(i)).unwrap();
+                i += 1;
+            }
+            store.put(&key(i), &content(i)).unwrap();
+            i
+        };
+
+        let store = SqliteContentStore::open(dir.path(), Some(0), None, Some(ChunkCompression::Zstd)).unwrap();
+        assert_eq!(store.zstd.read().unwrap().decoders.len(), 1);
+        assert!(store.zstd.read().unwrap().encoder.is_some());
+        for i in [0, after_dict] {
+            let lines = store.get_lines(&key(i), 0, 39).unwrap().unwrap();
+            let text = lines.into_iter().map(|(_, l)| l).collect::<Vec<_>>().join("\n");
+            assert_eq!(text, content(i));
+        }
+    }
+
+    fn count(store: &SqliteContentStore, table: &str) -> i64 {
+        let conn = store.write_conn.lock().unwrap();
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |r| r.get(0)).unwrap()
+    }
+
+    fn small_blob(i: usize) -> String {
+        format!("config entry {i}\nvalue = {}\n", i * 7)
+    }
+
+    /// Small blobs end up sharing packs and read back exactly, before and
+    /// after a reopen; deleting one leaves the rest of its pack readable.
+    #[test]
+    fn small_blobs_are_packed() {
+        let dir = TempDir::new().unwrap();
+        let key = |i: usize| ContentKey::new(format!("{i:064x}"));
+        {
+            let store = SqliteContentStore::open(dir.path(), None, None, Some(ChunkCompression::Gzip))
+                .unwrap()
+                .with_pack_size_kb(1);
+            for i in 0..100 {
+                store.put(&key(i), &small_blob(i)).unwrap();
+            }
+            assert!(count(&store, "packs") >= 2);
+            assert!(count(&store, "blobs") < 100, "most blobs should have left the blobs table");
+            assert_eq!(store.get_lines(&key(3), 1, 1).unwrap().unwrap(), vec![(1, "value = 21".to_string())]);
+            store.delete(&key(3)).unwrap();
+            assert!(store.get_lines(&key(3), 0, 1).unwrap().is_none());
+            assert!(!store.contains(&key(3)).unwrap());
+        }
+
+        let store = SqliteContentStore::open(dir.path(), None, None, None).unwrap();
+        for i in (0..100).filter(|&i| i != 3) {
+            let lines = store.get_lines(&key(i), 0, 10).unwrap().unwrap();
+            let text: Vec<String> = lines.into_iter().map(|(_, l)| l).collect();
+            assert_eq!(text.join("\n"), small_blob(i).trim_end());
+            assert!(store.stored_size(&key(i)).unwrap().unwrap() > 0);
+        }
+    }
+
+    /// Compaction packs blobs stored before packing was enabled, and rebuilds
+    /// packs left mostly empty by deletions.
+    #[test]
+    fn compaction_repacks_small_blobs() {
+        let dir = TempDir::new().unwrap();
+        let key = |i: usize| ContentKey::new(format!("{i:064x}"));
+        {
+            let store = SqliteContentStore::open(dir.path(), None, None, None).unwrap().with_pack_size_kb(0);
+            for i in 0..60 {
+                store.put(&key(i), &small_blob(i)).unwrap();
+            }
+            assert_eq!(count(&store, "packs"), 0);
+        }
+
+        let store = SqliteContentStore::open(dir.path(), None, None, None).unwrap();
+        let all: HashSet<ContentKey> = (0..60).map(key).collect();
+        let r = store.compact(&all, false).unwrap();
+        assert_eq!(r.units_rewritten, 1);
+        assert_eq!((count(&store, "packs"), count(&store, "blobs")), (1, 0));
+
+        let few: HashSet<ContentKey> = (0..5).map(key).collect();
+        let r = store.compact(&few, false).unwrap();
+        assert_eq!((r.units_deleted, r.units_rewritten), (55, 1));
+        assert_eq!((count(&store, "packs"), count(&store, "packed_blobs")), (1, 5));
+        for i in 0..5 {
+            let lines = store.get_lines(&key(i), 0, 0).unwrap().unwrap();
+            assert_eq!(lines, vec![(0, format!("config entry {i}"))]);
+        }
+    }
+
     /// Regression test: an empty line that falls exactly at a chunk boundary
     /// must not be silently dropped.  Previously, `push_str("")` left `current`
     /// empty after the flush, so the next non-empty line also skipped its `\n`
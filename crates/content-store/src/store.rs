@@ -49,6 +49,12 @@ pub trait ContentStore: Send + Sync {
     /// Return `true` if a complete blob is stored for `key`.
     fn contains(&self, key: &ContentKey) -> anyhow::Result<bool>;
 
+    /// Bytes `key` takes in the store, after compression.  `None` if the
+    /// key is not stored, or the backend cannot tell.
+    fn stored_size(&self, _key: &ContentKey) -> anyhow::Result<Option<u64>> {
+        Ok(None)
+    }
+
     /// Remove blobs not in `live_keys` and compact ZIP archives.
     fn compact(
         &self,
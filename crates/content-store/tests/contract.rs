@@ -7,6 +7,7 @@
 
 use std::collections::HashSet;
 use std::sync::Arc;
+use find_common::config::ChunkCompression;
 use find_content_store::{ContentKey, ContentStore, MultiContentStore, SqliteContentStore};
 use tempfile::TempDir;
 
@@ -20,7 +21,13 @@ fn make_sqlite_store() -> (SqliteContentStore, TempDir) {
 
 fn make_sqlite_store_compressed() -> (SqliteContentStore, TempDir) {
     let dir = TempDir::new().unwrap();
-    let store = SqliteContentStore::open(dir.path(), None, None, Some(true)).unwrap();
+    let store = SqliteContentStore::open(dir.path(), None, None, Some(ChunkCompression::Gzip)).unwrap();
+    (store, dir)
+}
+
+fn make_sqlite_store_zstd() -> (SqliteContentStore, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let store = SqliteContentStore::open(dir.path(), None, None, Some(ChunkCompression::Zstd)).unwrap();
     (store, dir)
 }
 
@@ -164,6 +171,26 @@ fn tc_compact_multiple_orphans(store: &dyn ContentStore) {
     assert!(!store.contains(&k3).unwrap());
 }
 
+fn tc_overwrite_after_read_returns_new_content(store: &dyn ContentStore) {
+    // A read may populate an in-memory cache; the overwrite must not be masked by it.
+    let key = k(K1);
+    store.put(&key, "old0\nold1").unwrap();
+    assert_eq!(store.get_lines(&key, 0, 1).unwrap().unwrap()[0].1, "old0");
+    store.put_overwrite(&key, "new0\nnew1").unwrap();
+    let lines = store.get_lines(&key, 0, 1).unwrap().unwrap();
+    assert_eq!(lines, vec![(0, "new0".to_string()), (1, "new1".to_string())]);
+}
+
+fn tc_stored_size_tracks_put_and_delete(store: &dyn ContentStore) {
+    let key = k(K1);
+    assert_eq!(store.stored_size(&key).unwrap(), None);
+    store.put(&key, &"some text worth storing\n".repeat(50)).unwrap();
+    let size = store.stored_size(&key).unwrap().expect("stored");
+    assert!(size > 0);
+    store.delete(&key).unwrap();
+    assert_eq!(store.stored_size(&key).unwrap(), None);
+}
+
 // ── Macro to stamp out the suite per implementation ──────────────────────────
 
 macro_rules! contract_tests {
@@ -185,6 +212,8 @@ macro_rules! contract_tests {
             #[test] fn multi_chunk_all_lines()          { let (s,_t)=store(); tc_multi_chunk_all_lines_retrievable(&s); }
             #[test] fn get_lines_boundary()             { let (s,_t)=store(); tc_get_lines_boundary(&s); }
             #[test] fn compact_multiple_orphans()       { let (s,_t)=store(); tc_compact_multiple_orphans(&s); }
+            #[test] fn overwrite_after_read()           { let (s,_t)=store(); tc_overwrite_after_read_returns_new_content(&s); }
+            #[test] fn stored_size()                    { let (s,_t)=store(); tc_stored_size_tracks_put_and_delete(&s); }
         }
     };
 }
@@ -206,6 +235,7 @@ fn make_multi_store() -> (MultiContentStore, TempDir) {
 
 contract_tests!(sqlite_store,           make_sqlite_store());
 contract_tests!(sqlite_store_compressed, make_sqlite_store_compressed());
+contract_tests!(sqlite_store_zstd,      make_sqlite_store_zstd());
 contract_tests!(multi_store,            make_multi_store());
 
 // ── MultiContentStore-specific behaviour ─────────────────────────────────────
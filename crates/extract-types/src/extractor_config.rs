@@ -52,6 +52,11 @@ pub struct ExtractorConfig {
     /// falling back to filename-only extraction.  Maps to
     /// `scan.archives.max_7z_solid_block_mb`.  Default: 256 MB.
     pub max_7z_solid_block_mb: usize,
+    /// When true, a 7z solid block over `max_7z_solid_block_mb` whose decoder
+    /// dictionary fits within it is still decoded, single-threaded, instead of
+    /// being indexed by filename only.  Maps to
+    /// `scan.archives.stream_oversized_7z_blocks`.  Default: false.
+    pub stream_oversized_7z_blocks: bool,
     /// Glob patterns (same syntax as `scan.exclude`) applied to archive member
     /// paths.  Members whose path matches any pattern are skipped entirely —
     /// not indexed by filename, not recursed into.  Empty = no filtering.
@@ -83,6 +88,7 @@ impl Default for ExtractorConfig {
             max_temp_file_mb: 500,
             include_hidden: false,
             max_7z_solid_block_mb: 256,
+            stream_oversized_7z_blocks: false,
             exclude_patterns: vec![],
             external_dispatch: std::collections::HashMap::new(),
             ffprobe_path: None,
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 /// that `find-scan --upgrade` can selectively re-index files that were indexed
 /// by an older version of the client. Increment this when extraction logic
 /// changes in a way that produces meaningfully different output.
-pub const SCANNER_VERSION: u32 = 8;
+pub const SCANNER_VERSION: u32 = 9;
 
 // ── Reserved line number slots ────────────────────────────────────────────────
 
@@ -22,6 +22,18 @@ pub const LINE_METADATA: usize = 1;
 /// All content extracted from the file body starts at this offset.
 pub const LINE_CONTENT_START: usize = 2;
 
+// ── Section markers ───────────────────────────────────────────────────────────
+
+/// Prefix of the content line emitted before each spreadsheet sheet's rows,
+/// followed by the sheet name.  Lets the server return a row with its sheet
+/// header as context.
+pub const SHEET_MARKER: &str = "[XLSX:sheet] ";
+
+/// Prefix of the content line emitted before each presentation slide's text,
+/// followed by the 1-based slide number.  Lets the server return a whole
+/// slide as context.
+pub const SLIDE_MARKER: &str = "[PPTX:slide] ";
+
 /// A single extracted line sent from client → server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexLine {
@@ -39,7 +51,8 @@ pub struct IndexLine {
 /// or "binary" based on the actual bytes.
 pub fn detect_kind_from_ext(ext: &str) -> &'static str {
     match ext.to_lowercase().as_str() {
-        "zip" | "tar" | "gz" | "bz2" | "xz" | "tgz" | "tbz2" | "txz" | "7z" => "archive",
+        "zip" | "tar" | "gz" | "bz2" | "xz" | "tgz" | "tbz2" | "txz" | "7z"
+        | "whl" | "nupkg" | "crx" | "xpi" | "vsix" | "svgz" => "archive",
         "pdf" => "pdf",
         "jpg" | "jpeg" | "png" | "gif" | "bmp" | "ico" | "webp" | "heic"
         | "tiff" | "tif" | "raw" | "cr2" | "nef" | "arw" => "image",
@@ -85,13 +98,36 @@ pub fn detect_kind_from_ext(ext: &str) -> &'static str {
     }
 }
 
+/// Map a MIME type string to a file kind string.
+///
+/// This is the single source of truth — previously duplicated in
+/// `find-client`'s `extract.rs` and `batch.rs`.  Lives here rather than in
+/// `find-extract-dispatch` so `find-client-lib` can use it without pulling in
+/// every extractor; dispatch re-exports it.
+pub fn mime_to_kind(mime: &str) -> &'static str {
+    if mime.starts_with("image/") { return "image"; }
+    if mime.starts_with("audio/") { return "audio"; }
+    if mime.starts_with("video/") { return "video"; }
+    if mime.starts_with("text/")  { return "text"; }
+    if mime == "application/pdf"   { return "pdf"; }
+    if mime == "application/dicom" { return "dicom"; }
+    if matches!(mime,
+        "application/zip"
+        | "application/x-tar"
+        | "application/gzip"
+        | "application/x-7z-compressed"
+    ) { return "archive"; }
+    if mime == "application/octet-stream" { return "binary"; }
+    "binary"
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_detect_kind_archives() {
-        for ext in &["zip", "tar", "gz", "bz2", "xz", "tgz", "tbz2", "txz", "7z"] {
+        for ext in &["zip", "tar", "gz", "bz2", "xz", "tgz", "tbz2", "txz", "7z", "whl", "crx", "svgz"] {
             assert_eq!(detect_kind_from_ext(ext), "archive", "ext={ext}");
         }
     }
@@ -7,8 +7,8 @@ pub use extractor_config::{
     ExtractorConfig, ExternalDispatchMode, ExternalMemberDispatch,
 };
 pub use index_line::{
-    detect_kind_from_ext, IndexLine, SCANNER_VERSION,
-    LINE_PATH, LINE_METADATA, LINE_CONTENT_START,
+    detect_kind_from_ext, mime_to_kind, IndexLine, SCANNER_VERSION,
+    LINE_PATH, LINE_METADATA, LINE_CONTENT_START, SHEET_MARKER, SLIDE_MARKER,
 };
 
 /// Compute the content-store key for raw file `bytes`.
@@ -103,9 +103,15 @@ pub fn is_archive_ext(ext: &str) -> bool {
         ext.to_lowercase().as_str(),
         "zip" | "tar" | "gz" | "bz2" | "xz" | "tgz" | "tbz2" | "txz" | "7z"
         | "pages" | "numbers" | "key"
+        | "whl" | "nupkg" | "crx" | "xpi" | "vsix" | "svgz"
     )
 }
 
+/// Formats that are ZIP archives under another extension: Python wheels,
+/// NuGet packages, and browser and VS Code extensions.  A `.crx` is a ZIP
+/// behind a signature header, which the ZIP reader skips.
+const ZIP_ALIASES: &[&str] = &[".whl", ".nupkg", ".crx", ".xpi", ".vsix"];
+
 // ============================================================================
 // ARCHIVE KIND DETECTION
 // ============================================================================
@@ -131,7 +137,8 @@ fn detect_kind_from_name(name: &str) -> Option<ArchiveKind> {
     if n.ends_with(".tar.xz") || n.ends_with(".txz")   { return Some(ArchiveKind::TarXz);   }
     if n.ends_with(".tar")                              { return Some(ArchiveKind::Tar);     }
     if n.ends_with(".zip")                              { return Some(ArchiveKind::Zip);     }
-    if n.ends_with(".gz")                               { return Some(ArchiveKind::Gz);      }
+    if ZIP_ALIASES.iter().any(|ext| n.ends_with(ext))   { return Some(ArchiveKind::Zip);     }
+    if n.ends_with(".gz") || n.ends_with(".svgz")       { return Some(ArchiveKind::Gz);      }
     if n.ends_with(".bz2")                              { return Some(ArchiveKind::Bz2);     }
     if n.ends_with(".xz")                               { return Some(ArchiveKind::Xz);      }
     if n.ends_with(".7z")                               { return Some(ArchiveKind::SevenZip);}
@@ -541,6 +548,28 @@ fn sevenz_streaming(path: &Path, display_prefix: &str, cfg: &ExtractorConfig, ca
         .map(|(i, _)| i)
         .collect();
 
+    // Slow path: the decoder's memory is bounded by its dictionary window,
+    // not the block size, so an oversized block whose window fits the limit
+    // can still be streamed member by member, single-threaded.  Maps block
+    // index → window in bytes.
+    let streamed: std::collections::HashMap<usize, u64> = if cfg.stream_oversized_7z_blocks {
+        oversized
+            .iter()
+            .filter_map(|&bi| Some((bi, sevenz_block_window(&archive.blocks[bi])?)))
+            .filter(|&(_, window)| window <= max_block_bytes as u64)
+            .collect()
+    } else {
+        Default::default()
+    };
+    if !streamed.is_empty() {
+        tracing::info!(
+            "7z: '{}': streaming {} oversized solid block(s) with a bounded decoder window",
+            path.display(),
+            streamed.len(),
+        );
+    }
+    let oversized: HashSet<usize> = oversized.into_iter().filter(|bi| !streamed.contains_key(bi)).collect();
+
     if !oversized.is_empty() {
         let skipped: usize = archive
             .stream_map
@@ -610,7 +639,11 @@ fn sevenz_streaming(path: &Path, display_prefix: &str, cfg: &ExtractorConfig, ca
         // block-level total (common in solid archives where individual file
         // sizes ARE stored but not summed).  Fall back to summing individual
         // file sizes as a memory estimate so we don't skip extractable blocks.
-        let unpack_size = {
+        //
+        // A streamed oversized block needs its decoder window instead.
+        let unpack_size = if let Some(&window) = streamed.get(&block_index) {
+            window
+        } else {
             let block_size = archive.blocks[block_index].get_unpack_size();
             if block_size == 0 {
                 archive
@@ -672,8 +705,9 @@ fn sevenz_streaming(path: &Path, display_prefix: &str, cfg: &ExtractorConfig, ca
             }
         }
 
+        let threads = if streamed.contains_key(&block_index) { 1 } else { thread_count };
         let block_dec = sevenz_rust2::BlockDecoder::new(
-            thread_count,
+            threads,
             block_index,
             &archive,
             &password,
@@ -702,14 +736,46 @@ fn sevenz_streaming(path: &Path, display_prefix: &str, cfg: &ExtractorConfig, ca
     Ok(())
 }
 
+/// Memory the decoders of a 7z block need whatever its size: the largest
+/// dictionary window among its coders.  `None` when a coder's window is
+/// unknown.
+fn sevenz_block_window(block: &sevenz_rust2::Block) -> Option<u64> {
+    block
+        .coders
+        .iter()
+        .map(|c| sevenz_coder_window(c.encoder_method_id(), &c.properties))
+        .try_fold(0, |max, w| Some(max.max(w?)))
+}
+
+/// The window a coder with `method_id` and `props` decodes with: the
+/// dictionary size for LZMA and LZMA2, the model size for PPMd, and nothing
+/// for the fixed-buffer coders (copy, Deflate, BZip2, delta and branch
+/// filters).  `None` for any other coder.
+fn sevenz_coder_window(method_id: &[u8], props: &[u8]) -> Option<u64> {
+    match method_id {
+        // LZMA2: one byte encoding the dictionary size.
+        [0x21] => match *props.first()? {
+            p @ 0..=39 => Some(u64::from(2 | (p & 1)) << (p / 2 + 11)),
+            40 => Some(u64::from(u32::MAX)),
+            _ => None,
+        },
+        // LZMA, PPMd: a 32-bit size after the first properties byte.
+        [3, 1, 1] | [3, 4, 1] => Some(u64::from(u32::from_le_bytes(props.get(1..5)?.try_into().ok()?))),
+        [0] | [3] | [3, 3, ..] | [4, 1, 8] | [4, 2, 2] => Some(0),
+        _ => None,
+    }
+}
+
 /// Extract a single-file compressed archive (bare .gz, .bz2, .xz).
 /// Decompresses up to `cfg.max_content_kb` bytes and indexes the inner content.
 fn single_compressed<R: Read>(reader: R, path: &Path, cfg: &ExtractorConfig) -> Result<MemberBatch> {
-    let inner_name = path
+    let stem = path
         .file_stem()
         .and_then(|s| s.to_str())
-        .unwrap_or("file")
-        .to_string();
+        .unwrap_or("file");
+    // `drawing.svgz` holds `drawing.svg`; other wrappers just drop their extension.
+    let is_svgz = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("svgz"));
+    let inner_name = if is_svgz { format!("{stem}.svg") } else { stem.to_string() };
 
     let size_limit = cfg.max_content_kb * 1024;
     let mut bytes = Vec::new();
@@ -1096,7 +1162,8 @@ mod tests {
 
     #[test]
     fn accepts_known_extensions() {
-        for ext in &["zip", "tar", "gz", "bz2", "xz", "tgz", "tbz2", "txz", "7z"] {
+        for ext in &["zip", "tar", "gz", "bz2", "xz", "tgz", "tbz2", "txz", "7z",
+                     "whl", "nupkg", "crx", "xpi", "vsix", "svgz"] {
             let name = format!("archive.{ext}");
             let p = std::path::Path::new(&name);
             assert!(accepts(p), "expected accepts() for .{ext}");
@@ -1142,6 +1209,9 @@ mod tests {
         assert_eq!(detect_kind_from_name("foo.bz2"),     Some(ArchiveKind::Bz2));
         assert_eq!(detect_kind_from_name("foo.xz"),      Some(ArchiveKind::Xz));
         assert_eq!(detect_kind_from_name("foo.7z"),      Some(ArchiveKind::SevenZip));
+        assert_eq!(detect_kind_from_name("foo-1.0-py3-none-any.whl"), Some(ArchiveKind::Zip));
+        assert_eq!(detect_kind_from_name("foo.VSIX"),    Some(ArchiveKind::Zip));
+        assert_eq!(detect_kind_from_name("foo.svgz"),    Some(ArchiveKind::Gz));
         assert_eq!(detect_kind_from_name("foo.txt"),     None);
     }
 
@@ -1151,6 +1221,24 @@ mod tests {
         assert_eq!(detect_kind_from_name("FOO.TAR.GZ"), Some(ArchiveKind::TarGz));
     }
 
+    // ── sevenz_coder_window ─────────────────────────────────────────────────
+
+    #[test]
+    fn sevenz_coder_window_reads_dictionary_sizes() {
+        // LZMA2 dictionary byte 24 = 16 MiB, 40 = 4 GiB - 1.
+        assert_eq!(sevenz_coder_window(&[0x21], &[24]), Some(16 << 20));
+        assert_eq!(sevenz_coder_window(&[0x21], &[25]), Some(24 << 20));
+        assert_eq!(sevenz_coder_window(&[0x21], &[40]), Some(u32::MAX as u64));
+        assert_eq!(sevenz_coder_window(&[0x21], &[41]), None);
+        // LZMA: lc/lp/pb byte, then the dictionary size.
+        assert_eq!(sevenz_coder_window(&[3, 1, 1], &[0x5d, 0, 0, 0, 4]), Some(64 << 20));
+        assert_eq!(sevenz_coder_window(&[3, 1, 1], &[0x5d]), None);
+        // BCJ x86 filter and copy need no window; unknown coders are unbounded.
+        assert_eq!(sevenz_coder_window(&[3, 3, 1, 3], &[]), Some(0));
+        assert_eq!(sevenz_coder_window(&[0], &[]), Some(0));
+        assert_eq!(sevenz_coder_window(&[4, 0xf7, 0x11, 1], &[]), None);
+    }
+
     // ── has_hidden_component ────────────────────────────────────────────────
 
     #[test]
@@ -1293,6 +1381,42 @@ mod tests {
         assert!(batch.lines.iter().any(|l| l.content.contains("hello from gz")));
     }
 
+    #[test]
+    fn svgz_member_is_named_as_svg() {
+        let mut tmp = NamedTempFile::with_suffix(".svgz").unwrap();
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(b"<svg><text>hello from svgz</text></svg>\n").unwrap();
+        tmp.write_all(&enc.finish().unwrap()).unwrap();
+        let mut batches = vec![];
+        extract_streaming(tmp.path(), &default_cfg(), &mut |b| batches.push(b)).unwrap();
+        let lines = &batches[0].lines;
+        assert!(lines.iter().any(|l| l.archive_path.as_deref().is_some_and(|p| p.ends_with(".svg"))), "{lines:?}");
+        assert!(lines.iter().any(|l| l.content.contains("hello from svgz")));
+    }
+
+    #[test]
+    fn crx_signature_header_is_skipped() {
+        use std::io::Cursor;
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buf));
+            zip.start_file("manifest.json", zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"{\"name\": \"crx test extension\"}\n").unwrap();
+            zip.finish().unwrap();
+        }
+        // CRX3: magic, version, header length, header (signatures), then the ZIP.
+        let mut tmp = NamedTempFile::with_suffix(".crx").unwrap();
+        tmp.write_all(b"Cr24").unwrap();
+        tmp.write_all(&3u32.to_le_bytes()).unwrap();
+        tmp.write_all(&16u32.to_le_bytes()).unwrap();
+        tmp.write_all(&[0u8; 16]).unwrap();
+        tmp.write_all(&buf).unwrap();
+
+        let mut batches = vec![];
+        extract_streaming(tmp.path(), &default_cfg(), &mut |b| batches.push(b)).unwrap();
+        assert!(batches.iter().flat_map(|b| &b.lines).any(|l| l.content.contains("crx test extension")));
+    }
+
     #[test]
     fn single_gz_empty_content() {
         let tmp = make_gz_file(b"");
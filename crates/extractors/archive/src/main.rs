@@ -46,6 +46,8 @@ fn main() {
         server_only_exts: args.get(6)
             .and_then(|s| serde_json::from_str(s).ok())
             .unwrap_or_default(),
+        max_7z_solid_block_mb: args.get(7).and_then(|s| s.parse().ok()).unwrap_or(256),
+        stream_oversized_7z_blocks: args.get(8).is_some_and(|s| s == "true"),
         ..Default::default()
     };
 
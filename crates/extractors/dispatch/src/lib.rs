@@ -3,6 +3,7 @@ use std::path::Path;
 use anyhow::Result;
 use find_extract_types::{IndexLine, LINE_METADATA};
 use find_extract_types::ExtractorConfig;
+pub use find_extract_types::mime_to_kind;
 use tracing::warn;
 
 /// Dispatch extraction from in-memory bytes.
@@ -226,24 +227,3 @@ pub fn sniff_kind_from_bytes(bytes: &[u8]) -> &'static str {
     }
     ""
 }
-
-/// Map a MIME type string to a file kind string.
-///
-/// This is the single source of truth — previously duplicated in
-/// `find-client`'s `extract.rs` and `batch.rs`.
-pub fn mime_to_kind(mime: &str) -> &'static str {
-    if mime.starts_with("image/") { return "image"; }
-    if mime.starts_with("audio/") { return "audio"; }
-    if mime.starts_with("video/") { return "video"; }
-    if mime.starts_with("text/")  { return "text"; }
-    if mime == "application/pdf"   { return "pdf"; }
-    if mime == "application/dicom" { return "dicom"; }
-    if matches!(mime,
-        "application/zip"
-        | "application/x-tar"
-        | "application/gzip"
-        | "application/x-7z-compressed"
-    ) { return "archive"; }
-    if mime == "application/octet-stream" { return "binary"; }
-    "binary"
-}
@@ -1,7 +1,7 @@
 use std::io::Read;
 use std::path::Path;
 
-use find_extract_types::{IndexLine, LINE_METADATA, LINE_CONTENT_START};
+use find_extract_types::{IndexLine, LINE_METADATA, LINE_CONTENT_START, SHEET_MARKER, SLIDE_MARKER};
 use find_extract_types::ExtractorConfig;
 use quick_xml::events::Event;
 
@@ -40,8 +40,10 @@ pub fn extract_from_bytes(bytes: &[u8], name: &str, cfg: &ExtractorConfig) -> an
 /// Extract text from an Office document.
 ///
 /// - DOCX: paragraphs from word/document.xml + metadata from docProps/core.xml
-/// - XLSX/XLS/XLSM: rows from all sheets (via calamine)
-/// - PPTX: text runs from each slide, grouped by paragraph
+/// - XLSX/XLS/XLSM: rows from all sheets (via calamine), each sheet preceded
+///   by a `SHEET_MARKER` line
+/// - PPTX: text runs from each slide, grouped by paragraph, each slide
+///   preceded by a `SLIDE_MARKER` line
 pub fn extract(path: &Path, _cfg: &ExtractorConfig) -> anyhow::Result<Vec<IndexLine>> {
     let ext = path
         .extension()
@@ -207,6 +209,7 @@ fn extract_xlsx(path: &Path) -> anyhow::Result<Vec<IndexLine>> {
 
     for sheet_name in &sheet_names {
         if let Ok(range) = wb.worksheet_range(sheet_name) {
+            let mut marked = false;
             for row in range.rows() {
                 let cells: Vec<String> = row
                     .iter()
@@ -225,6 +228,15 @@ fn extract_xlsx(path: &Path) -> anyhow::Result<Vec<IndexLine>> {
                     .collect();
 
                 if !cells.is_empty() {
+                    if !marked {
+                        content_line += 1;
+                        lines.push(IndexLine {
+                            archive_path: None,
+                            line_number: content_line,
+                            content: format!("{SHEET_MARKER}{sheet_name}"),
+                        });
+                        marked = true;
+                    }
                     content_line += 1;
                     lines.push(IndexLine {
                         archive_path: None,
@@ -280,7 +292,7 @@ fn extract_pptx(path: &Path) -> anyhow::Result<Vec<IndexLine>> {
 
     let mut content_line = LINE_CONTENT_START - 1;
 
-    for slide_name in &slide_names {
+    for (i, slide_name) in slide_names.iter().enumerate() {
         let xml = {
             let mut entry = archive.by_name(slide_name)?;
             let mut s = String::new();
@@ -288,7 +300,16 @@ fn extract_pptx(path: &Path) -> anyhow::Result<Vec<IndexLine>> {
             s
         };
 
-        for text in parse_pptx_paragraphs(&xml) {
+        let paragraphs = parse_pptx_paragraphs(&xml);
+        if !paragraphs.is_empty() {
+            content_line += 1;
+            lines.push(IndexLine {
+                archive_path: None,
+                line_number: content_line,
+                content: format!("{SLIDE_MARKER}{}", i + 1),
+            });
+        }
+        for text in paragraphs {
             content_line += 1;
             lines.push(IndexLine {
                 archive_path: None,
@@ -623,6 +644,13 @@ mod tests {
         assert!(meta.content.contains("[PPTX:slide] 2"), "meta: {}", meta.content);
         assert!(lines.iter().any(|l| l.content.contains("First slide")), "lines: {lines:?}");
         assert!(lines.iter().any(|l| l.content.contains("Second slide")), "lines: {lines:?}");
+
+        // Each slide's text is preceded by its marker line.
+        let content: Vec<&str> = lines.iter()
+            .filter(|l| l.line_number >= LINE_CONTENT_START)
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(content, ["[PPTX:slide] 1", "First slide", "[PPTX:slide] 2", "Second slide"]);
     }
 
     #[test]
@@ -651,6 +679,9 @@ mod tests {
         assert!(meta.content.contains("[XLSX:sheet] Sheet1"), "meta: {}", meta.content);
         let all_content: String = lines.iter().map(|l| l.content.as_str()).collect::<Vec<_>>().join(" ");
         assert!(all_content.contains("Hello"), "content: {all_content}");
+        let first = lines.iter().find(|l| l.line_number == LINE_CONTENT_START)
+            .expect("expected sheet marker line");
+        assert_eq!(first.content, "[XLSX:sheet] Sheet1");
     }
 
     #[test]
@@ -201,6 +201,7 @@ fn is_binary_ext(ext: &str) -> bool {
         | "mp3" | "mp4" | "avi" | "mov" | "mkv" | "flac" | "wav" | "ogg"
         | "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx"
         | "zip" | "tar" | "gz" | "bz2" | "xz" | "7z"
+        | "whl" | "nupkg" | "crx" | "xpi" | "vsix" | "svgz"
         | "exe" | "dll" | "so" | "dylib" | "sys" | "scr" | "efi"
         | "class" | "jar" | "pyc" | "pyd"
         | "o" | "a" | "lib" | "obj" | "wasm"
@@ -0,0 +1,169 @@
+//! Python bindings for the find-anything client API (`import find_anything`).
+//!
+//! A thin wrapper over [`find_client_lib::ApiClient`]: each method blocks on
+//! the request with the GIL released, and results cross the boundary as
+//! plain dicts and lists built from the same JSON the server sends, so the
+//! Python side sees the field names documented for the HTTP API.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use find_client_lib::{load_config, ApiClient, SearchFilters};
+use find_common::api::{BulkRequest, CreateAnnotationRequest};
+use find_common::config::default_config_path;
+
+create_exception!(find_anything, FindError, PyException, "A request to the find-anything server failed.");
+
+/// A connection to a find-anything server.
+#[pyclass(module = "find_anything", frozen)]
+struct Client {
+    api: ApiClient,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Client {
+    fn with_api(api: ApiClient) -> PyResult<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| FindError::new_err(format!("starting runtime: {e}")))?;
+        Ok(Self { api, runtime })
+    }
+
+    /// Run the request `make` starts to completion with the GIL released.
+    /// The future is created on the releasing thread, so it need not be `Send`.
+    fn block_on<T: Send, F: std::future::Future<Output = anyhow::Result<T>>>(
+        &self,
+        py: Python<'_>,
+        make: impl FnOnce() -> F + Send,
+    ) -> PyResult<T> {
+        py.allow_threads(|| self.runtime.block_on(make())).map_err(|e| FindError::new_err(format!("{e:#}")))
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// Connect to the server at `url` with its bearer `token`.
+    #[new]
+    fn new(url: &str, token: &str) -> PyResult<Self> {
+        Self::with_api(ApiClient::new(url, token))
+    }
+
+    /// Connect using the server URL and token from a client config file
+    /// (default: the same file `find-scan` and `find-anything` read).
+    #[staticmethod]
+    #[pyo3(signature = (path=None))]
+    fn from_config(path: Option<String>) -> PyResult<Self> {
+        let path = path.unwrap_or_else(default_config_path);
+        let (config, _warnings) = load_config(&path).map_err(|e| FindError::new_err(format!("{e:#}")))?;
+        Self::with_api(ApiClient::new(&config.server.url, &config.server.token))
+    }
+
+    /// Names of the indexed sources.
+    fn sources(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        let sources = self.block_on(py, || self.api.get_sources())?;
+        Ok(sources.into_iter().map(|s| s.name).collect())
+    }
+
+    /// Search the index.  Returns `{"results": [...], "total": n, ...}`;
+    /// page through large result sets with `offset`.
+    #[pyo3(signature = (
+        query, mode="fuzzy", sources=Vec::new(), limit=50, offset=0,
+        kinds=Vec::new(), exts=Vec::new(), path_prefix=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        mode: &str,
+        sources: Vec<String>,
+        limit: usize,
+        offset: usize,
+        kinds: Vec<String>,
+        exts: Vec<String>,
+        path_prefix: Option<String>,
+    ) -> PyResult<PyObject> {
+        let filters = SearchFilters { kinds, exts, path_prefix, ..Default::default() };
+        let resp = self.block_on(py, || self.api.search(query, mode, &sources, limit, offset, &filters))?;
+        to_python(py, &resp)
+    }
+
+    /// The lines around `line` of a file: `{"start", "match_index", "lines", "kind"}`.
+    #[pyo3(signature = (source, path, line, window=5, archive_path=None))]
+    fn context(
+        &self,
+        py: Python<'_>,
+        source: &str,
+        path: &str,
+        line: usize,
+        window: usize,
+        archive_path: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let resp = self.block_on(py, || self.api.context(source, path, archive_path, line, window))?;
+        to_python(py, &resp)
+    }
+
+    /// Up to `limit` indexed lines of a file from line index `offset`, with
+    /// its metadata and `total_lines`.
+    #[pyo3(signature = (source, path, archive_path=None, offset=0, limit=1000))]
+    fn get_file(
+        &self,
+        py: Python<'_>,
+        source: &str,
+        path: &str,
+        archive_path: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> PyResult<PyObject> {
+        let resp = self.block_on(py, || self.api.get_file(source, path, archive_path, offset, limit))?;
+        to_python(py, &resp)
+    }
+
+    /// Submit a bulk request (a dict shaped like `POST /api/v1/bulk`'s body:
+    /// `source`, `files`, `delete_paths`, ...).  The server indexes it
+    /// asynchronously.
+    fn bulk(&self, py: Python<'_>, request: &Bound<'_, PyAny>) -> PyResult<()> {
+        let req: BulkRequest = from_python(py, request)?;
+        self.block_on(py, || self.api.bulk(&req))
+    }
+
+    /// Attach a note to a file, or to one `line` of it.  Returns the stored
+    /// annotation.
+    #[pyo3(signature = (source, path, body, line=None, author=None))]
+    fn annotate(
+        &self,
+        py: Python<'_>,
+        source: String,
+        path: String,
+        body: String,
+        line: Option<usize>,
+        author: Option<String>,
+    ) -> PyResult<PyObject> {
+        let req = CreateAnnotationRequest { source, path, line_number: line, body, author };
+        let created = self.block_on(py, || self.api.create_annotation(&req))?;
+        to_python(py, &created)
+    }
+}
+
+/// Convert through JSON, so every API type maps to dicts, lists and scalars.
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| FindError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+fn from_python<T: DeserializeOwned>(py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json: String = py.import("json")?.call_method1("dumps", (value,))?.extract()?;
+    serde_json::from_str(&json).map_err(|e| FindError::new_err(format!("invalid request: {e}")))
+}
+
+#[pymodule]
+fn find_anything(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Client>()?;
+    m.add("FindError", m.py().get_type::<FindError>())?;
+    Ok(())
+}
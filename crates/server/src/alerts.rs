@@ -111,7 +111,7 @@ fn send_smtp(
     Ok(())
 }
 
-fn read_hostname() -> String {
+pub(crate) fn read_hostname() -> String {
     std::fs::read_to_string("/etc/hostname")
         .ok()
         .map(|s| s.trim().to_string())
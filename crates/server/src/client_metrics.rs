@@ -0,0 +1,66 @@
+//! Health reports pushed by `find-scan` and `find-watch`
+//! (`/api/v1/client-metrics`).
+//!
+//! The latest report per host, tool and scanned source is kept in memory and
+//! in `client-metrics.json` in the data directory, so the stats panel still
+//! shows them after a restart.  Reports not renewed for [`RETAIN_SECS`] are
+//! dropped: the client has been retired, or the source removed.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+
+use find_common::api::ClientMetrics;
+
+/// How long a report is kept without a newer one from the same client.
+pub const RETAIN_SECS: i64 = 30 * 86_400;
+
+pub struct ClientMetricsStore {
+    path: PathBuf,
+    reports: Mutex<Vec<ClientMetrics>>,
+}
+
+impl ClientMetricsStore {
+    /// Load the reports saved in `data_dir`; a missing or unreadable file
+    /// starts empty.
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join("client-metrics.json");
+        let reports = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+                tracing::warn!("ignoring {}: {e}", path.display());
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self { path, reports: Mutex::new(reports) }
+    }
+
+    /// Store `report`, received at `now`, in place of the previous one from
+    /// the same host, tool and source.
+    pub fn record(&self, mut report: ClientMetrics, now: i64) {
+        report.received_at = now;
+        let mut reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        reports.retain(|r| key(r) != key(&report) && now - r.received_at < RETAIN_SECS);
+        reports.push(report);
+        reports.sort_by(|a, b| key(a).cmp(&key(b)));
+        if let Err(e) = save(&self.path, &reports) {
+            tracing::warn!("{e:#}");
+        }
+    }
+
+    /// Every stored report, by host, tool and source.
+    pub fn list(&self) -> Vec<ClientMetrics> {
+        self.reports.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+fn key(r: &ClientMetrics) -> (&str, &str, Option<&str>) {
+    (&r.host, &r.tool, r.scan.as_ref().map(|s| s.source.as_str()))
+}
+
+fn save(path: &Path, reports: &[ClientMetrics]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(reports)?).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("renaming {} to {}", tmp.display(), path.display()))
+}
@@ -85,8 +85,8 @@ fn save_stats(data_dir: &Path, stats: &CompactionStats) -> Result<()> {
 
 // ── Core scan ─────────────────────────────────────────────────────────────────
 
-/// Collect all distinct `content_hash` values from every source DB.
-/// These are the live keys that the content store must keep.
+/// Collect all distinct `content_hash` values from every source DB, files in
+/// the trash included.  These are the live keys that the content store must keep.
 fn collect_live_keys(data_dir: &Path) -> HashSet<ContentKey> {
     let sources_dir = data_dir.join("sources");
     let mut keys = HashSet::new();
@@ -104,7 +104,8 @@ fn collect_live_keys(data_dir: &Path) -> HashSet<ContentKey> {
             Err(_) => continue,
         };
         let mut stmt = match conn.prepare(
-            "SELECT DISTINCT file_hash FROM files WHERE file_hash IS NOT NULL",
+            "SELECT file_hash FROM files WHERE file_hash IS NOT NULL
+             UNION SELECT file_hash FROM trash WHERE file_hash IS NOT NULL",
         ) {
             Ok(s) => s,
             Err(_) => continue,
@@ -144,8 +145,9 @@ pub fn scan_wasted_space(
 /// Remove orphaned chunks from all archives via the content store.
 ///
 /// Collects live keys from all source DBs, then delegates to
-/// `content_store.compact()`.  If `dry_run` is `true`, reports what would be
-/// freed without modifying any files.
+/// `content_store.compact()`.  Unreferenced files in the originals store are
+/// pruned with the same live-key set.  If `dry_run` is `true`, reports what
+/// would be freed without modifying any files.
 pub fn compact_archives(
     data_dir: &Path,
     content_store: &Arc<dyn ContentStore>,
@@ -153,12 +155,14 @@ pub fn compact_archives(
 ) -> Result<CompactResponse> {
     let live_keys = collect_live_keys(data_dir);
     let r = content_store.compact(&live_keys, dry_run)?;
+    let (originals_removed, originals_freed) = crate::originals::prune(data_dir, &live_keys, dry_run)?;
     Ok(CompactResponse {
         units_scanned:   r.units_scanned,
         units_rewritten: r.units_rewritten,
         units_deleted:   r.units_deleted,
         chunks_removed:     r.chunks_removed,
-        bytes_freed:        r.bytes_freed,
+        bytes_freed:        r.bytes_freed + originals_freed,
+        originals_removed,
         dry_run,
     })
 }
@@ -560,6 +564,28 @@ mod tests {
             "at least one orphan removed");
     }
 
+    #[test]
+    fn collect_live_keys_keeps_content_of_trashed_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let data_dir = tmp.path();
+        let cs = open_store(data_dir);
+
+        let hash = "eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+        cs.put(&find_content_store::ContentKey::new(hash), "deleted but restorable").unwrap();
+        seed_source_db(data_dir, "src", hash);
+        let conn = crate::db::open(&data_dir.join("sources").join("src.db")).unwrap();
+        crate::db::delete_files_phase1(&conn, &["test.txt".to_string()], Some(1000)).unwrap();
+
+        compact_archives(data_dir, &cs, false).unwrap();
+        assert!(cs.contains(&find_content_store::ContentKey::new(hash)).unwrap(),
+            "trashed content should survive until purged");
+
+        crate::db::purge_trash(&conn, 2000).unwrap();
+        compact_archives(data_dir, &cs, false).unwrap();
+        assert!(!cs.contains(&find_content_store::ContentKey::new(hash)).unwrap(),
+            "purged content should be removed");
+    }
+
     #[test]
     fn collect_live_keys_ignores_non_db_files_in_sources_dir() {
         let tmp = tempfile::TempDir::new().unwrap();
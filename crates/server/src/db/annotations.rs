@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::Path;
+use std::sync::Arc;
+
+use find_common::api::Annotation;
+
+use super::pool::{DbPool, PooledConn};
+
+/// Check out a connection to `annotations.db` from `pool`, creating the DB
+/// on first use.  The schema is only set up when a new connection is opened.
+///
+/// Annotations live in their own `annotations.db` rather than in the source
+/// DBs: they are user-authored data, while a source DB is a rebuildable index
+/// that may be deleted and re-scanned.  Rows are keyed by `(source, path)` so
+/// they survive re-indexing of the file.
+pub fn checkout(pool: &Arc<DbPool>, data_dir: &Path) -> Result<PooledConn> {
+    pool.get_with(&data_dir.join("annotations.db"), open)
+}
+
+fn open(db_path: &Path) -> Result<Connection> {
+    let conn = Connection::open(db_path)
+        .with_context(|| format!("opening {}", db_path.display()))?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS annotations (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            source      TEXT NOT NULL,
+            path        TEXT NOT NULL,
+            line_number INTEGER,
+            body        TEXT NOT NULL,
+            author      TEXT,
+            created_at  INTEGER NOT NULL,
+            updated_at  INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS annotations_file ON annotations(source, path);",
+    )
+    .context("creating annotations table")?;
+    Ok(conn)
+}
+
+const SELECT_COLUMNS: &str =
+    "SELECT id, source, path, line_number, body, author, created_at, updated_at FROM annotations";
+
+fn row_to_annotation(row: &rusqlite::Row<'_>) -> rusqlite::Result<Annotation> {
+    Ok(Annotation {
+        id: row.get(0)?,
+        source: row.get(1)?,
+        path: row.get(2)?,
+        line_number: row.get::<_, Option<i64>>(3)?.map(|n| n as usize),
+        body: row.get(4)?,
+        author: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// All annotations on one file: file-level notes first, then by line.
+pub fn list_for_file(conn: &Connection, source: &str, path: &str) -> Result<Vec<Annotation>> {
+    let mut stmt = conn.prepare(&format!(
+        "{SELECT_COLUMNS} WHERE source = ?1 AND path = ?2
+         ORDER BY line_number IS NOT NULL, line_number, id"
+    ))?;
+    let rows = stmt
+        .query_map(params![source, path], row_to_annotation)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Annotations relevant to a line window of one file: file-level notes plus
+/// line notes whose line falls within `lo..=hi`.
+pub fn list_for_lines(conn: &Connection, source: &str, path: &str, lo: usize, hi: usize) -> Result<Vec<Annotation>> {
+    Ok(list_for_file(conn, source, path)?
+        .into_iter()
+        .filter(|a| a.line_number.is_none_or(|n| (lo..=hi).contains(&n)))
+        .collect())
+}
+
+pub fn get(conn: &Connection, id: i64) -> Result<Option<Annotation>> {
+    Ok(conn
+        .query_row(&format!("{SELECT_COLUMNS} WHERE id = ?1"), params![id], row_to_annotation)
+        .optional()?)
+}
+
+pub fn create(
+    conn: &Connection,
+    source: &str,
+    path: &str,
+    line_number: Option<usize>,
+    body: &str,
+    author: Option<&str>,
+) -> Result<Annotation> {
+    let now = unix_now();
+    conn.execute(
+        "INSERT INTO annotations (source, path, line_number, body, author, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+        params![source, path, line_number.map(|n| n as i64), body, author, now],
+    )
+    .context("inserting annotation")?;
+    get(conn, conn.last_insert_rowid())?.context("reading back annotation")
+}
+
+/// Replace the body of an annotation.  Returns `None` when `id` does not exist.
+pub fn update(conn: &Connection, id: i64, body: &str) -> Result<Option<Annotation>> {
+    let changed = conn
+        .execute(
+            "UPDATE annotations SET body = ?1, updated_at = ?2 WHERE id = ?3",
+            params![body, unix_now(), id],
+        )
+        .context("updating annotation")?;
+    if changed == 0 {
+        return Ok(None);
+    }
+    get(conn, id)
+}
+
+/// Returns `true` when a row was deleted.
+pub fn delete(conn: &Connection, id: i64) -> Result<bool> {
+    let deleted = conn
+        .execute("DELETE FROM annotations WHERE id = ?1", params![id])
+        .context("deleting annotation")?;
+    Ok(deleted > 0)
+}
+
+/// Remove every annotation belonging to `source` (used when the source is deleted).
+pub fn delete_for_source(conn: &Connection, source: &str) -> Result<usize> {
+    let deleted = conn
+        .execute("DELETE FROM annotations WHERE source = ?1", params![source])
+        .context("deleting source annotations")?;
+    Ok(deleted)
+}
+
+/// Move every annotation of source `from` to `to` (used when the source is renamed).
+pub fn rename_source(conn: &Connection, from: &str, to: &str) -> Result<usize> {
+    let moved = conn
+        .execute("UPDATE annotations SET source = ?2 WHERE source = ?1", params![from, to])
+        .context("renaming source annotations")?;
+    Ok(moved)
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
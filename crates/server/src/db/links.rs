@@ -112,6 +112,13 @@ pub fn sweep_expired(conn: &Connection) -> Result<usize> {
     Ok(deleted)
 }
 
+pub fn rename_source(conn: &Connection, from: &str, to: &str) -> Result<usize> {
+    let moved = conn
+        .execute("UPDATE links SET source = ?2 WHERE source = ?1", params![from, to])
+        .context("renaming source links")?;
+    Ok(moved)
+}
+
 fn unix_now() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -6,15 +6,24 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use rusqlite::{Connection, OptionalExtension, functions::FunctionFlags, params};
 
-use find_common::api::{ContextLine, FileKind, FileRecord, IndexFile, PathRename, LINE_CONTENT_START};
+use find_common::api::{
+    ContextLine, FileKind, FileRecord, IndexFile, PathRename, Provenance, LINE_CONTENT_START,
+    SHEET_MARKER, SLIDE_MARKER,
+};
+use find_common::config::TokenSettings;
 use find_common::path::{composite_like_prefix, is_composite};
 
 use find_content_store::{ContentKey, ContentStore};
 
+pub mod annotations;
 pub mod constants;
 pub mod links;
+pub mod pool;
 pub mod search;
 pub mod stats;
+pub mod term_filter;
+pub mod tokens;
+pub mod trash;
 pub mod tree;
 
 #[allow(unused_imports)]
@@ -24,12 +33,14 @@ pub use constants::{
 };
 pub use search::{
     build_doc_or_expr, document_all_lines, document_candidates, document_qualifying_ids,
-    fetch_duplicates_for_file_ids, fts_candidates, DateFilter,
+    fetch_duplicates_for_file_ids, fts_candidates, fts_count, fts_file_lines, fts_matching_paths, paths_for_ids, DateFilter,
 };
 pub use stats::{
-    do_cleanup_writes, get_files_pending_content, get_fts_row_count, get_indexing_error,
-    get_indexing_error_count, get_indexing_errors, get_scan_history, get_stats, get_stats_by_ext,
+    do_cleanup_writes, downsample_history, get_files_pending_content, get_fts_row_count,
+    get_indexing_error, get_indexing_error_count, get_indexing_errors, get_scan_history,
+    get_scan_history_since, get_space_usage, get_stats, get_stats_by_ext, prune_scan_history,
 };
+pub use trash::{list_trash, purge_trash, restore_from_trash};
 pub use tree::{expand_tree, list_dir, split_composite_path};
 
 // ── Schema ────────────────────────────────────────────────────────────────────
@@ -40,7 +51,15 @@ pub use tree::{expand_tree, list_dir, split_composite_path};
 ///      DBs; chunk metadata now lives in data_dir/content.db (find-content-store).
 /// v14: Drop file_content table; rename content_hash → file_hash in files and
 ///      duplicates tables.
-pub const SCHEMA_VERSION: i64 = 14;
+/// v15: Add the `trash` table.
+/// v16: Add extraction provenance (`extractor`, `extractor_version`,
+///      `extract_truncated`) to `files` and `trash`.
+pub const SCHEMA_VERSION: i64 = 16;
+
+/// Size the write-ahead log is cut back to once a checkpoint has copied all
+/// of it into the database.  Without a limit the file stays at the size of
+/// the largest burst of writes it ever held.
+const WAL_SIZE_LIMIT_BYTES: i64 = 64 * 1024 * 1024;
 
 pub fn open(db_path: &Path) -> Result<Connection> {
     let conn = Connection::open(db_path)
@@ -54,19 +73,22 @@ pub fn open(db_path: &Path) -> Result<Connection> {
     // safe with WAL (data is never lost on crash) and much faster than the
     // default FULL mode (syncs at WAL checkpoints rather than every commit).
     conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA cache_size = -16384;")?;
+    conn.execute_batch(&format!("PRAGMA journal_size_limit = {WAL_SIZE_LIMIT_BYTES};"))?;
     // foreign_keys must be re-enabled on every connection; PRAGMA in schema SQL
     // only runs once at creation time and does not persist across connections.
     conn.execute_batch("PRAGMA foreign_keys = ON;")?;
     register_scalar_functions(&conn)?;
 
-    let version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+    let mut version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
     if version == 0 {
         // Brand-new database — initialise the full current schema and stamp the version.
         conn.execute_batch(include_str!("../schema_v4.sql"))
             .context("initialising schema")?;
         conn.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION};"))
             .context("stamping schema version")?;
-    } else if version == 13 {
+        version = SCHEMA_VERSION;
+    }
+    if version == 13 {
         // v13 → v14: drop file_content, rename content_hash → file_hash.
         conn.execute_batch(
             "DROP TABLE IF EXISTS file_content;
@@ -75,21 +97,91 @@ pub fn open(db_path: &Path) -> Result<Connection> {
              CREATE INDEX IF NOT EXISTS files_file_hash ON files(file_hash) WHERE file_hash IS NOT NULL;
              ALTER TABLE duplicates RENAME COLUMN content_hash TO file_hash;
              CREATE INDEX IF NOT EXISTS idx_files_mtime ON files(mtime);
-             CREATE INDEX IF NOT EXISTS idx_duplicates_file_id ON duplicates(file_id);",
+             CREATE INDEX IF NOT EXISTS idx_duplicates_file_id ON duplicates(file_id);
+             PRAGMA user_version = 14;",
         ).context("migrating schema v13 → v14")?;
-        conn.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION};"))
+        version = 14;
+    }
+    if version == 14 {
+        // v14 → v15: add the trash table.  The schema file only creates
+        // what is missing, so re-running it adds just that.
+        conn.execute_batch(include_str!("../schema_v4.sql"))
+            .context("migrating schema v14 → v15")?;
+        conn.execute_batch("PRAGMA user_version = 15;")
+            .context("stamping schema version")?;
+        version = 15;
+    }
+    if version == 15 {
+        // v15 → v16: add the provenance columns.  A trash table created by
+        // the v14 → v15 step above already has them.
+        let tx = conn.unchecked_transaction()?;
+        for table in ["files", "trash"] {
+            for (column, decl) in [
+                ("extractor", "TEXT"),
+                ("extractor_version", "TEXT"),
+                ("extract_truncated", "INTEGER NOT NULL DEFAULT 0"),
+            ] {
+                if !has_column(&tx, table, column)? {
+                    tx.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))
+                        .context("migrating schema v15 → v16")?;
+                }
+            }
+        }
+        tx.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION};"))
             .context("stamping schema version")?;
-    } else if version != SCHEMA_VERSION {
+        tx.commit()?;
+        version = SCHEMA_VERSION;
+    }
+    if version != SCHEMA_VERSION {
         anyhow::bail!(
             "database schema is v{version} but this server requires v{SCHEMA_VERSION}. \
              Delete {} and re-run find-scan to rebuild.",
             db_path.display()
         );
     }
+    // FTS writes go through fa_tokens(), which must match how this DB was indexed.
+    tokens::register(&conn, tokens::Normalizer::load(&conn)?)?;
+
+    Ok(conn)
+}
 
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        rusqlite::params![table, column],
+        |r| r.get::<_, i64>(0),
+    )? > 0)
+}
+
+/// WAL size, in pages, at which a worker commit still checkpoints on its own
+/// (64 MB at the default 4 KB page size).  The worker normally checkpoints
+/// long before this; it is the backstop for when it cannot keep up.
+const WRITER_AUTOCHECKPOINT_PAGES: i64 = 16384;
+
+/// Open a source DB for the inbox worker, which checkpoints the WAL itself
+/// with [`checkpoint`] every few hundred files and after each request.
+///
+/// By default SQLite checkpoints from inside whichever commit takes the WAL
+/// past 1000 pages, so during a large scan one file's commit in every few
+/// also copies megabytes into the database.  Checkpointing between files
+/// instead keeps commits, and so the time the write lock is held, short.
+/// The automatic checkpoint is raised to [`WRITER_AUTOCHECKPOINT_PAGES`]
+/// rather than disabled, so the WAL stays bounded whatever the worker does.
+pub fn open_writer(db_path: &Path) -> Result<Connection> {
+    let conn = open(db_path)?;
+    conn.execute_batch(&format!("PRAGMA wal_autocheckpoint = {WRITER_AUTOCHECKPOINT_PAGES};"))?;
     Ok(conn)
 }
 
+/// Copy WAL frames into the database as far as open readers allow.  A
+/// passive checkpoint never waits for readers and never blocks them: frames
+/// a search's snapshot still needs stay in the WAL for the next checkpoint.
+/// Returns the WAL's size in frames and how many of them are now copied.
+pub fn checkpoint(conn: &Connection) -> Result<(i64, i64)> {
+    conn.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |r| Ok((r.get(1)?, r.get(2)?)))
+        .context("checkpointing WAL")
+}
+
 /// Open a source DB for **read-only stats queries** with a short (1 s) busy
 /// timeout.  If the DB is locked by a worker, the stats background task will
 /// just skip it and return stale / zero values rather than blocking.
@@ -122,6 +214,10 @@ pub fn register_scalar_functions(conn: &Connection) -> Result<()> {
         }
     })?;
 
+    // fa_tokens(text) → text as lines_fts indexes it; unchanged until
+    // `open` re-registers it with the DB's recorded [tokens] settings.
+    tokens::register(conn, tokens::Normalizer::default())?;
+
     Ok(())
 }
 
@@ -129,7 +225,8 @@ pub fn register_scalar_functions(conn: &Connection) -> Result<()> {
 /// Open and migrate all existing source databases in `sources_dir` at startup.
 /// This ensures any pending schema migrations are applied eagerly rather than
 /// lazily on the first request, and catches truly incompatible databases early.
-pub fn check_all_sources(sources_dir: &Path) -> Result<()> {
+/// Also warns about databases indexed with other `[tokens]` settings.
+pub fn check_all_sources(sources_dir: &Path, token_settings: &TokenSettings) -> Result<()> {
     let read_dir = match std::fs::read_dir(sources_dir) {
         Ok(rd) => rd,
         Err(_) => return Ok(()), // sources dir doesn't exist yet — nothing to check
@@ -149,6 +246,7 @@ pub fn check_all_sources(sources_dir: &Path) -> Result<()> {
             "CREATE INDEX IF NOT EXISTS idx_files_mtime ON files(mtime);
              CREATE INDEX IF NOT EXISTS idx_duplicates_file_id ON duplicates(file_id);"
         ).with_context(|| format!("ensuring indexes on {}", path.display()))?;
+        tokens::warn_if_changed(&conn, &path, token_settings)?;
     }
     Ok(())
 }
@@ -295,24 +393,122 @@ pub fn count_files(conn: &Connection) -> Result<usize> {
     Ok(n as usize)
 }
 
+/// Merge the FTS index segments and/or rebuild the file to drop free pages,
+/// then checkpoint the WAL so the space reclaimed shows on disk.  Both steps
+/// hold the write lock while they run, so indexing of the source waits.
+pub fn maintain(conn: &Connection, vacuum: bool, optimize_fts: bool) -> Result<()> {
+    if optimize_fts {
+        conn.execute("INSERT INTO lines_fts(lines_fts) VALUES('optimize')", [])
+            .context("optimizing FTS index")?;
+    }
+    if vacuum {
+        conn.execute_batch("VACUUM").context("vacuuming")?;
+    }
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .context("checkpointing WAL")?;
+    Ok(())
+}
+
+/// Queue outer files for re-extraction by the next `find-scan --upgrade`, by
+/// resetting their `scanner_version` to 0: those whose recorded extractor
+/// version `is_outdated`, and with `include_unrecorded` those indexed without
+/// provenance.  Files from external extractors record no version and are
+/// never queued; archive members are re-extracted with their archive.
+/// Returns the number of files matched, which are left unchanged if `dry_run`.
+pub fn queue_reextract(
+    conn: &Connection,
+    kind: Option<&FileKind>,
+    extractor: Option<&str>,
+    include_unrecorded: bool,
+    is_outdated: impl Fn(&str) -> bool,
+    dry_run: bool,
+) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT id, extractor, extractor_version FROM files
+         WHERE path NOT LIKE '%::%'
+           AND (?1 IS NULL OR kind = ?1)
+           AND (?2 IS NULL OR extractor = ?2)",
+    )?;
+    let ids: Vec<i64> = stmt
+        .query_map(params![kind.map(|k| k.to_string()), extractor], |r| {
+            Ok((r.get::<_, i64>(0)?, r.get::<_, Option<String>>(1)?, r.get::<_, Option<String>>(2)?))
+        })?
+        .filter_map(|row| match row {
+            Ok((id, _, Some(version))) if is_outdated(&version) => Some(Ok(id)),
+            Ok((id, None, None)) if include_unrecorded => Some(Ok(id)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<rusqlite::Result<_>>()?;
+    if !dry_run && !ids.is_empty() {
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut update = tx.prepare("UPDATE files SET scanner_version = 0 WHERE id = ?1")?;
+            for id in &ids {
+                update.execute(params![id])?;
+            }
+        }
+        tx.commit()?;
+    }
+    Ok(ids.len())
+}
+
+/// Narrows the recent-files and activity feeds.
+#[derive(Debug, Default, Clone)]
+pub struct RecentFilter {
+    /// Only entries at or after this unix timestamp (seconds).
+    pub since: Option<i64>,
+    /// Allowlist of file kinds. Empty = any kind.
+    pub kinds: Vec<FileKind>,
+}
+
+impl RecentFilter {
+    /// Apply the filter to an entry already read (e.g. a live SSE event).
+    /// An entry of unknown kind passes only when no kinds are requested.
+    pub fn matches(&self, ts: i64, kind: Option<&FileKind>) -> bool {
+        self.since.is_none_or(|since| ts >= since)
+            && (self.kinds.is_empty() || kind.is_some_and(|k| self.kinds.contains(k)))
+    }
+
+    /// SQL conditions (each prefixed with ` AND `) over the timestamp
+    /// expression `ts` and the kind column `kind`.
+    fn sql(&self, ts: &str, kind: &str, p: &mut search::ParamBinder) -> String {
+        let mut sql = String::new();
+        if let Some(since) = self.since {
+            sql += &format!(" AND {ts} >= {}", p.push(since));
+        }
+        if !self.kinds.is_empty() {
+            let phs = self.kinds.iter().map(|k| p.push(k.to_string())).collect::<Vec<_>>().join(", ");
+            sql += &format!(" AND {kind} IN ({phs})");
+        }
+        sql
+    }
+}
+
 /// Return the `limit` most recently indexed outer files (no `::` in path).
 /// `sort_by_mtime = false` orders by `COALESCE(indexed_at, mtime)` (recently indexed);
 /// `sort_by_mtime = true` orders by raw `mtime` (recently modified on disk).
-/// Returns `(path, sort_ts)` pairs.
-pub fn recent_files(conn: &Connection, limit: usize, sort_by_mtime: bool) -> Result<Vec<(String, i64)>> {
-    let sql = if sort_by_mtime {
-        "SELECT path, mtime FROM files \
-         WHERE path NOT LIKE '%::%' \
-         ORDER BY mtime DESC LIMIT ?1"
-    } else {
-        "SELECT path, COALESCE(indexed_at, mtime) FROM files \
-         WHERE path NOT LIKE '%::%' \
-         ORDER BY COALESCE(indexed_at, mtime) DESC LIMIT ?1"
-    };
-    let mut stmt = conn.prepare(sql)?;
+/// Returns `(path, sort_ts, kind)` tuples.
+pub fn recent_files(
+    conn: &Connection,
+    limit: usize,
+    sort_by_mtime: bool,
+    filter: &RecentFilter,
+) -> Result<Vec<(String, i64, FileKind)>> {
+    let ts = if sort_by_mtime { "mtime" } else { "COALESCE(indexed_at, mtime)" };
+    let mut p = search::ParamBinder::new();
+    let conditions = filter.sql(ts, "kind", &mut p);
+    let sql = format!(
+        "SELECT path, {ts}, kind FROM files \
+         WHERE path NOT LIKE '%::%'{conditions} \
+         ORDER BY {ts} DESC LIMIT {}",
+        p.push(limit as i64),
+    );
+    let mut stmt = conn.prepare(&sql)?;
     let rows = stmt
-        .query_map(params![limit as i64], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        .query_map(p.as_refs().as_slice(), |row| {
+            let kind: String = row.get(2)?;
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, FileKind::from(kind.as_str())))
         })?
         .collect::<rusqlite::Result<Vec<_>>>()?;
     Ok(rows)
@@ -364,52 +560,89 @@ pub fn log_activity(
     Ok(())
 }
 
-/// One row from the activity log: `(action, path, new_path, occurred_at)`.
-pub type ActivityRow = (String, String, Option<String>, i64);
+/// One row from the activity log: `(action, path, new_path, occurred_at, kind)`.
+/// `kind` is that of the file now at `new_path` (renames) or `path`, and
+/// `None` once the file is gone from the index (e.g. deletions).
+pub type ActivityRow = (String, String, Option<String>, i64, Option<FileKind>);
 
 /// Return the `limit` most recent activity-log entries across outer files.
+/// A kind filter drops entries whose file is no longer indexed.
 pub fn recent_activity(
     conn: &Connection,
     limit: usize,
+    filter: &RecentFilter,
 ) -> Result<Vec<ActivityRow>> {
-    let mut stmt = conn.prepare(
-        "SELECT action, path, new_path, occurred_at FROM activity_log \
-         ORDER BY occurred_at DESC LIMIT ?1",
-    )?;
+    let mut p = search::ParamBinder::new();
+    let conditions = filter.sql("a.occurred_at", "f.kind", &mut p);
+    let sql = format!(
+        "SELECT a.action, a.path, a.new_path, a.occurred_at, f.kind FROM activity_log a \
+         LEFT JOIN files f ON f.path = COALESCE(a.new_path, a.path) \
+         WHERE 1{conditions} \
+         ORDER BY a.occurred_at DESC LIMIT {}",
+        p.push(limit as i64),
+    );
+    let mut stmt = conn.prepare(&sql)?;
     let rows = stmt
-        .query_map(params![limit as i64], |row| {
+        .query_map(p.as_refs().as_slice(), |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
                 row.get::<_, Option<String>>(2)?,
                 row.get::<_, i64>(3)?,
+                row.get::<_, Option<String>>(4)?.map(|k| FileKind::from(k.as_str())),
             ))
         })?
         .collect::<rusqlite::Result<Vec<_>>>()?;
     Ok(rows)
 }
 
+/// Kind of the indexed file at `path`, if any.
+pub fn file_kind(conn: &Connection, path: &str) -> Result<Option<FileKind>> {
+    let kind: Option<String> = conn
+        .query_row("SELECT kind FROM files WHERE path = ?1", [path], |r| r.get(0))
+        .optional()?;
+    Ok(kind.map(|k| FileKind::from(k.as_str())))
+}
+
 // ── File listing (for deletion detection) ────────────────────────────────────
 
 pub fn list_files(conn: &Connection) -> Result<Vec<FileRecord>> {
     let mut stmt = conn.prepare(
-        "SELECT path, mtime, kind, scanner_version, indexed_at FROM files ORDER BY path"
+        "SELECT path, mtime, kind, scanner_version, indexed_at, size, file_hash FROM files ORDER BY path"
     )?;
     let rows = stmt
-        .query_map([], |row| {
-            let kind_str: String = row.get(2)?;
-            Ok(FileRecord {
-                path: row.get(0)?,
-                mtime: row.get(1)?,
-                kind: FileKind::from(kind_str.as_str()),
-                scanner_version: row.get::<_, u32>(3).unwrap_or(0),
-                indexed_at: row.get(4)?,
-            })
-        })?
+        .query_map([], file_record)?
         .collect::<rusqlite::Result<Vec<_>>>()?;
     Ok(rows)
 }
 
+/// The members of archive `outer` (every `outer::…` path, nested members
+/// included), for sending only the members of a changed archive that differ.
+pub fn list_archive_members(conn: &Connection, outer: &str) -> Result<Vec<FileRecord>> {
+    // ';' sorts right after ':', so this range is exactly the `outer::` prefix.
+    let mut stmt = conn.prepare(
+        "SELECT path, mtime, kind, scanner_version, indexed_at, size, file_hash FROM files
+         WHERE path >= ?1 AND path < ?2 ORDER BY path"
+    )?;
+    let rows = stmt
+        .query_map(params![format!("{outer}::"), format!("{outer}:;")], file_record)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+fn file_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<FileRecord> {
+    let kind_str: String = row.get(2)?;
+    Ok(FileRecord {
+        path: row.get(0)?,
+        mtime: row.get(1)?,
+        kind: FileKind::from(kind_str.as_str()),
+        scanner_version: row.get::<_, u32>(3).unwrap_or(0),
+        indexed_at: row.get(4)?,
+        size: row.get(5)?,
+        file_hash: row.get(6)?,
+    })
+}
+
 // ── File search (for Ctrl+P palette) ─────────────────────────────────────────
 
 pub fn search_files(conn: &Connection, q: &str, limit: usize) -> Result<Vec<FileRecord>> {
@@ -421,7 +654,7 @@ pub fn search_files(conn: &Connection, q: &str, limit: usize) -> Result<Vec<File
         let rows = stmt.query_map(params![limit as i64], |row| {
             let kind_str: String = row.get(1)?;
             Ok(FileRecord { path: row.get(0)?, mtime: 0, kind: FileKind::from(kind_str.as_str()),
-                scanner_version: 0, indexed_at: Some(0) })
+                scanner_version: 0, indexed_at: Some(0), size: None, file_hash: None })
         })?.collect::<rusqlite::Result<Vec<_>>>()?;
         return Ok(rows);
     }
@@ -432,7 +665,7 @@ pub fn search_files(conn: &Connection, q: &str, limit: usize) -> Result<Vec<File
     let rows = stmt.query_map(params![pattern, limit as i64], |row| {
         let kind_str: String = row.get(1)?;
         Ok(FileRecord { path: row.get(0)?, mtime: 0, kind: FileKind::from(kind_str.as_str()),
-            scanner_version: 0, indexed_at: Some(0) })
+            scanner_version: 0, indexed_at: Some(0), size: None, file_hash: None })
     })?.collect::<rusqlite::Result<Vec<_>>>()?;
     Ok(rows)
 }
@@ -542,9 +775,11 @@ pub struct DeleteDelta {
 /// compaction rather than immediately rewriting archives.
 ///
 /// - Clears indexing errors for the deleted paths in the same transaction.
+/// - With `trash_at`, moves the rows to the trash first, deleted at that time
+///   (see [`trash`]).
 /// - Returns a `DeleteDelta` capturing the stats of all deleted outer files
 ///   (composite archive-member paths are excluded from the delta).
-pub fn delete_files_phase1(conn: &Connection, paths: &[String]) -> Result<DeleteDelta> {
+pub fn delete_files_phase1(conn: &Connection, paths: &[String], trash_at: Option<i64>) -> Result<DeleteDelta> {
     let mut delta = DeleteDelta { files_removed: 0, size_removed: 0, by_kind: HashMap::new() };
 
     let tx = conn.unchecked_transaction()?;
@@ -566,6 +801,9 @@ pub fn delete_files_phase1(conn: &Connection, paths: &[String]) -> Result<Delete
                 e.1 += size;
             }
         }
+        if let Some(now) = trash_at {
+            trash::move_to_trash(&tx, path, now)?;
+        }
         delete_one_path_simple(&tx, path)?;
         tx.execute("DELETE FROM indexing_errors WHERE path = ?1", params![path])?;
         tx.execute(
@@ -627,12 +865,12 @@ pub fn rename_files(conn: &Connection, renames: &[PathRename]) -> Result<()> {
         let rowid0 = crate::db::encode_fts_rowid(file_id, 0);
         // Delete old FTS entry for line 0
         tx.execute(
-            "INSERT INTO lines_fts(lines_fts, rowid, content) VALUES('delete', ?1, ?2)",
+            "INSERT INTO lines_fts(lines_fts, rowid, content) VALUES('delete', ?1, fa_tokens(?2))",
             params![rowid0, rename.old_path],
         )?;
         // Insert new FTS entry for line 0
         tx.execute(
-            "INSERT INTO lines_fts(rowid, content) VALUES(?1, ?2)",
+            "INSERT INTO lines_fts(rowid, content) VALUES(?1, fa_tokens(?2))",
             params![rowid0, rename.new_path],
         )?;
 
@@ -775,6 +1013,20 @@ pub fn get_file_lines_paged(
     Ok((lines, total_count, content_unavail))
 }
 
+/// Extraction time and provenance recorded for `path`, or `(None, None)` if
+/// the file is not indexed or was indexed without them.
+pub fn get_file_provenance(conn: &Connection, path: &str) -> Result<(Option<i64>, Option<Provenance>)> {
+    let row = conn.query_row(
+        "SELECT extract_ms, extractor, extractor_version, extract_truncated FROM files WHERE path = ?1",
+        params![path],
+        |r| Ok((r.get(0)?, r.get::<_, Option<String>>(1)?, r.get(2)?, r.get(3)?)),
+    ).optional()?;
+    let Some((extract_ms, extractor, version, truncated)) = row else {
+        return Ok((None, None));
+    };
+    Ok((extract_ms, extractor.map(|extractor| Provenance { extractor, version, truncated })))
+}
+
 // ── Context ───────────────────────────────────────────────────────────────────
 
 pub fn get_context(
@@ -788,7 +1040,162 @@ pub fn get_context(
 
     match kind {
         FileKind::Image | FileKind::Audio => get_metadata_context(conn, content_store, file_path),
-        _ => get_line_context(conn, content_store, file_path, center, window),
+        _ => {
+            let unit = ContextUnit::for_file(&kind, file_path);
+            if unit != ContextUnit::Lines && center >= LINE_CONTENT_START {
+                if let Some(lines) = get_unit_context(conn, content_store, file_path, center, unit)? {
+                    return Ok(lines);
+                }
+            }
+            get_line_context(conn, content_store, file_path, center, window)
+        }
+    }
+}
+
+/// The structural unit returned as context for a match, chosen per file type
+/// so the viewer shows a meaningful block rather than a fixed line window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContextUnit {
+    /// `center ± window` lines.
+    Lines,
+    /// Spreadsheet: the sheet marker, the sheet's first (header) row and the matching row.
+    SheetRow,
+    /// Presentation: every line of the slide containing the match.
+    Slide,
+    /// Word-processor / ebook: neighbouring paragraphs up to `BLOCK_CHAR_BUDGET`.
+    ParagraphBlock,
+}
+
+/// Most lines returned for one slide or paragraph block.
+const MAX_UNIT_LINES: usize = 100;
+/// How far back to look for a sheet or slide marker before falling back to
+/// a plain line window (files indexed before markers existed have none).
+const MAX_MARKER_SCAN: usize = 10_000;
+/// Approximate characters of surrounding text in a paragraph block.
+const BLOCK_CHAR_BUDGET: usize = 1500;
+
+impl ContextUnit {
+    fn for_file(kind: &FileKind, file_path: &str) -> Self {
+        // Archive members: the innermost path decides.
+        let name = file_path.rsplit("::").next().unwrap_or(file_path);
+        let ext = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match ext.as_str() {
+            "xlsx" | "xls" | "xlsm" | "xltx" | "xltm" => Self::SheetRow,
+            "pptx" | "pptm" | "potx" | "potm" => Self::Slide,
+            _ if matches!(kind, FileKind::Document | FileKind::Epub) => Self::ParagraphBlock,
+            _ => Self::Lines,
+        }
+    }
+}
+
+/// Build structural context for `center`.  Returns `None` when the unit
+/// cannot be determined (e.g. no marker line), so the caller falls back to
+/// a line window.
+fn get_unit_context(
+    conn: &Connection,
+    content_store: &dyn ContentStore,
+    file_path: &str,
+    center: usize,
+    unit: ContextUnit,
+) -> Result<Option<Vec<ContextLine>>> {
+    let hash: Option<String> = conn.query_row(
+        "SELECT file_hash FROM files WHERE path = ?1 AND file_hash IS NOT NULL LIMIT 1",
+        params![file_path],
+        |r| r.get(0),
+    ).optional()?;
+    let Some(hash) = hash else { return Ok(None) };
+    let key = ContentKey::new(hash.as_str());
+    let read = |lo: usize, hi: usize| -> Result<Vec<ContextLine>> {
+        Ok(content_store.get_lines(&key, lo, hi)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(pos, _)| *pos >= LINE_CONTENT_START)
+            .map(|(line_number, content)| ContextLine { line_number, content })
+            .collect())
+    };
+
+    match unit {
+        ContextUnit::Lines => Ok(None),
+        ContextUnit::SheetRow => {
+            let Some(marker) = find_marker_before(&read, center, SHEET_MARKER, MAX_MARKER_SCAN)? else {
+                return Ok(None);
+            };
+            // Marker, header row, then the matching row (deduplicated when the
+            // match is the marker or the header itself).
+            let mut lines = read(marker, marker + 1)?;
+            if center > marker + 1 {
+                lines.extend(read(center, center)?);
+            }
+            Ok(Some(lines))
+        }
+        ContextUnit::Slide => {
+            let Some(marker) = find_marker_before(&read, center, SLIDE_MARKER, MAX_UNIT_LINES)? else {
+                return Ok(None);
+            };
+            let mut lines = read(marker, marker + MAX_UNIT_LINES - 1)?;
+            if let Some(end) = lines.iter().skip(1).position(|l| l.content.starts_with(SLIDE_MARKER)) {
+                lines.truncate(end + 1);
+            }
+            Ok(Some(lines))
+        }
+        ContextUnit::ParagraphBlock => {
+            let half = MAX_UNIT_LINES / 2;
+            let all = read(center.saturating_sub(half), center + half)?;
+            let Some(mid) = all.iter().position(|l| l.line_number == center) else {
+                return Ok(None);
+            };
+            // Grow outwards from the match, one paragraph each side in turn,
+            // until the character budget is spent.
+            let (mut lo, mut hi) = (mid, mid);
+            let mut chars = all[mid].content.len();
+            loop {
+                let before = lo.checked_sub(1).map(|i| all[i].content.len());
+                let after = all.get(hi + 1).map(|l| l.content.len());
+                let mut grew = false;
+                if let Some(n) = before.filter(|n| chars + n <= BLOCK_CHAR_BUDGET) {
+                    lo -= 1;
+                    chars += n;
+                    grew = true;
+                }
+                if let Some(n) = after.filter(|n| chars + n <= BLOCK_CHAR_BUDGET) {
+                    hi += 1;
+                    chars += n;
+                    grew = true;
+                }
+                if !grew {
+                    break;
+                }
+            }
+            Ok(Some(all.into_iter().skip(lo).take(hi - lo + 1).collect()))
+        }
+    }
+}
+
+/// Scan backwards from `center` (inclusive) for the nearest line starting
+/// with `prefix`, reading at most `limit` lines.
+fn find_marker_before(
+    read: &dyn Fn(usize, usize) -> Result<Vec<ContextLine>>,
+    center: usize,
+    prefix: &str,
+    limit: usize,
+) -> Result<Option<usize>> {
+    const PAGE: usize = 256;
+    let floor = center.saturating_sub(limit).max(LINE_CONTENT_START);
+    let mut hi = center;
+    loop {
+        let lo = hi.saturating_sub(PAGE - 1).max(floor);
+        let page = read(lo, hi)?;
+        if let Some(l) = page.iter().rev().find(|l| l.content.starts_with(prefix)) {
+            return Ok(Some(l.line_number));
+        }
+        if lo == floor {
+            return Ok(None);
+        }
+        hi = lo - 1;
     }
 }
 
@@ -910,6 +1317,86 @@ mod tests {
         ).unwrap_or(0) > 0
     }
 
+    // ── checkpoint ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_checkpoint_leaves_frames_readers_need() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("s.db");
+        let writer = open_writer(&db_path).unwrap();
+        let reader = open(&db_path).unwrap();
+        insert_file(&writer, "a.txt", 1, &["[PATH] a.txt", "alpha"]);
+
+        // The reader's snapshot predates the second file.
+        reader.execute_batch("BEGIN").unwrap();
+        let seen: i64 = reader.query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0)).unwrap();
+        insert_file(&writer, "b.txt", 1, &["[PATH] b.txt", "beta"]);
+
+        let (frames, copied) = checkpoint(&writer).unwrap();
+        assert!(copied < frames, "frames after the reader's snapshot stay in the WAL");
+        let still: i64 = reader.query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0)).unwrap();
+        assert_eq!(still, seen, "the reader keeps its snapshot");
+        reader.execute_batch("COMMIT").unwrap();
+
+        let (frames, copied) = checkpoint(&writer).unwrap();
+        assert_eq!(copied, frames);
+    }
+
+    #[test]
+    fn test_open_migrates_v15() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("s.db");
+        drop(open(&db_path).unwrap());
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            for table in ["files", "trash"] {
+                for column in ["extractor", "extractor_version", "extract_truncated"] {
+                    conn.execute_batch(&format!("ALTER TABLE {table} DROP COLUMN {column};")).unwrap();
+                }
+            }
+            conn.execute_batch("PRAGMA user_version = 15;").unwrap();
+        }
+
+        let conn = open(&db_path).unwrap();
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        assert!(has_column(&conn, "files", "extractor").unwrap());
+        assert!(has_column(&conn, "trash", "extract_truncated").unwrap());
+    }
+
+    #[test]
+    fn test_queue_reextract_selects_outdated_files() {
+        let conn = test_conn();
+        let add = |path: &str, kind: &str, extractor: Option<&str>, version: Option<&str>| {
+            conn.execute(
+                "INSERT INTO files (path, mtime, kind, scanner_version, extractor, extractor_version)
+                 VALUES (?1, 1, ?2, 7, ?3, ?4)",
+                params![path, kind, extractor, version],
+            ).unwrap();
+        };
+        add("old.pdf", "pdf", Some("find-extract-pdf"), Some("0.7.0"));
+        add("new.pdf", "pdf", Some("find-extract-pdf"), Some("0.8.0"));
+        add("old.txt", "text", Some("find-extract-text"), Some("0.7.0"));
+        add("ext.pdf", "pdf", Some("pdftotext"), None);
+        add("legacy.pdf", "pdf", None, None);
+        add("a.zip::old.pdf", "pdf", Some("find-extract-pdf"), Some("0.7.0"));
+        let outdated = |v: &str| v < "0.8.0";
+        let queued = |conn: &Connection| -> Vec<String> {
+            let mut stmt = conn.prepare("SELECT path FROM files WHERE scanner_version = 0 ORDER BY path").unwrap();
+            stmt.query_map([], |r| r.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap()
+        };
+
+        let pdf = FileKind::Pdf;
+        assert_eq!(queue_reextract(&conn, Some(&pdf), None, false, outdated, true).unwrap(), 1);
+        assert!(queued(&conn).is_empty(), "dry run changes nothing");
+
+        assert_eq!(queue_reextract(&conn, Some(&pdf), None, true, outdated, false).unwrap(), 2);
+        assert_eq!(queued(&conn), ["legacy.pdf", "old.pdf"]);
+
+        assert_eq!(queue_reextract(&conn, None, Some("find-extract-text"), false, outdated, false).unwrap(), 1);
+        assert_eq!(queued(&conn), ["legacy.pdf", "old.pdf", "old.txt"]);
+    }
+
     // ── delete_files_phase1 ────────────────────────────────────────────────────
 
     #[test]
@@ -918,7 +1405,7 @@ mod tests {
         let _fid = insert_file(&conn, "docs/readme.txt", 1000, &["docs/readme.txt", "hello world"]);
         assert!(file_exists(&conn, "docs/readme.txt"));
 
-        delete_files_phase1(&conn, &["docs/readme.txt".to_string()]).unwrap();
+        delete_files_phase1(&conn, &["docs/readme.txt".to_string()], None).unwrap();
 
         assert!(!file_exists(&conn, "docs/readme.txt"));
     }
@@ -927,7 +1414,7 @@ mod tests {
     fn test_delete_noop_missing() {
         let conn = test_conn();
         // Should not error when path doesn't exist.
-        delete_files_phase1(&conn, &["nonexistent.txt".to_string()]).unwrap();
+        delete_files_phase1(&conn, &["nonexistent.txt".to_string()], None).unwrap();
     }
 
     #[test]
@@ -938,13 +1425,78 @@ mod tests {
         insert_file(&conn, "archive.zip::a.txt", 1000, &["archive.zip::a.txt", "content a"]);
         insert_file(&conn, "archive.zip::b.txt", 1000, &["archive.zip::b.txt", "content b"]);
 
-        delete_files_phase1(&conn, &["archive.zip".to_string()]).unwrap();
+        delete_files_phase1(&conn, &["archive.zip".to_string()], None).unwrap();
 
         assert!(!file_exists(&conn, "archive.zip"));
         assert!(!file_exists(&conn, "archive.zip::a.txt"));
         assert!(!file_exists(&conn, "archive.zip::b.txt"));
     }
 
+    // ── trash ──────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_trashed_file_hidden_then_restored() {
+        let conn = test_conn();
+        insert_file(&conn, "old/archive.zip", 1000, &["old/archive.zip"]);
+        insert_file(&conn, "old/archive.zip::a.txt", 1000, &["old/archive.zip::a.txt", "quarterly figures"]);
+        insert_file(&conn, "keep.txt", 1000, &["keep.txt", "other text"]);
+
+        delete_files_phase1(&conn, &["old/archive.zip".to_string()], Some(5000)).unwrap();
+        assert!(!file_exists(&conn, "old/archive.zip"));
+        assert_eq!(fts_live_count(&conn, "quarterly"), 0, "trashed content is hidden from search");
+
+        let (files, total) = list_trash(&conn, Some("old"), 7, 10, 0).unwrap();
+        assert_eq!(total, 1, "members are not listed");
+        assert_eq!(files[0].path, "old/archive.zip");
+        assert_eq!(files[0].purge_at, 5000 + 7 * 86_400);
+
+        let outcome = restore_from_trash(&conn, &[], Some("old/")).unwrap();
+        assert_eq!((outcome.restored, outcome.skipped), (1, 0));
+        assert!(file_exists(&conn, "old/archive.zip::a.txt"));
+        assert_eq!(fts_live_count(&conn, "quarterly"), 1, "restored content is found without re-indexing");
+        assert_eq!(list_trash(&conn, None, 7, 10, 0).unwrap().1, 0);
+    }
+
+    #[test]
+    fn test_restore_skips_paths_indexed_again() {
+        let conn = test_conn();
+        insert_file(&conn, "notes.txt", 1000, &["notes.txt", "first version"]);
+        delete_files_phase1(&conn, &["notes.txt".to_string()], Some(5000)).unwrap();
+        insert_file(&conn, "notes.txt", 2000, &["notes.txt", "second version"]);
+
+        let outcome = restore_from_trash(&conn, &["notes.txt".to_string()], None).unwrap();
+        assert_eq!((outcome.restored, outcome.skipped), (0, 1));
+        assert_eq!(fts_live_count(&conn, "second"), 1);
+    }
+
+    #[test]
+    fn test_purge_trash_removes_expired_entries() {
+        let conn = test_conn();
+        insert_file(&conn, "a.txt", 1000, &["a.txt"]);
+        insert_file(&conn, "b.txt", 1000, &["b.txt"]);
+        delete_files_phase1(&conn, &["a.txt".to_string()], Some(1000)).unwrap();
+        delete_files_phase1(&conn, &["b.txt".to_string()], Some(3000)).unwrap();
+
+        assert_eq!(purge_trash(&conn, 2000).unwrap(), 1);
+        let (files, _) = list_trash(&conn, None, 0, 10, 0).unwrap();
+        assert_eq!(files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["b.txt"]);
+    }
+
+    // ── list_archive_members ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_list_archive_members_only_that_archive() {
+        let conn = test_conn();
+        insert_file(&conn, "a.zip", 1000, &["a.zip"]);
+        insert_file(&conn, "a.zip::x.txt", 1000, &["a.zip::x.txt"]);
+        insert_file(&conn, "a.zip::inner.zip::y.txt", 1000, &["a.zip::inner.zip::y.txt"]);
+        insert_file(&conn, "a.zip2::z.txt", 1000, &["a.zip2::z.txt"]);
+        insert_file(&conn, "b.zip::x.txt", 1000, &["b.zip::x.txt"]);
+
+        let paths: Vec<String> = list_archive_members(&conn, "a.zip").unwrap().into_iter().map(|f| f.path).collect();
+        assert_eq!(paths, ["a.zip::inner.zip::y.txt", "a.zip::x.txt"]);
+    }
+
     // ── FTS round-trip ─────────────────────────────────────────────────────────
 
     #[test]
@@ -962,7 +1514,7 @@ mod tests {
         insert_file(&conn, "src/lib.rs", 1000, &["src/lib.rs", "unique_token_xyz"]);
         assert_eq!(fts_live_count(&conn, "unique_token_xyz"), 1);
 
-        delete_files_phase1(&conn, &["src/lib.rs".to_string()]).unwrap();
+        delete_files_phase1(&conn, &["src/lib.rs".to_string()], None).unwrap();
 
         // Lines are CASCADE deleted; FTS rowids are orphaned but JOIN returns nothing.
         assert_eq!(fts_live_count(&conn, "unique_token_xyz"), 0);
@@ -1076,7 +1628,7 @@ mod tests {
             100,
         ).unwrap();
 
-        let rows = recent_activity(&conn, 10).unwrap();
+        let rows = recent_activity(&conn, 10, &RecentFilter::default()).unwrap();
         assert_eq!(rows.len(), 1);
         assert_eq!(rows[0].0, "added");
         assert_eq!(rows[0].1, "new_file.txt");
@@ -1097,7 +1649,7 @@ mod tests {
             100,
         ).unwrap();
 
-        let rows = recent_activity(&conn, 10).unwrap();
+        let rows = recent_activity(&conn, 10, &RecentFilter::default()).unwrap();
         assert_eq!(rows.len(), 4);
         let actions: Vec<&str> = rows.iter().map(|r| r.0.as_str()).collect();
         assert!(actions.contains(&"added"));
@@ -1123,7 +1675,7 @@ mod tests {
             100,
         ).unwrap();
 
-        let rows = recent_activity(&conn, 10).unwrap();
+        let rows = recent_activity(&conn, 10, &RecentFilter::default()).unwrap();
         assert_eq!(rows.len(), 1);
         assert_eq!(rows[0].1, "normal.txt");
     }
@@ -1141,10 +1693,41 @@ mod tests {
             ).unwrap();
         }
 
-        let rows = recent_activity(&conn, 100).unwrap();
+        let rows = recent_activity(&conn, 100, &RecentFilter::default()).unwrap();
         assert_eq!(rows.len(), 5);
     }
 
+    #[test]
+    fn test_recent_filters_by_since_and_kind() {
+        let conn = test_conn();
+        for (path, kind, mtime) in [("a.txt", "text", 100), ("b.pdf", "pdf", 200), ("c.rs", "code", 300)] {
+            conn.execute(
+                "INSERT INTO files (path, mtime, kind, indexed_at) VALUES (?1, ?2, ?3, ?2)",
+                params![path, mtime, kind],
+            ).unwrap();
+        }
+        log_activity(&conn, 150, &["a.txt".to_string()], &[], &[], &[], 100).unwrap();
+        log_activity(&conn, 250, &["b.pdf".to_string()], &[], &["gone.pdf".to_string()], &[], 100).unwrap();
+
+        let filter = RecentFilter { since: Some(200), kinds: vec![] };
+        let paths: Vec<String> = recent_files(&conn, 10, true, &filter).unwrap().into_iter().map(|r| r.0).collect();
+        assert_eq!(paths, ["c.rs", "b.pdf"]);
+
+        let filter = RecentFilter { since: None, kinds: vec![FileKind::Pdf, FileKind::Text] };
+        let rows = recent_files(&conn, 10, false, &filter).unwrap();
+        assert_eq!(rows.iter().map(|r| r.0.as_str()).collect::<Vec<_>>(), ["b.pdf", "a.txt"]);
+        assert_eq!(rows[0].2, FileKind::Pdf);
+
+        // Deleted files have no kind, so a kind filter drops them.
+        let rows = recent_activity(&conn, 10, &RecentFilter::default()).unwrap();
+        assert_eq!(rows.len(), 3);
+        let rows = recent_activity(&conn, 10, &filter).unwrap();
+        assert_eq!(rows.iter().map(|r| r.1.as_str()).collect::<Vec<_>>(), ["b.pdf", "a.txt"]);
+        assert_eq!(rows[1].4, Some(FileKind::Text));
+        let filter = RecentFilter { since: Some(200), kinds: vec![] };
+        assert_eq!(recent_activity(&conn, 10, &filter).unwrap().len(), 2);
+    }
+
     // ── update_last_scan / get_last_scan ──────────────────────────────────────
 
     #[test]
@@ -0,0 +1,175 @@
+//! Per-database pool of open connections for the HTTP handlers.
+//!
+//! [`open`](super::open) configures pragmas, registers scalar functions and
+//! checks (or migrates) the schema, which costs more than a small query.
+//! Handlers therefore check connections out of [`DbPool`] instead; an idle
+//! connection is reused, and a new one is opened only when none is free.
+//! Besides the source DBs, the pool holds `annotations.db` connections (see
+//! [`annotations::checkout`](super::annotations::checkout)), which are
+//! opened by their own function.
+//!
+//! The inbox worker keeps opening its own writer connections: it holds one
+//! per request, so the open cost is amortised, and its request timeout
+//! interrupts that connection directly.
+//!
+//! Idle connections keep the database file open, so anything that deletes or
+//! replaces a source DB must call [`DbPool::evict`] first.  Connections that
+//! were checked out across an eviction are closed when returned.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use rusqlite::Connection;
+
+/// Idle connections kept per source DB.  Searches use one per source, so
+/// this covers several concurrent requests without holding many handles.
+const MAX_IDLE_PER_SOURCE: usize = 8;
+
+#[derive(Default)]
+struct Slot {
+    idle: Vec<Connection>,
+    /// Bumped by `evict`; connections opened under an older generation are
+    /// not returned to the pool.
+    generation: u64,
+}
+
+#[derive(Default)]
+pub struct DbPool {
+    slots: Mutex<HashMap<PathBuf, Slot>>,
+}
+
+impl DbPool {
+    /// Check out a connection to `db_path`.  Like [`open`](super::open),
+    /// this creates the database if it does not exist.
+    pub fn get(self: &Arc<Self>, db_path: &Path) -> Result<PooledConn> {
+        self.get_with(db_path, super::open)
+    }
+
+    /// Check out a connection to `db_path`, calling `open` when no idle one
+    /// is free.  Every caller for a given path must pass the same `open`.
+    pub fn get_with(self: &Arc<Self>, db_path: &Path, open: fn(&Path) -> Result<Connection>) -> Result<PooledConn> {
+        let (idle, generation) = {
+            let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = slots.entry(db_path.to_path_buf()).or_default();
+            // The file was removed behind our back: the idle handles point at
+            // the unlinked inode, so start afresh.
+            if !slot.idle.is_empty() && !db_path.exists() {
+                slot.idle.clear();
+                slot.generation += 1;
+            }
+            (slot.idle.pop(), slot.generation)
+        };
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open(db_path)?,
+        };
+        Ok(PooledConn {
+            conn: Some(conn),
+            path: db_path.to_path_buf(),
+            generation,
+            pool: Arc::clone(self),
+        })
+    }
+
+    /// Close the idle connections to `db_path` and refuse the return of any
+    /// currently checked out.  Call before deleting or replacing the file.
+    pub fn evict(&self, db_path: &Path) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(slot) = slots.get_mut(db_path) {
+            slot.idle.clear();
+            slot.generation += 1;
+        }
+    }
+
+    fn put(&self, path: &Path, generation: u64, conn: Connection) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(slot) = slots.get_mut(path) {
+            if slot.generation == generation && slot.idle.len() < MAX_IDLE_PER_SOURCE {
+                slot.idle.push(conn);
+            }
+        }
+    }
+}
+
+/// A checked-out connection; returned to its pool on drop.
+pub struct PooledConn {
+    conn: Option<Connection>,
+    path: PathBuf,
+    generation: u64,
+    pool: Arc<DbPool>,
+}
+
+impl Deref for PooledConn {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection present until drop")
+    }
+}
+
+impl DerefMut for PooledConn {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection present until drop")
+    }
+}
+
+impl Drop for PooledConn {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else { return };
+        // A connection left inside a transaction is in an unknown state.
+        if !conn.is_autocommit() {
+            return;
+        }
+        // Per-request hooks (e.g. the search time budget) must not leak into
+        // the next user.
+        let _ = conn.progress_handler(0, None::<fn() -> bool>);
+        self.pool.put(&self.path, self.generation, conn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn_id(conn: &Connection) -> i64 {
+        // Each connection has its own temp schema; tag it to tell them apart.
+        conn.execute_batch("CREATE TEMP TABLE IF NOT EXISTS tag (id INTEGER)").unwrap();
+        conn.query_row("SELECT count(*) FROM tag", [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn idle_connections_are_reused_until_evicted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("s.db");
+        let pool = Arc::new(DbPool::default());
+
+        let conn = pool.get(&db_path).unwrap();
+        conn_id(&conn);
+        conn.execute("INSERT INTO temp.tag VALUES (1)", []).unwrap();
+        drop(conn);
+        assert_eq!(conn_id(&pool.get(&db_path).unwrap()), 1, "same connection reused");
+
+        // Checked out across an eviction: closed on return, not pooled.
+        let held = pool.get(&db_path).unwrap();
+        pool.evict(&db_path);
+        drop(held);
+        assert_eq!(conn_id(&pool.get(&db_path).unwrap()), 0, "fresh connection after evict");
+    }
+
+    #[test]
+    fn connection_in_transaction_is_discarded() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("s.db");
+        let pool = Arc::new(DbPool::default());
+
+        let conn = pool.get(&db_path).unwrap();
+        conn_id(&conn);
+        conn.execute("INSERT INTO temp.tag VALUES (1)", []).unwrap();
+        conn.execute_batch("BEGIN").unwrap();
+        drop(conn);
+        assert_eq!(conn_id(&pool.get(&db_path).unwrap()), 0);
+    }
+}
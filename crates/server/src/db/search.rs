@@ -3,13 +3,14 @@ use std::collections::{HashMap, HashSet};
 use anyhow::Result;
 use rusqlite::{Connection, params};
 
-use find_common::api::FileKind;
+use find_common::api::{FileKind, SearchSort};
 
 use super::split_composite_path;
-use super::{SQL_FTS_FILE_ID, SQL_FTS_FILENAME_ONLY, SQL_FTS_LINE_NUMBER};
+use super::{decode_fts_rowid, encode_fts_rowid, MAX_LINES_PER_FILE, SQL_FTS_FILE_ID, SQL_FTS_FILENAME_ONLY, SQL_FTS_LINE_NUMBER};
 
-/// Combined search filter: optional date range (mtime), optional kind allowlist,
-/// optional path prefix, and optional filename-only restriction.
+/// Combined search filter: optional date range (mtime), optional kind and
+/// extension allowlists, optional size bounds, optional path prefix, path and
+/// extension exclusions, and optional filename-only restriction.
 #[derive(Debug, Clone, Default)]
 pub struct DateFilter {
     pub from: Option<i64>,
@@ -21,31 +22,92 @@ pub struct DateFilter {
     /// When set, restrict results to files whose path equals this prefix or
     /// starts with `<prefix>/`.  Already normalised (no leading/trailing slashes).
     pub path_prefix: Option<String>,
+    /// Allowlist of file extensions, lowercase without the dot. Empty = any.
+    pub exts: Vec<String>,
+    /// Size bounds in bytes, inclusive.  Files with no recorded size (some
+    /// archive members) never match when either is set.
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    /// Files whose path contains any of these substrings are excluded.
+    pub exclude_paths: Vec<String>,
+    /// Files with any of these extensions (lowercase, no dot) are excluded.
+    pub exclude_exts: Vec<String>,
+    /// Which candidates [`fts_candidates`] keeps when more than its limit
+    /// match: the first in this order (any order for `Score`).
+    pub sort: SearchSort,
+    /// When set, [`fts_candidates`] returns only rows after this FTS rowid,
+    /// in rowid order (overriding `sort`), so that every match can be read
+    /// in batches: start from 0 and continue from the last row's
+    /// `encode_fts_rowid(file_id, line_number)`.
+    pub after_rowid: Option<i64>,
 }
 
 impl DateFilter {
     pub fn is_active(&self) -> bool {
         self.from.is_some() || self.to.is_some() || !self.kinds.is_empty() || self.path_prefix.is_some()
+            || !self.exts.is_empty() || self.min_size.is_some() || self.max_size.is_some()
+            || !self.exclude_paths.is_empty() || !self.exclude_exts.is_empty()
+    }
+
+    /// `ORDER BY …` for `sort` over the `files` table aliased `f`.
+    fn order_clause(&self) -> &'static str {
+        if self.after_rowid.is_some() {
+            return "ORDER BY lines_fts.rowid";
+        }
+        match self.sort {
+            SearchSort::Score => "",
+            SearchSort::Mtime => "ORDER BY f.mtime DESC",
+            SearchSort::Path => "ORDER BY f.path, line_number",
+            SearchSort::Size => "ORDER BY f.size IS NULL, f.size DESC",
+            // Hit counts are only known once candidates are grouped by file.
+            SearchSort::Hits => "",
+        }
+    }
+
+    /// `AND …` clauses for the extension, size and exclusion filters.  `col`
+    /// qualifies the `files` columns: `"f."` or `""`.
+    fn ext_size_clause(&self, p: &mut ParamBinder, col: &str) -> String {
+        let mut sql = String::new();
+        if !self.exts.is_empty() {
+            let likes = self.exts.iter()
+                .map(|e| format!("{col}path LIKE {}", p.push(format!("%.{e}"))))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            sql.push_str(&format!("AND ({likes}) "));
+        }
+        if let Some(min) = self.min_size {
+            sql.push_str(&format!("AND {col}size >= {} ", p.push(min)));
+        }
+        if let Some(max) = self.max_size {
+            sql.push_str(&format!("AND {col}size <= {} ", p.push(max)));
+        }
+        for e in &self.exclude_exts {
+            sql.push_str(&format!("AND {col}path NOT LIKE {} ", p.push(format!("%.{e}"))));
+        }
+        for x in &self.exclude_paths {
+            sql.push_str(&format!("AND instr({col}path, {}) = 0 ", p.push(x.clone())));
+        }
+        sql
     }
 }
 
 // ── ParamBinder ───────────────────────────────────────────────────────────────
 
 /// Accumulates SQL parameters and auto-numbers their `?N` placeholders.
-struct ParamBinder {
+pub(super) struct ParamBinder {
     params: Vec<Box<dyn rusqlite::ToSql>>,
 }
 
 impl ParamBinder {
-    fn new() -> Self { Self { params: vec![] } }
+    pub(super) fn new() -> Self { Self { params: vec![] } }
 
     /// Append a value and return its `?N` placeholder string.
-    fn push(&mut self, v: impl rusqlite::ToSql + 'static) -> String {
+    pub(super) fn push(&mut self, v: impl rusqlite::ToSql + 'static) -> String {
         self.params.push(Box::new(v));
         format!("?{}", self.params.len())
     }
 
-    fn as_refs(&self) -> Vec<&dyn rusqlite::ToSql> {
+    pub(super) fn as_refs(&self) -> Vec<&dyn rusqlite::ToSql> {
         self.params.iter().map(|p| p.as_ref()).collect()
     }
 }
@@ -63,6 +125,8 @@ pub struct CandidateRow {
     pub content: String,
     pub mtime: i64,
     pub size: Option<i64>,
+    /// When the file was last indexed (Unix seconds).
+    pub indexed_at: Option<i64>,
     /// The file's row ID in the `files` table (used for duplicate lookup).
     pub file_id: i64,
 }
@@ -76,20 +140,99 @@ pub(crate) fn build_fts_query(query: &str, phrase: bool) -> Option<String> {
         }
         Some(format!("\"{}\"", query.replace('"', "\"\"")))
     } else {
-        let terms: Vec<String> = query
-            .split(|c: char| !c.is_alphanumeric() && c != '_')
-            .filter(|w| w.len() >= 3)
-            .map(|w| w.to_string())
-            .collect();
+        FuzzyQuery::parse(query).fts_expr()
+    }
+}
+
+/// A fuzzy-mode query split into its parts.  `"Quoted phrases"` must appear
+/// as written and `term*` must start a word; the remaining words stay fuzzy.
+/// An unclosed quote runs to the end of the query.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FuzzyQuery {
+    pub phrases: Vec<String>,
+    /// Prefix terms without their trailing `*`.
+    pub prefixes: Vec<String>,
+    /// The remaining words, space-separated.
+    pub fuzzy: String,
+}
+
+impl FuzzyQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = FuzzyQuery::default();
+        let mut words: Vec<&str> = Vec::new();
+        for (i, part) in query.split('"').enumerate() {
+            if i % 2 == 1 {
+                let phrase = part.trim();
+                if !phrase.is_empty() {
+                    parsed.phrases.push(phrase.to_string());
+                }
+                continue;
+            }
+            for word in part.split_whitespace() {
+                match word.strip_suffix('*').map(|w| w.trim_end_matches('*')) {
+                    Some(stem) if !stem.is_empty() => parsed.prefixes.push(stem.to_string()),
+                    Some(_) => {}
+                    None => words.push(word),
+                }
+            }
+        }
+        parsed.fuzzy = words.join(" ");
+        parsed
+    }
+
+    /// True when the query has no phrases or prefix terms.
+    pub fn is_plain(&self) -> bool {
+        self.phrases.is_empty() && self.prefixes.is_empty()
+    }
+
+    /// FTS5 expression requiring every part.  Phrases and prefix terms are
+    /// trigram substring matches; [`literal_match`](Self::literal_match)
+    /// checks word starts and parts too short for the index.
+    pub fn fts_expr(&self) -> Option<String> {
+        let literals = self.phrases.iter().chain(&self.prefixes)
+            .filter(|s| s.chars().count() >= 3)
+            .map(|s| format!("\"{}\"", s.replace('"', "\"\"")));
+        let terms: Vec<String> = literals.chain(fts_terms(&self.fuzzy)).collect();
         if terms.is_empty() {
             return None;
         }
         Some(terms.join(" AND "))
     }
+
+    /// The query without its phrase and prefix syntax, for the fuzzy scorer.
+    pub fn scorer_text(&self) -> String {
+        let parts = self.phrases.iter().chain(&self.prefixes).map(String::as_str);
+        parts.chain(std::iter::once(self.fuzzy.as_str()).filter(|f| !f.is_empty())).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Whether `text` contains every phrase and has a word starting with
+    /// every prefix term.
+    pub fn literal_match(&self, text: &str, case_sensitive: bool) -> bool {
+        let fold = |s: &str| if case_sensitive { s.to_string() } else { s.to_lowercase() };
+        let text = fold(text);
+        self.phrases.iter().all(|p| text.contains(&fold(p)))
+            && self.prefixes.iter().all(|stem| {
+                let stem = fold(stem);
+                text.match_indices(&stem)
+                    .any(|(i, _)| !matches!(text[..i].chars().next_back(), Some(c) if c.is_alphanumeric()))
+            })
+    }
+}
+
+/// The words of a non-phrase query that become FTS terms (all required).
+/// The CJK bigram mark counts as part of a word.
+pub(crate) fn fts_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != super::tokens::CJK_MARK)
+        .filter(|w| w.len() >= 3)
+        .map(|w| w.to_string())
+        .collect()
 }
 
 /// Count FTS5 matches, capped at `limit`.
 /// When `date` is active or `filename_only` is set, adds JOINs and WHERE clauses.
+/// Only the FTS pre-filter is applied, so for regex and case-sensitive
+/// searches this is an upper bound.
 pub fn fts_count(conn: &Connection, query: &str, limit: usize, phrase: bool, date: DateFilter) -> Result<usize> {
     let Some(fts_query) = build_fts_query(query, phrase) else {
         return Ok(0);
@@ -119,6 +262,14 @@ pub fn fts_count(conn: &Connection, query: &str, limit: usize, phrase: bool, dat
         let phs = date.kinds.iter().map(|k| p.push(k.to_string())).collect::<Vec<_>>().join(", ");
         format!("AND f.kind IN ({phs})")
     };
+    let path_prefix_clause = if let Some(ref prefix) = date.path_prefix {
+        let eq_ph   = p.push(prefix.clone());
+        let like_ph = p.push(format!("{prefix}/%"));
+        format!("AND (f.path = {eq_ph} OR f.path LIKE {like_ph})")
+    } else {
+        String::new()
+    };
+    let ext_size_clause = date.ext_size_clause(&mut p, "f.");
 
     let sql = format!(
         "SELECT count(*) FROM (
@@ -128,6 +279,8 @@ pub fn fts_count(conn: &Connection, query: &str, limit: usize, phrase: bool, dat
              WHERE lines_fts MATCH {fts_ph}
                AND f.mtime BETWEEN {from_ph} AND {to_ph}
                {kind_clause}
+               {path_prefix_clause}
+               {ext_size_clause}
                {filename_clause}
              LIMIT {limit_ph}
          )"
@@ -137,6 +290,106 @@ pub fn fts_count(conn: &Connection, query: &str, limit: usize, phrase: bool, dat
     Ok(count as usize)
 }
 
+/// Distinct paths (composite for archive members) of files with an FTS5
+/// match, capped at `limit`, in path order.
+pub fn fts_matching_paths(conn: &Connection, query: &str, limit: usize, phrase: bool, date: DateFilter) -> Result<Vec<String>> {
+    let Some(fts_query) = build_fts_query(query, phrase) else {
+        return Ok(vec![]);
+    };
+    let filename_clause = if date.filename_only { &format!("AND {SQL_FTS_FILENAME_ONLY}") } else { "" };
+
+    let mut p = ParamBinder::new();
+    let fts_ph   = p.push(fts_query);
+    let limit_ph = p.push(limit as i64);
+    let from_ph  = p.push(date.from.unwrap_or(i64::MIN));
+    let to_ph    = p.push(date.to.unwrap_or(i64::MAX));
+    let kind_clause = if date.kinds.is_empty() {
+        String::new()
+    } else {
+        let phs = date.kinds.iter().map(|k| p.push(k.to_string())).collect::<Vec<_>>().join(", ");
+        format!("AND f.kind IN ({phs})")
+    };
+    let path_prefix_clause = if let Some(ref prefix) = date.path_prefix {
+        let eq_ph   = p.push(prefix.clone());
+        let like_ph = p.push(format!("{prefix}/%"));
+        format!("AND (f.path = {eq_ph} OR f.path LIKE {like_ph})")
+    } else {
+        String::new()
+    };
+    let ext_size_clause = date.ext_size_clause(&mut p, "f.");
+
+    let sql = format!(
+        "SELECT path FROM (
+             SELECT DISTINCT f.path AS path
+             FROM lines_fts
+             JOIN files f ON f.id = {SQL_FTS_FILE_ID}
+             WHERE lines_fts MATCH {fts_ph}
+               AND f.mtime BETWEEN {from_ph} AND {to_ph}
+               {kind_clause}
+               {path_prefix_clause}
+               {ext_size_clause}
+               {filename_clause}
+             LIMIT {limit_ph}
+         ) ORDER BY path"
+    );
+    let refs = p.as_refs();
+    let mut stmt = conn.prepare(&sql)?;
+    let paths = stmt.query_map(refs.as_slice(), |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(paths)
+}
+
+/// Paths of the files in `ids`, capped at `limit`, in path order.
+pub fn paths_for_ids(conn: &Connection, ids: &HashSet<i64>, limit: usize) -> Result<Vec<String>> {
+    let ids: Vec<i64> = ids.iter().copied().collect();
+    let mut paths = Vec::new();
+    // Chunked to stay under SQLite's bound-parameter limit.
+    for chunk in ids.chunks(500) {
+        let mut p = ParamBinder::new();
+        let id_phs = chunk.iter().map(|&id| p.push(id)).collect::<Vec<_>>().join(", ");
+        let mut stmt = conn.prepare(&format!("SELECT path FROM files WHERE id IN ({id_phs})"))?;
+        let refs = p.as_refs();
+        paths.extend(stmt.query_map(refs.as_slice(), |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<_>>>()?);
+    }
+    paths.sort();
+    paths.truncate(limit);
+    Ok(paths)
+}
+
+/// `(file_id, line_number)` of the lines of each file in `file_ids` that the
+/// FTS index matches for `query`, excluding the path row, at most `limit`
+/// rows in all.  Each file is read through its own rowid range, so this is
+/// cheap for a page of results however common the query is.
+pub fn fts_file_lines(
+    conn: &Connection,
+    query: &str,
+    phrase: bool,
+    file_ids: &[i64],
+    limit: usize,
+) -> Result<Vec<(i64, i64)>> {
+    let Some(fts_query) = build_fts_query(query, phrase) else {
+        return Ok(vec![]);
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SQL_FTS_LINE_NUMBER} FROM lines_fts
+         WHERE lines_fts MATCH ?1 AND lines_fts.rowid BETWEEN ?2 AND ?3
+         LIMIT ?4"
+    ))?;
+    let mut lines = Vec::new();
+    for &file_id in file_ids {
+        let remaining = limit.saturating_sub(lines.len());
+        if remaining == 0 {
+            break;
+        }
+        let first = encode_fts_rowid(file_id, 1);
+        let last = encode_fts_rowid(file_id, MAX_LINES_PER_FILE - 1);
+        let rows = stmt.query_map(params![fts_query, first, last, remaining as i64], |row| row.get::<_, i64>(0))?;
+        for line in rows {
+            lines.push((file_id, line?));
+        }
+    }
+    Ok(lines)
+}
+
 /// FTS5 trigram pre-filter. Returns up to `limit` candidate rows.
 /// Content is intentionally left empty; callers that need content must fetch it separately.
 pub fn fts_candidates(
@@ -169,14 +422,23 @@ pub fn fts_candidates(
                 let phs = date.kinds.iter().map(|k| p.push(k.to_string())).collect::<Vec<_>>().join(", ");
                 format!("AND f.kind IN ({phs})")
             };
+            let ext_size_clause = date.ext_size_clause(&mut p, "f.");
+            // Batches continue from the last file returned.
+            let (cursor_clause, order_clause) = match date.after_rowid {
+                Some(after) => (format!("AND f.id > {}", p.push(decode_fts_rowid(after).0)), "ORDER BY f.id"),
+                None => (String::new(), date.order_clause()),
+            };
             // Return the filename row (line_number=0) for each matching file.
             let sql = format!(
-                "SELECT f.path, f.kind, 0 AS line_number, f.id, f.mtime, f.size
+                "SELECT f.path, f.kind, 0 AS line_number, f.id, f.mtime, f.size, f.indexed_at
                  FROM files f
                  WHERE (f.path = {eq_ph} OR f.path LIKE {like_ph})
                    AND f.mtime BETWEEN {from_ph} AND {to_ph}
                    {kind_clause}
+                   {ext_size_clause}
                    {filename_clause}
+                   {cursor_clause}
+                 {order_clause}
                  LIMIT {limit_ph}"
             );
             let refs = p.as_refs();
@@ -189,15 +451,16 @@ pub fn fts_candidates(
                     row.get::<_, i64>(3)?,
                     row.get::<_, i64>(4)?,
                     row.get::<_, Option<i64>>(5)?,
+                    row.get::<_, Option<i64>>(6)?,
                 ))
             })?.collect::<rusqlite::Result<_>>()?;
             let mut results = Vec::with_capacity(raw.len());
-            for (file_path, file_kind, file_id, mtime, size) in raw {
+            for (file_path, file_kind, file_id, mtime, size, indexed_at) in raw {
                 let (fp, ap) = split_composite_path(&file_path);
                 results.push(CandidateRow {
                     file_path: fp, file_kind, archive_path: ap,
                     line_number: 0, content: String::new(),
-                    mtime, size, file_id,
+                    mtime, size, indexed_at, file_id,
                 });
             }
             return Ok(results);
@@ -212,6 +475,7 @@ pub fn fts_candidates(
         file_id: i64,
         mtime: i64,
         size: Option<i64>,
+        indexed_at: Option<i64>,
     }
 
     let map_row = |row: &rusqlite::Row<'_>| -> rusqlite::Result<RawRow> {
@@ -223,12 +487,14 @@ pub fn fts_candidates(
             file_id:     row.get(3)?,
             mtime:       row.get(4)?,
             size:        row.get(5)?,
+            indexed_at:  row.get(6)?,
         })
     };
 
     let filename_clause = if date.filename_only { &format!("AND {SQL_FTS_FILENAME_ONLY}") } else { "" };
+    let order_clause = date.order_clause();
 
-    let raw: Vec<RawRow> = if date.is_active() || date.filename_only {
+    let raw: Vec<RawRow> = if date.is_active() || date.filename_only || date.after_rowid.is_some() {
         let from = date.from.unwrap_or(i64::MIN);
         let to = date.to.unwrap_or(i64::MAX);
 
@@ -250,17 +516,25 @@ pub fn fts_candidates(
         } else {
             String::new()
         };
+        let ext_size_clause = date.ext_size_clause(&mut p, "f.");
+        let cursor_clause = match date.after_rowid {
+            Some(after) => format!("AND lines_fts.rowid > {}", p.push(after)),
+            None => String::new(),
+        };
 
         let sql = format!(
             "SELECT f.path, f.kind, {SQL_FTS_LINE_NUMBER} AS line_number,
-                    f.id, f.mtime, f.size
+                    f.id, f.mtime, f.size, f.indexed_at
              FROM lines_fts
              JOIN files f ON f.id = {SQL_FTS_FILE_ID}
              WHERE lines_fts MATCH {fts_ph}
                AND f.mtime BETWEEN {from_ph} AND {to_ph}
                {kind_clause}
                {path_prefix_clause}
+               {ext_size_clause}
                {filename_clause}
+               {cursor_clause}
+             {order_clause}
              LIMIT {limit_ph}"
         );
         let refs = p.as_refs();
@@ -270,10 +544,11 @@ pub fn fts_candidates(
     } else {
         let mut stmt = conn.prepare(&format!(
             "SELECT f.path, f.kind, {SQL_FTS_LINE_NUMBER} AS line_number,
-                    f.id, f.mtime, f.size
+                    f.id, f.mtime, f.size, f.indexed_at
              FROM lines_fts
              JOIN files f ON f.id = {SQL_FTS_FILE_ID}
              WHERE lines_fts MATCH ?1
+             {order_clause}
              LIMIT ?2",
         ))?;
         let rows = stmt.query_map(params![fts_query, limit as i64], map_row)?
@@ -292,6 +567,7 @@ pub fn fts_candidates(
             content:     String::new(),
             mtime:       row.mtime,
             size:        row.size,
+            indexed_at:  row.indexed_at,
             file_id:     row.file_id,
         });
     }
@@ -372,9 +648,10 @@ pub fn document_candidates(
         } else {
             String::new()
         };
+        let ext_size_clause = date.ext_size_clause(&mut p, "");
 
         let sql = format!(
-            "SELECT id FROM files WHERE id IN ({id_phs}) AND mtime BETWEEN {from_ph} AND {to_ph} {kind_clause} {path_prefix_clause}"
+            "SELECT id FROM files WHERE id IN ({id_phs}) AND mtime BETWEEN {from_ph} AND {to_ph} {kind_clause} {path_prefix_clause} {ext_size_clause}"
         );
         let mut stmt = conn.prepare(&sql)?;
         let refs = p.as_refs();
@@ -401,7 +678,7 @@ pub fn document_candidates(
     {
         let sql = format!(
             "SELECT f.path, f.kind, {SQL_FTS_LINE_NUMBER} AS line_number,
-                    f.id, f.mtime, f.size
+                    f.id, f.mtime, f.size, f.indexed_at
              FROM lines_fts
              JOIN files f ON f.id = {SQL_FTS_FILE_ID}
              WHERE lines_fts MATCH ?1
@@ -426,6 +703,7 @@ pub fn document_candidates(
                 content:     String::new(),
                 mtime:       row.get(4)?,
                 size:        row.get(5)?,
+                indexed_at:  row.get(6)?,
                 file_id,
             });
             if file_order.len() >= limit { break; }
@@ -511,8 +789,9 @@ pub fn document_qualifying_ids(
         } else {
             String::new()
         };
+        let ext_size_clause = date.ext_size_clause(&mut p, "");
         let sql = format!(
-            "SELECT id FROM files WHERE id IN ({id_phs}) AND mtime BETWEEN {from_ph} AND {to_ph} {kind_clause} {path_prefix_clause}"
+            "SELECT id FROM files WHERE id IN ({id_phs}) AND mtime BETWEEN {from_ph} AND {to_ph} {kind_clause} {path_prefix_clause} {ext_size_clause}"
         );
         let mut stmt = conn.prepare(&sql)?;
         let refs = p.as_refs();
@@ -538,7 +817,7 @@ pub fn document_all_lines(
     let fetch_limit = (total_limit * per_file_limit * 2).max(50_000) as i64;
     let sql = format!(
         "SELECT f.path, f.kind, {SQL_FTS_LINE_NUMBER} AS line_number,
-                f.id, f.mtime, f.size
+                f.id, f.mtime, f.size, f.indexed_at
          FROM lines_fts
          JOIN files f ON f.id = {SQL_FTS_FILE_ID}
          WHERE lines_fts MATCH ?1
@@ -573,6 +852,7 @@ pub fn document_all_lines(
             content:     String::new(),
             mtime:       row.get(4)?,
             size:        row.get(5)?,
+            indexed_at:  row.get(6)?,
             file_id,
         });
         if file_order.len() >= total_limit
@@ -760,6 +1040,72 @@ mod tests {
         assert_eq!(results[0].file_kind, FileKind::Pdf);
     }
 
+    #[test]
+    fn fts_candidates_ext_and_size_filter() {
+        let conn = test_conn();
+        for (path, size) in [("a.RS", 100), ("b.rs", 5000), ("c.txt", 100)] {
+            let id = insert_inline_file(&conn, path, 1000, "text", &[
+                (0, &format!("[PATH] {path}")),
+                (1, "shared needle text"),
+            ]);
+            conn.execute("UPDATE files SET size = ?1 WHERE id = ?2", rusqlite::params![size, id]).unwrap();
+        }
+
+        let filter = DateFilter { exts: vec!["rs".into()], ..Default::default() };
+        let mut paths: Vec<_> = fts_candidates(&conn, "needle", 100, false, filter).unwrap()
+            .into_iter().map(|r| r.file_path).collect();
+        paths.sort();
+        assert_eq!(paths, ["a.RS", "b.rs"]);
+
+        let filter = DateFilter { exts: vec!["rs".into()], max_size: Some(1000), ..Default::default() };
+        let results = fts_candidates(&conn, "needle", 100, false, filter).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "a.RS");
+
+        let filter = DateFilter { min_size: Some(1000), ..Default::default() };
+        assert_eq!(fts_count(&conn, "needle", 100, false, filter).unwrap(), 1);
+    }
+
+    #[test]
+    fn fts_candidates_exclusion_filters() {
+        let conn = test_conn();
+        for path in ["src/main.rs", "src/test_util/fix.rs", "Cargo.LOCK", "docs/a_b.md", "docs/axb.md"] {
+            insert_inline_file(&conn, path, 1000, "text", &[
+                (0, &format!("[PATH] {path}")),
+                (1, "shared needle text"),
+            ]);
+        }
+        let paths = |filter: DateFilter| {
+            let mut paths: Vec<_> = fts_candidates(&conn, "needle", 100, false, filter).unwrap()
+                .into_iter().map(|r| r.file_path).collect();
+            paths.sort();
+            paths
+        };
+
+        let filter = DateFilter { exclude_exts: vec!["lock".into()], exclude_paths: vec!["test_".into()], ..Default::default() };
+        assert_eq!(paths(filter), ["docs/a_b.md", "docs/axb.md", "src/main.rs"]);
+
+        // Substrings match literally: `_` is not a wildcard.
+        let filter = DateFilter { exclude_paths: vec!["a_b".into(), "src/".into()], ..Default::default() };
+        assert_eq!(paths(filter.clone()), ["Cargo.LOCK", "docs/axb.md"]);
+        assert_eq!(fts_count(&conn, "needle", 100, false, filter).unwrap(), 2);
+    }
+
+    #[test]
+    fn fts_matching_paths_lists_each_file_once() {
+        let conn = test_conn();
+        insert_inline_file(&conn, "b.txt", 1000, "text", &[(0, "[PATH] b.txt"), (1, "needle one"), (2, "needle two")]);
+        insert_inline_file(&conn, "a.zip::x.txt", 1000, "text", &[(0, "[PATH] a.zip::x.txt"), (1, "needle")]);
+        insert_inline_file(&conn, "docs/c.txt", 1000, "text", &[(0, "[PATH] docs/c.txt"), (1, "haystack")]);
+
+        let paths = fts_matching_paths(&conn, "needle", 100, false, DateFilter::default()).unwrap();
+        assert_eq!(paths, ["a.zip::x.txt", "b.txt"]);
+        assert_eq!(fts_count(&conn, "needle", 100, false, DateFilter::default()).unwrap(), 3);
+
+        let filter = DateFilter { path_prefix: Some("docs".into()), ..Default::default() };
+        assert_eq!(fts_count(&conn, "needle", 100, false, filter).unwrap(), 0);
+    }
+
     #[test]
     fn fts_candidates_filename_only_restricts_to_line_zero() {
         let conn = test_conn();
@@ -795,6 +1141,32 @@ mod tests {
         assert_eq!(results.len(), 3);
     }
 
+    #[test]
+    fn fts_candidates_after_rowid_pages_through_every_match() {
+        let conn = test_conn();
+
+        for i in 0..5i64 {
+            insert_inline_file(&conn, &format!("file_{i}.txt"), 1000 + i, "text", &[
+                (0, &format!("[PATH] file_{i}.txt")),
+                (1, ""),
+                (2, "common content term here"),
+                (3, "more common content"),
+            ]);
+        }
+
+        let mut seen = Vec::new();
+        let mut after = 0;
+        loop {
+            let filter = DateFilter { after_rowid: Some(after), ..Default::default() };
+            let batch = fts_candidates(&conn, "common content", 3, false, filter).unwrap();
+            let Some(last) = batch.last() else { break };
+            after = encode_fts_rowid(last.file_id, last.line_number as i64);
+            seen.extend(batch.iter().map(|c| (c.file_id, c.line_number)));
+        }
+        assert_eq!(seen.len(), 10, "every matching line exactly once");
+        assert!(seen.windows(2).all(|w| w[0] < w[1]), "in rowid order");
+    }
+
     // ── build_fts_query ──────────────────────────────────────────────────────
 
     #[test]
@@ -848,6 +1220,26 @@ mod tests {
         assert!(q.contains("test") && q.contains("query"));
     }
 
+    #[test]
+    fn fts_fuzzy_honours_phrases_and_prefixes() {
+        let q = FuzzyQuery::parse("\"quarterly report\" budg* draft");
+        assert_eq!(q.phrases, vec!["quarterly report"]);
+        assert_eq!(q.prefixes, vec!["budg"]);
+        assert_eq!(q.fuzzy, "draft");
+        assert_eq!(q.fts_expr().as_deref(), Some("\"quarterly report\" AND \"budg\" AND draft"));
+        assert_eq!(q.scorer_text(), "quarterly report budg draft");
+        assert_eq!(build_fts_query("\"unclosed phrase", false).as_deref(), Some("\"unclosed phrase\""));
+    }
+
+    #[test]
+    fn fuzzy_literal_match_checks_word_starts() {
+        let q = FuzzyQuery::parse("\"Sales Report\" budg*");
+        assert!(q.literal_match("the sales report and budget", false));
+        assert!(!q.literal_match("the sales report and rebudgeted", false), "prefix must start a word");
+        assert!(!q.literal_match("report sales budget", false), "phrase words must be adjacent");
+        assert!(!q.literal_match("the sales report and budget", true), "case-sensitive phrase");
+    }
+
     // ── document_candidates ──────────────────────────────────────────────────
 
     #[test]
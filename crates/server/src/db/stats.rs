@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use rusqlite::{Connection, params};
 use find_content_store::{ContentKey, ContentStore};
 
-use find_common::api::{ExtStat, FileKind, IndexingError, IndexingFailure, KindStats, ScanHistoryPoint};
+use find_common::api::{ExtStat, FileKind, IndexingError, IndexingFailure, KindStats, ScanHistoryPoint, SpaceStat};
 
 // ── Stats ─────────────────────────────────────────────────────────────────────
 
@@ -70,6 +70,58 @@ pub fn get_stats_by_ext(conn: &Connection) -> Result<Vec<ExtStat>> {
     Ok(rows)
 }
 
+/// Index space used by a source, from [`get_space_usage`].
+#[derive(Default)]
+pub struct SpaceUsage {
+    pub content_bytes: u64,
+    pub by_kind: Vec<SpaceStat>,
+    /// The `top_dirs` largest top-level directories.
+    pub by_dir: Vec<SpaceStat>,
+}
+
+/// Add up the stored size of every file's content by kind and by top-level
+/// directory.  Content shared by several files of the source is counted
+/// once, for the first of them; archive members count under their own kind
+/// and their archive's directory.
+pub fn get_space_usage(conn: &Connection, content_store: &dyn ContentStore, top_dirs: usize) -> Result<SpaceUsage> {
+    let mut stmt = conn.prepare("SELECT path, kind, COALESCE(size, 0), file_hash FROM files")?;
+    let mut rows = stmt.query([])?;
+
+    let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut by_kind: HashMap<String, SpaceStat> = HashMap::new();
+    let mut by_dir: HashMap<String, SpaceStat> = HashMap::new();
+    let mut content_bytes = 0u64;
+    while let Some(row) = rows.next()? {
+        let path: String = row.get(0)?;
+        let kind: String = row.get(1)?;
+        let size: i64 = row.get(2)?;
+        let hash: Option<String> = row.get(3)?;
+        let stored = match hash {
+            Some(hash) if seen.insert(hash.clone()) => {
+                content_store.stored_size(&ContentKey::new(hash.as_str())).unwrap_or(None).unwrap_or(0)
+            }
+            _ => 0,
+        };
+        content_bytes += stored;
+        let dir = find_common::path::composite_outer(&path).split_once('/').map_or("", |(dir, _)| dir);
+        for (map, name) in [(&mut by_kind, kind.as_str()), (&mut by_dir, dir)] {
+            let stat = map.entry(name.to_string()).or_insert_with(|| SpaceStat { name: name.to_string(), ..Default::default() });
+            stat.files += 1;
+            stat.size += size;
+            stat.content_bytes += stored;
+        }
+    }
+
+    let sorted = |map: HashMap<String, SpaceStat>| {
+        let mut stats: Vec<SpaceStat> = map.into_values().collect();
+        stats.sort_by(|a, b| b.content_bytes.cmp(&a.content_bytes).then_with(|| a.name.cmp(&b.name)));
+        stats
+    };
+    let mut by_dir = sorted(by_dir);
+    by_dir.truncate(top_dirs);
+    Ok(SpaceUsage { content_bytes, by_kind: sorted(by_kind), by_dir })
+}
+
 /// Snapshot the current totals into the scan_history table.
 pub fn append_scan_history(conn: &Connection, scanned_at: i64) -> Result<()> {
     let (total_files, total_size, by_kind) = get_stats(conn)?;
@@ -124,6 +176,88 @@ pub fn get_scan_history(conn: &Connection, limit: usize) -> Result<Vec<ScanHisto
     Ok(rows)
 }
 
+/// Return all scan history points with `scanned_at >= since`, oldest first.
+pub fn get_scan_history_since(conn: &Connection, since: i64) -> Result<Vec<ScanHistoryPoint>> {
+    let mut stmt = conn.prepare(
+        "SELECT scanned_at, total_files, total_size
+         FROM scan_history WHERE scanned_at >= ?1 ORDER BY scanned_at ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok(ScanHistoryPoint {
+                scanned_at:  row.get(0)?,
+                total_files: row.get::<_, i64>(1)? as usize,
+                total_size:  row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+/// Reduce `points` (oldest first) to at most `max_points` by splitting the
+/// covered time range into equal-width buckets and keeping the last point in
+/// each.  The newest point is always retained.
+pub fn downsample_history(points: Vec<ScanHistoryPoint>, max_points: usize) -> Vec<ScanHistoryPoint> {
+    if max_points == 0 || points.len() <= max_points {
+        return points;
+    }
+    let first = points[0].scanned_at;
+    let last = points[points.len() - 1].scanned_at;
+    let span = (last - first).max(1);
+    let buckets = max_points as i64;
+
+    let mut out: Vec<ScanHistoryPoint> = Vec::with_capacity(max_points);
+    let mut current_bucket: Option<i64> = None;
+    for p in points {
+        let bucket = ((p.scanned_at - first) * buckets / span).min(buckets - 1);
+        if current_bucket == Some(bucket) {
+            // Same bucket — later point replaces the earlier one.
+            if let Some(slot) = out.last_mut() { *slot = p; }
+        } else {
+            current_bucket = Some(bucket);
+            out.push(p);
+        }
+    }
+    out
+}
+
+/// Apply the scan history retention policy.
+///
+/// Points older than `retention_days` are deleted (0 = never).  Points older
+/// than `full_resolution_days` are thinned to the last scan of each UTC day.
+/// Returns the number of rows deleted.
+pub fn prune_scan_history(
+    conn: &Connection,
+    now: i64,
+    retention_days: u64,
+    full_resolution_days: u64,
+) -> Result<usize> {
+    const DAY: i64 = 86_400;
+    let mut deleted = 0usize;
+
+    if retention_days > 0 {
+        let cutoff = now - retention_days as i64 * DAY;
+        deleted += conn.execute(
+            "DELETE FROM scan_history WHERE scanned_at < ?1",
+            params![cutoff],
+        )?;
+    }
+
+    let thin_cutoff = now - full_resolution_days as i64 * DAY;
+    deleted += conn.execute(
+        "DELETE FROM scan_history
+         WHERE scanned_at < ?1
+           AND id NOT IN (
+               SELECT MAX(id) FROM scan_history
+               WHERE scanned_at < ?1
+               GROUP BY scanned_at / ?2
+           )",
+        params![thin_cutoff, DAY],
+    )?;
+
+    Ok(deleted)
+}
+
 // ── Indexing errors ───────────────────────────────────────────────────────────
 
 /// Insert or update indexing errors. On conflict (same path), updates the error
@@ -182,6 +316,7 @@ pub fn clear_errors_for_paths(conn: &Connection, paths: &[String]) -> Result<()>
 /// extra write per request on the connection used for file/delete writes.
 ///
 /// - `clear_paths`: indexing-error rows to delete (successfully indexed files).
+///   Their trash entries, from an earlier deletion, go too.
 ///   Deleted-path errors are handled inside `delete_files` and must not be
 ///   passed here to avoid duplicating the DELETE.
 /// - `indexing_failures`: client- and server-side failures to record.
@@ -204,8 +339,10 @@ pub fn do_cleanup_writes(
 
     if !clear_paths.is_empty() {
         let mut stmt = tx.prepare_cached("DELETE FROM indexing_errors WHERE path = ?1")?;
+        let mut trash = tx.prepare_cached("DELETE FROM trash WHERE path = ?1")?;
         for path in clear_paths {
             stmt.execute(params![path])?;
+            trash.execute(params![path])?;
         }
     }
 
@@ -418,4 +555,68 @@ mod tests {
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM indexing_errors", [], |r| r.get(0)).unwrap();
         assert_eq!(count, 0);
     }
+
+    // ── Scan history ──────────────────────────────────────────────────────────
+
+    fn insert_history(conn: &Connection, scanned_at: i64, total_files: i64) {
+        conn.execute(
+            "INSERT INTO scan_history (scanned_at, total_files, total_size, by_kind)
+             VALUES (?1, ?2, 0, '{}')",
+            params![scanned_at, total_files],
+        ).unwrap();
+    }
+
+    fn point(scanned_at: i64) -> ScanHistoryPoint {
+        ScanHistoryPoint { scanned_at, total_files: 0, total_size: 0 }
+    }
+
+    #[test]
+    fn test_downsample_history_keeps_short_series() {
+        let pts: Vec<_> = (0..5).map(point).collect();
+        assert_eq!(downsample_history(pts, 10).len(), 5);
+    }
+
+    #[test]
+    fn test_downsample_history_caps_points_and_keeps_newest() {
+        let pts: Vec<_> = (0..1000).map(|i| point(i * 60)).collect();
+        let out = downsample_history(pts, 50);
+        assert!(out.len() <= 50, "got {} points", out.len());
+        assert_eq!(out.last().unwrap().scanned_at, 999 * 60);
+        assert!(out.windows(2).all(|w| w[0].scanned_at < w[1].scanned_at));
+    }
+
+    #[test]
+    fn test_prune_scan_history_applies_retention_and_thinning() {
+        let conn = test_conn();
+        let day = 86_400;
+        let now = 100 * day;
+
+        // Beyond retention (10 days): deleted.
+        insert_history(&conn, now - 20 * day, 1);
+        // Between retention and full-resolution window: thinned to one per day.
+        insert_history(&conn, now - 8 * day + 10, 2);
+        insert_history(&conn, now - 8 * day + 20, 3);
+        // Within full-resolution window (5 days): all kept.
+        insert_history(&conn, now - day + 10, 4);
+        insert_history(&conn, now - day + 20, 5);
+
+        let deleted = prune_scan_history(&conn, now, 10, 5).unwrap();
+        assert_eq!(deleted, 2);
+
+        let files: Vec<usize> = get_scan_history_since(&conn, 0).unwrap()
+            .into_iter().map(|p| p.total_files).collect();
+        assert_eq!(files, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_prune_scan_history_zero_retention_keeps_old_points() {
+        let conn = test_conn();
+        let day = 86_400;
+        let now = 1000 * day;
+        insert_history(&conn, now - 900 * day, 1);
+        insert_history(&conn, now - 800 * day, 2);
+
+        prune_scan_history(&conn, now, 0, 30).unwrap();
+        assert_eq!(get_scan_history_since(&conn, 0).unwrap().len(), 2);
+    }
 }
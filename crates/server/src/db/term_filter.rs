@@ -0,0 +1,271 @@
+//! Per-source trigram filter that lets a search skip sources which cannot
+//! match.
+//!
+//! `lines_fts` uses the trigram tokenizer, so a row matches a query only if
+//! every trigram of the query's literal terms is in the index.  Each source
+//! keeps a bitmap with one bit per ASCII trigram, case-folded as FTS5 folds
+//! it (128³ bits = 256 KiB).  When a query trigram's bit is clear the source
+//! has no matching row, and the search does not open it.  Trigrams with a
+//! non-ASCII character are not tracked and never rule a source out.
+//!
+//! Bits are only ever set: deleted content leaves its trigrams behind, which
+//! costs precision but never a result.
+//!
+//! The bitmap is saved in the source DB's `term_filter` table, and
+//! `meta.term_filter` marks the saved copy as covering everything indexed.
+//! The worker adds a request's trigrams in memory, clears that mark, and only
+//! then writes FTS rows; `save` copies the bitmap after taking the write
+//! lock.  A marked copy is therefore never missing committed content.  An
+//! unmarked or missing copy (a crash, or a DB from an older server) is
+//! rebuilt in the background from the FTS vocabulary; until then the source
+//! is always searched.  Loading never writes: the worker is the only writer
+//! of a source DB, and saves the rebuilt filter after its next request.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, TransactionBehavior};
+
+/// One bit per trigram of 7-bit characters.
+const WORDS: usize = 128 * 128 * 128 / 64;
+
+/// Value of `meta.term_filter` for a complete saved copy.  Change it when
+/// the bitmap layout changes so old copies are rebuilt.
+const FORMAT: &str = "ascii-trigram-v1";
+
+/// Bitmap indices of the ASCII trigrams in `text`.  Byte windows that touch
+/// a multi-byte character contain a non-ASCII byte and are skipped.
+fn trigrams(text: &str) -> impl Iterator<Item = usize> + '_ {
+    text.as_bytes()
+        .windows(3)
+        .filter(|w| w.is_ascii())
+        .map(|w| {
+            let [a, b, c] = [w[0], w[1], w[2]].map(|b| b.to_ascii_lowercase() as usize);
+            (a << 14) | (b << 7) | c
+        })
+}
+
+pub struct SourceFilter {
+    words: Box<[AtomicU64]>,
+    /// Set once the bitmap covers everything already indexed (loaded or
+    /// rebuilt); until then `may_match` always answers true.
+    ready: AtomicBool,
+    /// The bitmap has bits the DB's saved copy lacks.
+    unsaved: AtomicBool,
+}
+
+impl SourceFilter {
+    fn new() -> Self {
+        Self {
+            words: (0..WORDS).map(|_| AtomicU64::new(0)).collect(),
+            ready: AtomicBool::new(false),
+            unsaved: AtomicBool::new(false),
+        }
+    }
+
+    /// Record the trigrams of text about to be indexed.  Returns true if any
+    /// were new, in which case the caller must `mark_unsaved` before writing.
+    pub fn add(&self, text: &str) -> bool {
+        let mut added = false;
+        for t in trigrams(text) {
+            let (word, bit) = (&self.words[t / 64], 1u64 << (t % 64));
+            if word.load(Ordering::Acquire) & bit == 0 {
+                word.fetch_or(bit, Ordering::AcqRel);
+                added = true;
+            }
+        }
+        if added {
+            self.unsaved.store(true, Ordering::Release);
+        }
+        added
+    }
+
+    /// False only when some trigram of one of `literals` never occurs in the
+    /// source, so an FTS query requiring all of them cannot match.
+    pub fn may_match(&self, literals: &[String]) -> bool {
+        if !self.ready.load(Ordering::Acquire) {
+            return true;
+        }
+        literals.iter().all(|l| {
+            trigrams(l).all(|t| self.words[t / 64].load(Ordering::Acquire) & (1u64 << (t % 64)) != 0)
+        })
+    }
+
+    /// Save the bitmap and mark it complete, if it has changed.  A no-op
+    /// until the filter is ready: a partial bitmap is never marked complete.
+    pub fn save(&self, conn: &mut Connection) -> Result<()> {
+        if !self.ready.load(Ordering::Acquire) || !self.unsaved.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        // Copy the bits only once we hold the write lock, so every FTS row
+        // committed before this save has its trigrams in the copy.
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        self.unsaved.store(false, Ordering::Release);
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS term_filter (
+                id   INTEGER PRIMARY KEY CHECK (id = 0),
+                bits BLOB    NOT NULL
+            );",
+        )?;
+        let bits: Vec<u8> = self.words.iter()
+            .flat_map(|w| w.load(Ordering::Acquire).to_le_bytes())
+            .collect();
+        tx.execute("INSERT OR REPLACE INTO term_filter (id, bits) VALUES (0, ?1)", [bits])?;
+        tx.execute("INSERT OR REPLACE INTO meta (key, value) VALUES ('term_filter', ?1)", [FORMAT])?;
+        if let Err(e) = tx.commit() {
+            self.unsaved.store(true, Ordering::Release);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Fill the bitmap from the saved copy, or rebuild it from the FTS
+    /// vocabulary when there is no complete copy.
+    fn load(&self, db_path: &Path) -> Result<()> {
+        // Read-only, which also never creates a DB deleted meanwhile.
+        let mut conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        ).with_context(|| format!("opening {}", db_path.display()))?;
+        conn.busy_timeout(std::time::Duration::from_secs(30))?;
+
+        let saved: Option<Vec<u8>> = {
+            let tx = conn.transaction()?;
+            let format: Option<String> = tx
+                .query_row("SELECT value FROM meta WHERE key = 'term_filter'", [], |r| r.get(0))
+                .optional()?;
+            match format.as_deref() {
+                Some(FORMAT) => tx
+                    .query_row("SELECT bits FROM term_filter WHERE id = 0", [], |r| r.get(0))
+                    .optional()?,
+                _ => None,
+            }
+        };
+
+        match saved {
+            Some(bits) if bits.len() == WORDS * 8 => {
+                for (word, chunk) in self.words.iter().zip(bits.chunks_exact(8)) {
+                    let mut le = [0u8; 8];
+                    le.copy_from_slice(chunk);
+                    word.fetch_or(u64::from_le_bytes(le), Ordering::AcqRel);
+                }
+                self.ready.store(true, Ordering::Release);
+            }
+            _ => {
+                conn.execute_batch(
+                    "CREATE VIRTUAL TABLE IF NOT EXISTS temp.term_filter_vocab
+                     USING fts5vocab(main, lines_fts, row);",
+                )?;
+                let mut stmt = conn.prepare("SELECT term FROM temp.term_filter_vocab")?;
+                let mut rows = stmt.query([])?;
+                while let Some(row) = rows.next()? {
+                    let term: String = row.get(0)?;
+                    self.add(&term);
+                }
+                drop(rows);
+                drop(stmt);
+                self.ready.store(true, Ordering::Release);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Clear the completeness mark before writing FTS rows whose trigrams were
+/// new (see `SourceFilter::add`).
+pub fn mark_unsaved(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM meta WHERE key = 'term_filter'", [])?;
+    Ok(())
+}
+
+/// In-memory filters of all sources, keyed by source DB path.
+#[derive(Default)]
+pub struct TermFilters {
+    filters: Mutex<HashMap<PathBuf, Arc<SourceFilter>>>,
+}
+
+impl TermFilters {
+    /// The filter for `db_path`, loaded on a background thread on first use.
+    /// The DB must exist.
+    pub fn get(&self, db_path: &Path) -> Arc<SourceFilter> {
+        let mut filters = self.filters.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(filter) = filters.get(db_path) {
+            return Arc::clone(filter);
+        }
+        let filter = Arc::new(SourceFilter::new());
+        filters.insert(db_path.to_path_buf(), Arc::clone(&filter));
+
+        let (loading, path) = (Arc::clone(&filter), db_path.to_path_buf());
+        std::thread::spawn(move || {
+            let start = std::time::Instant::now();
+            match loading.load(&path) {
+                Ok(()) => tracing::debug!(
+                    "term filter for {} ready in {:.1}s", path.display(), start.elapsed().as_secs_f64()
+                ),
+                Err(e) => tracing::warn!("Failed to load term filter for {}: {e:#}", path.display()),
+            }
+        });
+        filter
+    }
+
+    /// Forget the filter of a source DB that is being deleted or replaced.
+    pub fn evict(&self, db_path: &Path) {
+        self.filters.lock().unwrap_or_else(|e| e.into_inner()).remove(db_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lits(l: &[&str]) -> Vec<String> {
+        l.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn ready_filter() -> SourceFilter {
+        let f = SourceFilter::new();
+        f.ready.store(true, Ordering::Release);
+        f
+    }
+
+    #[test]
+    fn ascii_trigrams_are_exact_and_other_text_never_excludes() {
+        let f = ready_filter();
+        assert!(f.add("Hello wörld"));
+        assert!(!f.add("hello"), "nothing new");
+        assert!(f.may_match(&lits(&["hello", "HELLO", "rld"])));
+        assert!(!f.may_match(&lits(&["help"])));
+        assert!(f.may_match(&lits(&["öö", "ab"])), "no ASCII trigrams to check");
+        assert!(SourceFilter::new().may_match(&lits(&["help"])), "not ready");
+    }
+
+    #[test]
+    fn load_prefers_complete_saved_copy_and_rebuilds_otherwise() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("s.db");
+        let mut conn = super::super::open(&db_path).unwrap();
+        conn.execute("INSERT INTO lines_fts (rowid, content) VALUES (1, 'alpha beta')", []).unwrap();
+
+        // No saved copy: rebuilt from the FTS vocabulary.
+        let f = SourceFilter::new();
+        f.load(&db_path).unwrap();
+        assert!(f.may_match(&lits(&["alpha", "beta"])));
+        assert!(!f.may_match(&lits(&["gamma"])));
+        f.save(&mut conn).unwrap();
+
+        // Marked copy: loaded as saved, even though it lacks a later row.
+        conn.execute("INSERT INTO lines_fts (rowid, content) VALUES (2, 'gamma')", []).unwrap();
+        let f = SourceFilter::new();
+        f.load(&db_path).unwrap();
+        assert!(!f.may_match(&lits(&["gamma"])));
+
+        // Unmarked copy: rebuilt.
+        mark_unsaved(&conn).unwrap();
+        let f = SourceFilter::new();
+        f.load(&db_path).unwrap();
+        assert!(f.may_match(&lits(&["gamma"])));
+    }
+}
@@ -0,0 +1,271 @@
+//! Token normalization for the full-text index (`[tokens]`).
+//!
+//! Text is normalized on its way into `lines_fts` by the `fa_tokens()` SQL
+//! function, so an insert and the `'delete'` that later removes it always
+//! agree, and queries are normalized the same way before they become FTS
+//! expressions.  A contentless FTS5 table can only delete a row given the
+//! exact text it indexed, so each source DB records the settings it was
+//! created with in `meta.tokens` and keeps using them: a DB from before this
+//! setting indexes text unchanged, and new settings apply to a source once
+//! its DB is rebuilt.
+//!
+//! With `cjk = "bigram"`, each run of CJK characters is rewritten as its
+//! overlapping character pairs, each followed by [`CJK_MARK`]: `東京都`
+//! becomes `東京␣京都␣` (␣ standing for the mark).  The trigram tokenizer
+//! then has a token for every two-character word, which it otherwise cannot
+//! match, and a query rewritten the same way is a contiguous part of the
+//! rewritten line whenever the original query was part of the original line.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{Connection, OptionalExtension};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use find_common::config::{CjkSegmentation, TokenSettings, UnicodeForm};
+
+/// Follows each CJK character pair written by `cjk = "bigram"`.  FTS term
+/// splitting treats it as part of a word.
+pub const CJK_MARK: char = '\u{2063}';
+
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer {
+    settings: TokenSettings,
+    /// `settings.stopwords`, lowercased.
+    stopwords: HashSet<String>,
+}
+
+impl Normalizer {
+    pub fn new(settings: &TokenSettings) -> Self {
+        let stopwords = settings.stopwords.iter().map(|w| w.to_lowercase()).collect();
+        Self { settings: settings.clone(), stopwords }
+    }
+
+    /// The normalizer `conn`'s source DB indexes with.
+    pub fn load(conn: &Connection) -> Result<Self> {
+        Ok(Self::new(&recorded(conn)?.unwrap_or_default()))
+    }
+
+    /// `text` as the index sees it: Unicode form, then diacritics, then
+    /// stopwords (each removed with the whitespace after it), then CJK
+    /// segmentation.
+    pub fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(text);
+        if !text.is_ascii() {
+            match self.settings.unicode {
+                UnicodeForm::None => {}
+                UnicodeForm::Nfc => out = Cow::Owned(text.nfc().collect()),
+                UnicodeForm::Nfkc => out = Cow::Owned(text.nfkc().collect()),
+            }
+            if self.settings.strip_diacritics {
+                let stripped: String = out.nfd().filter(|c| !is_combining_mark(*c)).nfc().collect();
+                out = Cow::Owned(stripped);
+            }
+        }
+        if !self.stopwords.is_empty() {
+            if let Some(stripped) = self.strip_stopwords(&out) {
+                out = Cow::Owned(stripped);
+            }
+        }
+        if self.settings.cjk == CjkSegmentation::Bigram && !out.is_ascii() {
+            if let Some(segmented) = cjk_bigrams(&out) {
+                out = Cow::Owned(segmented);
+            }
+        }
+        out
+    }
+
+    /// `text` without its stopwords, or `None` when it has none or is
+    /// nothing but stopwords (an empty row could not be deleted again).
+    fn strip_stopwords(&self, text: &str) -> Option<String> {
+        let mut out = String::with_capacity(text.len());
+        let mut removed = false;
+        let mut rest = text;
+        while let Some(start) = rest.find(char::is_alphanumeric) {
+            let end = rest[start..].find(|c: char| !c.is_alphanumeric()).map_or(rest.len(), |e| start + e);
+            out.push_str(&rest[..start]);
+            if self.stopwords.contains(&rest[start..end].to_lowercase()) {
+                rest = rest[end..].trim_start();
+                removed = true;
+            } else {
+                out.push_str(&rest[start..end]);
+                rest = &rest[end..];
+            }
+        }
+        out.push_str(rest);
+        (removed && !out.trim().is_empty()).then_some(out)
+    }
+}
+
+/// `text` with each run of two or more CJK characters replaced by its
+/// character pairs, each followed by [`CJK_MARK`]; `None` when it has none.
+fn cjk_bigrams(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if !chars.iter().any(|&c| is_cjk(c)) {
+        return None;
+    }
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        let run = chars[i..].iter().take_while(|&&c| is_cjk(c)).count();
+        if run < 2 {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        for pair in chars[i..i + run].windows(2) {
+            out.extend([pair[0], pair[1], CJK_MARK]);
+        }
+        i += run;
+    }
+    Some(out)
+}
+
+/// Han ideographs, kana and Hangul.
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF     // Hangul Jamo
+        | 0x3040..=0x30FF   // Hiragana, Katakana
+        | 0x3130..=0x318F   // Hangul compatibility Jamo
+        | 0x31F0..=0x31FF   // Katakana phonetic extensions
+        | 0x3400..=0x4DBF   // CJK extension A
+        | 0x4E00..=0x9FFF   // CJK unified ideographs
+        | 0xAC00..=0xD7AF   // Hangul syllables
+        | 0xF900..=0xFAFF   // CJK compatibility ideographs
+        | 0xFF66..=0xFF9F   // Half-width Katakana
+        | 0x20000..=0x2FFFF // CJK extensions B and later
+    )
+}
+
+/// The settings recorded in `meta.tokens`, if any.
+pub fn recorded(conn: &Connection) -> Result<Option<TokenSettings>> {
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = 'tokens'", [], |r| r.get(0))
+        .optional()?;
+    Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+}
+
+/// Register `fa_tokens(text)` on `conn`, normalizing with `normalizer`.
+pub fn register(conn: &Connection, normalizer: Normalizer) -> Result<()> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    conn.create_scalar_function("fa_tokens", 1, flags, move |ctx| {
+        let text: String = ctx.get(0)?;
+        Ok(normalizer.text(&text).into_owned())
+    })?;
+    Ok(())
+}
+
+/// The normalizer for the source DB behind the writer connection `conn`.
+/// A DB with no recorded settings records them first: `settings` when it
+/// has nothing indexed yet, otherwise the defaults it was indexed with.
+/// Re-registers `fa_tokens()` on `conn` to match.
+pub fn adopt(conn: &Connection, settings: &TokenSettings) -> Result<Normalizer> {
+    let settings = match recorded(conn)? {
+        Some(recorded) => recorded,
+        None => {
+            let settings = if has_indexed_rows(conn)? { TokenSettings::default() } else { settings.clone() };
+            conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('tokens', ?1)",
+                [serde_json::to_string(&settings)?],
+            )?;
+            settings
+        }
+    };
+    let normalizer = Normalizer::new(&settings);
+    register(conn, normalizer.clone())?;
+    Ok(normalizer)
+}
+
+/// Warn when the source DB at `db_path` indexes with settings other than
+/// the configured `settings`.
+pub fn warn_if_changed(conn: &Connection, db_path: &Path, settings: &TokenSettings) -> Result<()> {
+    let effective = match recorded(conn)? {
+        Some(recorded) => recorded,
+        None if has_indexed_rows(conn)? => TokenSettings::default(),
+        None => return Ok(()),
+    };
+    if &effective != settings {
+        tracing::warn!(
+            "{} was indexed with different [tokens] settings and keeps them; \
+             delete it and re-run find-scan to apply the current ones",
+            db_path.display(),
+        );
+    }
+    Ok(())
+}
+
+fn has_indexed_rows(conn: &Connection) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM files) OR EXISTS(SELECT 1 FROM trash)",
+        [],
+        |r| r.get(0),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalizer(unicode: UnicodeForm, strip_diacritics: bool, stopwords: &[&str]) -> Normalizer {
+        Normalizer::new(&TokenSettings {
+            unicode,
+            strip_diacritics,
+            stopwords: stopwords.iter().map(|w| w.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn default_leaves_text_alone() {
+        let n = Normalizer::default();
+        assert!(matches!(n.text("Café naïve"), Cow::Borrowed("Café naïve")));
+    }
+
+    #[test]
+    fn folds_unicode_forms_and_diacritics() {
+        // "e" + combining acute composes to "é" under NFC.
+        assert_eq!(normalizer(UnicodeForm::Nfc, false, &[]).text("cafe\u{301}"), "café");
+        assert_eq!(normalizer(UnicodeForm::Nfkc, false, &[]).text("ﬁle"), "file");
+        assert_eq!(normalizer(UnicodeForm::None, true, &[]).text("Café naïve"), "Cafe naive");
+    }
+
+    #[test]
+    fn stopwords_removed_case_insensitively() {
+        let n = normalizer(UnicodeForm::None, false, &["of", "The"]);
+        assert_eq!(n.text("The state of the art"), "state art");
+        assert_eq!(n.text("\"state of the art\" theory"), "\"state art\" theory");
+        assert!(matches!(n.text("nothing here"), Cow::Borrowed(_)));
+        assert_eq!(n.text("of the"), "of the");
+    }
+
+    #[test]
+    fn cjk_runs_become_marked_bigrams() {
+        let n = Normalizer::new(&TokenSettings { cjk: CjkSegmentation::Bigram, ..Default::default() });
+        let m = CJK_MARK;
+        assert_eq!(n.text("東京都 tower"), format!("東京{m}京都{m} tower"));
+        assert_eq!(n.text("の"), "の", "a lone character is left alone");
+        assert_eq!(n.text("café"), "café");
+        // A query inside a line stays inside it once both are segmented.
+        assert!(n.text("私は東京都に住む").contains(n.text("東京").as_ref()));
+    }
+
+    #[test]
+    fn adopt_records_settings_for_new_dbs_only() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("../schema_v4.sql")).unwrap();
+        let settings = TokenSettings { strip_diacritics: true, ..Default::default() };
+        adopt(&conn, &settings).unwrap();
+        assert_eq!(recorded(&conn).unwrap(), Some(settings));
+        let n: String = conn.query_row("SELECT fa_tokens('naïve')", [], |r| r.get(0)).unwrap();
+        assert_eq!(n, "naive");
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("../schema_v4.sql")).unwrap();
+        conn.execute("INSERT INTO files (path, mtime, kind) VALUES ('a.txt', 0, 'text')", []).unwrap();
+        adopt(&conn, &TokenSettings { strip_diacritics: true, ..Default::default() }).unwrap();
+        assert_eq!(recorded(&conn).unwrap(), Some(TokenSettings::default()), "an indexed DB keeps plain text");
+    }
+}
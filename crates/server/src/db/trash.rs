@@ -0,0 +1,159 @@
+//! Deleted files held back for `[server] trash_days` (the `trash` table).
+//!
+//! With a trash window, `delete_files_phase1` moves each deleted `files` row,
+//! and its archive members' rows, into `trash` with the same id.  The
+//! file's `lines_fts` rows stay where they are: search only returns rows
+//! whose id is in `files`, so the file is hidden until it is restored, and
+//! found again afterwards without re-indexing.  Its content stays live for
+//! compaction until the row is purged.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use find_common::api::{FileKind, TrashedFile};
+use find_common::path::composite_like_prefix;
+
+use super::DeleteDelta;
+
+const COLUMNS: &str = "id, path, mtime, size, kind, indexed_at, extract_ms, file_hash, scanner_version, line_count, \
+                       extractor, extractor_version, extract_truncated";
+
+/// Move `path` and its archive members from `files` to the trash, replacing
+/// any earlier deletion of the same path.  The caller deletes the `files` rows.
+pub(super) fn move_to_trash(tx: &rusqlite::Transaction, path: &str, now: i64) -> Result<()> {
+    let members = composite_like_prefix(path);
+    tx.execute("DELETE FROM trash WHERE path = ?1 OR path LIKE ?2", params![path, members])?;
+    tx.execute(
+        &format!(
+            "INSERT INTO trash ({COLUMNS}, deleted_at)
+             SELECT {COLUMNS}, ?3 FROM files WHERE path = ?1 OR path LIKE ?2"
+        ),
+        params![path, members, now],
+    )?;
+    Ok(())
+}
+
+/// Outer files in the trash, most recently deleted first, optionally only
+/// those under the directory `prefix`.  Returns one page and the total.
+pub fn list_trash(
+    conn: &Connection,
+    prefix: Option<&str>,
+    trash_days: u32,
+    limit: usize,
+    offset: usize,
+) -> Result<(Vec<TrashedFile>, usize)> {
+    let prefix = dir_prefix(prefix);
+    let filter = "path NOT LIKE '%::%' AND substr(path, 1, length(?1)) = ?1";
+    let total: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM trash WHERE {filter}"), params![prefix], |r| r.get(0))?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, kind, size, mtime, deleted_at FROM trash WHERE {filter}
+         ORDER BY deleted_at DESC, path LIMIT ?2 OFFSET ?3"
+    ))?;
+    let files = stmt
+        .query_map(params![prefix, limit as i64, offset as i64], |row| {
+            let kind: String = row.get(1)?;
+            let deleted_at: i64 = row.get(4)?;
+            Ok(TrashedFile {
+                path: row.get(0)?,
+                kind: FileKind::from(kind.as_str()),
+                size: row.get(2)?,
+                mtime: row.get(3)?,
+                deleted_at,
+                purge_at: deleted_at + i64::from(trash_days) * 86_400,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok((files, total as usize))
+}
+
+/// Result of [`restore_from_trash`].
+pub struct RestoreOutcome {
+    pub restored: usize,
+    /// Paths left in the trash because they are indexed again.
+    pub skipped: usize,
+    /// Stats of the restored outer files, counted as removals (negate to apply).
+    pub delta: DeleteDelta,
+}
+
+/// Move `paths`, and everything under the directory `prefix` when given,
+/// back from the trash into `files`, with their archive members.
+pub fn restore_from_trash(conn: &Connection, paths: &[String], prefix: Option<&str>) -> Result<RestoreOutcome> {
+    let tx = conn.unchecked_transaction()?;
+
+    let mut outer: Vec<String> = paths.to_vec();
+    if let Some(prefix) = prefix {
+        let mut stmt = tx.prepare(
+            "SELECT path FROM trash WHERE path NOT LIKE '%::%' AND substr(path, 1, length(?1)) = ?1",
+        )?;
+        let under = stmt.query_map(params![dir_prefix(Some(prefix))], |r| r.get::<_, String>(0))?;
+        outer.extend(under.collect::<rusqlite::Result<Vec<_>>>()?);
+    }
+    outer.sort();
+    outer.dedup();
+
+    let mut outcome = RestoreOutcome {
+        restored: 0,
+        skipped: 0,
+        delta: DeleteDelta { files_removed: 0, size_removed: 0, by_kind: HashMap::new() },
+    };
+    for path in &outer {
+        let row: Option<(i64, String)> = tx
+            .query_row("SELECT COALESCE(size, 0), kind FROM trash WHERE path = ?1", params![path], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .optional()?;
+        let Some((size, kind)) = row else { continue };
+        let indexed: bool = tx.query_row("SELECT EXISTS(SELECT 1 FROM files WHERE path = ?1)", params![path], |r| r.get(0))?;
+        if indexed {
+            outcome.skipped += 1;
+            continue;
+        }
+
+        let members = composite_like_prefix(path);
+        tx.execute(
+            &format!("INSERT INTO files ({COLUMNS}) SELECT {COLUMNS} FROM trash WHERE path = ?1 OR path LIKE ?2"),
+            params![path, members],
+        )?;
+        tx.execute("DELETE FROM trash WHERE path = ?1 OR path LIKE ?2", params![path, members])?;
+
+        outcome.restored += 1;
+        outcome.delta.files_removed += 1;
+        outcome.delta.size_removed += size;
+        let e = outcome.delta.by_kind.entry(FileKind::from(kind.as_str())).or_insert((0, 0));
+        e.0 += 1;
+        e.1 += size;
+    }
+
+    if outcome.restored > 0 {
+        // Deletion dropped the restored files' duplicate entries (and those
+        // of files left alone with the same content); recreate them.
+        tx.execute(
+            "INSERT OR IGNORE INTO duplicates (file_hash, file_id)
+             SELECT file_hash, id FROM files WHERE file_hash IN (
+                 SELECT file_hash FROM files WHERE file_hash IS NOT NULL
+                 GROUP BY file_hash HAVING COUNT(*) > 1
+             )",
+            [],
+        )?;
+    }
+    tx.commit()?;
+    Ok(outcome)
+}
+
+/// Remove trash entries deleted before `before` (Unix seconds).  Returns the
+/// number of rows removed, archive members included.
+pub fn purge_trash(conn: &Connection, before: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM trash WHERE deleted_at < ?1", params![before])?)
+}
+
+/// `prefix` as a directory prefix: `""` for the whole source, otherwise
+/// with a trailing `/`.
+fn dir_prefix(prefix: Option<&str>) -> String {
+    match prefix.map(|p| p.trim_matches('/')) {
+        None | Some("") => String::new(),
+        Some(p) => format!("{p}/"),
+    }
+}
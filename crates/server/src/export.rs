@@ -0,0 +1,184 @@
+//! Single-member extraction for `GET /api/v1/export`.
+//!
+//! Re-extracts one member of an archive (ZIP, TAR and its compressed
+//! variants, or a single-file `.gz`/`.bz2`/`.xz` wrapper) from the outer file
+//! on the source mount or in the originals store.  Nested members
+//! (`outer.tar.gz::inner.zip::doc.txt`) are handled by extracting each
+//! intermediate archive into memory in turn.
+
+use std::io::{Cursor, Read, Seek};
+
+use axum::http::StatusCode;
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use xz2::read::XzDecoder;
+
+/// Refuse to buffer members (or intermediate archives) larger than this.
+pub const MAX_MEMBER_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarBz2,
+    TarXz,
+    Gz,
+    Bz2,
+    Xz,
+}
+
+fn format_of(name: &str) -> Option<Format> {
+    let n = name.to_lowercase();
+    if n.ends_with(".tar.gz") || n.ends_with(".tgz")   { return Some(Format::TarGz);  }
+    if n.ends_with(".tar.bz2") || n.ends_with(".tbz2") { return Some(Format::TarBz2); }
+    if n.ends_with(".tar.xz") || n.ends_with(".txz")   { return Some(Format::TarXz);  }
+    if n.ends_with(".tar")                             { return Some(Format::Tar);    }
+    if [".zip", ".pages", ".numbers", ".key"].iter().any(|ext| n.ends_with(ext)) {
+        return Some(Format::Zip);
+    }
+    if n.ends_with(".gz")                              { return Some(Format::Gz);     }
+    if n.ends_with(".bz2")                             { return Some(Format::Bz2);    }
+    if n.ends_with(".xz")                              { return Some(Format::Xz);     }
+    None
+}
+
+/// Extract `member_path` (members separated by `::`) from the archive
+/// `archive_name` read from `reader`.
+pub fn extract_nested<R: Read + Seek>(reader: R, archive_name: &str, member_path: &str) -> Result<Vec<u8>, StatusCode> {
+    let mut parts = member_path.split("::");
+    let first = parts.next().unwrap_or_default();
+    let mut bytes = extract_member(reader, archive_name, first)?;
+    let mut name = first;
+    for part in parts {
+        bytes = extract_member(Cursor::new(bytes), name, part)?;
+        name = part;
+    }
+    Ok(bytes)
+}
+
+/// Extract a single member from the archive `archive_name` read from `reader`.
+///
+/// Errors: 404 when the member does not exist, 413 when it exceeds
+/// [`MAX_MEMBER_BYTES`], 422 when the archive format is unsupported or the
+/// archive is corrupt.
+pub fn extract_member<R: Read + Seek>(reader: R, archive_name: &str, member: &str) -> Result<Vec<u8>, StatusCode> {
+    let format = format_of(archive_name).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(reader).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+            let entry = zip.by_name(member).map_err(|_| StatusCode::NOT_FOUND)?;
+            read_capped(entry)
+        }
+        Format::Tar    => tar_member(reader, member),
+        Format::TarGz  => tar_member(GzDecoder::new(reader), member),
+        Format::TarBz2 => tar_member(BzDecoder::new(reader), member),
+        Format::TarXz  => tar_member(XzDecoder::new(reader), member),
+        Format::Gz | Format::Bz2 | Format::Xz => {
+            // Single-file wrappers have one member named after the file stem.
+            let leaf = archive_name.rsplit('/').next().unwrap_or(archive_name);
+            let stem = leaf.rsplit_once('.').map_or(leaf, |(stem, _)| stem);
+            if member != stem {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            match format {
+                Format::Gz  => read_capped(GzDecoder::new(reader)),
+                Format::Bz2 => read_capped(BzDecoder::new(reader)),
+                _           => read_capped(XzDecoder::new(reader)),
+            }
+        }
+    }
+}
+
+fn tar_member<R: Read>(reader: R, member: &str) -> Result<Vec<u8>, StatusCode> {
+    let mut archive = tar::Archive::new(reader);
+    let entries = archive.entries().map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    for entry in entries {
+        let entry = entry.map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+        if entry.header().entry_type().is_dir() {
+            continue;
+        }
+        let matches = entry.path().is_ok_and(|p| p.to_string_lossy() == member);
+        if matches {
+            return read_capped(entry);
+        }
+    }
+    Err(StatusCode::NOT_FOUND)
+}
+
+fn read_capped<R: Read>(reader: R) -> Result<Vec<u8>, StatusCode> {
+    let mut buf = Vec::new();
+    reader
+        .take(MAX_MEMBER_BYTES + 1)
+        .read_to_end(&mut buf)
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    if buf.len() as u64 > MAX_MEMBER_BYTES {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn make_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn make_tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(enc);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn extracts_tar_gz_member() {
+        let archive = make_tar_gz(&[("docs/a.txt", b"alpha"), ("docs/b.txt", b"beta")]);
+        let bytes = extract_member(Cursor::new(archive.clone()), "backup.tar.gz", "docs/b.txt").unwrap();
+        assert_eq!(bytes, b"beta");
+        assert_eq!(
+            extract_member(Cursor::new(archive), "backup.tar.gz", "docs/missing.txt"),
+            Err(StatusCode::NOT_FOUND),
+        );
+    }
+
+    #[test]
+    fn extracts_nested_zip_inside_tar_gz() {
+        let inner = make_zip(&[("report.txt", b"nested report")]);
+        let outer = make_tar_gz(&[("inner.zip", &inner)]);
+        let bytes = extract_nested(Cursor::new(outer), "backup.tgz", "inner.zip::report.txt").unwrap();
+        assert_eq!(bytes, b"nested report");
+    }
+
+    #[test]
+    fn single_file_wrapper_member_is_stem() {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(b"log line").unwrap();
+        let gz = enc.finish().unwrap();
+        let bytes = extract_member(Cursor::new(gz.clone()), "logs/app.log.gz", "app.log").unwrap();
+        assert_eq!(bytes, b"log line");
+        assert_eq!(extract_member(Cursor::new(gz), "logs/app.log.gz", "other"), Err(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn unsupported_format_is_rejected() {
+        assert_eq!(
+            extract_member(Cursor::new(Vec::new()), "photos.7z", "a.jpg"),
+            Err(StatusCode::UNPROCESSABLE_ENTITY),
+        );
+    }
+}
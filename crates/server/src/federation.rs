@@ -0,0 +1,60 @@
+/// Search federation across peer find-servers.
+///
+/// When `[[federation.peers]]` are configured, every search is also sent to
+/// each peer with `federate=false` (so peers never fan out in turn).  Peer
+/// hits are tagged with the peer's name in `SearchResult::origin` and merged
+/// into the local result list by the search handler.
+use std::time::Duration;
+
+use find_common::api::SearchResponse;
+use find_common::config::FederationConfig;
+
+/// Query every configured peer concurrently.
+///
+/// `query` is the query string to forward (already rewritten so that each
+/// peer returns enough hits to fill the caller's page).  Returns one
+/// response per peer that answered successfully; failures are logged and
+/// skipped so an unreachable peer never fails the whole search.
+pub async fn search_peers(cfg: &FederationConfig, query: &[(&str, String)]) -> Vec<SearchResponse> {
+    if cfg.peers.is_empty() {
+        return Vec::new();
+    }
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(cfg.timeout_secs))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => { tracing::error!("Failed to build federation client: {e:#}"); return Vec::new(); }
+    };
+
+    let handles: Vec<_> = cfg.peers.iter().cloned().map(|peer| {
+        let req = client
+            .get(format!("{}/api/v1/search", peer.url.trim_end_matches('/')))
+            .bearer_auth(&peer.token)
+            .query(query);
+        tokio::spawn(async move {
+            let resp = req.send().await
+                .and_then(|r| r.error_for_status());
+            match resp {
+                Ok(r) => match r.json::<SearchResponse>().await {
+                    Ok(mut body) => {
+                        for result in &mut body.results {
+                            result.origin = Some(peer.name.clone());
+                        }
+                        Some(body)
+                    }
+                    Err(e) => { tracing::warn!("Federation peer {} returned invalid JSON: {e:#}", peer.name); None }
+                },
+                Err(e) => { tracing::warn!("Federation peer {} failed: {e:#}", peer.name); None }
+            }
+        })
+    }).collect();
+
+    let mut responses = Vec::with_capacity(handles.len());
+    for handle in handles {
+        if let Ok(Some(resp)) = handle.await {
+            responses.push(resp);
+        }
+    }
+    responses
+}
@@ -3,13 +3,31 @@ use nucleo_matcher::{
     Config, Matcher, Utf32Str,
 };
 
+use find_common::api::{LINE_METADATA, LINE_PATH};
+use find_common::config::{ScoringAlgorithm, ScoringSettings};
+
+/// Points for each matched character (Smith-Waterman).
+const SW_MATCH: i32 = 16;
+/// Extra points for a match at a word start (Smith-Waterman).
+const SW_BOUNDARY_BONUS: i32 = 8;
+/// Cost of skipping one haystack character between matches, and of each
+/// further skipped character (Smith-Waterman).
+const SW_GAP_START: i32 = -3;
+const SW_GAP_EXTEND: i32 = -1;
+
 pub struct FuzzyScorer {
     matcher: Matcher,
     pattern: Pattern,
+    settings: ScoringSettings,
+    case_sensitive: bool,
+    /// Whitespace-separated query words, case-folded unless case-sensitive
+    /// (Smith-Waterman only).
+    atoms: Vec<Vec<char>>,
+    indices: Vec<u32>,
 }
 
 impl FuzzyScorer {
-    pub fn new(query: &str, case_sensitive: bool) -> Self {
+    pub fn new(query: &str, case_sensitive: bool, settings: &ScoringSettings) -> Self {
         let matcher = Matcher::new(Config::DEFAULT);
         let case = if case_sensitive { CaseMatching::Respect } else { CaseMatching::Ignore };
         let pattern = Pattern::new(
@@ -18,64 +36,230 @@ impl FuzzyScorer {
             Normalization::Smart,
             AtomKind::Fuzzy,
         );
-        Self { matcher, pattern }
+        let atoms = query.split_whitespace().map(|w| fold(w, case_sensitive)).collect();
+        Self { matcher, pattern, settings: settings.clone(), case_sensitive, atoms, indices: Vec::new() }
     }
 
-    /// Returns Some(score) if `haystack` matches, None otherwise.
-    pub fn score(&mut self, haystack: &str) -> Option<u32> {
+    /// Returns Some(score) if `haystack` matches, None otherwise.  The score
+    /// is weighted by the configured boost for the kind of line
+    /// `line_number` is (filename or metadata).
+    pub fn score(&mut self, haystack: &str, line_number: usize) -> Option<u32> {
+        let boost = match line_number {
+            LINE_PATH => self.settings.filename_boost,
+            LINE_METADATA => self.settings.metadata_boost,
+            _ => 1.0,
+        };
+        let (raw, consecutive) = match self.settings.algorithm {
+            ScoringAlgorithm::Nucleo => self.nucleo(haystack)?,
+            ScoringAlgorithm::SmithWaterman => self.smith_waterman(haystack)?,
+        };
+        let length = haystack.chars().count() as f64;
+        let score = (f64::from(raw) + f64::from(consecutive * self.settings.consecutive_bonus)
+            - self.settings.length_penalty * length)
+            * boost.max(0.0);
+        Some(score.max(0.0).round() as u32)
+    }
+
+    /// nucleo's score and, when a consecutive bonus is configured, the
+    /// number of adjacent matched character pairs.
+    fn nucleo(&mut self, haystack: &str) -> Option<(u32, u32)> {
         let mut buf = Vec::new();
         let s = Utf32Str::new(haystack, &mut buf);
-        self.pattern.score(s, &mut self.matcher)
+        if self.settings.consecutive_bonus == 0 {
+            return self.pattern.score(s, &mut self.matcher).map(|score| (score, 0));
+        }
+        self.indices.clear();
+        let score = self.pattern.indices(s, &mut self.matcher, &mut self.indices)?;
+        self.indices.sort_unstable();
+        self.indices.dedup();
+        let consecutive = self.indices.windows(2).filter(|w| w[1] == w[0] + 1).count() as u32;
+        Some((score, consecutive))
+    }
+
+    /// Sum of each query word's best alignment in `haystack`; None when a
+    /// word does not match.
+    fn smith_waterman(&self, haystack: &str) -> Option<(u32, u32)> {
+        let original: Vec<char> = haystack.chars().collect();
+        let folded = fold(haystack, self.case_sensitive);
+        let (mut total, mut consecutive) = (0i32, 0u32);
+        for atom in &self.atoms {
+            let (score, adjacent) = smith_waterman(atom, &folded, &original)?;
+            total += score;
+            consecutive += adjacent;
+        }
+        Some((total.max(0) as u32, consecutive))
     }
 }
 
+fn fold(s: &str, case_sensitive: bool) -> Vec<char> {
+    if case_sensitive {
+        s.chars().collect()
+    } else {
+        s.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+    }
+}
+
+/// Best local alignment of `atom` in `haystack` (`original` is the haystack
+/// before case folding) in which every atom character matches, in order.
+/// Returns the score and the number of adjacent matched pairs.
+fn smith_waterman(atom: &[char], haystack: &[char], original: &[char]) -> Option<(i32, u32)> {
+    let (m, n) = (atom.len(), haystack.len());
+    if m == 0 {
+        return Some((0, 0));
+    }
+    if m > n {
+        return None;
+    }
+    let bonus = |j: usize| {
+        let boundary = j == 0
+            || !original[j - 1].is_alphanumeric()
+            || (original[j - 1].is_lowercase() && original[j].is_uppercase());
+        SW_MATCH + if boundary { SW_BOUNDARY_BONUS } else { 0 }
+    };
+
+    // rows[i][j]: best score with atom[i] matched at haystack[j];
+    // from[i][j]: where atom[i - 1] matched in that alignment.
+    let mut rows: Vec<Vec<Option<i32>>> = vec![vec![None; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+    for j in 0..n {
+        if haystack[j] == atom[0] {
+            rows[0][j] = Some(bonus(j));
+        }
+    }
+    for i in 1..m {
+        let (done, rest) = rows.split_at_mut(i);
+        let (prev, cur) = (&done[i - 1], &mut rest[0]);
+        // Best earlier match followed by a gap of at least one character.
+        let mut gap: Option<(i32, usize)> = None;
+        for j in 1..n {
+            if j >= 2 {
+                let opened = prev[j - 2].map(|s| (s + SW_GAP_START, j - 2));
+                let extended = gap.map(|(s, k)| (s + SW_GAP_EXTEND, k));
+                gap = opened.into_iter().chain(extended).max_by_key(|&(s, _)| s);
+            }
+            if haystack[j] != atom[i] {
+                continue;
+            }
+            let adjacent = prev[j - 1].map(|s| (s, j - 1));
+            if let Some((s, k)) = adjacent.into_iter().chain(gap).max_by_key(|&(s, _)| s) {
+                cur[j] = Some(s + bonus(j));
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = rows[m - 1].iter().enumerate().filter_map(|(j, s)| s.map(|s| (j, s))).max_by_key(|&(_, s)| s)?;
+    let mut consecutive = 0;
+    for i in (1..m).rev() {
+        let k = from[i][j];
+        if k + 1 == j {
+            consecutive += 1;
+        }
+        j = k;
+    }
+    Some((score, consecutive))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const LINE: usize = find_common::api::LINE_CONTENT_START;
+
+    fn scorer(query: &str, case_sensitive: bool) -> FuzzyScorer {
+        FuzzyScorer::new(query, case_sensitive, &ScoringSettings::default())
+    }
+
+    fn smith_waterman_scorer(query: &str) -> FuzzyScorer {
+        let settings = ScoringSettings { algorithm: ScoringAlgorithm::SmithWaterman, ..Default::default() };
+        FuzzyScorer::new(query, false, &settings)
+    }
+
     #[test]
     fn exact_match_scores() {
-        let mut scorer = FuzzyScorer::new("hello", false);
-        assert!(scorer.score("hello").is_some());
+        let mut scorer = scorer("hello", false);
+        assert!(scorer.score("hello", LINE).is_some());
     }
 
     #[test]
     fn non_match_returns_none() {
-        let mut scorer = FuzzyScorer::new("zzz", false);
-        assert!(scorer.score("hello world").is_none());
+        let mut scorer = scorer("zzz", false);
+        assert!(scorer.score("hello world", LINE).is_none());
     }
 
     #[test]
     fn prefix_scores_higher_than_partial() {
-        let mut scorer = FuzzyScorer::new("rep", false);
-        let prefix_score = scorer.score("report.pdf").expect("prefix should match");
-        let partial_score = scorer.score("unrelated_rep_file").expect("partial should match");
+        let mut scorer = scorer("rep", false);
+        let prefix_score = scorer.score("report.pdf", LINE).expect("prefix should match");
+        let partial_score = scorer.score("unrelated_rep_file", LINE).expect("partial should match");
         assert!(prefix_score > partial_score);
     }
 
     #[test]
     fn empty_query_scores_everything() {
-        let mut scorer = FuzzyScorer::new("", false);
-        assert!(scorer.score("anything").is_some());
+        let mut scorer = scorer("", false);
+        assert!(scorer.score("anything", LINE).is_some());
     }
 
     #[test]
     fn case_insensitive_matches() {
-        let mut scorer = FuzzyScorer::new("Hello", false);
-        assert!(scorer.score("hello world").is_some());
+        let mut scorer = scorer("Hello", false);
+        assert!(scorer.score("hello world", LINE).is_some());
     }
 
     #[test]
     fn case_sensitive_no_match() {
-        let mut scorer = FuzzyScorer::new("Hello", true);
-        assert!(scorer.score("hello world").is_none());
+        let mut scorer = scorer("Hello", true);
+        assert!(scorer.score("hello world", LINE).is_none());
     }
 
     #[test]
     fn multiple_calls_consistent() {
-        let mut scorer = FuzzyScorer::new("foo", false);
-        let first = scorer.score("foobar");
-        let second = scorer.score("foobar");
+        let mut scorer = scorer("foo", false);
+        let first = scorer.score("foobar", LINE);
+        let second = scorer.score("foobar", LINE);
         assert_eq!(first, second);
     }
+
+    #[test]
+    fn line_boosts_apply_per_field() {
+        let settings = ScoringSettings { filename_boost: 2.0, metadata_boost: 0.5, ..Default::default() };
+        let mut scorer = FuzzyScorer::new("report", false, &settings);
+        let content = scorer.score("report", LINE).unwrap();
+        assert_eq!(scorer.score("report", LINE_PATH).unwrap(), content * 2);
+        assert_eq!(scorer.score("report", LINE_METADATA).unwrap(), (f64::from(content) * 0.5).round() as u32);
+    }
+
+    #[test]
+    fn length_penalty_prefers_shorter_lines() {
+        let settings = ScoringSettings { length_penalty: 1.0, ..Default::default() };
+        let mut scorer = FuzzyScorer::new("report", false, &settings);
+        let short = scorer.score("report", LINE).unwrap();
+        let long = scorer.score("report                                  ", LINE).unwrap();
+        assert!(short > long, "{short} vs {long}");
+    }
+
+    #[test]
+    fn consecutive_bonus_counts_adjacent_matches() {
+        let settings = ScoringSettings { consecutive_bonus: 10, ..Default::default() };
+        let plain = scorer("abc", false).score("xabcx", LINE).unwrap();
+        assert_eq!(FuzzyScorer::new("abc", false, &settings).score("xabcx", LINE).unwrap(), plain + 20);
+    }
+
+    #[test]
+    fn smith_waterman_prefers_compact_matches() {
+        let mut scorer = smith_waterman_scorer("rep");
+        let compact = scorer.score("report.pdf", LINE).expect("compact should match");
+        let scattered = scorer.score("roleplay.pdf", LINE).expect("scattered should match");
+        assert!(compact > scattered, "{compact} vs {scattered}");
+        assert!(scorer.score("per", LINE).is_none(), "characters must match in order");
+    }
+
+    #[test]
+    fn smith_waterman_needs_every_word() {
+        let mut scorer = smith_waterman_scorer("Quarterly report");
+        assert!(scorer.score("the QUARTERLY sales report", LINE).is_some());
+        assert!(scorer.score("the quarterly sales", LINE).is_none());
+        assert_eq!(smith_waterman(&['a', 'b'], &['x', 'a', 'b'], &['x', 'a', 'b']), Some((2 * SW_MATCH, 1)));
+    }
 }
@@ -0,0 +1,75 @@
+//! Per-source index size budgets (`[sources.<name>] max_index_mb`).
+//!
+//! A source's index is its database plus its content in the content store.
+//! The database size is read from disk when needed; the content share comes
+//! from the stats cache, which recomputes it whenever the archive queue
+//! drains (see `db::get_space_usage`), so it can trail a large batch.
+//!
+//! At [`WARN_PCT`] of its budget a source is logged as nearly full.  Once
+//! over it, the worker still applies changes to files the source already
+//! holds, and deletions and renames, but refuses new files: they are recorded
+//! as indexing errors and sent again by the next scan, after space is freed
+//! or the budget raised.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+
+use find_common::config::ServerAppConfig;
+
+use crate::stats_cache::SourceStatsCache;
+
+/// Share of the budget, in percent, at which a source is logged as nearly full.
+pub const WARN_PCT: u64 = 90;
+
+const MB: u64 = 1024 * 1024;
+
+/// `source`'s budget in bytes, if it has one.
+pub fn budget(config: &ServerAppConfig, source: &str) -> Option<u64> {
+    config.sources.get(source)?.max_index_mb.map(|mb| mb * MB)
+}
+
+/// Budgets by source name, in bytes.
+pub fn budgets(config: &ServerAppConfig) -> HashMap<String, u64> {
+    config.sources.keys().filter_map(|name| Some((name.clone(), budget(config, name)?))).collect()
+}
+
+/// On-disk size of `source`'s database and its write-ahead log.
+pub fn db_bytes(data_dir: &Path, source: &str) -> u64 {
+    let db = data_dir.join("sources").join(format!("{source}.db"));
+    let wal = db.with_extension("db-wal");
+    [db, wal].iter().filter_map(|p| std::fs::metadata(p).ok()).map(|m| m.len()).sum()
+}
+
+/// Space `source`'s index takes: its database, and its content as of the
+/// last stats rebuild.
+pub fn used_bytes(data_dir: &Path, source: &str, cache: &RwLock<SourceStatsCache>) -> u64 {
+    let content = cache
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .sources
+        .iter()
+        .find(|s| s.name == source)
+        .map_or(0, |s| s.content_bytes);
+    db_bytes(data_dir, source) + content
+}
+
+/// Check `used` against `budget`, logging when the source crosses
+/// [`WARN_PCT`] in either direction.  Returns the error to record against
+/// refused files when the budget is used up.
+pub fn check(source: &str, used: u64, budget: u64) -> Option<String> {
+    static NEARLY_FULL: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+    let nearly_full = used.saturating_mul(100) >= budget.saturating_mul(WARN_PCT);
+    let mut warned = NEARLY_FULL.lock().unwrap_or_else(|e| e.into_inner());
+    let warned = warned.get_or_insert_with(HashSet::new);
+    if nearly_full && warned.insert(source.to_string()) {
+        tracing::warn!(
+            "[{source}] index uses {} MB of its {} MB budget (max_index_mb); new files are refused once it is full",
+            used / MB,
+            budget / MB,
+        );
+    } else if !nearly_full && warned.remove(source) {
+        tracing::info!("[{source}] index is back under {WARN_PCT}% of its budget");
+    }
+    (used >= budget).then(|| format!("index size budget exceeded: {} MB used of {} MB (max_index_mb)", used / MB, budget / MB))
+}
@@ -1,12 +1,21 @@
 pub(crate) mod alerts;
+pub(crate) mod client_metrics;
 pub(crate) mod compaction;
 pub(crate) mod image_util;
+pub(crate) mod index_budget;
+pub(crate) mod meta_snippet;
 pub(crate) mod db;
+pub(crate) mod export;
+pub(crate) mod federation;
 pub(crate) mod fuzzy;
 pub(crate) mod normalize;
+pub(crate) mod originals;
+pub(crate) mod replica;
 pub(crate) mod routes;
+pub(crate) mod search_limiter;
 pub(crate) mod stats_cache;
 pub(crate) mod upload;
+pub(crate) mod webhooks;
 pub(crate) mod worker;
 
 use std::path::{Path, PathBuf};
@@ -16,16 +25,18 @@ use std::sync::atomic::{AtomicBool, AtomicU32};
 use anyhow::{Context, Result};
 use axum::{
     extract::{DefaultBodyLimit, State},
-    http::{header, StatusCode},
+    http::{header, Extensions, HeaderMap, StatusCode, Version},
     middleware,
     response::IntoResponse,
     routing::{delete, get, head, patch, post},
     Router,
 };
+use tower_http::compression::predicate::{DefaultPredicate, Predicate};
+use tower_http::compression::CompressionLayer;
 use tower_http::trace::TraceLayer;
 
-use find_common::api::{RecentFile, WorkerStatus};
-use find_common::config::ServerAppConfig;
+use find_common::api::RecentFile;
+use find_common::config::{parse_server_config, ServerAppConfig};
 use find_content_store::{ContentStore, MultiContentStore, open_backend};
 
 // ── Embedded web UI ────────────────────────────────────────────────────────────
@@ -79,9 +90,12 @@ pub struct CachedUpdateCheck {
 pub struct AppState {
     pub config: ServerAppConfig,
     pub data_dir: PathBuf,
-    pub worker_status: Arc<std::sync::Mutex<WorkerStatus>>,
+    pub worker_status: Arc<std::sync::Mutex<worker::WorkerLanes>>,
     pub content_store: Arc<dyn ContentStore>,
     pub inbox_paused: Arc<AtomicBool>,
+    /// Maintenance mode (`POST /api/v1/admin/maintenance`): while enabled,
+    /// indexing requests are refused with 503.  Not persisted across restarts.
+    pub maintenance: std::sync::RwLock<find_common::api::MaintenanceStatus>,
     /// Counts consecutive inbox request processing timeouts.  Reset to zero on
     /// the first successful request or when the inbox is manually resumed.
     /// When this reaches `config.server.inbox_timeout_circuit_breaker`, the
@@ -96,6 +110,18 @@ pub struct AppState {
     pub stats_watch: Arc<tokio::sync::watch::Sender<u64>>,
     /// In-memory rate limiter for `GET /api/v1/links/:code`: maps IP → (count, window_start).
     pub link_rate_limiter: std::sync::Mutex<std::collections::HashMap<std::net::IpAddr, (u32, std::time::Instant)>>,
+    /// Reusable source DB connections for request handlers (see `db::pool`).
+    pub db_pool: Arc<db::pool::DbPool>,
+    /// Per-source trigram filters used to skip sources a search cannot match
+    /// (see `db::term_filter`).
+    pub term_filters: Arc<db::term_filter::TermFilters>,
+    /// Slots for per-source search tasks (see `search_limiter`).
+    pub search_limiter: search_limiter::SearchLimiter,
+    /// Latest health reports from find-scan and find-watch.
+    pub client_metrics: client_metrics::ClientMetricsStore,
+    /// `[client_config]`, served to clients; replaced when `server.toml`
+    /// is reloaded.
+    pub client_config: std::sync::RwLock<find_common::config::RemoteClientConfig>,
 }
 
 // ── Server initialisation ──────────────────────────────────────────────────────
@@ -133,12 +159,12 @@ pub async fn create_app_state(config: ServerAppConfig) -> Result<Arc<AppState>>
     std::fs::create_dir_all(data_dir.join("inbox").join("failed"))
         .context("creating inbox directory")?;
 
-    db::check_all_sources(&data_dir.join("sources"))
+    db::check_all_sources(&data_dir.join("sources"), &config.tokens)
         .context("schema version check failed — delete the listed database(s) and re-run `find-scan`")?;
 
     let under_systemd = config.server.force_systemd
         .unwrap_or_else(|| std::env::var("INVOCATION_ID").is_ok());
-    let worker_status = Arc::new(std::sync::Mutex::new(WorkerStatus::Idle));
+    let worker_status = Arc::new(std::sync::Mutex::new(worker::WorkerLanes::default()));
     let inbox_paused = Arc::new(AtomicBool::new(false));
     let consecutive_timeouts = Arc::new(AtomicU32::new(0));
     let content_store: Arc<dyn ContentStore> = open_content_store(&config, &data_dir)
@@ -149,18 +175,29 @@ pub async fn create_app_state(config: ServerAppConfig) -> Result<Arc<AppState>>
     let (recent_tx, _) = tokio::sync::broadcast::channel::<RecentFile>(256);
     let (stats_watch_tx, _stats_watch_rx) = tokio::sync::watch::channel(0u64);
     let stats_watch = Arc::new(stats_watch_tx);
+    let db_pool = Arc::new(db::pool::DbPool::default());
+    let term_filters = Arc::new(db::term_filter::TermFilters::default());
 
     // Open links.db (creates table on first use).
     if let Err(e) = db::links::open_links_db(&data_dir) {
         tracing::warn!("Failed to open links.db (share links will be unavailable): {e:#}");
     }
 
+    // Open annotations.db up front too: creating it lazily on the first
+    // `/file` request would invalidate that response's ETag straight away.
+    if let Err(e) = db::annotations::checkout(&db_pool, &data_dir) {
+        tracing::warn!("Failed to open annotations.db: {e:#}");
+    }
+
+    let search_limiter = search_limiter::SearchLimiter::new(&config.search);
     let state = Arc::new(AppState {
+        client_config: std::sync::RwLock::new(config.client_config.clone()),
         config,
         data_dir: data_dir.clone(),
         worker_status: Arc::clone(&worker_status),
         content_store: Arc::clone(&content_store),
         inbox_paused: Arc::clone(&inbox_paused),
+        maintenance: std::sync::RwLock::new(Default::default()),
         consecutive_timeouts: Arc::clone(&consecutive_timeouts),
         compaction_stats: Arc::clone(&compaction_stats),
         source_stats_cache: Arc::clone(&source_stats_cache),
@@ -169,6 +206,10 @@ pub async fn create_app_state(config: ServerAppConfig) -> Result<Arc<AppState>>
         recent_tx,
         stats_watch: Arc::clone(&stats_watch),
         link_rate_limiter: std::sync::Mutex::new(std::collections::HashMap::new()),
+        db_pool: Arc::clone(&db_pool),
+        term_filters: Arc::clone(&term_filters),
+        search_limiter,
+        client_metrics: client_metrics::ClientMetricsStore::open(&data_dir),
     });
 
     if let Err(e) = worker::recover_stranded_requests(&data_dir).await {
@@ -184,6 +225,15 @@ pub async fn create_app_state(config: ServerAppConfig) -> Result<Arc<AppState>>
         normalization: state.config.normalization.clone(),
         consecutive_timeout_limit: state.config.server.inbox_timeout_circuit_breaker,
         alerts: state.config.alerts.clone(),
+        history: state.config.history.clone(),
+        webhooks: state.config.webhooks.clone(),
+        concurrency: state.config.worker.concurrency,
+        max_attempts: state.config.worker.max_attempts,
+        index_budgets: index_budget::budgets(&state.config),
+        trash_days: state.config.server.trash_days,
+        tokens: state.config.tokens.clone(),
+        part_max_bytes: (state.config.worker.part_max_mb.max(1) * 1024 * 1024) as usize,
+        split_over_bytes: state.config.worker.split_requests_over_mb * 1024 * 1024,
     };
     let worker_handles = worker::WorkerHandles {
         status: worker_status,
@@ -193,13 +243,28 @@ pub async fn create_app_state(config: ServerAppConfig) -> Result<Arc<AppState>>
         recent_tx: state.recent_tx.clone(),
         source_stats_cache: Arc::clone(&source_stats_cache),
         stats_watch: Arc::clone(&stats_watch),
+        term_filters: Arc::clone(&term_filters),
     };
-    let worker_data_dir = data_dir.clone();
-    tokio::spawn(async move {
-        if let Err(e) = worker::start_inbox_worker(worker_data_dir, worker_cfg, worker_handles).await {
-            tracing::error!("Inbox worker failed: {e}");
-        }
-    });
+    if state.config.replica.is_replica() {
+        // Replicas never accept bulk uploads, so there is no inbox to process.
+        replica::start_replica_sync(
+            data_dir.clone(),
+            state.config.replica.clone(),
+            state.config.server.token.clone(),
+            Arc::clone(&content_store),
+            Arc::clone(&source_stats_cache),
+            Arc::clone(&stats_watch),
+            Arc::clone(&db_pool),
+            Arc::clone(&term_filters),
+        );
+    } else {
+        let worker_data_dir = data_dir.clone();
+        tokio::spawn(async move {
+            if let Err(e) = worker::start_inbox_worker(worker_data_dir, worker_cfg, worker_handles).await {
+                tracing::error!("Inbox worker failed: {e}");
+            }
+        });
+    }
 
     let cleanup_data_dir = data_dir.clone();
     tokio::spawn(async move {
@@ -249,55 +314,165 @@ pub async fn create_app_state(config: ServerAppConfig) -> Result<Arc<AppState>>
         }
     });
 
+    // Hourly task to purge trash entries older than `trash_days`.  It runs
+    // with trash off too, so that turning it off empties the trash.
+    let trash_data_dir = data_dir.clone();
+    let trash_days = state.config.server.trash_days;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let dir = trash_data_dir.clone();
+            if let Err(e) = tokio::task::spawn_blocking(move || purge_expired_trash(&dir, trash_days)).await {
+                tracing::warn!("Trash purge failed: {e:#}");
+            }
+        }
+    });
+
     Ok(state)
 }
 
+/// Remove trash entries deleted more than `trash_days` ago from every source.
+fn purge_expired_trash(data_dir: &Path, trash_days: u32) {
+    let before = chrono::Utc::now().timestamp() - i64::from(trash_days) * 86_400;
+    let Ok(rd) = std::fs::read_dir(data_dir.join("sources")) else { return };
+    for entry in rd.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("db") {
+            continue;
+        }
+        let source = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        match db::open(&path).and_then(|conn| db::purge_trash(&conn, before)) {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("[{source}] purged {n} files from the trash"),
+            Err(e) => tracing::warn!("[{source}] trash purge failed: {e:#}"),
+        }
+    }
+}
+
+/// Apply each re-read `server.toml` received on `rx` (see
+/// `find_common::reload`).  The `[log] ignore` patterns and
+/// `[client_config]` take effect at once;
+/// other settings are read from `AppState::config` all over the server, so
+/// changes to them are reported as needing a restart.
+pub async fn apply_config_reloads(state: Arc<AppState>, mut rx: tokio::sync::mpsc::Receiver<String>) {
+    while let Some(text) = rx.recv().await {
+        let config = match parse_server_config(&text) {
+            Ok((config, warnings)) => {
+                for w in &warnings {
+                    tracing::warn!("config reload: {w}");
+                }
+                config
+            }
+            Err(e) => {
+                tracing::warn!("config reload: {e:#}; keeping the running configuration");
+                continue;
+            }
+        };
+        if let Err(e) = find_common::logging::set_ignore_patterns(&config.log.ignore) {
+            tracing::warn!("config reload: invalid log ignore pattern: {e}");
+        }
+        *state.client_config.write().unwrap_or_else(|e| e.into_inner()) = config.client_config.clone();
+        let mut applied = state.config.clone();
+        applied.log.ignore = config.log.ignore.clone();
+        applied.client_config = config.client_config.clone();
+        let pending = find_common::reload::changed_sections(&applied, &config);
+        if pending.is_empty() {
+            tracing::info!("config reloaded");
+        } else {
+            tracing::warn!("config reloaded; restart find-server to apply changes to [{}]", pending.join("], ["));
+        }
+    }
+}
+
+/// gzip/brotli response compression, negotiated via `Accept-Encoding`.
+///
+/// Besides the defaults (images, SSE and tiny bodies are sent as-is), file
+/// downloads that advertise byte ranges (`/raw`, video and PDF viewers) are
+/// left alone: compressing them would drop range support, and most such
+/// files are already compressed.
+fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let ranged = |_: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions| {
+        headers.get(header::ACCEPT_RANGES).is_none_or(|v| v != "bytes")
+    };
+    CompressionLayer::new().compress_when(DefaultPredicate::new().and(ranged))
+}
+
 /// Build the Axum router from the given shared state.
 pub fn build_router(state: Arc<AppState>) -> Router {
     let upload_routes = Router::new()
         .route("/api/v1/upload",        post(routes::upload_init))
         .route("/api/v1/upload/{id}",   patch(routes::upload_patch))
         .route("/api/v1/upload/{id}",   head(routes::upload_status))
+        .route("/api/v1/originals/{hash}", head(routes::head_original).put(routes::put_original))
+        .route("/api/v2/bulk",          post(routes::bulk_v2))
+        .route("/api/v1/bulk/sessions", post(routes::bulk_session_init))
+        .route("/api/v1/bulk/sessions/{id}",
+            get(routes::bulk_session_status).patch(routes::bulk_session_patch).delete(routes::bulk_session_delete))
+        .route("/api/v1/bulk/sessions/{id}/commit", post(routes::bulk_session_commit))
         .layer(DefaultBodyLimit::disable())
         .with_state(Arc::clone(&state));
 
     let app = Router::new()
         .route("/api/v1/sources",        get(routes::list_sources))
         .route("/api/v1/file",           get(routes::get_file))
+        .route("/api/v1/file/preview",   get(routes::get_file_preview))
         .route("/api/v1/files",          get(routes::list_files))
         .route("/api/v1/bulk",           post(routes::bulk))
+        .route("/api/v1/bulk/validate",  post(routes::bulk_validate))
+        .route("/api/v1/hashes/known",   post(routes::known_hashes))
         .route("/api/v1/search",         get(routes::search))
+        .route("/api/v1/search/summary", get(routes::search_summary))
+        .route("/api/v1/search/export", get(routes::search_export))
         .route("/api/v1/context",        get(routes::get_context))
         .route("/api/v1/context-batch",  post(routes::context_batch))
         .route("/api/v1/settings",       get(routes::get_settings))
+        .route("/api/v1/client-config",  get(routes::get_client_config))
         .route("/api/v1/metrics",        get(routes::get_metrics))
+        .route("/api/v1/client-metrics", get(routes::get_client_metrics).post(routes::post_client_metrics))
         .route("/api/v1/stats",          get(routes::get_stats))
         .route("/api/v1/stats/stream",   get(routes::stream_stats))
         .route("/api/v1/errors",         get(routes::get_errors))
+        .route("/api/v1/history",        get(routes::get_history))
         .route("/api/v1/recent",         get(routes::get_recent))
         .route("/api/v1/recent/stream",  get(routes::stream_recent))
         .route("/api/v1/tree",           get(routes::list_dir))
         .route("/api/v1/tree/expand",   get(routes::expand_tree))
         .route("/api/v1/raw",            get(routes::get_raw))
         .route("/api/v1/raw/{source}/{*path}", get(routes::get_raw_path))
+        .route("/api/v1/export",         get(routes::export_member))
         .route("/api/v1/view",           get(routes::get_view))
         .route("/api/v1/links",          post(routes::post_link))
         .route("/api/v1/links/{code}",   get(routes::get_link))
+        .route("/api/v1/annotations",      get(routes::list_annotations).post(routes::post_annotation))
+        .route("/api/v1/annotations/{id}", patch(routes::patch_annotation).delete(routes::delete_annotation))
         .route("/api/v1/auth/session",   post(routes::create_session).delete(routes::delete_session))
         .route("/api/v1/admin/compact",        post(routes::compact))
+        .route("/api/v1/admin/maintain",       post(routes::maintain))
+        .route("/api/v1/admin/reextract",      post(routes::reextract))
         .route("/api/v1/admin/source",         delete(routes::delete_source))
+        .route("/api/v1/admin/source/rename",  post(routes::rename_source))
+        .route("/api/v1/admin/trash",          get(routes::get_trash))
+        .route("/api/v1/admin/trash/restore",  post(routes::restore_trash))
         .route("/api/v1/admin/inbox",          get(routes::inbox_status).delete(routes::inbox_clear))
         .route("/api/v1/admin/inbox/retry",    post(routes::inbox_retry))
         .route("/api/v1/admin/inbox/pause",    post(routes::inbox_pause))
         .route("/api/v1/admin/inbox/resume",   post(routes::inbox_resume))
         .route("/api/v1/admin/inbox/show",     get(routes::inbox_show))
+        .route("/api/v1/admin/maintenance",    get(routes::get_maintenance).post(routes::set_maintenance))
         .route("/api/v1/admin/update/check",   get(routes::update_check))
         .route("/api/v1/admin/update/apply",   post(routes::update_apply))
+        .route("/api/v1/replica/manifest",     get(routes::get_replica_manifest))
+        .route("/api/v1/replica/source",       get(routes::get_replica_source))
+        .route("/api/v1/replica/blobs",        post(routes::post_replica_blobs))
         .fallback(serve_static)
         .layer(DefaultBodyLimit::max(32 * 1024 * 1024))
         .with_state(Arc::clone(&state));
 
     upload_routes.merge(app)
+        .layer(compression_layer())
+        .layer(middleware::from_fn_with_state(Arc::clone(&state), routes::maintenance_guard))
+        .layer(middleware::from_fn_with_state(Arc::clone(&state), routes::replica_guard))
         .layer(middleware::from_fn(routes::log_request))
         .layer(TraceLayer::new_for_http())
 }
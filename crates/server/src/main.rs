@@ -5,7 +5,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 use find_common::config::{default_server_config_path, parse_server_config};
 use find_common::logging::LogIgnoreFilter;
-use find_server::{build_router, create_app_state};
+use find_server::{apply_config_reloads, build_router, create_app_state};
 
 #[derive(Parser)]
 #[command(name = "find-server", about = "find-anything index server", version)]
@@ -48,6 +48,9 @@ async fn main() -> Result<()> {
     }
 
     for w in &config_warnings { warn!("{w}"); }
+    if let Some(profile) = &config.server.profile {
+        tracing::info!("Using the {profile} server profile");
+    }
 
     if let Err(e) = find_common::logging::set_ignore_patterns(&config.log.ignore) {
         tracing::warn!("invalid log ignore pattern: {e}");
@@ -56,6 +59,12 @@ async fn main() -> Result<()> {
     let bind = config.server.bind.clone();
 
     let state = create_app_state(config).await?;
+
+    // Re-read server.toml when it changes or on SIGHUP.
+    let (reload_tx, reload_rx) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(find_common::reload::watch_config_file(config_path.into(), Default::default(), reload_tx));
+    tokio::spawn(apply_config_reloads(state.clone(), reload_rx));
+
     let app = build_router(state);
 
     let listener = tokio::net::TcpListener::bind(&bind)
@@ -0,0 +1,190 @@
+//! Readable snippets for hits on a file's metadata line.
+//!
+//! Extractors store a file's metadata as one line of space-joined tags:
+//! `[EXIF:Make] Canon [EXIF:Model] Canon EOS R5 [EXIF:DateTimeOriginal] 2021-06-12 10:04:51`.
+//! A search hit on that line would show the raw tags, so its snippet is
+//! rewritten to the tags that matched the query, with readable labels, plus
+//! a little context such as the camera or date:
+//! `Camera: Canon EOS R5 · 2021-06-12`.
+
+use find_common::api::{SearchResult, LINE_METADATA};
+
+use crate::db::search::fts_terms;
+
+const SEPARATOR: &str = " · ";
+/// Most tags shown in one snippet.
+const MAX_PARTS: usize = 4;
+/// Context tags are added until the snippet has this many parts.
+const MIN_PARTS: usize = 2;
+
+/// Tags used for context, in order of preference.
+const CONTEXT_TAGS: &[(&str, &str)] = &[
+    ("EXIF", "Model"),
+    ("EXIF", "DateTimeOriginal"),
+    ("TAG", "artist"),
+    ("TAG", "title"),
+    ("VIDEO", "resolution"),
+    ("VIDEO", "duration"),
+];
+
+/// Rewrite the snippet of a hit on the metadata line, whose raw content it
+/// must hold, and mark it as a metadata match.  Hits with no readable tags
+/// stay unmarked.  Content and path hits, and results already formatted (by
+/// a federation peer), are left alone.
+pub fn format_result(result: &mut SearchResult, query: &str) {
+    if result.line_number != LINE_METADATA || result.metadata_match {
+        return;
+    }
+    if let Some(snippet) = format_snippet(&result.snippet, query) {
+        result.snippet = snippet;
+        result.metadata_match = true;
+    }
+}
+
+/// The readable snippet for the metadata line `content`, or `None` when it
+/// holds no tags.
+fn format_snippet(content: &str, query: &str) -> Option<String> {
+    let tags: Vec<Tag> = parse_tags(content).into_iter().filter(|t| !t.value.is_empty()).collect();
+    if tags.is_empty() {
+        return None;
+    }
+
+    let terms: Vec<String> = fts_terms(query).iter().map(|t| t.to_lowercase()).collect();
+    let mut shown: Vec<usize> = (0..tags.len()).filter(|&i| tags[i].matches(&terms)).take(MAX_PARTS).collect();
+    for &(prefix, key) in CONTEXT_TAGS {
+        if shown.len() >= MIN_PARTS {
+            break;
+        }
+        if let Some(i) = tags.iter().position(|t| t.prefix == prefix && t.key == key) {
+            if !shown.contains(&i) {
+                shown.push(i);
+            }
+        }
+    }
+    if shown.is_empty() {
+        shown = (0..tags.len().min(MIN_PARTS)).collect();
+    }
+
+    Some(shown.iter().map(|&i| tags[i].display()).collect::<Vec<_>>().join(SEPARATOR))
+}
+
+struct Tag<'a> {
+    prefix: &'a str,
+    key: &'a str,
+    value: &'a str,
+}
+
+impl Tag<'_> {
+    fn matches(&self, terms: &[String]) -> bool {
+        let key = self.key.to_lowercase();
+        let value = self.value.to_lowercase();
+        terms.iter().any(|t| key.contains(t.as_str()) || value.contains(t.as_str()))
+    }
+
+    /// `Label: value`, or the bare value for dates.
+    fn display(&self) -> String {
+        if let Some(date) = date_only(self.value) {
+            return date;
+        }
+        let label = match (self.prefix, self.key) {
+            ("EXIF", "Model") => "Camera".to_string(),
+            ("EXIF", "LensModel") => "Lens".to_string(),
+            (_, key) => humanize(key),
+        };
+        format!("{label}: {}", self.value)
+    }
+}
+
+/// Split a metadata line into its tags.  A tag opens with `[PREFIX:key]`
+/// (PREFIX in uppercase ASCII) and its value runs to the next opener, so
+/// other square brackets in values are kept.
+fn parse_tags(content: &str) -> Vec<Tag<'_>> {
+    // (start, end, prefix, key) of each opener.
+    let mut openers = Vec::new();
+    let mut pos = 0;
+    while let Some(off) = content[pos..].find('[') {
+        let start = pos + off;
+        pos = start + 1;
+        if let Some((prefix, key, len)) = opener_at(&content[start..]) {
+            openers.push((start, start + len, prefix, key));
+            pos = start + len;
+        }
+    }
+
+    openers
+        .iter()
+        .enumerate()
+        .map(|(n, &(_, end, prefix, key))| {
+            let next = openers.get(n + 1).map_or(content.len(), |o| o.0);
+            Tag { prefix, key, value: content[end..next].trim() }
+        })
+        .collect()
+}
+
+/// The prefix, key and length of a `[PREFIX:key]` opener at the start of `s`.
+fn opener_at(s: &str) -> Option<(&str, &str, usize)> {
+    let close = s.find(']')?;
+    let (prefix, key) = s[1..close].split_once(':')?;
+    let valid = !prefix.is_empty() && prefix.bytes().all(|b| b.is_ascii_uppercase()) && !key.contains('[');
+    valid.then_some((prefix, key, close + 1))
+}
+
+/// The date part of an EXIF-style timestamp (`2021:06:12 10:04:51` or
+/// `2021-06-12 10:04:51`) as `2021-06-12`.
+fn date_only(value: &str) -> Option<String> {
+    let b = value.as_bytes();
+    let is_date = b.len() >= 10
+        && b[..10].iter().enumerate().all(|(i, c)| match i {
+            4 | 7 => *c == b':' || *c == b'-',
+            _ => c.is_ascii_digit(),
+        })
+        && (b.len() == 10 || b[10] == b' ' || b[10] == b'T');
+    is_date.then(|| value[..10].replace(':', "-"))
+}
+
+/// `ExposureTime` → `Exposure time`, `album_artist` → `Album artist`.
+fn humanize(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if c == '_' {
+            out.push(' ');
+            prev_lower = false;
+        } else if c.is_ascii_uppercase() && prev_lower {
+            out.push(' ');
+            out.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else {
+            out.push(if out.is_empty() { c.to_ascii_uppercase() } else { c });
+            prev_lower = c.is_lowercase();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXIF: &str = "[EXIF:Make] Canon [EXIF:Model] Canon EOS R5 [EXIF:ExposureTime] 1/250 s \
+                        [EXIF:DateTimeOriginal] 2021-06-12 10:04:51";
+
+    #[test]
+    fn matched_tag_with_context() {
+        assert_eq!(format_snippet(EXIF, "eos").unwrap(), "Camera: Canon EOS R5 · 2021-06-12");
+        assert_eq!(format_snippet(EXIF, "250").unwrap(), "Exposure time: 1/250 s · Camera: Canon EOS R5");
+    }
+
+    #[test]
+    fn audio_tags_and_bracketed_values() {
+        let content = "[TAG:title] Hello [World] [TAG:album_artist] Jane Doe [AUDIO:codec] MP3";
+        assert_eq!(format_snippet(content, "jane").unwrap(), "Album artist: Jane Doe · Title: Hello [World]");
+    }
+
+    #[test]
+    fn untagged_line_is_left_alone() {
+        assert_eq!(format_snippet("plain text", "plain"), None);
+        assert_eq!(date_only("2021:06:12 10:04:51").as_deref(), Some("2021-06-12"));
+        assert_eq!(date_only("1/250 s"), None);
+    }
+}
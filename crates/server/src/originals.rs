@@ -0,0 +1,109 @@
+//! Original-content store: verbatim copies of indexed files, keyed by the
+//! file's `file_hash`.
+//!
+//! Layout: `data_dir/originals/<hash[0..2]>/<hash>`.  Files are written by
+//! `PUT /api/v1/originals/{hash}` when `[originals] enabled = true` and read
+//! back by `/api/v1/raw` for sources that have no mounted `path`.  Orphaned
+//! originals (no longer referenced by any source DB) are removed by
+//! compaction alongside orphaned content chunks.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use find_common::api::SCANNER_VERSION;
+use find_content_store::ContentKey;
+
+/// Root directory of the originals store.
+pub fn originals_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("originals")
+}
+
+/// `true` when `hash` looks like a `file_hash` (64 lowercase hex characters).
+/// Anything else is rejected before it is used to build a filesystem path.
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Finish `hasher` the way the client's `hash_file` does: blake3 of the
+/// file's bytes followed by [`SCANNER_VERSION`] little-endian.  The result is
+/// the `file_hash` an upload of those bytes must be stored under.
+pub fn finish_hash(mut hasher: blake3::Hasher) -> String {
+    hasher.update(&SCANNER_VERSION.to_le_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Path of the stored original for `hash`.  The caller must validate `hash`
+/// with [`is_valid_hash`] first.
+pub fn original_path(data_dir: &Path, hash: &str) -> PathBuf {
+    originals_dir(data_dir).join(&hash[..2]).join(hash)
+}
+
+/// Remove stored originals whose hash is not in `live_keys`.
+/// Returns `(files_removed, bytes_freed)`; with `dry_run` nothing is deleted.
+pub fn prune(data_dir: &Path, live_keys: &HashSet<ContentKey>, dry_run: bool) -> Result<(usize, u64)> {
+    let root = originals_dir(data_dir);
+    let shards = match std::fs::read_dir(&root) {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let mut removed = 0usize;
+    let mut freed = 0u64;
+    for shard in shards.flatten() {
+        let Ok(entries) = std::fs::read_dir(shard.path()) else { continue };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else { continue };
+            // Leftover partial uploads are never live; valid hashes are kept
+            // when any source DB still references them.
+            if is_valid_hash(name) && live_keys.contains(&ContentKey::new(name)) {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if !dry_run {
+                if let Err(e) = std::fs::remove_file(entry.path()) {
+                    tracing::warn!("failed to remove original {}: {e}", entry.path().display());
+                    continue;
+                }
+            }
+            removed += 1;
+            freed += size;
+        }
+    }
+    Ok((removed, freed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const HASH_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    #[test]
+    fn hash_validation() {
+        assert!(is_valid_hash(HASH_A));
+        assert!(!is_valid_hash(&HASH_A[..63]));
+        assert!(!is_valid_hash(&HASH_A.to_uppercase()));
+        assert!(!is_valid_hash("../../../../etc/passwd/aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"));
+    }
+
+    #[test]
+    fn prune_removes_unreferenced_originals() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        for hash in [HASH_A, HASH_B] {
+            let p = original_path(tmp.path(), hash);
+            std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+            std::fs::write(&p, b"bytes").unwrap();
+        }
+        let live: HashSet<ContentKey> = [ContentKey::new(HASH_A)].into_iter().collect();
+
+        assert_eq!(prune(tmp.path(), &live, true).unwrap(), (1, 5));
+        assert!(original_path(tmp.path(), HASH_B).exists(), "dry run keeps files");
+
+        assert_eq!(prune(tmp.path(), &live, false).unwrap(), (1, 5));
+        assert!(original_path(tmp.path(), HASH_A).exists());
+        assert!(!original_path(tmp.path(), HASH_B).exists());
+    }
+}
@@ -0,0 +1,369 @@
+// crates/server/src/replica.rs
+//
+// Read-only replica support.
+//
+// Primary side: `build_manifest` lists source databases with a cheap change
+// marker, and `snapshot_source` produces a consistent copy for download.
+//
+// Replica side: `start_replica_sync` periodically pulls the manifest, replaces
+// changed source databases, removes deleted ones, and fetches any content
+// blobs referenced by the new databases that are missing locally.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use find_common::api::{
+    ReplicaBlobsRequest, ReplicaBlobsResponse, ReplicaManifest, ReplicaSourceEntry,
+};
+use find_common::config::ReplicaConfig;
+use find_content_store::{ContentKey, ContentStore};
+
+use crate::db::pool::DbPool;
+use crate::db::term_filter::TermFilters;
+use crate::stats_cache::SourceStatsCache;
+
+/// Replica-side record of what has been synced, persisted between restarts.
+const STATE_FILE: &str = "replica_state.json";
+
+/// Number of blobs requested per `POST /api/v1/replica/blobs` call.
+const BLOB_BATCH_SIZE: usize = 200;
+
+// ── Primary side ──────────────────────────────────────────────────────────────
+
+/// List every `sources/*.db` with a change marker derived from the size and
+/// mtime of the database and its WAL file.  Any committed write touches one of
+/// the two, so an unchanged marker means the replica's copy is current.
+pub fn build_manifest(sources_dir: &Path) -> Result<Vec<ReplicaSourceEntry>> {
+    let mut sources = Vec::new();
+    let rd = match std::fs::read_dir(sources_dir) {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(sources),
+        Err(e) => return Err(e).context("reading sources directory"),
+    };
+    for entry in rd.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("db") { continue; }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        let Ok(meta) = entry.metadata() else { continue };
+        let wal = std::fs::metadata(path.with_extension("db-wal")).ok();
+        let version = format!(
+            "{}:{}:{}:{}",
+            mtime_nanos(&meta),
+            meta.len(),
+            wal.as_ref().map(mtime_nanos).unwrap_or(0),
+            wal.as_ref().map(|m| m.len()).unwrap_or(0),
+        );
+        sources.push(ReplicaSourceEntry { name: name.to_string(), version, size: meta.len() });
+    }
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sources)
+}
+
+fn mtime_nanos(meta: &std::fs::Metadata) -> u128 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+/// Write a consistent snapshot of `db_path` to a temporary file under
+/// `tmp_dir` using `VACUUM INTO`.  The file is deleted when the returned
+/// `TempPath` is dropped.
+pub fn snapshot_source(db_path: &Path, tmp_dir: &Path) -> Result<tempfile::TempPath> {
+    let tmp = tempfile::Builder::new()
+        .prefix("replica-snapshot-")
+        .suffix(".tmp")
+        .tempfile_in(tmp_dir)
+        .context("creating snapshot temp file")?
+        .into_temp_path();
+    // VACUUM INTO refuses to overwrite an existing file.
+    std::fs::remove_file(&tmp).context("clearing snapshot temp file")?;
+
+    let conn = crate::db::open_for_stats(db_path)?;
+    conn.execute("VACUUM INTO ?1", [tmp.to_string_lossy()])
+        .with_context(|| format!("snapshotting {}", db_path.display()))?;
+    Ok(tmp)
+}
+
+// ── Replica side ──────────────────────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize, Default)]
+struct ReplicaState {
+    sources: HashMap<String, SyncedSource>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncedSource {
+    /// Manifest version of the database currently on disk.
+    version: String,
+    /// False while blobs referenced by the database are still missing locally
+    /// (e.g. the primary had not archived them yet); retried every pass.
+    blobs_complete: bool,
+}
+
+impl ReplicaState {
+    fn load(data_dir: &Path) -> Self {
+        std::fs::read(data_dir.join(STATE_FILE))
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, data_dir: &Path) -> Result<()> {
+        let tmp = data_dir.join(format!("{STATE_FILE}.tmp"));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, data_dir.join(STATE_FILE))?;
+        Ok(())
+    }
+}
+
+/// Outcome of one sync pass.
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub sources_updated: usize,
+    pub sources_removed: usize,
+    pub blobs_fetched: usize,
+}
+
+impl SyncSummary {
+    fn changed(&self) -> bool {
+        self.sources_updated > 0 || self.sources_removed > 0 || self.blobs_fetched > 0
+    }
+}
+
+/// Spawn the periodic replica sync task.
+#[allow(clippy::too_many_arguments)]
+pub fn start_replica_sync(
+    data_dir: PathBuf,
+    cfg: ReplicaConfig,
+    own_token: String,
+    content_store: Arc<dyn ContentStore>,
+    source_stats_cache: Arc<std::sync::RwLock<SourceStatsCache>>,
+    stats_watch: Arc<tokio::sync::watch::Sender<u64>>,
+    db_pool: Arc<DbPool>,
+    term_filters: Arc<TermFilters>,
+) {
+    let Some(primary_url) = cfg.primary_url.clone() else { return };
+    let token = cfg.primary_token.clone().unwrap_or(own_token);
+    let interval_secs = cfg.sync_interval_secs.max(1);
+    tracing::info!("Replica mode: syncing from {primary_url} every {interval_secs}s");
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let dd = data_dir.clone();
+            let url = primary_url.clone();
+            let tok = token.clone();
+            let cs = Arc::clone(&content_store);
+            let pool = Arc::clone(&db_pool);
+            let filters = Arc::clone(&term_filters);
+            let result = tokio::task::spawn_blocking(move || sync_once(&dd, &url, &tok, cs.as_ref(), &pool, &filters))
+                .await
+                .unwrap_or_else(|e| Err(anyhow::anyhow!(e)));
+            match result {
+                Ok(summary) if summary.changed() => {
+                    tracing::info!(
+                        "Replica sync: {} source(s) updated, {} removed, {} blob(s) fetched",
+                        summary.sources_updated, summary.sources_removed, summary.blobs_fetched,
+                    );
+                    let cache = Arc::clone(&source_stats_cache);
+                    let cs = Arc::clone(&content_store);
+                    let dd = data_dir.clone();
+                    tokio::task::spawn_blocking(move || {
+                        crate::stats_cache::full_rebuild(&dd, &cache, &cs);
+                    }).await.ok();
+                    stats_watch.send_modify(|v| *v = v.wrapping_add(1));
+                }
+                Ok(_) => tracing::debug!("Replica sync: up to date"),
+                Err(e) => tracing::warn!("Replica sync failed: {e:#}"),
+            }
+        }
+    });
+}
+
+/// Run one sync pass against the primary at `primary_url`.
+pub fn sync_once(
+    data_dir: &Path,
+    primary_url: &str,
+    token: &str,
+    content_store: &dyn ContentStore,
+    db_pool: &DbPool,
+    term_filters: &TermFilters,
+) -> Result<SyncSummary> {
+    let base = primary_url.trim_end_matches('/');
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .timeout(std::time::Duration::from_secs(3600))
+        .build()?;
+    let sources_dir = data_dir.join("sources");
+    let mut state = ReplicaState::load(data_dir);
+    let mut summary = SyncSummary::default();
+
+    let manifest: ReplicaManifest = client
+        .get(format!("{base}/api/v1/replica/manifest"))
+        .bearer_auth(token)
+        .send()?
+        .error_for_status()
+        .context("fetching replica manifest")?
+        .json()?;
+
+    // Remove sources that no longer exist on the primary.
+    let live: std::collections::HashSet<&str> =
+        manifest.sources.iter().map(|s| s.name.as_str()).collect();
+    let removed: Vec<String> = state.sources.keys()
+        .filter(|name| !live.contains(name.as_str()))
+        .cloned()
+        .collect();
+    for name in removed {
+        let db_path = sources_dir.join(format!("{name}.db"));
+        db_pool.evict(&db_path);
+        term_filters.evict(&db_path);
+        remove_db_files(&db_path);
+        state.sources.remove(&name);
+        summary.sources_removed += 1;
+    }
+    state.save(data_dir)?;
+
+    for entry in &manifest.sources {
+        let db_path = sources_dir.join(format!("{}.db", entry.name));
+        let current = state.sources.get(&entry.name);
+        let up_to_date = current.is_some_and(|s| s.version == entry.version) && db_path.exists();
+
+        if !up_to_date {
+            download_source(&client, base, token, &entry.name, &db_path, db_pool)?;
+            term_filters.evict(&db_path);
+            state.sources.insert(entry.name.clone(), SyncedSource {
+                version: entry.version.clone(),
+                blobs_complete: false,
+            });
+            state.save(data_dir)?;
+            summary.sources_updated += 1;
+        }
+
+        let synced = state.sources.get_mut(&entry.name).expect("inserted above");
+        if !synced.blobs_complete {
+            let (fetched, complete) = fetch_missing_blobs(&client, base, token, &db_path, content_store)?;
+            summary.blobs_fetched += fetched;
+            synced.blobs_complete = complete;
+            state.save(data_dir)?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Download a snapshot of `name` and atomically replace `db_path` with it.
+fn download_source(
+    client: &reqwest::blocking::Client,
+    base: &str,
+    token: &str,
+    name: &str,
+    db_path: &Path,
+    db_pool: &DbPool,
+) -> Result<()> {
+    let part = db_path.with_extension("db.part");
+    let mut resp = client
+        .get(format!("{base}/api/v1/replica/source"))
+        .query(&[("name", name)])
+        .bearer_auth(token)
+        .send()?
+        .error_for_status()
+        .with_context(|| format!("downloading source '{name}'"))?;
+    {
+        let mut file = std::fs::File::create(&part)
+            .with_context(|| format!("creating {}", part.display()))?;
+        resp.copy_to(&mut file).with_context(|| format!("writing {}", part.display()))?;
+        file.sync_all()?;
+    }
+    // Pooled handles would keep reading the replaced file.  Evict before the
+    // rename to release them, and again after it for any opened meanwhile.
+    db_pool.evict(db_path);
+    std::fs::rename(&part, db_path)
+        .with_context(|| format!("replacing {}", db_path.display()))?;
+    // A WAL left over from the previous copy must never be replayed onto the
+    // new file.  Replica connections are read-only, so any WAL is frame-free.
+    let _ = std::fs::remove_file(db_path.with_extension("db-wal"));
+    let _ = std::fs::remove_file(db_path.with_extension("db-shm"));
+    db_pool.evict(db_path);
+    Ok(())
+}
+
+/// Fetch blobs referenced by `db_path` that are not in the local content
+/// store.  Returns `(fetched, complete)` where `complete` is false if the
+/// primary could not supply every missing blob.
+fn fetch_missing_blobs(
+    client: &reqwest::blocking::Client,
+    base: &str,
+    token: &str,
+    db_path: &Path,
+    content_store: &dyn ContentStore,
+) -> Result<(usize, bool)> {
+    let hashes: Vec<String> = {
+        let conn = crate::db::open_for_stats(db_path)?;
+        let mut stmt = conn.prepare("SELECT DISTINCT file_hash FROM files WHERE file_hash IS NOT NULL")?;
+        let rows = stmt.query_map([], |r| r.get(0))?.collect::<rusqlite::Result<_>>()?;
+        rows
+    };
+    let missing: Vec<String> = hashes.into_iter()
+        .filter(|h| !content_store.contains(&ContentKey::new(h.as_str())).unwrap_or(false))
+        .collect();
+
+    let mut fetched = 0usize;
+    for batch in missing.chunks(BLOB_BATCH_SIZE) {
+        let resp: ReplicaBlobsResponse = client
+            .post(format!("{base}/api/v1/replica/blobs"))
+            .bearer_auth(token)
+            .json(&ReplicaBlobsRequest { keys: batch.to_vec() })
+            .send()?
+            .error_for_status()
+            .context("fetching replica blobs")?
+            .json()?;
+        for blob in resp.blobs {
+            content_store.put(&ContentKey::new(blob.key.as_str()), &blob.content)?;
+            fetched += 1;
+        }
+    }
+    Ok((fetched, fetched == missing.len()))
+}
+
+fn remove_db_files(db_path: &Path) {
+    for ext in ["db", "db-wal", "db-shm"] {
+        let _ = std::fs::remove_file(db_path.with_extension(ext));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_lists_only_db_files_sorted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("beta.db"), b"x").unwrap();
+        std::fs::write(dir.path().join("alpha.db"), b"xy").unwrap();
+        std::fs::write(dir.path().join("alpha.db-wal"), b"").unwrap();
+        std::fs::write(dir.path().join("gamma.db.part"), b"").unwrap();
+
+        let m = build_manifest(dir.path()).unwrap();
+        let names: Vec<&str> = m.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "beta"]);
+        assert_eq!(m[0].size, 2);
+    }
+
+    #[test]
+    fn manifest_version_changes_when_wal_grows() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("src.db"), b"x").unwrap();
+        let before = build_manifest(dir.path()).unwrap()[0].version.clone();
+        std::fs::write(dir.path().join("src.db-wal"), b"frames").unwrap();
+        let after = build_manifest(dir.path()).unwrap()[0].version.clone();
+        assert_ne!(before, after);
+    }
+}
@@ -15,17 +15,23 @@ use std::sync::atomic::Ordering;
 
 use find_common::api::{
     InboxDeleteResponse, InboxItem, InboxPauseResponse, InboxResumeResponse, InboxRetryResponse,
-    InboxShowFile, InboxShowResponse, InboxStatusResponse, SourceDeleteResponse,
+    InboxShowFile, InboxShowResponse, InboxStatusResponse, MaintainRequest, MaintainResponse,
+    MaintenanceRequest, MaintenanceStatus,
+    ReextractRequest, ReextractResponse, SourceDeleteResponse, SourceMaintainResult,
+    SourceReextractResult, SourceRenameRequest, SourceRenameResponse,
     UpdateApplyResponse, UpdateCheckResponse, WorkerQueueSlot, LINE_CONTENT_START,
 };
 
 use crate::{AppState, CachedUpdateCheck};
 use crate::db;
+use crate::worker::quarantine;
 
 use super::{check_auth, run_blocking, source_db_path};
 
 const GITHUB_REPO: &str = "jamietre/find-anything";
 const UPDATE_CACHE_TTL: Duration = Duration::from_secs(3600);
+/// `Retry-After` sent while in maintenance mode when none was given.
+const DEFAULT_MAINTENANCE_RETRY_SECS: u64 = 300;
 
 // ── GET /api/v1/admin/inbox ───────────────────────────────────────────────────
 
@@ -65,7 +71,8 @@ pub async fn inbox_status(
                         .and_then(|m| now.duration_since(m).ok())
                         .map(|d| d.as_secs())
                         .unwrap_or(0);
-                    items.push(InboxItem { filename, size_bytes, age_secs });
+                    let failure = quarantine::read_failure(&path);
+                    items.push(InboxItem { filename, size_bytes, age_secs, failure });
                 }
             }
             items.sort_by(|a, b| a.filename.cmp(&b.filename));
@@ -127,6 +134,8 @@ pub async fn inbox_clear(
                 if path.extension().map(|x| x == "gz").unwrap_or(false)
                     && std::fs::remove_file(&path).is_ok()
                 {
+                    let _ = std::fs::remove_file(quarantine::attempts_sidecar(&path));
+                    let _ = std::fs::remove_file(quarantine::error_sidecar(&path));
                     count += 1;
                 }
             }
@@ -167,6 +176,9 @@ pub async fn inbox_retry(
             if path.extension().map(|x| x == "gz").unwrap_or(false) {
                 let dest = inbox_dir.join(entry.file_name());
                 if std::fs::rename(&path, &dest).is_ok() {
+                    // A manual retry starts with a fresh attempt count.
+                    let _ = std::fs::remove_file(quarantine::error_sidecar(&path));
+                    let _ = std::fs::remove_file(quarantine::attempts_sidecar(&dest));
                     count += 1;
                 }
             }
@@ -229,6 +241,53 @@ pub async fn inbox_resume(
     (StatusCode::OK, Json(InboxResumeResponse {})).into_response()
 }
 
+// ── GET /api/v1/admin/maintenance ────────────────────────────────────────────
+
+pub async fn get_maintenance(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    let status = state.maintenance.read().unwrap_or_else(|e| e.into_inner()).clone();
+    (StatusCode::OK, Json(status)).into_response()
+}
+
+// ── POST /api/v1/admin/maintenance ───────────────────────────────────────────
+
+pub async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<MaintenanceRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let mut maintenance = state.maintenance.write().unwrap_or_else(|e| e.into_inner());
+    if req.enabled {
+        *maintenance = MaintenanceStatus {
+            enabled: true,
+            reason: req.reason.filter(|r| !r.trim().is_empty()),
+            // Turning it on again (e.g. to change the reason) keeps the start time.
+            since: maintenance.since.or_else(|| Some(chrono::Utc::now().timestamp())),
+            retry_after_secs: req.retry_after_secs.unwrap_or(DEFAULT_MAINTENANCE_RETRY_SECS),
+        };
+        tracing::info!(
+            "Maintenance mode on{}: refusing indexing requests",
+            maintenance.reason.as_deref().map(|r| format!(" ({r})")).unwrap_or_default(),
+        );
+    } else {
+        if maintenance.enabled {
+            tracing::info!("Maintenance mode off: accepting indexing requests");
+        }
+        *maintenance = MaintenanceStatus::default();
+    }
+
+    (StatusCode::OK, Json(maintenance.clone())).into_response()
+}
+
 // ── GET /api/v1/admin/inbox/show ──────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -355,12 +414,13 @@ async fn fetch_latest(state: &AppState) -> anyhow::Result<(String, Option<String
     Ok((latest_version, asset_url))
 }
 
+fn parse_version(v: &str) -> Option<(u64, u64, u64)> {
+    let mut p = v.split('.');
+    Some((p.next()?.parse().ok()?, p.next()?.parse().ok()?, p.next()?.parse().ok()?))
+}
+
 fn version_gt(a: &str, b: &str) -> bool {
-    fn parse(v: &str) -> Option<(u64, u64, u64)> {
-        let mut p = v.split('.');
-        Some((p.next()?.parse().ok()?, p.next()?.parse().ok()?, p.next()?.parse().ok()?))
-    }
-    match (parse(a), parse(b)) {
+    match (parse_version(a), parse_version(b)) {
         (Some(a), Some(b)) => a > b,
         _ => false,
     }
@@ -617,6 +677,141 @@ pub async fn compact(
     }).await
 }
 
+// ── POST /api/v1/admin/maintain ───────────────────────────────────────────────
+
+pub async fn maintain(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<MaintainRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let db_paths = match &req.source {
+        Some(name) => match source_db_path(&state, name) {
+            Ok(p) if p.exists() => vec![(name.clone(), p)],
+            Ok(_) => {
+                return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "source not found" }))).into_response();
+            }
+            Err(s) => return (s, Json(serde_json::Value::Null)).into_response(),
+        },
+        None => list_source_dbs(&state.data_dir),
+    };
+
+    let data_dir      = state.data_dir.clone();
+    let content_store = Arc::clone(&state.content_store);
+
+    run_blocking("maintain", move || -> anyhow::Result<_> {
+        let mut sources = Vec::with_capacity(db_paths.len());
+        for (name, path) in db_paths {
+            let bytes_before = db_size_on_disk(&path);
+            let conn = db::open(&path)?;
+            db::maintain(&conn, req.vacuum, req.optimize_fts)
+                .with_context(|| format!("maintaining source {name}"))?;
+            drop(conn);
+            let bytes_after = db_size_on_disk(&path);
+            tracing::info!(
+                source = %name,
+                "maintain: {} -> {}",
+                find_common::mem::fmt_bytes(bytes_before),
+                find_common::mem::fmt_bytes(bytes_after),
+            );
+            sources.push(SourceMaintainResult { source: name, bytes_before, bytes_after });
+        }
+
+        let compact = if req.gc_chunks {
+            Some(crate::compaction::compact_archives(&data_dir, &content_store, false)?)
+        } else {
+            None
+        };
+        Ok(Json(MaintainResponse { sources, compact }))
+    }).await
+}
+
+/// `(name, path)` of every source DB, by name.
+fn list_source_dbs(data_dir: &std::path::Path) -> Vec<(String, std::path::PathBuf)> {
+    let Ok(rd) = std::fs::read_dir(data_dir.join("sources")) else {
+        return vec![];
+    };
+    let mut dbs: Vec<_> = rd
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("db"))
+        .filter_map(|p| Some((p.file_stem()?.to_str()?.to_string(), p)))
+        .collect();
+    dbs.sort();
+    dbs
+}
+
+/// Size of a SQLite DB including its WAL.
+fn db_size_on_disk(path: &std::path::Path) -> u64 {
+    [path.to_path_buf(), with_suffix(path, "-wal")]
+        .iter()
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// `path` with `suffix` appended to the file name (`x.db` → `x.db-wal`).
+fn with_suffix(path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(suffix);
+    std::path::PathBuf::from(s)
+}
+
+// ── POST /api/v1/admin/reextract ──────────────────────────────────────────────
+
+pub async fn reextract(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<ReextractRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let before = req.before.clone().unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+    if parse_version(&before).is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": format!("'{before}' is not a version like 1.2.3") })),
+        ).into_response();
+    }
+
+    let db_paths = match &req.source {
+        Some(name) => match source_db_path(&state, name) {
+            Ok(p) if p.exists() => vec![(name.clone(), p)],
+            Ok(_) => {
+                return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "source not found" }))).into_response();
+            }
+            Err(s) => return (s, Json(serde_json::Value::Null)).into_response(),
+        },
+        None => list_source_dbs(&state.data_dir),
+    };
+
+    run_blocking("reextract", move || -> anyhow::Result<_> {
+        let mut sources = Vec::with_capacity(db_paths.len());
+        for (name, path) in db_paths {
+            let conn = db::open(&path)?;
+            let files = db::queue_reextract(
+                &conn,
+                req.kind.as_ref(),
+                req.extractor.as_deref(),
+                req.include_unrecorded,
+                |version| version_gt(&before, version),
+                req.dry_run,
+            )
+            .with_context(|| format!("queueing re-extraction in source {name}"))?;
+            if files > 0 && !req.dry_run {
+                tracing::info!(source = %name, files, "queued files extracted before {before} for re-extraction");
+            }
+            sources.push(SourceReextractResult { source: name, files });
+        }
+        Ok(Json(ReextractResponse { before, sources }))
+    }).await
+}
+
 // ── DELETE /api/v1/admin/source ───────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -643,8 +838,11 @@ pub async fn delete_source(
     }
 
     let source_name = query.source.clone();
+    let data_dir = state.data_dir.clone();
     let source_stats_cache = Arc::clone(&state.source_stats_cache);
     let stats_watch = Arc::clone(&state.stats_watch);
+    let pool = Arc::clone(&state.db_pool);
+    let term_filters = Arc::clone(&state.term_filters);
 
     let resp = run_blocking("delete_source", move || -> anyhow::Result<_> {
         let conn = db::open(&db_path)?;
@@ -656,11 +854,15 @@ pub async fn delete_source(
             "source deleted — orphaned blobs reclaimed at next compaction"
         );
 
-        // Close the DB before deleting it.
+        // Close the DB, and any pooled handles to it, before deleting it.
         drop(conn);
+        pool.evict(&db_path);
+        term_filters.evict(&db_path);
 
         std::fs::remove_file(&db_path)
             .with_context(|| format!("removing {}", db_path.display()))?;
+        let annotations_conn = db::annotations::checkout(&pool, &data_dir)?;
+        db::annotations::delete_for_source(&annotations_conn, &source_name)?;
 
         // chunks_removed is 0: orphaned blobs in content.db are collected by
         // the next scheduled compaction pass rather than eagerly removed here.
@@ -677,3 +879,89 @@ pub async fn delete_source(
 
     resp
 }
+
+// ── POST /api/v1/admin/source/rename ─────────────────────────────────────────
+
+pub async fn rename_source(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<SourceRenameRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let (from_path, to_path) = match (source_db_path(&state, &req.from), source_db_path(&state, &req.to)) {
+        (Ok(f), Ok(t)) => (f, t),
+        (Err(s), _) | (_, Err(s)) => return (s, Json(serde_json::Value::Null)).into_response(),
+    };
+    if req.to.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": "new name is empty" }))).into_response();
+    }
+
+    if !from_path.exists() {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "source not found" }))).into_response();
+    }
+    if to_path.exists() {
+        return (StatusCode::CONFLICT, Json(serde_json::json!({ "error": "target source already exists" }))).into_response();
+    }
+
+    // A running request for the old name would keep writing to the moved
+    // file, and a queued one would create `from.db` again.
+    let running = state.worker_status.lock().map(|w| w.has_lane(&req.from)).unwrap_or(true);
+    let inbox_dir = state.data_dir.join("inbox");
+    let from = req.from.clone();
+    let queued = tokio::task::spawn_blocking(move || crate::worker::has_queued_request(&inbox_dir, &from))
+        .await
+        .unwrap_or(true);
+    if running || queued {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "source has inbox requests pending; retry once they are indexed" })),
+        ).into_response();
+    }
+
+    let (from, to) = (req.from.clone(), req.to.clone());
+    let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
+    let term_filters = Arc::clone(&state.term_filters);
+
+    let resp = run_blocking("rename_source", move || -> anyhow::Result<_> {
+        let conn = db::open(&from_path)?;
+        let files = db::count_files(&conn)?;
+
+        // Close the DB, and any pooled handles to it, before moving it.
+        drop(conn);
+        pool.evict(&from_path);
+        term_filters.evict(&from_path);
+
+        std::fs::rename(&from_path, &to_path)
+            .with_context(|| format!("renaming {} to {}", from_path.display(), to_path.display()))?;
+        // SQLite's WAL and shared-memory files belong with the DB; they are
+        // normally gone once the last handle closes, but move any left over.
+        for suffix in ["-wal", "-shm"] {
+            let side = with_suffix(&from_path, suffix);
+            if side.exists() {
+                std::fs::rename(&side, with_suffix(&to_path, suffix))
+                    .with_context(|| format!("renaming {}", side.display()))?;
+            }
+        }
+
+        let annotations_conn = db::annotations::checkout(&pool, &data_dir)?;
+        let annotations = db::annotations::rename_source(&annotations_conn, &from, &to)?;
+        let links_conn = db::links::open_links_db(&data_dir)?;
+        let links = db::links::rename_source(&links_conn, &from, &to)?;
+
+        tracing::info!(from = %from, to = %to, files, annotations, links, "source renamed");
+        Ok(Json(SourceRenameResponse { files, annotations, links }))
+    }).await;
+
+    if let Ok(mut guard) = state.source_stats_cache.write() {
+        if let Some(s) = guard.sources.iter_mut().find(|s| s.name == req.from) {
+            s.name = req.to.clone();
+        }
+    }
+    state.stats_watch.send_modify(|v| *v = v.wrapping_add(1));
+
+    resp
+}
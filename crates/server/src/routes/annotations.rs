@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use find_common::api::{
+    Annotation, ContextLine, CreateAnnotationRequest, UpdateAnnotationRequest,
+};
+
+use crate::{db, AppState};
+
+use super::{check_auth, run_blocking, source_db_path};
+
+/// Longest accepted annotation body, in characters.  Annotations are short
+/// review notes, not documents.
+const MAX_BODY_CHARS: usize = 4000;
+
+// ── GET /api/v1/annotations?source=X&path=Y ───────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct AnnotationParams {
+    pub source: String,
+    pub path: String,
+}
+
+pub async fn list_annotations(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<AnnotationParams>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    if let Err(s) = source_db_path(&state, &params.source) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
+    run_blocking("list_annotations", move || {
+        let conn = db::annotations::checkout(&pool, &data_dir)?;
+        Ok(Json(db::annotations::list_for_file(&conn, &params.source, &params.path)?))
+    }).await
+}
+
+// ── POST /api/v1/annotations ──────────────────────────────────────────────────
+
+pub async fn post_annotation(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<CreateAnnotationRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    if let Err(s) = source_db_path(&state, &req.source) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    if let Some(resp) = invalid_body(&req.body) {
+        return resp;
+    }
+    let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
+    run_blocking("post_annotation", move || -> anyhow::Result<Response> {
+        let conn = db::annotations::checkout(&pool, &data_dir)?;
+        let created = db::annotations::create(
+            &conn, &req.source, &req.path, req.line_number, req.body.trim(), req.author.as_deref(),
+        )?;
+        Ok((StatusCode::CREATED, Json(created)).into_response())
+    }).await
+}
+
+// ── PATCH/DELETE /api/v1/annotations/{id} ─────────────────────────────────────
+
+pub async fn patch_annotation(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateAnnotationRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    if let Some(resp) = invalid_body(&req.body) {
+        return resp;
+    }
+    let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
+    run_blocking("patch_annotation", move || -> anyhow::Result<Response> {
+        let conn = db::annotations::checkout(&pool, &data_dir)?;
+        Ok(match db::annotations::update(&conn, id, req.body.trim())? {
+            Some(a) => Json(a).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        })
+    }).await
+}
+
+pub async fn delete_annotation(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+    let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
+    run_blocking("delete_annotation", move || -> anyhow::Result<Response> {
+        let conn = db::annotations::checkout(&pool, &data_dir)?;
+        Ok(if db::annotations::delete(&conn, id)? {
+            StatusCode::NO_CONTENT.into_response()
+        } else {
+            StatusCode::NOT_FOUND.into_response()
+        })
+    }).await
+}
+
+/// 400 response when `body` is empty or longer than [`MAX_BODY_CHARS`].
+fn invalid_body(body: &str) -> Option<Response> {
+    let body = body.trim();
+    if body.is_empty() || body.chars().count() > MAX_BODY_CHARS {
+        return Some((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": format!("annotation body must be 1–{MAX_BODY_CHARS} characters") })),
+        ).into_response());
+    }
+    None
+}
+
+// ── Helpers for file/context responses ────────────────────────────────────────
+
+/// Annotations to attach to a context window: file-level notes plus notes on
+/// any line between the first and last returned line.  Lookup failures are
+/// logged and yield no annotations rather than failing the context request.
+pub(super) fn window_annotations(
+    conn: &Connection,
+    source: &str,
+    path: &str,
+    lines: &[ContextLine],
+) -> Vec<Annotation> {
+    let lo = lines.first().map_or(0, |l| l.line_number);
+    let hi = lines.last().map_or(0, |l| l.line_number);
+    db::annotations::list_for_lines(conn, source, path, lo, hi).unwrap_or_else(|e| {
+        tracing::warn!("annotations for {source}/{path}: {e:#}");
+        vec![]
+    })
+}
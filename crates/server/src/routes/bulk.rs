@@ -1,15 +1,25 @@
 use std::sync::Arc;
 
 use axum::{
-    body::Bytes,
+    body::{Body, Bytes},
     extract::State,
     http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
+    Json,
 };
+use flate2::read::GzDecoder;
+
+use find_common::api::{BulkProblem, BulkRequest, BulkValidateResponse, KnownHashesRequest, KnownHashesResponse};
+use find_content_store::ContentKey;
 
 use crate::AppState;
 
-use super::check_auth;
+use super::{check_auth, run_blocking};
+
+/// Maximum number of problems reported by `/api/v1/bulk/validate`.
+const MAX_PROBLEMS: usize = 100;
+/// Maximum number of hashes checked by one `/api/v1/hashes/known` request.
+const MAX_KNOWN_HASHES: usize = 10_000;
 
 // ── POST /api/v1/bulk ─────────────────────────────────────────────────────────
 
@@ -20,21 +30,11 @@ pub async fn bulk(
 ) -> impl IntoResponse {
     if let Err(s) = check_auth(&state, &headers) { return s.into_response(); }
 
-    let is_gzip = headers
-        .get(header::CONTENT_ENCODING)
-        .and_then(|v| v.to_str().ok())
-        .map(|v| v == "gzip")
-        .unwrap_or(false);
-
-    if !is_gzip {
+    if !is_gzip(&headers) {
         return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
     }
 
-    let request_id = format!(
-        "req_{}_{}",
-        chrono::Utc::now().format("%Y%m%d_%H%M%S"),
-        uuid::Uuid::new_v4().simple()
-    );
+    let request_id = new_request_id();
 
     let inbox_path = state.data_dir.join("inbox").join(format!("{request_id}.gz"));
 
@@ -49,3 +49,275 @@ pub async fn bulk(
         }
     }
 }
+
+// ── POST /api/v2/bulk ─────────────────────────────────────────────────────────
+
+/// Bulk protocol v2: a gzip-compressed NDJSON stream of unbounded size whose
+/// first line is a `BulkRequest` header (source, deletes, renames, scan
+/// timestamp, failures) and every following line one `IndexFile`.  Several
+/// concatenated gzip members are accepted, so a client can compress and send
+/// the stream in chunks.  The body is streamed to the inbox as
+/// `<id>.ndjson.gz`; the worker splits it into ordinary requests.
+pub async fn bulk_v2(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Body,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) { return s.into_response(); }
+    if !is_gzip(&headers) {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    }
+
+    let inbox_dir = state.data_dir.join("inbox");
+    let request_id = new_request_id();
+    // Not `.gz` until complete, so the worker never sees a partial stream.
+    let tmp = inbox_dir.join(format!("{request_id}.ndjson.part"));
+    let dest = inbox_dir.join(format!("{request_id}{}", crate::worker::stream::STREAM_SUFFIX));
+
+    let result = match super::originals::write_body(&tmp, body, u64::MAX, None).await {
+        Ok(()) => tokio::fs::rename(&tmp, &dest).await.map_err(|e| {
+            tracing::error!("Failed to queue streamed bulk request: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }),
+        Err(s) => Err(s),
+    };
+    match result {
+        Ok(()) => {
+            tracing::debug!("Queued streamed bulk request: {}", dest.display());
+            StatusCode::ACCEPTED.into_response()
+        }
+        Err(s) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            s.into_response()
+        }
+    }
+}
+
+// ── POST /api/v1/bulk/validate ────────────────────────────────────────────────
+
+/// Decode a bulk payload the way the worker does and lint it, reporting counts
+/// and problems without queueing anything.  The checks are advisory: they
+/// flag payloads a well-behaved client should not send, but `POST
+/// /api/v1/bulk` does not apply them, and a payload that passes can still
+/// fail in the worker (an unknown source DB schema, a full index budget).  A
+/// payload that fails to decode is still a 200 with `valid: false`; the
+/// decode error is the single problem.
+pub async fn bulk_validate(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) { return s.into_response(); }
+    if !is_gzip(&headers) {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    }
+
+    run_blocking("bulk_validate", move || {
+        let report = match serde_json::from_reader::<_, BulkRequest>(GzDecoder::new(&body[..])) {
+            Ok(req) => validate(&req),
+            Err(e) => BulkValidateResponse {
+                problems: vec![BulkProblem { path: None, message: format!("invalid payload: {e}") }],
+                ..Default::default()
+            },
+        };
+        Ok(Json(report))
+    }).await
+}
+
+// ── POST /api/v1/hashes/known ─────────────────────────────────────────────────
+
+/// Report which of the given content hashes the content store already holds,
+/// so the client can send those files with `reuse_content` instead of
+/// extracting and uploading them again.
+pub async fn known_hashes(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<KnownHashesRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) { return s.into_response(); }
+    if req.hashes.len() > MAX_KNOWN_HASHES {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+
+    let cs = Arc::clone(&state.content_store);
+    run_blocking("known_hashes", move || {
+        let mut known = Vec::new();
+        for hash in req.hashes {
+            if cs.contains(&ContentKey::new(hash.as_str()))? {
+                known.push(hash);
+            }
+        }
+        Ok(Json(KnownHashesResponse { known }))
+    }).await
+}
+
+/// Inbox file stem for a new request.  Sub-second precision keeps names in
+/// arrival order when two requests land within the filesystem's mtime
+/// granularity.
+pub(super) fn new_request_id() -> String {
+    format!(
+        "req_{}_{}",
+        chrono::Utc::now().format("%Y%m%d_%H%M%S_%9f"),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+fn is_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == "gzip")
+        .unwrap_or(false)
+}
+
+/// Counts and lint problems for a decoded bulk request.
+fn validate(req: &BulkRequest) -> BulkValidateResponse {
+    let mut problems: Vec<BulkProblem> = Vec::new();
+    let mut add = |path: Option<&str>, message: String| {
+        problems.push(BulkProblem { path: path.map(str::to_owned), message });
+    };
+
+    if req.source.is_empty()
+        || !req.source.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        add(None, format!("invalid source name {:?}: use letters, digits, '-' and '_'", req.source));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    for file in &req.files {
+        if let Some(msg) = path_problem(&file.path) {
+            add(Some(&file.path), msg.to_string());
+        }
+        if !seen.insert(file.path.as_str()) {
+            add(Some(&file.path), "path appears more than once in files".to_string());
+        }
+        let mut line_numbers = std::collections::HashSet::new();
+        for line in &file.lines {
+            if !line_numbers.insert((line.archive_path.as_deref(), line.line_number)) {
+                add(Some(&file.path), format!("duplicate line_number {}", line.line_number));
+                break;
+            }
+        }
+        if let Some(hash) = &file.file_hash {
+            if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                add(Some(&file.path), format!("file_hash {hash:?} is not a hex digest"));
+            }
+        }
+    }
+    for path in &req.delete_paths {
+        if let Some(msg) = path_problem(path) {
+            add(Some(path), format!("delete_paths: {msg}"));
+        }
+    }
+    for rename in &req.rename_paths {
+        for path in [&rename.old_path, &rename.new_path] {
+            if let Some(msg) = path_problem(path) {
+                add(Some(path), format!("rename_paths: {msg}"));
+            }
+        }
+    }
+
+    let total = problems.len();
+    if total > MAX_PROBLEMS {
+        problems.truncate(MAX_PROBLEMS);
+        problems.push(BulkProblem { path: None, message: format!("… and {} more", total - MAX_PROBLEMS) });
+    }
+
+    BulkValidateResponse {
+        valid: problems.is_empty(),
+        source: Some(req.source.clone()),
+        files: req.files.len(),
+        lines: req.files.iter().map(|f| f.lines.len()).sum(),
+        content_bytes: req.files.iter().flat_map(|f| &f.lines).map(|l| l.content.len()).sum(),
+        delete_paths: req.delete_paths.len(),
+        rename_paths: req.rename_paths.len(),
+        indexing_failures: req.indexing_failures.len(),
+        problems,
+    }
+}
+
+/// Why `path` is not a valid source-relative path, if it isn't.
+fn path_problem(path: &str) -> Option<&'static str> {
+    if path.is_empty() {
+        return Some("path is empty");
+    }
+    if path.starts_with('/') || path.starts_with('\\') {
+        return Some("path must be relative to the source root");
+    }
+    if path.split(['/', '\\']).flat_map(|seg| seg.split("::")).any(|seg| seg == "..") {
+        return Some("path must not contain '..' components");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path_problem, validate};
+    use find_common::api::{BulkRequest, IndexFile, IndexLine, FileKind};
+
+    fn file(path: &str, line_numbers: &[usize]) -> IndexFile {
+        IndexFile {
+            path: path.to_string(),
+            mtime: 1000,
+            size: Some(10),
+            kind: FileKind::Text,
+            lines: line_numbers.iter().map(|&n| IndexLine {
+                archive_path: None,
+                line_number: n,
+                content: "x".to_string(),
+            }).collect(),
+            extract_ms: None,
+            file_hash: None,
+            scanner_version: 0,
+            is_new: false,
+            force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
+        }
+    }
+
+    fn request(source: &str, files: Vec<IndexFile>) -> BulkRequest {
+        BulkRequest {
+            source: source.to_string(),
+            files,
+            delete_paths: vec![],
+            scan_timestamp: None,
+            indexing_failures: vec![],
+            rename_paths: vec![],
+        }
+    }
+
+    #[test]
+    fn path_problems() {
+        assert_eq!(path_problem("docs/a.txt"), None);
+        assert_eq!(path_problem("a.zip::inner/b.txt"), None);
+        assert!(path_problem("").is_some());
+        assert!(path_problem("/etc/passwd").is_some());
+        assert!(path_problem("docs/../../x").is_some());
+        assert!(path_problem("a.zip::../x").is_some());
+    }
+
+    #[test]
+    fn valid_request_counts() {
+        let report = validate(&request("docs", vec![file("a.txt", &[0, 1, 2]), file("b.txt", &[0, 1])]));
+        assert!(report.valid, "{:?}", report.problems);
+        assert_eq!(report.files, 2);
+        assert_eq!(report.lines, 5);
+        assert_eq!(report.content_bytes, 5);
+    }
+
+    #[test]
+    fn reports_each_problem() {
+        let mut req = request("bad name", vec![file("a.txt", &[0, 1, 1]), file("a.txt", &[0])]);
+        req.delete_paths.push("../escape".to_string());
+        let report = validate(&req);
+        assert!(!report.valid);
+        let messages: Vec<&str> = report.problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(messages.len(), 4, "{messages:?}");
+        assert!(messages[0].starts_with("invalid source name"));
+        assert_eq!(messages[1], "duplicate line_number 1");
+        assert_eq!(messages[2], "path appears more than once in files");
+        assert!(messages[3].starts_with("delete_paths:"));
+    }
+}
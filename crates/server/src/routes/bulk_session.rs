@@ -0,0 +1,243 @@
+/// Resumable bulk uploads: POST, PATCH, GET, DELETE /api/v1/bulk/sessions
+///
+/// A large bulk payload (either format: the gzip JSON body of
+/// `POST /api/v1/bulk` or the NDJSON stream of `POST /api/v2/bulk`) is sent
+/// in byte ranges that are appended to `data_dir/uploads/bulk-<id>.part`.
+/// After an interrupted `PATCH` the client asks for the session's `received`
+/// offset and continues from there instead of resending the whole payload.
+/// Committing moves the assembled payload into the inbox exactly as the
+/// single-request endpoints would have queued it.  Abandoned sessions are
+/// removed by the upload cleanup task.
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use find_common::api::{BulkSessionInitRequest, BulkSessionResponse, UploadPatchResponse};
+
+use crate::upload::{meta_path, part_path, part_size, touch_meta, uploads_dir};
+use crate::AppState;
+
+use super::bulk::new_request_id;
+use super::check_auth;
+use super::upload::{append_bytes, parse_content_range_start};
+
+/// Sidecar metadata of an open bulk session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BulkSessionMeta {
+    stream: bool,
+    size: Option<u64>,
+    created_at: i64,
+}
+
+/// Upload-directory stem of a session.  Session ids are UUIDs; anything else
+/// is rejected so the id can never name a path outside the uploads dir.
+fn session_stem(id: &str) -> Option<String> {
+    Uuid::parse_str(id).ok().map(|u| format!("bulk-{u}"))
+}
+
+fn read_session(uploads: &std::path::Path, stem: &str) -> Option<BulkSessionMeta> {
+    let content = std::fs::read(meta_path(uploads, stem)).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn session_response(id: &str, meta: &BulkSessionMeta, received: u64) -> BulkSessionResponse {
+    BulkSessionResponse {
+        session_id: id.to_string(),
+        received,
+        size: meta.size,
+        stream: meta.stream,
+    }
+}
+
+/// `POST /api/v1/bulk/sessions` — open a resumable bulk upload.
+pub async fn bulk_session_init(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<BulkSessionInitRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let uploads = uploads_dir(&state.data_dir);
+    if let Err(e) = std::fs::create_dir_all(&uploads) {
+        warn!("failed to create uploads dir: {e}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let stem = format!("bulk-{id}");
+    let meta = BulkSessionMeta {
+        stream: req.stream,
+        size: req.size,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    let json = serde_json::to_vec(&meta).unwrap_or_default();
+    if let Err(e) = std::fs::write(meta_path(&uploads, &stem), json) {
+        warn!("failed to write bulk session meta for {id}: {e}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::Value::Null)).into_response();
+    }
+
+    (StatusCode::CREATED, Json(session_response(&id, &meta, 0))).into_response()
+}
+
+/// `PATCH /api/v1/bulk/sessions/{id}` — append a byte range.
+///
+/// Requires `Content-Range: bytes <start>-<end>/<total>` (`total` may be `*`).
+/// Returns 409 with `{"received": n}` if `start` is not the current offset.
+pub async fn bulk_session_patch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let uploads = uploads_dir(&state.data_dir);
+    let Some(stem) = session_stem(&id) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
+    };
+    let Some(meta) = read_session(&uploads, &stem) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
+    };
+
+    let Some(start) = headers
+        .get("Content-Range")
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range_start)
+    else {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::Value::Null)).into_response();
+    };
+
+    let current_size = part_size(&uploads, &stem);
+    if start != current_size {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "received": current_size })),
+        )
+            .into_response();
+    }
+    if meta.size.is_some_and(|size| current_size + body.len() as u64 > size) {
+        return (StatusCode::PAYLOAD_TOO_LARGE, Json(serde_json::Value::Null)).into_response();
+    }
+
+    if let Err(e) = append_bytes(&part_path(&uploads, &stem), &body) {
+        warn!("failed to write chunk for bulk session {id}: {e:#}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::Value::Null)).into_response();
+    }
+    touch_meta(&uploads, &stem);
+
+    let received = part_size(&uploads, &stem);
+    (StatusCode::OK, Json(UploadPatchResponse { received })).into_response()
+}
+
+/// `GET /api/v1/bulk/sessions/{id}` — current offset (for resume).
+pub async fn bulk_session_status(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let uploads = uploads_dir(&state.data_dir);
+    let Some(stem) = session_stem(&id) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
+    };
+    let Some(meta) = read_session(&uploads, &stem) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
+    };
+
+    let received = part_size(&uploads, &stem);
+    (StatusCode::OK, Json(session_response(&id, &meta, received))).into_response()
+}
+
+/// `POST /api/v1/bulk/sessions/{id}/commit` — queue the assembled payload.
+///
+/// Returns 409 with the session state if fewer bytes than the declared size
+/// have arrived, and 415 if the payload is not gzip.
+pub async fn bulk_session_commit(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let uploads = uploads_dir(&state.data_dir);
+    let Some(stem) = session_stem(&id) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
+    };
+    let Some(meta) = read_session(&uploads, &stem) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
+    };
+
+    let part = part_path(&uploads, &stem);
+    let received = part_size(&uploads, &stem);
+    if meta.size.is_some_and(|size| size != received) {
+        return (StatusCode::CONFLICT, Json(session_response(&id, &meta, received))).into_response();
+    }
+    if !has_gzip_magic(&part) {
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let suffix = if meta.stream { crate::worker::stream::STREAM_SUFFIX } else { ".gz" };
+    let dest = state.data_dir.join("inbox").join(format!("{}{suffix}", new_request_id()));
+    // The part's mtime is that of its last chunk; the inbox orders by mtime,
+    // so stamp it with the commit time to queue it behind earlier requests.
+    let queued = std::fs::File::options()
+        .write(true)
+        .open(&part)
+        .and_then(|f| f.set_modified(std::time::SystemTime::now()))
+        .and_then(|()| std::fs::rename(&part, &dest));
+    if let Err(e) = queued {
+        tracing::error!("Failed to queue bulk session {id}: {e}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::Value::Null)).into_response();
+    }
+    let _ = std::fs::remove_file(meta_path(&uploads, &stem));
+
+    tracing::debug!("Queued bulk session {id} as {}", dest.display());
+    (StatusCode::ACCEPTED, Json(serde_json::Value::Null)).into_response()
+}
+
+/// `DELETE /api/v1/bulk/sessions/{id}` — abandon a session.
+pub async fn bulk_session_delete(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+
+    let uploads = uploads_dir(&state.data_dir);
+    let Some(stem) = session_stem(&id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if read_session(&uploads, &stem).is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let _ = std::fs::remove_file(part_path(&uploads, &stem));
+    let _ = std::fs::remove_file(meta_path(&uploads, &stem));
+    StatusCode::NO_CONTENT.into_response()
+}
+
+fn has_gzip_magic(path: &std::path::Path) -> bool {
+    use std::io::Read;
+    let mut magic = [0u8; 2];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok_and(|()| magic == [0x1f, 0x8b])
+}
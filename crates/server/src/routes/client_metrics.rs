@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+
+use find_common::api::ClientMetrics;
+
+use crate::AppState;
+
+use super::check_auth;
+
+// ── POST /api/v1/client-metrics ───────────────────────────────────────────────
+
+/// Store a health report from `find-scan` or `find-watch`.
+pub async fn post_client_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(report): Json<ClientMetrics>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+    state.client_metrics.record(report, chrono::Utc::now().timestamp());
+    StatusCode::NO_CONTENT.into_response()
+}
+
+// ── GET /api/v1/client-metrics ────────────────────────────────────────────────
+
+/// The latest report from each client.
+pub async fn get_client_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    Json(state.client_metrics.list()).into_response()
+}
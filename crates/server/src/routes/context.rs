@@ -14,6 +14,7 @@ use find_common::api::{
 
 use crate::{db, AppState};
 
+use super::annotations::window_annotations;
 use super::{check_auth, compact_lines, composite_path, run_blocking, source_db_path};
 
 // ── GET /api/v1/context ───────────────────────────────────────────────────────
@@ -44,9 +45,11 @@ pub async fn get_context(
     let full_path = composite_path(&params.path, params.archive_path.as_deref());
     let window = params.window.unwrap_or(state.config.search.context_window);
     let content_store = Arc::clone(&state.content_store);
+    let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
 
     run_blocking("context", move || {
-        let conn = db::open(&db_path)?;
+        let conn = pool.get(&db_path)?;
         let kind: FileKind = conn.query_row(
             "SELECT kind FROM files WHERE path = ?1",
             rusqlite::params![full_path],
@@ -54,7 +57,9 @@ pub async fn get_context(
         ).map(|s| FileKind::from(s.as_str())).unwrap_or(FileKind::Text);
         let raw = db::get_context(&conn, content_store.as_ref(), &full_path, params.line, window)?;
         let (start, match_index, lines) = compact_lines(raw, params.line);
-        Ok(Json(ContextResponse { start, match_index, lines, kind }))
+        let annotations_conn = db::annotations::checkout(&pool, &data_dir)?;
+        let annotations = window_annotations(&annotations_conn, &params.source, &full_path, &lines);
+        Ok(Json(ContextResponse { start, match_index, lines, kind, annotations }))
     }).await
 }
 
@@ -71,6 +76,7 @@ pub async fn context_batch(
 
     let content_store = Arc::clone(&state.content_store);
     let data_dir = state.data_dir.clone();
+    let pool = Arc::clone(&state.db_pool);
 
     run_blocking("context_batch", move || {
 
@@ -84,14 +90,15 @@ pub async fn context_batch(
             }
         }
 
+        let annotations_conn = db::annotations::checkout(&pool, &data_dir)?;
         let mut results: Vec<ContextBatchResult> = Vec::new();
         for (_source_name, (db_path, items)) in by_source {
-            let conn = match db::open(&db_path) {
+            let conn = match pool.get(&db_path) {
                 Ok(c) => c,
                 Err(e) => {
                     tracing::warn!("context_batch open {}: {e:#}", db_path.display());
                     for item in items {
-                        results.push(ContextBatchResult { source: item.source, path: item.path, line: item.line, start: 0, match_index: None, lines: vec![], kind: FileKind::Unknown });
+                        results.push(ContextBatchResult { source: item.source, path: item.path, archive_path: item.archive_path, line: item.line, start: 0, match_index: None, lines: vec![], kind: FileKind::Unknown, annotations: vec![] });
                     }
                     continue;
                 }
@@ -116,7 +123,8 @@ pub async fn context_batch(
                     }
                 };
 
-                results.push(ContextBatchResult { source: item.source, path: item.path, line: item.line, start, match_index, lines, kind });
+                let annotations = window_annotations(&annotations_conn, &item.source, &full_path, &lines);
+                results.push(ContextBatchResult { source: item.source, path: item.path, archive_path: item.archive_path, line: item.line, start, match_index, lines, kind, annotations });
             }
         }
 
@@ -43,9 +43,10 @@ pub async fn get_errors(
 
     let limit = params.limit.min(1000);
     let offset = params.offset;
+    let pool = Arc::clone(&state.db_pool);
 
     run_blocking("get_errors", move || {
-        let conn = db::open(&db_path)?;
+        let conn = pool.get(&db_path)?;
         let total = db::get_indexing_error_count(&conn)?;
         let errors = db::get_indexing_errors(&conn, limit, offset)?;
         Ok(Json(ErrorsResponse { errors, total }))
@@ -0,0 +1,167 @@
+/// HTTP validators for read endpoints polled by the web UI and the tray.
+///
+/// The `ETag` is a hash of the cheap-to-read inputs a response is derived
+/// from.  These are the (mtime, size) of the SQLite files behind it,
+/// including the `-wal` file because writes land there first, plus the
+/// request parameters.  Where content-store contents matter, the worker's
+/// change counter (`stats_watch`) is included too; it is bumped after every
+/// inbox request and archive batch.  A per-process nonce keeps the counter,
+/// which restarts at zero, from colliding across restarts.
+///
+/// Handlers build the validator *before* reading any data.  A write that
+/// lands in between then yields newer data under an older tag, which only
+/// costs a cache miss on the next poll, never a stale 304.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::AppState;
+
+pub(super) struct Validator {
+    hasher: DefaultHasher,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validator {
+    pub(super) fn new(endpoint: &'static str) -> Self {
+        static NONCE: OnceLock<u128> = OnceLock::new();
+        let nonce = NONCE.get_or_init(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        });
+        let mut hasher = DefaultHasher::new();
+        (nonce, env!("CARGO_PKG_VERSION"), endpoint).hash(&mut hasher);
+        Self { hasher, last_modified: None }
+    }
+
+    /// Mix a request parameter or other response input into the tag.
+    pub(super) fn value(mut self, v: impl Hash) -> Self {
+        v.hash(&mut self.hasher);
+        self
+    }
+
+    /// Mix in the worker's change counter, for responses that also depend on
+    /// the content store or the stats cache.
+    pub(super) fn worker_version(self, state: &AppState) -> Self {
+        let version = *state.stats_watch.borrow();
+        self.value(version)
+    }
+
+    /// Mix in the state of the SQLite database at `path` and its WAL.  The
+    /// latest mtime becomes the `Last-Modified` date.  An empty file counts as
+    /// missing: opening a connection creates an empty WAL without changing
+    /// any data.
+    pub(super) fn sqlite_file(mut self, path: &Path) -> Self {
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+        for p in [path, Path::new(&wal)] {
+            let meta = std::fs::metadata(p).ok().filter(|m| m.len() > 0);
+            let mtime = meta.as_ref().and_then(|m| m.modified().ok());
+            (mtime, meta.map(|m| m.len())).hash(&mut self.hasher);
+            if let Some(t) = mtime {
+                self.last_modified = Some(self.last_modified.map_or(t, |lm| lm.max(t)));
+            }
+        }
+        self
+    }
+
+    /// Mix in a directory's mtime, which changes when entries are added or
+    /// removed.
+    pub(super) fn dir(mut self, path: &Path) -> Self {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok().hash(&mut self.hasher);
+        self
+    }
+
+    fn opaque_tag(&self) -> String {
+        format!("\"{:016x}\"", self.hasher.clone().finish())
+    }
+
+    /// The tag is weak: the response compression layer may send the same
+    /// entity gzip- or brotli-encoded, and a strong tag would have to differ
+    /// per encoding.
+    fn etag(&self) -> String {
+        format!("W/{}", self.opaque_tag())
+    }
+
+    /// True when the request's `If-None-Match` names the current tag (weak
+    /// comparison, as RFC 9110 requires for `If-None-Match`).
+    pub(super) fn matches(&self, headers: &HeaderMap) -> bool {
+        let Some(inm) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) else {
+            return false;
+        };
+        let tag = self.opaque_tag();
+        inm.split(',')
+            .map(|t| t.trim())
+            .any(|t| t == "*" || t.trim_start_matches("W/") == tag)
+    }
+
+    /// An empty `304 Not Modified` carrying the validators.
+    pub(super) fn not_modified(&self) -> Response {
+        self.tag(StatusCode::NOT_MODIFIED.into_response())
+    }
+
+    /// Add `ETag`, `Last-Modified` and `Cache-Control: no-cache` (always
+    /// revalidate) to a successful response; others pass through untouched.
+    pub(super) fn tag(&self, mut resp: Response) -> Response {
+        let status = resp.status();
+        if !(status.is_success() || status == StatusCode::NOT_MODIFIED) {
+            return resp;
+        }
+        let headers = resp.headers_mut();
+        if let Ok(v) = HeaderValue::from_str(&self.etag()) {
+            headers.insert(header::ETAG, v);
+        }
+        if let Some(lm) = self.last_modified {
+            let date = chrono::DateTime::<chrono::Utc>::from(lm).format("%a, %d %b %Y %H:%M:%S GMT");
+            if let Ok(v) = HeaderValue::from_str(&date.to_string()) {
+                headers.insert(header::LAST_MODIFIED, v);
+            }
+        }
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        resp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(inm: &str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        h.insert(header::IF_NONE_MATCH, HeaderValue::from_str(inm).unwrap());
+        h
+    }
+
+    #[test]
+    fn if_none_match_forms() {
+        let v = Validator::new("test").value("a");
+        let etag = v.etag();
+        assert!(etag.starts_with("W/\""));
+        assert!(v.matches(&request(&etag)));
+        assert!(v.matches(&request(etag.trim_start_matches("W/"))));
+        assert!(v.matches(&request(&format!("\"other\", {etag}"))));
+        assert!(v.matches(&request("*")));
+        assert!(!v.matches(&request("\"other\"")));
+        assert!(!v.matches(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn tag_changes_with_inputs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = dir.path().join("s.db");
+        std::fs::write(&db, b"one").unwrap();
+        let before = Validator::new("test").sqlite_file(&db).etag();
+        assert_eq!(before, Validator::new("test").sqlite_file(&db).etag());
+        assert_ne!(before, Validator::new("other").sqlite_file(&db).etag());
+
+        std::fs::write(dir.path().join("s.db-wal"), b"frame").unwrap();
+        assert_ne!(before, Validator::new("test").sqlite_file(&db).etag());
+    }
+}
@@ -0,0 +1,88 @@
+/// Archive member export: GET /api/v1/export
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use find_common::path::split_composite;
+
+use crate::AppState;
+
+use super::check_auth;
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    source: String,
+    /// Composite path of the member, e.g. `backups/home.tar.gz::docs/report.pdf`.
+    path: String,
+}
+
+/// GET /api/v1/export?source=<name>&path=<outer>::<member>
+///
+/// Re-extracts a single archive member and returns it as a download.  The
+/// outer archive is read from the source mount (`[sources.<name>] path`) or,
+/// failing that, from the originals store.  Supports ZIP (and iWork), TAR,
+/// `.tar.gz`/`.tar.bz2`/`.tar.xz` and single-file `.gz`/`.bz2`/`.xz`, nested
+/// up to `server.download_zip_member_levels` deep.
+pub async fn export_member(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+
+    let Some((outer, member)) = split_composite(&params.path) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let member_depth = member.matches("::").count() + 1;
+    if member_depth > state.config.server.download_zip_member_levels {
+        return StatusCode::UNPROCESSABLE_ENTITY.into_response();
+    }
+
+    let archive_file = match super::resolve_source_path(&state, &params.source, outer) {
+        Ok((_, p)) => p,
+        Err(StatusCode::BAD_REQUEST) => return StatusCode::BAD_REQUEST.into_response(),
+        Err(_) => match super::raw::stored_original(&state, &params.source, outer).await {
+            Some(p) => p,
+            None => {
+                tracing::warn!(source = %params.source, outer, "export: archive not on mount or in originals store");
+                return StatusCode::NOT_FOUND.into_response();
+            }
+        },
+    };
+
+    let outer = outer.to_owned();
+    let member = member.to_owned();
+    let result = tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&archive_file).map_err(|_| StatusCode::NOT_FOUND)?;
+        crate::export::extract_nested(std::io::BufReader::new(file), &outer, &member)
+    })
+    .await
+    .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR));
+    let bytes = match result {
+        Ok(b) => b,
+        Err(s) => return s.into_response(),
+    };
+
+    let leaf = params.path.rsplit("::").next().unwrap_or_default();
+    let filename = std::path::Path::new(leaf)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .replace('"', "");
+    let mime = mime_guess::from_path(leaf).first_or_octet_stream();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, mime.essence_str())
+        .header(header::CONTENT_LENGTH, bytes.len().to_string())
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\""))
+        .body(Body::from(bytes))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
@@ -8,13 +8,15 @@ use axum::{
 };
 use serde::Deserialize;
 
-use find_common::api::{FileKind, FileResponse, LINE_CONTENT_START};
+use find_common::api::{FileKind, FileResponse, PreviewResponse, LINE_CONTENT_START};
+use find_common::language::{detect_language, language_for_path};
 use find_common::path::split_composite;
 
 use rusqlite::OptionalExtension;
 
 use crate::{db, AppState};
 
+use super::etag::Validator;
 use super::{check_auth, check_link_code_auth, composite_path, run_blocking, source_db_path};
 
 // ── GET /api/v1/file?source=X&path=Y[&archive_path=Z][&link_code=C] ──────────
@@ -62,7 +64,21 @@ pub async fn get_file(
     let offset = params.offset.unwrap_or(0);
     let limit = params.limit;
 
-    run_blocking("get_file", move || -> anyhow::Result<Response> {
+    // Share-link requests are authorised inside the blocking task, so they
+    // are never answered from a validator alone.
+    let validator = link_code.is_none().then(|| {
+        Validator::new("file")
+            .value((&source, &full_path, offset, limit))
+            .worker_version(&state)
+            .sqlite_file(&db_path)
+            .sqlite_file(&data_dir.join("annotations.db"))
+    });
+    if let Some(v) = validator.as_ref().filter(|v| v.matches(&headers)) {
+        return v.not_modified();
+    }
+
+    let pool = Arc::clone(&state.db_pool);
+    let resp = run_blocking("get_file", move || -> anyhow::Result<Response> {
         // Validate link code if provided (alternative to bearer auth).
         if let Some(code) = &link_code {
             if let Err(s) = check_link_code_auth(&data_dir, code, &source, &full_path) {
@@ -70,7 +86,7 @@ pub async fn get_file(
             }
         }
 
-        let conn = db::open(&db_path)?;
+        let conn = pool.get(&db_path)?;
 
         let (kind, mtime, size): (FileKind, Option<i64>, Option<i64>) = conn
             .query_row(
@@ -80,6 +96,7 @@ pub async fn get_file(
             )
             .map(|(s, m, sz)| (FileKind::from(s.as_str()), m, sz))
             .unwrap_or_else(|_| (FileKind::Text, None, None));
+        let (extract_ms, provenance) = db::get_file_provenance(&conn, &full_path)?;
 
         let (all_lines, total_lines, content_unavailable) =
             db::get_file_lines_paged(&conn, content_store.as_ref(), &full_path, offset, limit)?;
@@ -134,19 +151,121 @@ pub async fn get_file(
             Some(paths)
         }).unwrap_or_default();
 
+        let annotations_conn = db::annotations::checkout(&pool, &data_dir)?;
+        let annotations = db::annotations::list_for_file(&annotations_conn, &source, &full_path)?;
+
         Ok(Json(FileResponse {
             lines, line_offsets, metadata,
             file_kind: kind, total_lines, mtime, size,
-            indexing_error, content_unavailable, duplicate_paths,
+            indexing_error, content_unavailable, duplicate_paths, annotations,
+            extract_ms, provenance,
         }).into_response())
-    }).await
+    }).await;
+    match validator {
+        Some(v) => v.tag(resp),
+        None => resp,
+    }
+}
+
+// ── GET /api/v1/file/preview?source=X&path=Y[&line=N][&lines=M] ──────────────
+//
+// A bounded window of a file for syntax-highlighted previews: up to `lines`
+// content lines centred on `line` (a search hit's line number), or from the
+// top of the file, plus the highlight.js language detected from the file name
+// or its `#!` line.  Clients highlight the window themselves.
+
+const PREVIEW_DEFAULT_LINES: usize = 100;
+const PREVIEW_MAX_LINES: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct PreviewParams {
+    pub source: String,
+    pub path: String,
+    /// Legacy: combine with `path` into a composite path if provided.
+    pub archive_path: Option<String>,
+    /// Line number to centre the window on (server-space, as in search results).
+    pub line: Option<usize>,
+    /// Window size in lines (default 100, at most 1000).
+    pub lines: Option<usize>,
+}
+
+pub async fn get_file_preview(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<PreviewParams>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let db_path = match source_db_path(&state, &params.source) {
+        Ok(p) => p,
+        Err(s) => return (s, Json(serde_json::Value::Null)).into_response(),
+    };
+
+    let full_path = composite_path(&params.path, params.archive_path.as_deref());
+    let limit = params.lines.unwrap_or(PREVIEW_DEFAULT_LINES).clamp(1, PREVIEW_MAX_LINES);
+    let offset = params.line.map_or(0, |line| {
+        line.saturating_sub(LINE_CONTENT_START).saturating_sub(limit / 2)
+    });
+
+    let validator = Validator::new("preview")
+        .value((&params.source, &full_path, offset, limit))
+        .worker_version(&state)
+        .sqlite_file(&db_path);
+    if validator.matches(&headers) {
+        return validator.not_modified();
+    }
+
+    let content_store = Arc::clone(&state.content_store);
+    let pool = Arc::clone(&state.db_pool);
+    let resp = run_blocking("file_preview", move || {
+        let conn = pool.get(&db_path)?;
+
+        let kind: FileKind = conn.query_row(
+            "SELECT kind FROM files WHERE path = ?1",
+            rusqlite::params![full_path],
+            |row| row.get::<_, String>(0),
+        ).map(|s| FileKind::from(s.as_str())).unwrap_or(FileKind::Text);
+
+        let (all_lines, total_lines, content_unavailable) =
+            db::get_file_lines_paged(&conn, content_store.as_ref(), &full_path, offset, Some(limit))?;
+        let lines: Vec<_> = all_lines.into_iter()
+            .filter(|l| l.line_number >= LINE_CONTENT_START)
+            .collect();
+
+        // Only source-like content gets a language; the `#!` fallback needs
+        // the file's first line, which may lie outside the window.
+        let language = if matches!(kind, FileKind::Text | FileKind::Code) {
+            let first_line = match lines.first() {
+                Some(l) if l.line_number == LINE_CONTENT_START => Some(l.content.clone()),
+                _ if language_for_path(&full_path).is_some() => None,
+                _ => db::get_file_lines_paged(&conn, content_store.as_ref(), &full_path, 0, Some(1))?
+                    .0.into_iter()
+                    .find(|l| l.line_number == LINE_CONTENT_START)
+                    .map(|l| l.content),
+            };
+            detect_language(&full_path, first_line.as_deref()).map(str::to_string)
+        } else {
+            None
+        };
+
+        let start = lines.first().map_or(0, |l| l.line_number);
+        let truncated = offset > 0 || offset + limit < total_lines;
+        Ok(Json(PreviewResponse {
+            start, lines, total_lines, kind, language, truncated, content_unavailable,
+        }))
+    }).await;
+    validator.tag(resp)
 }
 
 
-// ── GET /api/v1/files?source=<name>[&q=<query>&limit=<n>] ────────────────────
+// ── GET /api/v1/files?source=<name>[&q=<query>&limit=<n>][&archive=<path>] ───
 //
 // Without `q`: returns the full file list (used by find-scan for deletion detection).
 // With `q`: returns up to `limit` (default 50) matching files for the Ctrl+P palette.
+// With `archive`: returns only that archive's members (used by find-watch to
+// re-index changed archives incrementally).
 
 #[derive(Deserialize)]
 pub struct FilesParams {
@@ -155,6 +274,8 @@ pub struct FilesParams {
     pub q: Option<String>,
     /// Maximum results for palette mode (default 50).
     pub limit: Option<usize>,
+    /// List only the members of this archive.
+    pub archive: Option<String>,
 }
 
 pub async fn list_files(
@@ -171,12 +292,15 @@ pub async fn list_files(
 
     let q = params.q.clone();
     let limit = params.limit.unwrap_or(50);
+    let archive = params.archive.clone();
+    let pool = Arc::clone(&state.db_pool);
 
     run_blocking("list_files", move || {
-        let conn = db::open(&db_path)?;
-        match q {
-            Some(q) => db::search_files(&conn, &q, limit).map(Json),
-            None    => db::list_files(&conn).map(Json),
+        let conn = pool.get(&db_path)?;
+        match (q, archive) {
+            (Some(q), _)       => db::search_files(&conn, &q, limit).map(Json),
+            (None, Some(path)) => db::list_archive_members(&conn, &path).map(Json),
+            (None, None)       => db::list_files(&conn).map(Json),
         }
     }).await
 }
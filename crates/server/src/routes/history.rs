@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+
+use find_common::api::ScanHistoryResponse;
+
+use crate::{db, AppState};
+
+use super::{check_auth, run_blocking, source_db_path};
+
+// ── GET /api/v1/history?source=X[&days=30&points=200] ─────────────────────────
+
+#[derive(Deserialize)]
+pub struct HistoryParams {
+    pub source: String,
+    /// How many days back to return. 0 = all retained history.
+    #[serde(default = "default_days")]
+    pub days: u64,
+    /// Maximum number of points to return after downsampling.
+    #[serde(default = "default_points")]
+    pub points: usize,
+}
+
+fn default_days() -> u64 { 30 }
+fn default_points() -> usize { 200 }
+
+pub async fn get_history(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<HistoryParams>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let db_path = match source_db_path(&state, &params.source) {
+        Ok(p) => p,
+        Err(s) => return (s, Json(serde_json::Value::Null)).into_response(),
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let since = if params.days == 0 { 0 } else { now - params.days as i64 * 86_400 };
+    let max_points = params.points.clamp(1, 1000);
+    let source = params.source;
+    let pool = Arc::clone(&state.db_pool);
+
+    run_blocking("get_history", move || {
+        let conn = pool.get(&db_path)?;
+        let points = db::get_scan_history_since(&conn, since)?;
+        let points = db::downsample_history(points, max_points);
+        Ok(Json(ScanHistoryResponse { source, points }))
+    }).await
+}
@@ -46,10 +46,11 @@ pub async fn post_link(
     let source = body.source.clone();
     let path = body.path.clone();
     let archive_path = body.archive_path.clone();
+    let pool = Arc::clone(&state.db_pool);
 
     run_blocking("post_link", move || {
         let full_path = composite_path(&path, archive_path.as_deref());
-        let source_conn = db::open(&db_path)?;
+        let source_conn = pool.get(&db_path)?;
         let (kind_str, mtime): (String, i64) = source_conn
             .query_row(
                 "SELECT kind, mtime FROM files WHERE path = ?1",
@@ -1,33 +1,50 @@
 mod admin;
+mod annotations;
 mod bulk;
+mod bulk_session;
+mod client_metrics;
 mod context;
 mod errors;
+mod etag;
+mod export;
 mod file;
+mod history;
 mod links;
+mod originals;
 mod raw;
 mod recent;
+mod replica;
 mod search;
 mod session;
 mod settings;
 mod stats;
+mod trash;
 mod tree;
 pub mod upload;
 mod view;
 
-pub use admin::{compact, delete_source, inbox_clear, inbox_pause, inbox_resume, inbox_retry, inbox_show, inbox_status, update_check, update_apply};
-pub use bulk::bulk;
+pub use admin::{compact, delete_source, inbox_clear, inbox_pause, inbox_resume, inbox_retry, inbox_show, inbox_status, get_maintenance, maintain, reextract, set_maintenance, rename_source, update_check, update_apply};
+pub use annotations::{delete_annotation, list_annotations, patch_annotation, post_annotation};
+pub use bulk::{bulk, bulk_v2, bulk_validate, known_hashes};
+pub use bulk_session::{bulk_session_commit, bulk_session_delete, bulk_session_init, bulk_session_patch, bulk_session_status};
+pub use client_metrics::{get_client_metrics, post_client_metrics};
 pub use context::{context_batch, get_context};
 pub use errors::get_errors;
-pub use file::{get_file, list_files};
+pub use export::export_member;
+pub use file::{get_file, get_file_preview, list_files};
+pub use history::get_history;
 pub use links::{get_link, post_link};
+pub use originals::{head_original, put_original};
 pub use raw::{get_raw, get_raw_path};
 pub use recent::{get_recent, stream_recent};
-pub use search::search;
+pub use replica::{get_replica_manifest, get_replica_source, post_replica_blobs};
+pub use search::{search, search_export, search_summary};
 pub use session::{create_session, delete_session};
 pub use stats::{get_stats, stream_stats};
+pub use trash::{get_trash, restore_trash};
 pub use tree::{expand_tree, list_dir, list_sources};
 pub use upload::{upload_init, upload_patch, upload_status};
-pub use self::settings::get_settings;
+pub use self::settings::{get_client_config, get_settings};
 pub use view::get_view;
 
 use std::net::SocketAddr;
@@ -35,7 +52,7 @@ use std::sync::Arc;
 
 use axum::{
     extract::{ConnectInfo, State},
-    http::{HeaderMap, Request, StatusCode},
+    http::{HeaderMap, Method, Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
@@ -77,6 +94,73 @@ pub async fn log_request(req: Request<axum::body::Body>, next: Next) -> Response
     response
 }
 
+// ── Read-only replica guard ────────────────────────────────────────────────────
+
+/// Middleware that rejects write requests with 403 when the server runs as a
+/// read-only replica (`[replica] primary_url` is set).
+pub async fn replica_guard(
+    State(state): State<Arc<AppState>>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    if state.config.replica.is_replica() && is_write_request(req.method(), req.uri().path()) {
+        return (StatusCode::FORBIDDEN, "server is a read-only replica").into_response();
+    }
+    next.run(req).await
+}
+
+/// Endpoints that mutate indexed data.  Reads that happen to use POST
+/// (`context-batch`, sessions, share links) are allowed on replicas.
+fn is_write_request(method: &Method, path: &str) -> bool {
+    path == "/api/v1/bulk"
+        || path == "/api/v2/bulk"
+        || (path.starts_with("/api/v1/bulk/sessions") && method != Method::GET)
+        || path.starts_with("/api/v1/upload")
+        || (path.starts_with("/api/v1/originals/") && method == Method::PUT)
+        || (path.starts_with("/api/v1/admin/") && method != Method::GET)
+        || (path.starts_with("/api/v1/annotations") && method != Method::GET)
+        || (path == "/api/v1/client-metrics" && method == Method::POST)
+}
+
+// ── Maintenance mode guard ─────────────────────────────────────────────────────
+
+/// Middleware that refuses indexing requests with 503 and `Retry-After` while
+/// maintenance mode is on.  Search, admin and other reads are unaffected;
+/// clients spool refused batches and resend them later.
+pub async fn maintenance_guard(
+    State(state): State<Arc<AppState>>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    if is_ingest_request(req.method(), req.uri().path()) {
+        let maintenance = state.maintenance.read().unwrap_or_else(|e| e.into_inner());
+        if maintenance.enabled {
+            let message = match &maintenance.reason {
+                Some(reason) => format!("server is in maintenance mode: {reason}"),
+                None => "server is in maintenance mode".to_string(),
+            };
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(axum::http::header::RETRY_AFTER, maintenance.retry_after_secs.to_string())],
+                message,
+            ).into_response();
+        }
+    }
+    next.run(req).await
+}
+
+/// Requests that submit content to be indexed.
+fn is_ingest_request(method: &Method, path: &str) -> bool {
+    if method == Method::GET || method == Method::HEAD {
+        return false;
+    }
+    path == "/api/v1/bulk"
+        || path == "/api/v2/bulk"
+        || path.starts_with("/api/v1/bulk/sessions")
+        || path.starts_with("/api/v1/upload")
+        || path.starts_with("/api/v1/originals/")
+}
+
 // ── Shared helpers ─────────────────────────────────────────────────────────────
 
 /// Build a composite path from a base path and an optional legacy `archive_path`.
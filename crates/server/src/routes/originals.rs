@@ -0,0 +1,139 @@
+/// Original-content routes: HEAD, PUT /api/v1/originals/{hash}
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tokio::io::AsyncWriteExt as _;
+use tokio_stream::StreamExt as _;
+use uuid::Uuid;
+
+use crate::originals::{finish_hash, is_valid_hash, original_path};
+use crate::AppState;
+
+use super::check_auth;
+
+/// `HEAD /api/v1/originals/{hash}` — 200 when the original is stored,
+/// 404 when it is not, 403 when the originals store is disabled.
+pub async fn head_original(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(hash): Path<String>,
+) -> Response {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+    if !state.config.originals.enabled {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if !is_valid_hash(&hash) {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    match tokio::fs::metadata(original_path(&state.data_dir, &hash)).await {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// `PUT /api/v1/originals/{hash}` — store a file's original bytes.
+///
+/// The body is streamed to a temporary file next to its final location and
+/// renamed into place once complete, so readers never see a partial file.
+/// The body is hashed as it streams and must hash to `{hash}`; otherwise it
+/// is discarded with 422, so a bad upload can never stand in for the real
+/// original of every file sharing that hash.
+/// Returns 201 when stored, 200 when the hash was already present, 403 when
+/// the store is disabled and 413 when the body exceeds
+/// `[originals] max_file_size_mb`.
+pub async fn put_original(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(hash): Path<String>,
+    body: Body,
+) -> Response {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+    let cfg = &state.config.originals;
+    if !cfg.enabled {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if !is_valid_hash(&hash) {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let max_bytes = cfg.max_file_size_mb * 1024 * 1024;
+    let declared_len = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared_len.is_some_and(|n| n > max_bytes) {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+
+    let dest = original_path(&state.data_dir, &hash);
+    if tokio::fs::metadata(&dest).await.is_ok() {
+        return StatusCode::OK.into_response();
+    }
+    let Some(shard) = dest.parent() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    if let Err(e) = tokio::fs::create_dir_all(shard).await {
+        tracing::warn!("failed to create originals dir {}: {e}", shard.display());
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    let tmp = shard.join(format!("{hash}.{}.part", Uuid::new_v4()));
+    let mut hasher = blake3::Hasher::new();
+    let status = match write_body(&tmp, body, max_bytes, Some(&mut hasher)).await {
+        Ok(()) if finish_hash(hasher) != hash => {
+            tracing::warn!("rejected original upload: body does not hash to {hash}");
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        Ok(()) => match tokio::fs::rename(&tmp, &dest).await {
+            Ok(()) => return StatusCode::CREATED.into_response(),
+            Err(e) => {
+                tracing::warn!("failed to store original {hash}: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        },
+        Err(status) => status,
+    };
+    let _ = tokio::fs::remove_file(&tmp).await;
+    status.into_response()
+}
+
+/// Stream `body` into a new file at `path`, failing with 413 once more than
+/// `max_bytes` have been received.  Every chunk written is also fed to
+/// `hasher` when one is given.
+pub(super) async fn write_body(
+    path: &std::path::Path,
+    body: Body,
+    max_bytes: u64,
+    mut hasher: Option<&mut blake3::Hasher>,
+) -> Result<(), StatusCode> {
+    let mut file = tokio::fs::File::create(path).await.map_err(|e| {
+        tracing::warn!("failed to create {}: {e}", path.display());
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let mut stream = body.into_data_stream();
+    let mut written = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+        written += chunk.len() as u64;
+        if written > max_bytes {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        if let Some(h) = hasher.as_deref_mut() {
+            h.update(&chunk);
+        }
+        file.write_all(&chunk).await.map_err(|e| {
+            tracing::warn!("failed to write {}: {e}", path.display());
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+    file.flush().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(())
+}
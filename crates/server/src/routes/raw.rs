@@ -78,8 +78,10 @@ pub struct RawParams {
 
 /// GET /api/v1/raw?source=<name>&path=<relative_path>[&convert=png][&link_code=C][&download=1]
 ///
-/// Streams the original file from the source's configured filesystem root.
-/// Requires the source to have a `path` configured in `[sources.<name>]`.
+/// Streams the original file from the source's configured filesystem root
+/// (`[sources.<name>] path`).  When the source has no mount or the file is
+/// missing from it, falls back to the copy in the originals store if the
+/// scanner uploaded one (`[originals] enabled = true`).
 /// Auth: bearer/cookie, or a valid `link_code` that matches source+path.
 pub async fn get_raw(
     State(state): State<Arc<AppState>>,
@@ -128,53 +130,27 @@ pub async fn get_raw(
         }
     }
 
-    // Look up the source's configured filesystem root.
-    let source_root_str = match state
-        .config
-        .sources
-        .get(&params.source)
-        .and_then(|sc| sc.path.as_deref())
-    {
-        Some(p) => p.to_owned(),
-        None => {
-            tracing::warn!(source = %params.source, path = %params.path, "raw: source not configured or has no path");
-            return StatusCode::NOT_FOUND.into_response();
-        }
-    };
-
-    let source_root = std::path::Path::new(&source_root_str);
-    let full_path = source_root.join(&params.path);
-
-    // Canonicalize both paths and confirm the file is still inside the root.
-    let canonical_root = match source_root.canonicalize() {
-        Ok(p) => p,
-        Err(e) => {
-            tracing::warn!(source = %params.source, root = %source_root_str, error = %e,
-                "raw: source root not accessible — mount may have failed or path is misconfigured");
-            return StatusCode::NOT_FOUND.into_response();
-        }
-    };
-    let canonical_full = match full_path.canonicalize() {
-        Ok(p) => p,
-        Err(e) => {
-            log_file_not_found(&params.source, source_root, &full_path, &e, "raw");
-            return StatusCode::NOT_FOUND.into_response();
-        }
+    let canonical_full = match resolve_mounted_file(&state, &params.source, &params.path) {
+        Ok(Some(p)) => p,
+        Ok(None) => match stored_original(&state, &params.source, &params.path).await {
+            Some(p) => p,
+            None => return StatusCode::NOT_FOUND.into_response(),
+        },
+        Err(s) => return s.into_response(),
     };
-    if !canonical_full.starts_with(&canonical_root) {
-        tracing::warn!(source = %params.source, path = %params.path, "raw: path escapes source root");
-        return StatusCode::BAD_REQUEST.into_response();
-    }
+    // Name the response after the requested path: a stored original lives
+    // under its hash, not its real filename.
+    let name_path = std::path::Path::new(&params.path);
 
     // If convert=png is requested, decode the image and re-encode as PNG.
     // Build Content-Disposition with the real filename so browser PDF/image
     // viewers show the actual name rather than "raw" (the endpoint path).
-    let display_filename = canonical_full
+    let display_filename = name_path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("file");
     // For convert=png the extension changes, so use the stem + ".png".
-    let png_filename = canonical_full
+    let png_filename = name_path
         .file_stem()
         .and_then(|n| n.to_str())
         .map(|stem| format!("{stem}.png"))
@@ -195,7 +171,7 @@ pub async fn get_raw(
         // (detected by magic bytes), serve it directly with the correct MIME type
         // rather than decoding and re-encoding to PNG.
         if let Some((mime, ext)) = crate::image_util::sniff_browser_format(&bytes) {
-            let stem = canonical_full.file_stem().and_then(|n| n.to_str()).unwrap_or("file").replace('"', "");
+            let stem = name_path.file_stem().and_then(|n| n.to_str()).unwrap_or("file").replace('"', "");
             let native_disp = format!("{disp_kind}; filename=\"{stem}.{ext}\"");
             return Response::builder()
                 .status(StatusCode::OK)
@@ -233,7 +209,7 @@ pub async fn get_raw(
         Err(_) => return StatusCode::NOT_FOUND.into_response(),
     };
 
-    let mime = mime_guess::from_path(&canonical_full).first_or_octet_stream();
+    let mime = mime_guess::from_path(name_path).first_or_octet_stream();
 
     // Parse Range header if present.
     let range = headers
@@ -287,6 +263,81 @@ pub async fn get_raw(
     }
 }
 
+/// Resolve `rel_path` under the source's mounted filesystem root.
+///
+/// Returns `Ok(None)` when the source has no `path`, the root is not
+/// accessible or the file is missing, so the caller can fall back to the
+/// originals store.  A path that escapes the root is a hard 400.
+fn resolve_mounted_file(
+    state: &AppState,
+    source: &str,
+    rel_path: &str,
+) -> Result<Option<std::path::PathBuf>, StatusCode> {
+    // Look up the source's configured filesystem root.
+    let Some(source_root_str) = state
+        .config
+        .sources
+        .get(source)
+        .and_then(|sc| sc.path.as_deref())
+    else {
+        if !state.config.originals.enabled {
+            tracing::warn!(source, path = rel_path, "raw: source not configured or has no path");
+        }
+        return Ok(None);
+    };
+
+    let source_root = std::path::Path::new(source_root_str);
+    let full_path = source_root.join(rel_path);
+
+    // Canonicalize both paths and confirm the file is still inside the root.
+    let canonical_root = match source_root.canonicalize() {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::warn!(source, root = %source_root_str, error = %e,
+                "raw: source root not accessible — mount may have failed or path is misconfigured");
+            return Ok(None);
+        }
+    };
+    let canonical_full = match full_path.canonicalize() {
+        Ok(p) => p,
+        Err(e) => {
+            log_file_not_found(source, source_root, &full_path, &e, "raw");
+            return Ok(None);
+        }
+    };
+    if !canonical_full.starts_with(&canonical_root) {
+        tracing::warn!(source, path = rel_path, "raw: path escapes source root");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(Some(canonical_full))
+}
+
+/// Locate the stored original for `rel_path` via its `file_hash`, when the
+/// originals store is enabled and the scanner uploaded a copy.
+pub(super) async fn stored_original(state: &AppState, source: &str, rel_path: &str) -> Option<std::path::PathBuf> {
+    if !state.config.originals.enabled {
+        return None;
+    }
+    let db_path = super::source_db_path(state, source).ok()?;
+    let rel_path = rel_path.to_owned();
+    let hash = tokio::task::spawn_blocking(move || -> Option<String> {
+        if !db_path.exists() {
+            return None;
+        }
+        let conn = crate::db::open_for_stats(&db_path).ok()?;
+        conn.query_row(
+            "SELECT file_hash FROM files WHERE path = ?1 AND file_hash IS NOT NULL",
+            [&rel_path],
+            |r| r.get(0),
+        ).ok()
+    }).await.ok()??;
+    if !crate::originals::is_valid_hash(&hash) {
+        return None;
+    }
+    let path = crate::originals::original_path(&state.data_dir, &hash);
+    tokio::fs::metadata(&path).await.ok().map(|_| path)
+}
+
 /// Serve a member from a ZIP archive at `outer_path` within the source root.
 /// `outer_path` is the path of the outer ZIP file (no `::`, no leading `/`).
 /// `member_name` is the path of the member inside the ZIP (may be nested).
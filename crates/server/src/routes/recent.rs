@@ -3,161 +3,135 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use axum::{
-    extract::{Query, State},
-    http::HeaderMap,
+    extract::{FromRequestParts, State},
+    http::{request::Parts, HeaderMap, StatusCode},
     response::{sse::{Event, KeepAlive, Sse}, IntoResponse},
     Json,
 };
-use serde::Deserialize;
 use tokio::task::spawn_blocking;
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt as _;
 
-use find_common::api::{RecentAction, RecentFile, RecentResponse};
+use find_common::api::{FileKind, RecentAction, RecentFile, RecentResponse};
 
-use crate::{db, AppState};
+use crate::db::{self, RecentFilter};
+use crate::AppState;
 
-use super::check_auth;
+use super::{check_auth, source_db_path};
 
 // ── GET /api/v1/recent ────────────────────────────────────────────────────────
 
-#[derive(Deserialize)]
-pub struct RecentQuery {
-    #[serde(default = "default_limit")]
+pub struct RecentParams {
     limit: usize,
-    /// `mtime` = sort by file modification time; anything else (or absent) = sort by indexed time.
-    #[serde(default)]
-    sort: String,
+    /// `sort=mtime` = sort by file modification time; anything else (or absent)
+    /// = the activity log, by indexed time.
+    sort_by_mtime: bool,
+    /// Collected from repeated ?source=a&source=b params. Empty = all sources.
+    sources: Vec<String>,
+    /// `since` (unix seconds) and repeated `kind` params.
+    filter: RecentFilter,
 }
 
-const MAX_RECENT_LIMIT: usize = 1000;
+impl<S: Send + Sync> FromRequestParts<S> for RecentParams {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let raw = parts.uri.query().unwrap_or("");
+        let mut params = RecentParams {
+            limit: 20,
+            sort_by_mtime: false,
+            sources: Vec::new(),
+            filter: RecentFilter::default(),
+        };
+        for (k, v) in form_urlencoded::parse(raw.as_bytes()) {
+            match k.as_ref() {
+                "limit"  => params.limit = v.parse::<usize>()
+                    .map_err(|_| (StatusCode::BAD_REQUEST, "invalid limit".to_string()))?,
+                "sort"   => params.sort_by_mtime = v == "mtime",
+                "source" => params.sources.push(v.into_owned()),
+                "kind"   => params.filter.kinds.push(FileKind::from(v.as_ref())),
+                "since"  => params.filter.since = Some(v.parse::<i64>()
+                    .map_err(|_| (StatusCode::BAD_REQUEST, "invalid since".to_string()))?),
+                _ => {}
+            }
+        }
+        Ok(params)
+    }
+}
 
-fn default_limit() -> usize { 20 }
+const MAX_RECENT_LIMIT: usize = 1000;
 
 pub async fn get_recent(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Query(query): Query<RecentQuery>,
+    params: RecentParams,
 ) -> impl IntoResponse {
     if let Err(s) = check_auth(&state, &headers) {
         return (s, Json(serde_json::Value::Null)).into_response();
     }
-
-    let sources_dir = state.data_dir.join("sources");
-    if query.limit > MAX_RECENT_LIMIT {
+    if params.limit > MAX_RECENT_LIMIT {
         return (
-            axum::http::StatusCode::BAD_REQUEST,
+            StatusCode::BAD_REQUEST,
             Json(serde_json::json!({ "error": format!("limit exceeds maximum of {MAX_RECENT_LIMIT}") })),
         ).into_response();
     }
-    let limit = query.limit;
-    let sort_by_mtime = query.sort == "mtime";
-
-    let source_dbs: Vec<(String, std::path::PathBuf)> = match std::fs::read_dir(&sources_dir) {
-        Err(_) => vec![],
-        Ok(rd) => rd
-            .filter_map(|e| {
-                let e = e.ok()?;
-                let name = e.file_name().into_string().ok()?;
-                let source_name = name.strip_suffix(".db")?.to_string();
-                Some((source_name, e.path()))
-            })
-            .collect(),
-    };
-
-    let handles: Vec<_> = source_dbs
-        .into_iter()
-        .map(|(source_name, db_path)| {
-            spawn_blocking(move || -> anyhow::Result<Vec<RecentFile>> {
-                if !db_path.exists() {
-                    return Ok(vec![]);
-                }
-                let conn = db::open(&db_path)?;
-                if sort_by_mtime {
-                    let rows = db::recent_files(&conn, limit, true)?;
-                    Ok(rows
-                        .into_iter()
-                        .map(|(path, indexed_at)| RecentFile {
-                            source: source_name.clone(),
-                            path,
-                            indexed_at,
-                            action: RecentAction::Modified,
-                            new_path: None,
-                        })
-                        .collect())
-                } else {
-                    let rows = db::recent_activity(&conn, limit)?;
-                    Ok(rows
-                        .into_iter()
-                        .map(|(action_str, path, new_path, occurred_at)| RecentFile {
-                            source: source_name.clone(),
-                            path,
-                            indexed_at: occurred_at,
-                            action: RecentAction::from(action_str.as_str()),
-                            new_path,
-                        })
-                        .collect())
-                }
-            })
-        })
-        .collect();
-
-    let mut all: Vec<RecentFile> = Vec::new();
-    for handle in handles {
-        match handle.await.unwrap_or_else(|e| Err(anyhow::anyhow!(e))) {
-            Ok(files) => all.extend(files),
-            Err(e) => tracing::warn!("recent files error: {e:#}"),
-        }
-    }
-
-    all.sort_by_key(|a| Reverse(a.indexed_at));
-    all.truncate(limit);
 
-    Json(RecentResponse { files: all }).into_response()
+    let files = fetch_recent_from_dbs(&state, &params).await;
+    Json(RecentResponse { files }).into_response()
 }
 
-// ── GET /api/v1/recent/stream (SSE) ──────────────────────────────────────────
-
-/// Shared helper: fetch recent files from all source DBs, sorted newest-first.
-async fn fetch_recent_from_dbs(state: &AppState, limit: usize, sort_by_mtime: bool) -> Vec<RecentFile> {
-    let sources_dir = state.data_dir.join("sources");
-    let source_dbs: Vec<(String, std::path::PathBuf)> = match std::fs::read_dir(&sources_dir) {
-        Err(_) => vec![],
-        Ok(rd) => rd
-            .filter_map(|e| {
-                let e = e.ok()?;
-                let name = e.file_name().into_string().ok()?;
-                let source_name = name.strip_suffix(".db")?.to_string();
-                Some((source_name, e.path()))
-            })
-            .collect(),
+/// Fetch recent files from the requested source DBs (all when none are
+/// named), sorted newest-first.
+async fn fetch_recent_from_dbs(state: &AppState, params: &RecentParams) -> Vec<RecentFile> {
+    let source_dbs: Vec<(String, std::path::PathBuf)> = if params.sources.is_empty() {
+        match std::fs::read_dir(state.data_dir.join("sources")) {
+            Err(_) => vec![],
+            Ok(rd) => rd
+                .filter_map(|e| {
+                    let e = e.ok()?;
+                    let name = e.file_name().into_string().ok()?;
+                    let source_name = name.strip_suffix(".db")?.to_string();
+                    Some((source_name, e.path()))
+                })
+                .collect(),
+        }
+    } else {
+        params.sources.iter().filter_map(|s| {
+            source_db_path(state, s).ok().map(|p| (s.clone(), p))
+        }).collect()
     };
+
+    let (limit, sort_by_mtime) = (params.limit, params.sort_by_mtime);
     let handles: Vec<_> = source_dbs
         .into_iter()
         .map(|(source_name, db_path)| {
+            let pool = Arc::clone(&state.db_pool);
+            let filter = params.filter.clone();
             spawn_blocking(move || -> anyhow::Result<Vec<RecentFile>> {
                 if !db_path.exists() {
                     return Ok(vec![]);
                 }
-                let conn = db::open(&db_path)?;
+                let conn = pool.get(&db_path)?;
                 if sort_by_mtime {
-                    db::recent_files(&conn, limit, true).map(|rows| {
-                        rows.into_iter().map(|(path, indexed_at)| RecentFile {
+                    db::recent_files(&conn, limit, true, &filter).map(|rows| {
+                        rows.into_iter().map(|(path, indexed_at, kind)| RecentFile {
                             source: source_name.clone(),
                             path,
                             indexed_at,
                             action: RecentAction::Modified,
                             new_path: None,
+                            kind: Some(kind),
                         }).collect()
                     })
                 } else {
-                    db::recent_activity(&conn, limit).map(|rows| {
-                        rows.into_iter().map(|(action_str, path, new_path, occurred_at)| RecentFile {
+                    db::recent_activity(&conn, limit, &filter).map(|rows| {
+                        rows.into_iter().map(|(action_str, path, new_path, occurred_at, kind)| RecentFile {
                             source: source_name.clone(),
                             path,
                             indexed_at: occurred_at,
                             action: RecentAction::from(action_str.as_str()),
                             new_path,
+                            kind,
                         }).collect()
                     })
                 }
@@ -176,24 +150,25 @@ async fn fetch_recent_from_dbs(state: &AppState, limit: usize, sort_by_mtime: bo
     all
 }
 
+// ── GET /api/v1/recent/stream (SSE) ──────────────────────────────────────────
+
 pub async fn stream_recent(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Query(query): Query<RecentQuery>,
+    mut params: RecentParams,
 ) -> impl IntoResponse {
     if let Err(s) = check_auth(&state, &headers) {
         return (s, "Unauthorized").into_response();
     }
 
-    let limit = query.limit.min(MAX_RECENT_LIMIT);
-    let sort_by_mtime = query.sort == "mtime";
+    params.limit = params.limit.min(MAX_RECENT_LIMIT);
 
     // Subscribe before the DB query so we don't miss events that arrive
     // while we're fetching history.
     let rx = state.recent_tx.subscribe();
 
     // Fetch historical entries; send them oldest-first (tail -f style).
-    let mut initial = fetch_recent_from_dbs(&state, limit, sort_by_mtime).await;
+    let mut initial = fetch_recent_from_dbs(&state, &params).await;
     initial.reverse();
 
     let make_event = |f: RecentFile| -> Result<Event, std::convert::Infallible> {
@@ -202,8 +177,13 @@ pub async fn stream_recent(
 
     let initial_stream = tokio_stream::iter(initial).map(make_event);
 
+    // Live events pass the same source, since and kind filters.
     let live_stream = BroadcastStream::new(rx)
         .filter_map(|r| r.ok())
+        .filter(move |f| {
+            (params.sources.is_empty() || params.sources.contains(&f.source))
+                && params.filter.matches(f.indexed_at, f.kind.as_ref())
+        })
         .map(|f| Ok::<Event, std::convert::Infallible>(
             Event::default().json_data(&f).unwrap_or_default()
         ));
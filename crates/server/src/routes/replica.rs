@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tokio_stream::StreamExt as _;
+use tokio_util::io::ReaderStream;
+
+use find_common::api::{ReplicaBlob, ReplicaBlobsRequest, ReplicaBlobsResponse, ReplicaManifest};
+use find_content_store::ContentKey;
+
+use crate::AppState;
+
+use super::{check_auth, run_blocking, source_db_path};
+
+/// Maximum number of blobs a replica may request in one call.
+const MAX_BLOBS_PER_REQUEST: usize = 500;
+
+// ── GET /api/v1/replica/manifest ──────────────────────────────────────────────
+
+pub async fn get_replica_manifest(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let sources_dir = state.data_dir.join("sources");
+    run_blocking("get_replica_manifest", move || {
+        let sources = crate::replica::build_manifest(&sources_dir)?;
+        Ok(Json(ReplicaManifest { sources }))
+    }).await
+}
+
+// ── GET /api/v1/replica/source?name=X ─────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ReplicaSourceParams {
+    pub name: String,
+}
+
+/// Stream a consistent snapshot of one source database.
+///
+/// The snapshot is taken with `VACUUM INTO` so replicas never receive a
+/// half-written file, regardless of concurrent worker writes.
+pub async fn get_replica_source(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ReplicaSourceParams>,
+) -> Response {
+    if let Err(s) = check_auth(&state, &headers) {
+        return s.into_response();
+    }
+    let db_path = match source_db_path(&state, &params.name) {
+        Ok(p) => p,
+        Err(s) => return s.into_response(),
+    };
+    if !db_path.exists() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let tmp_dir = state.data_dir.clone();
+    let snapshot = tokio::task::spawn_blocking(move || crate::replica::snapshot_source(&db_path, &tmp_dir))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e)));
+    let snapshot = match snapshot {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("get_replica_source: {e:#}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let file = match tokio::fs::File::open(&snapshot).await {
+        Ok(f) => f,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let size = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+    // The closure owns the TempPath, so the snapshot is deleted once the
+    // stream is dropped (fully sent or client disconnected).
+    let stream = ReaderStream::new(file).map(move |chunk| {
+        let _keep = &snapshot;
+        chunk
+    });
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/vnd.sqlite3")
+        .header(header::CONTENT_LENGTH, size.to_string())
+        .body(Body::from_stream(stream))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+// ── POST /api/v1/replica/blobs ────────────────────────────────────────────────
+
+pub async fn post_replica_blobs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<ReplicaBlobsRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    if req.keys.len() > MAX_BLOBS_PER_REQUEST {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let cs = Arc::clone(&state.content_store);
+    run_blocking("post_replica_blobs", move || {
+        let mut blobs = Vec::with_capacity(req.keys.len());
+        for key in req.keys {
+            let Some(lines) = cs.get_lines(&ContentKey::new(key.as_str()), 0, i64::MAX as usize)? else {
+                continue;
+            };
+            let content = lines.into_iter().map(|(_, l)| l).collect::<Vec<_>>().join("\n");
+            blobs.push(ReplicaBlob { key, content });
+        }
+        Ok(Json(ReplicaBlobsResponse { blobs }))
+    }).await
+}
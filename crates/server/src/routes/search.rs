@@ -1,15 +1,22 @@
-use std::cmp::Reverse;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use axum::{
-    extract::{FromRequestParts, State},
-    http::{request::Parts, HeaderMap, StatusCode},
-    response::IntoResponse,
+    body::Body,
+    extract::{FromRequestParts, Query, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use serde::Deserialize;
 use tokio::task::spawn_blocking;
 
-use find_common::api::{ContextLine, FileKind, SearchMode, SearchResponse, SearchResult};
+use find_common::api::{
+    format_stale_sources, ContextLine, FileKind, MatchingFile, SearchExplain, SearchExportRow, SearchMode,
+    SearchResponse, SearchResult, SearchSort, SearchSummaryResponse, SourceExplain, SourceMatchCount,
+    LINE_METADATA, STALE_SOURCES_HEADER,
+};
 
 use crate::fuzzy::FuzzyScorer;
 use crate::{db, db::search::CandidateRow, db::DateFilter, AppState};
@@ -20,7 +27,130 @@ struct ScoredResult {
     file_id: i64,
 }
 
-use super::{check_auth, source_db_path};
+use super::{check_auth, run_blocking, source_db_path};
+
+// ── Time budget ───────────────────────────────────────────────────────────────
+
+/// Cooperative stop signal shared by the per-source search tasks: set once
+/// the `search.timeout_ms` deadline passes or the client goes away.
+///
+/// `spawn_blocking` tasks cannot be aborted, so they poll this between
+/// candidates, and SQLite polls it through a progress handler so that a long
+/// FTS query is interrupted mid-statement.
+#[derive(Clone)]
+struct SearchBudget {
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchBudget {
+    fn new(timeout_ms: u64) -> Self {
+        Self {
+            deadline: (timeout_ms > 0).then(|| Instant::now() + Duration::from_millis(timeout_ms)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.exhausted() {
+            anyhow::bail!("search time budget exhausted");
+        }
+        Ok(())
+    }
+
+    /// Interrupt statements on `conn` once the budget is exhausted.
+    fn watch(&self, conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+        let budget = self.clone();
+        conn.progress_handler(10_000, Some(move || budget.exhausted()))
+    }
+}
+
+/// Cancels the budget when dropped.  axum drops the handler future when the
+/// client disconnects, so a search nobody is waiting for stops early.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+// ── Explain ───────────────────────────────────────────────────────────────────
+
+/// Where a source's search task records its cost for `explain=true`.  The
+/// entry outlives the task, so a source that fails or times out still
+/// reports what it did before it stopped.  Records nothing when explain is
+/// off.
+#[derive(Clone, Default)]
+struct CostRecorder(Option<Arc<Mutex<SourceExplain>>>);
+
+impl CostRecorder {
+    fn new(enabled: bool, source: &str) -> Self {
+        Self(enabled.then(|| {
+            Arc::new(Mutex::new(SourceExplain { source: source.to_string(), ..Default::default() }))
+        }))
+    }
+
+    fn record(&self, f: impl FnOnce(&mut SourceExplain)) {
+        if let Some(cost) = &self.0 {
+            f(&mut cost.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+    }
+
+    /// Run `f`, a full-text index query, adding its time to `fts_ms`.
+    fn fts<T>(&self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let out = f();
+        self.record(|c| c.fts_ms += start.elapsed().as_millis() as u64);
+        out
+    }
+
+    fn candidates(&self, found: usize, limit: usize) {
+        self.record(|c| {
+            c.candidates += found;
+            c.candidate_limit = limit;
+            c.capped |= found >= limit;
+        });
+    }
+
+    /// [`db::read_content_batch`], counting the lines and files read.
+    fn read_content(
+        &self,
+        conn: &rusqlite::Connection,
+        cs: &dyn find_content_store::ContentStore,
+        pairs: &[(i64, i64)],
+    ) -> std::collections::HashMap<(i64, i64), String> {
+        self.record(|c| {
+            let files: std::collections::HashSet<i64> = pairs.iter().map(|&(file_id, _)| file_id).collect();
+            c.lines_read += pairs.len();
+            c.content_reads += files.len();
+        });
+        db::read_content_batch(conn, cs, pairs)
+    }
+
+    /// Start timing the task; `elapsed_ms` is set when the timer is dropped,
+    /// however the task ends.
+    fn timer(&self) -> CostTimer {
+        CostTimer(self.clone(), Instant::now())
+    }
+
+    fn finish(self) -> Option<SourceExplain> {
+        self.0.map(|cost| cost.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+}
+
+struct CostTimer(CostRecorder, Instant);
+
+impl Drop for CostTimer {
+    fn drop(&mut self) {
+        let elapsed_ms = self.1.elapsed().as_millis() as u64;
+        self.0.record(|c| c.elapsed_ms = elapsed_ms);
+    }
+}
 
 // ── GET /api/v1/search ────────────────────────────────────────────────────────
 
@@ -41,6 +171,29 @@ pub struct SearchParams {
     /// Optional path prefix filter from `dir:` client keyword.
     /// Already normalised (no leading/trailing slashes).
     pub path_prefix: Option<String>,
+    /// Optional extension allowlist from repeated `?ext=`, lowercase without
+    /// the dot. Empty = any extension.
+    pub exts: Vec<String>,
+    /// Optional size bounds in bytes, inclusive.
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    /// Path substrings and extensions to exclude, from repeated
+    /// `?exclude_path=` / `?exclude_ext=` and from `-path:` / `-ext:` terms,
+    /// which are removed from `q`.
+    pub exclude_paths: Vec<String>,
+    pub exclude_exts: Vec<String>,
+    /// When false, skip federation peers and search only local sources.
+    /// Peers are always queried with `federate=false`.  Default: true.
+    pub federate: bool,
+    /// When false, hits on files with identical content are collapsed into
+    /// the best-scoring one, which lists the others in `duplicate_paths`.
+    /// Default: false.
+    pub expand_duplicates: bool,
+    /// Result order.  Default: score.
+    pub sort: SearchSort,
+    /// When true, the response includes per-source timings and candidate
+    /// counts (`explain`).  Default: false.
+    pub explain: bool,
 }
 
 impl<S: Send + Sync> FromRequestParts<S> for SearchParams {
@@ -58,6 +211,15 @@ impl<S: Send + Sync> FromRequestParts<S> for SearchParams {
         let mut kinds = Vec::new();
         let mut case_sensitive = false;
         let mut path_prefix: Option<String> = None;
+        let mut exts = Vec::new();
+        let mut min_size = None;
+        let mut max_size = None;
+        let mut exclude_paths = Vec::new();
+        let mut exclude_exts = Vec::new();
+        let mut federate = true;
+        let mut expand_duplicates = false;
+        let mut sort = SearchSort::default();
+        let mut explain = false;
 
         for (k, v) in form_urlencoded::parse(raw.as_bytes()) {
             match k.as_ref() {
@@ -73,7 +235,24 @@ impl<S: Send + Sync> FromRequestParts<S> for SearchParams {
                     .map_err(|_| (StatusCode::BAD_REQUEST, "invalid date_from".to_string()))?),
                 "date_to"        => date_to   = Some(v.parse::<i64>()
                     .map_err(|_| (StatusCode::BAD_REQUEST, "invalid date_to".to_string()))?),
+                "ext"            => {
+                    let e = v.trim().trim_start_matches('.').to_lowercase();
+                    if !e.is_empty() { exts.push(e); }
+                }
+                "min_size"       => min_size  = Some(v.parse::<i64>()
+                    .map_err(|_| (StatusCode::BAD_REQUEST, "invalid min_size".to_string()))?),
+                "max_size"       => max_size  = Some(v.parse::<i64>()
+                    .map_err(|_| (StatusCode::BAD_REQUEST, "invalid max_size".to_string()))?),
+                "exclude_path" if !v.is_empty() => exclude_paths.push(v.into_owned()),
+                "exclude_ext"    => {
+                    let e = v.trim().trim_start_matches('.').to_lowercase();
+                    if !e.is_empty() { exclude_exts.push(e); }
+                }
                 "case_sensitive" => case_sensitive = matches!(v.as_ref(), "1" | "true"),
+                "federate"       => federate = !matches!(v.as_ref(), "0" | "false"),
+                "expand_duplicates" => expand_duplicates = matches!(v.as_ref(), "1" | "true"),
+                "explain"        => explain = matches!(v.as_ref(), "1" | "true"),
+                "sort"           => sort = serde_json::from_value(serde_json::Value::String(v.into_owned())).unwrap_or_default(),
                 "path_prefix"    => {
                     let p = v.trim().trim_start_matches('/').trim_end_matches('/').to_string();
                     if !p.is_empty() { path_prefix = Some(p); }
@@ -82,8 +261,11 @@ impl<S: Send + Sync> FromRequestParts<S> for SearchParams {
             }
         }
 
+        let q = q.ok_or_else(|| (StatusCode::BAD_REQUEST, "missing 'q'".to_string()))?;
+        let q = split_exclusions(&q, &mut exclude_paths, &mut exclude_exts).unwrap_or(q);
+
         Ok(SearchParams {
-            q:    q.ok_or_else(|| (StatusCode::BAD_REQUEST, "missing 'q'".to_string()))?,
+            q,
             mode,
             source,
             limit:     limit.unwrap_or(50),
@@ -93,13 +275,160 @@ impl<S: Send + Sync> FromRequestParts<S> for SearchParams {
             kinds,
             case_sensitive,
             path_prefix,
+            exts,
+            min_size,
+            max_size,
+            exclude_paths,
+            exclude_exts,
+            federate,
+            expand_duplicates,
+            sort,
+            explain,
+        })
+    }
+}
+
+impl SearchParams {
+    /// The filters shared by every mode.
+    fn date_filter(&self) -> DateFilter {
+        DateFilter {
+            from: self.date_from,
+            to: self.date_to,
+            kinds: self.kinds.iter().map(|s| FileKind::from(s.as_str())).collect(),
+            filename_only: false,
+            path_prefix: self.path_prefix.clone(),
+            exts: self.exts.clone(),
+            min_size: self.min_size,
+            max_size: self.max_size,
+            exclude_paths: self.exclude_paths.clone(),
+            exclude_exts: self.exclude_exts.clone(),
+            sort: self.sort,
+            after_rowid: None,
+        }
+    }
+
+    /// Query string forwarded to federation peers: the same search, asking
+    /// for the first `offset + limit` hits so the merged page can be cut
+    /// locally, with `federate=false` to stop peers fanning out again.
+    fn peer_query(&self, limit: usize) -> Vec<(&'static str, String)> {
+        let mut q = vec![("q", self.q.clone())];
+        if let Ok(serde_json::Value::String(mode)) = serde_json::to_value(&self.mode) {
+            q.push(("mode", mode));
+        }
+        q.extend(self.source.iter().map(|s| ("source", s.clone())));
+        q.extend(self.kinds.iter().map(|k| ("kind", k.clone())));
+        if let Some(from) = self.date_from { q.push(("date_from", from.to_string())); }
+        if let Some(to) = self.date_to { q.push(("date_to", to.to_string())); }
+        if self.case_sensitive { q.push(("case_sensitive", "true".to_string())); }
+        if let Some(p) = &self.path_prefix { q.push(("path_prefix", p.clone())); }
+        q.extend(self.exts.iter().map(|e| ("ext", e.clone())));
+        if let Some(min) = self.min_size { q.push(("min_size", min.to_string())); }
+        if let Some(max) = self.max_size { q.push(("max_size", max.to_string())); }
+        q.extend(self.exclude_paths.iter().map(|p| ("exclude_path", p.clone())));
+        q.extend(self.exclude_exts.iter().map(|e| ("exclude_ext", e.clone())));
+        if self.expand_duplicates { q.push(("expand_duplicates", "true".to_string())); }
+        if let Ok(serde_json::Value::String(sort)) = serde_json::to_value(self.sort) {
+            q.push(("sort", sort));
+        }
+        q.push(("limit", (self.offset + limit).to_string()));
+        q.push(("offset", "0".to_string()));
+        q.push(("federate", "false".to_string()));
+        q
+    }
+}
+
+/// Move the `-path:<substring>` and `-ext:<ext>` terms of `q` into
+/// `exclude_paths` / `exclude_exts`, returning the rest of the query, or
+/// `None` when it has no such terms.
+fn split_exclusions(q: &str, exclude_paths: &mut Vec<String>, exclude_exts: &mut Vec<String>) -> Option<String> {
+    let mut rest = Vec::new();
+    let mut found = false;
+    for word in q.split_whitespace() {
+        if let Some(p) = word.strip_prefix("-path:").filter(|p| !p.is_empty()) {
+            exclude_paths.push(p.to_string());
+        } else if let Some(e) = word.strip_prefix("-ext:").map(|e| e.trim_start_matches('.')).filter(|e| !e.is_empty()) {
+            exclude_exts.push(e.to_lowercase());
+        } else {
+            rest.push(word);
+            continue;
+        }
+        found = true;
+    }
+    found.then(|| rest.join(" "))
+}
+
+/// Order `results` by `sort`, breaking ties by score.
+fn sort_results(results: &mut [SearchResult], sort: SearchSort) {
+    let by_score = |a: &SearchResult, b: &SearchResult| b.score.cmp(&a.score);
+    match sort {
+        SearchSort::Score => results.sort_by(by_score),
+        SearchSort::Mtime => results.sort_by(|a, b| b.mtime.cmp(&a.mtime).then_with(|| by_score(a, b))),
+        SearchSort::Path => results.sort_by(|a, b| {
+            (&a.path, &a.archive_path, a.line_number, &a.source)
+                .cmp(&(&b.path, &b.archive_path, b.line_number, &b.source))
+                .then_with(|| by_score(a, b))
+        }),
+        // `None` sorts below every size, so it comes last.
+        SearchSort::Size => results.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| by_score(a, b))),
+        // A file's hits stay together, in line order.  Hits without a count
+        // (other modes) come last, by score.
+        SearchSort::Hits => results.sort_by(|a, b| match (a.hit_count, b.hit_count) {
+            (None, None) => by_score(a, b),
+            _ => b.hit_count.cmp(&a.hit_count)
+                .then_with(|| (&a.source, &a.path, &a.archive_path).cmp(&(&b.source, &b.path, &b.archive_path)))
+                .then_with(|| a.line_number.cmp(&b.line_number)),
+        }),
+    }
+}
+
+/// Drop hits on files whose content is identical to an earlier hit's on the
+/// same line; the kept hit already lists them in `duplicate_paths`.
+fn collapse_duplicates(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    // (origin, source, full path, line) of each kept hit.
+    let mut kept = std::collections::HashSet::<(Option<String>, String, String, usize)>::new();
+    results
+        .into_iter()
+        .filter(|r| {
+            let key = |path: String| (r.origin.clone(), r.source.clone(), path, r.line_number);
+            if r.duplicate_paths.iter().any(|d| kept.contains(&key(d.clone()))) {
+                return false;
+            }
+            let full = match &r.archive_path {
+                Some(member) => find_common::path::make_composite(&r.path, member),
+                None => r.path.clone(),
+            };
+            kept.insert(key(full));
+            true
         })
+        .collect()
+}
+
+/// `(source_name, db_path)` for each source to search: `names`, or every
+/// source when empty.
+fn source_dbs(state: &AppState, names: &[String]) -> Vec<(String, std::path::PathBuf)> {
+    if names.is_empty() {
+        // All sources: scan the sources directory.
+        match std::fs::read_dir(state.data_dir.join("sources")) {
+            Err(_) => vec![],
+            Ok(rd) => rd
+                .filter_map(|e| {
+                    let e = e.ok()?;
+                    let name = e.file_name().into_string().ok()?;
+                    let source_name = name.strip_suffix(".db")?.to_string();
+                    Some((source_name, e.path()))
+                })
+                .collect(),
+        }
+    } else {
+        names.iter().filter_map(|s| {
+            source_db_path(state, s).ok().map(|p| (s.clone(), p))
+        }).collect()
     }
 }
 
 /// Extract maximal sequences of non-special characters from a regex pattern
 /// to use as FTS5 pre-filter terms. Special regex chars (`^$.*+?|()[]{}\`)
-/// act as delimiters; escaped sequences are skipped entirely.
+/// and double quotes act as delimiters; escaped sequences are skipped entirely.
 ///
 /// Examples:
 ///   `^fn\s+\w+`   → "fn"   (too short, filtered out by fts_candidates)
@@ -117,8 +446,9 @@ fn regex_to_fts_terms(pattern: &str) -> String {
                 terms.push(std::mem::take(&mut current));
             }
             chars.next();
-        } else if "^$.*+?|()[]{}".contains(c) {
-            // Regex special char — flush current literal sequence.
+        } else if "^$.*+?|()[]{}\"".contains(c) {
+            // Regex special char (or a quote, which FTS would read as a
+            // phrase) — flush current literal sequence.
             if !current.is_empty() {
                 terms.push(std::mem::take(&mut current));
             }
@@ -132,6 +462,69 @@ fn regex_to_fts_terms(pattern: &str) -> String {
     terms.join(" ")
 }
 
+/// The text a source's FTS index is searched with: `query` (for regex modes,
+/// its literal fragments) normalized the way `tokens` normalized the
+/// source's lines.
+fn fts_text(mode: &SearchMode, query: &str, tokens: &db::tokens::Normalizer) -> String {
+    match mode {
+        SearchMode::Regex | SearchMode::FileRegex | SearchMode::DocRegex => {
+            tokens.text(&regex_to_fts_terms(query)).into_owned()
+        }
+        _ => tokens.text(query).into_owned(),
+    }
+}
+
+/// Literal strings that every FTS match of `query` contains, mirroring how
+/// each mode builds its FTS expression.  A source whose term filter lacks
+/// one of them cannot match and is skipped.  Empty when nothing is required.
+fn required_literals(mode: &SearchMode, query: &str) -> Vec<String> {
+    let doc_tokens = |q: &str| -> Vec<String> {
+        q.split_whitespace().filter(|w| w.len() >= 3).map(str::to_string).collect()
+    };
+    match mode {
+        SearchMode::Exact | SearchMode::FileExact | SearchMode::DocExact => {
+            if query.len() >= 3 { vec![query.to_string()] } else { vec![] }
+        }
+        SearchMode::Fuzzy | SearchMode::FileFuzzy => db::search::fts_terms(query),
+        SearchMode::Regex | SearchMode::FileRegex => db::search::fts_terms(&regex_to_fts_terms(query)),
+        SearchMode::Document => doc_tokens(query),
+        SearchMode::DocRegex => doc_tokens(&regex_to_fts_terms(query)),
+    }
+}
+
+/// Number of lines in each of `file_ids` that the FTS index matches for
+/// `fts_text` and, when given, that `verify` accepts: the exact and regex
+/// modes' post-filters, for searches the index alone cannot answer exactly.
+/// At most `limit` lines are examined in all.
+#[allow(clippy::too_many_arguments)]
+fn hit_counts(
+    conn: &rusqlite::Connection,
+    cs: &dyn find_content_store::ContentStore,
+    fts_text: &str,
+    phrase: bool,
+    verify: Option<&dyn Fn(&str) -> bool>,
+    file_ids: &[i64],
+    limit: usize,
+    cost: &CostRecorder,
+) -> anyhow::Result<std::collections::HashMap<i64, usize>> {
+    let lines = cost.fts(|| db::fts_file_lines(conn, fts_text, phrase, file_ids, limit))?;
+    let content = match verify {
+        Some(_) => cost.read_content(conn, cs, &lines),
+        None => Default::default(),
+    };
+    let mut counts = std::collections::HashMap::new();
+    for key in lines {
+        let matches = match verify {
+            Some(verify) => content.get(&key).is_some_and(|line| verify(line)),
+            None => true,
+        };
+        if matches {
+            *counts.entry(key.0).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
 /// Group line-level candidates by file, returning one result per file.
 /// The first occurrence per file (highest-ranked by FTS) is the representative;
 /// additional occurrences on different lines become `extra_matches`.
@@ -185,12 +578,97 @@ fn make_result(
         duplicate_paths: vec![],
         extra_matches,
         hits_truncated: false,
+        hit_count: None,
+        metadata_match: false,
+        origin: None,
+        indexed_at: c.indexed_at,
+        source_last_scan: None,
     }
 }
 
+/// When `find-scan` last completed a full scan of each of `sources`.  Sources
+/// that were never fully scanned, or whose database can't be read, are left out.
+async fn last_scans(state: &AppState, sources: &[String]) -> std::collections::HashMap<String, i64> {
+    let dbs = source_dbs(state, sources);
+    let pool = Arc::clone(&state.db_pool);
+    spawn_blocking(move || {
+        dbs.into_iter()
+            .filter(|(_, db_path)| db_path.exists())
+            .filter_map(|(name, db_path)| {
+                let conn = pool.get(&db_path).ok()?;
+                db::get_last_scan(&conn).ok().flatten().map(|ts| (name, ts))
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::regex_to_fts_terms;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use axum::extract::FromRequestParts;
+    use find_common::api::SearchResponse;
+
+    use super::{
+        regex_to_fts_terms, required_literals, run_search, split_exclusions, CancelOnDrop, SearchBudget, SearchMode,
+        SearchParams,
+    };
+
+    #[test]
+    fn split_exclusions_moves_negative_terms_out_of_query() {
+        let (mut paths, mut exts) = (vec![], vec![]);
+        let q = split_exclusions("parse  config -path:test/ -ext:.LOCK -path:", &mut paths, &mut exts);
+        assert_eq!(q.as_deref(), Some("parse config -path:"));
+        assert_eq!(paths, ["test/"]);
+        assert_eq!(exts, ["lock"]);
+        assert_eq!(split_exclusions("a  -b", &mut paths, &mut exts), None, "other queries are left untouched");
+    }
+
+    #[test]
+    fn search_budget_zero_timeout_never_expires() {
+        let budget = SearchBudget::new(0);
+        assert!(!budget.exhausted());
+        drop(CancelOnDrop(std::sync::Arc::clone(&budget.cancelled)));
+        assert!(budget.exhausted(), "dropping the guard cancels the search");
+    }
+
+    #[tokio::test]
+    async fn search_with_exhausted_budget_reports_timed_out() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let toml = format!("[server]\ndata_dir = \"{}\"\ntoken = \"t\"\n", dir.path().display());
+        let (config, _) = find_common::config::parse_server_config(&toml).unwrap();
+        let state = crate::create_app_state(config).await.unwrap();
+        for source in ["a", "b"] {
+            crate::db::open(&state.data_dir.join("sources").join(format!("{source}.db"))).unwrap();
+        }
+
+        let (mut parts, ()) = axum::http::Request::builder()
+            .uri("/api/v1/search?q=otter&mode=regex")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let params = SearchParams::from_request_parts(&mut parts, &()).await.unwrap();
+        let budget = SearchBudget { deadline: Some(Instant::now()), cancelled: Arc::new(AtomicBool::new(false)) };
+
+        let body = axum::body::to_bytes(run_search(&state, params, budget).await.into_body(), usize::MAX).await.unwrap();
+        let resp: SearchResponse = serde_json::from_slice(&body).unwrap();
+        assert!(resp.timed_out, "no source can run on an exhausted budget");
+        assert!(resp.capped, "partial results are reported as capped");
+        assert!(resp.results.is_empty());
+    }
+
+    #[test]
+    fn required_literals_follow_each_mode() {
+        assert_eq!(required_literals(&SearchMode::Exact, "foo bar"), vec!["foo bar"]);
+        assert_eq!(required_literals(&SearchMode::Fuzzy, "foo-bar x"), vec!["foo", "bar"]);
+        assert_eq!(required_literals(&SearchMode::Regex, r"class\s+Foo.*"), vec!["class", "Foo"]);
+        assert_eq!(required_literals(&SearchMode::Document, "alpha be"), vec!["alpha"]);
+        assert!(required_literals(&SearchMode::Exact, "ab").is_empty());
+    }
 
     #[test]
     fn regex_to_fts_terms_plain_word() {
@@ -223,6 +701,11 @@ mod tests {
         assert_eq!(regex_to_fts_terms("^$.*+?|()[]{}"), "");
     }
 
+    #[test]
+    fn regex_to_fts_terms_quotes_are_delimiters() {
+        assert_eq!(regex_to_fts_terms("\"abc.def\""), "abc def");
+    }
+
     #[test]
     fn regex_to_fts_terms_escaped_sequence_skipped() {
         // \s is an escape sequence — both chars skipped, "hello world" extracted
@@ -236,44 +719,43 @@ pub async fn search(
     params: SearchParams,
 ) -> impl IntoResponse {
     if let Err(s) = check_auth(&state, &headers) { return (s, Json(serde_json::Value::Null)).into_response(); }
+    let budget = SearchBudget::new(state.config.search.timeout_ms);
+    run_search(&state, params, budget).await
+}
 
-    let sources_dir = state.data_dir.join("sources");
+/// Runs a search against `budget`, split from the handler so that tests can
+/// hand in a budget that has already run out.
+async fn run_search(state: &AppState, params: SearchParams, budget: SearchBudget) -> Response {
+    let started = Instant::now();
     let fts_limit = state.config.search.fts_candidate_limit;
     let query = params.q.clone();
-    let mode = params.mode;
+    let mode = params.mode.clone();
     let limit = params.limit.min(state.config.search.max_limit);
+    let _cancel_on_drop = CancelOnDrop(Arc::clone(&budget.cancelled));
 
-    // Build the list of (source_name, db_path) to query.
-    let source_dbs: Vec<(String, std::path::PathBuf)> = if params.source.is_empty() {
-        // All sources: scan the sources directory.
-        match std::fs::read_dir(&sources_dir) {
-            Err(_) => vec![],
-            Ok(rd) => rd
-                .filter_map(|e| {
-                    let e = e.ok()?;
-                    let name = e.file_name().into_string().ok()?;
-                    let source_name = name.strip_suffix(".db")?.to_string();
-                    Some((source_name, e.path()))
-                })
-                .collect(),
-        }
-    } else {
-        params.source.iter().filter_map(|s| {
-            source_db_path(&state, s).ok().map(|p| (s.clone(), p))
-        }).collect()
-    };
+    // Start federation peers first so they run concurrently with local search.
+    let peers = (params.federate && !state.config.federation.peers.is_empty()).then(|| {
+        let cfg = state.config.federation.clone();
+        let peer_query = params.peer_query(limit);
+        tokio::spawn(async move { crate::federation::search_peers(&cfg, &peer_query).await })
+    });
+
+    let source_dbs = source_dbs(state, &params.source);
 
     let content_store = Arc::clone(&state.content_store);
     let offset = params.offset;
-    let date_filter = DateFilter { from: params.date_from, to: params.date_to, kinds: params.kinds.into_iter().map(|s| FileKind::from(s.as_str())).collect(), filename_only: false, path_prefix: params.path_prefix };
+    let date_filter = params.date_filter();
     let case_sensitive = params.case_sensitive;
+    let explain = params.explain;
 
     // Only score enough candidates to fill this page plus a buffer for fuzzy
     // filtering. This avoids reading thousands of ZIP chunks for common queries
     // where the total far exceeds what we show.
     let scoring_limit = (offset + limit + 200).min(fts_limit);
+    let scoring = Arc::new(state.config.search.scoring.clone());
 
-    // Query each source DB in parallel.
+    // Query each source DB in parallel, as far as the search limiter allows.
+    let limiter = state.search_limiter.request();
     let handles: Vec<_> = source_dbs
         .into_iter()
         .map(|(source_name, db_path)| {
@@ -281,36 +763,59 @@ pub async fn search(
             let mode = mode.clone();
             let cs = Arc::clone(&content_store);
             let date_filter = date_filter.clone();
-            spawn_blocking(move || -> anyhow::Result<(usize, Vec<SearchResult>)> {
+            let budget = budget.clone();
+            let pool = Arc::clone(&state.db_pool);
+            let term_filters = Arc::clone(&state.term_filters);
+            let scoring = Arc::clone(&scoring);
+            let cost = CostRecorder::new(explain, &source_name);
+            let task_cost = cost.clone();
+            let limiter = limiter.clone();
+            let deadline = budget.deadline;
+            let task = move || -> anyhow::Result<(usize, Vec<SearchResult>)> {
+                let cost = task_cost;
+                let _timer = cost.timer();
                 if !db_path.exists() { return Ok((0, vec![])); }
-                let conn = db::open(&db_path)?;
+                budget.check()?;
+                let conn = pool.get(&db_path)?;
+                // FTS is queried with the query normalized the way this source's
+                // lines were indexed; content post-filters still see `query`.
+                let tokens = db::tokens::Normalizer::load(&conn)?;
+                let fts_text = fts_text(&mode, &query, &tokens);
+                if !term_filters.get(&db_path).may_match(&required_literals(&mode, &fts_text)) {
+                    tracing::debug!("search: skipping source {source_name}, term filter rules out a match");
+                    cost.record(|c| c.skipped = true);
+                    return Ok((0, vec![]));
+                }
+                budget.watch(&conn)?;
 
                 // Document-family modes: one result per file.
                 match mode {
                     SearchMode::Document => {
                         // Qualify: files containing ALL tokens.
-                        let qualifying_ids = db::document_qualifying_ids(&conn, &query, date_filter)?;
+                        let qualifying_ids = cost.fts(|| db::document_qualifying_ids(&conn, &fts_text, date_filter))?;
                         let doc_total = qualifying_ids.len();
                         if qualifying_ids.is_empty() {
                             return Ok((0, vec![]));
                         }
-                        let Some(or_expr) = db::build_doc_or_expr(&query) else {
+                        let Some(or_expr) = db::build_doc_or_expr(&fts_text) else {
                             return Ok((0, vec![]));
                         };
 
                         // Fetch all lines matching any token, capped at 20 per file.
                         const MAX_LINES_PER_FILE: usize = 20;
-                        let (candidates, truncated_ids) = db::document_all_lines(
+                        let (candidates, truncated_ids) = cost.fts(|| db::document_all_lines(
                             &conn, &qualifying_ids, &or_expr, MAX_LINES_PER_FILE, scoring_limit,
-                        )?;
+                        ))?;
+                        cost.candidates(candidates.len(), scoring_limit);
 
                         // Batch-fetch content for all candidate lines.
                         let pairs: Vec<(i64, i64)> = candidates.iter()
                             .map(|c| (c.file_id, c.line_number as i64))
                             .collect();
-                        let content_map = db::read_content_batch(&conn, cs.as_ref(), &pairs);
+                        let content_map = cost.read_content(&conn, cs.as_ref(), &pairs);
+                        budget.check()?;
 
-                        let mut scorer = FuzzyScorer::new(&query, case_sensitive);
+                        let mut scorer = FuzzyScorer::new(&query, case_sensitive, &scoring);
                         let result_pairs: Vec<ScoredResult> = candidates
                             .into_iter()
                             .map(|mut c| {
@@ -318,7 +823,7 @@ pub async fn search(
                                 if let Some(content) = content_map.get(&(file_id, c.line_number as i64)) {
                                     c.content = content.clone();
                                 }
-                                let score = scorer.score(&c.content).unwrap_or(1);
+                                let score = scorer.score(&c.content, c.line_number).unwrap_or(1);
                                 ScoredResult { result: make_result(&source_name, &c, score, vec![]), file_id }
                             })
                             .collect();
@@ -342,7 +847,8 @@ pub async fn search(
                     SearchMode::DocExact => {
                         // Phrase FTS pre-filter → fts_candidates → group by file.
                         // FTS phrase match is sufficient; no content post-filter needed.
-                        let candidates = db::fts_candidates(&conn, &query, scoring_limit, true, date_filter)?;
+                        let candidates = cost.fts(|| db::fts_candidates(&conn, &fts_text, scoring_limit, true, date_filter))?;
+                        cost.candidates(candidates.len(), scoring_limit);
                         let source_total = candidates.len();
                         let result_pairs = group_by_file(candidates, &source_name);
                         let file_ids: Vec<i64> = result_pairs.iter().map(|sr| sr.file_id).collect();
@@ -360,7 +866,6 @@ pub async fn search(
                         // DocRegex: literal fragments FTS pre-filter at the file level,
                         // then apply the regex to the full joined document text so that
                         // patterns like `.*UART.*updates.*` can span multiple lines.
-                        let fts_terms = regex_to_fts_terms(&query);
                         let re = regex::RegexBuilder::new(&query)
                             .case_insensitive(!case_sensitive)
                             .dot_matches_new_line(true)
@@ -368,11 +873,18 @@ pub async fn search(
                         // Use document_candidates so the FTS pre-filter intersects per-token
                         // file sets — a file qualifies if each literal term appears *somewhere*
                         // in it (not necessarily on the same line).
-                        let (_, doc_groups) = db::document_candidates(&conn, &fts_terms, scoring_limit, date_filter)?;
+                        let (_, doc_groups) = cost.fts(|| db::document_candidates(&conn, &fts_text, scoring_limit, date_filter))?;
+                        cost.candidates(doc_groups.len(), scoring_limit);
+                        let examined = doc_groups.len();
                         let mut result_pairs: Vec<ScoredResult> = Vec::new();
                         for group in doc_groups {
+                            budget.check()?;
                             let file_id = group.representative.file_id;
                             let doc_text = db::read_file_document(&conn, cs.as_ref(), file_id);
+                            cost.record(|c| {
+                                c.lines_read += doc_text.lines().count();
+                                c.content_reads += 1;
+                            });
                             if re.is_match(&doc_text) {
                                 let mut rep = group.representative;
                                 // Find the line where the first match starts for the snippet.
@@ -388,6 +900,7 @@ pub async fn search(
                             }
                         }
                         let source_total = result_pairs.len();
+                        cost.record(|c| c.dropped += examined - source_total);
                         let file_ids: Vec<i64> = result_pairs.iter().map(|sr| sr.file_id).collect();
                         let dups_map = db::fetch_duplicates_for_file_ids(&conn, &file_ids)?;
                         let results: Vec<SearchResult> = result_pairs
@@ -411,11 +924,10 @@ pub async fn search(
                 // for FTS5 pre-filtering, then apply the full regex as a post-filter.
                 // For exact mode, treat the whole query as a phrase (literal substring).
                 // For fuzzy mode, AND individual words.
-                let (fts_phrase, fts_query) = match mode {
-                    SearchMode::Fuzzy | SearchMode::FileFuzzy => (false, query.clone()),
-                    SearchMode::Regex | SearchMode::FileRegex => (false, regex_to_fts_terms(&query)),
-                    _ /* Exact | FileExact */ => (true, query.clone()),
-                };
+                let fts_phrase = !matches!(
+                    mode,
+                    SearchMode::Fuzzy | SearchMode::FileFuzzy | SearchMode::Regex | SearchMode::FileRegex
+                );
 
                 // For filename-only mode the SQL rowid filter (line_number == 0) is applied
                 // after SQLite has already consumed the LIMIT from the FTS5 posting list.
@@ -425,7 +937,8 @@ pub async fn search(
                 // so that enough raw rows are fetched to produce scoring_limit filename rows
                 // after the rowid filter.
                 let candidate_limit = if filename_only { fts_limit } else { scoring_limit };
-                let mut candidates = db::fts_candidates(&conn, &fts_query, candidate_limit, fts_phrase, date_filter)?;
+                let mut candidates = cost.fts(|| db::fts_candidates(&conn, &fts_text, candidate_limit, fts_phrase, date_filter))?;
+                cost.candidates(candidates.len(), candidate_limit);
 
                 // For file-* modes, restrict to line_number == 0 (filename rows).
                 // The FTS SQL already enforces this via SQL_FTS_FILENAME_ONLY; this is a
@@ -434,6 +947,7 @@ pub async fn search(
                     candidates.retain(|c| c.line_number == 0);
                     candidates.truncate(scoring_limit);
                 }
+                let examined = candidates.len();
 
                 // Build ScoredResult pairs for alias lookup.
                 let result_pairs: Vec<ScoredResult> = match mode {
@@ -449,8 +963,10 @@ pub async fn search(
                         let re = regex::RegexBuilder::new(&query).case_insensitive(!case_sensitive).build()?;
                         // Read content for regex post-filtering (ZIP reads needed for correctness).
                         let pairs: Vec<(i64, i64)> = candidates.iter().map(|c| (c.file_id, c.line_number as i64)).collect();
-                        let content_map = db::read_content_batch(&conn, cs.as_ref(), &pairs);
+                        let content_map = cost.read_content(&conn, cs.as_ref(), &pairs);
+                        budget.check()?;
                         candidates.into_iter()
+                            .take_while(|_| !budget.exhausted())
                             .filter_map(|mut c| {
                                 let content = content_map.get(&(c.file_id, c.line_number as i64)).cloned().unwrap_or_default();
                                 // For filename-only regex: match against the file path.
@@ -461,13 +977,27 @@ pub async fn search(
                             .collect()
                     }
                     _ /* Fuzzy | FileFuzzy */ => {
+                        let parsed = db::search::FuzzyQuery::parse(&fts_text);
                         let query_terms: Vec<&str> = if case_sensitive {
-                            query.split_whitespace().collect()
+                            parsed.fuzzy.split_whitespace().collect()
                         } else {
                             vec![]
                         };
-                        let mut scorer = FuzzyScorer::new(&query, case_sensitive);
+                        let mut scorer = FuzzyScorer::new(&parsed.scorer_text(), case_sensitive, &scoring);
+                        // Phrases and prefix terms are checked against the line itself
+                        // (FTS only matched them as substrings), so read its content.
+                        if !parsed.is_plain() && !filename_only {
+                            let pairs: Vec<(i64, i64)> = candidates.iter().map(|c| (c.file_id, c.line_number as i64)).collect();
+                            let content_map = cost.read_content(&conn, cs.as_ref(), &pairs);
+                            budget.check()?;
+                            for c in &mut candidates {
+                                if let Some(content) = content_map.get(&(c.file_id, c.line_number as i64)) {
+                                    c.content = content.clone();
+                                }
+                            }
+                        }
                         candidates.into_iter()
+                            .take_while(|_| !budget.exhausted())
                             .filter_map(|c| {
                                 // After plan 080, content is not populated for non-regex modes.
                                 // For FileFuzzy (filename search): score against the composite path.
@@ -496,6 +1026,9 @@ pub async fn search(
                                     // for relative ranking (files whose path matches score higher).
                                     &c.file_path
                                 };
+                                // The query terms are normalized, so compare like with like.
+                                let normalized = tokens.text(score_text);
+                                let score_text: &str = &normalized;
                                 // In case-sensitive mode, require every query term to appear
                                 // as a literal substring.
                                 if !query_terms.is_empty()
@@ -503,13 +1036,16 @@ pub async fn search(
                                 {
                                     return None;
                                 }
+                                if !parsed.is_plain() && !parsed.literal_match(score_text, case_sensitive) {
+                                    return None;
+                                }
                                 let score = if filename_only || !c.content.is_empty() {
                                     // Use real fuzzy score when content is available or for filename search.
-                                    scorer.score(score_text)?
+                                    scorer.score(score_text, c.line_number)?
                                 } else {
                                     // Content search without content: FTS validated it, use path score
                                     // or default score=1 so all FTS matches are included.
-                                    scorer.score(score_text).unwrap_or(1)
+                                    scorer.score(score_text, c.line_number).unwrap_or(1)
                                 };
                                 Some(ScoredResult { result: make_result(&source_name, &c, score, vec![]), file_id: c.file_id })
                             })
@@ -517,51 +1053,469 @@ pub async fn search(
                     }
                 };
 
+                // A loop cut short by `take_while` leaves a partial list; drop it.
+                budget.check()?;
+                cost.record(|c| c.dropped += examined - result_pairs.len());
+
                 // Look up duplicates for all file IDs in the result set.
                 let file_ids: Vec<i64> = result_pairs.iter().map(|sr| sr.file_id).collect();
                 let dups_map = db::fetch_duplicates_for_file_ids(&conn, &file_ids)?;
 
+                // Metadata hits are shown as readable tags, which needs the
+                // line itself; fuzzy and exact candidates carry no content.
+                let meta_pairs: Vec<(i64, i64)> = result_pairs.iter()
+                    .filter(|sr| sr.result.line_number == LINE_METADATA && sr.result.snippet.is_empty())
+                    .map(|sr| (sr.file_id, LINE_METADATA as i64))
+                    .collect();
+                let meta_content = cost.read_content(&conn, cs.as_ref(), &meta_pairs);
+
+                // Exact and regex results carry their file's number of matching lines.
+                let mut hit_files = file_ids.clone();
+                hit_files.sort_unstable();
+                hit_files.dedup();
+                let counts = match mode {
+                    SearchMode::Exact => {
+                        let verify = |line: &str| line.contains(query.as_str());
+                        let verify: Option<&dyn Fn(&str) -> bool> = case_sensitive.then_some(&verify);
+                        hit_counts(&conn, cs.as_ref(), &fts_text, true, verify, &hit_files, fts_limit, &cost)?
+                    }
+                    SearchMode::Regex => {
+                        let re = regex::RegexBuilder::new(&query).case_insensitive(!case_sensitive).build()?;
+                        let verify = |line: &str| re.is_match(line);
+                        hit_counts(&conn, cs.as_ref(), &fts_text, false, Some(&verify), &hit_files, fts_limit, &cost)?
+                    }
+                    _ => Default::default(),
+                };
+
                 let results: Vec<SearchResult> = result_pairs
                     .into_iter()
                     .map(|mut sr| {
                         if let Some(dups) = dups_map.get(&sr.file_id) {
                             sr.result.duplicate_paths = dups.clone();
                         }
+                        sr.result.hit_count = counts.get(&sr.file_id).copied();
+                        if let Some(content) = meta_content.get(&(sr.file_id, sr.result.line_number as i64)) {
+                            sr.result.snippet = content.clone();
+                        }
                         sr.result
                     })
                     .collect();
 
                 Ok((results.len(), results))
-            })
+            };
+            // Waiting for a slot counts against the time budget.
+            let queue_cost = cost.clone();
+            let handle = tokio::spawn(async move {
+                let queued = Instant::now();
+                let Some(_permit) = limiter.acquire(deadline).await else {
+                    anyhow::bail!("search time budget exhausted");
+                };
+                queue_cost.record(|c| c.queued_ms = queued.elapsed().as_millis() as u64);
+                spawn_blocking(task).await?
+            });
+            (cost, handle)
         })
         .collect();
 
+    // A source that fails after the budget ran out was stopped (by a budget
+    // check or an interrupted SQLite statement) rather than broken.
     let mut all_results: Vec<SearchResult> = Vec::new();
-    for handle in handles {
+    let mut timed_out = false;
+    let mut explained = Vec::new();
+    for (cost, handle) in handles {
         match handle.await.unwrap_or_else(|e| Err(anyhow::anyhow!(e))) {
             Ok((_source_total, mut r)) => {
                 all_results.append(&mut r);
             }
-            Err(e) => tracing::error!("search source error: {e:#}"),
+            Err(_) if budget.exhausted() => {
+                timed_out = true;
+                cost.record(|c| c.error = Some("search time budget exhausted".to_string()));
+            }
+            Err(e) => {
+                tracing::error!("search source error: {e:#}");
+                cost.record(|c| c.error = Some(format!("{e:#}")));
+            }
+        }
+        explained.extend(cost.finish());
+    }
+    if timed_out {
+        tracing::warn!(
+            "search for {query:?} exceeded search.timeout_ms ({} ms); returning partial results",
+            state.config.search.timeout_ms,
+        );
+    }
+
+    // Merge federation peer hits.  Peers score with the same fuzzy scorer, so
+    // re-ranking the combined list by score interleaves them fairly.  Peers
+    // only return their first `offset + limit` hits, so the remainder of their
+    // totals is added separately.
+    let mut peer_total = 0;
+    let mut peer_capped = false;
+    if let Some(peers) = peers {
+        for resp in peers.await.unwrap_or_default() {
+            peer_total += resp.total.saturating_sub(resp.results.len());
+            peer_capped |= resp.capped;
+            timed_out |= resp.timed_out;
+            all_results.extend(resp.results);
         }
     }
 
-    all_results.sort_by_key(|a| Reverse(a.score));
+    sort_results(&mut all_results, params.sort);
 
-    // Deduplicate by (source, path, archive_path, line_number), keeping the
-    // highest-scoring occurrence (first after sort). Duplicates arise when FTS5
+    // Deduplicate by (origin, source, path, archive_path, line_number), keeping
+    // the first occurrence after sorting (the highest-scoring, as ties in
+    // every order are broken by score). Duplicates arise when FTS5
     // returns multiple rows for the same logical match (e.g. two members of the
     // same archive that share a line number after composite-path splitting).
     let mut seen = std::collections::HashSet::new();
     let unique: Vec<_> = all_results
         .into_iter()
-        .filter(|r| seen.insert((r.source.clone(), r.path.clone(), r.archive_path.clone(), r.line_number)))
+        .filter(|r| seen.insert((r.origin.clone(), r.source.clone(), r.path.clone(), r.archive_path.clone(), r.line_number)))
         .collect();
+    let unique = if params.expand_duplicates { unique } else { collapse_duplicates(unique) };
 
-    let unique_total = unique.len();
-    let results: Vec<_> = unique.into_iter().skip(offset).take(limit).collect();
+    let unique_total = unique.len() + peer_total;
+    let mut results: Vec<_> = unique.into_iter().skip(offset).take(limit).collect();
+    for r in &mut results {
+        crate::meta_snippet::format_result(r, &query);
+    }
+
+    // Freshness: stamp local results with their source's last full scan and
+    // list the sources whose scan is older than `search.stale_scan_days`.
+    let mut local_sources: Vec<String> = results.iter().filter(|r| r.origin.is_none()).map(|r| r.source.clone()).collect();
+    local_sources.sort();
+    local_sources.dedup();
+    let mut stale_sources = Vec::new();
+    if !local_sources.is_empty() {
+        let scans = last_scans(state, &local_sources).await;
+        for r in results.iter_mut().filter(|r| r.origin.is_none()) {
+            r.source_last_scan = scans.get(&r.source).copied();
+        }
+        let stale_days = state.config.search.stale_scan_days;
+        if stale_days > 0 {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            let cutoff = now - (stale_days * 86_400) as i64;
+            stale_sources = local_sources
+                .into_iter()
+                .filter_map(|s| scans.get(&s).copied().filter(|&ts| ts < cutoff).map(|ts| (s, ts)))
+                .collect();
+        }
+    }
 
     // capped = the current page is full, meaning more results are likely available.
-    let capped = results.len() == limit;
-    Json(SearchResponse { results, total: unique_total, capped }).into_response()
+    let capped = results.len() == limit || peer_capped || timed_out;
+    let explain = params.explain.then(|| {
+        explained.sort_by(|a, b| a.source.cmp(&b.source));
+        SearchExplain { elapsed_ms: started.elapsed().as_millis() as u64, fts_candidate_limit: fts_limit, sources: explained }
+    });
+    let stale_header = (!stale_sources.is_empty())
+        .then(|| header::HeaderValue::from_str(&format_stale_sources(&stale_sources)).ok())
+        .flatten();
+    let mut response =
+        Json(SearchResponse { results, total: unique_total, capped, timed_out, explain, stale_sources }).into_response();
+    if let Some(value) = stale_header {
+        response.headers_mut().insert(STALE_SOURCES_HEADER, value);
+    }
+    response
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SummaryParams {
+    /// Also list the matching files.
+    #[serde(default)]
+    files: bool,
+}
+
+/// GET /api/v1/search/summary — per-source match counts and, with
+/// `files=true`, the matching files.  Takes the same parameters as
+/// `/api/v1/search` (`limit` and `offset` are ignored), but answers from the
+/// FTS index alone: no content is read or scored, and federation peers are
+/// not asked.
+pub async fn search_summary(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    params: SearchParams,
+    Query(opts): Query<SummaryParams>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) { return (s, Json(serde_json::Value::Null)).into_response(); }
+
+    let mut source_dbs = source_dbs(&state, &params.source);
+    source_dbs.sort();
+    let limit = state.config.search.fts_candidate_limit;
+    let date_filter = params.date_filter();
+    let approximate = params.case_sensitive
+        || matches!(params.mode, SearchMode::Regex | SearchMode::FileRegex | SearchMode::DocRegex);
+    let pool = Arc::clone(&state.db_pool);
+    let term_filters = Arc::clone(&state.term_filters);
+    let SearchParams { q: query, mode, .. } = params;
+    let _permit = state.search_limiter.request().acquire(None).await;
+
+    run_blocking("search_summary", move || {
+        let mut sources = Vec::new();
+        let mut files = Vec::new();
+        for (source, db_path) in source_dbs {
+            if !db_path.exists() {
+                continue;
+            }
+            let conn = pool.get(&db_path)?;
+            let fts_text = fts_text(&mode, &query, &db::tokens::Normalizer::load(&conn)?);
+            if !term_filters.get(&db_path).may_match(&required_literals(&mode, &fts_text)) {
+                continue;
+            }
+            let (count, paths) = match mode {
+                SearchMode::Document | SearchMode::DocRegex => {
+                    let ids = db::document_qualifying_ids(&conn, &fts_text, date_filter.clone())?;
+                    let paths = if opts.files { db::paths_for_ids(&conn, &ids, limit)? } else { vec![] };
+                    (ids.len(), paths)
+                }
+                _ => {
+                    let filename_only = matches!(mode, SearchMode::FileFuzzy | SearchMode::FileExact | SearchMode::FileRegex);
+                    let filter = DateFilter { filename_only, ..date_filter.clone() };
+                    // The same FTS expression each mode's search starts from.
+                    let phrase = !matches!(
+                        mode,
+                        SearchMode::Fuzzy | SearchMode::FileFuzzy | SearchMode::Regex | SearchMode::FileRegex
+                    );
+                    let count = db::fts_count(&conn, &fts_text, limit, phrase, filter.clone())?;
+                    let paths = if opts.files && count > 0 {
+                        db::fts_matching_paths(&conn, &fts_text, limit, phrase, filter)?
+                    } else {
+                        vec![]
+                    };
+                    (count, paths)
+                }
+            };
+            if count == 0 {
+                continue;
+            }
+            files.extend(paths.into_iter().map(|path| MatchingFile { source: source.clone(), path }));
+            sources.push(SourceMatchCount { source, count, capped: count >= limit });
+        }
+        Ok(Json(SearchSummaryResponse { sources, files, approximate }))
+    }).await
+}
+
+// ── GET /api/v1/search/export ─────────────────────────────────────────────────
+
+/// Matching lines read from the index per batch.
+const EXPORT_BATCH: usize = 1000;
+
+/// Encoded batches buffered between the reader and the response body.  The
+/// reader blocks once this many are waiting, so a slow client throttles it
+/// instead of the export piling up in memory.
+const EXPORT_CHANNEL_CAPACITY: usize = 4;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Ndjson,
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// GET /api/v1/search/export — every matching line, streamed as CSV or
+/// NDJSON (`format=csv|ndjson`, one [`SearchExportRow`] per line).  Takes the
+/// same parameters as `/api/v1/search` in the `fuzzy`, `exact` and `regex`
+/// modes, but `limit`, `offset` and `sort` are ignored: lines are read from
+/// the index in batches, in index order, with no candidate limit or time
+/// budget.  Federation peers are not asked.
+pub async fn search_export(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    params: SearchParams,
+    Query(opts): Query<ExportParams>,
+) -> Response {
+    if let Err(s) = check_auth(&state, &headers) { return s.into_response(); }
+    if !matches!(params.mode, SearchMode::Fuzzy | SearchMode::Exact | SearchMode::Regex) {
+        return (StatusCode::BAD_REQUEST, "export supports the fuzzy, exact and regex modes").into_response();
+    }
+    let regex = match params.mode {
+        SearchMode::Regex => match regex::RegexBuilder::new(&params.q).case_insensitive(!params.case_sensitive).build() {
+            Ok(re) => Some(re),
+            Err(e) => return (StatusCode::BAD_REQUEST, format!("invalid regex: {e}")).into_response(),
+        },
+        _ => None,
+    };
+
+    let mut source_dbs = source_dbs(&state, &params.source);
+    source_dbs.sort();
+    let export = SearchExport {
+        date_filter: params.date_filter(),
+        query: params.q,
+        mode: params.mode,
+        case_sensitive: params.case_sensitive,
+        regex,
+        format: opts.format,
+        pool: Arc::clone(&state.db_pool),
+        term_filters: Arc::clone(&state.term_filters),
+        content_store: Arc::clone(&state.content_store),
+    };
+
+    // An export holds one search slot for as long as it streams.
+    let permit = state.search_limiter.request().acquire(None).await;
+    let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+    spawn_blocking(move || {
+        let _permit = permit;
+        if let Err(e) = export.run(source_dbs, &tx) {
+            tracing::error!("search_export: {e:#}");
+            // Fail the body rather than end it cleanly, so the client does
+            // not mistake a partial export for a complete one.
+            let _ = tx.blocking_send(Err(std::io::Error::other(e.to_string())));
+        }
+    });
+
+    let (content_type, ext) = match opts.format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Ndjson => ("application/x-ndjson", "ndjson"),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"search.{ext}\""))
+        .body(Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// One export's query and filters, run on a blocking thread.
+struct SearchExport {
+    date_filter: DateFilter,
+    query: String,
+    mode: SearchMode,
+    case_sensitive: bool,
+    regex: Option<regex::Regex>,
+    format: ExportFormat,
+    pool: Arc<db::pool::DbPool>,
+    term_filters: Arc<db::term_filter::TermFilters>,
+    content_store: Arc<dyn find_content_store::ContentStore>,
+}
+
+type ExportSender = tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>;
+
+impl SearchExport {
+    /// Send every matching line of each source to `tx`, one batch at a time.
+    /// Returns early, without error, once the client has gone away.
+    fn run(&self, source_dbs: Vec<(String, std::path::PathBuf)>, tx: &ExportSender) -> anyhow::Result<()> {
+        if self.format == ExportFormat::Csv {
+            let header = "source,path,archive_path,line_number,kind,mtime,size,content\n";
+            if tx.blocking_send(Ok(header.as_bytes().to_vec())).is_err() {
+                return Ok(());
+            }
+        }
+        for (source, db_path) in source_dbs {
+            if !db_path.exists() {
+                continue;
+            }
+            if !self.export_source(&source, &db_path, tx)? {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// Export one source.  Returns false once the client has gone away.
+    fn export_source(&self, source: &str, db_path: &std::path::Path, tx: &ExportSender) -> anyhow::Result<bool> {
+        let conn = self.pool.get(db_path)?;
+        let tokens = db::tokens::Normalizer::load(&conn)?;
+        let fts_text = fts_text(&self.mode, &self.query, &tokens);
+        if !self.term_filters.get(db_path).may_match(&required_literals(&self.mode, &fts_text)) {
+            return Ok(true);
+        }
+        // The same FTS expression and post-filters as `/api/v1/search`.
+        let phrase = self.mode == SearchMode::Exact;
+        let parsed = db::search::FuzzyQuery::parse(&fts_text);
+        let query_terms: Vec<&str> = if self.case_sensitive && self.mode == SearchMode::Fuzzy {
+            parsed.fuzzy.split_whitespace().collect()
+        } else {
+            vec![]
+        };
+        let keep = |line: &str| -> bool {
+            match self.mode {
+                SearchMode::Exact => !self.case_sensitive || line.contains(self.query.as_str()),
+                SearchMode::Regex => self.regex.as_ref().is_some_and(|re| re.is_match(line)),
+                _ => {
+                    let normalized = tokens.text(line);
+                    query_terms.iter().all(|t| line.contains(*t) || normalized.contains(*t))
+                        && (parsed.is_plain() || parsed.literal_match(&normalized, self.case_sensitive))
+                }
+            }
+        };
+
+        let mut after = 0;
+        loop {
+            let filter = DateFilter { after_rowid: Some(after), ..self.date_filter.clone() };
+            let batch = db::fts_candidates(&conn, &fts_text, EXPORT_BATCH, phrase, filter)?;
+            let Some(last) = batch.last() else { break };
+            after = db::encode_fts_rowid(last.file_id, last.line_number as i64);
+
+            let pairs: Vec<(i64, i64)> = batch.iter().map(|c| (c.file_id, c.line_number as i64)).collect();
+            let content = db::read_content_batch(&conn, self.content_store.as_ref(), &pairs);
+            let mut buf = Vec::new();
+            for c in &batch {
+                let line = content.get(&(c.file_id, c.line_number as i64)).map(String::as_str).unwrap_or_default();
+                if !keep(line) {
+                    continue;
+                }
+                let row = SearchExportRow {
+                    source: source.to_string(),
+                    path: c.file_path.clone(),
+                    archive_path: c.archive_path.clone(),
+                    line_number: c.line_number,
+                    kind: c.file_kind.clone(),
+                    mtime: c.mtime,
+                    size: c.size,
+                    content: line.strip_prefix("[PATH] ").unwrap_or(line).to_string(),
+                };
+                match self.format {
+                    ExportFormat::Csv => write_csv_row(&mut buf, &row),
+                    ExportFormat::Ndjson => {
+                        serde_json::to_writer(&mut buf, &row)?;
+                        buf.push(b'\n');
+                    }
+                }
+            }
+            if !buf.is_empty() && tx.blocking_send(Ok(buf)).is_err() {
+                return Ok(false);
+            }
+            if batch.len() < EXPORT_BATCH {
+                break;
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Append `row` to `buf` as one CSV record (RFC 4180 quoting).
+fn write_csv_row(buf: &mut Vec<u8>, row: &SearchExportRow) {
+    let fields = [
+        row.source.clone(),
+        row.path.clone(),
+        row.archive_path.clone().unwrap_or_default(),
+        row.line_number.to_string(),
+        row.kind.to_string(),
+        row.mtime.to_string(),
+        row.size.map(|s| s.to_string()).unwrap_or_default(),
+        row.content.clone(),
+    ];
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            buf.push(b',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            buf.push(b'"');
+            buf.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+            buf.push(b'"');
+        } else {
+            buf.extend_from_slice(field.as_bytes());
+        }
+    }
+    buf.push(b'\n');
 }
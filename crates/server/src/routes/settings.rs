@@ -50,3 +50,17 @@ pub async fn get_settings(
     })
     .into_response()
 }
+
+// ── GET /api/v1/client-config ─────────────────────────────────────────────────
+
+/// `[client_config]`: scan settings for clients with
+/// `[server] remote_config = true`.
+pub async fn get_client_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+    Json(state.client_config.read().unwrap_or_else(|e| e.into_inner()).clone()).into_response()
+}
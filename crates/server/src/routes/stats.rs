@@ -18,6 +18,7 @@ use find_common::api::{SourceStats, SourceStreamSnapshot, StatsResponse, StatsSt
 use crate::{db, AppState};
 
 use super::check_auth;
+use super::etag::Validator;
 
 // ── GET /api/v1/stats ─────────────────────────────────────────────────────────
 
@@ -54,19 +55,13 @@ pub async fn get_stats(
     let failed_requests = count_gz(&failed_dir);
     let archive_queue = count_gz(&to_archive_dir);
 
-    // Archive totals from content store.
-    let (content_file_count, content_size_bytes) = state
-        .content_store
-        .storage_stats()
-        .map(|(c, b)| (c as usize, b))
-        .unwrap_or((0, 0));
-
-    let worker_status = state.worker_status
+    let (worker_status, worker_lanes) = state.worker_status
         .lock()
-        .map(|g| g.clone())
-        .unwrap_or(WorkerStatus::Idle);
+        .map(|g| (g.status(), g.lanes()))
+        .unwrap_or((WorkerStatus::Idle, Vec::new()));
 
     let inbox_paused = state.inbox_paused.load(std::sync::atomic::Ordering::Relaxed);
+    let maintenance = state.maintenance.read().map(|m| m.enabled).unwrap_or(false);
 
     // If ?refresh=true, rebuild the cache and refresh compaction stats before reading.
     if query.refresh {
@@ -115,6 +110,35 @@ pub async fn get_stats(
         guard.sources.clone()
     };
 
+    // Everything below (content store totals, per-source DB reads) is derived
+    // from inputs already gathered, the worker's change counter and the source
+    // DB files.  `orphaned_stats_age_secs` ticks every second and is left out
+    // of the tag: a 304 may carry a stale age, which clients only display.
+    let validator = (!query.refresh).then(|| {
+        let compaction = state.compaction_stats
+            .read()
+            .ok()
+            .and_then(|g| g.as_ref().map(|s| (s.orphaned_bytes, s.scanned_at)));
+        let status = serde_json::to_string(&worker_lanes).unwrap_or_default();
+        cached.iter().fold(
+            Validator::new("stats")
+                .value((inbox_pending, failed_requests, archive_queue, status, inbox_paused, maintenance))
+                .value((db_size_bytes, compaction))
+                .worker_version(&state),
+            |v, s| v.value(&s.name).sqlite_file(&state.data_dir.join("sources").join(format!("{}.db", s.name))),
+        )
+    });
+    if let Some(v) = validator.as_ref().filter(|v| v.matches(&headers)) {
+        return v.not_modified();
+    }
+
+    // Archive totals from content store.
+    let (content_file_count, content_size_bytes) = state
+        .content_store
+        .storage_stats()
+        .map(|(c, b)| (c as usize, b))
+        .unwrap_or((0, 0));
+
     let sources: Vec<SourceStats> = cached.into_iter().map(|s| {
         let db_path = state.data_dir.join("sources").join(format!("{}.db", s.name));
         let (last_scan, history, indexing_error_count) = if let Ok(conn) = db::open_for_stats(&db_path) {
@@ -137,10 +161,15 @@ pub async fn get_stats(
             indexing_error_count,
             fts_row_count:          s.fts_row_count,
             files_pending_content:  s.files_pending_content,
+            db_size_bytes:          crate::index_budget::db_bytes(&state.data_dir, &s.name),
+            content_bytes:          s.content_bytes,
+            index_budget_bytes:     crate::index_budget::budget(&state.config, &s.name),
+            space_by_kind:          s.space_by_kind,
+            space_by_dir:           s.space_by_dir,
         }
     }).collect();
 
-    Json(StatsResponse {
+    let resp = Json(StatsResponse {
         sources,
         inbox_pending,
         failed_requests,
@@ -149,25 +178,32 @@ pub async fn get_stats(
         db_size_bytes,
         content_size_bytes,
         worker_status,
+        worker_lanes,
         inbox_paused,
+        maintenance,
         orphaned_bytes,
         orphaned_stats_age_secs,
-    }).into_response()
+    }).into_response();
+    match validator {
+        Some(v) => v.tag(resp),
+        None => resp,
+    }
 }
 
 // ── GET /api/v1/stats/stream (SSE) ───────────────────────────────────────────
 
 fn build_stream_event(state: &AppState) -> StatsStreamEvent {
-    let sources = {
+    let (sources, files_indexed, lines_indexed) = {
         let guard = state.source_stats_cache.read().unwrap_or_else(|e| e.into_inner());
-        guard.sources.iter().map(|s| SourceStreamSnapshot {
+        let sources = guard.sources.iter().map(|s| SourceStreamSnapshot {
             name:                  s.name.clone(),
             total_files:           s.total_files,
             total_size:            s.total_size,
             by_kind:               s.by_kind.clone(),
             fts_row_count:         s.fts_row_count,
             files_pending_content: s.files_pending_content,
-        }).collect()
+        }).collect();
+        (sources, guard.files_indexed, guard.lines_indexed)
     };
 
     let inbox_dir = state.data_dir.join("inbox");
@@ -181,10 +217,10 @@ fn build_stream_event(state: &AppState) -> StatsStreamEvent {
             .unwrap_or(0)
     };
 
-    let worker_status = state.worker_status
+    let (worker_status, worker_lanes) = state.worker_status
         .lock()
-        .map(|g| g.clone())
-        .unwrap_or(WorkerStatus::Idle);
+        .map(|g| (g.status(), g.lanes()))
+        .unwrap_or((WorkerStatus::Idle, Vec::new()));
 
     let inbox_paused = state.inbox_paused.load(std::sync::atomic::Ordering::Relaxed);
 
@@ -221,9 +257,12 @@ fn build_stream_event(state: &AppState) -> StatsStreamEvent {
         content_size_bytes:      state.content_store.storage_stats().map(|(_, b)| b).unwrap_or(0),
         db_size_bytes,
         worker_status,
+        worker_lanes,
         inbox_paused,
         orphaned_bytes,
         orphaned_stats_age_secs,
+        files_indexed,
+        lines_indexed,
     }
 }
 
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+
+use find_common::api::{TrashResponse, TrashRestoreRequest, TrashRestoreResponse};
+
+use crate::{db, AppState};
+
+use super::{check_auth, run_blocking, source_db_path};
+
+// ── GET /api/v1/admin/trash?source=X[&prefix=dir&limit=200&offset=0] ─────────
+
+#[derive(Deserialize)]
+pub struct TrashParams {
+    pub source: String,
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+fn default_limit() -> usize { 200 }
+
+/// List the deleted files held in a source's trash.
+pub async fn get_trash(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TrashParams>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let db_path = match source_db_path(&state, &params.source) {
+        Ok(p) => p,
+        Err(s) => return (s, Json(serde_json::Value::Null)).into_response(),
+    };
+    if !db_path.exists() {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "source not found" }))).into_response();
+    }
+
+    let limit = params.limit.min(1000);
+    let trash_days = state.config.server.trash_days;
+    let pool = Arc::clone(&state.db_pool);
+
+    run_blocking("get_trash", move || {
+        let conn = pool.get(&db_path)?;
+        let (files, total) = db::list_trash(&conn, params.prefix.as_deref(), trash_days, limit, params.offset)?;
+        Ok(Json(TrashResponse { files, total }))
+    }).await
+}
+
+// ── POST /api/v1/admin/trash/restore ─────────────────────────────────────────
+
+/// Put files back from a source's trash into its index.
+pub async fn restore_trash(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<TrashRestoreRequest>,
+) -> impl IntoResponse {
+    if let Err(s) = check_auth(&state, &headers) {
+        return (s, Json(serde_json::Value::Null)).into_response();
+    }
+
+    let db_path = match source_db_path(&state, &req.source) {
+        Ok(p) => p,
+        Err(s) => return (s, Json(serde_json::Value::Null)).into_response(),
+    };
+    if !db_path.exists() {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "source not found" }))).into_response();
+    }
+
+    let state = Arc::clone(&state);
+    run_blocking("restore_trash", move || {
+        let conn = db::open(&db_path)?;
+        let outcome = db::restore_from_trash(&conn, &req.paths, req.prefix.as_deref())?;
+        tracing::info!(source = %req.source, restored = outcome.restored, skipped = outcome.skipped, "restored files from the trash");
+
+        if outcome.restored > 0 {
+            let delta = crate::stats_cache::SourceStatsDelta {
+                source: req.source.clone(),
+                files_delta: outcome.delta.files_removed,
+                size_delta: outcome.delta.size_removed,
+                kind_deltas: outcome.delta.by_kind,
+                ..Default::default()
+            };
+            if let Ok(mut guard) = state.source_stats_cache.write() {
+                guard.apply_delta(&delta);
+            }
+            state.stats_watch.send_modify(|v| *v = v.wrapping_add(1));
+        }
+        Ok(Json(TrashRestoreResponse { restored: outcome.restored, skipped: outcome.skipped }))
+    }).await
+}
@@ -13,6 +13,7 @@ use find_common::api::{SourceInfo, TreeExpandResponse, TreeResponse};
 use crate::AppState;
 
 use crate::db;
+use super::etag::Validator;
 use super::{check_auth, run_blocking, source_db_path};
 
 // ── GET /api/v1/sources ───────────────────────────────────────────────────────
@@ -25,6 +26,10 @@ pub async fn list_sources(
         return (s, Json(serde_json::Value::Null)).into_response();
     }
     let sources_dir = state.data_dir.join("sources");
+    let validator = Validator::new("sources").dir(&sources_dir);
+    if validator.matches(&headers) {
+        return validator.not_modified();
+    }
     let names: Vec<String> = match std::fs::read_dir(&sources_dir) {
         Err(_) => vec![],
         Ok(rd) => rd
@@ -40,7 +45,7 @@ pub async fn list_sources(
         .map(|name| SourceInfo { name })
         .collect();
     infos.sort_by(|a, b| a.name.cmp(&b.name));
-    Json(infos).into_response()
+    validator.tag(Json(infos).into_response())
 }
 
 // ── GET /api/v1/tree ──────────────────────────────────────────────────────────
@@ -72,11 +77,18 @@ pub async fn list_dir(
         return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
     }
 
+    let validator = Validator::new("tree").value(&params.prefix).sqlite_file(&db_path);
+    if validator.matches(&headers) {
+        return validator.not_modified();
+    }
+
     let prefix = params.prefix.clone();
-    run_blocking("list_dir", move || {
-        let conn = db::open(&db_path)?;
+    let pool = Arc::clone(&state.db_pool);
+    let resp = run_blocking("list_dir", move || {
+        let conn = pool.get(&db_path)?;
         db::list_dir(&conn, &prefix).map(|entries| Json(TreeResponse { entries }))
-    }).await
+    }).await;
+    validator.tag(resp)
 }
 
 // ── GET /api/v1/tree/expand ───────────────────────────────────────────────────
@@ -107,9 +119,16 @@ pub async fn expand_tree(
         return (StatusCode::NOT_FOUND, Json(serde_json::Value::Null)).into_response();
     }
 
+    let validator = Validator::new("tree/expand").value(&params.path).sqlite_file(&db_path);
+    if validator.matches(&headers) {
+        return validator.not_modified();
+    }
+
     let path = params.path.clone();
-    run_blocking("expand_tree", move || {
-        let conn = db::open(&db_path)?;
+    let pool = Arc::clone(&state.db_pool);
+    let resp = run_blocking("expand_tree", move || {
+        let conn = pool.get(&db_path)?;
         db::expand_tree(&conn, &path).map(|levels| Json(TreeExpandResponse { levels }))
-    }).await
+    }).await;
+    validator.tag(resp)
 }
@@ -165,7 +165,7 @@ fn port_from_bind(bind: &str) -> &str {
     bind.rsplit_once(':').map(|(_, port)| port).unwrap_or("8765")
 }
 
-fn append_bytes(path: &std::path::Path, data: &[u8]) -> anyhow::Result<()> {
+pub(super) fn append_bytes(path: &std::path::Path, data: &[u8]) -> anyhow::Result<()> {
     use std::io::Write;
     let mut file = std::fs::OpenOptions::new()
         .create(true)
@@ -177,7 +177,7 @@ fn append_bytes(path: &std::path::Path, data: &[u8]) -> anyhow::Result<()> {
 
 /// Parse the start byte offset from a `Content-Range: bytes start-end/total` header.
 /// Returns None if the header is missing or malformed.
-fn parse_content_range_start(header: &str) -> Option<u64> {
+pub(super) fn parse_content_range_start(header: &str) -> Option<u64> {
     // Format: "bytes <start>-<end>/<total>"
     let without_prefix = header.strip_prefix("bytes ")?;
     let dash_pos = without_prefix.find('-')?;
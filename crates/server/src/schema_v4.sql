@@ -3,6 +3,7 @@
 -- data_dir/content.db, owned by find-content-store.
 --
 -- v14: file_content table dropped; files.content_hash renamed to files.file_hash.
+-- v16: extractor, extractor_version and extract_truncated added to files and trash.
 
 PRAGMA journal_mode=WAL;
 PRAGMA foreign_keys=ON;
@@ -22,7 +23,10 @@ CREATE TABLE IF NOT EXISTS files (
     extract_ms       INTEGER,
     file_hash        TEXT,
     scanner_version  INTEGER NOT NULL DEFAULT 0,
-    line_count       INTEGER
+    line_count       INTEGER,
+    extractor        TEXT,
+    extractor_version TEXT,
+    extract_truncated INTEGER NOT NULL DEFAULT 0
 );
 
 -- Inner archive members use composite paths: "archive.zip::member.txt"
@@ -83,3 +87,25 @@ CREATE TABLE IF NOT EXISTS activity_log (
 );
 CREATE INDEX IF NOT EXISTS idx_activity_log_occurred_at
     ON activity_log(occurred_at DESC);
+
+-- Trash (v15): `files` rows moved here on deletion when server.trash_days > 0.
+-- `id` keeps the file's id so its `lines_fts` rows (which deletion leaves in
+-- place) match again on restore.  Rows older than trash_days are purged
+-- hourly; their content is then reclaimed by compaction.
+CREATE TABLE IF NOT EXISTS trash (
+    id               INTEGER PRIMARY KEY,
+    path             TEXT    NOT NULL UNIQUE,
+    mtime            INTEGER NOT NULL,
+    size             INTEGER,
+    kind             TEXT    NOT NULL,
+    indexed_at       INTEGER,
+    extract_ms       INTEGER,
+    file_hash        TEXT,
+    scanner_version  INTEGER NOT NULL,
+    line_count       INTEGER,
+    extractor        TEXT,
+    extractor_version TEXT,
+    extract_truncated INTEGER NOT NULL DEFAULT 0,
+    deleted_at       INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_trash_deleted_at ON trash(deleted_at);
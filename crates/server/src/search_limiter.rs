@@ -0,0 +1,111 @@
+//! Admission control for search work on the blocking thread pool
+//! (`[search] max_concurrent_sources` and `max_sources_per_request`).
+//!
+//! A search runs one blocking task per source, so without a limit a few
+//! concurrent searches over many sources can occupy the whole blocking pool
+//! and stall the indexing worker, which shares it.  Each task first takes a
+//! slot from its own request's allowance, then one of the server-wide slots.
+//! Because a request never queues more than its allowance for the shared
+//! slots, and [`Semaphore`] hands slots out first come, first served, a
+//! search over every source cannot hold back a later one behind all of its
+//! tasks: requests take turns.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use find_common::config::SearchSettings;
+
+/// The server-wide slots, shared by every request.
+pub struct SearchLimiter {
+    shared: Arc<Semaphore>,
+    per_request: usize,
+}
+
+impl SearchLimiter {
+    pub fn new(settings: &SearchSettings) -> Self {
+        Self {
+            shared: Arc::new(Semaphore::new(settings.max_concurrent_sources.max(1))),
+            per_request: settings.max_sources_per_request.max(1),
+        }
+    }
+
+    /// The slots available to one request.
+    pub fn request(&self) -> RequestLimiter {
+        RequestLimiter {
+            shared: Arc::clone(&self.shared),
+            own: Arc::new(Semaphore::new(self.per_request)),
+        }
+    }
+}
+
+/// One request's allowance, cloned into each of its source tasks.
+#[derive(Clone)]
+pub struct RequestLimiter {
+    shared: Arc<Semaphore>,
+    own: Arc<Semaphore>,
+}
+
+/// Held while a source task runs; the slots are returned when it is dropped.
+pub struct SearchPermit {
+    _own: OwnedSemaphorePermit,
+    _shared: OwnedSemaphorePermit,
+}
+
+impl RequestLimiter {
+    /// Wait for a slot, giving up at `deadline`.
+    pub async fn acquire(&self, deadline: Option<Instant>) -> Option<SearchPermit> {
+        let slots = async {
+            let own = Arc::clone(&self.own).acquire_owned().await.ok()?;
+            let shared = Arc::clone(&self.shared).acquire_owned().await.ok()?;
+            Some(SearchPermit { _own: own, _shared: shared })
+        };
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), slots).await.ok().flatten(),
+            None => slots.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn limiter(shared: usize, per_request: usize) -> SearchLimiter {
+        SearchLimiter::new(&SearchSettings {
+            max_concurrent_sources: shared,
+            max_sources_per_request: per_request,
+            ..SearchSettings::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn request_is_held_to_its_allowance() {
+        let limiter = limiter(4, 2);
+        let req = limiter.request();
+        let _a = req.acquire(None).await.unwrap();
+        let _b = req.acquire(None).await.unwrap();
+        let soon = Instant::now() + Duration::from_millis(20);
+        assert!(req.acquire(Some(soon)).await.is_none(), "third slot exceeds the allowance");
+
+        // Another request still gets the shared slots left over.
+        let other = limiter.request();
+        assert!(other.acquire(Some(soon + Duration::from_millis(20))).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn shared_slots_bound_all_requests() {
+        let limiter = limiter(1, 4);
+        let first = limiter.request();
+        let held = first.acquire(None).await.unwrap();
+        let second = limiter.request();
+        let soon = Instant::now() + Duration::from_millis(20);
+        assert!(second.acquire(Some(soon)).await.is_none(), "no shared slot free");
+
+        drop(held);
+        assert!(second.acquire(None).await.is_some());
+    }
+}
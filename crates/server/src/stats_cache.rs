@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
-use find_common::api::{ExtStat, FileKind, KindStats};
+use find_common::api::{ExtStat, FileKind, KindStats, SpaceStat};
 use find_content_store::ContentStore;
 
 /// In-memory cache of per-source stats.  Wrapped in Arc<RwLock<...>> in AppState.
@@ -13,6 +13,10 @@ pub struct SourceStatsCache {
     pub sources: Vec<CachedSourceStats>,
     /// Unix timestamp of the last full rebuild.
     pub rebuilt_at: Option<i64>,
+    /// Files and lines written by the worker since the server started, across
+    /// all sources.  Live views derive throughput from these.
+    pub files_indexed: u64,
+    pub lines_indexed: u64,
 }
 
 #[derive(Clone, Default)]
@@ -27,8 +31,15 @@ pub struct CachedSourceStats {
     pub fts_row_count: i64,
     /// Files whose content hasn't been written to ZIP yet.
     pub files_pending_content: usize,
+    /// Only populated on full rebuild (see `db::get_space_usage`).
+    pub content_bytes: u64,
+    pub space_by_kind: Vec<SpaceStat>,
+    pub space_by_dir:  Vec<SpaceStat>,
 }
 
+/// Directories listed in `SourceStats::space_by_dir`.
+const SPACE_TOP_DIRS: usize = 10;
+
 /// Run all expensive queries for every source DB and store results in `cache`.
 /// Called at startup, daily, and on `?refresh=true`.
 pub fn full_rebuild(
@@ -59,7 +70,19 @@ pub fn full_rebuild(
         let by_ext     = crate::db::get_stats_by_ext(&conn).unwrap_or_default();
         let fts_row_count = crate::db::get_fts_row_count(&conn).unwrap_or(0);
         let files_pending_content = crate::db::get_files_pending_content(&conn, content_store.as_ref()).unwrap_or(0);
-        sources.push(CachedSourceStats { name: source_name, total_files, total_size, by_kind, by_ext, fts_row_count, files_pending_content });
+        let space = crate::db::get_space_usage(&conn, content_store.as_ref(), SPACE_TOP_DIRS).unwrap_or_default();
+        sources.push(CachedSourceStats {
+            name: source_name,
+            total_files,
+            total_size,
+            by_kind,
+            by_ext,
+            fts_row_count,
+            files_pending_content,
+            content_bytes: space.content_bytes,
+            space_by_kind: space.by_kind,
+            space_by_dir:  space.by_dir,
+        });
     }
 
     sources.sort_by(|a, b| a.name.cmp(&b.name));
@@ -85,6 +108,9 @@ pub struct SourceStatsDelta {
     pub size_delta:  i64,
     /// Positive = added, negative = removed.
     pub kind_deltas: HashMap<FileKind, (i64, i64)>, // kind → (count_delta, size_delta)
+    /// Files and lines the batch wrote, whether new or re-indexed.
+    pub files_indexed: u64,
+    pub lines_indexed: u64,
 }
 
 impl SourceStatsCache {
@@ -98,6 +124,8 @@ impl SourceStatsCache {
             });
             self.sources.sort_by(|a, b| a.name.cmp(&b.name));
         }
+        self.files_indexed += delta.files_indexed;
+        self.lines_indexed += delta.lines_indexed;
         let s = self.sources.iter_mut().find(|s| s.name == delta.source).unwrap();
         s.total_files = (s.total_files as i64 + delta.files_delta).max(0) as usize;
         s.total_size  = (s.total_size  + delta.size_delta).max(0);
@@ -262,6 +262,8 @@ fn toml_extractors(extractors: &std::collections::HashMap<String, ExternalExtrac
 ///
 /// Runs every 10 minutes. Deletes `.part` + `.meta` pairs where the `.meta`
 /// file is older than 2 hours, and any orphan `.part` files without a `.meta`.
+/// Resumable bulk sessions (`bulk-<id>.part` + `.meta`) are cleaned up the
+/// same way.
 pub async fn start_cleanup_task(data_dir: PathBuf) {
     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10 * 60));
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -0,0 +1,123 @@
+/// Outgoing webhook notifications.
+///
+/// Each event is delivered as a JSON `POST` to every endpoint in the
+/// `[[webhooks.endpoints]]` configuration that subscribes to it.  When an
+/// endpoint has a `secret`, the raw request body is signed with HMAC-SHA256
+/// and the signature sent as `X-Find-Signature: sha256=<hex>`.
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+use find_common::config::{WebhookEvent, WebhooksConfig};
+
+/// Header carrying the event name, so receivers can route without parsing the body.
+pub const EVENT_HEADER: &str = "X-Find-Event";
+/// Header carrying the HMAC-SHA256 body signature.
+pub const SIGNATURE_HEADER: &str = "X-Find-Signature";
+
+#[derive(Serialize)]
+struct WebhookPayload {
+    event: WebhookEvent,
+    /// Unix timestamp (seconds) at which the event fired.
+    timestamp: i64,
+    host: String,
+    data: serde_json::Value,
+}
+
+/// Deliver `event` to all subscribed endpoints.
+///
+/// Spawns a task and returns immediately — delivery does not block the
+/// worker.  Failures are logged but not retried or propagated.
+pub fn send(cfg: &WebhooksConfig, event: WebhookEvent, data: serde_json::Value) {
+    let endpoints: Vec<_> = cfg.endpoints.iter()
+        .filter(|e| e.wants(event))
+        .cloned()
+        .collect();
+    if endpoints.is_empty() {
+        return;
+    }
+
+    let payload = WebhookPayload {
+        event,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64,
+        host: crate::alerts::read_hostname(),
+        data,
+    };
+    let body = match serde_json::to_vec(&payload) {
+        Ok(b) => b,
+        Err(e) => { tracing::error!("Failed to serialise webhook payload: {e:#}"); return; }
+    };
+    let event_name = event_name(event);
+    let timeout = std::time::Duration::from_secs(cfg.timeout_secs);
+
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(timeout).build() {
+            Ok(c) => c,
+            Err(e) => { tracing::error!("Failed to build webhook client: {e:#}"); return; }
+        };
+        for endpoint in endpoints {
+            let mut req = client
+                .post(&endpoint.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event_name);
+            if let Some(secret) = &endpoint.secret {
+                req = req.header(SIGNATURE_HEADER, sign(secret, &body));
+            }
+            match req.body(body.clone()).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    tracing::debug!("Webhook {event_name} delivered to {}", endpoint.url);
+                }
+                Ok(resp) => {
+                    tracing::warn!("Webhook {event_name} to {} returned {}", endpoint.url, resp.status());
+                }
+                Err(e) => {
+                    tracing::warn!("Webhook {event_name} to {} failed: {e:#}", endpoint.url);
+                }
+            }
+        }
+    });
+}
+
+/// Compute the `X-Find-Signature` header value for `body`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+fn event_name(event: WebhookEvent) -> &'static str {
+    match event {
+        WebhookEvent::ScanCompleted => "scan_completed",
+        WebhookEvent::ErrorSpike    => "error_spike",
+        WebhookEvent::InboxBacklog  => "inbox_backlog",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_rfc4231_vector() {
+        // RFC 4231 test case 2.
+        let sig = sign("Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            sig,
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn event_name_matches_serde_name() {
+        for event in [WebhookEvent::ScanCompleted, WebhookEvent::ErrorSpike, WebhookEvent::InboxBacklog] {
+            let json = serde_json::to_string(&event).unwrap();
+            assert_eq!(json, format!("\"{}\"", event_name(event)));
+        }
+    }
+}
@@ -213,6 +213,15 @@ mod tests {
             normalization: NormalizationSettings::default(),
             consecutive_timeout_limit: 0, // disabled in tests
             alerts: find_common::config::AlertsConfig::default(),
+            history: find_common::config::HistoryConfig::default(),
+            webhooks: find_common::config::WebhooksConfig::default(),
+            concurrency: 1,
+            max_attempts: 3,
+            index_budgets: Default::default(),
+            trash_days: 0,
+            tokens: Default::default(),
+            part_max_bytes: 16 * 1024 * 1024,
+            split_over_bytes: 0,
         }
     }
 
@@ -241,6 +250,9 @@ mod tests {
                 file_hash: Some("testhash".to_string()),
                 is_new: true,
                 force: false,
+                append_to: None,
+                reuse_content: false,
+                provenance: None,
             }],
             delete_paths: vec![],
             rename_paths: vec![],
@@ -398,6 +410,9 @@ mod tests {
                 file_hash: Some("oldhash".to_string()),
                 is_new: false,
                 force: false,
+                append_to: None,
+                reuse_content: false,
+                provenance: None,
             }],
             delete_paths: vec![],
             rename_paths: vec![],
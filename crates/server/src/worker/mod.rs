@@ -1,14 +1,16 @@
 mod archive_batch;
 mod pipeline;
+pub(crate) mod quarantine;
 mod request;
+pub(crate) mod stream;
 
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
-use find_common::api::{RecentFile, WorkerStatus};
-use find_common::config::{AlertsConfig, NormalizationSettings};
+use find_common::api::{RecentFile, WorkerLane, WorkerStatus};
+use find_common::config::{AlertsConfig, HistoryConfig, NormalizationSettings, TokenSettings, WebhookEvent, WebhooksConfig};
 use find_content_store::ContentStore;
 
 
@@ -27,6 +29,26 @@ pub struct WorkerConfig {
     pub consecutive_timeout_limit: u32,
     /// Alert notification configuration.
     pub alerts: AlertsConfig,
+    /// Scan history retention policy, applied after each completed scan.
+    pub history: HistoryConfig,
+    /// Webhook endpoints and event thresholds.
+    pub webhooks: WebhooksConfig,
+    /// Maximum number of inbox requests indexed concurrently (at most one
+    /// per source).
+    pub concurrency: usize,
+    /// Attempts before a request that keeps failing or crashing the worker
+    /// is quarantined to `failed/`. 0 = unlimited.
+    pub max_attempts: u32,
+    /// `[sources.<name>] max_index_mb`, in bytes (see `index_budget`).
+    pub index_budgets: std::collections::HashMap<String, u64>,
+    /// Days deleted files are kept in the trash. 0 = deletions are final.
+    pub trash_days: u32,
+    /// `[tokens]` settings, recorded by each new source DB (see `db::tokens`).
+    pub tokens: TokenSettings,
+    /// Maximum line content of each part a request is split into.
+    pub part_max_bytes: usize,
+    /// v1 requests larger than this (compressed) are split. 0 = never.
+    pub split_over_bytes: u64,
 }
 
 /// Log the start and finish of a labelled step at DEBUG level, including elapsed ms.
@@ -60,7 +82,59 @@ pub(super) fn warn_slow(start: std::time::Instant, threshold_secs: u64, step: &s
     }
 }
 
-type StatusHandle = std::sync::Arc<std::sync::Mutex<WorkerStatus>>;
+/// In-flight inbox requests, keyed by the router's lane (one per source),
+/// with the file each one is currently indexing.  With `concurrency > 1`
+/// several lanes run at once, so the worker is idle only once every lane
+/// has finished.
+#[derive(Debug, Default)]
+pub struct WorkerLanes(std::collections::BTreeMap<String, String>);
+
+impl WorkerLanes {
+    /// Open `lane` before its request starts.
+    pub(crate) fn begin(&mut self, lane: &str) {
+        self.0.insert(lane.to_string(), String::new());
+    }
+
+    /// Record the file `lane` is working on.  Ignored once the lane has
+    /// finished, so a timed-out request that is still unwinding cannot
+    /// reopen it.
+    pub(crate) fn update(&mut self, lane: &str, file: String) {
+        if let Some(slot) = self.0.get_mut(lane) {
+            *slot = file;
+        }
+    }
+
+    /// Close `lane` once its request is done.
+    pub(crate) fn finish(&mut self, lane: &str) {
+        self.0.remove(lane);
+    }
+
+    /// Whether a request for `source` is in flight.
+    pub fn has_lane(&self, source: &str) -> bool {
+        self.0.contains_key(source)
+    }
+
+    /// Every open lane, ordered by source.  Lanes of unreadable requests
+    /// (see the router) have no source to report.
+    pub fn lanes(&self) -> Vec<WorkerLane> {
+        self.0.iter()
+            .map(|(lane, file)| WorkerLane {
+                source: if lane.starts_with('\0') { String::new() } else { lane.clone() },
+                file: file.clone(),
+            })
+            .collect()
+    }
+
+    /// `Idle` when no request is in flight, otherwise the first lane.
+    pub fn status(&self) -> WorkerStatus {
+        match self.lanes().into_iter().next() {
+            Some(WorkerLane { source, file }) => WorkerStatus::Processing { source, file },
+            None => WorkerStatus::Idle,
+        }
+    }
+}
+
+type StatusHandle = std::sync::Arc<std::sync::Mutex<WorkerLanes>>;
 
 /// Runtime handles passed to the inbox worker at startup.
 /// Bundles the Arc channels and broadcast sender so `start_inbox_worker`
@@ -77,6 +151,8 @@ pub struct WorkerHandles {
     pub source_stats_cache: Arc<std::sync::RwLock<crate::stats_cache::SourceStatsCache>>,
     /// Watch channel incremented after every stats cache update.
     pub stats_watch: Arc<tokio::sync::watch::Sender<u64>>,
+    /// Per-source trigram filters, updated with everything indexed.
+    pub term_filters: Arc<crate::db::term_filter::TermFilters>,
 }
 
 /// Ensure inbox subdirectories exist on startup.
@@ -91,6 +167,19 @@ pub async fn recover_stranded_requests(data_dir: &Path) -> anyhow::Result<()> {
     tokio::fs::create_dir_all(inbox_dir.join("failed")).await?;
     tokio::fs::create_dir_all(inbox_dir.join("to-archive")).await?;
 
+    // Partial v2 uploads (`.ndjson.part`) and half-written split parts
+    // (`.gz.tmp`) from a previous run can never complete; drop them.  An
+    // interrupted split is redone from its `.ndjson.gz`, which is only
+    // removed once every part is in place.
+    let mut entries = tokio::fs::read_dir(&inbox_dir).await?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.ends_with(".ndjson.part") || name.ends_with(".gz.tmp") {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+
     // One-time migration: if a `processing/` directory exists from an older
     // server version, move any stranded files back to `inbox/`.
     let processing_dir = inbox_dir.join("processing");
@@ -113,9 +202,10 @@ pub async fn recover_stranded_requests(data_dir: &Path) -> anyhow::Result<()> {
 
 /// Start the two-phase inbox worker.
 ///
-/// Phase 1 (indexing loop): a single worker processes inbox requests
-/// sequentially, writing to SQLite only (no ZIP I/O). On success, moves
-/// the .gz to `inbox/to-archive/` and signals the archive thread.
+/// Phase 1 (indexing loop): the router dispatches up to `cfg.concurrency`
+/// inbox requests at a time, never more than one per source, writing to
+/// SQLite only (no ZIP I/O). On success, moves the .gz to
+/// `inbox/to-archive/` and signals the archive thread.
 ///
 /// Phase 2 (archive loop): a single archive thread batches up to
 /// `archive_batch_size` requests from `to-archive/`, coalesces work, rewrites
@@ -125,12 +215,14 @@ pub async fn start_inbox_worker(
     cfg: WorkerConfig,
     handles: WorkerHandles,
 ) -> anyhow::Result<()> {
-    let WorkerHandles { status, content_store, inbox_paused, consecutive_timeouts, recent_tx, source_stats_cache, stats_watch } = handles;
+    let WorkerHandles { status, content_store, inbox_paused, consecutive_timeouts, recent_tx, source_stats_cache, stats_watch, term_filters } = handles;
     let stats_watch_archive = Arc::clone(&stats_watch);
     let source_stats_cache_archive = Arc::clone(&source_stats_cache);
     let inbox_dir = data_dir.join("inbox");
     let failed_dir = inbox_dir.join("failed");
     let to_archive_dir = inbox_dir.join("to-archive");
+    let webhooks_cfg = cfg.webhooks.clone();
+    let concurrency = cfg.concurrency.max(1);
 
     tokio::fs::create_dir_all(&to_archive_dir).await?;
 
@@ -141,50 +233,21 @@ pub async fn start_inbox_worker(
 
     let archive_notify = Arc::new(tokio::sync::Notify::new());
 
-    // Channel from router → worker (capacity 1: at most one file buffered ahead).
-    let (work_tx, mut work_rx) = tokio::sync::mpsc::channel::<PathBuf>(1);
-    // Channel from worker → router: signals that a path is no longer in-flight.
+    // Channel from request tasks → router: signals that a path is no longer in-flight.
     let (done_tx, done_rx) = tokio::sync::mpsc::channel::<PathBuf>(64);
 
-    // Spawn the single indexing worker task.
-    {
-        let data_dir = data_dir.clone();
-        let failed_dir = failed_dir.clone();
-        let to_archive_dir_clone = to_archive_dir.clone();
-        let status = status.clone();
-        let archive_notify = Arc::clone(&archive_notify);
-        let cfg_index = cfg.clone();
-        let content_store_index = Arc::clone(&content_store);
-        let inbox_paused_index = Arc::clone(&inbox_paused);
-        let consecutive_timeouts_index = Arc::clone(&consecutive_timeouts);
-
-        tokio::spawn(async move {
-            tracing::debug!("Indexing worker started");
-            let handles = request::IndexerHandles {
-                status,
-                cfg: cfg_index,
-                archive_notify,
-                recent_tx,
-                source_stats_cache,
-                content_store: content_store_index,
-                stats_watch,
-                inbox_paused: inbox_paused_index,
-                consecutive_timeouts: consecutive_timeouts_index,
-            };
-            while let Some(path) = work_rx.recv().await {
-                let ctx = request::RequestContext {
-                    data_dir: data_dir.clone(),
-                    request_path: path.clone(),
-                    failed_dir: failed_dir.clone(),
-                    to_archive_dir: to_archive_dir_clone.clone(),
-                };
-                request::process_request_async(&ctx, &handles).await;
-                // Signal the router that this path is done (success or failure).
-                let _ = done_tx.send(path).await;
-            }
-            tracing::debug!("Indexing worker exited");
-        });
-    }
+    let indexer = Arc::new(request::IndexerHandles {
+        status,
+        cfg: cfg.clone(),
+        archive_notify: Arc::clone(&archive_notify),
+        recent_tx,
+        source_stats_cache,
+        content_store: Arc::clone(&content_store),
+        stats_watch,
+        inbox_paused: Arc::clone(&inbox_paused),
+        consecutive_timeouts,
+        term_filters,
+    });
 
     // Spawn the archive loop (blocking, spawn_blocking wrapper).
     {
@@ -258,18 +321,22 @@ pub async fn start_inbox_worker(
         });
     }
 
-    // Router loop: poll inbox, dispatch files not already in-flight to the worker.
+    // Router loop: poll inbox, dispatch files not already in-flight to a
+    // request task, keeping at most one request per source in flight.
     let mut interval = tokio::time::interval(POLL_INTERVAL);
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-    let mut in_flight: std::collections::HashSet<PathBuf> = std::collections::HashSet::new();
+    // In-flight request path → source it writes to.
+    let mut in_flight: std::collections::HashMap<PathBuf, String> = std::collections::HashMap::new();
+    // Source names read from pending requests, so each file is peeked once.
+    let mut sources: std::collections::HashMap<PathBuf, String> = std::collections::HashMap::new();
     let mut done_rx = done_rx;
+    let mut backlog_alerted = false;
 
     loop {
         tokio::select! {
             _ = interval.tick() => {}
             Some(done_path) = done_rx.recv() => {
                 in_flight.remove(&done_path);
-                while let Ok(p) = done_rx.try_recv() { in_flight.remove(&p); }
             }
         }
 
@@ -296,34 +363,137 @@ pub async fn start_inbox_worker(
                 gz_files.push((mtime, path));
             }
         }
-        gz_files.sort_unstable_by_key(|(mtime, _)| *mtime);
+        // Names start with the arrival time, so they break mtime ties in order.
+        gz_files.sort_unstable();
+
+        // Edge-triggered backlog webhook: fire once when the threshold is
+        // crossed, re-arm once the inbox drains back below it.
+        let backlog_threshold = webhooks_cfg.backlog_threshold;
+        if backlog_threshold > 0 {
+            let pending = gz_files.len();
+            if pending >= backlog_threshold && !backlog_alerted {
+                backlog_alerted = true;
+                crate::webhooks::send(&webhooks_cfg, WebhookEvent::InboxBacklog, serde_json::json!({
+                    "inbox_pending": pending,
+                    "threshold":     backlog_threshold,
+                    "inbox_paused":  inbox_paused.load(Ordering::Relaxed),
+                }));
+            } else if pending < backlog_threshold {
+                backlog_alerted = false;
+            }
+        }
 
         if inbox_paused.load(Ordering::Relaxed) {
             continue;
         }
 
+        let pending: std::collections::HashSet<&PathBuf> = gz_files.iter().map(|(_, p)| p).collect();
+        sources.retain(|p, _| pending.contains(p));
         for (_, inbox_path) in gz_files {
-            if in_flight.contains(&inbox_path) {
+            if in_flight.len() >= concurrency {
+                break;
+            }
+            if in_flight.contains_key(&inbox_path) {
                 continue;
             }
-            match work_tx.try_send(inbox_path.clone()) {
-                Ok(()) => {
-                    in_flight.insert(inbox_path);
+            let source = match sources.get(&inbox_path) {
+                Some(s) => s.clone(),
+                None => {
+                    let p = inbox_path.clone();
+                    // Unreadable requests get a lane of their own; the request
+                    // task will fail them and move them to `failed/`.
+                    let source = tokio::task::spawn_blocking(move || peek_source(&p))
+                        .await
+                        .ok()
+                        .flatten()
+                        .unwrap_or_else(|| format!("\0{}", inbox_path.display()));
+                    sources.insert(inbox_path.clone(), source.clone());
+                    source
                 }
-                Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
-                    break;
+            };
+            // Oldest-first within a source: a later request for a busy source
+            // must not overtake the one already running.
+            if in_flight.values().any(|s| *s == source) {
+                continue;
+            }
+            in_flight.insert(inbox_path.clone(), source.clone());
+
+            let ctx = request::RequestContext {
+                data_dir: data_dir.clone(),
+                request_path: inbox_path,
+                lane: source,
+                failed_dir: failed_dir.clone(),
+                to_archive_dir: to_archive_dir.clone(),
+            };
+            let indexer = Arc::clone(&indexer);
+            let done_tx = done_tx.clone();
+            tokio::spawn(async move {
+                let cfg = &indexer.cfg;
+                // Splitting queues ordinary requests for the same source,
+                // picked up on the next pass once this lane is free.
+                if stream::is_stream_request(&ctx.request_path) {
+                    stream::split_request(&ctx, cfg.part_max_bytes).await;
+                } else if !stream::split_oversized_request(&ctx, cfg.split_over_bytes, cfg.part_max_bytes).await {
+                    request::process_request_async(&ctx, &indexer).await;
                 }
-                Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
-                    tracing::error!("Worker channel closed unexpectedly; stopping router");
-                    return Ok(());
+                // Signal the router that this path is done (success or failure).
+                let _ = done_tx.send(ctx.request_path).await;
+            });
+        }
+    }
+}
+
+/// Whether a request for `source` is waiting in `inbox_dir`.  Requests stay
+/// there until the worker is done with them, so this includes running ones.
+pub(crate) fn has_queued_request(inbox_dir: &Path, source: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(inbox_dir) else { return false };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension() == Some(OsStr::new("gz")))
+        .any(|p| peek_source(&p).as_deref() == Some(source))
+}
+
+/// Read the `source` field of a queued bulk request without decoding the
+/// rest of it.  The client serializes `source` first, so this normally stops
+/// after the first few bytes of the decompressed stream.  Returns `None` when
+/// the file is unreadable or has no `source`.
+fn peek_source(path: &Path) -> Option<String> {
+    use serde::de::{Deserializer, Error, IgnoredAny, MapAccess, Visitor};
+
+    // Stores the source and then aborts with an error: returning normally
+    // would make serde_json read on to the closing brace of the object.
+    struct SourceVisitor<'a>(&'a mut Option<String>);
+
+    impl<'de> Visitor<'de> for SourceVisitor<'_> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a bulk request object")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            while let Some(key) = map.next_key::<String>()? {
+                if key == "source" {
+                    *self.0 = Some(map.next_value()?);
+                    return Err(A::Error::custom("source found"));
                 }
+                map.next_value::<IgnoredAny>()?;
             }
+            Ok(())
         }
     }
+
+    let file = std::fs::File::open(path).ok()?;
+    let decoder = flate2::read::GzDecoder::new(std::io::BufReader::new(file));
+    let mut source = None;
+    let _ = serde_json::Deserializer::from_reader(decoder).deserialize_map(SourceVisitor(&mut source));
+    source
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{peek_source, WorkerLanes};
     use super::pipeline::{filename_only_file, is_outer_archive, outer_archive_stub};
     use find_common::api::{FileKind, IndexFile, IndexLine};
 
@@ -343,9 +513,43 @@ mod tests {
             scanner_version: 0,
             is_new: false,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }
     }
 
+    fn write_gz(dir: &std::path::Path, name: &str, json: &str) -> std::path::PathBuf {
+        use std::io::Write;
+        let path = dir.join(name);
+        let mut enc = flate2::write::GzEncoder::new(
+            std::fs::File::create(&path).unwrap(),
+            flate2::Compression::fast(),
+        );
+        enc.write_all(json.as_bytes()).unwrap();
+        enc.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn peek_source_reads_source_field() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let first = write_gz(dir.path(), "a.gz", r#"{"source":"docs","files":[]}"#);
+        assert_eq!(peek_source(&first).as_deref(), Some("docs"));
+        let later = write_gz(dir.path(), "b.gz", r#"{"files":[{"x":[1,2]}],"source":"photos"}"#);
+        assert_eq!(peek_source(&later).as_deref(), Some("photos"));
+    }
+
+    #[test]
+    fn peek_source_none_for_unreadable_request() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = write_gz(dir.path(), "a.gz", r#"{"files":[]}"#);
+        assert_eq!(peek_source(&missing), None);
+        let garbage = dir.path().join("b.gz");
+        std::fs::write(&garbage, b"not gzip").unwrap();
+        assert_eq!(peek_source(&garbage), None);
+    }
+
     #[test]
     fn outer_archive_detected() {
         assert!(is_outer_archive("data.zip", &FileKind::Archive));
@@ -401,6 +605,30 @@ mod tests {
         assert_eq!(stub.kind, FileKind::Archive);
     }
 
+    #[test]
+    fn worker_idle_only_when_every_lane_finishes() {
+        use find_common::api::WorkerStatus;
+        let mut lanes = WorkerLanes::default();
+        lanes.begin("alpha");
+        lanes.begin("bravo");
+        lanes.update("alpha", "a.txt".into());
+        lanes.update("bravo", "b.txt".into());
+        assert_eq!(lanes.lanes().len(), 2);
+
+        lanes.finish("alpha");
+        assert_eq!(
+            lanes.status(),
+            WorkerStatus::Processing { source: "bravo".into(), file: "b.txt".into() },
+        );
+        // A late update from a finished lane does not reopen it.
+        lanes.update("alpha", "late.txt".into());
+        assert_eq!(lanes.lanes().len(), 1);
+
+        lanes.finish("bravo");
+        assert_eq!(lanes.status(), WorkerStatus::Idle);
+        assert!(lanes.lanes().is_empty());
+    }
+
     #[test]
     fn outer_archive_stub_uses_zero_mtime() {
         let f = make_file("backup.7z", FileKind::Archive);
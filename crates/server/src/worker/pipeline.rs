@@ -2,11 +2,11 @@
 ///
 /// These functions operate on a single `IndexFile` at a time and are called
 /// by `process_request_phase1` in the request-level coordinator.
-use anyhow::Result;
+use anyhow::{Context, Result};
 use rusqlite::Connection;
 use rusqlite::OptionalExtension;
 
-use find_common::api::{FileKind, IndexFile, IndexLine, LINE_PATH, LINE_METADATA};
+use find_common::api::{FileKind, IndexFile, IndexLine, LINE_PATH, LINE_METADATA, LINE_CONTENT_START};
 use find_common::path::{composite_like_prefix, is_composite};
 use find_content_store::{ContentKey, ContentStore};
 
@@ -118,29 +118,7 @@ pub(super) fn process_file_phase1_fallback(
     let line_count = file.lines.len() as i64;
 
     // Upsert the file record, keeping the same file_id on re-index.
-    let file_id: i64 = tx.query_row(
-        "INSERT INTO files (path, mtime, size, kind, scanner_version, indexed_at, extract_ms, file_hash, line_count)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-         ON CONFLICT(path) DO UPDATE SET
-           mtime             = excluded.mtime,
-           size              = excluded.size,
-           kind              = excluded.kind,
-           scanner_version   = excluded.scanner_version,
-           indexed_at        = excluded.indexed_at,
-           extract_ms        = excluded.extract_ms,
-           file_hash         = excluded.file_hash,
-           line_count        = excluded.line_count
-         RETURNING id",
-        rusqlite::params![
-            file.path, file.mtime, file.size, file.kind.to_string(),
-            file.scanner_version,
-            now_secs,
-            file.extract_ms.map(|ms| ms as i64),
-            file.file_hash.as_deref(),
-            line_count,
-        ],
-        |row| row.get(0),
-    )?;
+    let file_id = upsert_file_row(&tx, file, now_secs, line_count)?;
 
     // On re-index: remove old FTS entries using the FTS5 'delete' command.
     // contentless FTS5 supports 'delete' as long as we supply the original content —
@@ -158,7 +136,7 @@ pub(super) fn process_file_phase1_fallback(
         if (pos as i64) < MAX_LINES_PER_FILE {
             let old_rowid = encode_fts_rowid(file_id, pos as i64);
             tx.execute(
-                "INSERT INTO lines_fts(lines_fts, rowid, content) VALUES('delete', ?1, ?2)",
+                "INSERT INTO lines_fts(lines_fts, rowid, content) VALUES('delete', ?1, fa_tokens(?2))",
                 rusqlite::params![old_rowid, content],
             )?;
         }
@@ -178,7 +156,7 @@ pub(super) fn process_file_phase1_fallback(
         }
         let rowid = encode_fts_rowid(file_id, line_number);
         tx.execute(
-            "INSERT INTO lines_fts(rowid, content) VALUES (?1, ?2)",
+            "INSERT INTO lines_fts(rowid, content) VALUES (?1, fa_tokens(?2))",
             rusqlite::params![rowid, line.content.trim_end()],
         )?;
     }
@@ -199,6 +177,160 @@ pub(super) fn process_file_phase1_fallback(
     }
 }
 
+/// Fill in the lines of a file sent with `reuse_content` from the content the
+/// store holds under its `file_hash`, with the path line rewritten for this
+/// file's path.  Fails if the content is no longer stored; nothing is written
+/// then, so the client extracts and sends the file on its next scan.
+pub(super) fn load_reused_content(file: &mut IndexFile, content_store: &dyn ContentStore) -> Result<()> {
+    let hash = file.file_hash.as_deref()
+        .with_context(|| format!("reused content for {} has no file_hash", file.path))?;
+    let lines = content_store
+        .get_lines(&ContentKey::new(hash), 0, i64::MAX as usize)?
+        .with_context(|| format!("content {hash} for {} is not stored", file.path))?;
+    file.lines = lines
+        .into_iter()
+        .map(|(pos, content)| IndexLine {
+            archive_path: None,
+            line_number: pos,
+            content: if pos == LINE_PATH { format!("[PATH] {}", file.path) } else { content },
+        })
+        .collect();
+    file.reuse_content = false;
+    Ok(())
+}
+
+/// Apply an appended-lines delta (`file.append_to`, see `AppendBase`):
+/// keep the indexed lines and their FTS rows, index only the new lines after
+/// them, and replace `file.lines` with the merged content so the archive
+/// phase stores the complete new version under the new `file_hash`.
+///
+/// Fails without writing anything when the indexed version is not the one the
+/// delta extends, or its content is not in the content store yet (still queued
+/// for the archive phase).  The stored mtime then stays older than the file's,
+/// so the client sends the file again on its next scan.
+pub(super) fn process_append_phase1(
+    conn: &mut Connection,
+    file: &mut IndexFile,
+    content_store: &dyn ContentStore,
+) -> Result<Phase1Outcome> {
+    let Some(base) = file.append_to.take() else {
+        return process_file_phase1(conn, file, Some(content_store));
+    };
+    let now_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let existing: Option<(i64, i64, String, Option<String>, i64)> = conn.query_row(
+        "SELECT mtime, COALESCE(size,0), kind, file_hash, COALESCE(line_count,0) FROM files WHERE path = ?1",
+        rusqlite::params![file.path],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+    ).optional()?;
+    let Some((stored_mtime, old_size, old_kind, stored_hash, old_line_count)) = existing else {
+        anyhow::bail!("append delta: {} is not indexed", file.path);
+    };
+    if stored_hash.as_deref() != Some(base.file_hash.as_str()) {
+        anyhow::bail!(
+            "append delta: indexed version of {} is {}, not {}",
+            file.path, stored_hash.as_deref().unwrap_or("unhashed"), base.file_hash
+        );
+    }
+    if !file.force && file.mtime > 0 && file.mtime < stored_mtime {
+        tracing::debug!(
+            "skipping stale append for {} (incoming mtime={} < stored={})",
+            file.path, file.mtime, stored_mtime
+        );
+        return Ok(Phase1Outcome::Skipped);
+    }
+
+    // Read the indexed lines before opening the write transaction (see
+    // `process_file_phase1_fallback`).
+    let base_lines = content_store
+        .get_lines(&ContentKey::new(base.file_hash.as_str()), 0, old_line_count as usize)?
+        .with_context(|| format!("append delta: content of {} is not stored yet", file.path))?;
+
+    let mut appended: Vec<IndexLine> = std::mem::take(&mut file.lines)
+        .into_iter()
+        .filter(|l| l.line_number >= LINE_CONTENT_START)
+        .collect();
+    appended.sort_by_key(|l| l.line_number);
+    let first_new = base_lines.last().map_or(LINE_CONTENT_START, |(pos, _)| pos + 1);
+    for (i, line) in appended.iter_mut().enumerate() {
+        line.line_number = first_new + i;
+    }
+
+    let t_fts = std::time::Instant::now();
+    let tx = conn.transaction()?;
+    let line_count = (base_lines.len() + appended.len()) as i64;
+    let file_id = upsert_file_row(&tx, file, now_secs, line_count)?;
+    for line in &appended {
+        let line_number = line.line_number as i64;
+        if line_number >= MAX_LINES_PER_FILE {
+            tracing::warn!(
+                "file {} line {} exceeds MAX_LINES_PER_FILE — skipping FTS",
+                file.path, line_number
+            );
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO lines_fts(rowid, content) VALUES (?1, fa_tokens(?2))",
+            rusqlite::params![encode_fts_rowid(file_id, line_number), line.content.trim_end()],
+        )?;
+    }
+    if let Some(hash) = &file.file_hash {
+        upsert_duplicate_tracking(&tx, hash, file_id)?;
+    }
+    tx.commit()?;
+    super::warn_slow(t_fts, 10, "fts_insert_append", &file.path);
+
+    file.lines = base_lines
+        .into_iter()
+        .map(|(pos, content)| IndexLine { archive_path: None, line_number: pos, content })
+        .chain(appended)
+        .collect();
+    Ok(Phase1Outcome::Modified { old_size, old_kind: FileKind::from(old_kind.as_str()) })
+}
+
+/// Insert or update the `files` row for `file`, keeping the same id on
+/// re-index.  Returns the file id.
+fn upsert_file_row(
+    tx: &rusqlite::Transaction,
+    file: &IndexFile,
+    now_secs: i64,
+    line_count: i64,
+) -> Result<i64> {
+    Ok(tx.query_row(
+        "INSERT INTO files (path, mtime, size, kind, scanner_version, indexed_at, extract_ms, file_hash, line_count,
+                            extractor, extractor_version, extract_truncated)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+         ON CONFLICT(path) DO UPDATE SET
+           mtime             = excluded.mtime,
+           size              = excluded.size,
+           kind              = excluded.kind,
+           scanner_version   = excluded.scanner_version,
+           indexed_at        = excluded.indexed_at,
+           extract_ms        = excluded.extract_ms,
+           file_hash         = excluded.file_hash,
+           line_count        = excluded.line_count,
+           extractor         = excluded.extractor,
+           extractor_version = excluded.extractor_version,
+           extract_truncated = excluded.extract_truncated
+         RETURNING id",
+        rusqlite::params![
+            file.path, file.mtime, file.size, file.kind.to_string(),
+            file.scanner_version,
+            now_secs,
+            file.extract_ms.map(|ms| ms as i64),
+            file.file_hash.as_deref(),
+            line_count,
+            file.provenance.as_ref().map(|p| p.extractor.as_str()),
+            file.provenance.as_ref().and_then(|p| p.version.as_deref()),
+            file.provenance.as_ref().is_some_and(|p| p.truncated),
+        ],
+        |row| row.get(0),
+    )?)
+}
+
 /// Insert duplicate tracking entries when 2+ files share a file_hash.
 fn upsert_duplicate_tracking(
     tx: &rusqlite::Transaction,
@@ -263,6 +395,9 @@ pub(super) fn filename_only_file(file: &IndexFile) -> IndexFile {
         scanner_version: file.scanner_version,
         is_new: file.is_new,
         force: file.force,
+        append_to: None,
+        reuse_content: false,
+        provenance: None,
     }
 }
 
@@ -291,6 +426,9 @@ pub(super) fn outer_archive_stub(file: &IndexFile) -> IndexFile {
         scanner_version: file.scanner_version,
         is_new: file.is_new,
         force: file.force,
+        append_to: None,
+        reuse_content: false,
+        provenance: None,
     }
 }
 
@@ -327,6 +465,9 @@ mod tests {
             file_hash: None,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }
     }
 
@@ -501,4 +642,30 @@ mod tests {
         // New content must be findable: "distinct" only appears in v2.
         assert!(fts_match_count(&conn, "distinct") > 0, "new term 'distinct' must be in FTS");
     }
+
+    #[test]
+    fn reused_content_is_loaded_under_the_new_path() {
+        let (_tmp, store) = open_store();
+        let hash = "cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc";
+        store.put(&ContentKey::new(hash), "[PATH] old/notes.txt\n\nshared content line").unwrap();
+
+        let mut file = make_file("new/notes.txt", 1000, "");
+        file.lines.clear();
+        file.file_hash = Some(hash.to_string());
+        file.reuse_content = true;
+        load_reused_content(&mut file, store.as_ref()).unwrap();
+
+        assert!(!file.reuse_content);
+        let contents: Vec<(usize, &str)> = file.lines.iter().map(|l| (l.line_number, l.content.as_str())).collect();
+        assert_eq!(contents, [(0, "[PATH] new/notes.txt"), (1, ""), (2, "shared content line")]);
+
+        let mut conn = test_conn();
+        process_file_phase1(&mut conn, &file, Some(store.as_ref())).unwrap();
+        assert!(fts_match_count(&conn, "shared") > 0);
+
+        let mut missing = make_file("other.txt", 1000, "");
+        missing.file_hash = Some("d".repeat(64));
+        missing.reuse_content = true;
+        assert!(load_reused_content(&mut missing, store.as_ref()).is_err());
+    }
 }
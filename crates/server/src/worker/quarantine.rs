@@ -0,0 +1,170 @@
+/// Attempt tracking and quarantine for poison inbox requests.
+///
+/// Every time the worker picks up a request it bumps a small
+/// `inbox/<name>.attempts` sidecar *before* doing any work, so attempts that
+/// never finish (the server crashed, was killed, or ran out of memory while
+/// processing) are still counted across restarts.  A request that errors, or
+/// that reaches `max_attempts`, is moved to `inbox/failed/` together with an
+/// `<name>.error.json` describing why (see [`InboxFailure`]).
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use find_common::api::InboxFailure;
+
+/// Sidecar holding the attempt count of a pending request.
+pub(crate) fn attempts_sidecar(request_path: &Path) -> PathBuf {
+    request_path.with_extension("attempts")
+}
+
+/// Diagnostics sidecar of a quarantined request in `failed/`.
+pub(crate) fn error_sidecar(request_path: &Path) -> PathBuf {
+    request_path.with_extension("error.json")
+}
+
+/// Contents of the `.attempts` sidecar.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct Attempts {
+    pub attempts: u32,
+    /// Last retryable error (e.g. database locked), if any.
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+async fn read_attempts(request_path: &Path) -> Attempts {
+    tokio::fs::read(attempts_sidecar(request_path))
+        .await
+        .ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default()
+}
+
+async fn write_attempts(request_path: &Path, record: &Attempts) {
+    let path = attempts_sidecar(request_path);
+    let json = serde_json::to_vec(record).unwrap_or_default();
+    if let Err(e) = tokio::fs::write(&path, json).await {
+        tracing::warn!("Failed to write {}: {e}", path.display());
+    }
+}
+
+/// Count a new attempt at `request_path`.  Returns the record as it stood
+/// before this attempt, so the caller can quarantine the request instead of
+/// processing it when earlier attempts already used up the limit.
+pub(super) async fn begin_attempt(request_path: &Path) -> Attempts {
+    let previous = read_attempts(request_path).await;
+    let next = Attempts {
+        attempts: previous.attempts + 1,
+        last_error: previous.last_error.clone(),
+        file: previous.file.clone(),
+    };
+    write_attempts(request_path, &next).await;
+    previous
+}
+
+/// Remember a retryable error; the request stays in the inbox.
+pub(super) async fn record_retryable(request_path: &Path, error: &anyhow::Error, file: Option<String>) {
+    let mut record = read_attempts(request_path).await;
+    record.last_error = Some(format!("{error:#}"));
+    record.file = file;
+    write_attempts(request_path, &record).await;
+}
+
+/// Forget the attempts of a request that completed.
+pub(super) async fn clear(request_path: &Path) {
+    let _ = tokio::fs::remove_file(attempts_sidecar(request_path)).await;
+}
+
+/// Move a request to `failed_dir` and write its `.error.json`.
+pub(super) async fn quarantine(
+    request_path: &Path,
+    failed_dir: &Path,
+    error: &anyhow::Error,
+    file: Option<String>,
+    attempts: u32,
+) {
+    tracing::error!("Failed to process {}: {error:#}", request_path.display());
+
+    let Some(name) = request_path.file_name() else { return };
+    let failed_path = failed_dir.join(name);
+    let source = tokio::task::spawn_blocking({
+        let p = request_path.to_path_buf();
+        move || super::peek_source(&p)
+    })
+    .await
+    .ok()
+    .flatten();
+    let failure = InboxFailure {
+        error: format!("{error:#}"),
+        source,
+        file,
+        attempts,
+        failed_at: chrono::Utc::now().timestamp(),
+    };
+
+    if let Err(e) = tokio::fs::rename(request_path, &failed_path).await {
+        tracing::error!(
+            "Failed to move {} to failed directory: {}",
+            request_path.display(),
+            e
+        );
+        return;
+    }
+    tracing::warn!("Moved failed request to: {}", failed_path.display());
+    clear(request_path).await;
+
+    let json = serde_json::to_vec_pretty(&failure).unwrap_or_default();
+    if let Err(e) = tokio::fs::write(error_sidecar(&failed_path), json).await {
+        tracing::warn!("Failed to write diagnostics for {}: {e}", failed_path.display());
+    }
+}
+
+/// Read the diagnostics of a quarantined request, if they were recorded.
+pub(crate) fn read_failure(failed_path: &Path) -> Option<InboxFailure> {
+    let bytes = std::fs::read(error_sidecar(failed_path)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn attempts_accumulate_until_cleared() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let req = dir.path().join("req_1.gz");
+        std::fs::write(&req, b"x").unwrap();
+
+        assert_eq!(begin_attempt(&req).await.attempts, 0);
+        record_retryable(&req, &anyhow::anyhow!("database is locked"), Some("a.txt".into())).await;
+        let before = begin_attempt(&req).await;
+        assert_eq!(before.attempts, 1);
+        assert_eq!(before.last_error.as_deref(), Some("database is locked"));
+        assert_eq!(before.file.as_deref(), Some("a.txt"));
+        assert_eq!(begin_attempt(&req).await.attempts, 2);
+
+        clear(&req).await;
+        assert_eq!(begin_attempt(&req).await.attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn quarantine_writes_error_json() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let failed = dir.path().join("failed");
+        std::fs::create_dir(&failed).unwrap();
+        let req = dir.path().join("req_2.gz");
+        std::fs::write(&req, b"not gzip").unwrap();
+        begin_attempt(&req).await;
+
+        quarantine(&req, &failed, &anyhow::anyhow!("boom"), Some("bad.pdf".into()), 1).await;
+
+        assert!(!req.exists());
+        assert!(!attempts_sidecar(&req).exists());
+        let failure = read_failure(&failed.join("req_2.gz")).expect("error.json");
+        assert_eq!(failure.error, "boom");
+        assert_eq!(failure.file.as_deref(), Some("bad.pdf"));
+        assert_eq!(failure.attempts, 1);
+        assert_eq!(failure.source, None);
+    }
+}
@@ -10,21 +10,25 @@ use anyhow::{Context, Result};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use rusqlite::ErrorCode;
+use std::collections::HashMap;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use tokio::sync::broadcast;
 
-use find_common::api::{BulkRequest, IndexingFailure, RecentAction, RecentFile};
+use find_common::api::{BulkRequest, FileKind, IndexingFailure, RecentAction, RecentFile};
+use find_common::config::{WebhookEvent, WebhooksConfig};
 use find_common::path::is_composite;
 use find_content_store::ContentStore;
 
 use crate::db;
 use crate::normalize;
+use crate::webhooks;
 
 use super::{StatusHandle, WorkerConfig, timed, warn_slow};
 use super::pipeline;
+use super::quarantine;
 
 // ── Context structs ─────────────────────────────────────────────────────────────
 
@@ -32,6 +36,8 @@ use super::pipeline;
 pub(super) struct RequestContext {
     pub data_dir:       PathBuf,
     pub request_path:   PathBuf,
+    /// Router lane the request runs in: its source, as peeked from the file.
+    pub lane:           String,
     pub failed_dir:     PathBuf,
     pub to_archive_dir: PathBuf,
 }
@@ -50,6 +56,7 @@ pub(super) struct IndexerHandles {
     pub inbox_paused:        Arc<AtomicBool>,
     /// Counts consecutive timeouts for the circuit-breaker check.
     pub consecutive_timeouts: Arc<AtomicU32>,
+    pub term_filters:        Arc<db::term_filter::TermFilters>,
 }
 
 // ── Public entry point ─────────────────────────────────────────────────────────
@@ -71,9 +78,32 @@ pub(super) async fn process_request_async(
     ctx: &RequestContext,
     handles: &IndexerHandles,
 ) {
-    let status_reset = handles.status.clone();
     let request_timeout = handles.cfg.request_timeout;
 
+    // Count the attempt before doing any work so that attempts which never
+    // finish (crash, OOM kill) still count towards the quarantine limit.
+    let previous = quarantine::begin_attempt(&ctx.request_path).await;
+    let attempt = previous.attempts + 1;
+    let max_attempts = handles.cfg.max_attempts;
+    if max_attempts > 0 && previous.attempts >= max_attempts {
+        let error = match &previous.last_error {
+            Some(e) => anyhow::anyhow!("gave up after {} attempts; last error: {e}", previous.attempts),
+            None => anyhow::anyhow!(
+                "gave up after {} attempts; the server stopped while processing this request",
+                previous.attempts,
+            ),
+        };
+        quarantine::quarantine(&ctx.request_path, &ctx.failed_dir, &error, previous.file, previous.attempts).await;
+        return;
+    }
+
+    if let Ok(mut lanes) = handles.status.lock() {
+        lanes.begin(&ctx.lane);
+    }
+
+    // Path of the file entry being indexed, for the quarantine diagnostics.
+    let current_file = Arc::new(std::sync::Mutex::new(None::<String>));
+
     // The blocking task sends back the interrupt handle as soon as it opens the
     // SQLite connection, before doing any work that could block.
     let (interrupt_tx, mut interrupt_rx) =
@@ -88,13 +118,17 @@ pub(super) async fn process_request_async(
         let recent_tx = handles.recent_tx.clone();
         let stats_watch = Arc::clone(&handles.stats_watch);
         let content_store = Arc::clone(&handles.content_store);
-        move || process_request_phase1(interrupt_tx, &data_dir, &request_path, &to_archive_dir, &status, cfg, &recent_tx, &stats_watch, &content_store)
+        let current_file = Arc::clone(&current_file);
+        let term_filters = Arc::clone(&handles.term_filters);
+        let source_stats_cache = Arc::clone(&handles.source_stats_cache);
+        move || process_request_phase1(interrupt_tx, &data_dir, &request_path, &to_archive_dir, &status, &current_file, cfg, &recent_tx, &stats_watch, &content_store, &term_filters, &source_stats_cache)
     });
 
     let timed_result = tokio::time::timeout(request_timeout, blocking_task).await;
+    let failed_file = current_file.lock().ok().and_then(|f| f.clone());
 
-    if let Ok(mut guard) = status_reset.lock() {
-        *guard = find_common::api::WorkerStatus::Idle;
+    if let Ok(mut lanes) = handles.status.lock() {
+        lanes.finish(&ctx.lane);
     }
 
     match timed_result {
@@ -114,10 +148,12 @@ pub(super) async fn process_request_async(
                 request_timeout.as_secs(),
                 ctx.request_path.display(),
             );
-            handle_failure(
+            quarantine::quarantine(
                 &ctx.request_path,
                 &ctx.failed_dir,
-                anyhow::anyhow!("Processing timed out after {}s", request_timeout.as_secs()),
+                &anyhow::anyhow!("Processing timed out after {}s", request_timeout.as_secs()),
+                failed_file,
+                attempt,
             )
             .await;
 
@@ -157,6 +193,7 @@ pub(super) async fn process_request_async(
                 tracing::debug!("Phase 1 complete, queued for archive: {}", ctx.request_path.display());
                 handles.archive_notify.notify_one();
             }
+            quarantine::clear(&ctx.request_path).await;
             // Apply incremental stats delta to the cache.
             if let Ok(mut guard) = handles.source_stats_cache.write() {
                 guard.apply_delta(&delta);
@@ -166,26 +203,30 @@ pub(super) async fn process_request_async(
         Ok(Ok(Err(e))) => {
             if is_db_locked(&e) {
                 // File is still in inbox/ — the router will rediscover and
-                // retry it on the next scan tick.  Do not touch the timeout
-                // counter: a lock error is a transient condition, not a sign
-                // the worker has recovered or is stuck for 1800s.
+                // retry it on the next scan tick, until `max_attempts` is
+                // reached.  Do not touch the timeout counter: a lock error is
+                // a transient condition, not a sign the worker has recovered
+                // or is stuck for 1800s.
                 tracing::warn!(
-                    "Database locked while processing {}, will retry: {e:#}",
+                    "Database locked while processing {} (attempt {attempt}), will retry: {e:#}",
                     ctx.request_path.display(),
                 );
+                quarantine::record_retryable(&ctx.request_path, &e, failed_file).await;
             } else {
                 // Non-lock error resolved quickly — reset the timeout counter.
                 handles.consecutive_timeouts.store(0, Ordering::Relaxed);
-                handle_failure(&ctx.request_path, &ctx.failed_dir, e).await;
+                quarantine::quarantine(&ctx.request_path, &ctx.failed_dir, &e, failed_file, attempt).await;
             }
         }
         Ok(Err(e)) => {
             // Blocking task panic or join error — reset the timeout counter.
             handles.consecutive_timeouts.store(0, Ordering::Relaxed);
-            handle_failure(
+            quarantine::quarantine(
                 &ctx.request_path,
                 &ctx.failed_dir,
-                anyhow::anyhow!("Task error: {}", e),
+                &anyhow::anyhow!("Task error: {}", e),
+                failed_file,
+                attempt,
             )
             .await;
         }
@@ -194,6 +235,10 @@ pub(super) async fn process_request_async(
 
 // ── Phase 1: synchronous request processing ───────────────────────────────────
 
+/// Files indexed between the passive WAL checkpoints phase 1 runs within a
+/// request.
+const CHECKPOINT_EVERY_FILES: usize = 200;
+
 /// Phase 1: process a single inbox request — SQLite only, no content store I/O.
 /// Writes a normalized `.gz` to `to_archive_dir` for the archive phase.
 ///
@@ -208,10 +253,13 @@ fn process_request_phase1(
     request_path: &Path,
     to_archive_dir: &Path,
     status: &StatusHandle,
+    current_file: &std::sync::Mutex<Option<String>>,
     cfg: WorkerConfig,
     recent_tx: &tokio::sync::broadcast::Sender<RecentFile>,
     stats_watch: &Arc<tokio::sync::watch::Sender<u64>>,
     content_store: &Arc<dyn ContentStore>,
+    term_filters: &db::term_filter::TermFilters,
+    source_stats_cache: &std::sync::RwLock<crate::stats_cache::SourceStatsCache>,
 ) -> Result<crate::stats_cache::SourceStatsDelta> {
     let request_start = std::time::Instant::now();
 
@@ -246,38 +294,43 @@ fn process_request_phase1(
 
     let mut delta = crate::stats_cache::SourceStatsDelta {
         source: request.source.clone(),
+        files_indexed: n_files as u64,
+        lines_indexed: total_content_lines as u64,
         ..Default::default()
     };
 
     // Signal batch start so the live status view shows Processing immediately.
-    if let Ok(mut guard) = status.lock() {
-        *guard = find_common::api::WorkerStatus::Processing {
-            source: request.source.clone(),
-            file: format!("(0/{n_files})"),
-        };
+    if let Ok(mut lanes) = status.lock() {
+        lanes.update(&request.source, format!("(0/{n_files})"));
     }
     stats_watch.send_modify(|v| *v = v.wrapping_add(1));
 
     tracing::debug!("{tag} start: {} files, {} deletes, {} renames", n_files, n_deletes, n_renames);
 
     let db_path = data_dir.join("sources").join(format!("{}.db", request.source));
-    let mut conn = timed!(tag, "open db", { db::open(&db_path)? });
+    let mut conn = timed!(tag, "open db", { db::open_writer(&db_path)? });
+    let tokens = db::tokens::adopt(&conn, &cfg.tokens)?;
 
     // Send the interrupt handle to the async side so it can unblock us if the
     // request timeout fires.  Errors are ignored: if the receiver was dropped
     // (timeout already fired before we opened the connection), this is a no-op.
     let _ = interrupt_tx.send(conn.get_interrupt_handle());
 
+    // Trigrams of everything this request writes to `lines_fts` go into the
+    // source's term filter before the rows do (see `db::term_filter`).  The
+    // router runs one request per source at a time, so trigrams that are
+    // already set were written by an earlier, finished request.  Text is
+    // normalized first, as `fa_tokens()` does for the rows themselves.
+    let term_filter = term_filters.get(&db_path);
+
     // Process deletes (SQLite only — orphaned ZIP chunks cleaned up by compaction).
     if !request.delete_paths.is_empty() {
-        if let Ok(mut guard) = status.lock() {
-            *guard = find_common::api::WorkerStatus::Processing {
-                source: request.source.clone(),
-                file: format!("(deleting {} files)", n_deletes),
-            };
+        if let Ok(mut lanes) = status.lock() {
+            lanes.update(&request.source, format!("(deleting {} files)", n_deletes));
         }
+        let trash_at = (cfg.trash_days > 0).then(|| chrono::Utc::now().timestamp());
         let delete_delta = timed!(tag, format!("delete {} paths", n_deletes), {
-            db::delete_files_phase1(&conn, &request.delete_paths)?
+            db::delete_files_phase1(&conn, &request.delete_paths, trash_at)?
         });
         delta.files_delta -= delete_delta.files_removed;
         delta.size_delta  -= delete_delta.size_removed;
@@ -290,6 +343,13 @@ fn process_request_phase1(
 
     // Process renames after deletes, before upserts.
     if !request.rename_paths.is_empty() {
+        let mut added = false;
+        for rename in &request.rename_paths {
+            added |= term_filter.add(&tokens.text(&rename.new_path));
+        }
+        if added {
+            db::term_filter::mark_unsaved(&conn)?;
+        }
         timed!(tag, format!("rename {} paths", n_renames), {
             db::rename_files(&conn, &request.rename_paths)?
         });
@@ -299,12 +359,35 @@ fn process_request_phase1(
     let mut successfully_indexed: Vec<String> = Vec::new();
     let mut activity_added: Vec<String> = Vec::new();
     let mut activity_modified: Vec<String> = Vec::new();
+    let mut activity_kinds: HashMap<String, FileKind> = HashMap::new();
 
     // Bug fix: take ownership of the files vec before the loop so each file is
     // consumed by value — no clone of lines for normalization and no clone when
     // pushing to normalized_files.  request.files becomes empty here; all other
     // request fields (source, delete_paths, etc.) remain accessible below.
     let mut files_owned = std::mem::take(&mut request.files);
+
+    // Over its size budget, the source only takes changes to files it
+    // already holds (see `index_budget`).
+    if let Some(&budget) = cfg.index_budgets.get(&request.source) {
+        let used = crate::index_budget::used_bytes(data_dir, &request.source, source_stats_cache);
+        if let Some(error) = crate::index_budget::check(&request.source, used, budget) {
+            let before = files_owned.len();
+            let mut known = conn.prepare("SELECT 1 FROM files WHERE path = ?1")?;
+            files_owned.retain(|file| {
+                let outer = find_common::path::composite_outer(&file.path);
+                if known.exists(rusqlite::params![outer]).unwrap_or(true) {
+                    return true;
+                }
+                server_side_failures.push(IndexingFailure { path: file.path.clone(), error: error.clone() });
+                false
+            });
+            let refused = before - files_owned.len();
+            if refused > 0 {
+                tracing::warn!("{src_tag} refused {refused} new files: {error}");
+            }
+        }
+    }
     let mut normalized_files: Vec<find_common::api::IndexFile> = Vec::with_capacity(files_owned.len());
 
     // Batch normalize: collect (index, path, lines) for all text-like files,
@@ -325,18 +408,64 @@ fn process_request_phase1(
     }
     tracing::debug!("{tag} ← normalize {} files ({:.1}ms)", n_files, norm_start.elapsed().as_secs_f64() * 1000.0);
 
+    // Files sent with `reuse_content` carry no lines: index the content the
+    // store already holds for their hash.  Stored content is already
+    // normalized.
+    files_owned.retain_mut(|file| {
+        if !file.reuse_content {
+            return true;
+        }
+        match pipeline::load_reused_content(file, content_store.as_ref()) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Failed to reuse stored content for {}: {e:#}", file.path);
+                server_side_failures.push(IndexingFailure {
+                    path: file.path.clone(),
+                    error: format!("{e:#}"),
+                });
+                false
+            }
+        }
+    });
+
+    {
+        let mut added = false;
+        for file in &files_owned {
+            // The path covers the filename-only fallback rows.
+            added |= term_filter.add(&tokens.text(&file.path));
+            for line in &file.lines {
+                added |= term_filter.add(&tokens.text(&line.content));
+            }
+        }
+        if added {
+            db::term_filter::mark_unsaved(&conn)?;
+        }
+    }
+
     tracing::debug!("{tag} → index {} files", n_files);
     let index_loop_start = std::time::Instant::now();
-    for file in files_owned {
-        if let Ok(mut guard) = status.lock() {
-            *guard = find_common::api::WorkerStatus::Processing {
-                source: request.source.clone(),
-                file: file.path.clone(),
-            };
+    for (i, mut file) in files_owned.into_iter().enumerate() {
+        // Each file commits on its own, so the WAL can be checkpointed
+        // between files; a large request would otherwise grow it until the
+        // end-of-request checkpoint below.
+        if i > 0 && i % CHECKPOINT_EVERY_FILES == 0 {
+            run_checkpoint(&conn, &tag);
+        }
+        if let Ok(mut lanes) = status.lock() {
+            lanes.update(&request.source, file.path.clone());
+        }
+        if let Ok(mut guard) = current_file.lock() {
+            *guard = Some(file.path.clone());
         }
         let file_start = std::time::Instant::now();
 
-        match pipeline::process_file_phase1(&mut conn, &file, Some(content_store.as_ref())) {
+        let is_delta = file.append_to.is_some();
+        let result = if is_delta {
+            pipeline::process_append_phase1(&mut conn, &mut file, content_store.as_ref())
+        } else {
+            pipeline::process_file_phase1(&mut conn, &file, Some(content_store.as_ref()))
+        };
+        match result {
             Ok(outcome) => {
                 successfully_indexed.push(file.path.clone());
                 if file.mtime != 0 && !is_composite(&file.path) {
@@ -345,6 +474,7 @@ fn process_request_phase1(
                         pipeline::Phase1Outcome::Modified { .. } => activity_modified.push(file.path.clone()),
                         pipeline::Phase1Outcome::Skipped  => {}
                     }
+                    activity_kinds.insert(file.path.clone(), file.kind.clone());
                 }
                 // Accumulate incremental stats delta (composite paths excluded).
                 if !is_composite(&file.path) {
@@ -371,6 +501,17 @@ fn process_request_phase1(
                     }
                 }
             }
+            Err(e) if is_delta => {
+                // Nothing was written and the delta holds only part of the
+                // file, so there is no fallback and nothing to archive; the
+                // client resends the file on its next scan.
+                tracing::warn!("Failed to apply append delta for {}: {e:#}", file.path);
+                server_side_failures.push(IndexingFailure {
+                    path: file.path.clone(),
+                    error: format!("{e:#}"),
+                });
+                continue;
+            }
             Err(e) => {
                 if is_db_locked(&e) {
                     tracing::warn!("Failed to index {} (db locked, will retry): {e:#}", file.path);
@@ -399,6 +540,9 @@ fn process_request_phase1(
         normalized_files.push(file); // moved, not cloned
     }
     tracing::debug!("{tag} ← index {} files ({:.1}ms)", n_files, index_loop_start.elapsed().as_secs_f64() * 1000.0);
+    if let Ok(mut guard) = current_file.lock() {
+        *guard = None;
+    }
 
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -421,6 +565,24 @@ fn process_request_phase1(
         )?
     });
 
+    if request.scan_timestamp.is_some() {
+        match db::prune_scan_history(&conn, now, cfg.history.retention_days, cfg.history.full_resolution_days) {
+            Ok(0) => {}
+            Ok(n) => tracing::debug!("{tag} pruned {n} scan history points"),
+            Err(e) => tracing::warn!("Failed to prune scan history: {e:#}"),
+        }
+    }
+
+    if let Err(e) = term_filter.save(&mut conn) {
+        // Harmless: the filter is rebuilt from the FTS index on next load.
+        tracing::warn!("{tag} failed to save term filter: {e:#}");
+    }
+
+    // Commits rarely reach the automatic checkpoint (see `db::open_writer`).
+    run_checkpoint(&conn, &tag);
+
+    fire_webhooks(&conn, &cfg.webhooks, &request.source, request.scan_timestamp, &all_failures);
+
     // Log activity and broadcast SSE events.
     {
         let deleted: Vec<String> = request.delete_paths.iter()
@@ -436,16 +598,19 @@ fn process_request_phase1(
         } else {
             let source = &request.source;
             for path in &activity_added {
-                let _ = recent_tx.send(RecentFile { source: source.clone(), path: path.clone(), indexed_at: now, action: RecentAction::Added,    new_path: None });
+                let kind = activity_kinds.get(path).cloned();
+                let _ = recent_tx.send(RecentFile { source: source.clone(), path: path.clone(), indexed_at: now, action: RecentAction::Added,    new_path: None, kind });
             }
             for path in &activity_modified {
-                let _ = recent_tx.send(RecentFile { source: source.clone(), path: path.clone(), indexed_at: now, action: RecentAction::Modified, new_path: None });
+                let kind = activity_kinds.get(path).cloned();
+                let _ = recent_tx.send(RecentFile { source: source.clone(), path: path.clone(), indexed_at: now, action: RecentAction::Modified, new_path: None, kind });
             }
             for path in &deleted {
-                let _ = recent_tx.send(RecentFile { source: source.clone(), path: path.clone(), indexed_at: now, action: RecentAction::Deleted,  new_path: None });
+                let _ = recent_tx.send(RecentFile { source: source.clone(), path: path.clone(), indexed_at: now, action: RecentAction::Deleted,  new_path: None, kind: None });
             }
             for (old, new) in &renamed {
-                let _ = recent_tx.send(RecentFile { source: source.clone(), path: old.clone(),  indexed_at: now, action: RecentAction::Renamed,  new_path: Some(new.clone()) });
+                let kind = db::file_kind(&conn, new).ok().flatten();
+                let _ = recent_tx.send(RecentFile { source: source.clone(), path: old.clone(),  indexed_at: now, action: RecentAction::Renamed,  new_path: Some(new.clone()), kind });
             }
         }
     }
@@ -506,6 +671,50 @@ fn process_request_phase1(
     Ok(delta)
 }
 
+/// Passive-checkpoint the source DB's WAL (see `db::checkpoint`), logging
+/// rather than failing: frames left behind are copied by the next one.
+fn run_checkpoint(conn: &rusqlite::Connection, tag: &str) {
+    match timed!(tag, "checkpoint", { db::checkpoint(conn) }) {
+        Ok((frames, copied)) if copied < frames => {
+            tracing::debug!("{tag} checkpoint: {copied}/{frames} WAL frames copied, the rest held by readers");
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("{tag} WAL checkpoint failed: {e:#}"),
+    }
+}
+
+/// Fire `scan_completed` / `error_spike` webhooks for a processed request.
+fn fire_webhooks(
+    conn: &rusqlite::Connection,
+    cfg: &WebhooksConfig,
+    source: &str,
+    scan_timestamp: Option<i64>,
+    failures: &[IndexingFailure],
+) {
+    if cfg.endpoints.is_empty() {
+        return;
+    }
+
+    if let Some(ts) = scan_timestamp {
+        let totals = db::get_stats(conn).ok();
+        webhooks::send(cfg, WebhookEvent::ScanCompleted, serde_json::json!({
+            "source":      source,
+            "scanned_at":  ts,
+            "total_files": totals.as_ref().map(|(files, _, _)| *files),
+            "total_size":  totals.as_ref().map(|(_, size, _)| *size),
+        }));
+    }
+
+    if cfg.error_spike_threshold > 0 && failures.len() >= cfg.error_spike_threshold {
+        let sample: Vec<&str> = failures.iter().take(10).map(|f| f.path.as_str()).collect();
+        webhooks::send(cfg, WebhookEvent::ErrorSpike, serde_json::json!({
+            "source":        source,
+            "failure_count": failures.len(),
+            "sample_paths":  sample,
+        }));
+    }
+}
+
 // ── Tests ──────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -534,7 +743,7 @@ mod tests {
     ) -> Result<crate::stats_cache::SourceStatsDelta> {
         let cs = make_content_store(data_dir);
         let (interrupt_tx, _interrupt_rx) = tokio::sync::oneshot::channel();
-        process_request_phase1(interrupt_tx, data_dir, request_path, to_archive_dir, status, cfg, recent_tx, stats_watch, &cs)
+        process_request_phase1(interrupt_tx, data_dir, request_path, to_archive_dir, status, &std::sync::Mutex::new(None), cfg, recent_tx, stats_watch, &cs, &db::term_filter::TermFilters::default(), &Default::default())
     }
 
     fn make_worker_config() -> WorkerConfig {
@@ -545,6 +754,15 @@ mod tests {
             normalization: find_common::config::NormalizationSettings::default(),
             consecutive_timeout_limit: 0, // disabled in tests
             alerts: find_common::config::AlertsConfig::default(),
+            history: find_common::config::HistoryConfig::default(),
+            webhooks: find_common::config::WebhooksConfig::default(),
+            concurrency: 1,
+            max_attempts: 3,
+            index_budgets: Default::default(),
+            trash_days: 0,
+            tokens: Default::default(),
+            part_max_bytes: 16 * 1024 * 1024,
+            split_over_bytes: 0,
         }
     }
 
@@ -554,7 +772,7 @@ mod tests {
     }
 
     fn make_status() -> StatusHandle {
-        Arc::new(Mutex::new(find_common::api::WorkerStatus::Idle))
+        Arc::new(Mutex::new(crate::worker::WorkerLanes::default()))
     }
 
     fn make_index_file(path: &str, kind: FileKind) -> IndexFile {
@@ -573,6 +791,9 @@ mod tests {
             file_hash: None,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }
     }
 
@@ -640,6 +861,63 @@ mod tests {
         assert_eq!(count, 1, "expected file record in DB");
     }
 
+    #[test]
+    fn large_request_keeps_wal_bounded() {
+        let (_tmp, data_dir, to_archive_dir, inbox_dir) = setup_dirs();
+        let (recent_tx, _rx) = tokio::sync::broadcast::channel::<RecentFile>(16);
+        let db_path = data_dir.join("sources").join("testsource.db");
+        let wal_path = data_dir.join("sources").join("testsource.db-wal");
+        std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+        // An idle connection keeps the WAL file from being removed when the
+        // worker's connection closes.  The file never shrinks, so its size is
+        // the most the WAL ever held.
+        let _keep = crate::db::open(&db_path).unwrap();
+
+        let mut next = 0;
+        let mut index = |n_files: usize| {
+            let files = (next..next + n_files)
+                .map(|i| {
+                    let mut f = make_index_file(&format!("docs/file{i}.txt"), FileKind::Text);
+                    f.lines.extend((0..10).map(|n| IndexLine {
+                        archive_path: None,
+                        line_number: n + LINE_CONTENT_START,
+                        content: format!("line {n} of file {i}: w{i}x{n}"),
+                    }));
+                    f
+                })
+                .collect();
+            next += n_files;
+            let req = BulkRequest {
+                source: "testsource".to_string(),
+                files,
+                delete_paths: vec![],
+                rename_paths: vec![],
+                scan_timestamp: None,
+                indexing_failures: vec![],
+            };
+            let request_path = inbox_dir.join(format!("req{next}.gz"));
+            write_bulk_request_gz(&request_path, &req);
+            call_phase1(
+                &data_dir,
+                &request_path,
+                &to_archive_dir,
+                &make_status(),
+                make_worker_config(),
+                &recent_tx,
+                &make_stats_watch(),
+            )
+            .unwrap();
+            std::fs::metadata(&wal_path).unwrap().len()
+        };
+
+        let one_batch = index(CHECKPOINT_EVERY_FILES);
+        let many_batches = index(CHECKPOINT_EVERY_FILES * 6);
+        assert!(
+            many_batches < one_batch * 2,
+            "WAL grew to {many_batches} bytes over a large request; one batch needs {one_batch}",
+        );
+    }
+
     #[test]
     fn delete_removes_db_record() {
         let (_tmp, data_dir, to_archive_dir, inbox_dir) = setup_dirs();
@@ -946,6 +1224,9 @@ mod tests {
                 file_hash: None,
                 is_new: true,
                 force: false,
+                append_to: None,
+                reuse_content: false,
+                provenance: None,
             }],
             delete_paths: vec![],
             rename_paths: vec![],
@@ -1020,6 +1301,9 @@ mod tests {
                 file_hash: None,
                 is_new: true,
                 force: false,
+                append_to: None,
+                reuse_content: false,
+                provenance: None,
             }],
             delete_paths: vec![],
             rename_paths: vec![],
@@ -1068,18 +1352,3 @@ pub(super) fn is_db_locked(error: &anyhow::Error) -> bool {
     }
     false
 }
-
-pub(super) async fn handle_failure(path: &Path, failed_dir: &Path, error: anyhow::Error) {
-    tracing::error!("Failed to process {}: {}", path.display(), error);
-
-    let failed_path = failed_dir.join(path.file_name().unwrap());
-    if let Err(e) = tokio::fs::rename(path, &failed_path).await {
-        tracing::error!(
-            "Failed to move {} to failed directory: {}",
-            path.display(),
-            e
-        );
-    } else {
-        tracing::warn!("Moved failed request to: {}", failed_path.display());
-    }
-}
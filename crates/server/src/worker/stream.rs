@@ -0,0 +1,491 @@
+/// Bulk protocol v2: streamed NDJSON requests.
+///
+/// `POST /api/v2/bulk` stores its body as `inbox/<id>.ndjson.gz`: a gzip
+/// stream whose first line is a [`BulkRequest`] header (source, deletes,
+/// renames, scan timestamp, failures — `files` normally empty) followed by one
+/// [`IndexFile`] per line.  The payload has no size limit, so rather than
+/// decoding it whole the worker splits it into ordinary `.gz` inbox requests
+/// of bounded size, which then flow through phase 1 and the archive phase
+/// unchanged.  Deletes and renames go with the first part (they are applied
+/// before upserts in v1 too); the scan timestamp and indexing failures go
+/// with the last, so scan completion is recorded after every file is indexed.
+///
+/// With `[worker] split_requests_over_mb`, oversized v1 requests are split
+/// the same way, decoding their `files` array one entry at a time.
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use flate2::write::GzEncoder;
+
+use find_common::api::{BulkRequest, IndexFile};
+
+use super::request::RequestContext;
+use super::quarantine;
+
+/// File-name suffix of a v2 request in the inbox.
+pub(crate) const STREAM_SUFFIX: &str = ".ndjson.gz";
+
+/// Maximum files per split part.
+const PART_MAX_FILES: usize = 1000;
+
+/// Receives each finished part with its 1-based number.
+type Emit<'a> = dyn FnMut(usize, &BulkRequest) -> Result<()> + 'a;
+
+pub(super) fn is_stream_request(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(STREAM_SUFFIX))
+}
+
+/// Whether `path` is a part written by a split (`<stem>_p00001.gz`).
+fn is_part(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.rsplit_once("_p"))
+        .is_some_and(|(_, n)| n.len() == 5 && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Split a v2 request into v1 parts; quarantine it when it cannot be decoded.
+pub(super) async fn split_request(ctx: &RequestContext, part_max_bytes: usize) {
+    let path = ctx.request_path.clone();
+    let result = tokio::task::spawn_blocking(move || split_stream(&path, part_max_bytes))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!("Task error: {e}")));
+    match result {
+        Ok(parts) => tracing::debug!("Split {} into {parts} request(s)", ctx.request_path.display()),
+        Err(e) => quarantine::quarantine(&ctx.request_path, &ctx.failed_dir, &e, None, 1).await,
+    }
+}
+
+/// Split a v1 request into parts when it is larger than `split_over_bytes`
+/// (0 = never).  Returns `false` when the request should be indexed whole:
+/// it is small enough, is itself a part, or could not be split — in which
+/// case indexing it reports the problem the usual way.
+pub(super) async fn split_oversized_request(ctx: &RequestContext, split_over_bytes: u64, part_max_bytes: usize) -> bool {
+    if split_over_bytes == 0 || is_part(&ctx.request_path) {
+        return false;
+    }
+    let size = tokio::fs::metadata(&ctx.request_path).await.map_or(0, |m| m.len());
+    if size <= split_over_bytes {
+        return false;
+    }
+    let path = ctx.request_path.clone();
+    let result = tokio::task::spawn_blocking(move || split_bulk(&path, part_max_bytes))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!("Task error: {e}")));
+    match result {
+        Ok(parts) => {
+            tracing::debug!("Split {} ({size} bytes) into {parts} request(s)", ctx.request_path.display());
+            true
+        }
+        Err(e) => {
+            tracing::debug!("Not splitting {}: {e:#}", ctx.request_path.display());
+            false
+        }
+    }
+}
+
+fn split_stream(path: &Path, part_max_bytes: usize) -> Result<usize> {
+    split(path, STREAM_SUFFIX, |emit| write_stream_parts(path, part_max_bytes, emit))
+}
+
+fn split_bulk(path: &Path, part_max_bytes: usize) -> Result<usize> {
+    split(path, ".gz", |emit| write_bulk_parts(path, part_max_bytes, emit))
+}
+
+/// Write the parts produced by `write_parts` next to `path` and remove it.
+/// Parts are written under temporary names and only renamed into the inbox
+/// once the whole request decoded, so a malformed entry queues nothing.
+/// Returns the number of parts.
+fn split(path: &Path, suffix: &str, write_parts: impl FnOnce(&mut Emit) -> Result<()>) -> Result<usize> {
+    let name = path.file_name().and_then(|n| n.to_str()).context("request path has no filename")?;
+    let stem = name.trim_end_matches(suffix);
+    let dir = path.parent().context("request path has no parent")?;
+    // Parts inherit the request's mtime so they keep its place in the queue.
+    let mtime = std::fs::metadata(path)?.modified()?;
+
+    let mut written: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let result = write_parts(&mut |n, part| {
+        let dest = dir.join(format!("{stem}_p{n:05}.gz"));
+        let tmp = dest.with_extension("gz.tmp");
+        write_part(&tmp, part, mtime)?;
+        written.push((tmp, dest));
+        Ok(())
+    });
+    if let Err(e) = result {
+        for (tmp, _) in &written {
+            let _ = std::fs::remove_file(tmp);
+        }
+        return Err(e);
+    }
+    for (tmp, dest) in &written {
+        std::fs::rename(tmp, dest).with_context(|| format!("renaming {}", tmp.display()))?;
+    }
+    std::fs::remove_file(path).with_context(|| format!("removing {}", path.display()))?;
+    Ok(written.len())
+}
+
+/// Collects files into parts of at most [`PART_MAX_FILES`] files and
+/// `max_bytes` of line content, emitting each part once it is full.
+struct PartWriter<'a, 'e> {
+    emit: &'a mut Emit<'e>,
+    part: BulkRequest,
+    part_bytes: usize,
+    max_bytes: usize,
+    emitted: usize,
+}
+
+impl<'a, 'e> PartWriter<'a, 'e> {
+    /// `first` carries what goes with part 1: source, deletes and renames.
+    fn new(emit: &'a mut Emit<'e>, max_bytes: usize, first: BulkRequest) -> Self {
+        Self { emit, part: first, part_bytes: 0, max_bytes, emitted: 0 }
+    }
+
+    fn push(&mut self, file: IndexFile) -> Result<()> {
+        if self.part.files.len() >= PART_MAX_FILES || self.part_bytes >= self.max_bytes {
+            anyhow::ensure!(!self.part.source.is_empty(), "files precede the source");
+            self.emitted += 1;
+            (self.emit)(self.emitted, &self.part)?;
+            self.part.files.clear();
+            self.part.delete_paths.clear();
+            self.part.rename_paths.clear();
+            self.part_bytes = 0;
+        }
+        self.part_bytes += file.lines.iter().map(|l| l.content.len()).sum::<usize>();
+        self.part.files.push(file);
+        Ok(())
+    }
+
+    /// Emit the last part, which records scan completion.
+    fn finish(mut self, scan_timestamp: Option<i64>, indexing_failures: Vec<find_common::api::IndexingFailure>) -> Result<()> {
+        self.part.scan_timestamp = scan_timestamp;
+        self.part.indexing_failures = indexing_failures;
+        (self.emit)(self.emitted + 1, &self.part)
+    }
+}
+
+fn empty_part(source: String) -> BulkRequest {
+    BulkRequest {
+        source,
+        files: Vec::new(),
+        delete_paths: Vec::new(),
+        scan_timestamp: None,
+        indexing_failures: Vec::new(),
+        rename_paths: Vec::new(),
+    }
+}
+
+/// Decode the v2 stream at `path` and hand each part to `emit` in order.
+fn write_stream_parts(path: &Path, part_max_bytes: usize, emit: &mut Emit) -> Result<()> {
+    let file = std::fs::File::open(path)?;
+    // Multi-member: a client may send the stream as several concatenated
+    // gzip members, e.g. one per chunk.
+    let mut reader = BufReader::new(MultiGzDecoder::new(BufReader::new(file)));
+    let mut line = String::new();
+    let mut line_no = 0usize;
+
+    let header: BulkRequest = loop {
+        line.clear();
+        line_no += 1;
+        if reader.read_line(&mut line).context("reading header line")? == 0 {
+            anyhow::bail!("empty stream: expected a header line");
+        }
+        if !line.trim().is_empty() {
+            break serde_json::from_str(&line).with_context(|| format!("line {line_no}: parsing bulk header"))?;
+        }
+    };
+    let BulkRequest { source, files, delete_paths, scan_timestamp, indexing_failures, rename_paths } = header;
+
+    let first = BulkRequest { delete_paths, rename_paths, ..empty_part(source) };
+    let mut parts = PartWriter::new(emit, part_max_bytes, first);
+    for file in files {
+        parts.push(file)?;
+    }
+    loop {
+        line.clear();
+        line_no += 1;
+        if reader.read_line(&mut line).with_context(|| format!("line {line_no}: read error"))? == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        parts.push(serde_json::from_str(&line).with_context(|| format!("line {line_no}: parsing file entry"))?)?;
+    }
+    parts.finish(scan_timestamp, indexing_failures)
+}
+
+/// Decode the v1 request at `path` one file at a time and hand each part to
+/// `emit`.  Clients send `source` first but deletes and renames after the
+/// files, so when the files span several parts those go in a part 0 of
+/// their own, which the worker still indexes first.
+fn write_bulk_parts(path: &Path, part_max_bytes: usize, emit: &mut Emit) -> Result<()> {
+    use serde::Deserializer as _;
+
+    let file = std::fs::File::open(path)?;
+    let mut de = serde_json::Deserializer::from_reader(GzDecoder::new(BufReader::new(file)));
+    let mut rest = empty_part(String::new());
+    let mut failed = None;
+    let mut parts = PartWriter::new(emit, part_max_bytes, empty_part(String::new()));
+    let decoded = de.deserialize_map(BulkVisitor { parts: &mut parts, rest: &mut rest, failed: &mut failed });
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    decoded.context("parsing bulk request JSON")?;
+    de.end().context("parsing bulk request JSON")?;
+    anyhow::ensure!(!parts.part.source.is_empty(), "bulk request has no source");
+
+    if parts.emitted == 0 {
+        parts.part.delete_paths = std::mem::take(&mut rest.delete_paths);
+        parts.part.rename_paths = std::mem::take(&mut rest.rename_paths);
+    } else if !rest.delete_paths.is_empty() || !rest.rename_paths.is_empty() {
+        let first = BulkRequest {
+            delete_paths: std::mem::take(&mut rest.delete_paths),
+            rename_paths: std::mem::take(&mut rest.rename_paths),
+            ..empty_part(parts.part.source.clone())
+        };
+        (parts.emit)(0, &first)?;
+    }
+    parts.finish(rest.scan_timestamp, std::mem::take(&mut rest.indexing_failures))
+}
+
+/// Visits a v1 request object, passing each entry of `files` to `parts` as
+/// it is decoded and keeping the other fields in `rest`.
+struct BulkVisitor<'v, 'a, 'e> {
+    parts: &'v mut PartWriter<'a, 'e>,
+    rest: &'v mut BulkRequest,
+    /// Why writing a part failed, kept apart from JSON errors.
+    failed: &'v mut Option<anyhow::Error>,
+}
+
+impl<'de> serde::de::Visitor<'de> for BulkVisitor<'_, '_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a bulk request object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "source" => self.parts.part.source = map.next_value()?,
+                "files" => map.next_value_seed(FilesSeed { parts: &mut *self.parts, failed: &mut *self.failed })?,
+                "delete_paths" => self.rest.delete_paths = map.next_value()?,
+                "scan_timestamp" => self.rest.scan_timestamp = map.next_value()?,
+                "indexing_failures" => self.rest.indexing_failures = map.next_value()?,
+                "rename_paths" => self.rest.rename_paths = map.next_value()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct FilesSeed<'v, 'a, 'e> {
+    parts: &'v mut PartWriter<'a, 'e>,
+    failed: &'v mut Option<anyhow::Error>,
+}
+
+impl<'de> serde::de::DeserializeSeed<'de> for FilesSeed<'_, '_, '_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for FilesSeed<'_, '_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a list of files")
+    }
+
+    fn visit_seq<S: serde::de::SeqAccess<'de>>(self, mut seq: S) -> Result<(), S::Error> {
+        use serde::de::Error;
+        while let Some(file) = seq.next_element::<IndexFile>()? {
+            if let Err(e) = self.parts.push(file) {
+                let msg = e.to_string();
+                *self.failed = Some(e);
+                return Err(S::Error::custom(msg));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_part(path: &Path, part: &BulkRequest, mtime: std::time::SystemTime) -> Result<()> {
+    let file = std::fs::File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut encoder = GzEncoder::new(file, flate2::Compression::fast());
+    serde_json::to_writer(&mut encoder, part).context("serializing part")?;
+    encoder.flush()?;
+    encoder.finish()?.set_modified(mtime)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use find_common::api::{FileKind, IndexLine};
+
+    const PART_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+    fn file(path: &str) -> IndexFile {
+        IndexFile {
+            path: path.to_string(),
+            mtime: 1000,
+            size: Some(5),
+            kind: FileKind::Text,
+            lines: vec![IndexLine { archive_path: None, line_number: 1, content: "hello".to_string() }],
+            extract_ms: None,
+            file_hash: None,
+            scanner_version: 0,
+            is_new: false,
+            force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
+        }
+    }
+
+    fn write_stream(path: &Path, lines: &[String]) {
+        let mut enc = GzEncoder::new(std::fs::File::create(path).unwrap(), flate2::Compression::fast());
+        for l in lines {
+            writeln!(enc, "{l}").unwrap();
+        }
+        enc.finish().unwrap();
+    }
+
+    fn read_part(path: &Path) -> BulkRequest {
+        serde_json::from_reader(flate2::read::GzDecoder::new(std::fs::File::open(path).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn splits_into_bounded_parts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("req_1.ndjson.gz");
+        let mut lines = vec![r#"{"source":"docs","delete_paths":["gone.txt"],"scan_timestamp":42}"#.to_string()];
+        for i in 0..(PART_MAX_FILES + 5) {
+            lines.push(serde_json::to_string(&file(&format!("f{i}.txt"))).unwrap());
+        }
+        write_stream(&path, &lines);
+
+        assert_eq!(split_stream(&path, PART_MAX_BYTES).unwrap(), 2);
+        assert!(!path.exists());
+
+        let first = read_part(&dir.path().join("req_1_p00001.gz"));
+        assert_eq!(first.source, "docs");
+        assert_eq!(first.files.len(), PART_MAX_FILES);
+        assert_eq!(first.delete_paths, vec!["gone.txt".to_string()]);
+        assert_eq!(first.scan_timestamp, None);
+
+        let last = read_part(&dir.path().join("req_1_p00002.gz"));
+        assert_eq!(last.files.len(), 5);
+        assert!(last.delete_paths.is_empty());
+        assert_eq!(last.scan_timestamp, Some(42));
+    }
+
+    #[test]
+    fn malformed_line_queues_nothing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("req_2.ndjson.gz");
+        write_stream(&path, &[
+            r#"{"source":"docs"}"#.to_string(),
+            serde_json::to_string(&file("a.txt")).unwrap(),
+            "{not json".to_string(),
+        ]);
+
+        let err = split_stream(&path, PART_MAX_BYTES).unwrap_err();
+        assert!(format!("{err:#}").starts_with("line 3"), "{err:#}");
+        assert!(path.exists());
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers.len(), 1, "{leftovers:?}");
+    }
+
+    #[test]
+    fn header_only_stream_is_one_part() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("req_3.ndjson.gz");
+        write_stream(&path, &[r#"{"source":"docs","scan_timestamp":7}"#.to_string()]);
+        assert_eq!(split_stream(&path, PART_MAX_BYTES).unwrap(), 1);
+        let only = read_part(&dir.path().join("req_3_p00001.gz"));
+        assert!(only.files.is_empty());
+        assert_eq!(only.scan_timestamp, Some(7));
+    }
+
+    fn write_bulk(path: &Path, json: &str) {
+        let mut enc = GzEncoder::new(std::fs::File::create(path).unwrap(), flate2::Compression::fast());
+        enc.write_all(json.as_bytes()).unwrap();
+        enc.finish().unwrap();
+    }
+
+    fn bulk_json(files: usize) -> String {
+        let req = BulkRequest {
+            source: "docs".to_string(),
+            files: (0..files).map(|i| file(&format!("f{i}.txt"))).collect(),
+            delete_paths: vec!["gone.txt".to_string()],
+            scan_timestamp: Some(42),
+            indexing_failures: Vec::new(),
+            rename_paths: Vec::new(),
+        };
+        serde_json::to_string(&req).unwrap()
+    }
+
+    #[test]
+    fn bulk_request_splits_with_deletes_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("req_4.gz");
+        write_bulk(&path, &bulk_json(7));
+
+        // Each file holds 5 bytes of content, so parts close after three files.
+        assert_eq!(split_bulk(&path, 12).unwrap(), 4);
+        assert!(!path.exists());
+
+        let first = read_part(&dir.path().join("req_4_p00000.gz"));
+        assert_eq!(first.source, "docs");
+        assert!(first.files.is_empty());
+        assert_eq!(first.delete_paths, vec!["gone.txt".to_string()]);
+        assert_eq!(first.scan_timestamp, None);
+
+        let sizes: Vec<usize> = (1..=3)
+            .map(|n| read_part(&dir.path().join(format!("req_4_p{n:05}.gz"))).files.len())
+            .collect();
+        assert_eq!(sizes, [3, 3, 1]);
+        let last = read_part(&dir.path().join("req_4_p00003.gz"));
+        assert!(last.delete_paths.is_empty());
+        assert_eq!(last.scan_timestamp, Some(42));
+        assert!(is_part(&dir.path().join("req_4_p00003.gz")));
+        assert!(!is_part(&path));
+    }
+
+    #[test]
+    fn bulk_request_within_one_part_keeps_its_deletes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("req_5.gz");
+        write_bulk(&path, &bulk_json(2));
+        assert_eq!(split_bulk(&path, PART_MAX_BYTES).unwrap(), 1);
+        let only = read_part(&dir.path().join("req_5_p00001.gz"));
+        assert_eq!(only.files.len(), 2);
+        assert_eq!(only.delete_paths, vec!["gone.txt".to_string()]);
+        assert_eq!(only.scan_timestamp, Some(42));
+    }
+
+    #[test]
+    fn bulk_request_without_leading_source_is_left_whole() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("req_6.gz");
+        let files: Vec<String> = (0..4).map(|i| serde_json::to_string(&file(&format!("f{i}.txt"))).unwrap()).collect();
+        write_bulk(&path, &format!(r#"{{"files":[{}],"source":"docs"}}"#, files.join(",")));
+
+        let err = split_bulk(&path, 6).unwrap_err();
+        assert!(format!("{err:#}").contains("precede the source"), "{err:#}");
+        assert!(path.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -2,8 +2,9 @@ mod helpers;
 use helpers::{make_text_bulk, make_text_bulk_hashed, write_fake_gz, TestServer};
 
 use find_common::api::{
-    CompactResponse, InboxDeleteResponse, InboxRetryResponse, InboxShowResponse,
-    InboxStatusResponse, SearchResponse, SourceDeleteResponse, StatsResponse,
+    CompactResponse, FileKind, FileRecord, InboxDeleteResponse, InboxRetryResponse,
+    InboxShowResponse, InboxStatusResponse, MaintainResponse, MaintenanceStatus, Provenance, ReextractResponse,
+    SearchResponse, SourceDeleteResponse, SourceRenameResponse, StatsResponse,
     UpdateApplyResponse,
 };
 
@@ -117,6 +118,95 @@ async fn test_delete_source_other_sources_unaffected() {
     assert!(resp.total >= 1, "deleting one source must not affect others");
 }
 
+// ── rename_source ─────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_rename_source_moves_index() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("old-name", "doc.txt", "peripatetic renamed content")).await;
+    srv.wait_for_idle().await;
+
+    let resp = srv
+        .client
+        .post(srv.url("/api/v1/admin/source/rename"))
+        .json(&serde_json::json!({ "from": "old-name", "to": "new-name" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200, "rename_source should return 200");
+    let body: SourceRenameResponse = resp.json().await.unwrap();
+    assert_eq!(body.files, 1);
+
+    let found: SearchResponse = srv
+        .client
+        .get(srv.url("/api/v1/search?q=peripatetic&source=new-name"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(found.total >= 1, "file should be findable under the new name");
+    assert!(found.results.iter().all(|r| r.source == "new-name"));
+
+    let stats: StatsResponse = srv
+        .client
+        .get(srv.url("/api/v1/stats"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!stats.sources.iter().any(|s| s.name == "old-name"), "old name must not appear in stats");
+}
+
+#[tokio::test]
+async fn test_rename_source_errors() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("a", "a.txt", "alpha")).await;
+    srv.post_bulk(&make_text_bulk("b", "b.txt", "beta")).await;
+    srv.wait_for_idle().await;
+
+    let rename = |from: &str, to: &str| {
+        srv.client
+            .post(srv.url("/api/v1/admin/source/rename"))
+            .json(&serde_json::json!({ "from": from, "to": to }))
+            .send()
+    };
+    assert_eq!(rename("missing", "c").await.unwrap().status().as_u16(), 404);
+    assert_eq!(rename("a", "b").await.unwrap().status().as_u16(), 409, "target must not exist");
+    assert_eq!(rename("a", "../escape").await.unwrap().status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn test_rename_source_refused_while_requests_pending() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("a", "a.txt", "alpha")).await;
+    srv.wait_for_idle().await;
+    pause_and_queue_one(&srv, "a").await;
+
+    let rename = || {
+        srv.client
+            .post(srv.url("/api/v1/admin/source/rename"))
+            .json(&serde_json::json!({ "from": "a", "to": "c" }))
+            .send()
+    };
+    assert_eq!(rename().await.unwrap().status().as_u16(), 409, "a queued request still targets the old name");
+
+    srv.client.post(srv.url("/api/v1/admin/inbox/resume")).send().await.unwrap();
+    srv.wait_for_idle().await;
+    assert_eq!(rename().await.unwrap().status().as_u16(), 200);
+
+    let stats = srv.get_stats_refresh().await;
+    let names: Vec<&str> = stats.sources.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["c"], "the source must not be split");
+    assert_eq!(stats.sources[0].total_files, 2);
+}
+
 // ── compact ───────────────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -168,6 +258,117 @@ async fn test_compact_on_empty_server_returns_200() {
     assert_eq!(status.as_u16(), 200, "compact on empty server should return 200");
 }
 
+// ── maintain ──────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_maintain_reports_each_source() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("one", "a.txt", "first source")).await;
+    srv.post_bulk(&make_text_bulk("two", "b.txt", "second source")).await;
+    srv.wait_for_idle().await;
+
+    let resp: MaintainResponse = srv
+        .client
+        .post(srv.url("/api/v1/admin/maintain"))
+        .json(&serde_json::json!({ "vacuum": true, "optimize_fts": true, "gc_chunks": true }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<&str> = resp.sources.iter().map(|s| s.source.as_str()).collect();
+    assert_eq!(names, ["one", "two"]);
+    assert!(resp.sources.iter().all(|s| s.bytes_after > 0));
+    assert!(resp.compact.is_some_and(|c| !c.dry_run));
+
+    // Still searchable afterwards.
+    let found: SearchResponse = srv
+        .client
+        .get(srv.url("/api/v1/search?q=second+source&source=two"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(found.total >= 1);
+}
+
+#[tokio::test]
+async fn test_maintain_single_source() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("one", "a.txt", "first source")).await;
+    srv.post_bulk(&make_text_bulk("two", "b.txt", "second source")).await;
+    srv.wait_for_idle().await;
+
+    let maintain = |body: serde_json::Value| {
+        srv.client.post(srv.url("/api/v1/admin/maintain")).json(&body).send()
+    };
+    let resp: MaintainResponse = maintain(serde_json::json!({ "source": "two", "vacuum": true }))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.sources.len(), 1);
+    assert_eq!(resp.sources[0].source, "two");
+    assert!(resp.compact.is_none(), "chunks are only collected when asked");
+
+    let status = maintain(serde_json::json!({ "source": "missing" })).await.unwrap().status();
+    assert_eq!(status.as_u16(), 404);
+}
+
+// ── reextract ─────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_reextract_queues_outdated_files() {
+    let srv = TestServer::spawn().await;
+    for (path, version) in [("old.pdf", "0.1.0"), ("new.pdf", "99.0.0")] {
+        let mut req = make_text_bulk("docs", path, "text");
+        req.files[0].kind = FileKind::Pdf;
+        req.files[0].provenance = Some(Provenance {
+            extractor: "find-extract-pdf".into(),
+            version: Some(version.into()),
+            truncated: false,
+        });
+        srv.post_bulk(&req).await;
+    }
+    srv.post_bulk(&make_text_bulk("docs", "notes.txt", "text")).await;
+    srv.wait_for_idle().await;
+
+    let reextract = |body: serde_json::Value| {
+        srv.client.post(srv.url("/api/v1/admin/reextract")).json(&body).send()
+    };
+    let dry: ReextractResponse = reextract(serde_json::json!({ "kind": "pdf", "dry_run": true }))
+        .await.unwrap().json().await.unwrap();
+    assert_eq!(dry.before, env!("CARGO_PKG_VERSION"));
+    assert_eq!(dry.sources.len(), 1);
+    assert_eq!(dry.sources[0].files, 1);
+
+    let resp: ReextractResponse = reextract(serde_json::json!({ "source": "docs", "kind": "pdf" }))
+        .await.unwrap().json().await.unwrap();
+    assert_eq!(resp.sources[0].files, 1);
+
+    let records: Vec<FileRecord> = srv
+        .client
+        .get(srv.url("/api/v1/files"))
+        .query(&[("source", "docs")])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let queued: Vec<&str> = records.iter().filter(|r| r.scanner_version == 0).map(|r| r.path.as_str()).collect();
+    assert_eq!(queued, ["old.pdf"]);
+
+    let status = reextract(serde_json::json!({ "before": "latest" })).await.unwrap().status();
+    assert_eq!(status.as_u16(), 400);
+}
+
 // ── inbox pause / resume ──────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -293,6 +494,61 @@ async fn test_inbox_retry_moves_failed_to_pending() {
     assert!(!after.pending.is_empty(), "item should be in pending after retry");
 }
 
+// ── inbox quarantine ──────────────────────────────────────────────────────
+
+async fn inbox_status(srv: &TestServer) -> InboxStatusResponse {
+    srv.client
+        .get(srv.url("/api/v1/admin/inbox"))
+        .send().await.unwrap().json().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_request_has_error_diagnostics() {
+    let srv = TestServer::spawn().await;
+    write_fake_gz(&srv.data_dir_path().join("inbox/req_poison.gz"));
+    srv.wait_for_idle().await;
+
+    let status = inbox_status(&srv).await;
+    assert_eq!(status.failed.len(), 1);
+    let failure = status.failed[0].failure.as_ref().expect("failure diagnostics");
+    assert_eq!(failure.attempts, 1);
+    assert!(failure.error.contains("parsing bulk request"), "{}", failure.error);
+    assert!(srv.data_dir_path().join("inbox/failed/req_poison.error.json").exists());
+
+    // A manual retry discards the diagnostics along with the failed entry.
+    srv.client.post(srv.url("/api/v1/admin/inbox/pause")).send().await.unwrap();
+    srv.client.post(srv.url("/api/v1/admin/inbox/retry")).send().await.unwrap();
+    assert!(!srv.data_dir_path().join("inbox/failed/req_poison.error.json").exists());
+}
+
+#[tokio::test]
+async fn test_request_quarantined_after_max_attempts() {
+    let srv = TestServer::spawn().await;
+    let name = pause_and_queue_one(&srv, "src").await;
+
+    // Simulate three earlier attempts that never finished (server crashed).
+    let stem = name.trim_end_matches(".gz");
+    std::fs::write(
+        srv.data_dir_path().join(format!("inbox/{stem}.attempts")),
+        r#"{"attempts":3}"#,
+    ).unwrap();
+    srv.client.post(srv.url("/api/v1/admin/inbox/resume")).send().await.unwrap();
+    srv.wait_for_idle().await;
+
+    let status = inbox_status(&srv).await;
+    assert_eq!(status.failed.len(), 1);
+    let failure = status.failed[0].failure.as_ref().expect("failure diagnostics");
+    assert_eq!(failure.attempts, 3);
+    assert_eq!(failure.source.as_deref(), Some("src"));
+    assert!(failure.error.starts_with("gave up after 3 attempts"), "{}", failure.error);
+    assert!(!srv.data_dir_path().join(format!("inbox/{stem}.attempts")).exists());
+
+    let stats: StatsResponse = srv.client
+        .get(srv.url("/api/v1/stats"))
+        .send().await.unwrap().json().await.unwrap();
+    assert!(stats.sources.is_empty(), "quarantined request must not be indexed");
+}
+
 // ── inbox pause stops processing ──────────────────────────────────────────
 
 #[tokio::test]
@@ -320,6 +576,47 @@ async fn test_inbox_pause_stops_processing() {
     assert!(after.pending.is_empty(), "inbox should drain after resume");
 }
 
+// ── maintenance mode refuses ingestion, keeps search ─────────────────────────
+
+#[tokio::test]
+async fn test_maintenance_mode_refuses_bulk_but_serves_search() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("src", "before.txt", "indexed before maintenance")).await;
+    srv.wait_for_idle().await;
+
+    let on: MaintenanceStatus = srv.client
+        .post(srv.url("/api/v1/admin/maintenance"))
+        .json(&serde_json::json!({ "enabled": true, "reason": "backup", "retry_after_secs": 120 }))
+        .send().await.unwrap().json().await.unwrap();
+    assert!(on.enabled);
+    assert_eq!((on.reason.as_deref(), on.retry_after_secs), (Some("backup"), 120));
+    assert!(on.since.is_some());
+
+    let resp = srv.client
+        .post(srv.url("/api/v1/bulk"))
+        .json(&make_text_bulk("src", "during.txt", "refused"))
+        .send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 503);
+    assert_eq!(resp.headers()["retry-after"], "120");
+    assert!(resp.text().await.unwrap().contains("backup"));
+
+    let search: SearchResponse = srv.client
+        .get(srv.url("/api/v1/search?q=maintenance&mode=exact&source=src"))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(search.total, 1, "search keeps working during maintenance");
+    assert!(srv.get_stats().await.maintenance);
+
+    srv.client
+        .post(srv.url("/api/v1/admin/maintenance"))
+        .json(&serde_json::json!({ "enabled": false }))
+        .send().await.unwrap();
+    srv.post_bulk(&make_text_bulk("src", "after.txt", "accepted again")).await;
+    let off: MaintenanceStatus = srv.client
+        .get(srv.url("/api/v1/admin/maintenance"))
+        .send().await.unwrap().json().await.unwrap();
+    assert!(!off.enabled && off.since.is_none());
+}
+
 // ── compact with real content ─────────────────────────────────────────────
 
 #[tokio::test]
@@ -621,6 +918,19 @@ async fn test_inbox_pause_requires_auth() {
     assert_eq!(status.as_u16(), 401);
 }
 
+#[tokio::test]
+async fn test_maintenance_requires_auth() {
+    let srv = TestServer::spawn().await;
+    let status = reqwest::Client::new()
+        .post(srv.url("/api/v1/admin/maintenance"))
+        .json(&serde_json::json!({ "enabled": true }))
+        .send()
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status.as_u16(), 401);
+}
+
 #[tokio::test]
 async fn test_inbox_resume_requires_auth() {
     let srv = TestServer::spawn().await;
@@ -0,0 +1,84 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::{Annotation, ContextResponse, FileResponse};
+use reqwest::StatusCode;
+use serde_json::json;
+
+async fn annotate(srv: &TestServer, line: Option<usize>, body: &str) -> Annotation {
+    let resp = srv.client
+        .post(srv.url("/api/v1/annotations"))
+        .json(&json!({ "source": "docs", "path": "notes.txt", "line_number": line, "body": body, "author": "auditor" }))
+        .send()
+        .await
+        .expect("POST annotations");
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    resp.json().await.expect("annotation json")
+}
+
+async fn seed(srv: &TestServer) {
+    let content: Vec<String> = (1..=30).map(|i| format!("line {i}")).collect();
+    srv.post_bulk(&make_text_bulk("docs", "notes.txt", &content.join("\n"))).await;
+    srv.wait_for_idle().await;
+}
+
+#[tokio::test]
+async fn test_annotations_returned_with_file_and_context() {
+    let srv = TestServer::spawn().await;
+    seed(&srv).await;
+
+    let whole = annotate(&srv, None, "checked for PII").await;
+    let near = annotate(&srv, Some(5), "typo here").await;
+    annotate(&srv, Some(25), "far away").await;
+    assert_eq!(whole.author.as_deref(), Some("auditor"));
+
+    let listed: Vec<Annotation> = srv.client
+        .get(srv.url("/api/v1/annotations?source=docs&path=notes.txt"))
+        .send().await.unwrap().json().await.unwrap();
+    let lines: Vec<Option<usize>> = listed.iter().map(|a| a.line_number).collect();
+    assert_eq!(lines, vec![None, Some(5), Some(25)], "file-level notes first, then by line");
+
+    let file: FileResponse = srv.client
+        .get(srv.url("/api/v1/file?source=docs&path=notes.txt"))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(file.annotations.len(), 3);
+
+    let ctx: ContextResponse = srv.client
+        .get(srv.url("/api/v1/context?source=docs&path=notes.txt&line=5&window=2"))
+        .send().await.unwrap().json().await.unwrap();
+    let ids: Vec<i64> = ctx.annotations.iter().map(|a| a.id).collect();
+    assert_eq!(ids, vec![whole.id, near.id], "only notes inside the window: {ids:?}");
+}
+
+#[tokio::test]
+async fn test_annotation_update_and_delete() {
+    let srv = TestServer::spawn().await;
+    seed(&srv).await;
+    let note = annotate(&srv, Some(3), "first draft").await;
+
+    let updated: Annotation = srv.client
+        .patch(srv.url(&format!("/api/v1/annotations/{}", note.id)))
+        .json(&json!({ "body": "final wording" }))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(updated.body, "final wording");
+    assert_eq!(updated.line_number, Some(3));
+
+    let status = srv.client
+        .delete(srv.url(&format!("/api/v1/annotations/{}", note.id)))
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let status = srv.client
+        .delete(srv.url(&format!("/api/v1/annotations/{}", note.id)))
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_annotation_body_validated() {
+    let srv = TestServer::spawn().await;
+    let status = srv.client
+        .post(srv.url("/api/v1/annotations"))
+        .json(&json!({ "source": "docs", "path": "notes.txt", "body": "   " }))
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,74 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::{AppendBase, ErrorsResponse, FileResponse, IndexLine, SearchResponse, LINE_CONTENT_START};
+
+async fn search_total(srv: &TestServer, q: &str) -> usize {
+    let resp: SearchResponse = srv.client
+        .get(srv.url(&format!("/api/v1/search?q={q}&source=logs")))
+        .send().await.unwrap().json().await.unwrap();
+    resp.total
+}
+
+async fn file_lines(srv: &TestServer) -> Vec<String> {
+    let resp: FileResponse = srv.client
+        .get(srv.url("/api/v1/file?source=logs&path=app.log"))
+        .send().await.unwrap().json().await.unwrap();
+    resp.lines
+}
+
+/// A delta carrying only `new_lines`, extending the indexed version `base`.
+fn append_bulk(base: &str, new_lines: &[&str], mtime: i64) -> find_common::api::BulkRequest {
+    let mut req = make_text_bulk("logs", "app.log", "unused");
+    let file = &mut req.files[0];
+    file.mtime = mtime;
+    file.is_new = false;
+    file.file_hash = Some(format!("{:0>64}", mtime));
+    file.lines = new_lines.iter().enumerate()
+        .map(|(i, l)| IndexLine { archive_path: None, line_number: LINE_CONTENT_START + i, content: l.to_string() })
+        .collect();
+    file.append_to = Some(AppendBase { file_hash: base.to_string() });
+    req
+}
+
+#[tokio::test]
+async fn test_append_delta_extends_indexed_file() {
+    let srv = TestServer::spawn().await;
+    let base = make_text_bulk("logs", "app.log", "started pelican\nready");
+    let base_hash = base.files[0].file_hash.clone().unwrap();
+    srv.post_bulk(&base).await;
+    srv.wait_for_idle().await;
+
+    let delta = append_bulk(&base_hash, &["request cormorant", "stopped"], 1_700_000_100);
+    let delta_hash = delta.files[0].file_hash.clone().unwrap();
+    srv.post_bulk(&delta).await;
+    srv.wait_for_idle().await;
+
+    assert_eq!(file_lines(&srv).await, ["started pelican", "ready", "request cormorant", "stopped"]);
+    assert_eq!(search_total(&srv, "pelican").await, 1);
+    assert_eq!(search_total(&srv, "cormorant").await, 1);
+
+    // The merged version is stored, so it can be extended again.
+    srv.post_bulk(&append_bulk(&delta_hash, &["restarted albatross"], 1_700_000_200)).await;
+    srv.wait_for_idle().await;
+    assert_eq!(file_lines(&srv).await.len(), 5);
+    assert_eq!(search_total(&srv, "albatross").await, 1);
+}
+
+#[tokio::test]
+async fn test_append_delta_on_wrong_base_is_rejected() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("logs", "app.log", "first line")).await;
+    srv.wait_for_idle().await;
+
+    srv.post_bulk(&append_bulk(&"f".repeat(64), &["gannet"], 1_700_000_100)).await;
+    srv.wait_for_idle().await;
+
+    assert_eq!(file_lines(&srv).await, ["first line"], "indexed version untouched");
+    assert_eq!(search_total(&srv, "gannet").await, 0);
+    let errors: ErrorsResponse = srv.client
+        .get(srv.url("/api/v1/errors?source=logs"))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(errors.errors.len(), 1);
+    assert!(errors.errors[0].error.starts_with("append delta"), "{}", errors.errors[0].error);
+}
@@ -0,0 +1,89 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use std::io::Write;
+
+use find_common::api::{BulkSessionInitRequest, BulkSessionResponse, SearchResponse, UploadPatchResponse};
+use flate2::{write::GzEncoder, Compression};
+use reqwest::StatusCode;
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(bytes).unwrap();
+    enc.finish().unwrap()
+}
+
+async fn open_session(srv: &TestServer, size: usize) -> String {
+    let resp = srv.client
+        .post(srv.url("/api/v1/bulk/sessions"))
+        .json(&BulkSessionInitRequest { stream: false, size: Some(size as u64) })
+        .send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    resp.json::<BulkSessionResponse>().await.unwrap().session_id
+}
+
+async fn patch(srv: &TestServer, id: &str, start: usize, data: &[u8], total: usize) -> reqwest::Response {
+    srv.client
+        .patch(srv.url(&format!("/api/v1/bulk/sessions/{id}")))
+        .header("Content-Range", format!("bytes {start}-{}/{total}", start + data.len() - 1))
+        .body(data.to_vec())
+        .send().await.unwrap()
+}
+
+async fn commit(srv: &TestServer, id: &str) -> StatusCode {
+    srv.client
+        .post(srv.url(&format!("/api/v1/bulk/sessions/{id}/commit")))
+        .send().await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_session_resumes_after_gap() {
+    let srv = TestServer::spawn().await;
+    let payload = gzip(&serde_json::to_vec(&make_text_bulk("docs", "big.txt", "narwhal tusk")).unwrap());
+    let half = payload.len() / 2;
+    let id = open_session(&srv, payload.len()).await;
+
+    let resp = patch(&srv, &id, 0, &payload[..half], payload.len()).await;
+    assert_eq!(resp.json::<UploadPatchResponse>().await.unwrap().received, half as u64);
+
+    // A retried chunk that skips ahead is refused with the offset to resume from.
+    let resp = patch(&srv, &id, half + 1, &payload[half + 1..], payload.len()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let status: BulkSessionResponse = srv.client
+        .get(srv.url(&format!("/api/v1/bulk/sessions/{id}")))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(status.received, half as u64);
+
+    // Committing early is refused; nothing is queued.
+    assert_eq!(commit(&srv, &id).await, StatusCode::CONFLICT);
+
+    let resp = patch(&srv, &id, half, &payload[half..], payload.len()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(commit(&srv, &id).await, StatusCode::ACCEPTED);
+    srv.wait_for_idle().await;
+
+    let resp: SearchResponse = srv.client
+        .get(srv.url("/api/v1/search?q=narwhal&source=docs"))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(resp.total, 1);
+    assert_eq!(commit(&srv, &id).await, StatusCode::NOT_FOUND, "a session commits once");
+}
+
+#[tokio::test]
+async fn test_session_rejects_bad_ids_and_payloads() {
+    let srv = TestServer::spawn().await;
+    let status = srv.client
+        .get(srv.url("/api/v1/bulk/sessions/..%2Finbox"))
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let id = open_session(&srv, 4).await;
+    patch(&srv, &id, 0, b"{}{}", 4).await;
+    assert_eq!(commit(&srv, &id).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let status = srv.client
+        .delete(srv.url(&format!("/api/v1/bulk/sessions/{id}")))
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(commit(&srv, &id).await, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,121 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use std::io::Write;
+
+use find_common::api::{InboxStatusResponse, SearchResponse};
+use flate2::{write::GzEncoder, Compression};
+use reqwest::StatusCode;
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(bytes).unwrap();
+    enc.finish().unwrap()
+}
+
+async fn post_v2(srv: &TestServer, body: Vec<u8>) -> StatusCode {
+    srv.client
+        .post(srv.url("/api/v2/bulk"))
+        .header("Content-Encoding", "gzip")
+        .header("Content-Type", "application/x-ndjson")
+        .body(body)
+        .send()
+        .await
+        .expect("POST /api/v2/bulk")
+        .status()
+}
+
+async fn search_total(srv: &TestServer, q: &str) -> usize {
+    let resp: SearchResponse = srv.client
+        .get(srv.url(&format!("/api/v1/search?q={q}&source=docs")))
+        .send().await.unwrap().json().await.unwrap();
+    resp.total
+}
+
+#[tokio::test]
+async fn test_v2_stream_indexes_every_file() {
+    let srv = TestServer::spawn().await;
+
+    // Header and each file line as separate gzip members, as a client
+    // compressing chunk by chunk would send them.
+    let mut body = gzip(br#"{"source":"docs","scan_timestamp":1700000000}"#);
+    body.extend(gzip(b"\n"));
+    for i in 0..3 {
+        let file = make_text_bulk("docs", &format!("f{i}.txt"), "wombat burrow").files.remove(0);
+        body.extend(gzip(format!("{}\n", serde_json::to_string(&file).unwrap()).as_bytes()));
+    }
+    assert_eq!(post_v2(&srv, body).await, StatusCode::ACCEPTED);
+    srv.wait_for_idle().await;
+
+    assert_eq!(search_total(&srv, "wombat").await, 3);
+    let inbox: InboxStatusResponse = srv.client
+        .get(srv.url("/api/v1/admin/inbox"))
+        .send().await.unwrap().json().await.unwrap();
+    assert!(inbox.failed.is_empty());
+}
+
+#[tokio::test]
+async fn test_v2_malformed_stream_quarantined() {
+    let srv = TestServer::spawn().await;
+    let body = gzip(b"{\"source\":\"docs\"}\n{\"path\": 3}\n");
+    assert_eq!(post_v2(&srv, body).await, StatusCode::ACCEPTED);
+    srv.wait_for_idle().await;
+
+    let inbox: InboxStatusResponse = srv.client
+        .get(srv.url("/api/v1/admin/inbox"))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(inbox.failed.len(), 1);
+    let failure = inbox.failed[0].failure.as_ref().expect("diagnostics");
+    assert!(failure.error.starts_with("line 2"), "{}", failure.error);
+    assert_eq!(failure.source.as_deref(), Some("docs"));
+}
+
+#[tokio::test]
+async fn test_v2_requires_gzip() {
+    let srv = TestServer::spawn().await;
+    let status = srv.client
+        .post(srv.url("/api/v2/bulk"))
+        .body("{\"source\":\"docs\"}\n")
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+/// With the low-memory profile, a v1 request over `split_requests_over_mb`
+/// is split into parts that together index every file and apply its deletes.
+#[tokio::test]
+async fn test_low_memory_profile_splits_large_v1_request() {
+    let srv = TestServer::spawn_with_extra_config("profile = \"low-memory\"\n").await;
+    srv.post_bulk(&make_text_bulk("docs", "old.txt", "wombat leftover")).await;
+    srv.wait_for_idle().await;
+
+    // About 3 MB of barely compressible text: over the profile's 1 MB split
+    // threshold and its 2 MB part size.
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_";
+    let mut seed = 1u64;
+    let mut req = make_text_bulk("docs", "old.txt", "");
+    req.files.clear();
+    req.delete_paths = vec!["old.txt".to_string()];
+    for i in 0..60 {
+        let noise: Vec<String> = (0..500)
+            .map(|_| {
+                (0..100)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ALPHABET[(seed >> 58) as usize] as char
+                    })
+                    .collect()
+            })
+            .collect();
+        let content = format!("wombat {i}\n{}", noise.join("\n"));
+        req.files.push(make_text_bulk("docs", &format!("f{i}.txt"), &content).files.remove(0));
+    }
+    srv.post_bulk(&req).await;
+    srv.wait_for_idle().await;
+
+    // Exact mode: fuzzy matching could also hit the noise.
+    assert_eq!(search_total(&srv, "wombat&mode=exact").await, 60);
+    let inbox: InboxStatusResponse = srv.client
+        .get(srv.url("/api/v1/admin/inbox"))
+        .send().await.unwrap().json().await.unwrap();
+    assert!(inbox.failed.is_empty());
+}
@@ -0,0 +1,65 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use std::io::Write;
+
+use find_common::api::BulkValidateResponse;
+use flate2::{write::GzEncoder, Compression};
+use reqwest::StatusCode;
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(bytes).unwrap();
+    enc.finish().unwrap()
+}
+
+async fn validate(srv: &TestServer, json: &[u8]) -> BulkValidateResponse {
+    let resp = srv.client
+        .post(srv.url("/api/v1/bulk/validate"))
+        .header("Content-Encoding", "gzip")
+        .body(gzip(json))
+        .send()
+        .await
+        .expect("POST bulk/validate");
+    assert_eq!(resp.status(), StatusCode::OK);
+    resp.json().await.expect("validate json")
+}
+
+#[tokio::test]
+async fn test_validate_reports_counts_without_indexing() {
+    let srv = TestServer::spawn().await;
+    let mut req = make_text_bulk("docs", "notes.txt", "one\ntwo");
+    req.delete_paths.push("old.txt".to_string());
+
+    let report = validate(&srv, &serde_json::to_vec(&req).unwrap()).await;
+    assert!(report.valid, "{:?}", report.problems);
+    assert_eq!(report.source.as_deref(), Some("docs"));
+    assert_eq!(report.files, 1);
+    assert_eq!(report.lines, 4, "path + metadata + two content lines");
+    assert_eq!(report.delete_paths, 1);
+
+    let stats = srv.get_stats().await;
+    assert_eq!(stats.inbox_pending, 0);
+    assert!(stats.sources.is_empty(), "validate must not create the source");
+}
+
+#[tokio::test]
+async fn test_validate_reports_problems() {
+    let srv = TestServer::spawn().await;
+    let req = make_text_bulk("docs", "../outside.txt", "x");
+    let report = validate(&srv, &serde_json::to_vec(&req).unwrap()).await;
+    assert!(!report.valid);
+    assert_eq!(report.problems.len(), 1);
+    assert_eq!(report.problems[0].path.as_deref(), Some("../outside.txt"));
+
+    let report = validate(&srv, br#"{"files": []}"#).await;
+    assert!(!report.valid);
+    assert!(report.source.is_none());
+    assert!(report.problems[0].message.starts_with("invalid payload"), "{:?}", report.problems);
+
+    let status = srv.client
+        .post(srv.url("/api/v1/bulk/validate"))
+        .body("{}")
+        .send().await.unwrap().status();
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
@@ -0,0 +1,52 @@
+mod helpers;
+use helpers::TestServer;
+
+use find_common::config::RemoteClientConfig;
+
+// ── /api/v1/client-config ─────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_client_config_serves_the_client_config_table() {
+    let srv = TestServer::spawn_with_extra_config(
+        "[client_config.scan]\nexclude = [\"**/*.iso\"]\nmax_content_size_mb = 5\n\n\
+         [client_config.sources.photos]\nextractors = { heic = \"server_only\" }\n",
+    )
+    .await;
+
+    let config: RemoteClientConfig = srv
+        .client
+        .get(srv.url("/api/v1/client-config"))
+        .send()
+        .await
+        .expect("client-config get")
+        .json()
+        .await
+        .expect("client-config json");
+    assert_eq!(config.scan.exclude, ["**/*.iso"]);
+    assert_eq!(config.scan.max_content_size_mb, Some(5));
+    assert_eq!(config.sources["photos"].extractors["heic"], "server_only");
+}
+
+#[tokio::test]
+async fn test_client_config_is_empty_by_default_and_requires_auth() {
+    let srv = TestServer::spawn().await;
+
+    let config: RemoteClientConfig = srv
+        .client
+        .get(srv.url("/api/v1/client-config"))
+        .send()
+        .await
+        .expect("client-config get")
+        .json()
+        .await
+        .expect("client-config json");
+    assert!(config.scan.exclude.is_empty() && config.sources.is_empty());
+
+    let status = reqwest::Client::new()
+        .get(srv.url("/api/v1/client-config"))
+        .send()
+        .await
+        .expect("client-config get")
+        .status();
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,89 @@
+mod helpers;
+use helpers::TestServer;
+
+use find_common::api::{ClientMetrics, Percentiles, ScanMetrics, WatchMetrics};
+
+fn scan_report(host: &str, source: &str, files_indexed: usize) -> ClientMetrics {
+    ClientMetrics {
+        host: host.to_string(),
+        tool: "find-scan".to_string(),
+        version: "0.7.6".to_string(),
+        scan: Some(ScanMetrics {
+            source: source.to_string(),
+            duration_secs: 12.5,
+            files_indexed,
+            extract_ms: Percentiles::of(vec![5, 10, 400]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+async fn post_report(srv: &TestServer, report: &ClientMetrics) -> reqwest::StatusCode {
+    srv.client
+        .post(srv.url("/api/v1/client-metrics"))
+        .json(report)
+        .send()
+        .await
+        .expect("client-metrics post")
+        .status()
+}
+
+async fn get_reports(srv: &TestServer) -> Vec<ClientMetrics> {
+    srv.client
+        .get(srv.url("/api/v1/client-metrics"))
+        .send()
+        .await
+        .expect("client-metrics get")
+        .json()
+        .await
+        .expect("client-metrics json")
+}
+
+// ── /api/v1/client-metrics ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_latest_report_per_host_tool_and_source_is_kept() {
+    let srv = TestServer::spawn().await;
+
+    for report in [
+        scan_report("nas", "docs", 10),
+        scan_report("nas", "photos", 20),
+        scan_report("nas", "docs", 30),
+        ClientMetrics {
+            host: "nas".to_string(),
+            tool: "find-watch".to_string(),
+            watch: Some(WatchMetrics { events_received: 7, ..Default::default() }),
+            ..Default::default()
+        },
+    ] {
+        assert!(post_report(&srv, &report).await.is_success());
+    }
+
+    let reports = get_reports(&srv).await;
+    let summary: Vec<(&str, Option<&str>, Option<usize>)> = reports
+        .iter()
+        .map(|r| (r.tool.as_str(), r.scan.as_ref().map(|s| s.source.as_str()), r.scan.as_ref().map(|s| s.files_indexed)))
+        .collect();
+    assert_eq!(
+        summary,
+        [("find-scan", Some("docs"), Some(30)), ("find-scan", Some("photos"), Some(20)), ("find-watch", None, None)]
+    );
+    assert!(reports.iter().all(|r| r.received_at > 0), "the server stamps each report");
+    assert_eq!(reports[0].scan.as_ref().unwrap().extract_ms.as_ref().unwrap().max, 400);
+    assert_eq!(reports[2].watch.as_ref().unwrap().events_received, 7);
+}
+
+#[tokio::test]
+async fn test_reports_require_auth() {
+    let srv = TestServer::spawn().await;
+    let status = reqwest::Client::new()
+        .post(srv.url("/api/v1/client-metrics"))
+        .json(&scan_report("nas", "docs", 1))
+        .send()
+        .await
+        .expect("client-metrics post")
+        .status();
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+    assert!(get_reports(&srv).await.is_empty());
+}
@@ -0,0 +1,35 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer, TEST_TOKEN};
+
+use reqwest::header;
+
+/// Reports the `Content-Encoding` the server sent.  The client crates enable
+/// reqwest's `gzip`/`brotli` features, which Cargo unifies into this build,
+/// so decompression is switched off or reqwest would strip the header.
+async fn content_encoding(srv: &TestServer, path: &str, accept: Option<&str>) -> Option<String> {
+    let client = reqwest::Client::builder().no_gzip().no_brotli().build().unwrap();
+    let mut req = client.get(srv.url(path)).bearer_auth(TEST_TOKEN);
+    if let Some(accept) = accept {
+        req = req.header(header::ACCEPT_ENCODING, accept);
+    }
+    let resp = req.send().await.unwrap();
+    assert!(resp.status().is_success(), "{path}: {}", resp.status());
+    resp.headers().get(header::CONTENT_ENCODING).map(|v| v.to_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn test_json_responses_are_compressed_when_accepted() {
+    let srv = TestServer::spawn().await;
+    let content: Vec<String> = (0..500).map(|i| format!("line {i} of a long puffin log")).collect();
+    srv.post_bulk(&make_text_bulk("docs", "big.log", &content.join("\n"))).await;
+    srv.wait_for_idle().await;
+
+    let file = "/api/v1/file?source=docs&path=big.log";
+    assert_eq!(content_encoding(&srv, file, None).await, None);
+    assert_eq!(content_encoding(&srv, file, Some("gzip")).await.as_deref(), Some("gzip"));
+    assert_eq!(content_encoding(&srv, file, Some("br, gzip")).await.as_deref(), Some("br"));
+    assert_eq!(
+        content_encoding(&srv, "/api/v1/search?q=puffin&source=docs", Some("gzip")).await.as_deref(),
+        Some("gzip"),
+    );
+}
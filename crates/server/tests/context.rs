@@ -1,7 +1,7 @@
 mod helpers;
 use helpers::{make_text_bulk, TestServer};
 
-use find_common::api::{ContextBatchItem, ContextBatchRequest, ContextBatchResponse, ContextResponse, LINE_CONTENT_START};
+use find_common::api::{ContextBatchItem, ContextBatchRequest, ContextBatchResponse, ContextResponse, FileKind, LINE_CONTENT_START};
 
 // ── GET /api/v1/context ───────────────────────────────────────────────────────
 
@@ -191,3 +191,73 @@ async fn test_context_batch_requires_auth() {
 
     assert_eq!(status.as_u16(), 401, "context-batch without auth should return 401");
 }
+
+// ── Kind-aware context ────────────────────────────────────────────────────────
+
+async fn get_context(srv: &TestServer, path: &str, line: usize, window: usize) -> ContextResponse {
+    srv.client
+        .get(srv.url(&format!("/api/v1/context?source=docs&path={path}&line={line}&window={window}")))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+async fn index_document(srv: &TestServer, path: &str, content: &str) {
+    let mut req = make_text_bulk("docs", path, content);
+    req.files[0].kind = FileKind::Document;
+    srv.post_bulk(&req).await;
+    srv.wait_for_idle().await;
+}
+
+fn contents(resp: &ContextResponse) -> Vec<&str> {
+    resp.lines.iter().map(|l| l.content.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_spreadsheet_context_returns_row_with_sheet_header() {
+    let srv = TestServer::spawn().await;
+    let content = "[XLSX:sheet] Sales\nRegion\tAmount\nNorth\t10\nSouth\t20\n\
+                   [XLSX:sheet] Costs\nItem\tCost\nPower\t80\nRent\t500";
+    index_document(&srv, "book.xlsx", content).await;
+
+    let resp = get_context(&srv, "book.xlsx", LINE_CONTENT_START + 7, 3).await;
+    assert_eq!(contents(&resp), ["[XLSX:sheet] Costs", "Item\tCost", "Rent\t500"]);
+    assert_eq!(resp.match_index, Some(2));
+}
+
+#[tokio::test]
+async fn test_presentation_context_returns_whole_slide() {
+    let srv = TestServer::spawn().await;
+    let content = "[PPTX:slide] 1\nIntro\n[PPTX:slide] 2\nAgenda\nBudget review\nQ&A\n[PPTX:slide] 3\nThanks";
+    index_document(&srv, "deck.pptx", content).await;
+
+    let resp = get_context(&srv, "deck.pptx", LINE_CONTENT_START + 4, 0).await;
+    assert_eq!(contents(&resp), ["[PPTX:slide] 2", "Agenda", "Budget review", "Q&A"]);
+    assert_eq!(resp.match_index, Some(2));
+}
+
+#[tokio::test]
+async fn test_document_context_returns_paragraph_block() {
+    let srv = TestServer::spawn().await;
+    let content = "Para one.\nPara two.\nPara three.\nPara four.\nPara five.";
+    index_document(&srv, "notes.docx", content).await;
+
+    // Short paragraphs fit in the block budget, so neighbours beyond the
+    // requested window are included.
+    let resp = get_context(&srv, "notes.docx", LINE_CONTENT_START + 2, 0).await;
+    assert_eq!(resp.lines.len(), 5);
+    assert_eq!(resp.match_index, Some(2));
+}
+
+#[tokio::test]
+async fn test_spreadsheet_without_markers_falls_back_to_lines() {
+    let srv = TestServer::spawn().await;
+    // Indexed before sheet markers existed.
+    index_document(&srv, "old.xlsx", "a\nb\nc\nd\ne").await;
+
+    let resp = get_context(&srv, "old.xlsx", LINE_CONTENT_START + 2, 1).await;
+    assert_eq!(contents(&resp), ["b", "c", "d"]);
+}
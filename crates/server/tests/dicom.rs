@@ -35,6 +35,9 @@ async fn index_dicom(srv: &TestServer, path: &str) {
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: Some(1_700_000_000),
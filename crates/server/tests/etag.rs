@@ -0,0 +1,61 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use reqwest::{header, StatusCode};
+
+/// GET `path`, returning the status and the `ETag` header.
+async fn get(srv: &TestServer, path: &str, if_none_match: Option<&str>) -> (StatusCode, Option<String>) {
+    let mut req = srv.client.get(srv.url(path));
+    if let Some(etag) = if_none_match {
+        req = req.header(header::IF_NONE_MATCH, etag);
+    }
+    let resp = req.send().await.unwrap();
+    let etag = resp.headers().get(header::ETAG).map(|v| v.to_str().unwrap().to_string());
+    (resp.status(), etag)
+}
+
+#[tokio::test]
+async fn test_read_endpoints_revalidate_until_changed() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("docs", "a.txt", "heron")).await;
+    srv.wait_for_idle().await;
+
+    let paths = [
+        "/api/v1/file?source=docs&path=a.txt",
+        "/api/v1/tree?source=docs",
+        "/api/v1/tree/expand?source=docs&path=a.txt",
+        "/api/v1/sources",
+        "/api/v1/stats",
+    ];
+
+    let mut etags = Vec::new();
+    for path in paths {
+        let (status, etag) = get(&srv, path, None).await;
+        assert_eq!(status, StatusCode::OK, "{path}");
+        let etag = etag.unwrap_or_else(|| panic!("{path}: no ETag"));
+        let (status, _) = get(&srv, path, Some(&etag)).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED, "{path}");
+        let (status, _) = get(&srv, path, Some("\"stale\"")).await;
+        assert_eq!(status, StatusCode::OK, "{path}");
+        etags.push(etag);
+    }
+
+    // Re-indexing the file and adding a second source changes every response.
+    srv.post_bulk(&make_text_bulk("docs", "a.txt", "heron egret")).await;
+    srv.post_bulk(&make_text_bulk("notes", "b.txt", "ibis")).await;
+    srv.wait_for_idle().await;
+
+    for (path, etag) in paths.iter().zip(&etags) {
+        let (status, new_etag) = get(&srv, path, Some(etag)).await;
+        assert_eq!(status, StatusCode::OK, "{path}");
+        assert_ne!(new_etag.as_ref(), Some(etag), "{path}");
+    }
+}
+
+#[tokio::test]
+async fn test_stats_refresh_is_never_304() {
+    let srv = TestServer::spawn().await;
+    let (_, etag) = get(&srv, "/api/v1/stats", None).await;
+    let (status, _) = get(&srv, "/api/v1/stats?refresh=true", Some(&etag.unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+}
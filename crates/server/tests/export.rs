@@ -0,0 +1,76 @@
+mod helpers;
+use helpers::{make_text_bulk, original_hash, TestServer};
+
+use find_common::api::FileKind;
+use reqwest::StatusCode;
+
+/// Build a `.tar.gz` holding `files`.
+fn make_tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(enc);
+    for (name, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *data).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+async fn export(srv: &TestServer, source: &str, path: &str) -> reqwest::Response {
+    srv.client
+        .get(srv.url("/api/v1/export"))
+        .query(&[("source", source), ("path", path)])
+        .send()
+        .await
+        .expect("GET export")
+}
+
+#[tokio::test]
+async fn test_export_member_from_mounted_tar_gz() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("backup.tar.gz"),
+        make_tar_gz(&[("home/notes.txt", b"remember the milk")]),
+    ).unwrap();
+    let root = dir.path().to_str().unwrap().replace('\\', "/");
+    let srv = TestServer::spawn_with_extra_config(&format!("[sources.files]\npath = \"{root}\"\n")).await;
+
+    let resp = export(&srv, "files", "backup.tar.gz::home/notes.txt").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let disposition = resp.headers()["content-disposition"].to_str().unwrap().to_string();
+    assert_eq!(disposition, "attachment; filename=\"notes.txt\"");
+    assert_eq!(resp.text().await.unwrap(), "remember the milk");
+
+    assert_eq!(export(&srv, "files", "backup.tar.gz::home/absent.txt").await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(export(&srv, "files", "backup.tar.gz").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(export(&srv, "files", "../backup.tar.gz::x").await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_export_member_from_stored_original() {
+    let srv = TestServer::spawn_with_extra_config("[originals]\nenabled = true\n").await;
+    let archive = make_tar_gz(&[("a.txt", b"from the originals store")]);
+    let hash = original_hash(&archive);
+    let mut req = make_text_bulk("docs", "archive.tgz", "");
+    req.files[0].kind = FileKind::Archive;
+    req.files[0].file_hash = Some(hash.clone());
+    srv.post_bulk(&req).await;
+    srv.wait_for_idle().await;
+
+    assert_eq!(export(&srv, "docs", "archive.tgz::a.txt").await.status(), StatusCode::NOT_FOUND);
+
+    let status = srv.client
+        .put(srv.url(&format!("/api/v1/originals/{hash}")))
+        .body(archive)
+        .send()
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status, StatusCode::CREATED);
+
+    let resp = export(&srv, "docs", "archive.tgz::a.txt").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text().await.unwrap(), "from the originals store");
+}
@@ -0,0 +1,65 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer, TEST_TOKEN};
+
+use find_common::api::SearchResponse;
+
+async fn spawn_front(peer_url: &str) -> TestServer {
+    TestServer::spawn_with_extra_config(&format!(
+        "[[federation.peers]]\nname = \"nas\"\nurl = \"{peer_url}\"\ntoken = \"{TEST_TOKEN}\"\n",
+    )).await
+}
+
+async fn search(srv: &TestServer, query: &str) -> SearchResponse {
+    srv.client
+        .get(srv.url(&format!("/api/v1/search?q={query}")))
+        .send()
+        .await
+        .expect("search request")
+        .json()
+        .await
+        .expect("search json")
+}
+
+#[tokio::test]
+async fn test_federated_search_merges_peer_hits() {
+    let peer = TestServer::spawn().await;
+    peer.post_bulk(&make_text_bulk("archive", "remote.txt", "shared needle remote")).await;
+    peer.wait_for_idle().await;
+
+    let front = spawn_front(&peer.base_url).await;
+    front.post_bulk(&make_text_bulk("docs", "local.txt", "shared needle local")).await;
+    front.wait_for_idle().await;
+
+    let resp = search(&front, "needle").await;
+    assert_eq!(resp.total, 2);
+    let local = resp.results.iter().find(|r| r.path == "local.txt").expect("local hit");
+    assert_eq!(local.origin, None);
+    let remote = resp.results.iter().find(|r| r.path == "remote.txt").expect("peer hit");
+    assert_eq!(remote.origin.as_deref(), Some("nas"));
+    assert_eq!(remote.source, "archive");
+
+    // federate=false restricts the search to local sources.
+    let local_only: SearchResponse = front
+        .client
+        .get(front.url("/api/v1/search?q=needle&federate=false"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(local_only.total, 1);
+    assert_eq!(local_only.results[0].path, "local.txt");
+}
+
+#[tokio::test]
+async fn test_unreachable_peer_is_skipped() {
+    // Nothing listens on port 9 (discard) on the loopback interface.
+    let front = spawn_front("http://127.0.0.1:9").await;
+    front.post_bulk(&make_text_bulk("docs", "local.txt", "lonely needle")).await;
+    front.wait_for_idle().await;
+
+    let resp = search(&front, "needle").await;
+    assert_eq!(resp.total, 1);
+    assert_eq!(resp.results[0].path, "local.txt");
+}
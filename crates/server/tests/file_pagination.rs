@@ -1,7 +1,7 @@
 mod helpers;
 use helpers::{make_text_bulk, TestServer};
 
-use find_common::api::FileResponse;
+use find_common::api::{FileResponse, Provenance};
 
 /// Build a multi-line file with N numbered content lines and index it.
 /// Content lines are "line 1", "line 2", ..., "line N".
@@ -238,3 +238,30 @@ async fn test_offset_without_limit_returns_all() {
     assert_eq!(resp.lines.len(), 8);
     assert_eq!(resp.total_lines, 8);
 }
+
+// ── Provenance ────────────────────────────────────────────────────────────────
+
+/// The extractor, its version, the extraction time and the truncation flag
+/// sent with a file are returned with it.
+#[tokio::test]
+async fn test_file_response_includes_provenance() {
+    let srv = TestServer::spawn().await;
+    let mut req = make_text_bulk("docs", "report.pdf", "page one");
+    let provenance = Provenance {
+        extractor: "find-extract-pdf".into(),
+        version: Some("1.2.3".into()),
+        truncated: true,
+    };
+    req.files[0].extract_ms = Some(42);
+    req.files[0].provenance = Some(provenance.clone());
+    srv.post_bulk(&req).await;
+    srv.post_bulk(&make_text_bulk("docs", "plain.txt", "no provenance")).await;
+    srv.wait_for_idle().await;
+
+    let resp = get_file(&srv, "docs", "report.pdf").await;
+    assert_eq!(resp.provenance, Some(provenance));
+    assert_eq!(resp.extract_ms, Some(42));
+
+    let resp = get_file(&srv, "docs", "plain.txt").await;
+    assert_eq!(resp.provenance, None);
+}
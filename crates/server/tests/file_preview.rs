@@ -0,0 +1,70 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::{PreviewResponse, LINE_CONTENT_START};
+
+async fn get_preview(srv: &TestServer, query: &str) -> PreviewResponse {
+    srv.client
+        .get(srv.url(&format!("/api/v1/file/preview?{query}")))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+// ── Window ────────────────────────────────────────────────────────────────────
+
+/// `line` + `lines` return a window centred on the line, flagged as truncated.
+#[tokio::test]
+async fn test_preview_window_is_centred_and_bounded() {
+    let srv = TestServer::spawn().await;
+    let content = (1..=50).map(|i| format!("let x{i} = {i};")).collect::<Vec<_>>().join("\n");
+    srv.post_bulk(&make_text_bulk("code", "src/lib.rs", &content)).await;
+    srv.wait_for_idle().await;
+
+    let center = LINE_CONTENT_START + 24; // "let x25 = 25;"
+    let resp = get_preview(&srv, &format!("source=code&path=src/lib.rs&line={center}&lines=10")).await;
+
+    assert_eq!(resp.lines.len(), 10);
+    assert_eq!(resp.start, center - 5);
+    assert!(resp.lines.iter().any(|l| l.line_number == center && l.content == "let x25 = 25;"));
+    assert_eq!(resp.total_lines, 50);
+    assert!(resp.truncated);
+    assert_eq!(resp.language.as_deref(), Some("rust"));
+}
+
+/// Without `line` the window starts at the top; a short file is not truncated.
+#[tokio::test]
+async fn test_preview_whole_short_file() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("code", "notes.txt", "one\ntwo")).await;
+    srv.wait_for_idle().await;
+
+    let resp = get_preview(&srv, "source=code&path=notes.txt").await;
+
+    assert_eq!(resp.start, LINE_CONTENT_START);
+    assert_eq!(resp.lines.len(), 2);
+    assert!(!resp.truncated);
+    assert_eq!(resp.language, None, "plain text has no highlighting language");
+}
+
+// ── Language detection ────────────────────────────────────────────────────────
+
+/// An extensionless script is recognised from its `#!` line, even when the
+/// requested window does not include it.
+#[tokio::test]
+async fn test_preview_detects_language_from_shebang() {
+    let srv = TestServer::spawn().await;
+    let mut content = String::from("#!/usr/bin/env python3\n");
+    content.push_str(&(1..=40).map(|i| format!("print({i})")).collect::<Vec<_>>().join("\n"));
+    srv.post_bulk(&make_text_bulk("code", "bin/deploy", &content)).await;
+    srv.wait_for_idle().await;
+
+    let center = LINE_CONTENT_START + 30;
+    let resp = get_preview(&srv, &format!("source=code&path=bin/deploy&line={center}&lines=5")).await;
+
+    assert!(resp.start > LINE_CONTENT_START, "window should not include the first line");
+    assert_eq!(resp.language.as_deref(), Some("python"));
+}
@@ -2,7 +2,7 @@
 
 use std::time::{Duration, Instant};
 
-use find_common::api::{BulkRequest, FileKind, IndexFile, IndexLine, StatsResponse, SCANNER_VERSION};
+use find_common::api::{BulkRequest, FileKind, IndexFile, IndexLine, StatsResponse, WorkerStatus, SCANNER_VERSION};
 use find_common::config::parse_server_config;
 use find_server::{build_router, create_app_state};
 use flate2::{write::GzEncoder, Compression};
@@ -73,7 +73,8 @@ impl TestServer {
         format!("{}{path}", self.base_url)
     }
 
-    /// Poll GET /api/v1/stats until both inbox_pending and archive_queue are 0.
+    /// Poll GET /api/v1/stats until both inbox_pending and archive_queue are 0
+    /// and the worker reports Idle.
     /// Requires two consecutive idle readings 100 ms apart to guard against the
     /// transient window between the worker finishing one file and picking up the
     /// next (during which counts are briefly 0 even though more work is pending).
@@ -92,7 +93,8 @@ impl TestServer {
                 .await
                 .expect("stats json");
 
-            if resp.inbox_pending == 0 && resp.archive_queue == 0 {
+            let worker_idle = resp.worker_status == WorkerStatus::Idle;
+            if resp.inbox_pending == 0 && resp.archive_queue == 0 && worker_idle {
                 consecutive_idle += 1;
                 if consecutive_idle >= 2 {
                     return;
@@ -171,6 +173,15 @@ pub fn make_text_bulk_hashed(source: &str, path: &str, content: &str) -> BulkReq
     req
 }
 
+/// The `file_hash` the client computes for a file holding `bytes` — the hash
+/// an upload to `PUT /api/v1/originals/{hash}` must match.
+pub fn original_hash(bytes: &[u8]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(bytes);
+    hasher.update(&SCANNER_VERSION.to_le_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
 /// Write a minimal valid gzip file to `path` (used to seed the failed dir in tests).
 pub fn write_fake_gz(path: &std::path::Path) {
     let file = std::fs::File::create(path).unwrap();
@@ -218,6 +229,9 @@ pub fn make_text_bulk(source: &str, path: &str, content: &str) -> BulkRequest {
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: Some(1_700_000_000),
@@ -0,0 +1,97 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::ScanHistoryResponse;
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+async fn get_history(srv: &TestServer, query: &str) -> ScanHistoryResponse {
+    srv.client
+        .get(srv.url(&format!("/api/v1/history?{query}")))
+        .send()
+        .await
+        .expect("history request")
+        .json()
+        .await
+        .expect("history json")
+}
+
+/// Post a single-file scan completion with the given `scan_timestamp`.
+async fn post_scan(srv: &TestServer, source: &str, path: &str, scanned_at: i64) {
+    let mut req = make_text_bulk(source, path, "hello");
+    req.scan_timestamp = Some(scanned_at);
+    srv.post_bulk(&req).await;
+}
+
+// ── GET /api/v1/history ───────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_history_returns_points_within_window() {
+    let srv = TestServer::spawn().await;
+    let now = now_secs();
+
+    post_scan(&srv, "src", "a.txt", now - 40 * 86_400).await;
+    post_scan(&srv, "src", "b.txt", now - 2 * 86_400).await;
+    post_scan(&srv, "src", "c.txt", now - 3600).await;
+    srv.wait_for_idle().await;
+
+    let resp = get_history(&srv, "source=src&days=7").await;
+    assert_eq!(resp.source, "src");
+    let files: Vec<usize> = resp.points.iter().map(|p| p.total_files).collect();
+    assert_eq!(files, vec![2, 3], "only points within the last 7 days: {files:?}");
+
+    let all = get_history(&srv, "source=src&days=0").await;
+    assert_eq!(all.points.len(), 3);
+}
+
+#[tokio::test]
+async fn test_history_downsamples_to_requested_points() {
+    let srv = TestServer::spawn().await;
+    let now = now_secs();
+
+    let newest = now - 3600;
+    for i in 0..6 {
+        post_scan(&srv, "src", &format!("f{i}.txt"), now - (6 - i) * 3600).await;
+    }
+    srv.wait_for_idle().await;
+
+    let resp = get_history(&srv, "source=src&points=2").await;
+    assert!(resp.points.len() <= 2, "got {} points", resp.points.len());
+    // Requests may be processed out of order, so check the timestamp rather
+    // than the file count captured at that point.
+    assert_eq!(resp.points.last().unwrap().scanned_at, newest, "newest point retained");
+}
+
+#[tokio::test]
+async fn test_history_retention_prunes_old_points() {
+    let srv = TestServer::spawn_with_extra_config(
+        "[history]\nretention_days = 10\nfull_resolution_days = 5\n",
+    ).await;
+    let now = now_secs();
+
+    post_scan(&srv, "src", "a.txt", now - 30 * 86_400).await;
+    post_scan(&srv, "src", "b.txt", now - 3600).await;
+    srv.wait_for_idle().await;
+
+    let resp = get_history(&srv, "source=src&days=0").await;
+    let files: Vec<usize> = resp.points.iter().map(|p| p.total_files).collect();
+    assert_eq!(files, vec![2], "point older than retention_days should be pruned");
+}
+
+#[tokio::test]
+async fn test_history_invalid_source_rejected() {
+    let srv = TestServer::spawn().await;
+    let status = srv
+        .client
+        .get(srv.url("/api/v1/history?source=../etc"))
+        .send()
+        .await
+        .expect("history request")
+        .status();
+    assert_eq!(status.as_u16(), 400);
+}
@@ -2,9 +2,9 @@ mod helpers;
 use helpers::{make_text_bulk, TestServer};
 
 use find_common::api::{
-    BulkRequest, DirEntry, FileKind, FileResponse, IndexFile, IndexLine, SearchResponse,
-    SourceInfo, StatsResponse, TreeResponse, SCANNER_VERSION, LINE_PATH, LINE_METADATA,
-    LINE_CONTENT_START,
+    parse_stale_sources, BulkRequest, DirEntry, FileKind, FileResponse, IndexFile, IndexLine,
+    SearchResponse, SourceInfo, StatsResponse, TreeResponse, SCANNER_VERSION, LINE_PATH,
+    LINE_METADATA, LINE_CONTENT_START, STALE_SOURCES_HEADER,
 };
 
 #[tokio::test]
@@ -48,6 +48,33 @@ async fn test_search_without_source_searches_all() {
     assert!(resp.total >= 1, "expected zymurgy to be found without source filter");
 }
 
+#[tokio::test]
+async fn test_search_reports_index_freshness() {
+    let srv = TestServer::spawn().await;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    // make_text_bulk records a scan from 2023, well past stale_scan_days.
+    srv.post_bulk(&make_text_bulk("old", "a.txt", "quokka sighting")).await;
+    let mut fresh = make_text_bulk("fresh", "b.txt", "quokka again");
+    fresh.scan_timestamp = Some(now);
+    srv.post_bulk(&fresh).await;
+    srv.wait_for_idle().await;
+
+    let http = srv.client.get(srv.url("/api/v1/search?q=quokka")).send().await.unwrap();
+    let stale = parse_stale_sources(http.headers()[STALE_SOURCES_HEADER].to_str().unwrap());
+    assert_eq!(stale, vec![("old".to_string(), 1_700_000_000)]);
+
+    let resp: SearchResponse = http.json().await.unwrap();
+    assert_eq!(resp.results.len(), 2);
+    for r in &resp.results {
+        let expected = if r.source == "old" { 1_700_000_000 } else { now };
+        assert_eq!(r.source_last_scan, Some(expected), "{}", r.source);
+        assert!(r.indexed_at.is_some_and(|t| t >= now - 60), "{:?}", r.indexed_at);
+    }
+}
+
 #[tokio::test]
 async fn test_context_retrieval() {
     let srv = TestServer::spawn().await;
@@ -221,6 +248,9 @@ fn make_archive_member_bulk(source: &str, archive_path: &str, member_name: &str,
         scanner_version: SCANNER_VERSION,
         is_new: true,
         force: false,
+        append_to: None,
+        reuse_content: false,
+        provenance: None,
     };
 
     let member = IndexFile {
@@ -238,6 +268,9 @@ fn make_archive_member_bulk(source: &str, archive_path: &str, member_name: &str,
         scanner_version: SCANNER_VERSION,
         is_new: true,
         force: false,
+        append_to: None,
+        reuse_content: false,
+        provenance: None,
     };
 
     BulkRequest {
@@ -0,0 +1,55 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::ErrorsResponse;
+
+async fn get_errors(srv: &TestServer, source: &str) -> ErrorsResponse {
+    srv.client
+        .get(srv.url(&format!("/api/v1/errors?source={source}")))
+        .send()
+        .await
+        .expect("errors request")
+        .json()
+        .await
+        .expect("errors json")
+}
+
+// ── [sources.<name>] max_index_mb ─────────────────────────────────────────────
+
+/// A source over its budget records new files as indexing errors instead of
+/// indexing them.
+#[tokio::test]
+async fn test_new_files_refused_over_budget() {
+    let srv = TestServer::spawn_with_extra_config("\n[sources.full]\nmax_index_mb = 0\n").await;
+
+    srv.post_bulk(&make_text_bulk("full", "new.txt", "refused content")).await;
+    srv.wait_for_idle().await;
+
+    let errors = get_errors(&srv, "full").await;
+    assert_eq!(errors.total, 1);
+    assert_eq!(errors.errors[0].path, "new.txt");
+    assert!(errors.errors[0].error.contains("index size budget exceeded"), "{}", errors.errors[0].error);
+
+    let stats = srv.get_stats_refresh().await;
+    let full = stats.sources.iter().find(|s| s.name == "full").expect("source not found");
+    assert_eq!(full.total_files, 0);
+    assert_eq!(full.index_budget_bytes, Some(0));
+}
+
+/// Under its budget a source indexes normally and reports its index space.
+#[tokio::test]
+async fn test_stats_report_index_space_and_budget() {
+    let srv = TestServer::spawn_with_extra_config("\n[sources.docs]\nmax_index_mb = 100\n").await;
+
+    srv.post_bulk(&make_text_bulk("docs", "notes.txt", "quarterly earnings")).await;
+    srv.wait_for_idle().await;
+
+    assert_eq!(get_errors(&srv, "docs").await.total, 0);
+    let stats = srv.get_stats_refresh().await;
+    let docs = stats.sources.iter().find(|s| s.name == "docs").expect("source not found");
+    assert_eq!(docs.total_files, 1);
+    assert_eq!(docs.index_budget_bytes, Some(100 * 1024 * 1024));
+    assert!(docs.db_size_bytes > 0);
+    assert!(docs.content_bytes > 0);
+    assert_eq!(docs.space_by_kind.iter().map(|s| s.files).sum::<usize>(), 1);
+}
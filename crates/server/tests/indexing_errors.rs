@@ -53,6 +53,9 @@ fn completion_upsert_bulk(source: &str, path: &str, mtime: i64) -> BulkRequest {
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: None,
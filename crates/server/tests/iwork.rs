@@ -36,6 +36,9 @@ async fn force_flag_bypasses_stale_mtime_guard() {
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: None,
@@ -67,6 +70,9 @@ async fn force_flag_bypasses_stale_mtime_guard() {
             scanner_version: SCANNER_VERSION,
             is_new: false,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: None,
@@ -98,6 +104,9 @@ async fn force_flag_bypasses_stale_mtime_guard() {
             scanner_version: SCANNER_VERSION,
             is_new: false,
             force: true,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: None,
@@ -162,6 +171,9 @@ async fn iwork_preview_served_by_view_endpoint() {
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: Some(1_700_000_000),
@@ -0,0 +1,94 @@
+mod helpers;
+use helpers::{make_text_bulk, original_hash, TestServer};
+
+use reqwest::StatusCode;
+
+const ENABLED: &str = "[originals]\nenabled = true\n";
+
+async fn put_original(srv: &TestServer, hash: &str, body: &'static [u8]) -> StatusCode {
+    srv.client
+        .put(srv.url(&format!("/api/v1/originals/{hash}")))
+        .body(body)
+        .send()
+        .await
+        .expect("PUT originals")
+        .status()
+}
+
+async fn head_original(srv: &TestServer, hash: &str) -> StatusCode {
+    srv.client
+        .head(srv.url(&format!("/api/v1/originals/{hash}")))
+        .send()
+        .await
+        .expect("HEAD originals")
+        .status()
+}
+
+#[tokio::test]
+async fn test_originals_disabled_by_default() {
+    let srv = TestServer::spawn().await;
+    let hash = "a".repeat(64);
+    assert_eq!(head_original(&srv, &hash).await, StatusCode::FORBIDDEN);
+    assert_eq!(put_original(&srv, &hash, b"bytes").await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_put_and_head_original() {
+    let srv = TestServer::spawn_with_extra_config(ENABLED).await;
+    let hash = original_hash(b"original bytes");
+
+    assert_eq!(head_original(&srv, &hash).await, StatusCode::NOT_FOUND);
+    assert_eq!(put_original(&srv, &hash, b"original bytes").await, StatusCode::CREATED);
+    assert_eq!(head_original(&srv, &hash).await, StatusCode::OK);
+    // A second upload of the same hash is a no-op.
+    assert_eq!(put_original(&srv, &hash, b"original bytes").await, StatusCode::OK);
+
+    assert_eq!(put_original(&srv, "not-a-hash", b"x").await, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_put_original_rejects_hash_mismatch() {
+    let srv = TestServer::spawn_with_extra_config(ENABLED).await;
+    let hash = original_hash(b"genuine bytes");
+
+    assert_eq!(put_original(&srv, &hash, b"forged bytes").await, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(head_original(&srv, &hash).await, StatusCode::NOT_FOUND);
+
+    // Once the genuine bytes are stored, a forged re-upload leaves them alone.
+    assert_eq!(put_original(&srv, &hash, b"genuine bytes").await, StatusCode::CREATED);
+    assert_eq!(put_original(&srv, &hash, b"forged bytes").await, StatusCode::OK);
+    let shard = srv.data_dir_path().join("originals").join(&hash[..2]);
+    assert_eq!(std::fs::read(shard.join(&hash)).unwrap(), b"genuine bytes");
+    let leftovers = std::fs::read_dir(&shard).unwrap().count();
+    assert_eq!(leftovers, 1, "rejected upload must not leave a .part file");
+}
+
+#[tokio::test]
+async fn test_put_original_too_large() {
+    let srv = TestServer::spawn_with_extra_config(
+        "[originals]\nenabled = true\nmax_file_size_mb = 0\n",
+    ).await;
+    let hash = "c".repeat(64);
+    assert_eq!(put_original(&srv, &hash, b"too big").await, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(head_original(&srv, &hash).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_raw_served_from_originals_without_source_path() {
+    let srv = TestServer::spawn_with_extra_config(ENABLED).await;
+    let mut req = make_text_bulk("docs", "reports/q1.txt", "quarterly numbers");
+    let hash = original_hash(b"quarterly numbers\n");
+    req.files[0].file_hash = Some(hash.clone());
+    srv.post_bulk(&req).await;
+    srv.wait_for_idle().await;
+
+    let raw_url = srv.url("/api/v1/raw?source=docs&path=reports/q1.txt");
+    assert_eq!(srv.client.get(&raw_url).send().await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    assert_eq!(put_original(&srv, &hash, b"quarterly numbers\n").await, StatusCode::CREATED);
+    let resp = srv.client.get(&raw_url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let disposition = resp.headers()["content-disposition"].to_str().unwrap().to_string();
+    assert!(disposition.contains("filename=\"q1.txt\""), "got {disposition}");
+    assert_eq!(resp.text().await.unwrap(), "quarterly numbers\n");
+}
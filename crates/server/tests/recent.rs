@@ -26,6 +26,9 @@ fn make_bulk_with_mtime(source: &str, path: &str, content: &str, mtime: i64) ->
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: Some(mtime),
@@ -34,6 +37,19 @@ fn make_bulk_with_mtime(source: &str, path: &str, content: &str, mtime: i64) ->
     }
 }
 
+async fn recent_paths(srv: &TestServer, query: &str) -> Vec<String> {
+    let resp: RecentResponse = srv
+        .client
+        .get(srv.url(&format!("/api/v1/recent?{query}")))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    resp.files.into_iter().map(|f| format!("{}:{}", f.source, f.path)).collect()
+}
+
 // ── GET /api/v1/recent ────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -109,6 +125,41 @@ async fn test_recent_limit_respected() {
     assert_eq!(resp.files.len(), 3, "limit=3 should return exactly 3 results");
 }
 
+#[tokio::test]
+async fn test_recent_filters_by_source_since_and_kind() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_bulk_with_mtime("src-a", "old.txt", "content", 1000)).await;
+    srv.post_bulk(&make_bulk_with_mtime("src-a", "new.txt", "content", 9000)).await;
+    let mut code = make_bulk_with_mtime("src-a", "main.rs", "fn main() {}", 9500);
+    code.files[0].kind = FileKind::Code;
+    srv.post_bulk(&code).await;
+    srv.post_bulk(&make_bulk_with_mtime("src-b", "other.txt", "content", 9000)).await;
+    srv.wait_for_idle().await;
+
+    assert_eq!(
+        recent_paths(&srv, "sort=mtime&source=src-a&since=5000").await,
+        ["src-a:main.rs", "src-a:new.txt"],
+    );
+    assert_eq!(recent_paths(&srv, "sort=mtime&kind=code").await, ["src-a:main.rs"]);
+    assert_eq!(recent_paths(&srv, "source=src-b").await, ["src-b:other.txt"]);
+
+    let resp: RecentResponse = srv
+        .client
+        .get(srv.url("/api/v1/recent?kind=code"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp.files.len(), 1);
+    assert_eq!(resp.files[0].kind, Some(FileKind::Code));
+
+    let status = srv.client.get(srv.url("/api/v1/recent?since=yesterday")).send().await.unwrap().status();
+    assert_eq!(status.as_u16(), 400);
+}
+
 #[tokio::test]
 async fn test_recent_limit_above_max_returns_400() {
     let srv = TestServer::spawn().await;
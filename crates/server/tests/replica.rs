@@ -0,0 +1,116 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer, TEST_TOKEN};
+
+use std::time::{Duration, Instant};
+
+use find_common::api::{ClientMetrics, ContextResponse, ReplicaManifest, SearchResponse, LINE_CONTENT_START};
+
+async fn spawn_replica(primary: &TestServer) -> TestServer {
+    TestServer::spawn_with_extra_config(&format!(
+        "[replica]\nprimary_url = \"{}\"\nprimary_token = \"{TEST_TOKEN}\"\nsync_interval_secs = 1\n",
+        primary.base_url,
+    )).await
+}
+
+/// Poll the replica until `query` returns at least one hit in `source`.
+/// Panics after 30 seconds.
+async fn wait_for_replica_hit(replica: &TestServer, query: &str, source: &str) -> SearchResponse {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        let resp = replica
+            .client
+            .get(replica.url(&format!("/api/v1/search?q={query}&source={source}")))
+            .send()
+            .await
+            .expect("search request");
+        if resp.status().is_success() {
+            let body: SearchResponse = resp.json().await.expect("search json");
+            if body.total >= 1 {
+                return body;
+            }
+        }
+        if Instant::now() >= deadline {
+            panic!("'{query}' not searchable on replica after 30s");
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+// ── Primary endpoints ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_manifest_lists_sources() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("docs", "a.txt", "hello")).await;
+    srv.wait_for_idle().await;
+
+    let m: ReplicaManifest = srv
+        .client
+        .get(srv.url("/api/v1/replica/manifest"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(m.sources.len(), 1);
+    assert_eq!(m.sources[0].name, "docs");
+    assert!(m.sources[0].size > 0);
+}
+
+// ── Replica sync ──────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_replica_serves_primary_content() {
+    let primary = TestServer::spawn().await;
+    primary.post_bulk(&make_text_bulk("docs", "notes.txt", "replicated needle\nsecond line")).await;
+    primary.wait_for_idle().await;
+
+    let replica = spawn_replica(&primary).await;
+    wait_for_replica_hit(&replica, "needle", "docs").await;
+
+    // Content blobs were pulled too, so context works on the replica.
+    let ctx: ContextResponse = replica
+        .client
+        .get(replica.url(&format!(
+            "/api/v1/context?source=docs&path=notes.txt&line={LINE_CONTENT_START}&window=1"
+        )))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(
+        ctx.lines.iter().any(|l| l.content.contains("replicated needle")),
+        "context should be served from replicated blobs: {:?}", ctx.lines
+    );
+}
+
+#[tokio::test]
+async fn test_replica_rejects_writes() {
+    let primary = TestServer::spawn().await;
+    let replica = spawn_replica(&primary).await;
+
+    let status = replica.post_bulk_status(&make_text_bulk("docs", "a.txt", "x")).await;
+    assert_eq!(status.as_u16(), 403);
+
+    let status = replica
+        .client
+        .post(replica.url("/api/v1/admin/inbox/pause"))
+        .send()
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status.as_u16(), 403);
+
+    let status = replica
+        .client
+        .post(replica.url("/api/v1/client-metrics"))
+        .json(&ClientMetrics { host: "laptop".into(), tool: "find-scan".into(), ..Default::default() })
+        .send()
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status.as_u16(), 403);
+}
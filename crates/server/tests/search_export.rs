@@ -0,0 +1,82 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::{SearchExportRow, LINE_CONTENT_START};
+
+async fn export(srv: &TestServer, query: &str) -> reqwest::Response {
+    srv.client
+        .get(srv.url(&format!("/api/v1/search/export?{query}")))
+        .send()
+        .await
+        .unwrap()
+}
+
+// ── Completeness ──────────────────────────────────────────────────────────────
+
+/// Every matching line is exported, across several read batches, regardless
+/// of `limit`.
+#[tokio::test]
+async fn test_export_includes_every_match() {
+    let srv = TestServer::spawn().await;
+    let content = (0..2500)
+        .map(|i| if i % 2 == 0 { format!("needle {i}") } else { format!("hay {i}") })
+        .collect::<Vec<_>>()
+        .join("\n");
+    srv.post_bulk(&make_text_bulk("logs", "big.log", &content)).await;
+    srv.post_bulk(&make_text_bulk("logs", "small.log", "one needle\nno match")).await;
+    srv.wait_for_idle().await;
+
+    let resp = export(&srv, "q=needle&mode=exact&source=logs&limit=5&format=ndjson").await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "application/x-ndjson");
+    let body = resp.text().await.unwrap();
+    let rows: Vec<SearchExportRow> = body.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+
+    assert_eq!(rows.len(), 1251);
+    assert_eq!(rows.iter().filter(|r| r.path == "big.log").count(), 1250);
+    assert!(rows.iter().all(|r| r.content.contains("needle")));
+    let small = rows.iter().find(|r| r.path == "small.log").unwrap();
+    assert_eq!((small.source.as_str(), small.line_number), ("logs", LINE_CONTENT_START));
+}
+
+/// Regex export applies the pattern itself, not just the index pre-filter.
+#[tokio::test]
+async fn test_export_regex_filters_lines() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("src", "conf.yaml", "port: 80\nport: none\nport: 8080")).await;
+    srv.wait_for_idle().await;
+
+    let body = export(&srv, "q=port:%20%5Cd%2B&mode=regex&source=src").await.text().await.unwrap();
+    let rows: Vec<SearchExportRow> = body.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let contents: Vec<&str> = rows.iter().map(|r| r.content.as_str()).collect();
+    assert_eq!(contents, ["port: 80", "port: 8080"]);
+}
+
+// ── Formats ───────────────────────────────────────────────────────────────────
+
+/// CSV starts with a header row and quotes fields holding commas or quotes.
+#[tokio::test]
+async fn test_export_csv_quotes_fields() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("docs", "notes.txt", "say \"hi\", needle\nplain needle")).await;
+    srv.wait_for_idle().await;
+
+    let resp = export(&srv, "q=needle&mode=exact&source=docs&format=csv").await;
+    assert!(resp.headers()["content-type"].to_str().unwrap().starts_with("text/csv"));
+    let body = resp.text().await.unwrap();
+    let lines: Vec<&str> = body.lines().collect();
+
+    assert_eq!(lines[0], "source,path,archive_path,line_number,kind,mtime,size,content");
+    assert_eq!(lines.len(), 3, "{body}");
+    assert!(lines[1].ends_with(",\"say \"\"hi\"\", needle\""), "{}", lines[1]);
+    assert!(lines[2].starts_with("docs,notes.txt,,"), "{}", lines[2]);
+    assert!(lines[2].ends_with(",plain needle"), "{}", lines[2]);
+}
+
+/// The file and document modes have no per-line rows to export.
+#[tokio::test]
+async fn test_export_rejects_unsupported_mode() {
+    let srv = TestServer::spawn().await;
+    let resp = export(&srv, "q=needle&mode=document").await;
+    assert_eq!(resp.status(), 400);
+}
@@ -2,7 +2,7 @@ mod helpers;
 use helpers::{make_text_bulk, make_text_bulk_hashed, TestServer};
 
 use find_common::api::{
-    BulkRequest, FileKind, IndexFile, IndexLine, SearchResponse, SCANNER_VERSION,
+    BulkRequest, FileKind, IndexFile, IndexLine, SearchResponse, SearchSummaryResponse, SCANNER_VERSION,
     LINE_CONTENT_START, LINE_METADATA, LINE_PATH,
 };
 
@@ -27,6 +27,9 @@ fn make_bulk_with_kind(source: &str, path: &str, content: &str, kind: FileKind)
             scanner_version: SCANNER_VERSION,
             is_new: true,
             force: false,
+            append_to: None,
+            reuse_content: false,
+            provenance: None,
         }],
         delete_paths: vec![],
         scan_timestamp: Some(1_700_000_000),
@@ -232,6 +235,142 @@ async fn test_search_returns_duplicate_paths() {
         resp.results.iter().map(|r| (&r.path, &r.duplicate_paths)).collect::<Vec<_>>());
 }
 
+#[tokio::test]
+async fn test_search_collapses_duplicate_hits_unless_expanded() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk_hashed("src", "original.txt", "collapsible alias content")).await;
+    srv.post_bulk(&make_text_bulk_hashed("src", "copy.txt",     "collapsible alias content")).await;
+    srv.wait_for_idle().await;
+
+    let search = |extra: &'static str| {
+        let url = srv.url(&format!("/api/v1/search?q=collapsible&source=src{extra}"));
+        let client = srv.client.clone();
+        async move { client.get(url).send().await.unwrap().json::<SearchResponse>().await.unwrap() }
+    };
+
+    let collapsed = search("").await;
+    assert_eq!(collapsed.results.len(), 1, "identical files should give one hit");
+    assert_eq!(collapsed.total, 1);
+    assert_eq!(collapsed.results[0].duplicate_paths.len(), 1, "the hit lists the other path");
+
+    let expanded = search("&expand_duplicates=true").await;
+    let mut paths: Vec<&str> = expanded.results.iter().map(|r| r.path.as_str()).collect();
+    paths.sort();
+    assert_eq!(paths, vec!["copy.txt", "original.txt"]);
+}
+
+// ── sort order ────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_search_sort_orders() {
+    let srv = TestServer::spawn().await;
+
+    // (path, mtime, size)
+    for (path, mtime, size) in [("b.txt", 3_000, 10), ("c.txt", 1_000, 30), ("a.txt", 2_000, 20)] {
+        let mut req = make_text_bulk("src", path, "sortable content");
+        req.files[0].mtime = mtime;
+        req.files[0].size = Some(size);
+        srv.post_bulk(&req).await;
+    }
+    srv.wait_for_idle().await;
+
+    for (sort, expected) in [
+        ("mtime", ["b.txt", "a.txt", "c.txt"]),
+        ("path", ["a.txt", "b.txt", "c.txt"]),
+        ("size", ["c.txt", "a.txt", "b.txt"]),
+    ] {
+        let resp: SearchResponse = srv
+            .client
+            .get(srv.url(&format!("/api/v1/search?q=sortable&mode=exact&source=src&sort={sort}")))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let paths: Vec<&str> = resp.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, expected, "sort={sort}");
+    }
+}
+
+#[tokio::test]
+async fn test_search_hit_counts_and_sort_by_hits() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("src", "few.yaml", "port: 80\nname: web\nport: 81")).await;
+    srv.post_bulk(&make_text_bulk("src", "many.yaml", "port: 1\nport: 2\nhost: x\nport: 3\nPORT: 4")).await;
+    srv.wait_for_idle().await;
+
+    let search = |query: &'static str| {
+        let srv = &srv;
+        async move {
+            let resp: SearchResponse = srv
+                .client
+                .get(srv.url(&format!("/api/v1/search?source=src&sort=hits&{query}")))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            resp
+        }
+    };
+
+    let exact = search("q=port&mode=exact").await;
+    let counts: Vec<(&str, Option<usize>)> =
+        exact.results.iter().map(|r| (r.path.as_str(), r.hit_count)).collect();
+    assert_eq!(counts.first(), Some(&("many.yaml", Some(4))), "densest file first: {counts:?}");
+    assert!(counts.contains(&("few.yaml", Some(2))), "{counts:?}");
+
+    // Case-sensitive regex re-checks each line: "PORT: 4" no longer counts.
+    let regex = search("q=port:%20%5Cd&mode=regex&case_sensitive=true").await;
+    let many = regex.results.iter().find(|r| r.path == "many.yaml").unwrap();
+    assert_eq!(many.hit_count, Some(3));
+
+    let fuzzy = search("q=port").await;
+    assert!(fuzzy.results.iter().all(|r| r.hit_count.is_none()), "fuzzy results carry no count");
+}
+
+/// `explain=true` reports each source's candidates, post-filter drops and
+/// content reads; without it the response has no `explain`.
+#[tokio::test]
+async fn test_search_explain_reports_source_costs() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("a", "one.txt", "Needle here
+needle there
+NEEDLE")).await;
+    srv.post_bulk(&make_text_bulk("b", "two.txt", "nothing to see")).await;
+    srv.wait_for_idle().await;
+
+    let get = |query: &'static str| {
+        let srv = &srv;
+        async move {
+            let resp: SearchResponse =
+                srv.client.get(srv.url(&format!("/api/v1/search?{query}"))).send().await.unwrap().json().await.unwrap();
+            resp
+        }
+    };
+
+    assert!(get("q=needle&mode=exact").await.explain.is_none());
+
+    let resp = get("q=Needle&mode=regex&case_sensitive=true&explain=true").await;
+    assert_eq!(resp.results.len(), 1);
+    let explain = resp.explain.expect("explain requested");
+    let names: Vec<&str> = explain.sources.iter().map(|s| s.source.as_str()).collect();
+    assert_eq!(names, ["a", "b"], "one entry per source, by name");
+
+    let a = &explain.sources[0];
+    assert_eq!(a.candidates, 3, "the index matches case-insensitively");
+    assert_eq!(a.dropped, 2, "the case-sensitive regex drops two");
+    assert!(a.lines_read >= 3 && a.content_reads >= 1, "the regex reads each candidate line: {a:?}");
+    assert!(!a.capped && a.candidate_limit > 0);
+    assert!(a.error.is_none());
+    assert_eq!(explain.sources[1].candidates, 0);
+}
+
 // ── case sensitivity ──────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -493,3 +632,62 @@ async fn test_path_prefix_exact_match_included() {
 
     assert_eq!(resp.results.len(), 2, "should include both exact match and children");
 }
+
+#[tokio::test]
+async fn test_search_excludes_negative_path_and_ext_terms() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("src", "src/main.rs",       "negative filter term zvk")).await;
+    srv.post_bulk(&make_text_bulk("src", "src/test/parse.rs", "negative filter term zvk")).await;
+    srv.post_bulk(&make_text_bulk("src", "Cargo.lock",        "negative filter term zvk")).await;
+    srv.wait_for_idle().await;
+
+    let srv = &srv;
+    let search = |query: &'static str| async move {
+        let resp: SearchResponse = srv
+            .client
+            .get(srv.url(&format!("/api/v1/search?source=src&{query}")))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let mut paths: Vec<String> = resp.results.into_iter().map(|r| r.path).collect();
+        paths.sort();
+        paths
+    };
+
+    assert_eq!(search("q=zvk+-path%3Atest%2F+-ext%3Alock").await, ["src/main.rs"]);
+    assert_eq!(search("q=zvk&exclude_ext=rs").await, ["Cargo.lock"]);
+    assert_eq!(search("q=zvk&mode=exact&exclude_path=src%2F").await, ["Cargo.lock"]);
+}
+
+// ── search summary ────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_search_summary_counts_per_source_and_lists_files() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("alpha", "a.txt", "zorblax one\nzorblax two")).await;
+    srv.post_bulk(&make_text_bulk("alpha", "b.txt", "zorblax three")).await;
+    srv.post_bulk(&make_text_bulk("beta",  "c.txt", "zorblax four")).await;
+    srv.post_bulk(&make_text_bulk("beta",  "d.txt", "nothing here")).await;
+    srv.wait_for_idle().await;
+
+    let resp: SearchSummaryResponse = srv
+        .client
+        .get(srv.url("/api/v1/search/summary?q=zorblax&mode=exact&files=true"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let counts: Vec<(&str, usize)> = resp.sources.iter().map(|s| (s.source.as_str(), s.count)).collect();
+    assert_eq!(counts, [("alpha", 3), ("beta", 1)]);
+    let files: Vec<(&str, &str)> = resp.files.iter().map(|f| (f.source.as_str(), f.path.as_str())).collect();
+    assert_eq!(files, [("alpha", "a.txt"), ("alpha", "b.txt"), ("beta", "c.txt")]);
+    assert!(!resp.approximate);
+}
@@ -319,3 +319,103 @@ async fn test_fuzzy_mode_is_default() {
 
     assert!(resp.total >= 1, "default mode should be fuzzy and find matches");
 }
+
+// ── metadata matches ──────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_metadata_match_has_readable_snippet() {
+    let srv = TestServer::spawn().await;
+    let mut req = make_text_bulk("docs", "photo.jpg", "");
+    req.files[0].lines[1].content =
+        "[EXIF:Make] Canon [EXIF:Model] Canon EOS R5 [EXIF:DateTimeOriginal] 2021-06-12 10:04:51".to_string();
+    srv.post_bulk(&req).await;
+    let mut req = make_text_bulk("docs", "scan.pdf", "");
+    req.files[0].lines[1].content = "scanned with eos utility".to_string();
+    srv.post_bulk(&req).await;
+    srv.wait_for_idle().await;
+
+    let resp: SearchResponse = srv
+        .client
+        .get(srv.url("/api/v1/search?q=eos&source=docs"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let hit = resp.results.iter().find(|r| r.path == "photo.jpg").expect("metadata hit");
+    assert!(hit.metadata_match);
+    assert_eq!(hit.snippet, "Camera: Canon EOS R5 · 2021-06-12");
+
+    let untagged = resp.results.iter().find(|r| r.path == "scan.pdf").expect("untagged metadata hit");
+    assert!(!untagged.metadata_match, "no readable tags, so not marked");
+    assert_eq!(untagged.snippet, "scanned with eos utility");
+}
+
+// ── fuzzy phrases and prefixes ────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_fuzzy_mode_honours_phrases_and_prefixes() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("docs", "match.txt", "the quarterly report covers the budget")).await;
+    srv.post_bulk(&make_text_bulk("docs", "apart.txt", "the report for this quarterly period")).await;
+    srv.post_bulk(&make_text_bulk("docs", "inner.txt", "the quarterly report was rebudgeted")).await;
+    srv.wait_for_idle().await;
+
+    let resp: SearchResponse = srv
+        .client
+        .get(srv.url("/api/v1/search?q=%22quarterly+report%22+budg*&source=docs"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let paths: Vec<&str> = resp.results.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, vec!["match.txt"], "phrase must be adjacent and the prefix must start a word");
+}
+
+#[tokio::test]
+async fn test_token_normalization_folds_diacritics() {
+    let srv = TestServer::spawn_with_extra_config("[tokens]\nunicode = \"nfc\"\nstrip_diacritics = true\n").await;
+    srv.post_bulk(&make_text_bulk("docs", "menu.txt", "a naïve café order")).await;
+    srv.wait_for_idle().await;
+
+    for q in ["cafe", "caf%C3%A9", "naive+order"] {
+        let resp: SearchResponse = srv
+            .client
+            .get(srv.url(&format!("/api/v1/search?q={q}&source=docs")))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let paths: Vec<&str> = resp.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["menu.txt"], "query {q}");
+    }
+}
+
+#[tokio::test]
+async fn test_cjk_bigrams_find_two_character_words() {
+    let srv = TestServer::spawn_with_extra_config("[tokens]\ncjk = \"bigram\"\n").await;
+    srv.post_bulk(&make_text_bulk("docs", "tokyo.txt", "私は東京都に住んでいます")).await;
+    srv.wait_for_idle().await;
+
+    // 東京 and 東京都 (percent-encoded), in fuzzy and exact mode.
+    for q in ["%E6%9D%B1%E4%BA%AC", "%E6%9D%B1%E4%BA%AC%E9%83%BD&mode=exact"] {
+        let resp: SearchResponse = srv
+            .client
+            .get(srv.url(&format!("/api/v1/search?q={q}&source=docs")))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let paths: Vec<&str> = resp.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["tokyo.txt"], "query {q}");
+    }
+}
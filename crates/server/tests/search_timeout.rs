@@ -0,0 +1,29 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::SearchResponse;
+
+/// One file with `n` matching lines.
+///
+/// The over-budget case runs against an exhausted budget in the
+/// `routes::search` unit tests, where it does not depend on machine speed.
+fn otter_log(n: usize) -> String {
+    (0..n).map(|i| format!("otter sighting number {i}")).collect::<Vec<_>>().join("\n")
+}
+
+async fn regex_search(srv: &TestServer) -> SearchResponse {
+    srv.client
+        .get(srv.url("/api/v1/search?q=otter.%2Bnumber&mode=regex&limit=500"))
+        .send().await.unwrap().json().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_search_within_budget_is_complete() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("a", "otters.log", &otter_log(20))).await;
+    srv.wait_for_idle().await;
+
+    let resp = regex_search(&srv).await;
+    assert!(!resp.timed_out);
+    assert_eq!(resp.total, 20);
+}
@@ -1,7 +1,7 @@
 mod helpers;
 use helpers::{make_text_bulk, TestServer};
 
-use find_common::api::{BulkRequest, FileKind};
+use find_common::api::{BulkRequest, FileKind, StatsStreamEvent};
 
 /// ?refresh=true returns the correct file count after indexing.
 #[tokio::test]
@@ -175,6 +175,32 @@ async fn test_stats_stream_returns_sse_headers() {
     assert!(ct.contains("text/event-stream"), "stream must use text/event-stream, got: {ct}");
 }
 
+#[tokio::test]
+async fn test_stats_stream_counts_indexed_files_and_lines() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("src", "a.txt", "one\ntwo\nthree")).await;
+    srv.post_bulk(&make_text_bulk("src", "b.txt", "four")).await;
+    srv.wait_for_idle().await;
+
+    let mut resp = srv
+        .client
+        .get(srv.url("/api/v1/stats/stream"))
+        .send()
+        .await
+        .unwrap();
+    let mut buf = String::new();
+    while !buf.contains("\n\n") {
+        let chunk = resp.chunk().await.unwrap().expect("stream ended before first event");
+        buf.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    let data = buf.lines().find_map(|l| l.strip_prefix("data:")).expect("no data line");
+    let event: StatsStreamEvent = serde_json::from_str(data.trim()).unwrap();
+    assert_eq!(event.files_indexed, 2);
+    // Each file also has its path line.
+    assert!(event.lines_indexed >= 4, "got {}", event.lines_indexed);
+}
+
 #[tokio::test]
 async fn test_stats_stream_requires_auth() {
     let srv = TestServer::spawn().await;
@@ -0,0 +1,43 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::SearchResponse;
+
+async fn sources_matching(srv: &TestServer, q: &str, mode: &str) -> Vec<String> {
+    let resp: SearchResponse = srv
+        .client
+        .get(srv.url(&format!("/api/v1/search?q={q}&mode={mode}")))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let mut sources: Vec<String> = resp.results.into_iter().map(|r| r.source).collect();
+    sources.sort();
+    sources.dedup();
+    sources
+}
+
+/// Sources ruled out by their term filter must still be found once they gain
+/// matching content, in every mode that consults the filter.
+#[tokio::test]
+async fn test_filtered_sources_pick_up_new_content() {
+    let srv = TestServer::spawn().await;
+    srv.post_bulk(&make_text_bulk("src-a", "a.txt", "the Heron flies")).await;
+    srv.post_bulk(&make_text_bulk("src-b", "b.txt", "a quiet pond")).await;
+    srv.wait_for_idle().await;
+
+    // The first search loads the filters in the background.
+    assert_eq!(sources_matching(&srv, "heron", "fuzzy").await, vec!["src-a"]);
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert_eq!(sources_matching(&srv, "heron", "fuzzy").await, vec!["src-a"]);
+
+    srv.post_bulk(&make_text_bulk("src-b", "c.txt", "grey HERON wading")).await;
+    srv.wait_for_idle().await;
+
+    for mode in ["fuzzy", "exact", "regex", "document"] {
+        assert_eq!(sources_matching(&srv, "heron", mode).await, vec!["src-a", "src-b"], "{mode}");
+    }
+    assert_eq!(sources_matching(&srv, "b.txt", "file-exact").await, vec!["src-b"]);
+}
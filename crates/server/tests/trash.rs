@@ -0,0 +1,86 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::{BulkRequest, SearchResponse, TrashResponse, TrashRestoreRequest, TrashRestoreResponse};
+
+async fn search_total(srv: &TestServer, q: &str) -> usize {
+    let resp: SearchResponse = srv
+        .client
+        .get(srv.url(&format!("/api/v1/search?q={q}&source=docs")))
+        .send()
+        .await
+        .expect("search request")
+        .json()
+        .await
+        .expect("search json");
+    resp.total
+}
+
+async fn get_trash(srv: &TestServer) -> TrashResponse {
+    srv.client
+        .get(srv.url("/api/v1/admin/trash?source=docs"))
+        .send()
+        .await
+        .expect("trash request")
+        .json()
+        .await
+        .expect("trash json")
+}
+
+fn delete_bulk(path: &str) -> BulkRequest {
+    BulkRequest {
+        source: "docs".to_string(),
+        files: vec![],
+        delete_paths: vec![path.to_string()],
+        scan_timestamp: None,
+        indexing_failures: vec![],
+        rename_paths: vec![],
+    }
+}
+
+// ── [server] trash_days ───────────────────────────────────────────────────────
+
+/// A deleted file leaves search, is listed in the trash, and is found again
+/// once restored.
+#[tokio::test]
+async fn test_deleted_file_restored_from_trash() {
+    let srv = TestServer::spawn_with_extra_config("trash_days = 7\n").await;
+
+    srv.post_bulk(&make_text_bulk("docs", "report.txt", "trashword456 quarterly")).await;
+    srv.wait_for_idle().await;
+    srv.post_bulk(&delete_bulk("report.txt")).await;
+    srv.wait_for_idle().await;
+    assert_eq!(search_total(&srv, "trashword456").await, 0, "trashed file is hidden from search");
+
+    let trash = get_trash(&srv).await;
+    assert_eq!(trash.total, 1);
+    assert_eq!(trash.files[0].path, "report.txt");
+    assert_eq!(trash.files[0].purge_at - trash.files[0].deleted_at, 7 * 86_400);
+
+    let resp: TrashRestoreResponse = srv
+        .client
+        .post(srv.url("/api/v1/admin/trash/restore"))
+        .json(&TrashRestoreRequest { source: "docs".to_string(), paths: vec!["report.txt".to_string()], prefix: None })
+        .send()
+        .await
+        .expect("restore request")
+        .json()
+        .await
+        .expect("restore json");
+    assert_eq!((resp.restored, resp.skipped), (1, 0));
+    assert_eq!(search_total(&srv, "trashword456").await, 1, "restored file is found again");
+    assert_eq!(get_trash(&srv).await.total, 0);
+}
+
+/// Without `trash_days`, deletions are final.
+#[tokio::test]
+async fn test_trash_off_by_default() {
+    let srv = TestServer::spawn().await;
+
+    srv.post_bulk(&make_text_bulk("docs", "gone.txt", "goneword789")).await;
+    srv.wait_for_idle().await;
+    srv.post_bulk(&delete_bulk("gone.txt")).await;
+    srv.wait_for_idle().await;
+
+    assert_eq!(get_trash(&srv).await.total, 0);
+}
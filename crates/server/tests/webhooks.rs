@@ -0,0 +1,105 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::HeaderMap, routing::post, Router};
+use find_common::api::IndexingFailure;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const SECRET: &str = "webhook-test-secret";
+
+#[derive(Clone, Debug)]
+struct Received {
+    event: String,
+    signature: Option<String>,
+    body: serde_json::Value,
+    raw: Vec<u8>,
+}
+
+type Inbox = Arc<Mutex<Vec<Received>>>;
+
+/// Spawn a local HTTP receiver that records every webhook POST.
+async fn spawn_receiver() -> (String, Inbox) {
+    async fn receive(State(inbox): State<Inbox>, headers: HeaderMap, body: axum::body::Bytes) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        inbox.lock().unwrap().push(Received {
+            event: header("x-find-event").unwrap_or_default(),
+            signature: header("x-find-signature"),
+            body: serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null),
+            raw: body.to_vec(),
+        });
+    }
+
+    let inbox: Inbox = Arc::default();
+    let app = Router::new().route("/hook", post(receive)).with_state(Arc::clone(&inbox));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local_addr");
+    tokio::spawn(async move { axum::serve(listener, app).await.expect("serve") });
+    (format!("http://{addr}/hook"), inbox)
+}
+
+/// Poll `inbox` until an event named `event` arrives. Panics after 10 seconds.
+async fn wait_for_event(inbox: &Inbox, event: &str) -> Received {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(r) = inbox.lock().unwrap().iter().find(|r| r.event == event) {
+            return r.clone();
+        }
+        if Instant::now() >= deadline {
+            panic!("webhook event '{event}' not received within 10s");
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+fn expected_signature(body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+    mac.update(body);
+    let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+// ── Webhook delivery ──────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_scan_completed_webhook_is_signed() {
+    let (url, inbox) = spawn_receiver().await;
+    let srv = TestServer::spawn_with_extra_config(&format!(
+        "[[webhooks.endpoints]]\nurl = \"{url}\"\nevents = [\"scan_completed\"]\nsecret = \"{SECRET}\"\n"
+    )).await;
+
+    srv.post_bulk(&make_text_bulk("docs", "a.txt", "hello")).await;
+    srv.wait_for_idle().await;
+
+    let r = wait_for_event(&inbox, "scan_completed").await;
+    assert_eq!(r.body["event"], "scan_completed");
+    assert_eq!(r.body["data"]["source"], "docs");
+    assert_eq!(r.body["data"]["total_files"], 1);
+    assert_eq!(r.signature.as_deref(), Some(expected_signature(&r.raw).as_str()));
+}
+
+#[tokio::test]
+async fn test_error_spike_webhook_fires_at_threshold() {
+    let (url, inbox) = spawn_receiver().await;
+    let srv = TestServer::spawn_with_extra_config(&format!(
+        "[webhooks]\nerror_spike_threshold = 3\n\n[[webhooks.endpoints]]\nurl = \"{url}\"\nevents = [\"error_spike\"]\n"
+    )).await;
+
+    let mut req = make_text_bulk("docs", "a.txt", "hello");
+    req.indexing_failures = (0..3)
+        .map(|i| IndexingFailure { path: format!("bad{i}.pdf"), error: "boom".to_string() })
+        .collect();
+    srv.post_bulk(&req).await;
+    srv.wait_for_idle().await;
+
+    let r = wait_for_event(&inbox, "error_spike").await;
+    assert_eq!(r.body["data"]["failure_count"], 3);
+    assert!(r.signature.is_none(), "no secret configured, so no signature expected");
+    assert!(
+        inbox.lock().unwrap().iter().all(|r| r.event != "scan_completed"),
+        "endpoint did not subscribe to scan_completed"
+    );
+}
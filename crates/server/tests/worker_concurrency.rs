@@ -0,0 +1,89 @@
+mod helpers;
+use helpers::{make_text_bulk, TestServer};
+
+use find_common::api::{FileResponse, SearchResponse, StatsResponse, WorkerStatus};
+
+async fn search_total(srv: &TestServer, q: &str, source: &str) -> usize {
+    let resp: SearchResponse = srv.client
+        .get(srv.url(&format!("/api/v1/search?q={q}&source={source}")))
+        .send().await.unwrap().json().await.unwrap();
+    resp.total
+}
+
+#[tokio::test]
+async fn test_concurrent_worker_indexes_every_source() {
+    let srv = TestServer::spawn_with_extra_config("[worker]\nconcurrency = 4\n").await;
+    let sources = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot"];
+    for source in sources {
+        for i in 0..3 {
+            let req = make_text_bulk(source, &format!("file{i}.txt"), &format!("quokka {source} {i}"));
+            srv.post_bulk(&req).await;
+        }
+    }
+    srv.wait_for_idle().await;
+
+    for source in sources {
+        assert_eq!(search_total(&srv, "quokka", source).await, 3, "source {source}");
+    }
+    assert_eq!(srv.get_stats().await.failed_requests, 0);
+}
+
+#[tokio::test]
+async fn test_concurrent_worker_keeps_per_source_order() {
+    let srv = TestServer::spawn_with_extra_config("[worker]\nconcurrency = 4\n").await;
+    for version in 1..=5 {
+        let mut req = make_text_bulk("docs", "notes.txt", &format!("version {version}"));
+        req.files[0].mtime += version;
+        srv.post_bulk(&req).await;
+        srv.post_bulk(&make_text_bulk("other", &format!("f{version}.txt"), "filler")).await;
+    }
+    srv.wait_for_idle().await;
+
+    let file: FileResponse = srv.client
+        .get(srv.url("/api/v1/file?source=docs&path=notes.txt"))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!(file.lines, vec!["version 5".to_string()], "last upload for a source wins");
+}
+
+#[tokio::test]
+async fn test_concurrent_worker_reports_every_lane() {
+    let srv = TestServer::spawn_with_extra_config("[worker]\nconcurrency = 2\n").await;
+    for source in ["alpha", "bravo"] {
+        let mut req = make_text_bulk(source, "file0.txt", "quokka 0");
+        for i in 1..1000 {
+            let more = make_text_bulk(source, &format!("file{i}.txt"), &format!("quokka {i}"));
+            req.files.extend(more.files);
+        }
+        srv.post_bulk(&req).await;
+    }
+
+    // Sample the status until both requests are done.  The worker only
+    // reports Idle once no lane is left, and every lane is a distinct source.
+    loop {
+        let stats: StatsResponse = srv.client
+            .get(srv.url("/api/v1/stats"))
+            .send().await.unwrap().json().await.unwrap();
+        let lanes = &stats.worker_lanes;
+        assert!(lanes.len() <= 2, "{lanes:?}");
+        match &stats.worker_status {
+            WorkerStatus::Idle => assert!(lanes.is_empty(), "idle with lanes open: {lanes:?}"),
+            WorkerStatus::Processing { source, .. } => assert_eq!(*source, lanes[0].source),
+        }
+        if lanes.len() == 2 {
+            assert_ne!(lanes[0].source, lanes[1].source);
+        }
+        if stats.inbox_pending == 0 && stats.worker_status == WorkerStatus::Idle {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    srv.wait_for_idle().await;
+
+    let stats = srv.get_stats_refresh().await;
+    for source in ["alpha", "bravo"] {
+        let indexed = stats.sources.iter().find(|s| s.name == source).map(|s| s.total_files);
+        assert_eq!(indexed, Some(1000), "source {source}");
+    }
+    assert_eq!(stats.worker_status, WorkerStatus::Idle);
+    assert!(stats.worker_lanes.is_empty());
+}
@@ -1,7 +1,17 @@
 //! Windows Service support for find-watch.
 //!
 //! Provides `install_service` and `uninstall_service` for managing the
-//! `FindAnythingWatcher` Windows Service.
+//! `FindAnythingWatcher` Windows Service.  The service is installed with SCM
+//! recovery actions, so a watcher that crashes or stops with an error is
+//! restarted rather than staying down until someone notices.
+//!
+//! The service runs as LocalSystem unless another [`ServiceAccount`] is
+//! chosen.  LocalSystem cannot reach network shares with the user's
+//! credentials, so indexing a NAS needs a user account.
+//!
+//! [`install_full_scan_task`] optionally adds a Task Scheduler job that runs
+//! `find-scan --full` on a fixed cadence, so events the watcher missed (while
+//! it was stopped, or on shares that do not report changes) are caught up.
 //!
 //! The `service_main` entry point lives in `find-watch`'s `watch_main.rs`
 //! because `define_windows_service!` emits a public FFI symbol that must
@@ -13,11 +23,12 @@ use std::ffi::OsString;
 use std::path::Path;
 use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use windows_service::{
     service::{
-        ServiceAccess, ServiceErrorControl, ServiceInfo, ServiceStartType, ServiceState,
-        ServiceType,
+        Service, ServiceAccess, ServiceAction, ServiceActionType, ServiceErrorControl,
+        ServiceFailureActions, ServiceFailureResetPeriod, ServiceInfo, ServiceStartType,
+        ServiceState, ServiceType,
     },
     service_manager::{ServiceManager, ServiceManagerAccess},
 };
@@ -32,11 +43,221 @@ const SERVICE_DESCRIPTION: &str =
 const REGISTRY_RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
 const REGISTRY_VALUE_NAME: &str = "FindAnythingTray";
 
+/// Restart delays after the first, second and subsequent failures.
+const RESTART_DELAYS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+];
+/// A day without failures resets the count, so the next one restarts after
+/// a minute again.
+const FAILURE_RESET_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+/// How long Windows waits at shutdown for the watcher to stop (it accepts
+/// the pre-shutdown notification) before ending it.
+const PRESHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// The account the service runs as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceAccount {
+    LocalSystem,
+    /// `NT AUTHORITY\LocalService`: minimal rights, anonymous on the network.
+    LocalService,
+    /// `NT AUTHORITY\NetworkService`: minimal rights, the computer's
+    /// account on the network.
+    NetworkService,
+    /// `NT SERVICE\<service name>`, a virtual account Windows manages.
+    Virtual,
+    /// A local (`.\name`) or domain (`DOMAIN\name`) user.
+    User { name: String, password: String },
+}
+
+impl ServiceAccount {
+    /// Parse `find-watch install --account`: `LocalSystem`, `LocalService`,
+    /// `NetworkService`, `virtual`, or a user name, which needs `password`
+    /// (else the `FIND_SERVICE_PASSWORD` environment variable, which keeps
+    /// it off the command line).
+    pub fn parse(account: &str, password: Option<String>) -> Result<Self> {
+        let builtin = match account.to_ascii_lowercase().as_str() {
+            "localsystem" => Some(Self::LocalSystem),
+            "localservice" | "nt authority\\localservice" => Some(Self::LocalService),
+            "networkservice" | "nt authority\\networkservice" => Some(Self::NetworkService),
+            "virtual" => Some(Self::Virtual),
+            _ => None,
+        };
+        match (builtin, password) {
+            (Some(_), Some(_)) => bail!("--password is only used with a user account, not {account}"),
+            (Some(builtin), None) => Ok(builtin),
+            (None, password) => {
+                let password = password
+                    .or_else(|| std::env::var("FIND_SERVICE_PASSWORD").ok())
+                    .with_context(|| format!("--account {account} needs a password (--password or FIND_SERVICE_PASSWORD)"))?;
+                Ok(Self::User { name: account.to_string(), password })
+            }
+        }
+    }
+
+    /// The name the SCM is given; `None` for LocalSystem.
+    fn name(&self, service_name: &str) -> Option<String> {
+        match self {
+            Self::LocalSystem => None,
+            Self::LocalService => Some("NT AUTHORITY\\LocalService".to_string()),
+            Self::NetworkService => Some("NT AUTHORITY\\NetworkService".to_string()),
+            Self::Virtual => Some(format!("NT SERVICE\\{service_name}")),
+            Self::User { name, .. } => Some(name.clone()),
+        }
+    }
+}
+
+/// When the scheduled full scan runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullScanSchedule {
+    cadence: Cadence,
+    /// `HH:MM`, local time.
+    at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cadence {
+    Daily,
+    /// Sundays.
+    Weekly,
+    /// The first of each month.
+    Monthly,
+}
+
+impl FullScanSchedule {
+    /// Parse `find-watch install --full-scan <cadence> --full-scan-at <at>`.
+    pub fn parse(cadence: &str, at: &str) -> Result<Self> {
+        let cadence = match cadence.to_ascii_lowercase().as_str() {
+            "daily" => Cadence::Daily,
+            "weekly" => Cadence::Weekly,
+            "monthly" => Cadence::Monthly,
+            _ => bail!("--full-scan must be daily, weekly or monthly, not {cadence}"),
+        };
+        let valid_time = at
+            .split_once(':')
+            .and_then(|(h, m)| Some((h.parse::<u8>().ok()?, m.parse::<u8>().ok()?, m.len())))
+            .is_some_and(|(h, m, m_len)| h < 24 && m < 60 && m_len == 2);
+        if !valid_time {
+            bail!("--full-scan-at must be a time such as 03:00, not {at}");
+        }
+        Ok(Self { cadence, at: at.to_string() })
+    }
+
+    /// `schtasks /Create` schedule arguments.
+    fn schtasks_args(&self) -> Vec<&str> {
+        let mut args = match self.cadence {
+            Cadence::Daily => vec!["/SC", "DAILY"],
+            Cadence::Weekly => vec!["/SC", "WEEKLY", "/D", "SUN"],
+            Cadence::Monthly => vec!["/SC", "MONTHLY", "/D", "1"],
+        };
+        args.extend(["/ST", self.at.as_str()]);
+        args
+    }
+
+    fn describe(&self) -> String {
+        let when = match self.cadence {
+            Cadence::Daily => "daily",
+            Cadence::Weekly => "every Sunday",
+            Cadence::Monthly => "on the 1st of each month",
+        };
+        format!("{when} at {}", self.at)
+    }
+}
+
+/// Task Scheduler name of the full-scan job for `service_name`.
+pub fn full_scan_task_name(service_name: &str) -> String {
+    format!("{service_name} Full Scan")
+}
+
+/// Register (or replace) a Task Scheduler job that runs
+/// `find-scan --full --quiet` on `schedule`, as the same `account` as the
+/// service so it sees the same files.  The `virtual` service account has no
+/// Task Scheduler equivalent.  A user account's password is passed to
+/// `schtasks.exe` on its command line.
+///
+/// Requires Administrator privileges.
+pub fn install_full_scan_task(
+    config_path: &Path,
+    service_name: &str,
+    account: &ServiceAccount,
+    schedule: &FullScanSchedule,
+) -> Result<()> {
+    let current_exe = std::env::current_exe().context("resolving current executable path")?;
+    let scan_exe = current_exe
+        .parent()
+        .map(|p| p.join("find-scan.exe"))
+        .unwrap_or_else(|| std::path::PathBuf::from("find-scan.exe"));
+    let config_abs = config_path
+        .canonicalize()
+        .unwrap_or_else(|_| config_path.to_path_buf());
+    let command = format!(
+        "\"{}\" --config \"{}\" --full --quiet",
+        scan_exe.display(),
+        config_abs.display()
+    );
+
+    let (run_as, password) = match account {
+        ServiceAccount::LocalSystem => ("SYSTEM".to_string(), None),
+        ServiceAccount::Virtual => {
+            bail!("--full-scan cannot run as a virtual account; choose another --account")
+        }
+        ServiceAccount::User { name, password } => (name.clone(), Some(password.as_str())),
+        other => (other.name(service_name).unwrap_or_default(), None),
+    };
+
+    let task_name = full_scan_task_name(service_name);
+    let mut cmd = std::process::Command::new("schtasks.exe");
+    cmd.args(["/Create", "/F", "/TN", &task_name, "/TR", &command])
+        .args(schedule.schtasks_args())
+        .args(["/RU", &run_as]);
+    if let Some(password) = password {
+        cmd.args(["/RP", password]);
+    }
+    let output = cmd.output().context("running schtasks.exe")?;
+    if !output.status.success() {
+        bail!(
+            "creating scheduled task '{task_name}': {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    println!("Scheduled task '{task_name}' runs a full scan {}.", schedule.describe());
+    Ok(())
+}
+
+/// Delete the full-scan job for `service_name`.  Returns `false` if there
+/// was none.
+pub fn uninstall_full_scan_task(service_name: &str) -> Result<bool> {
+    let task_name = full_scan_task_name(service_name);
+    let exists = std::process::Command::new("schtasks.exe")
+        .args(["/Query", "/TN", &task_name])
+        .output()
+        .context("running schtasks.exe")?
+        .status
+        .success();
+    if !exists {
+        return Ok(false);
+    }
+    let output = std::process::Command::new("schtasks.exe")
+        .args(["/Delete", "/F", "/TN", &task_name])
+        .output()
+        .context("running schtasks.exe")?;
+    if !output.status.success() {
+        bail!(
+            "deleting scheduled task '{task_name}': {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    println!("Scheduled task '{task_name}' removed.");
+    Ok(true)
+}
+
 /// Register the Find Anything watcher as a Windows Service and add the tray
-/// app to the current user's startup run key.
+/// app to the current user's startup run key.  A user `account` is granted
+/// the "Log on as a service" right it needs.
 ///
 /// Requires Administrator privileges.
-pub fn install_service(config_path: &Path, service_name: &str) -> Result<()> {
+pub fn install_service(config_path: &Path, service_name: &str, account: &ServiceAccount) -> Result<()> {
     let manager = ServiceManager::local_computer(
         None::<&str>,
         ServiceManagerAccess::CREATE_SERVICE,
@@ -62,10 +283,18 @@ pub fn install_service(config_path: &Path, service_name: &str) -> Result<()> {
             config_abs.clone().into_os_string(),
         ],
         dependencies: vec![],
-        account_name: None,
-        account_password: None,
+        account_name: account.name(service_name).map(OsString::from),
+        account_password: match account {
+            ServiceAccount::User { password, .. } => Some(OsString::from(password)),
+            _ => None,
+        },
     };
 
+    // Built-in and virtual accounts already have the right.
+    if let ServiceAccount::User { name, .. } = account {
+        grant_service_logon_right(name)?;
+    }
+
     // If the service already exists (e.g. reinstall/upgrade), delete it first
     // so we can recreate it with the latest configuration.
     if let Ok(existing) = manager.open_service(
@@ -113,6 +342,7 @@ pub fn install_service(config_path: &Path, service_name: &str) -> Result<()> {
     service
         .set_description(SERVICE_DESCRIPTION)
         .context("setting service description")?;
+    configure_recovery(&service)?;
 
     // Grant BUILTIN\Users the ability to start, stop, and query the service so
     // the tray app can control it without requiring Administrator privileges.
@@ -156,12 +386,103 @@ pub fn install_service(config_path: &Path, service_name: &str) -> Result<()> {
         .start(&[] as &[&std::ffi::OsStr])
         .context("starting service after install")?;
 
-    println!("Service '{service_name}' installed and started.");
+    match account.name(service_name) {
+        Some(name) => println!("Service '{service_name}' installed and started as {name}."),
+        None => println!("Service '{service_name}' installed and started."),
+    }
     println!("Tray app registered to start at login: {run_value}");
 
     Ok(())
 }
 
+/// Restart the service after failures (see [`RESTART_DELAYS`]) and give it
+/// time to stop cleanly at system shutdown.  Needs `CHANGE_CONFIG` and, for
+/// restart actions, `START` access.
+fn configure_recovery(service: &Service) -> Result<()> {
+    let actions = RESTART_DELAYS
+        .iter()
+        .map(|&delay| ServiceAction { action_type: ServiceActionType::Restart, delay })
+        .collect();
+    service
+        .update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(FAILURE_RESET_PERIOD),
+            reboot_msg: None,
+            command: None,
+            actions: Some(actions),
+        })
+        .context("setting service recovery actions")?;
+    // Without this only a crash counts as a failure; the watcher also stops
+    // with a non-zero exit code when it gives up on an error.
+    service
+        .set_failure_actions_on_non_crash_failures(true)
+        .context("enabling recovery for non-crash failures")?;
+    service
+        .set_preshutdown_timeout(PRESHUTDOWN_TIMEOUT)
+        .context("setting service pre-shutdown timeout")?;
+    Ok(())
+}
+
+/// Grant `account` the "Log on as a service" right (`SeServiceLogonRight`),
+/// as `secpol.msc` would.  Granting it again is harmless.
+fn grant_service_logon_right(account: &str) -> Result<()> {
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::Foundation::GetLastError;
+    use windows_sys::Win32::Security::Authentication::Identity::{
+        LsaAddAccountRights, LsaClose, LsaNtStatusToWinError, LsaOpenPolicy, LSA_HANDLE,
+        LSA_OBJECT_ATTRIBUTES, LSA_UNICODE_STRING, POLICY_CREATE_ACCOUNT, POLICY_LOOKUP_NAMES,
+    };
+    use windows_sys::Win32::Security::{LookupAccountNameW, SID_NAME_USE};
+
+    let wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(Some(0)).collect() };
+    let name = wide(account);
+
+    // The first lookup only sizes the SID and domain buffers.
+    let (mut sid_len, mut domain_len) = (0u32, 0u32);
+    let mut sid_use: SID_NAME_USE = 0;
+    unsafe {
+        LookupAccountNameW(null(), name.as_ptr(), null_mut(), &mut sid_len, null_mut(), &mut domain_len, &mut sid_use);
+    }
+    if sid_len == 0 {
+        bail!("account {account} not found (error {})", unsafe { GetLastError() });
+    }
+    let mut sid = vec![0u8; sid_len as usize];
+    let mut domain = vec![0u16; domain_len as usize];
+    let found = unsafe {
+        LookupAccountNameW(
+            null(),
+            name.as_ptr(),
+            sid.as_mut_ptr().cast(),
+            &mut sid_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    };
+    if found == 0 {
+        bail!("looking up account {account} (error {})", unsafe { GetLastError() });
+    }
+
+    let attributes: LSA_OBJECT_ATTRIBUTES = unsafe { std::mem::zeroed() };
+    let mut policy: LSA_HANDLE = unsafe { std::mem::zeroed() };
+    let status = unsafe { LsaOpenPolicy(null(), &attributes, POLICY_CREATE_ACCOUNT | POLICY_LOOKUP_NAMES, &mut policy) };
+    if status != 0 {
+        bail!("opening the local security policy (error {})", unsafe { LsaNtStatusToWinError(status) });
+    }
+    // LSA strings are counted, not NUL-terminated.
+    let mut right: Vec<u16> = "SeServiceLogonRight".encode_utf16().collect();
+    let bytes = (right.len() * 2) as u16;
+    let right = LSA_UNICODE_STRING { Length: bytes, MaximumLength: bytes, Buffer: right.as_mut_ptr() };
+    let status = unsafe { LsaAddAccountRights(policy, sid.as_mut_ptr().cast(), &right, 1) };
+    unsafe { LsaClose(policy) };
+    if status != 0 {
+        bail!(
+            "granting {account} the \"Log on as a service\" right (error {})",
+            unsafe { LsaNtStatusToWinError(status) }
+        );
+    }
+    Ok(())
+}
+
 /// Stop and delete the Find Anything watcher service, and remove the tray
 /// app from the current user's startup run key.
 ///
@@ -16,8 +16,8 @@ use anyhow::{Context, Result};
 use windows_sys::core::GUID;
 use windows_sys::Win32::Foundation::HWND;
 use windows_sys::Win32::UI::Shell::{
-    Shell_NotifyIconW, NIF_GUID, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY,
-    NOTIFYICONDATAW,
+    Shell_NotifyIconW, NIF_GUID, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_INFO,
+    NIIF_RESPECT_QUIET_TIME, NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
 };
 use windows_sys::Win32::UI::WindowsAndMessaging::{CreateIcon, HICON};
 
@@ -49,9 +49,30 @@ pub static NEED_REREGISTER: AtomicBool = AtomicBool::new(false);
 /// icon is rendered identically.  The returned handle is intentionally
 /// process-lifetime; do not call `DestroyIcon` on it.
 pub fn load_hicon(bytes: &[u8]) -> Result<HICON> {
+    hicon_from_rgba(decode_ico(bytes)?)
+}
+
+/// Like [`load_hicon`], with a pause symbol (two amber bars) drawn over the
+/// bottom-right quarter, for the "indexing paused" state.
+pub fn load_paused_hicon(bytes: &[u8]) -> Result<HICON> {
+    let mut img = decode_ico(bytes)?;
+    let (w, h) = img.dimensions();
+    const AMBER: image::Rgba<u8> = image::Rgba([0xF5, 0x9E, 0x0B, 0xFF]);
+    for y in h / 2..h * 15 / 16 {
+        for x in (w * 9 / 16..w * 11 / 16).chain(w * 13 / 16..w * 15 / 16) {
+            img.put_pixel(x, y, AMBER);
+        }
+    }
+    hicon_from_rgba(img)
+}
+
+fn decode_ico(bytes: &[u8]) -> Result<image::RgbaImage> {
     let img = image::load_from_memory_with_format(bytes, image::ImageFormat::Ico)
         .context("decoding ICO file")?;
-    let img = img.into_rgba8();
+    Ok(img.into_rgba8())
+}
+
+fn hicon_from_rgba(img: image::RgbaImage) -> Result<HICON> {
     let (w, h) = img.dimensions();
     let mut rgba = img.into_raw();
 
@@ -141,3 +162,25 @@ pub unsafe fn update_tooltip(hwnd: HWND, tooltip: &str) {
     nid.guidItem = TRAY_GUID;
     Shell_NotifyIconW(NIM_MODIFY, &mut nid);
 }
+
+/// Show a balloon notification from the GUID-registered icon.  Windows 10
+/// and later present it as a toast in the Action Center; it is suppressed
+/// during the user's quiet hours and focus assist.
+///
+/// # Safety
+/// `hwnd` must be the valid hidden window returned by `tray_icon.hwnd()`.
+pub unsafe fn show_balloon(hwnd: HWND, title: &str, message: &str, warning: bool) {
+    let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
+    nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uFlags = NIF_GUID | NIF_INFO;
+    nid.guidItem = TRAY_GUID;
+    for (i, c) in title.encode_utf16().take(nid.szInfoTitle.len() - 1).enumerate() {
+        nid.szInfoTitle[i] = c;
+    }
+    for (i, c) in message.encode_utf16().take(nid.szInfo.len() - 1).enumerate() {
+        nid.szInfo[i] = c;
+    }
+    nid.dwInfoFlags = if warning { NIIF_WARNING } else { NIIF_INFO } | NIIF_RESPECT_QUIET_TIME;
+    Shell_NotifyIconW(NIM_MODIFY, &mut nid);
+}
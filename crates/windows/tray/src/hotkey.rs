@@ -0,0 +1,109 @@
+//! Global hotkey for the quick search window (`[tray] hotkey`).
+//!
+//! The key is registered with `RegisterHotKey` against the search window, so
+//! Windows posts `WM_HOTKEY` to it from anywhere in the session.
+
+use anyhow::Result;
+use windows_sys::Win32::Foundation::HWND;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+};
+
+/// Identifier passed to `RegisterHotKey`; the tray registers only one.
+pub const HOTKEY_ID: i32 = 1;
+
+/// A parsed hotkey: `MOD_*` flags and a virtual-key code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: u32,
+    pub vk: u32,
+}
+
+/// Parse a hotkey such as `Ctrl+Alt+Space` or `Win+Shift+F`.  Modifiers are
+/// `Ctrl`, `Alt`, `Shift` and `Win`; the key is a letter, digit, `F1`–`F24`,
+/// `Space`, or one of a few named keys.  At least one modifier is required,
+/// so a bare letter cannot swallow ordinary typing.
+pub fn parse(spec: &str) -> Result<Hotkey, String> {
+    let mut modifiers = 0;
+    let mut vk = None;
+    for part in spec.split('+').map(str::trim) {
+        let lower = part.to_ascii_lowercase();
+        let flag = match lower.as_str() {
+            "ctrl" | "control" => Some(MOD_CONTROL),
+            "alt" => Some(MOD_ALT),
+            "shift" => Some(MOD_SHIFT),
+            "win" | "super" => Some(MOD_WIN),
+            _ => None,
+        };
+        if let Some(flag) = flag {
+            modifiers |= flag;
+            continue;
+        }
+        if vk.is_some() {
+            return Err(format!("{spec:?} names more than one key"));
+        }
+        vk = Some(key_code(&lower).ok_or_else(|| format!("unknown key {part:?} in {spec:?}"))?);
+    }
+    let vk = vk.ok_or_else(|| format!("{spec:?} has no key"))?;
+    if modifiers == 0 {
+        return Err(format!("{spec:?} needs at least one of Ctrl, Alt, Shift or Win"));
+    }
+    Ok(Hotkey { modifiers, vk })
+}
+
+/// Virtual-key code for a lower-cased key name.
+fn key_code(name: &str) -> Option<u32> {
+    let named = match name {
+        "space" => Some(0x20),
+        "enter" | "return" => Some(0x0D),
+        "tab" => Some(0x09),
+        "insert" => Some(0x2D),
+        "home" => Some(0x24),
+        "end" => Some(0x23),
+        "pause" => Some(0x13),
+        "`" | "backtick" => Some(0xC0),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+    if let [c] = name.as_bytes() {
+        if c.is_ascii_alphanumeric() {
+            // VK_A..VK_Z and VK_0..VK_9 are the upper-case ASCII codes.
+            return Some(c.to_ascii_uppercase() as u32);
+        }
+    }
+    let n: u32 = name.strip_prefix('f')?.parse().ok()?;
+    // VK_F1 = 0x70 … VK_F24 = 0x87.
+    (1..=24).contains(&n).then(|| 0x6F + n)
+}
+
+/// Register `hotkey` so `WM_HOTKEY` is posted to `hwnd`.  Fails when another
+/// program already owns the combination.
+pub fn register(hwnd: HWND, hotkey: Hotkey) -> Result<()> {
+    let ok = unsafe { RegisterHotKey(hwnd, HOTKEY_ID, hotkey.modifiers | MOD_NOREPEAT, hotkey.vk) };
+    if ok == 0 {
+        anyhow::bail!("RegisterHotKey failed: {}", std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(parse("Ctrl+Alt+Space"), Ok(Hotkey { modifiers: MOD_CONTROL | MOD_ALT, vk: 0x20 }));
+        assert_eq!(parse("win + shift + f"), Ok(Hotkey { modifiers: MOD_WIN | MOD_SHIFT, vk: 0x46 }));
+        assert_eq!(parse("Alt+F12"), Ok(Hotkey { modifiers: MOD_ALT, vk: 0x7B }));
+        assert_eq!(parse("Ctrl+7"), Ok(Hotkey { modifiers: MOD_CONTROL, vk: 0x37 }));
+    }
+
+    #[test]
+    fn rejects_incomplete_or_unknown_keys() {
+        for spec in ["Space", "Ctrl+Alt", "Ctrl+A+B", "Ctrl+F25", "Ctrl+Banana"] {
+            assert!(parse(spec).is_err(), "{spec:?} should not parse");
+        }
+    }
+}
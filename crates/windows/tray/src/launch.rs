@@ -0,0 +1,230 @@
+//! Opening quick search results and web UI pages.
+//!
+//! A hit this machine has a local path for — from its source's own `path`,
+//! or `[cli.path_map]`, as for `find-anything --open` — opens with the file's
+//! default app or is shown in Explorer.  Archive members cannot be opened
+//! directly, so the archive itself is shown instead.  Otherwise a
+//! `[cli.path_map]` URL template is used, and failing that the web UI.  The
+//! same mapping takes a local folder back to its source for Explorer's
+//! "Search here with Find Anything" entry.
+
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use find_common::api::SearchResult;
+use find_common::config::ClientConfig;
+use find_common::path_map::PathMapper;
+
+/// What to do with a selected hit.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Open the file with its default app.
+    Open(PathBuf),
+    /// Show the file selected in an Explorer window.
+    Reveal(PathBuf),
+    /// Open this web UI URL in the default browser.
+    Web(String),
+}
+
+pub struct Launcher {
+    mapper: PathMapper,
+    server_url: String,
+}
+
+impl Launcher {
+    pub fn new(config: &ClientConfig) -> Self {
+        Self {
+            mapper: PathMapper::new(config),
+            server_url: config.server.url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Decide how to open `hit`; `reveal` asks for Explorer rather than the
+    /// default app.  Local paths that no longer exist fall back to a URL.
+    pub fn target(&self, hit: &SearchResult, reveal: bool) -> Target {
+        match self.mapper.local_path(&hit.source, &hit.path) {
+            Some(path) if path.exists() => {
+                if reveal || hit.archive_path.is_some() {
+                    Target::Reveal(path)
+                } else {
+                    Target::Open(path)
+                }
+            }
+            _ => {
+                let mapped = match hit.archive_path {
+                    None => self.mapper.url(&hit.source, &hit.path, hit.line_number),
+                    Some(_) => None,
+                };
+                Target::Web(mapped.unwrap_or_else(|| self.web_url(hit)))
+            }
+        }
+    }
+
+    /// Web UI search pre-filtered to `folder` (`source:<name>/<path>`), or
+    /// `None` when no source contains it.
+    pub fn folder_url(&self, folder: &Path) -> Option<String> {
+        let (source, rel) = self.mapper.source_path(folder)?;
+        let mut filter = if rel.is_empty() { source } else { format!("{source}/{rel}") };
+        if filter.contains(char::is_whitespace) {
+            filter = format!("\"{filter}\"");
+        }
+        let base = format!("{}/", self.server_url);
+        let url = reqwest::Url::parse_with_params(&base, &[("q", format!("source:{filter}"))]).ok()?;
+        Some(url.into())
+    }
+
+    /// The web UI's indexing errors panel.
+    pub fn errors_panel_url(&self) -> String {
+        format!("{}/settings?section=errors", self.server_url)
+    }
+
+    fn web_url(&self, hit: &SearchResult) -> String {
+        let mut params = vec![("view", "file"), ("fsource", hit.source.as_str()), ("path", hit.path.as_str())];
+        if let Some(inner) = &hit.archive_path {
+            params.push(("apath", inner.as_str()));
+        }
+        let base = format!("{}/", self.server_url);
+        match reqwest::Url::parse_with_params(&base, &params) {
+            Ok(mut url) => {
+                if hit.line_number > 0 {
+                    url.set_fragment(Some(&format!("L{}", hit.line_number)));
+                }
+                url.into()
+            }
+            Err(_) => base,
+        }
+    }
+}
+
+/// Carry out `target`.
+pub fn launch(target: &Target) {
+    match target {
+        Target::Open(path) => shell_open(path.as_os_str()),
+        Target::Web(url) => shell_open(OsStr::new(url)),
+        Target::Reveal(path) => reveal(path),
+    }
+}
+
+/// ShellExecute "open": the default app for a file, the browser for a URL.
+pub fn shell_open(target: &OsStr) {
+    let target_w: Vec<u16> = target.encode_wide().chain(std::iter::once(0)).collect();
+    let verb: Vec<u16> = "open\0".encode_utf16().collect();
+    unsafe {
+        windows_sys::Win32::UI::Shell::ShellExecuteW(
+            0,
+            verb.as_ptr(),
+            target_w.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL,
+        );
+    }
+}
+
+/// Show `path` selected in Explorer.  `/select` is unreliable for UNC paths,
+/// so for those the containing folder is opened instead (as `find-handler`
+/// does).
+fn reveal(path: &Path) {
+    use std::os::windows::process::CommandExt;
+    // `CREATE_NO_WINDOW` prevents a console flash.
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let safe = path.to_string_lossy().replace('"', "");
+    let is_local_drive = safe.len() >= 2 && safe.as_bytes()[1] == b':';
+    let raw = if is_local_drive {
+        format!("/select,\"{safe}\"")
+    } else {
+        let folder = path.parent().map(|p| p.to_string_lossy().replace('"', "")).unwrap_or(safe);
+        format!("\"{folder}\"")
+    };
+    if let Err(e) = std::process::Command::new("explorer.exe")
+        .raw_arg(&raw)
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+    {
+        tracing::warn!("launching explorer.exe: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use find_common::api::FileKind;
+
+    fn hit(source: &str, path: &str, archive_path: Option<&str>, line_number: usize) -> SearchResult {
+        SearchResult {
+            source: source.to_string(),
+            path: path.to_string(),
+            archive_path: archive_path.map(str::to_string),
+            line_number,
+            snippet: String::new(),
+            score: 0,
+            kind: FileKind::Text,
+            mtime: 0,
+            size: None,
+            context_lines: vec![],
+            duplicate_paths: vec![],
+            extra_matches: vec![],
+            hits_truncated: false,
+            hit_count: None,
+            metadata_match: false,
+            origin: None,
+            indexed_at: None,
+            source_last_scan: None,
+        }
+    }
+
+    fn launcher(path_map: &str) -> Launcher {
+        let toml = format!("[server]\nurl = \"http://server:8765\"\ntoken = \"t\"\n\n[cli.path_map]\n{path_map}");
+        let (config, _) = find_common::config::parse_client_config(&toml).unwrap();
+        Launcher::new(&config)
+    }
+
+    #[test]
+    fn local_hits_open_and_archive_members_reveal() {
+        let dir = std::env::temp_dir().join("find-tray-launch-test");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("a.zip"), b"").unwrap();
+        let launcher = launcher(&format!("docs = '{}'", dir.display()));
+        let file = dir.join("sub").join("a.zip");
+        assert_eq!(launcher.target(&hit("docs", "sub/a.zip", None, 0), false), Target::Open(file.clone()));
+        assert_eq!(launcher.target(&hit("docs", "sub/a.zip", None, 0), true), Target::Reveal(file.clone()));
+        assert_eq!(launcher.target(&hit("docs", "sub/a.zip", Some("x.txt"), 3), false), Target::Reveal(file));
+    }
+
+    #[test]
+    fn folders_map_to_the_deepest_source_root() {
+        let launcher = launcher(r"
+            home = 'C:\Users\me'
+            docs = 'C:\Users\me\Documents\'
+        ");
+        assert_eq!(
+            launcher.folder_url(Path::new(r"c:\users\me\Documents\Tax Returns")).as_deref(),
+            Some("http://server:8765/?q=source%3A%22docs%2FTax+Returns%22")
+        );
+        assert_eq!(
+            launcher.folder_url(Path::new(r"C:\Users\me")).as_deref(),
+            Some("http://server:8765/?q=source%3Ahome")
+        );
+        assert_eq!(launcher.folder_url(Path::new(r"C:\Users\meg")), None);
+        assert_eq!(launcher.folder_url(Path::new(r"D:\")), None);
+    }
+
+    #[test]
+    fn other_hits_open_in_the_web_ui() {
+        let launcher = launcher(r#"
+            [[cli.path_map.nas]]
+            prefix = "wiki"
+            url = "https://wiki.example/{path}"
+        "#);
+        assert_eq!(
+            launcher.target(&hit("nas", "a b/c.txt", Some("d.txt"), 12), false),
+            Target::Web("http://server:8765/?view=file&fsource=nas&path=a+b%2Fc.txt&apath=d.txt#L12".to_string())
+        );
+        assert_eq!(
+            launcher.target(&hit("nas", "wiki/Home Page.md", None, 0), false),
+            Target::Web("https://wiki.example/Home%20Page.md".to_string())
+        );
+    }
+}
@@ -1,9 +1,14 @@
 //! find-tray: Windows system tray app for find-anything.
 //!
 //! Starts at login (registered by `find-watch install`), shows service status,
-//! file counts, and provides quick actions for scan / start / stop.
+//! file counts, and provides quick actions for scan / start / stop, and for
+//! pausing indexing without stopping the service.
 //! Left-clicking the tray icon shows a borderless popup listing recently
-//! indexed files; right-clicking shows the context menu.
+//! indexed files; right-clicking shows the context menu.  Finished scans,
+//! indexing-error spikes and a backed-up server inbox are announced with
+//! balloon notifications.  A global hotkey opens a quick search window that
+//! searches the index as you type.  `find-tray --search-here <folder>`, run
+//! from Explorer's context menu, opens the web UI filtered to that folder.
 
 // Suppress the console window on Windows.
 #![cfg_attr(windows, windows_subsystem = "windows")]
@@ -18,12 +23,22 @@ fn main() {
 #[cfg(windows)]
 mod guid_icon;
 #[cfg(windows)]
+mod hotkey;
+#[cfg(windows)]
+mod launch;
+#[cfg(windows)]
 mod menu;
 #[cfg(windows)]
+mod notify;
+#[cfg(windows)]
 mod poller;
 #[cfg(windows)]
 mod popup;
 #[cfg(windows)]
+mod search_window;
+#[cfg(windows)]
+mod searcher;
+#[cfg(windows)]
 mod service_ctl;
 
 #[cfg(windows)]
@@ -40,7 +55,13 @@ use windows_sys::Win32::UI::WindowsAndMessaging::HICON;
 #[cfg(windows)]
 use anyhow::{Context, Result};
 #[cfg(windows)]
-use find_common::{api::RecentFile, config::ClientConfig};
+use find_common::{
+    api::{RecentFile, SearchResult},
+    config::ClientConfig,
+    control::{ControlRequest, ControlResponse},
+    pause::PauseFile,
+    watch_status::WatchStatus,
+};
 #[cfg(windows)]
 use tray_icon::{
     menu::MenuEvent,
@@ -86,9 +107,20 @@ fn show_error(title: &str, message: &str) {
 pub enum AppEvent {
     StatusUpdate {
         service_running: bool,
-        file_count: Option<u64>,
-        source_count: Option<usize>,
+        /// `None` while the server is unreachable.
+        stats: Option<poller::ServerStats>,
         recent_files: Vec<RecentFile>,
+        /// Newest indexing errors across sources, for the errors submenu.
+        recent_errors: Vec<poller::RecentError>,
+        /// find-watch's heartbeat file, if it has written one.
+        watch_status: Option<WatchStatus>,
+        /// The on-demand pause in force, if any.
+        pause: Option<PauseFile>,
+    },
+    /// Results of quick search number `seq`, or why it failed.
+    SearchResults {
+        seq: u64,
+        result: Result<Vec<SearchResult>, String>,
     },
 }
 
@@ -129,14 +161,30 @@ fn main() -> Result<()> {
         }
     };
 
+    // `--search-here <folder>` comes from Explorer's context menu: open the
+    // web UI filtered to that folder and exit without starting the tray.
+    if let Some(folder) = parse_search_here_arg() {
+        match launch::Launcher::new(&config).folder_url(&folder) {
+            Some(url) => launch::shell_open(std::ffi::OsStr::new(&url)),
+            None => show_error(
+                "Find Anything",
+                &format!("{}\n\nis not inside any source in\n{}", folder.display(), config_path.display()),
+            ),
+        }
+        return Ok(());
+    }
+
     let server_url = config.server.url.trim_end_matches('/').to_string();
     let token = config.server.token.clone();
-    let poll_interval_ms = config.tray.poll_interval_ms;
 
     // Register the popup window class and create the (hidden) popup window
     // eagerly so we have a valid HWND for the right-click context menu.
     popup::register_class().context("registering popup window class")?;
     let popup = popup::Popup::create().context("creating popup window")?;
+    search_window::register_class().context("registering search window class")?;
+    let search_window =
+        search_window::SearchWindow::create().context("creating search window")?;
+    let hotkey = register_hotkey(&config.tray.hotkey, search_window.hwnd());
 
     // Build event loop with user-event type for cross-thread messaging.
     let event_loop = EventLoop::<AppEvent>::with_user_event()
@@ -147,7 +195,20 @@ fn main() -> Result<()> {
 
     // Spawn background poller; it sends AppEvent via the mpsc channel.
     let (tx, rx) = mpsc::channel::<AppEvent>();
-    let poller = poller::spawn(tx, server_url, token, poll_interval_ms);
+    let searcher = searcher::spawn(tx.clone(), server_url.clone(), token.clone());
+    let pause_path = find_common::pause::pause_path(&config.scan);
+    let poller = poller::spawn(
+        tx,
+        poller::PollerSettings {
+            server_url,
+            token,
+            poll_interval_ms: config.tray.poll_interval_ms,
+            notify_interval_secs: config.tray.notify_interval_secs,
+            status_path: find_common::watch_status::status_path(&config.watch),
+            pause_path: pause_path.clone(),
+            recent_errors: config.tray.recent_errors,
+        },
+    );
 
     // Bridge the mpsc channel to the winit proxy in a helper thread.
     std::thread::spawn(move || {
@@ -158,13 +219,19 @@ fn main() -> Result<()> {
         }
     });
 
-    let tray_menu = menu::TrayMenu::new().context("building tray menu")?;
+    let silenced = !config.tray.notifications;
+    let hotkey_label = matches!(hotkey, Ok(true)).then_some(config.tray.hotkey.as_str());
+    let tray_menu =
+        menu::TrayMenu::new(silenced, hotkey_label, config.tray.recent_errors > 0)
+            .context("building tray menu")?;
 
     // Load HICONs for direct Shell_NotifyIconW calls via NIF_GUID.
     let active_hicon = guid_icon::load_hicon(include_bytes!("../assets/icon_active.ico"))
         .context("loading active icon")?;
     let stopped_hicon = guid_icon::load_hicon(include_bytes!("../assets/icon_stopped.ico"))
         .context("loading stopped icon")?;
+    let paused_hicon = guid_icon::load_paused_hicon(include_bytes!("../assets/icon_active.ico"))
+        .context("loading paused icon")?;
 
     // tray-icon registers the Shell icon with uID on build.  We immediately
     // replace it with a GUID-based registration so Windows can track the icon
@@ -186,6 +253,10 @@ fn main() -> Result<()> {
             "Find Anything \u{2014} Watcher Running",
         );
     }
+    if let Err(e) = &hotkey {
+        tracing::warn!("quick search hotkey: {e}");
+        unsafe { guid_icon::show_balloon(tray_hwnd, "Quick search hotkey", e, true); }
+    }
 
     let mut app = TrayApp {
         tray_icon,
@@ -193,12 +264,25 @@ fn main() -> Result<()> {
         tray_hwnd,
         active_hicon,
         stopped_hicon,
+        paused_hicon,
         config_path,
+        pause_path,
+        control_addr: find_common::control::control_address(&config.watch),
         service_running: false,
+        pause: None,
+        watch_paused: false,
         should_quit: false,
         poller,
         popup,
         last_recent_files: vec![],
+        last_errors: (vec![], 0),
+        notifier: notify::Notifier::new(&config.tray),
+        silenced,
+        search_window,
+        searcher,
+        launcher: launch::Launcher::new(&config),
+        search_seq: 0,
+        search_hits: vec![],
     };
 
     event_loop
@@ -218,12 +302,35 @@ struct TrayApp {
     /// Raw icon handles for the GUID-based Shell registration.  Process-lifetime.
     active_hicon: HICON,
     stopped_hicon: HICON,
+    paused_hicon: HICON,
     config_path: PathBuf,
+    /// `[scan] pause_file`, which find-watch and find-scan check.
+    pause_path: PathBuf,
+    /// find-watch's control pipe.  Pausing, resuming and scans go through
+    /// the watcher when it answers, and fall back to the pause file and a
+    /// find-scan of our own when it does not.
+    control_addr: Option<String>,
     service_running: bool,
+    /// The on-demand pause from the pause file, if one is in force.
+    pause: Option<PauseFile>,
+    /// find-watch reports itself paused (on demand or quiet hours).
+    watch_paused: bool,
     should_quit: bool,
     poller: poller::PollerHandle,
     popup: popup::Popup,
     last_recent_files: Vec<RecentFile>,
+    /// What the errors submenu currently lists, and the total it shows.
+    last_errors: (Vec<poller::RecentError>, usize),
+    notifier: notify::Notifier,
+    /// Notifications are switched off from the menu (or by config).
+    silenced: bool,
+    search_window: search_window::SearchWindow,
+    searcher: searcher::SearchHandle,
+    launcher: launch::Launcher,
+    /// Sequence number of the latest query; older results are dropped.
+    search_seq: u64,
+    /// The hits currently listed in the search window, in list order.
+    search_hits: Vec<SearchResult>,
 }
 
 #[cfg(windows)]
@@ -245,13 +352,43 @@ impl ApplicationHandler<AppEvent> for TrayApp {
         match event {
             AppEvent::StatusUpdate {
                 service_running,
-                file_count,
-                source_count,
+                stats,
                 recent_files,
+                recent_errors,
+                watch_status,
+                pause,
             } => {
                 self.service_running = service_running;
-                self.tray_menu
-                    .update_status(service_running, file_count, source_count);
+                self.watch_paused = watch_status.as_ref().is_some_and(|s| s.paused.is_some());
+                self.set_pause(pause);
+                self.tray_menu.update_status(
+                    service_running,
+                    stats.as_ref().map(|s| s.total_files()),
+                    stats.as_ref().map(|s| s.sources.len()),
+                );
+                if let Some(stats) = &stats {
+                    if let Err(e) = self.tray_menu.update_sources(&stats.sources, now_secs()) {
+                        tracing::warn!("updating sources menu: {e:#}");
+                    }
+                }
+
+                let error_total = stats.as_ref().map_or(0, |s| s.indexing_errors());
+                if (&recent_errors, error_total) != (&self.last_errors.0, self.last_errors.1) {
+                    if let Err(e) = self.tray_menu.update_errors(&recent_errors, error_total) {
+                        tracing::warn!("updating errors menu: {e:#}");
+                    }
+                    self.last_errors = (recent_errors, error_total);
+                }
+
+                if let Some(stats) = &stats {
+                    for n in self.notifier.check(stats) {
+                        if !self.silenced {
+                            unsafe {
+                                guid_icon::show_balloon(self.tray_hwnd, &n.title, &n.message, n.warning);
+                            }
+                        }
+                    }
+                }
 
                 // Update the popup list if it is currently visible.
                 self.last_recent_files = recent_files;
@@ -260,19 +397,19 @@ impl ApplicationHandler<AppEvent> for TrayApp {
                 }
 
                 // Swap tray icon and tooltip based on service state.
-                let hicon = if service_running {
-                    self.active_hicon
-                } else {
-                    self.stopped_hicon
-                };
-                let tooltip = if service_running {
-                    "Find Anything \u{2014} Watcher Running"
-                } else {
-                    "Find Anything \u{2014} Watcher Stopped"
-                };
+                let tooltip = watcher_tooltip(
+                    service_running,
+                    watch_status.as_ref(),
+                    self.pause.as_ref(),
+                );
                 unsafe {
-                    guid_icon::update_icon(self.tray_hwnd, hicon);
-                    guid_icon::update_tooltip(self.tray_hwnd, tooltip);
+                    guid_icon::update_icon(self.tray_hwnd, self.current_hicon());
+                    guid_icon::update_tooltip(self.tray_hwnd, &tooltip);
+                }
+            }
+            AppEvent::SearchResults { seq, result } => {
+                if seq == self.search_seq {
+                    self.show_search_results(result);
                 }
             }
         }
@@ -292,6 +429,17 @@ impl ApplicationHandler<AppEvent> for TrayApp {
             self.poller.set_active(false);
         }
 
+        if search_window::take_hotkey() {
+            self.open_search();
+        }
+        if search_window::take_query_change() {
+            self.search_seq += 1;
+            self.searcher.search(self.search_seq, self.search_window.query());
+        }
+        if let Some(reveal) = search_window::take_open_request() {
+            self.open_search_hit(reveal);
+        }
+
         // Poll tray icon events (clicks).
         while let Ok(tray_event) = TrayIconEvent::receiver().try_recv() {
             match tray_event {
@@ -357,10 +505,32 @@ impl ApplicationHandler<AppEvent> for TrayApp {
             if cmd_id == parse(self.tray_menu.quit_id()) {
                 self.should_quit = true;
                 event_loop.exit();
+            } else if self
+                .tray_menu
+                .is_errors_item(&tray_icon::menu::MenuId::new(cmd_id.to_string()))
+            {
+                launch::shell_open(std::ffi::OsStr::new(&self.launcher.errors_panel_url()));
+            } else if cmd_id == parse(self.tray_menu.search_id()) {
+                self.open_search();
             } else if cmd_id == parse(self.tray_menu.scan_id()) {
-                self.run_scan();
+                self.run_scan(None);
+            } else if let Some(source) = self
+                .tray_menu
+                .source_to_scan(&tray_icon::menu::MenuId::new(cmd_id.to_string()))
+            {
+                self.run_scan(Some(source));
             } else if cmd_id == parse(self.tray_menu.toggle_id()) {
                 self.toggle_service();
+            } else if cmd_id == parse(self.tray_menu.pause_1h_id()) {
+                self.pause_indexing(Some(3600));
+            } else if cmd_id == parse(self.tray_menu.pause_4h_id()) {
+                self.pause_indexing(Some(4 * 3600));
+            } else if cmd_id == parse(self.tray_menu.pause_id()) {
+                self.pause_indexing(None);
+            } else if cmd_id == parse(self.tray_menu.resume_id()) {
+                self.resume_indexing();
+            } else if cmd_id == parse(self.tray_menu.silence_id()) {
+                self.toggle_silence();
             } else if cmd_id == parse(self.tray_menu.config_id()) {
                 self.open_config();
             }
@@ -375,11 +545,7 @@ impl ApplicationHandler<AppEvent> for TrayApp {
         // tray-icon re-adds its uID=1 icon in its own wndproc first; we then
         // replace it with our stable GUID version.
         if guid_icon::NEED_REREGISTER.swap(false, Ordering::Relaxed) {
-            let hicon = if self.service_running {
-                self.active_hicon
-            } else {
-                self.stopped_hicon
-            };
+            let hicon = self.current_hicon();
             let tooltip = if self.service_running {
                 "Find Anything \u{2014} Watcher Running"
             } else {
@@ -417,26 +583,54 @@ impl TrayApp {
         if event.id == self.tray_menu.quit_id() {
             self.should_quit = true;
             event_loop.exit();
+        } else if self.tray_menu.is_errors_item(&event.id) {
+            launch::shell_open(std::ffi::OsStr::new(&self.launcher.errors_panel_url()));
+        } else if event.id == self.tray_menu.search_id() {
+            self.open_search();
         } else if event.id == self.tray_menu.scan_id() {
-            self.run_scan();
+            self.run_scan(None);
+        } else if let Some(source) = self.tray_menu.source_to_scan(&event.id) {
+            self.run_scan(Some(source));
         } else if event.id == self.tray_menu.toggle_id() {
             self.toggle_service();
+        } else if event.id == self.tray_menu.pause_1h_id() {
+            self.pause_indexing(Some(3600));
+        } else if event.id == self.tray_menu.pause_4h_id() {
+            self.pause_indexing(Some(4 * 3600));
+        } else if event.id == self.tray_menu.pause_id() {
+            self.pause_indexing(None);
+        } else if event.id == self.tray_menu.resume_id() {
+            self.resume_indexing();
+        } else if event.id == self.tray_menu.silence_id() {
+            self.toggle_silence();
         } else if event.id == self.tray_menu.config_id() {
             self.open_config();
         }
     }
 
-    fn run_scan(&self) {
+    /// Scan every source, or only `source`: find-watch starts the scan
+    /// when it is running, otherwise find-scan is launched from here.
+    fn run_scan(&self, source: Option<&str>) {
+        let request = ControlRequest::Scan { source: source.map(str::to_string), force: false };
+        match self.ask_watcher(&request) {
+            Some(Ok(_)) => return,
+            Some(Err(e)) => {
+                show_error("Find Anything — Scan Error", &format!("{e:#}"));
+                return;
+            }
+            None => {}
+        }
         let scan_exe = std::env::current_exe()
             .ok()
             .and_then(|p| p.parent().map(|d| d.join("find-scan.exe")))
             .unwrap_or_else(|| PathBuf::from("find-scan.exe"));
 
-        if let Err(e) = std::process::Command::new(&scan_exe)
-            .arg("--config")
-            .arg(&self.config_path)
-            .spawn()
-        {
+        let mut cmd = std::process::Command::new(&scan_exe);
+        cmd.arg("--config").arg(&self.config_path);
+        if let Some(source) = source {
+            cmd.arg("--source").arg(source);
+        }
+        if let Err(e) = cmd.spawn() {
             show_error(
                 "Find Anything — Scan Error",
                 &format!("Failed to launch find-scan.exe:\n{e}"),
@@ -467,28 +661,193 @@ impl TrayApp {
         }
     }
 
+    /// Stopped, paused or running.
+    fn current_hicon(&self) -> HICON {
+        if !self.service_running {
+            self.stopped_hicon
+        } else if self.pause.is_some() || self.watch_paused {
+            self.paused_hicon
+        } else {
+            self.active_hicon
+        }
+    }
+
+    /// Pause indexing for `secs` seconds, or until resumed, through
+    /// find-watch or by writing the pause file it checks.  The service keeps running and keeps
+    /// collecting changes.
+    fn pause_indexing(&mut self, secs: Option<i64>) {
+        let until = secs.map(|s| now_secs() + s);
+        let result = match self.ask_watcher(&ControlRequest::Pause { until }) {
+            Some(resp) => resp.map(|_| ()),
+            None => find_common::pause::write_pause(&self.pause_path, until),
+        };
+        match result {
+            Ok(()) => {
+                self.set_pause(Some(PauseFile { until }));
+                unsafe { guid_icon::update_icon(self.tray_hwnd, self.current_hicon()); }
+                self.poller.poll_once();
+            }
+            Err(e) => show_error(
+                "Find Anything \u{2014} Pause Error",
+                &format!(
+                    "Failed to pause indexing:\n{e:#}\n\n\
+                     If the watcher runs as a service, set [scan] pause_file to a path \
+                     both the service and your account can write."
+                ),
+            ),
+        }
+    }
+
+    fn resume_indexing(&mut self) {
+        let result = match self.ask_watcher(&ControlRequest::Resume) {
+            Some(resp) => resp.map(|_| ()),
+            None => find_common::pause::clear_pause(&self.pause_path).map(|_| ()),
+        };
+        match result {
+            Ok(_) => {
+                self.set_pause(None);
+                self.watch_paused = false;
+                unsafe { guid_icon::update_icon(self.tray_hwnd, self.current_hicon()); }
+                self.poller.poll_once();
+            }
+            Err(e) => show_error(
+                "Find Anything \u{2014} Pause Error",
+                &format!("Failed to resume indexing:\n{e:#}"),
+            ),
+        }
+    }
+
+    /// Send `request` to find-watch; `None` when it is not listening.
+    fn ask_watcher(&self, request: &ControlRequest) -> Option<Result<ControlResponse>> {
+        let addr = self.control_addr.as_deref()?;
+        match find_common::control::send(addr, request) {
+            Ok(resp) => Some(resp.into_result()),
+            Err(e) => {
+                tracing::debug!("{e:#}");
+                None
+            }
+        }
+    }
+
+    fn set_pause(&mut self, pause: Option<PauseFile>) {
+        self.tray_menu.update_pause(pause.as_ref().map(pause_label).as_deref());
+        self.pause = pause;
+    }
+
+    /// Silencing only stops balloons from being shown; the poller keeps
+    /// feeding the notifier so un-silencing does not replay old events.
+    fn toggle_silence(&mut self) {
+        self.silenced = !self.silenced;
+        self.tray_menu.silence_item.set_checked(self.silenced);
+    }
+
     fn open_config(&self) {
         // ShellExecute "open" on the config file opens it in the default editor.
-        use std::os::windows::ffi::OsStrExt;
-        let path_wide: Vec<u16> = self
-            .config_path
-            .as_os_str()
-            .encode_wide()
-            .chain(std::iter::once(0))
-            .collect();
-        let verb: Vec<u16> = "open\0".encode_utf16().collect();
-
-        unsafe {
-            windows_sys::Win32::UI::Shell::ShellExecuteW(
-                0,
-                verb.as_ptr(),
-                path_wide.as_ptr(),
-                std::ptr::null(),
-                std::ptr::null(),
-                windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL,
-            );
+        launch::shell_open(self.config_path.as_os_str());
+    }
+
+    fn open_search(&self) {
+        if self.popup.is_visible() {
+            self.popup.hide();
+            self.poller.set_active(false);
+        }
+        self.search_window.show();
+    }
+
+    fn show_search_results(&mut self, result: Result<Vec<SearchResult>, String>) {
+        match result {
+            Ok(hits) => {
+                if hits.is_empty() && !self.search_window.query().trim().is_empty() {
+                    self.search_window.set_message("No matches");
+                } else {
+                    self.search_window.set_results(&hits);
+                }
+                self.search_hits = hits;
+            }
+            Err(e) => {
+                self.search_window.set_message(&e);
+                self.search_hits.clear();
+            }
+        }
+    }
+
+    /// Open the selected search hit, or show it in Explorer with `reveal`.
+    fn open_search_hit(&self, reveal: bool) {
+        let Some(hit) = self.search_window.selected().and_then(|i| self.search_hits.get(i)) else {
+            return;
+        };
+        self.search_window.hide();
+        launch::launch(&self.launcher.target(hit, reveal));
+    }
+}
+
+/// Tray tooltip: service state, plus what the watcher reports in its
+/// heartbeat while that is fresh.
+#[cfg(windows)]
+fn watcher_tooltip(
+    service_running: bool,
+    status: Option<&WatchStatus>,
+    pause: Option<&PauseFile>,
+) -> String {
+    if !service_running {
+        return "Find Anything \u{2014} Watcher Stopped".to_string();
+    }
+    let now = now_secs();
+    match status {
+        Some(s) if s.is_stale(now) => "Find Anything \u{2014} Watcher Not Responding".to_string(),
+        Some(WatchStatus { paused: Some(reason), .. }) => {
+            format!("Find Anything \u{2014} Indexing Paused ({reason})")
+        }
+        // Paused from the menu; the heartbeat has not caught up yet.
+        _ if pause.is_some() => format!(
+            "Find Anything \u{2014} Indexing Paused ({})",
+            pause.map(pause_label).unwrap_or_default()
+        ),
+        Some(s) if s.spooled_batches > 0 => format!(
+            "Find Anything \u{2014} Server Unreachable ({} batches waiting)",
+            s.spooled_batches
+        ),
+        Some(s) if s.pending_paths > 0 => format!(
+            "Find Anything \u{2014} Watcher Running ({} changes pending)",
+            s.pending_paths
+        ),
+        _ => "Find Anything \u{2014} Watcher Running".to_string(),
+    }
+}
+
+/// "until resumed", "until 14:30", or "until Sat 09:00" beyond today.
+#[cfg(windows)]
+fn pause_label(pause: &PauseFile) -> String {
+    use chrono::{Local, TimeZone};
+    let Some(until) = pause.until else {
+        return "until resumed".to_string();
+    };
+    match Local.timestamp_opt(until, 0).single() {
+        Some(t) if t.date_naive() == Local::now().date_naive() => {
+            format!("until {}", t.format("%H:%M"))
         }
+        Some(t) => format!("until {}", t.format("%a %H:%M")),
+        None => "until resumed".to_string(),
+    }
+}
+
+#[cfg(windows)]
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Register `[tray] hotkey` against the search window.  `Ok(false)` when
+/// none is configured; the error says why the configured one is unusable.
+#[cfg(windows)]
+fn register_hotkey(spec: &str, hwnd: HWND) -> std::result::Result<bool, String> {
+    if spec.trim().is_empty() {
+        return Ok(false);
     }
+    let key = hotkey::parse(spec).map_err(|e| format!("[tray] hotkey: {e}"))?;
+    hotkey::register(hwnd, key).map_err(|e| format!("{spec} is not available ({e:#})"))?;
+    Ok(true)
 }
 
 #[cfg(windows)]
@@ -506,3 +865,15 @@ fn parse_config_arg() -> PathBuf {
         .map(|p| PathBuf::from(p).join(".config").join("FindAnything").join("client.toml"))
         .unwrap_or_else(|| PathBuf::from("client.toml"))
 }
+
+/// The folder passed by Explorer's "Search here with Find Anything" entry.
+#[cfg(windows)]
+fn parse_search_here_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--search-here" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
@@ -1,38 +1,95 @@
 //! Tray icon menu construction and dynamic label updates.
 
-use tray_icon::menu::{Menu, MenuId, MenuItem, PredefinedMenuItem};
+use tray_icon::menu::{CheckMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu};
+
+use crate::poller::{RecentError, SourceSummary};
+
+/// One source's submenu under "Sources".
+pub struct SourceEntry {
+    pub name: String,
+    pub menu: Submenu,
+    pub last_scan_item: MenuItem,
+    pub pending_item: MenuItem,
+    pub scan_item: MenuItem,
+}
 
 /// Holds references to menu items that need runtime text updates.
 pub struct TrayMenu {
     pub menu: Menu,
     pub status_item: MenuItem,
     pub filecount_item: MenuItem,
+    pub sources_menu: Submenu,
+    /// One entry per source, rebuilt by [`TrayMenu::update_sources`] when
+    /// the set of sources changes.
+    pub source_entries: Vec<SourceEntry>,
+    pub errors_menu: Submenu,
+    /// One item per listed error, rebuilt by [`TrayMenu::update_errors`].
+    pub error_items: Vec<MenuItem>,
+    pub errors_panel_item: MenuItem,
+    pub search_item: MenuItem,
     pub scan_item: MenuItem,
     pub toggle_item: MenuItem,
+    pub pause_menu: Submenu,
+    pub pause_1h_item: MenuItem,
+    pub pause_4h_item: MenuItem,
+    pub pause_item: MenuItem,
+    pub resume_item: MenuItem,
+    pub silence_item: CheckMenuItem,
     pub config_item: MenuItem,
     pub quit_item: MenuItem,
 }
 
 impl TrayMenu {
-    pub fn new() -> anyhow::Result<Self> {
+    /// `hotkey` is shown next to "Quick Search" when one is registered.
+    /// `show_errors` adds the "Recent Errors" submenu.
+    pub fn new(silenced: bool, hotkey: Option<&str>, show_errors: bool) -> anyhow::Result<Self> {
         let menu = Menu::new();
 
         // Disabled informational labels at the top.
         let status_item = MenuItem::new("Watcher: Unknown", false, None);
         let filecount_item = MenuItem::new("Connecting to server\u{2026}", false, None);
+        let sources_menu = Submenu::new("Sources", false);
+        // Filled in once the server reports errors; disabled until then.
+        let errors_menu = Submenu::new("Recent Errors", false);
+        let errors_panel_item = MenuItem::new("Open Errors Panel", true, None);
+        errors_menu.append(&PredefinedMenuItem::separator())?;
+        errors_menu.append(&errors_panel_item)?;
 
         // Action items.
+        let search_text = match hotkey {
+            Some(key) => format!("Quick Search\t{key}"),
+            None => "Quick Search".to_string(),
+        };
+        let search_item = MenuItem::new(search_text, true, None);
         let scan_item = MenuItem::new("Run Full Scan", true, None);
         let toggle_item = MenuItem::new("Stop Watcher", true, None);
+        // Pausing leaves the service running and only holds off indexing.
+        let pause_menu = Submenu::new("Pause Indexing", true);
+        let pause_1h_item = MenuItem::new("For 1 Hour", true, None);
+        let pause_4h_item = MenuItem::new("For 4 Hours", true, None);
+        let pause_item = MenuItem::new("Until Resumed", true, None);
+        pause_menu.append(&pause_1h_item)?;
+        pause_menu.append(&pause_4h_item)?;
+        pause_menu.append(&pause_item)?;
+        let resume_item = MenuItem::new("Resume Indexing", false, None);
+        let silence_item = CheckMenuItem::new("Silence Notifications", true, silenced, None);
         let config_item = MenuItem::new("Open Config File", true, None);
         let quit_item = MenuItem::new("Quit Tray", true, None);
 
         menu.append(&status_item)?;
         menu.append(&filecount_item)?;
+        menu.append(&sources_menu)?;
+        if show_errors {
+            menu.append(&errors_menu)?;
+        }
         menu.append(&PredefinedMenuItem::separator())?;
+        menu.append(&search_item)?;
         menu.append(&scan_item)?;
+        menu.append(&pause_menu)?;
+        menu.append(&resume_item)?;
         menu.append(&toggle_item)?;
         menu.append(&PredefinedMenuItem::separator())?;
+        menu.append(&silence_item)?;
         menu.append(&config_item)?;
         menu.append(&quit_item)?;
 
@@ -40,8 +97,20 @@ impl TrayMenu {
             menu,
             status_item,
             filecount_item,
+            sources_menu,
+            source_entries: Vec::new(),
+            errors_menu,
+            error_items: Vec::new(),
+            errors_panel_item,
+            search_item,
             scan_item,
             toggle_item,
+            pause_menu,
+            pause_1h_item,
+            pause_4h_item,
+            pause_item,
+            resume_item,
+            silence_item,
             config_item,
             quit_item,
         })
@@ -59,9 +128,109 @@ impl TrayMenu {
         self.toggle_item.set_enabled(false);
     }
 
+    /// Reflect an on-demand pause: `label` describes it (e.g. "until
+    /// 14:30"), `None` when indexing is not paused.
+    pub fn update_pause(&self, label: Option<&str>) {
+        match label {
+            Some(label) => {
+                self.resume_item.set_text(format!("Resume Indexing (paused {label})"));
+                self.resume_item.set_enabled(true);
+            }
+            None => {
+                self.resume_item.set_text("Resume Indexing");
+                self.resume_item.set_enabled(false);
+            }
+        }
+    }
+
+    /// Show each source's file count, last scan and content backlog.  `now`
+    /// is the current Unix time, for the scan age.
+    pub fn update_sources(&mut self, sources: &[SourceSummary], now: i64) -> anyhow::Result<()> {
+        let unchanged = sources.len() == self.source_entries.len()
+            && sources.iter().zip(&self.source_entries).all(|(s, e)| s.name == e.name);
+        if !unchanged {
+            for entry in self.source_entries.drain(..) {
+                self.sources_menu.remove(&entry.menu)?;
+            }
+            for source in sources {
+                let entry = SourceEntry {
+                    name: source.name.clone(),
+                    menu: Submenu::new(&source.name, true),
+                    last_scan_item: MenuItem::new("", false, None),
+                    pending_item: MenuItem::new("", false, None),
+                    scan_item: MenuItem::new("Scan This Source Now", true, None),
+                };
+                entry.menu.append(&entry.last_scan_item)?;
+                entry.menu.append(&entry.pending_item)?;
+                entry.menu.append(&PredefinedMenuItem::separator())?;
+                entry.menu.append(&entry.scan_item)?;
+                self.sources_menu.append(&entry.menu)?;
+                self.source_entries.push(entry);
+            }
+        }
+
+        for (source, entry) in sources.iter().zip(&self.source_entries) {
+            entry.menu.set_text(format!(
+                "{}  ({} files)",
+                source.name.replace('&', "&&"),
+                format_num(source.total_files)
+            ));
+            entry.last_scan_item.set_text(match source.last_scan {
+                Some(t) => format!("Last scan: {}", format_age(now - t)),
+                None => "Not scanned yet".to_string(),
+            });
+            entry.pending_item.set_text(match source.files_pending_content {
+                0 => "Fully indexed".to_string(),
+                n => format!("{} files waiting for content", format_num(n)),
+            });
+        }
+        self.sources_menu.set_enabled(!sources.is_empty());
+        Ok(())
+    }
+
+    /// The source whose "Scan This Source Now" item has `id`, if any.
+    pub fn source_to_scan(&self, id: &MenuId) -> Option<&str> {
+        self.source_entries
+            .iter()
+            .find(|e| e.scan_item.id() == id)
+            .map(|e| e.name.as_str())
+    }
+
+    /// List `errors` (newest first) in the "Recent Errors" submenu, titled
+    /// with the server's `total`.
+    pub fn update_errors(&mut self, errors: &[RecentError], total: usize) -> anyhow::Result<()> {
+        for item in self.error_items.drain(..) {
+            self.errors_menu.remove(&item)?;
+        }
+        for (i, e) in errors.iter().enumerate() {
+            let item = MenuItem::new(error_label(e), true, None);
+            self.errors_menu.insert(&item, i)?;
+            self.error_items.push(item);
+        }
+        self.errors_menu.set_text(if total > 0 {
+            format!("Recent Errors ({})", format_num(total as u64))
+        } else {
+            "Recent Errors".to_string()
+        });
+        self.errors_menu.set_enabled(total > 0);
+        Ok(())
+    }
+
     /// Returns the MenuId of each action item for event matching.
+    pub fn errors_panel_id(&self) -> MenuId { self.errors_panel_item.id().clone() }
+    /// True for the panel item and every listed error, which all open the
+    /// web errors panel.
+    pub fn is_errors_item(&self, id: &MenuId) -> bool {
+        *id == self.errors_panel_id() || self.error_items.iter().any(|item| item.id() == id)
+    }
+    pub fn search_id(&self) -> MenuId { self.search_item.id().clone() }
     pub fn scan_id(&self) -> MenuId { self.scan_item.id().clone() }
     pub fn toggle_id(&self) -> MenuId { self.toggle_item.id().clone() }
+    pub fn pause_1h_id(&self) -> MenuId { self.pause_1h_item.id().clone() }
+    pub fn pause_4h_id(&self) -> MenuId { self.pause_4h_item.id().clone() }
+    pub fn pause_id(&self) -> MenuId { self.pause_item.id().clone() }
+    pub fn resume_id(&self) -> MenuId { self.resume_item.id().clone() }
+    pub fn silence_id(&self) -> MenuId { self.silence_item.id().clone() }
     pub fn config_id(&self) -> MenuId { self.config_item.id().clone() }
     pub fn quit_id(&self) -> MenuId { self.quit_item.id().clone() }
 
@@ -92,6 +261,32 @@ impl TrayMenu {
     }
 }
 
+/// `[source] path: error`, shortened to fit a menu line.
+fn error_label(e: &RecentError) -> String {
+    const MAX_CHARS: usize = 90;
+    let error = e.error.lines().next().unwrap_or_default();
+    let label = format!("[{}] {}: {}", e.source, e.path, error);
+    // `&` marks a mnemonic in Win32 menus.
+    let label = label.replace('&', "&&");
+    if label.chars().count() > MAX_CHARS {
+        let cut: String = label.chars().take(MAX_CHARS - 1).collect();
+        format!("{cut}\u{2026}")
+    } else {
+        label
+    }
+}
+
+/// "just now", "5 min ago", "3 h ago", "2 days ago".
+fn format_age(secs: i64) -> String {
+    match secs {
+        ..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86_399 => format!("{} h ago", secs / 3600),
+        86_400..=172_799 => "1 day ago".to_string(),
+        _ => format!("{} days ago", secs / 86_400),
+    }
+}
+
 fn format_num(n: u64) -> String {
     let s = n.to_string();
     let digits: Vec<char> = s.chars().collect();
@@ -118,4 +313,27 @@ mod tests {
         assert_eq!(format_num(42153), "42,153");
         assert_eq!(format_num(1_000_000), "1,000,000");
     }
+
+    #[test]
+    fn format_age_picks_a_unit() {
+        assert_eq!(format_age(-5), "just now");
+        assert_eq!(format_age(59), "just now");
+        assert_eq!(format_age(300), "5 min ago");
+        assert_eq!(format_age(3 * 3600 + 10), "3 h ago");
+        assert_eq!(format_age(86_400), "1 day ago");
+        assert_eq!(format_age(10 * 86_400), "10 days ago");
+    }
+
+    #[test]
+    fn error_label_is_one_short_line() {
+        let e = RecentError {
+            source: "docs".into(),
+            path: "a & b.pdf".into(),
+            error: "bad xref\nstack trace".into(),
+            last_seen: 0,
+        };
+        assert_eq!(error_label(&e), "[docs] a && b.pdf: bad xref");
+        let long = RecentError { error: "x".repeat(200), ..e };
+        assert_eq!(error_label(&long).chars().count(), 90);
+    }
 }
@@ -0,0 +1,181 @@
+//! Decides which tray notifications a new `/stats` snapshot warrants.
+//!
+//! Each snapshot is compared with the previous one: a source whose
+//! `last_scan` moved forward has finished a scan, and a rise in indexing
+//! errors of at least `error_spike_threshold` is a spike.  The inbox backlog
+//! warning fires once when `inbox_pending` crosses its threshold and re-arms
+//! when the backlog drains below it again.  The first snapshot only sets the
+//! baseline, so starting the tray never produces a burst of notifications.
+
+use std::collections::HashMap;
+
+use find_common::config::TrayConfig;
+
+use crate::poller::ServerStats;
+
+/// A notification to show as a balloon / toast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    /// Shown with the warning icon rather than the info icon.
+    pub warning: bool,
+}
+
+pub struct Notifier {
+    error_spike_threshold: usize,
+    inbox_backlog_threshold: usize,
+    /// `last_scan` per source from the previous snapshot; `None` until the
+    /// first one arrives.
+    last_scans: Option<HashMap<String, Option<i64>>>,
+    last_errors: usize,
+    backlog_reported: bool,
+}
+
+impl Notifier {
+    pub fn new(config: &TrayConfig) -> Self {
+        Self {
+            error_spike_threshold: config.error_spike_threshold,
+            inbox_backlog_threshold: config.inbox_backlog_threshold,
+            last_scans: None,
+            last_errors: 0,
+            backlog_reported: false,
+        }
+    }
+
+    /// Compare `stats` with the previous snapshot and return what to show.
+    /// Call this for every snapshot, even while notifications are silenced,
+    /// so that un-silencing does not replay old events.
+    pub fn check(&mut self, stats: &ServerStats) -> Vec<Notification> {
+        let mut out = Vec::new();
+        let scans: HashMap<String, Option<i64>> = stats
+            .sources
+            .iter()
+            .map(|s| (s.name.clone(), s.last_scan))
+            .collect();
+        let errors = stats.indexing_errors();
+
+        let Some(previous) = self.last_scans.replace(scans) else {
+            self.last_errors = errors;
+            self.backlog_reported = self.over_backlog(stats);
+            return out;
+        };
+
+        let finished: Vec<_> = stats
+            .sources
+            .iter()
+            .filter(|s| match (previous.get(&s.name), s.last_scan) {
+                (Some(before), Some(now)) => before.is_none_or(|b| now > b),
+                _ => false,
+            })
+            .collect();
+        match finished.as_slice() {
+            [] => {}
+            [source] => out.push(Notification {
+                title: "Scan complete".to_string(),
+                message: format!("{}: {} files indexed", source.name, source.total_files),
+                warning: false,
+            }),
+            many => out.push(Notification {
+                title: "Scans complete".to_string(),
+                message: many.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", "),
+                warning: false,
+            }),
+        }
+
+        let new_errors = errors.saturating_sub(self.last_errors);
+        if self.error_spike_threshold > 0 && new_errors >= self.error_spike_threshold {
+            out.push(Notification {
+                title: "Indexing errors".to_string(),
+                message: format!("{new_errors} new indexing errors ({errors} in total)"),
+                warning: true,
+            });
+        }
+        self.last_errors = errors;
+
+        let over = self.over_backlog(stats);
+        if over && !self.backlog_reported {
+            out.push(Notification {
+                title: "Server inbox backlog".to_string(),
+                message: format!("{} requests are waiting to be indexed", stats.inbox_pending),
+                warning: true,
+            });
+        }
+        self.backlog_reported = over;
+
+        out
+    }
+
+    fn over_backlog(&self, stats: &ServerStats) -> bool {
+        self.inbox_backlog_threshold > 0 && stats.inbox_pending >= self.inbox_backlog_threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::SourceSummary;
+
+    fn stats(sources: &[(&str, Option<i64>, usize)], inbox_pending: usize) -> ServerStats {
+        ServerStats {
+            sources: sources
+                .iter()
+                .map(|&(name, last_scan, errors)| SourceSummary {
+                    name: name.to_string(),
+                    total_files: 100,
+                    last_scan,
+                    indexing_error_count: errors,
+                    files_pending_content: 0,
+                })
+                .collect(),
+            inbox_pending,
+        }
+    }
+
+    fn notifier() -> Notifier {
+        Notifier::new(&TrayConfig { error_spike_threshold: 5, inbox_backlog_threshold: 50, ..Default::default() })
+    }
+
+    #[test]
+    fn first_snapshot_is_only_a_baseline() {
+        let mut n = notifier();
+        assert!(n.check(&stats(&[("docs", Some(10), 99)], 500)).is_empty());
+    }
+
+    #[test]
+    fn finished_scans_are_reported() {
+        let mut n = notifier();
+        n.check(&stats(&[("docs", Some(10), 0), ("code", None, 0)], 0));
+        assert!(n.check(&stats(&[("docs", Some(10), 0), ("code", None, 0)], 0)).is_empty());
+
+        let shown = n.check(&stats(&[("docs", Some(20), 0), ("code", None, 0)], 0));
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].message, "docs: 100 files indexed");
+
+        let shown = n.check(&stats(&[("docs", Some(30), 0), ("code", Some(30), 0)], 0));
+        assert_eq!(shown[0].message, "docs, code");
+        // A source added since the last snapshot has not "finished" anything.
+        assert!(n.check(&stats(&[("docs", Some(30), 0), ("code", Some(30), 0), ("new", Some(40), 0)], 0)).is_empty());
+    }
+
+    #[test]
+    fn error_spikes_compare_with_the_previous_snapshot() {
+        let mut n = notifier();
+        n.check(&stats(&[("docs", None, 10)], 0));
+        assert!(n.check(&stats(&[("docs", None, 14)], 0)).is_empty());
+        let shown = n.check(&stats(&[("docs", None, 19)], 0));
+        assert_eq!(shown.len(), 1);
+        assert!(shown[0].warning);
+        assert_eq!(shown[0].message, "5 new indexing errors (19 in total)");
+    }
+
+    #[test]
+    fn backlog_warns_once_until_it_drains() {
+        let mut n = notifier();
+        n.check(&stats(&[], 0));
+        assert_eq!(n.check(&stats(&[], 60)).len(), 1);
+        assert!(n.check(&stats(&[], 80)).is_empty());
+        assert!(n.check(&stats(&[], 10)).is_empty());
+        assert_eq!(n.check(&stats(&[], 50)).len(), 1);
+    }
+}
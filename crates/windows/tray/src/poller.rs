@@ -1,17 +1,22 @@
-//! Background thread that polls the Windows SCM for service state and the
-//! find-anything server for file counts and recent files.
+//! Background thread that polls the Windows SCM for service state, the
+//! find-anything server for file counts and recent files, find-watch's
+//! heartbeat file for its queue and upload state, and the pause file.
 //!
-//! Polling is demand-driven: the thread is idle (sleeping 100 ms) when the
-//! popup is closed, and active (polling every `poll_interval_ms`) while it is
-//! open.  A one-shot poll can also be requested for right-click menu refresh.
+//! Polling is demand-driven: the thread polls every `poll_interval_ms` while
+//! the popup is open, and otherwise only every `notify_interval_secs` so that
+//! notifications still fire.  A one-shot poll can also be requested for
+//! right-click menu refresh.
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use find_common::api::RecentFile;
+use find_common::api::{ErrorsResponse, RecentFile};
+use find_common::pause::read_pause;
+use find_common::watch_status::read_status;
 
 use crate::AppEvent;
 use crate::service_ctl;
@@ -36,13 +41,22 @@ impl PollerHandle {
     }
 }
 
+/// What the poller polls and how often.
+pub struct PollerSettings {
+    pub server_url: String,
+    pub token: String,
+    /// Refresh interval while the popup is open.
+    pub poll_interval_ms: u64,
+    /// Background refresh interval for notifications; 0 = none.
+    pub notify_interval_secs: u64,
+    pub status_path: PathBuf,
+    pub pause_path: PathBuf,
+    /// How many recent indexing errors to fetch for the errors submenu.
+    pub recent_errors: usize,
+}
+
 /// Spawn the background poller thread and return a handle to control it.
-pub fn spawn(
-    tx: Sender<AppEvent>,
-    server_url: String,
-    token: String,
-    poll_interval_ms: u64,
-) -> PollerHandle {
+pub fn spawn(tx: Sender<AppEvent>, settings: PollerSettings) -> PollerHandle {
     let active = Arc::new(AtomicBool::new(false));
     let poll_once = Arc::new(AtomicBool::new(false));
 
@@ -51,9 +65,7 @@ pub fn spawn(
 
     thread::Builder::new()
         .name("find-tray-poller".into())
-        .spawn(move || {
-            run(tx, server_url, token, poll_interval_ms, active_clone, poll_once_clone)
-        })
+        .spawn(move || run(tx, settings, active_clone, poll_once_clone))
         .expect("spawning poller thread");
 
     PollerHandle { active, poll_once }
@@ -61,31 +73,61 @@ pub fn spawn(
 
 fn run(
     tx: Sender<AppEvent>,
-    server_url: String,
-    token: String,
-    poll_interval_ms: u64,
+    settings: PollerSettings,
     active: Arc<AtomicBool>,
     poll_once: Arc<AtomicBool>,
 ) {
+    let PollerSettings {
+        server_url,
+        token,
+        poll_interval_ms,
+        notify_interval_secs,
+        status_path,
+        pause_path,
+        recent_errors: error_limit,
+    } = settings;
     let client = reqwest::blocking::Client::builder()
         .timeout(Duration::from_secs(5))
         .build()
         .unwrap_or_default();
 
+    let mut stats_cache = StatsCache::default();
+    let mut errors_cache = ErrorsCache::default();
+    let background = (notify_interval_secs > 0).then(|| Duration::from_secs(notify_interval_secs));
+    // The first background poll runs straight away so notifications have a
+    // baseline to compare against.
+    let mut next_background = Instant::now();
+
     loop {
         let is_active = active.load(Ordering::Relaxed);
-        let do_once = poll_once.swap(false, Ordering::Relaxed);
+        let mut do_once = poll_once.swap(false, Ordering::Relaxed);
+        if let Some(interval) = background {
+            if Instant::now() >= next_background {
+                next_background = Instant::now() + interval;
+                do_once = true;
+            }
+        }
 
         if is_active || do_once {
             let service_running = service_ctl::is_service_running();
-            let (file_count, source_count) = query_status(&client, &server_url, &token);
+            let stats = query_status(&client, &server_url, &token, &mut stats_cache);
             let recent_files = query_recent(&client, &server_url, &token);
+            if let Some(stats) = &stats {
+                errors_cache.refresh(&client, &server_url, &token, stats, error_limit);
+            }
+            let watch_status = read_status(&status_path).ok().flatten();
+            let pause = read_pause(&pause_path)
+                .ok()
+                .flatten()
+                .filter(|p| p.active(crate::now_secs()));
 
             let event = AppEvent::StatusUpdate {
                 service_running,
-                file_count,
-                source_count,
+                stats,
                 recent_files,
+                recent_errors: errors_cache.errors.clone(),
+                watch_status,
+                pause,
             };
 
             if tx.send(event).is_err() {
@@ -104,34 +146,150 @@ fn run(
     }
 }
 
+/// The parts of `/stats` the tray shows in its menu or watches for
+/// notifications.
+#[derive(Debug, Clone, Default)]
+pub struct ServerStats {
+    pub sources: Vec<SourceSummary>,
+    /// Bulk requests waiting in the server's inbox.
+    pub inbox_pending: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceSummary {
+    pub name: String,
+    pub total_files: u64,
+    /// When the last scan of this source finished (Unix seconds).
+    pub last_scan: Option<i64>,
+    pub indexing_error_count: usize,
+    /// Files indexed but whose content has not been stored yet.
+    pub files_pending_content: u64,
+}
+
+impl ServerStats {
+    pub fn total_files(&self) -> u64 {
+        self.sources.iter().map(|s| s.total_files).sum()
+    }
+
+    pub fn indexing_errors(&self) -> usize {
+        self.sources.iter().map(|s| s.indexing_error_count).sum()
+    }
+}
+
+/// Last `/stats` result and its `ETag`, so unchanged stats come back as a
+/// body-less 304 instead of being recomputed by the server on every poll.
+#[derive(Default)]
+struct StatsCache {
+    etag: Option<String>,
+    stats: Option<ServerStats>,
+}
+
 fn query_status(
     client: &reqwest::blocking::Client,
     server_url: &str,
     token: &str,
-) -> (Option<u64>, Option<usize>) {
+    cache: &mut StatsCache,
+) -> Option<ServerStats> {
     let url = format!("{server_url}/api/v1/stats");
-    let resp = match client.get(&url).bearer_auth(token).send() {
-        Ok(r) => r,
-        Err(_) => return (None, None),
-    };
+    let mut req = client.get(&url).bearer_auth(token);
+    if let Some(etag) = &cache.etag {
+        req = req.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let resp = req.send().ok()?;
 
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return cache.stats.clone();
+    }
     if !resp.status().is_success() {
-        return (None, None);
+        return None;
     }
 
-    let json: serde_json::Value = match resp.json() {
-        Ok(v) => v,
-        Err(_) => return (None, None),
-    };
+    let etag = resp
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
 
-    if let Some(sources) = json.get("sources").and_then(|v| v.as_array()) {
-        let total_files: u64 = sources
+    let json: serde_json::Value = resp.json().ok()?;
+
+    let sources = json.get("sources").and_then(|v| v.as_array())?;
+    let stats = ServerStats {
+        sources: sources
             .iter()
-            .filter_map(|s| s.get("total_files").and_then(|v| v.as_u64()))
-            .sum();
-        (Some(total_files), Some(sources.len()))
-    } else {
-        (None, None)
+            .map(|s| SourceSummary {
+                name: s.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                total_files: s.get("total_files").and_then(|v| v.as_u64()).unwrap_or(0),
+                last_scan: s.get("last_scan").and_then(|v| v.as_i64()),
+                indexing_error_count: s
+                    .get("indexing_error_count")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as usize,
+                files_pending_content: s
+                    .get("files_pending_content")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0),
+            })
+            .collect(),
+        inbox_pending: json.get("inbox_pending").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+    };
+    *cache = StatsCache { etag, stats: Some(stats.clone()) };
+    Some(stats)
+}
+
+/// One entry of the errors submenu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentError {
+    pub source: String,
+    pub path: String,
+    pub error: String,
+    /// Unix seconds the error was last reported.
+    pub last_seen: i64,
+}
+
+/// The newest indexing errors across sources.  `/api/v1/errors` is queried
+/// per source, so this is only refreshed when the total error count in
+/// `/stats` changes.
+#[derive(Default)]
+struct ErrorsCache {
+    total: Option<usize>,
+    errors: Vec<RecentError>,
+}
+
+impl ErrorsCache {
+    fn refresh(
+        &mut self,
+        client: &reqwest::blocking::Client,
+        server_url: &str,
+        token: &str,
+        stats: &ServerStats,
+        limit: usize,
+    ) {
+        let total = stats.indexing_errors();
+        if limit == 0 || self.total == Some(total) {
+            return;
+        }
+        let mut errors = Vec::new();
+        for source in stats.sources.iter().filter(|s| s.indexing_error_count > 0) {
+            let resp = client
+                .get(format!("{server_url}/api/v1/errors"))
+                .bearer_auth(token)
+                .query(&[("source", source.name.as_str()), ("limit", &limit.to_string())])
+                .send()
+                .ok()
+                .filter(|r| r.status().is_success())
+                .and_then(|r| r.json::<ErrorsResponse>().ok());
+            // Try again on the next poll rather than cache a partial list.
+            let Some(resp) = resp else { return };
+            errors.extend(resp.errors.into_iter().map(|e| RecentError {
+                source: source.name.clone(),
+                path: e.path,
+                error: e.error,
+                last_seen: e.last_seen,
+            }));
+        }
+        errors.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+        errors.truncate(limit);
+        *self = ErrorsCache { total: Some(total), errors };
     }
 }
 
@@ -11,7 +11,7 @@ use anyhow::Result;
 use find_common::api::RecentFile;
 
 use windows_sys::Win32::Foundation::{GetLastError, HWND, LPARAM, LRESULT, RECT, WPARAM};
-use windows_sys::Win32::Graphics::Gdi::CreateFontW;
+use windows_sys::Win32::Graphics::Gdi::{CreateFontW, HFONT};
 use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows_sys::Win32::UI::WindowsAndMessaging::{
     ChangeWindowMessageFilterEx, CreateWindowExW, DefWindowProcW, DestroyWindow, GetClientRect,
@@ -263,18 +263,8 @@ impl Popup {
         LISTBOX_HWND.store(listbox as isize, Ordering::Relaxed);
 
         // Apply Segoe UI 10pt with ClearType so the list reads cleanly.
-        // -13 logical units ≈ 10pt at 96 DPI.  The HFONT is intentionally
-        // leaked — it lives for the process lifetime alongside the listbox.
-        let face: Vec<u16> = "Segoe UI\0".encode_utf16().collect();
-        let hfont = unsafe {
-            CreateFontW(
-                -13, 0, 0, 0,
-                FW_NORMAL, 0, 0, 0,
-                ANSI_CHARSET, OUT_DEFAULT_PRECIS, CLIP_DEFAULT_PRECIS,
-                CLEARTYPE_QUALITY, DEFAULT_PITCH,
-                face.as_ptr(),
-            )
-        };
+        // -13 logical units ≈ 10pt at 96 DPI.
+        let hfont = ui_font(-13);
         if hfont != 0 {
             unsafe {
                 SendMessageW(listbox, WM_SETFONT, hfont as WPARAM, 1);
@@ -353,7 +343,23 @@ pub fn take_pending_command() -> Option<u32> {
     if id != 0 { Some(id) } else { None }
 }
 
-fn add_row(listbox: HWND, text: &str) {
+/// Segoe UI with ClearType at `height` logical units (negative = character
+/// height).  The HFONT is intentionally leaked — it lives for the process
+/// lifetime alongside the controls that use it.
+pub fn ui_font(height: i32) -> HFONT {
+    let face: Vec<u16> = "Segoe UI\0".encode_utf16().collect();
+    unsafe {
+        CreateFontW(
+            height, 0, 0, 0,
+            FW_NORMAL, 0, 0, 0,
+            ANSI_CHARSET, OUT_DEFAULT_PRECIS, CLIP_DEFAULT_PRECIS,
+            CLEARTYPE_QUALITY, DEFAULT_PITCH,
+            face.as_ptr(),
+        )
+    }
+}
+
+pub fn add_row(listbox: HWND, text: &str) {
     let wide: Vec<u16> = format!("{text}\0").encode_utf16().collect();
     unsafe { SendMessageW(listbox, LB_ADDSTRING, 0, wide.as_ptr() as LPARAM) };
 }
@@ -0,0 +1,362 @@
+//! Always-on-top quick search window, opened by the global hotkey or from the
+//! tray menu.
+//!
+//! An edit box above a result list.  Every edit re-runs the search; Up/Down
+//! move through the results, Enter opens the selected one, Ctrl+Enter shows
+//! it in Explorer, and Escape or clicking elsewhere hides the window.  The
+//! edit control is subclassed because a single-line edit otherwise swallows
+//! those keys.  Like [`crate::popup`], the WndProcs only set flags that the
+//! main thread drains in `about_to_wait`.
+
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU8, Ordering};
+
+use anyhow::Result;
+use find_common::api::SearchResult;
+
+use windows_sys::Win32::Foundation::{GetLastError, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetKeyState, SetFocus};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    CallWindowProcW, CreateWindowExW, DefWindowProcW, DestroyWindow, GetWindowTextLengthW,
+    GetWindowTextW, IsWindowVisible, RegisterClassExW, SendMessageW, SetForegroundWindow,
+    SetWindowLongPtrW, SetWindowPos, ShowWindow, SystemParametersInfoW, CS_DROPSHADOW,
+    GWLP_WNDPROC, SPI_GETWORKAREA, SWP_NOZORDER, SW_HIDE, SW_SHOW, WM_ACTIVATE, WM_KEYDOWN,
+    WNDCLASSEXW, WNDPROC, WS_BORDER, WS_CHILD, WS_CLIPCHILDREN, WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
+    WS_POPUP, WS_VISIBLE, WS_VSCROLL,
+};
+
+use crate::popup::{add_row, ui_font};
+
+const WM_COMMAND: u32 = 0x0111;
+const WM_CHAR: u32 = 0x0102;
+const WM_HOTKEY: u32 = 0x0312;
+const WM_SETFONT: u32 = 0x0030;
+const EM_SETSEL: u32 = 0x00B1;
+
+// Control styles and notifications.
+const ES_AUTOHSCROLL: u32 = 0x0080;
+const EN_CHANGE: usize = 0x0300;
+const LBS_NOTIFY: u32 = 0x0001;
+const LBS_NOINTEGRALHEIGHT: u32 = 0x0100;
+const LBN_DBLCLK: usize = 2;
+const LB_RESETCONTENT: u32 = 0x0184;
+const LB_SETCURSEL: u32 = 0x0186;
+const LB_GETCURSEL: u32 = 0x0188;
+const LB_GETCOUNT: u32 = 0x018B;
+
+const VK_RETURN: usize = 0x0D;
+const VK_ESCAPE: usize = 0x1B;
+const VK_UP: usize = 0x26;
+const VK_DOWN: usize = 0x28;
+const VK_CONTROL: i32 = 0x11;
+const WA_INACTIVE: usize = 0;
+
+const WINDOW_WIDTH: i32 = 640;
+const WINDOW_HEIGHT: i32 = 420;
+const PADDING: i32 = 6;
+const EDIT_HEIGHT: i32 = 28;
+
+/// Set when `WM_HOTKEY` arrives; drained by [`take_hotkey`].
+static HOTKEY_PRESSED: AtomicBool = AtomicBool::new(false);
+/// Set when the query text changes; drained by [`take_query_change`].
+static QUERY_CHANGED: AtomicBool = AtomicBool::new(false);
+/// Pending open request: 0 = none, 1 = open, 2 = show in Explorer.
+static OPEN_REQUEST: AtomicU8 = AtomicU8::new(0);
+
+static WINDOW_HWND: AtomicIsize = AtomicIsize::new(0);
+static EDIT_HWND: AtomicIsize = AtomicIsize::new(0);
+static LIST_HWND: AtomicIsize = AtomicIsize::new(0);
+/// The edit control's original WndProc, which the subclass forwards to.
+static EDIT_PROC: AtomicIsize = AtomicIsize::new(0);
+
+fn class_name_w() -> Vec<u16> {
+    "FindAnythingSearch\0".encode_utf16().collect()
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_HOTKEY => {
+            HOTKEY_PRESSED.store(true, Ordering::Relaxed);
+            0
+        }
+        WM_ACTIVATE => {
+            if (wparam & 0xFFFF) == WA_INACTIVE {
+                ShowWindow(hwnd, SW_HIDE);
+            }
+            0
+        }
+        WM_COMMAND => {
+            let code = wparam >> 16;
+            if lparam == EDIT_HWND.load(Ordering::Relaxed) && code == EN_CHANGE {
+                QUERY_CHANGED.store(true, Ordering::Relaxed);
+            } else if lparam == LIST_HWND.load(Ordering::Relaxed) && code == LBN_DBLCLK {
+                request_open();
+            }
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+/// Subclass procedure for the edit control: navigation and open keys act on
+/// the result list instead of the text.
+unsafe extern "system" fn edit_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match (msg, wparam) {
+        (WM_KEYDOWN, VK_DOWN) => {
+            move_selection(1);
+            return 0;
+        }
+        (WM_KEYDOWN, VK_UP) => {
+            move_selection(-1);
+            return 0;
+        }
+        (WM_KEYDOWN, VK_RETURN) => {
+            request_open();
+            return 0;
+        }
+        (WM_KEYDOWN, VK_ESCAPE) => {
+            ShowWindow(WINDOW_HWND.load(Ordering::Relaxed), SW_HIDE);
+            return 0;
+        }
+        // The WM_CHARs that follow Enter (CR, or LF with Ctrl) and Escape
+        // would make the edit beep.
+        (WM_CHAR, 0x0D | 0x0A | 0x1B) => return 0,
+        _ => {}
+    }
+    let original: WNDPROC = std::mem::transmute(EDIT_PROC.load(Ordering::Relaxed));
+    CallWindowProcW(original, hwnd, msg, wparam, lparam)
+}
+
+unsafe fn request_open() {
+    let reveal = GetKeyState(VK_CONTROL) < 0;
+    OPEN_REQUEST.store(if reveal { 2 } else { 1 }, Ordering::Relaxed);
+}
+
+unsafe fn move_selection(delta: isize) {
+    let list = LIST_HWND.load(Ordering::Relaxed);
+    let count = SendMessageW(list, LB_GETCOUNT, 0, 0);
+    if count <= 0 {
+        return;
+    }
+    let current = SendMessageW(list, LB_GETCURSEL, 0, 0);
+    let next = if current < 0 { 0 } else { (current + delta).clamp(0, count - 1) };
+    SendMessageW(list, LB_SETCURSEL, next as WPARAM, 0);
+}
+
+/// Register the search window class.  Safe to call multiple times.
+pub fn register_class() -> Result<()> {
+    let class_name = class_name_w();
+    let hinstance = unsafe { GetModuleHandleW(std::ptr::null()) };
+
+    let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: CS_DROPSHADOW,
+        lpfnWndProc: Some(wnd_proc),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: hinstance,
+        hIcon: 0,
+        hCursor: 0,
+        // COLOR_WINDOW + 1 = 6: use system window background colour.
+        hbrBackground: 6,
+        lpszMenuName: std::ptr::null(),
+        lpszClassName: class_name.as_ptr(),
+        hIconSm: 0,
+    };
+
+    let atom = unsafe { RegisterClassExW(&wc) };
+    if atom == 0 {
+        let err = unsafe { GetLastError() };
+        const ERROR_CLASS_ALREADY_EXISTS: u32 = 1410;
+        if err != ERROR_CLASS_ALREADY_EXISTS {
+            anyhow::bail!("RegisterClassExW failed: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// The search window with its edit and list children.
+pub struct SearchWindow {
+    hwnd: HWND,
+    edit: HWND,
+    list: HWND,
+}
+
+impl SearchWindow {
+    /// Create the window (hidden).  Call [`SearchWindow::show`] to display it.
+    pub fn create() -> Result<Self> {
+        let class_name = class_name_w();
+        let title: Vec<u16> = "Find Anything Search\0".encode_utf16().collect();
+        let hinstance = unsafe { GetModuleHandleW(std::ptr::null()) };
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOOLWINDOW | WS_EX_TOPMOST,
+                class_name.as_ptr(),
+                title.as_ptr(),
+                WS_POPUP | WS_BORDER | WS_CLIPCHILDREN,
+                0, 0, WINDOW_WIDTH, WINDOW_HEIGHT,
+                0,
+                0,
+                hinstance,
+                std::ptr::null(),
+            )
+        };
+        if hwnd == 0 {
+            anyhow::bail!("CreateWindowExW failed for search window");
+        }
+
+        let edit_class: Vec<u16> = "EDIT\0".encode_utf16().collect();
+        let edit = unsafe {
+            CreateWindowExW(
+                0,
+                edit_class.as_ptr(),
+                std::ptr::null(),
+                WS_CHILD | WS_VISIBLE | WS_BORDER | ES_AUTOHSCROLL,
+                PADDING, PADDING, WINDOW_WIDTH - 2 * PADDING, EDIT_HEIGHT,
+                hwnd,
+                0,
+                hinstance,
+                std::ptr::null(),
+            )
+        };
+        let lb_class: Vec<u16> = "LISTBOX\0".encode_utf16().collect();
+        let list_y = PADDING + EDIT_HEIGHT + PADDING;
+        let list = unsafe {
+            CreateWindowExW(
+                0,
+                lb_class.as_ptr(),
+                std::ptr::null(),
+                WS_CHILD | WS_VISIBLE | WS_VSCROLL | LBS_NOTIFY | LBS_NOINTEGRALHEIGHT,
+                PADDING, list_y, WINDOW_WIDTH - 2 * PADDING, WINDOW_HEIGHT - list_y - PADDING,
+                hwnd,
+                0,
+                hinstance,
+                std::ptr::null(),
+            )
+        };
+        if edit == 0 || list == 0 {
+            unsafe { DestroyWindow(hwnd); }
+            anyhow::bail!("CreateWindowExW failed for search controls");
+        }
+
+        WINDOW_HWND.store(hwnd, Ordering::Relaxed);
+        EDIT_HWND.store(edit, Ordering::Relaxed);
+        LIST_HWND.store(list, Ordering::Relaxed);
+        unsafe {
+            let original = SetWindowLongPtrW(edit, GWLP_WNDPROC, edit_proc as usize as isize);
+            EDIT_PROC.store(original, Ordering::Relaxed);
+        }
+
+        let query_font = ui_font(-18);
+        let list_font = ui_font(-13);
+        unsafe {
+            if query_font != 0 {
+                SendMessageW(edit, WM_SETFONT, query_font as WPARAM, 1);
+            }
+            if list_font != 0 {
+                SendMessageW(list, WM_SETFONT, list_font as WPARAM, 1);
+            }
+        }
+
+        Ok(Self { hwnd, edit, list })
+    }
+
+    /// The window handle the global hotkey is registered against.
+    pub fn hwnd(&self) -> HWND { self.hwnd }
+
+    pub fn is_visible(&self) -> bool {
+        unsafe { IsWindowVisible(self.hwnd) != 0 }
+    }
+
+    /// Show the window centred in the upper part of the work area, with the
+    /// previous query selected so typing replaces it.
+    pub fn show(&self) {
+        let mut work_area = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+        unsafe {
+            SystemParametersInfoW(
+                SPI_GETWORKAREA,
+                0,
+                &mut work_area as *mut RECT as *mut std::ffi::c_void,
+                0,
+            );
+        }
+        let x = work_area.left + (work_area.right - work_area.left - WINDOW_WIDTH) / 2;
+        let y = work_area.top + (work_area.bottom - work_area.top) / 5;
+
+        unsafe {
+            SetWindowPos(self.hwnd, 0, x, y, WINDOW_WIDTH, WINDOW_HEIGHT, SWP_NOZORDER);
+            ShowWindow(self.hwnd, SW_SHOW);
+            SetForegroundWindow(self.hwnd);
+            SetFocus(self.edit);
+            SendMessageW(self.edit, EM_SETSEL, 0, -1);
+        }
+    }
+
+    pub fn hide(&self) {
+        unsafe { ShowWindow(self.hwnd, SW_HIDE); }
+    }
+
+    /// The current query text.
+    pub fn query(&self) -> String {
+        unsafe {
+            let len = GetWindowTextLengthW(self.edit);
+            let mut buf = vec![0u16; len as usize + 1];
+            let n = GetWindowTextW(self.edit, buf.as_mut_ptr(), buf.len() as i32);
+            String::from_utf16_lossy(&buf[..n.max(0) as usize])
+        }
+    }
+
+    /// Replace the list with `hits` and select the first.
+    pub fn set_results(&self, hits: &[SearchResult]) {
+        unsafe { SendMessageW(self.list, LB_RESETCONTENT, 0, 0) };
+        for hit in hits {
+            add_row(self.list, &format_row(hit));
+        }
+        if !hits.is_empty() {
+            unsafe { SendMessageW(self.list, LB_SETCURSEL, 0, 0) };
+        }
+    }
+
+    /// Replace the list with a single informational line.
+    pub fn set_message(&self, text: &str) {
+        unsafe { SendMessageW(self.list, LB_RESETCONTENT, 0, 0) };
+        add_row(self.list, text);
+    }
+
+    /// Index of the selected row, if any.
+    pub fn selected(&self) -> Option<usize> {
+        let sel = unsafe { SendMessageW(self.list, LB_GETCURSEL, 0, 0) };
+        usize::try_from(sel).ok()
+    }
+}
+
+/// Drain a `WM_HOTKEY` press.
+pub fn take_hotkey() -> bool {
+    HOTKEY_PRESSED.swap(false, Ordering::Relaxed)
+}
+
+/// Drain a change of the query text.
+pub fn take_query_change() -> bool {
+    QUERY_CHANGED.swap(false, Ordering::Relaxed)
+}
+
+/// Drain an open request: `Some(true)` to show the result in Explorer,
+/// `Some(false)` to open it.
+pub fn take_open_request() -> Option<bool> {
+    match OPEN_REQUEST.swap(0, Ordering::Relaxed) {
+        0 => None,
+        n => Some(n == 2),
+    }
+}
+
+/// Format a result row: `name    [source]  folder`, with archive members
+/// named by their innermost path.
+fn format_row(hit: &SearchResult) -> String {
+    let full = match &hit.archive_path {
+        Some(inner) => format!("{}::{}", hit.path, inner),
+        None => hit.path.clone(),
+    };
+    let (dir, name) = full.rsplit_once('/').unwrap_or(("", &full));
+    format!("{name}    [{}]  {dir}", hit.source)
+}
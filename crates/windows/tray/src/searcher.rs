@@ -0,0 +1,82 @@
+//! Background thread that runs the quick search window's queries.
+//!
+//! The window sends every edit of the query; the thread skips to the newest
+//! one queued before each request, so fast typing costs at most one request
+//! in flight.  Results go back to the main thread as
+//! [`AppEvent::SearchResults`] tagged with the query's sequence number, and
+//! the window ignores any that a later query has superseded.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use find_common::api::{SearchResponse, SearchResult};
+
+use crate::AppEvent;
+
+/// Results requested per query; the window shows a short list.
+const RESULT_LIMIT: usize = 30;
+
+/// Handle returned by [`spawn`].
+pub struct SearchHandle {
+    tx: Sender<(u64, String)>,
+}
+
+impl SearchHandle {
+    /// Queue `query` as search number `seq`.
+    pub fn search(&self, seq: u64, query: String) {
+        let _ = self.tx.send((seq, query));
+    }
+}
+
+/// Spawn the search thread.
+pub fn spawn(events: Sender<AppEvent>, server_url: String, token: String) -> SearchHandle {
+    let (tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("find-tray-search".into())
+        .spawn(move || run(rx, events, server_url, token))
+        .expect("spawning search thread");
+    SearchHandle { tx }
+}
+
+fn run(rx: Receiver<(u64, String)>, events: Sender<AppEvent>, server_url: String, token: String) {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+
+    while let Ok(mut next) = rx.recv() {
+        while let Ok(newer) = rx.try_recv() {
+            next = newer;
+        }
+        let (seq, query) = next;
+        let result = if query.trim().is_empty() {
+            Ok(vec![])
+        } else {
+            search(&client, &server_url, &token, &query)
+        };
+        if events.send(AppEvent::SearchResults { seq, result }).is_err() {
+            break;
+        }
+    }
+}
+
+fn search(
+    client: &reqwest::blocking::Client,
+    server_url: &str,
+    token: &str,
+    query: &str,
+) -> Result<Vec<SearchResult>, String> {
+    let resp = client
+        .get(format!("{server_url}/api/v1/search"))
+        .bearer_auth(token)
+        .query(&[("q", query), ("mode", "fuzzy"), ("limit", &RESULT_LIMIT.to_string())])
+        .send()
+        .map_err(|e| format!("server unreachable: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("search failed: HTTP {}", resp.status()));
+    }
+    resp.json::<SearchResponse>()
+        .map(|r| r.results)
+        .map_err(|e| format!("unreadable search response: {e}"))
+}
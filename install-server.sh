@@ -310,6 +310,7 @@ Type=simple
 User=${SERVICE_USER}
 Group=${SERVICE_USER}
 ExecStart=${INSTALL_DIR}/find-server --config ${CONFIG_FILE}
+ExecReload=/bin/kill -HUP \$MAINPID
 Restart=on-failure
 RestartSec=5s
 Environment=RUST_LOG=find_server=info
@@ -340,6 +341,7 @@ After=network.target
 [Service]
 Type=simple
 ExecStart=${INSTALL_DIR}/find-server --config ${CONFIG_FILE}
+ExecReload=/bin/kill -HUP \$MAINPID
 Restart=on-failure
 RestartSec=5s
 Environment=RUST_LOG=find_server=info
@@ -96,6 +96,18 @@ Write-Host "Adding find-tray to startup..."
 $RunKey = "HKCU:\SOFTWARE\Microsoft\Windows\CurrentVersion\Run"
 Set-ItemProperty -Path $RunKey -Name "FindAnythingTray" -Value "`"$InstallDir\find-tray.exe`""
 
+# ── Explorer context menu ─────────────────────────────────────────────────────
+
+Write-Host "Adding ""Search here with Find Anything"" to the Explorer folder menu..."
+foreach ($Entry in @(@{ Key = "Directory"; Arg = "%1" }, @{ Key = "Directory\Background"; Arg = "%V" })) {
+    $MenuKey = "HKCU:\Software\Classes\$($Entry.Key)\shell\FindAnything"
+    New-Item -Path "$MenuKey\command" -Force | Out-Null
+    Set-ItemProperty -Path $MenuKey -Name "(default)" -Value "Search here with Find Anything"
+    Set-ItemProperty -Path $MenuKey -Name "Icon" -Value "$InstallDir\find-tray.exe"
+    Set-ItemProperty -Path "$MenuKey\command" -Name "(default)" `
+        -Value "`"$InstallDir\find-tray.exe`" --config `"$ClientConfig`" --search-here `"$($Entry.Arg)`""
+}
+
 # ── Add install directory to user PATH ────────────────────────────────────────
 
 $UserPath = [Environment]::GetEnvironmentVariable("Path", "User")
@@ -106,7 +106,7 @@ tar -xzf "${TMPDIR}/${TARBALL}" -C "${TMPDIR}"
 mkdir -p "$INSTALL_DIR"
 EXTRACTED_DIR="${TMPDIR}/find-anything-${VERSION}-${PLATFORM}"
 
-BINARIES="find-anything find-scan find-watch find-server find-admin find-handler \
+BINARIES="find-anything find-scan find-watch find-server find-admin find-mcp find-handler \
   find-extract-text find-extract-pdf find-extract-media find-extract-archive \
   find-extract-html find-extract-office find-extract-epub"
 
@@ -141,6 +141,7 @@ echo "  find-scan            — initial indexer"
 echo "  find-watch           — incremental file watcher"
 echo "  find-anything        — command-line search client"
 echo "  find-admin           — admin utilities: config, stats, sources, check, inbox"
+echo "  find-mcp             — search server for AI assistants (Model Context Protocol)"
 echo "  find-extract-*       — extractor binaries (used by find-watch)"
 echo ""
 
@@ -330,6 +331,7 @@ After=network.target
 [Service]
 Type=simple
 ExecStart=${INSTALL_DIR}/find-watch --config ${CONFIG_FILE}
+ExecReload=/bin/kill -HUP \$MAINPID
 Restart=on-failure
 RestartSec=5s
 Environment=RUST_LOG=find_watch=info
@@ -388,6 +390,7 @@ After=network.target
 [Service]
 User=${CURRENT_USER}
 ExecStart=${INSTALL_DIR}/find-watch --config ${CONFIG_FILE}
+ExecReload=/bin/kill -HUP \$MAINPID
 Restart=on-failure
 RestartSec=5s
 Environment=RUST_LOG=find_watch=info
@@ -450,30 +453,9 @@ elif [ "$OS_NAME" = "macos" ]; then
   PLIST_DIR="$HOME/Library/LaunchAgents"
   mkdir -p "$PLIST_DIR"
 
-  cat > "$PLIST_DIR/com.jamietre.find-watch.plist" <<EOF
-<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-  <key>Label</key>
-  <string>com.jamietre.find-watch</string>
-  <key>ProgramArguments</key>
-  <array>
-    <string>${INSTALL_DIR}/find-watch</string>
-    <string>--config</string>
-    <string>${CONFIG_FILE}</string>
-  </array>
-  <key>RunAtLoad</key>
-  <true/>
-  <key>KeepAlive</key>
-  <true/>
-  <key>StandardOutPath</key>
-  <string>${HOME}/Library/Logs/find-watch.log</string>
-  <key>StandardErrorPath</key>
-  <string>${HOME}/Library/Logs/find-watch.log</string>
-</dict>
-</plist>
-EOF
+  # find-watch writes and loads its own LaunchAgent (keepalive, throttling,
+  # log paths); see docs/manual/08-services.md.
+  "${INSTALL_DIR}/find-watch" --config "$CONFIG_FILE" install
 
   cat > "$PLIST_DIR/com.jamietre.find-scan.plist" <<EOF
 <?xml version="1.0" encoding="UTF-8"?>
@@ -500,16 +482,13 @@ EOF
 </plist>
 EOF
 
-  launchctl load "$PLIST_DIR/com.jamietre.find-watch.plist"
   launchctl load "$PLIST_DIR/com.jamietre.find-scan.plist"
 
   WATCH_SERVICE_TYPE="macos"
   SCAN_SERVICE_TYPE="macos"
   echo ""
   echo "find-watch launchd agent installed and started."
-  echo "  Status:  launchctl list com.jamietre.find-watch"
-  echo "  Logs:    tail -f ~/Library/Logs/find-watch.log"
-  echo "  Stop:    launchctl unload $PLIST_DIR/com.jamietre.find-watch.plist"
+  echo "  Remove:  find-watch uninstall"
 
 else
   # No systemd at all
@@ -12,6 +12,10 @@ if (Test-Path "$InstallDir\find-watch.exe") {
     Write-Warning "find-watch.exe not found at $InstallDir; skipping service removal."
 }
 
+foreach ($Key in @("Directory", "Directory\Background")) {
+    Remove-Item "HKCU:\Software\Classes\$Key\shell\FindAnything" -Recurse -ErrorAction SilentlyContinue
+}
+
 if (Test-Path $InstallDir) {
     Remove-Item $InstallDir -Recurse -Force
     Write-Host "Removed $InstallDir"
@@ -28,6 +28,12 @@ export interface SearchResult {
 	extra_matches?: ContextLine[];
 	/** True when this file had more matching lines than the display cap (document mode only). */
 	hits_truncated?: boolean;
+	/** Number of matching lines in this file (exact and regex modes only). */
+	hit_count?: number;
+	/** True for hits on the metadata line; `snippet` holds the matching tags as readable text. */
+	metadata_match?: boolean;
+	/** Federation peer that produced this hit; absent for local hits. */
+	origin?: string;
 }
 
 export interface SearchResponse {
@@ -35,6 +41,8 @@ export interface SearchResponse {
 	total: number;
 	/** True when the result set was capped; display "N+" instead of "N". */
 	capped: boolean;
+	/** True when the server's search time budget ran out; results are partial (and `capped` is set). */
+	timed_out?: boolean;
 }
 
 export interface FileResponse {
@@ -53,6 +61,8 @@ export interface FileResponse {
 	content_unavailable?: boolean;
 	/** Other paths with identical content. */
 	duplicate_paths?: string[];
+	/** Notes attached to this file or its lines. */
+	annotations?: Annotation[];
 }
 
 export interface ContextResponse {
@@ -62,6 +72,21 @@ export interface ContextResponse {
 	/** Each line carries its own line_number — use line.line_number, not start + index. */
 	lines: ContextLine[];
 	kind: string;
+	/** File-level notes plus notes on lines within the returned window. */
+	annotations?: Annotation[];
+}
+
+export interface PreviewResponse {
+	start: number;
+	/** Each line carries its own line_number — use line.line_number, not start + index. */
+	lines: ContextLine[];
+	total_lines: number;
+	kind: string;
+	/** highlight.js language id, when the file is recognisable source code. */
+	language?: string;
+	/** True when the file has content outside the returned window. */
+	truncated?: boolean;
+	content_unavailable?: boolean;
 }
 
 export interface DirEntry {
@@ -146,6 +171,10 @@ export interface SearchParams {
 	caseSensitive?: boolean;
 	/** Restrict results to files whose path starts with this prefix (no leading slash). */
 	pathPrefix?: string;
+	/** When true, files with identical content are returned separately instead of collapsed. */
+	expandDuplicates?: boolean;
+	/** Result order: "score" (default), "mtime", "path", "size" or "hits". */
+	sort?: 'score' | 'mtime' | 'path' | 'size' | 'hits';
 }
 
 export async function search(params: SearchParams): Promise<SearchResponse> {
@@ -164,6 +193,8 @@ export async function search(params: SearchParams): Promise<SearchResponse> {
 	}
 	if (params.caseSensitive) url.searchParams.set('case_sensitive', '1');
 	if (params.pathPrefix) url.searchParams.set('path_prefix', params.pathPrefix);
+	if (params.expandDuplicates) url.searchParams.set('expand_duplicates', '1');
+	if (params.sort && params.sort !== 'score') url.searchParams.set('sort', params.sort);
 
 	const resp = await apiFetch(url.toString());
 	if (!resp.ok) {
@@ -192,6 +223,24 @@ export async function getFile(
 	return resp.json();
 }
 
+/** A window of up to `lines` lines centred on `line` (from the top when omitted). */
+export async function getPreview(
+	source: string,
+	path: string,
+	line?: number,
+	lines?: number
+): Promise<PreviewResponse> {
+	const url = new URL('/api/v1/file/preview', location.origin);
+	url.searchParams.set('source', source);
+	url.searchParams.set('path', path);
+	if (line != null) url.searchParams.set('line', String(line));
+	if (lines != null) url.searchParams.set('lines', String(lines));
+
+	const resp = await apiFetch(url.toString());
+	if (!resp.ok) throw new Error(`getPreview: ${resp.status} ${resp.statusText}`);
+	return resp.json();
+}
+
 export async function listFiles(source: string, q?: string, limit = 50): Promise<FileRecord[]> {
 	const url = new URL('/api/v1/files', location.origin);
 	url.searchParams.set('source', source);
@@ -253,6 +302,8 @@ export interface ContextBatchResult {
 	/** Each line carries its own line_number — use line.line_number, not start + index. */
 	lines: ContextLine[];
 	kind: string;
+	/** File-level notes plus notes on lines within the returned window. */
+	annotations?: Annotation[];
 }
 
 export interface ContextBatchResponse {
@@ -328,6 +379,14 @@ export interface ExtStat {
 	size: number;
 }
 
+/** Index space taken by one kind or top-level directory of a source. */
+export interface SpaceStat {
+	name: string;
+	files: number;
+	size: number;
+	content_bytes: number;
+}
+
 export interface SourceStats {
 	name: string;
 	last_scan: number | null;
@@ -337,12 +396,22 @@ export interface SourceStats {
 	by_ext: ExtStat[];
 	history: ScanHistoryPoint[];
 	indexing_error_count: number;
+	db_size_bytes?: number;
+	content_bytes?: number;
+	index_budget_bytes?: number;
+	space_by_kind?: SpaceStat[];
+	space_by_dir?: SpaceStat[];
 }
 
 export type WorkerStatus =
 	| { state: 'idle' }
 	| { state: 'processing'; source: string; file: string };
 
+export interface WorkerLane {
+	source: string;
+	file: string;
+}
+
 export interface StatsResponse {
 	sources: SourceStats[];
 	inbox_pending: number;
@@ -351,6 +420,7 @@ export interface StatsResponse {
 	db_size_bytes: number;
 	content_size_bytes: number;
 	worker_status: WorkerStatus;
+	worker_lanes?: WorkerLane[];
 }
 
 export async function getStats(): Promise<StatsResponse> {
@@ -359,6 +429,91 @@ export async function getStats(): Promise<StatsResponse> {
 	return resp.json();
 }
 
+// ── Client metrics ───────────────────────────────────────────────────────────
+
+export interface Percentiles {
+	count: number;
+	p50: number;
+	p90: number;
+	p99: number;
+	max: number;
+}
+
+export interface ScanMetrics {
+	source: string;
+	started_at: number;
+	duration_secs: number;
+	files_indexed: number;
+	files_unchanged: number;
+	files_excluded: number;
+	files_deleted: number;
+	failures: number;
+	extract_ms: Percentiles | null;
+}
+
+export interface WatchMetrics {
+	started_at: number;
+	events_received: number;
+	files_updated: number;
+	files_deleted: number;
+	errors: number;
+	pending_paths: number;
+	spooled_batches: number;
+	last_event_at: number | null;
+	last_error: string | null;
+}
+
+/** Latest health report from a find-scan run or a running find-watch. */
+export interface ClientMetrics {
+	host: string;
+	tool: string;
+	version: string;
+	received_at: number;
+	scan?: ScanMetrics;
+	watch?: WatchMetrics;
+}
+
+export async function getClientMetrics(): Promise<ClientMetrics[]> {
+	const resp = await apiFetch('/api/v1/client-metrics');
+	if (!resp.ok) throw new Error(`getClientMetrics: ${resp.status} ${resp.statusText}`);
+	return resp.json();
+}
+
+// ── Recent files ─────────────────────────────────────────────────────────────
+
+export interface RecentFile {
+	source: string;
+	path: string;
+	indexed_at: number;
+	action: 'added' | 'modified' | 'deleted' | 'renamed';
+	new_path?: string | null;
+	/** Absent for deleted files. */
+	kind?: string;
+}
+
+export interface RecentParams {
+	sources?: string[];
+	/** Unix timestamp (seconds); only entries at or after it. */
+	since?: number;
+	kinds?: string[];
+	/** `mtime` lists files by modification time instead of index activity. */
+	sort?: 'indexed' | 'mtime';
+	limit?: number;
+}
+
+export async function getRecent(params: RecentParams = {}): Promise<RecentFile[]> {
+	const url = new URL('/api/v1/recent', location.origin);
+	for (const s of params.sources ?? []) url.searchParams.append('source', s);
+	for (const k of params.kinds ?? []) url.searchParams.append('kind', k);
+	if (params.since != null) url.searchParams.set('since', String(params.since));
+	if (params.sort) url.searchParams.set('sort', params.sort);
+	if (params.limit != null) url.searchParams.set('limit', String(params.limit));
+	const resp = await apiFetch(url.toString());
+	if (!resp.ok) throw new Error(`getRecent: ${resp.status} ${resp.statusText}`);
+	const data: { files: RecentFile[] } = await resp.json();
+	return data.files;
+}
+
 // ── Indexing errors ───────────────────────────────────────────────────────────
 
 export interface IndexingError {
@@ -438,12 +593,76 @@ export async function resolveLink(
 	return resp.json();
 }
 
+// ── Annotations ───────────────────────────────────────────────────────────────
+
+export interface Annotation {
+	id: number;
+	source: string;
+	path: string;
+	/** Line the note is attached to (same numbering as ContextLine.line_number); absent for file-level notes. */
+	line_number?: number;
+	body: string;
+	author?: string;
+	created_at: number;
+	updated_at: number;
+}
+
+export async function listAnnotations(source: string, path: string): Promise<Annotation[]> {
+	const url = new URL('/api/v1/annotations', location.origin);
+	url.searchParams.set('source', source);
+	url.searchParams.set('path', path);
+	const resp = await apiFetch(url.toString());
+	if (!resp.ok) throw new Error(`listAnnotations: ${resp.status} ${resp.statusText}`);
+	return resp.json();
+}
+
+export async function createAnnotation(
+	source: string,
+	path: string,
+	body: string,
+	lineNumber?: number,
+	author?: string
+): Promise<Annotation> {
+	const resp = await apiFetch('/api/v1/annotations', {
+		method: 'POST',
+		headers: { 'content-type': 'application/json' },
+		body: JSON.stringify({ source, path, body, line_number: lineNumber, author })
+	});
+	if (!resp.ok) throw new Error(`createAnnotation: ${resp.status} ${resp.statusText}`);
+	return resp.json();
+}
+
+export async function updateAnnotation(id: number, body: string): Promise<Annotation> {
+	const resp = await apiFetch(`/api/v1/annotations/${id}`, {
+		method: 'PATCH',
+		headers: { 'content-type': 'application/json' },
+		body: JSON.stringify({ body })
+	});
+	if (!resp.ok) throw new Error(`updateAnnotation: ${resp.status} ${resp.statusText}`);
+	return resp.json();
+}
+
+export async function deleteAnnotation(id: number): Promise<void> {
+	const resp = await apiFetch(`/api/v1/annotations/${id}`, { method: 'DELETE' });
+	if (!resp.ok) throw new Error(`deleteAnnotation: ${resp.status} ${resp.statusText}`);
+}
+
 // ── Admin inbox ───────────────────────────────────────────────────────────────
 
 export interface InboxItem {
 	filename: string;
 	size_bytes: number;
 	age_secs: number;
+	/** Quarantine diagnostics (failed items only). */
+	failure?: InboxFailure;
+}
+
+export interface InboxFailure {
+	error: string;
+	source?: string;
+	file?: string;
+	attempts: number;
+	failed_at: number;
 }
 
 export interface InboxStatusResponse {
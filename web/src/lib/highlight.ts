@@ -48,6 +48,7 @@ async function ensureLanguage(lang: string): Promise<void> {
 }
 
 // ── Extension → language map ──────────────────────────────────────────────────
+// Mirrored server-side in crates/common/src/language.rs; keep the two in step.
 
 const EXT_MAP: Record<string, string> = {
 	// Systems
@@ -138,9 +139,14 @@ function escapeHtml(text: string): string {
  * Highlight a full file. Returns the highlighted HTML as a single string
  * suitable for rendering inside a <pre><code> block.
  * Rendering as a single block preserves multi-line spans (strings, comments).
+ * `language` (e.g. from `/api/v1/file/preview`) overrides the extension lookup.
  */
-export async function highlightFile(lines: string[], filePath: string): Promise<string> {
-	const lang = getLanguage(filePath);
+export async function highlightFile(
+	lines: string[],
+	filePath: string,
+	language?: string | null
+): Promise<string> {
+	const lang = language ?? getLanguage(filePath);
 	const code = lines.join('\n');
 
 	try {
@@ -160,8 +166,12 @@ export async function highlightFile(lines: string[], filePath: string): Promise<
  * Highlight a single line snippet for search result context.
  * Returns escaped HTML (no full-file context, so syntax may be approximate).
  */
-export async function highlightLine(content: string, filePath: string): Promise<string> {
-	const lang = getLanguage(filePath);
+export async function highlightLine(
+	content: string,
+	filePath: string,
+	language?: string | null
+): Promise<string> {
+	const lang = language ?? getLanguage(filePath);
 	try {
 		if (lang) {
 			await ensureLanguage(lang);
@@ -8,6 +8,7 @@ export interface LiveEvent {
 	action: 'added' | 'modified' | 'deleted' | 'renamed';
 	new_path?: string;
 	indexed_at: number;
+	kind?: string;
 }
 
 const _store = writable<LiveEvent | null>(null);
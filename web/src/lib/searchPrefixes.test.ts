@@ -280,6 +280,13 @@ describe('source: prefix', () => {
 		expect(r.dirPrefix).toBe('multimedia');
 	});
 
+	it('accepts a quoted path with spaces', () => {
+		const r = parseSearchPrefixes('source:"nas-data/My Movies/2020" hello');
+		expect(r.dirSource).toBe('nas-data');
+		expect(r.dirPrefix).toBe('My Movies/2020');
+		expect(r.query).toBe('hello');
+	});
+
 	it('empty after normalisation produces error', () => {
 		const r = parseSearchPrefixes('source:/ hello');
 		expect(r.dirSource).toBeNull();
@@ -83,8 +83,9 @@ export function parseSearchPrefixes(raw: string): PrefixParseResult {
 
 		// source: prefix — source-scoped path filter (e.g. source:nas-data/multimedia/movies)
 		// Format: source:<source>[/<path>]  — source is the first path segment.
+		// Quote the value for paths with spaces: source:"nas-data/My Movies".
 		if (lower.startsWith('source:')) {
-			const rest = token.slice(7).replace(/^\/+/, '').replace(/\/+$/, '');
+			const rest = token.slice(7).replace(/^"(.*)"$/, '$1').replace(/^\/+/, '').replace(/\/+$/, '');
 			if (!rest) {
 				dirPrefixError = `"${token}" — expected format: source:source-name/optional/path`;
 			} else {